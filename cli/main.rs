@@ -30,6 +30,50 @@ struct Opt {
     /// Useful for benchmarking or performance-critical scripts.
     #[structopt(long, default_value = "0")]
     warmup: usize,
+
+    /// Custom condition to match in package.json "exports" and "imports",
+    /// e.g. "development". May be given more than once.
+    #[structopt(long = "conditions", number_of_values = 1)]
+    conditions: Vec<String>,
+
+    /// Import map file for remapping import specifiers. Defaults to the
+    /// import map configured in the nearest jstime.json, if any
+    #[structopt(long)]
+    import_map: Option<String>,
+
+    /// Only import modules from http(s) URLs that are already cached, without
+    /// downloading. The cache is in $JSTIME_CACHE_DIR, or ~/.cache/jstime
+    #[structopt(long)]
+    cached_only: bool,
+
+    /// Lockfile to check remote and node_modules modules against. Defaults
+    /// to jstime.lock beside the nearest jstime.json, if there is one
+    #[structopt(long)]
+    lock: Option<String>,
+
+    /// Regenerate the lockfile from the modules loaded by this run
+    #[structopt(long)]
+    lock_write: bool,
+
+    /// Don't store or reuse the code V8 compiles for modules and script
+    /// files. It's cached in $JSTIME_CACHE_DIR, or ~/.cache/jstime
+    #[structopt(long)]
+    no_code_cache: bool,
+
+    /// Keep running after the program finishes, and reload modules as
+    /// their files change. Modules can take part with import.meta.hot
+    #[structopt(long)]
+    watch: bool,
+
+    /// Function that JSX elements are transformed into calls of, e.g. "h".
+    /// Defaults to React.createElement
+    #[structopt(long)]
+    jsx_factory: Option<String>,
+
+    /// Component used for JSX fragments, e.g. "Fragment". Defaults to
+    /// React.Fragment
+    #[structopt(long)]
+    jsx_fragment: Option<String>,
 }
 
 fn main() {
@@ -52,7 +96,12 @@ fn main() {
             structopt_args.push(arg.clone());
             // Check if this flag expects a value (doesn't use = syntax)
             let is_option_with_value = (arg.starts_with("--warmup")
-                || arg.starts_with("--v8-options"))
+                || arg.starts_with("--v8-options")
+                || arg.starts_with("--conditions")
+                || arg.starts_with("--import-map")
+                || arg.starts_with("--jsx-factory")
+                || arg.starts_with("--jsx-fragment")
+                || arg == "--lock")
                 && !arg.contains('=');
             if is_option_with_value {
                 expect_value = true;
@@ -118,11 +167,62 @@ fn main() {
         "/snapshot_data.blob"
     ))))
     .with_process_argv(process_argv)
-    .with_warmup(opt.warmup);
+    .with_warmup(opt.warmup)
+    .with_cached_only(opt.cached_only)
+    .with_code_cache(!opt.no_code_cache)
+    .with_hot_reload(opt.watch);
+    let options = if opt.conditions.is_empty() {
+        options
+    } else {
+        options.with_conditions(opt.conditions)
+    };
+    let options = match opt.jsx_factory {
+        Some(factory) => options.with_jsx_factory(factory),
+        None => options,
+    };
+    let options = match opt.jsx_fragment {
+        Some(fragment) => options.with_jsx_fragment(fragment),
+        None => options,
+    };
+    let config = find_config();
+    let import_map = match opt.import_map {
+        Some(path) => jstime::ImportMap::from_file(path).map(Some),
+        None => config
+            .as_ref()
+            .map_or(Ok(None), jstime::ImportMap::from_config_file),
+    };
+    let options = match import_map {
+        Ok(Some(import_map)) => options.with_import_map(import_map),
+        Ok(None) => options,
+        Err(e) => {
+            eprintln!("{e}");
+            process::exit(1);
+        }
+    };
+
+    let lock_path = opt
+        .lock
+        .map(std::path::PathBuf::from)
+        .or_else(|| config.map(|config| config.with_file_name("jstime.lock")))
+        .or_else(|| opt.lock_write.then(|| "jstime.lock".into()));
+    let options = match lock_path {
+        Some(path) if opt.lock_write => options.with_lockfile(jstime::Lockfile::regenerate(path)),
+        Some(path) => match jstime::Lockfile::open(path) {
+            Ok(lockfile) => options.with_lockfile(lockfile),
+            Err(e) => {
+                eprintln!("{e}");
+                process::exit(1);
+            }
+        },
+        None => options,
+    };
 
     let mut jstime = jstime::JSTime::new(options);
 
     if let Some(filename) = opt.filename {
+        if opt.watch {
+            watch(jstime, &filename);
+        }
         std::process::exit(match jstime.import(&filename) {
             Ok(_) => 0,
             Err(e) => {
@@ -135,6 +235,27 @@ fn main() {
     }
 }
 
+/// Import `filename`, then keep running the event loop, which reloads the
+/// modules that change, until the process is stopped
+fn watch(mut jstime: jstime::JSTime, filename: &str) -> ! {
+    // A failed import is reloaded once it's fixed
+    if let Err(e) = jstime.import(filename) {
+        eprintln!("{e}");
+    }
+    loop {
+        jstime.tick_event_loop();
+        std::thread::sleep(std::time::Duration::from_millis(10));
+    }
+}
+
+/// The nearest jstime.json in the working directory or its parents
+fn find_config() -> Option<std::path::PathBuf> {
+    let cwd = env::current_dir().ok()?;
+    cwd.ancestors()
+        .map(|dir| dir.join("jstime.json"))
+        .find(|path| path.is_file())
+}
+
 fn repl(mut jstime: jstime::JSTime) {
     use dirs::home_dir;
     use std::sync::mpsc::{RecvTimeoutError, channel};
@@ -0,0 +1,5 @@
+export interface Person {
+  name: string;
+}
+
+export const greet = (person: Person): string => `hello ${person.name}`;
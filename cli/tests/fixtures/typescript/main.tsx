@@ -0,0 +1,6 @@
+import { greet, type Person } from './greet';
+
+const h = (tag: string, _props: null, ...children: string[]): string => children.join('');
+const person: Person = { name: 'typescript' };
+
+console.log(<p>{greet(person)}</p>);
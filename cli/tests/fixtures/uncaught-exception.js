@@ -0,0 +1,4 @@
+// An exception a timer throws that nothing handles fails the program
+setTimeout(() => {
+  throw new Error('thrown in a timer');
+}, 0);
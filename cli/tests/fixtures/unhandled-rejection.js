@@ -0,0 +1,4 @@
+// A rejection that nothing handles fails the program
+setTimeout(() => {
+  Promise.reject(new Error('never caught'));
+}, 0);
@@ -55,6 +55,26 @@ fn throw() {
         .code(1);
 }
 
+#[test]
+fn unhandled_rejection() {
+    Command::new(assert_cmd::cargo::cargo_bin!("jstime"))
+        .arg("./tests/fixtures/unhandled-rejection.js")
+        .assert()
+        .stderr(predicate::str::contains("Error: never caught"))
+        .failure()
+        .code(1);
+}
+
+#[test]
+fn uncaught_exception() {
+    Command::new(assert_cmd::cargo::cargo_bin!("jstime"))
+        .arg("./tests/fixtures/uncaught-exception.js")
+        .assert()
+        .stderr(predicate::str::contains("Error: thrown in a timer"))
+        .failure()
+        .code(1);
+}
+
 #[test]
 fn invalid_code() {
     Command::new(assert_cmd::cargo::cargo_bin!("jstime"))
@@ -149,3 +169,37 @@ fn fetch_api() {
         .success()
         .code(0);
 }
+
+#[test]
+fn import_map() {
+    Command::new(assert_cmd::cargo::cargo_bin!("jstime"))
+        .arg("--import-map")
+        .arg("./tests/fixtures/import-map/import_map.json")
+        .arg("./tests/fixtures/import-map/main.js")
+        .assert()
+        .stdout("hello from the import map\n")
+        .success()
+        .code(0);
+}
+
+#[test]
+fn import_map_not_found() {
+    Command::new(assert_cmd::cargo::cargo_bin!("jstime"))
+        .arg("--import-map=./tests/fixtures/import-map/missing.json")
+        .arg("./tests/fixtures/import-map/main.js")
+        .assert()
+        .stderr(predicate::str::contains("Cannot read import map"))
+        .failure()
+        .code(1);
+}
+
+#[test]
+fn typescript_and_jsx() {
+    Command::new(assert_cmd::cargo::cargo_bin!("jstime"))
+        .arg("--jsx-factory=h")
+        .arg("./tests/fixtures/typescript/main.tsx")
+        .assert()
+        .stdout("hello typescript\n")
+        .success()
+        .code(0);
+}
@@ -0,0 +1,42 @@
+// jstime.connectTls TLS client
+// Modeled on Deno.connectTls: https://docs.deno.com/api/deno/~/Deno.connectTls
+// A promise-based front end for node:tls connect().
+
+'use strict';
+
+// eslint-disable-next-line no-unused-expressions
+(({}) => {
+  const tls = globalThis.__node_modules['node:tls'];
+
+  /**
+   * Open a TLS connection.
+   * Certificate and connection errors reject the returned promise.
+   * @param {Object} options - { hostname, port, serverName, caCerts, alpnProtocols }
+   * @returns {Promise<TLSSocket>} Resolves once the handshake has completed
+   */
+  function connectTls(options) {
+    if (options === null || typeof options !== 'object') {
+      return Promise.reject(new TypeError('jstime.connectTls: options must be an object'));
+    }
+    const hostname = options.hostname === undefined ? '127.0.0.1' : String(options.hostname);
+
+    return new Promise((resolve, reject) => {
+      const socket = tls.connect({
+        host: hostname,
+        port: options.port,
+        servername: options.serverName,
+        ca: options.caCerts,
+        ALPNProtocols: options.alpnProtocols,
+      });
+      const onError = (error) => reject(error);
+      socket.once('error', onError);
+      socket.once('secureConnect', () => {
+        socket.removeListener('error', onError);
+        resolve(socket);
+      });
+    });
+  }
+
+  globalThis.jstime = globalThis.jstime || {};
+  globalThis.jstime.connectTls = connectTls;
+});
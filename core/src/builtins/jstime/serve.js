@@ -0,0 +1,266 @@
+// jstime.serve HTTP server
+// Modeled on Deno.serve: https://docs.deno.com/api/deno/~/Deno.serve
+// Requests and responses are the WHATWG Request and Response classes from fetch.js.
+
+'use strict';
+
+// eslint-disable-next-line no-unused-expressions
+(({ serveListen, serveRespond, serveWrite, serveEnd, serveUpgrade, serveClose, serveRef }) => {
+  // Requests passed to handlers, mapped to their IDs, until they can no longer be upgraded
+  const upgradableRequests = new WeakMap();
+  // Responses returned by upgradeWebSocket(), mapped to the upgrade they complete
+  const upgradeResponses = new WeakMap();
+
+  function defaultOnError(error) {
+    console.error(error);
+    return new Response('Internal Server Error', { status: 500 });
+  }
+
+  function defaultOnListen({ hostname, port }) {
+    const host = hostname.includes(':') ? `[${hostname}]` : hostname;
+    console.log(`Listening on http://${host}:${port}/`);
+  }
+
+  /**
+   * A running HTTP server, returned by jstime.serve()
+   */
+  class HttpServer {
+    #id;
+    #addr;
+    #closed = false;
+    #inFlight = 0;
+    #resolveFinished;
+    #finished;
+
+    constructor(id, addr) {
+      this.#id = id;
+      this.#addr = addr;
+      this.#finished = new Promise((resolve) => {
+        this.#resolveFinished = resolve;
+      });
+    }
+
+    /** The address the server is listening on */
+    get addr() {
+      return this.#addr;
+    }
+
+    /** Resolves once the server has shut down and all in-flight requests are answered */
+    get finished() {
+      return this.#finished;
+    }
+
+    /**
+     * Stop accepting new connections. Requests that are being handled still get a response.
+     * @returns {Promise<void>} Resolves when the server has finished
+     */
+    shutdown() {
+      if (!this.#closed) {
+        this.#closed = true;
+        serveClose(this.#id);
+        this._maybeFinish();
+      }
+      return this.#finished;
+    }
+
+    /** Make the server keep the event loop alive (the default) */
+    ref() {
+      serveRef(this.#id, true);
+    }
+
+    /** Allow the event loop to exit while the server is still listening */
+    unref() {
+      serveRef(this.#id, false);
+    }
+
+    // Internal: track requests so `finished` waits for their responses
+    _requestStarted() {
+      this.#inFlight++;
+    }
+
+    _requestFinished() {
+      this.#inFlight--;
+      this._maybeFinish();
+    }
+
+    _maybeFinish() {
+      if (this.#closed && this.#inFlight === 0) {
+        this.#resolveFinished();
+      }
+    }
+  }
+
+  // Send a Response back to the connection waiting on requestId
+  async function sendResponse(requestId, response) {
+    const headers = response.headers._toArray();
+
+    const upgrade = upgradeResponses.get(response);
+    if (upgrade && upgrade.requestId === requestId) {
+      upgradeResponses.delete(response);
+      upgrade.socket._attach((callback) => serveUpgrade(requestId, upgrade.key, upgrade.protocol, headers, callback));
+      return;
+    }
+
+    if (!response._isStreamed()) {
+      const body = await response._consumeBytes();
+      serveRespond(requestId, response.status, headers, body, false);
+      return;
+    }
+
+    const reader = response.body.getReader();
+    response._bodyUsed = true;
+    serveRespond(requestId, response.status, headers, null, true);
+    while (true) {
+      const { done, value } = await reader.read();
+      if (done) break;
+      const chunk = typeof value === 'string' ? new TextEncoder().encode(value) : value;
+      if (!serveWrite(requestId, chunk)) {
+        // The client went away; stop producing the body
+        await reader.cancel();
+        break;
+      }
+    }
+    serveEnd(requestId, false);
+  }
+
+  /**
+   * Start an HTTP server.
+   * @param {Function|Object} options - A handler, or options ({ port, hostname, handler, onListen, onError, signal })
+   * @param {Function} [handler] - Called with (request, info) and returns a Response or a Promise of one
+   * @returns {HttpServer}
+   */
+  function serve(options, handler) {
+    if (typeof options === 'function') {
+      handler = options;
+      options = {};
+    } else if (options === null || typeof options !== 'object') {
+      throw new TypeError('jstime.serve: options must be an object');
+    }
+    handler = handler || options.handler;
+    if (typeof handler !== 'function') {
+      throw new TypeError('jstime.serve: a handler function is required');
+    }
+
+    const hostname = options.hostname === undefined ? '0.0.0.0' : String(options.hostname);
+    const port = options.port === undefined ? 8000 : Number(options.port);
+    if (!Number.isInteger(port) || port < 0 || port > 65535) {
+      throw new RangeError(`jstime.serve: invalid port ${options.port}`);
+    }
+    const onError = options.onError || defaultOnError;
+    const onListen = options.onListen || defaultOnListen;
+    const signal = options.signal;
+
+    let server;
+    const listening = serveListen(hostname, port, (requestId, method, target, headers, body, remoteHostname, remotePort) => {
+      server._requestStarted();
+      handleRequest(requestId, method, target, headers, body, remoteHostname, remotePort)
+        .finally(() => server._requestFinished());
+    });
+    const addr = { hostname: listening.hostname, port: listening.port, transport: 'tcp' };
+    server = new HttpServer(listening.id, addr);
+
+    async function handleRequest(requestId, method, target, headers, body, remoteHostname, remotePort) {
+      let response;
+      try {
+        const host = headers.find(([name]) => name === 'host');
+        const authority = host ? host[1] : `${addr.hostname}:${addr.port}`;
+        const url = /^https?:\/\//i.test(target) ? target : `http://${authority}${target}`;
+        const request = new Request(url, { method, headers, body });
+        upgradableRequests.set(request, requestId);
+        const info = { remoteAddr: { hostname: remoteHostname, port: remotePort, transport: 'tcp' } };
+        response = await handler(request, info);
+        if (!(response instanceof Response)) {
+          throw new TypeError('jstime.serve: handler must return a Response');
+        }
+      } catch (error) {
+        try {
+          response = await onError(error);
+          if (!(response instanceof Response)) {
+            throw new TypeError('jstime.serve: onError must return a Response');
+          }
+        } catch (onErrorFailure) {
+          console.error(onErrorFailure);
+          response = new Response('Internal Server Error', { status: 500 });
+        }
+      }
+
+      try {
+        await sendResponse(requestId, response);
+      } catch (error) {
+        // Fails the response: a 500 if nothing was sent yet, otherwise the connection is closed
+        serveEnd(requestId, true);
+        console.error(error);
+      }
+    }
+
+    if (signal) {
+      if (signal.aborted) {
+        server.shutdown();
+      } else {
+        signal.addEventListener('abort', () => server.shutdown(), { once: true });
+      }
+    }
+
+    onListen(addr);
+    return server;
+  }
+
+  /**
+   * Upgrade a request received by a jstime.serve handler to a WebSocket.
+   * The handler must return the returned response for the upgrade to happen.
+   * @param {Request} request - A WebSocket handshake request
+   * @param {Object} [options] - { protocol }: the subprotocol to accept, one of those the client offered
+   * @returns {{ socket: WebSocket, response: Response }}
+   */
+  function upgradeWebSocket(request, options = {}) {
+    const requestId = upgradableRequests.get(request);
+    if (requestId === undefined) {
+      throw new TypeError('jstime.upgradeWebSocket: request must be one passed to a jstime.serve handler that has not been upgraded');
+    }
+
+    const headers = request.headers;
+    const hasToken = (name, token) => (headers.get(name) || '')
+      .split(',')
+      .some((value) => value.trim().toLowerCase() === token);
+    if (request.method !== 'GET') {
+      throw new TypeError('jstime.upgradeWebSocket: request method must be GET');
+    }
+    if (!hasToken('upgrade', 'websocket')) {
+      throw new TypeError("jstime.upgradeWebSocket: missing 'Upgrade: websocket' header");
+    }
+    if (!hasToken('connection', 'upgrade')) {
+      throw new TypeError("jstime.upgradeWebSocket: missing 'Connection: Upgrade' header");
+    }
+    if (headers.get('sec-websocket-version') !== '13') {
+      throw new TypeError("jstime.upgradeWebSocket: 'Sec-WebSocket-Version' must be 13");
+    }
+    const key = headers.get('sec-websocket-key') || '';
+    let keyLength = -1;
+    try {
+      keyLength = atob(key).length;
+    } catch {
+      // Reported below
+    }
+    if (keyLength !== 16) {
+      throw new TypeError("jstime.upgradeWebSocket: invalid 'Sec-WebSocket-Key' header");
+    }
+
+    const protocol = options.protocol === undefined ? '' : String(options.protocol);
+    if (protocol !== '') {
+      const offered = (headers.get('sec-websocket-protocol') || '').split(',').map((value) => value.trim());
+      if (!offered.includes(protocol)) {
+        throw new TypeError(`jstime.upgradeWebSocket: the client did not offer the subprotocol '${protocol}'`);
+      }
+    }
+
+    upgradableRequests.delete(request);
+    const socket = WebSocket._accept(request.url);
+    const response = new Response(null, { status: 101 });
+    upgradeResponses.set(response, { requestId, socket, key, protocol });
+    return { socket, response };
+  }
+
+  globalThis.jstime = globalThis.jstime || {};
+  globalThis.jstime.serve = serve;
+  globalThis.jstime.upgradeWebSocket = upgradeWebSocket;
+});
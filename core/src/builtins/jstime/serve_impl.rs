@@ -0,0 +1,475 @@
+// jstime.serve HTTP server bindings
+// Sockets are handled by crate::http_server; requests reach JS through the event loop.
+
+use crate::http_server::{HttpServer, ResponseChunk};
+use crate::tcp::ActiveTcpSocket;
+use crate::websocket::ActiveWebSocket;
+
+pub(crate) fn get_external_references() -> Vec<v8::ExternalReference> {
+    vec![
+        v8::ExternalReference {
+            function: v8::MapFnTo::map_fn_to(serve_listen),
+        },
+        v8::ExternalReference {
+            function: v8::MapFnTo::map_fn_to(serve_respond),
+        },
+        v8::ExternalReference {
+            function: v8::MapFnTo::map_fn_to(serve_write),
+        },
+        v8::ExternalReference {
+            function: v8::MapFnTo::map_fn_to(serve_end),
+        },
+        v8::ExternalReference {
+            function: v8::MapFnTo::map_fn_to(serve_upgrade),
+        },
+        v8::ExternalReference {
+            function: v8::MapFnTo::map_fn_to(serve_close),
+        },
+        v8::ExternalReference {
+            function: v8::MapFnTo::map_fn_to(serve_ref),
+        },
+    ]
+}
+
+pub(crate) fn register_bindings(scope: &mut v8::PinScope, bindings: v8::Local<v8::Object>) {
+    let name = v8::String::new(scope, "serveListen").unwrap();
+    let value = v8::Function::new(scope, serve_listen).unwrap();
+    bindings.set(scope, name.into(), value.into());
+
+    let name = v8::String::new(scope, "serveRespond").unwrap();
+    let value = v8::Function::new(scope, serve_respond).unwrap();
+    bindings.set(scope, name.into(), value.into());
+
+    let name = v8::String::new(scope, "serveWrite").unwrap();
+    let value = v8::Function::new(scope, serve_write).unwrap();
+    bindings.set(scope, name.into(), value.into());
+
+    let name = v8::String::new(scope, "serveEnd").unwrap();
+    let value = v8::Function::new(scope, serve_end).unwrap();
+    bindings.set(scope, name.into(), value.into());
+
+    let name = v8::String::new(scope, "serveUpgrade").unwrap();
+    let value = v8::Function::new(scope, serve_upgrade).unwrap();
+    bindings.set(scope, name.into(), value.into());
+
+    let name = v8::String::new(scope, "serveClose").unwrap();
+    let value = v8::Function::new(scope, serve_close).unwrap();
+    bindings.set(scope, name.into(), value.into());
+
+    let name = v8::String::new(scope, "serveRef").unwrap();
+    let value = v8::Function::new(scope, serve_ref).unwrap();
+    bindings.set(scope, name.into(), value.into());
+}
+
+/// Read a numeric ID argument, throwing if it is not a number
+#[inline]
+fn get_id(scope: &mut v8::PinScope, value: v8::Local<v8::Value>, name: &str) -> Option<u64> {
+    if value.is_number() {
+        Some(value.number_value(scope).unwrap() as u64)
+    } else {
+        crate::error::throw_type_error(scope, &format!("{} must be a number", name));
+        None
+    }
+}
+
+/// Convert an array of [name, value] pairs into header tuples
+fn get_headers(
+    scope: &mut v8::PinScope,
+    value: v8::Local<v8::Value>,
+) -> Option<Vec<(String, String)>> {
+    let Ok(array) = crate::error::try_get_array_result(value) else {
+        crate::error::throw_type_error(scope, "Headers must be an array");
+        return None;
+    };
+    let mut headers = Vec::with_capacity(array.length() as usize);
+    for i in 0..array.length() {
+        let entry = array.get_index(scope, i)?;
+        let Ok(entry) = crate::error::try_get_array_result(entry) else {
+            crate::error::throw_type_error(scope, "Header must be a [name, value] array");
+            return None;
+        };
+        let name = entry.get_index(scope, 0)?;
+        let name = crate::error::to_rust_string_or_throw(scope, name, "header name")?;
+        let value = entry.get_index(scope, 1)?;
+        let value = crate::error::to_rust_string_or_throw(scope, value, "header value")?;
+        headers.push((name, value));
+    }
+    Some(headers)
+}
+
+/// Send a chunk to the connection waiting on a request
+/// Returns false if the request is unknown or the client has gone away
+#[inline]
+fn send_chunk(scope: &mut v8::PinScope, request_id: u64, chunk: ResponseChunk) -> bool {
+    let isolate: &mut v8::Isolate = scope;
+    let state = crate::IsolateState::get(isolate);
+    let state = state.borrow();
+    let responses = state.server_responses.borrow();
+    match responses.get(&request_id) {
+        Some(sender) => sender.send(chunk).is_ok(),
+        None => false,
+    }
+}
+
+/// Stop tracking a request once its response is complete
+#[inline]
+fn remove_request(scope: &mut v8::PinScope, request_id: u64) {
+    let isolate: &mut v8::Isolate = scope;
+    let state = crate::IsolateState::get(isolate);
+    state
+        .borrow()
+        .server_responses
+        .borrow_mut()
+        .remove(&request_id);
+}
+
+/// Start listening for HTTP requests
+/// Args: hostname, port, callback (called by the event loop for each request)
+/// Returns: { id, hostname, port }
+#[inline]
+fn serve_listen(
+    scope: &mut v8::PinScope,
+    args: v8::FunctionCallbackArguments,
+    mut retval: v8::ReturnValue,
+) {
+    if !crate::error::check_arg_count(scope, &args, 3, "serveListen") {
+        return;
+    }
+
+    let Some(hostname) = crate::error::to_rust_string_or_throw(scope, args.get(0), "hostname")
+    else {
+        return;
+    };
+    let Some(port) = get_id(scope, args.get(1), "Port") else {
+        return;
+    };
+    if port > u16::MAX as u64 {
+        crate::error::throw_range_error(scope, &format!("Invalid port: {}", port));
+        return;
+    }
+    let callback = match crate::error::try_get_function_result(args.get(2)) {
+        Ok(callback) => callback,
+        Err(msg) => {
+            crate::error::throw_type_error(scope, msg);
+            return;
+        }
+    };
+
+    // IPv6 addresses need brackets to be combined with a port
+    let bind_addr = if hostname.contains(':') && !hostname.starts_with('[') {
+        format!("[{}]:{}", hostname, port)
+    } else {
+        format!("{}:{}", hostname, port)
+    };
+    let server = match HttpServer::bind(&bind_addr) {
+        Ok(server) => server,
+        Err(e) => {
+            crate::error::throw_error(scope, &format!("Failed to listen on {}: {}", bind_addr, e));
+            return;
+        }
+    };
+    let local_addr = server.local_addr();
+
+    let isolate: &mut v8::Isolate = scope;
+    let state = crate::IsolateState::get(isolate);
+    let server_id = {
+        let next_server_id = state.borrow().next_server_id.clone();
+        let mut next_id = next_server_id.borrow_mut();
+        let id = *next_id;
+        *next_id += 1;
+        id
+    };
+
+    let active_server = crate::isolate_state::ActiveServer {
+        server,
+        callback: v8::Global::new(scope, callback),
+        is_ref: true, // By default, servers keep the event loop alive
+    };
+    state
+        .borrow()
+        .active_net
+        .borrow_mut()
+        .http_servers
+        .insert(server_id, active_server);
+
+    let result = v8::Object::new(scope);
+    let key = v8::String::new(scope, "id").unwrap();
+    let value = v8::Number::new(scope, server_id as f64);
+    result.set(scope, key.into(), value.into());
+    let key = v8::String::new(scope, "hostname").unwrap();
+    let value = v8::String::new(scope, &local_addr.ip().to_string()).unwrap();
+    result.set(scope, key.into(), value.into());
+    let key = v8::String::new(scope, "port").unwrap();
+    let value = v8::Number::new(scope, local_addr.port() as f64);
+    result.set(scope, key.into(), value.into());
+    retval.set(result.into());
+}
+
+/// Send a response head, and the whole body unless it is streamed
+/// Args: requestId, status, headers ([name, value][]), body (Uint8Array | null), streaming (boolean)
+/// A streamed body is sent afterwards with serveWrite and finished with serveEnd.
+#[inline]
+fn serve_respond(
+    scope: &mut v8::PinScope,
+    args: v8::FunctionCallbackArguments,
+    _retval: v8::ReturnValue,
+) {
+    if !crate::error::check_arg_count(scope, &args, 5, "serveRespond") {
+        return;
+    }
+
+    let Some(request_id) = get_id(scope, args.get(0), "Request ID") else {
+        return;
+    };
+    let Some(status) = get_id(scope, args.get(1), "Status") else {
+        return;
+    };
+    let Some(headers) = get_headers(scope, args.get(2)) else {
+        return;
+    };
+    let body = if args.get(3).is_null_or_undefined() {
+        Vec::new()
+    } else {
+        match crate::error::try_get_buffer_source_bytes(args.get(3)) {
+            Ok(bytes) => bytes,
+            Err(msg) => {
+                crate::error::throw_type_error(scope, msg);
+                return;
+            }
+        }
+    };
+    let streaming = args.get(4).boolean_value(scope);
+
+    if streaming {
+        send_chunk(
+            scope,
+            request_id,
+            ResponseChunk::Head {
+                status: status as u16,
+                headers,
+                content_length: None,
+            },
+        );
+        return;
+    }
+
+    let content_length = Some(body.len());
+    if send_chunk(
+        scope,
+        request_id,
+        ResponseChunk::Head {
+            status: status as u16,
+            headers,
+            content_length,
+        },
+    ) {
+        send_chunk(scope, request_id, ResponseChunk::Data(body));
+        send_chunk(scope, request_id, ResponseChunk::End);
+    }
+    remove_request(scope, request_id);
+}
+
+/// Write a chunk of a streamed response body
+/// Args: requestId, chunk (Uint8Array)
+/// Returns: false if the client has disconnected
+#[inline]
+fn serve_write(
+    scope: &mut v8::PinScope,
+    args: v8::FunctionCallbackArguments,
+    mut retval: v8::ReturnValue,
+) {
+    if !crate::error::check_arg_count(scope, &args, 2, "serveWrite") {
+        return;
+    }
+
+    let Some(request_id) = get_id(scope, args.get(0), "Request ID") else {
+        return;
+    };
+    let chunk = match crate::error::try_get_buffer_source_bytes(args.get(1)) {
+        Ok(bytes) => bytes,
+        Err(msg) => {
+            crate::error::throw_type_error(scope, msg);
+            return;
+        }
+    };
+
+    let sent = send_chunk(scope, request_id, ResponseChunk::Data(chunk));
+    retval.set_bool(sent);
+}
+
+/// Finish a streamed response body
+/// Args: requestId, failed (boolean, closes the connection without completing the body)
+#[inline]
+fn serve_end(
+    scope: &mut v8::PinScope,
+    args: v8::FunctionCallbackArguments,
+    _retval: v8::ReturnValue,
+) {
+    if !crate::error::check_arg_count(scope, &args, 1, "serveEnd") {
+        return;
+    }
+
+    let Some(request_id) = get_id(scope, args.get(0), "Request ID") else {
+        return;
+    };
+    let chunk = if args.get(1).boolean_value(scope) {
+        ResponseChunk::Error
+    } else {
+        ResponseChunk::End
+    };
+
+    send_chunk(scope, request_id, chunk);
+    remove_request(scope, request_id);
+}
+
+/// Answer a request by upgrading its connection to a WebSocket
+/// Args: requestId, key (Sec-WebSocket-Key), protocol ('' for none), headers ([name, value][]),
+/// callback (called with (event, ...data) by the event loop, like wsConnect's)
+/// Returns: WebSocket ID, usable with the WebSocket bindings
+#[inline]
+fn serve_upgrade(
+    scope: &mut v8::PinScope,
+    args: v8::FunctionCallbackArguments,
+    mut retval: v8::ReturnValue,
+) {
+    if !crate::error::check_arg_count(scope, &args, 5, "serveUpgrade") {
+        return;
+    }
+
+    let Some(request_id) = get_id(scope, args.get(0), "Request ID") else {
+        return;
+    };
+    let Some(key) = crate::error::to_rust_string_or_throw(scope, args.get(1), "key") else {
+        return;
+    };
+    let Some(protocol) = crate::error::to_rust_string_or_throw(scope, args.get(2), "protocol")
+    else {
+        return;
+    };
+    let Some(headers) = get_headers(scope, args.get(3)) else {
+        return;
+    };
+    let callback = match crate::error::try_get_function_result(args.get(4)) {
+        Ok(callback) => v8::Global::new(scope, callback),
+        Err(msg) => {
+            crate::error::throw_type_error(scope, msg);
+            return;
+        }
+    };
+
+    let mut response_headers = vec![
+        ("upgrade".to_string(), "websocket".to_string()),
+        ("connection".to_string(), "Upgrade".to_string()),
+        (
+            "sec-websocket-accept".to_string(),
+            crate::websocket::accept_key(&key),
+        ),
+    ];
+    if !protocol.is_empty() {
+        response_headers.push(("sec-websocket-protocol".to_string(), protocol.clone()));
+    }
+    // The handshake headers are managed here; anything else from the response is kept
+    response_headers.extend(headers.into_iter().filter(|(name, _)| {
+        ![
+            "upgrade",
+            "connection",
+            "content-length",
+            "transfer-encoding",
+        ]
+        .iter()
+        .any(|managed| name.eq_ignore_ascii_case(managed))
+            && !name.to_ascii_lowercase().starts_with("sec-websocket-")
+    }));
+
+    // If the client has already gone away, the stream sender is dropped and
+    // the WebSocket fails when it is first polled
+    let (stream_tx, stream_rx) = std::sync::mpsc::channel();
+    send_chunk(
+        scope,
+        request_id,
+        ResponseChunk::Upgrade {
+            headers: response_headers,
+            stream: stream_tx,
+        },
+    );
+    remove_request(scope, request_id);
+
+    let websocket =
+        ActiveWebSocket::accept(ActiveTcpSocket::handed_off(stream_rx), protocol, callback);
+    let isolate: &mut v8::Isolate = scope;
+    let state = crate::IsolateState::get(isolate);
+    let id = {
+        let state = state.borrow();
+        let mut next_id = state.next_tcp_id.borrow_mut();
+        let id = *next_id;
+        *next_id += 1;
+        id
+    };
+    state
+        .borrow()
+        .active_net
+        .borrow_mut()
+        .websockets
+        .insert(id, websocket);
+
+    let result = v8::Number::new(scope, id as f64);
+    retval.set(result.into());
+}
+
+/// Stop accepting connections
+/// Args: serverId
+#[inline]
+fn serve_close(
+    scope: &mut v8::PinScope,
+    args: v8::FunctionCallbackArguments,
+    _retval: v8::ReturnValue,
+) {
+    if !crate::error::check_arg_count(scope, &args, 1, "serveClose") {
+        return;
+    }
+
+    let Some(server_id) = get_id(scope, args.get(0), "Server ID") else {
+        return;
+    };
+
+    // Dropping the server shuts down its accept thread
+    let isolate: &mut v8::Isolate = scope;
+    let state = crate::IsolateState::get(isolate);
+    let server = state
+        .borrow()
+        .active_net
+        .borrow_mut()
+        .http_servers
+        .remove(&server_id);
+    drop(server);
+}
+
+/// Set whether a server keeps the event loop alive
+/// Args: serverId, ref (boolean)
+#[inline]
+fn serve_ref(
+    scope: &mut v8::PinScope,
+    args: v8::FunctionCallbackArguments,
+    _retval: v8::ReturnValue,
+) {
+    if !crate::error::check_arg_count(scope, &args, 2, "serveRef") {
+        return;
+    }
+
+    let Some(server_id) = get_id(scope, args.get(0), "Server ID") else {
+        return;
+    };
+    let is_ref = args.get(1).boolean_value(scope);
+
+    let isolate: &mut v8::Isolate = scope;
+    let state = crate::IsolateState::get(isolate);
+    if let Some(server) = state
+        .borrow()
+        .active_net
+        .borrow_mut()
+        .http_servers
+        .get_mut(&server_id)
+    {
+        server.is_ref = is_ref;
+    }
+}
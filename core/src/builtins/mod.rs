@@ -6,16 +6,19 @@ mod whatwg {
     pub(crate) mod console_impl;
     pub(crate) mod event_impl;
     pub(crate) mod fetch_impl;
+    pub(crate) mod form_data_impl;
     pub(crate) mod queue_microtask_impl;
     pub(crate) mod streams_impl;
     pub(crate) mod structured_clone_impl;
     pub(crate) mod text_encoding_impl;
     pub(crate) mod timers_impl;
     pub(crate) mod url_impl;
+    pub(crate) mod websocket_impl;
 }
 
 // W3C Standards
 mod w3c {
+    pub(crate) mod blob_impl;
     pub(crate) mod crypto_impl;
     pub(crate) mod performance_impl;
 }
@@ -25,14 +28,23 @@ mod node {
     pub(crate) mod buffer_impl;
     pub(crate) mod dgram_impl;
     pub(crate) mod fs_impl;
+    pub(crate) mod module_impl;
+    pub(crate) mod net_impl;
     pub(crate) mod process_impl;
+    pub(crate) mod tls_impl;
+}
+
+// jstime APIs
+mod jstime {
+    pub(crate) mod serve_impl;
 }
 
 pub(crate) fn get_external_references() -> SmallVec<[v8::ExternalReference; 128]> {
     // Pre-allocate with capacity to avoid reallocation
-    // Total: 2 (base64) + 1 (console) + 6 (event) + 1 (queue_microtask) + 3 (url lazy) + 3 (timers)
-    //        + 1 (fetch) + 3 (streams) + 1 (structured_clone) + 3 (text_encoding)
-    //        + 3 (crypto) + 2 (performance) + 21 (fs) + 7 (process) = 57
+    // Total: 2 (base64) + 1 (console) + 7 (event) + 1 (queue_microtask) + 3 (url lazy) + 3 (timers)
+    //        + 1 (fetch) + 1 (form_data) + 3 (streams) + 1 (structured_clone) + 3 (text_encoding)
+    //        + 4 (websocket) + 7 (blob) + 3 (crypto) + 2 (performance) + 21 (fs) + 4 (module) + 12 (net)
+    //        + 7 (process) + 2 (tls) + 7 (serve) = 87
     let mut refs = SmallVec::new();
 
     // WHATWG
@@ -43,11 +55,14 @@ pub(crate) fn get_external_references() -> SmallVec<[v8::ExternalReference; 128]
     refs.extend(whatwg::url_impl::get_external_references());
     refs.extend(whatwg::timers_impl::get_external_references());
     refs.extend(whatwg::fetch_impl::get_external_references());
+    refs.extend(whatwg::form_data_impl::get_external_references());
     refs.extend(whatwg::streams_impl::get_external_references());
     refs.extend(whatwg::structured_clone_impl::get_external_references());
     refs.extend(whatwg::text_encoding_impl::get_external_references());
+    refs.extend(whatwg::websocket_impl::get_external_references());
 
     // W3C
+    refs.extend(w3c::blob_impl::get_external_references());
     refs.extend(w3c::crypto_impl::get_external_references());
     refs.extend(w3c::performance_impl::get_external_references());
 
@@ -55,7 +70,13 @@ pub(crate) fn get_external_references() -> SmallVec<[v8::ExternalReference; 128]
     refs.extend(node::buffer_impl::get_external_references());
     refs.extend(node::dgram_impl::get_external_references());
     refs.extend(node::fs_impl::get_external_references());
+    refs.extend(node::module_impl::get_external_references());
+    refs.extend(node::net_impl::get_external_references());
     refs.extend(node::process_impl::get_external_references());
+    refs.extend(node::tls_impl::get_external_references());
+
+    // jstime
+    refs.extend(jstime::serve_impl::get_external_references());
 
     refs
 }
@@ -75,11 +96,14 @@ impl Builtins {
         whatwg::url_impl::register_bindings(scope, bindings); // V8-native URL with property accessors
         whatwg::timers_impl::register_bindings(scope, bindings);
         whatwg::fetch_impl::register_bindings(scope, bindings);
+        whatwg::form_data_impl::register_bindings(scope, bindings);
         whatwg::streams_impl::register_bindings(scope, bindings);
         whatwg::structured_clone_impl::register_bindings(scope, bindings);
         whatwg::text_encoding_impl::register_bindings(scope, bindings);
+        whatwg::websocket_impl::register_bindings(scope, bindings);
 
         // W3C
+        w3c::blob_impl::register_bindings(scope, bindings);
         w3c::crypto_impl::register_bindings(scope, bindings);
         w3c::performance_impl::register_bindings(scope, bindings);
 
@@ -87,7 +111,13 @@ impl Builtins {
         node::buffer_impl::register_bindings(scope, bindings);
         node::dgram_impl::register_bindings(scope, bindings);
         node::fs_impl::register_bindings(scope, bindings);
+        node::module_impl::register_bindings(scope, bindings);
+        node::net_impl::register_bindings(scope, bindings);
         node::process_impl::register_bindings(scope, bindings);
+        node::tls_impl::register_bindings(scope, bindings);
+
+        // jstime
+        jstime::serve_impl::register_bindings(scope, bindings);
 
         macro_rules! builtin {
             ($name:expr) => {
@@ -107,15 +137,20 @@ impl Builtins {
         builtin!("./whatwg/base64.js");
         builtin!("./whatwg/console.js");
         builtin!("./whatwg/event.js");
+        builtin!("./whatwg/dom_exception.js");
+        builtin!("./whatwg/abort.js");
         builtin!("./whatwg/queue_microtask.js");
         builtin!("./whatwg/url.js"); // Lazy-loading wrapper with Rust-side storage
         builtin!("./whatwg/timers.js");
         builtin!("./whatwg/fetch.js");
+        builtin!("./whatwg/form_data.js");
         builtin!("./whatwg/streams.js");
         builtin!("./whatwg/structured_clone.js");
         builtin!("./whatwg/text_encoding.js");
+        builtin!("./whatwg/websocket.js");
 
         // W3C
+        builtin!("./w3c/blob.js");
         builtin!("./w3c/crypto.js");
         builtin!("./w3c/performance.js");
 
@@ -124,7 +159,14 @@ impl Builtins {
         builtin!("./node/dgram.js");
         builtin!("./node/events.js");
         builtin!("./node/fs.js");
+        builtin!("./node/net.js");
+        builtin!("./node/tls.js");
         builtin!("./node/process.js");
+        builtin!("./node/module.js");
+
+        // jstime
+        builtin!("./jstime/serve.js");
+        builtin!("./jstime/connect_tls.js");
 
         // Polyfills (load last to override native implementations)
         // Date locale methods polyfill - replaces broken V8 ICU implementation
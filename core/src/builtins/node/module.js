@@ -0,0 +1,235 @@
+// Node.js Module API
+// CommonJS require() with Node-compatible resolution and caching
+// https://nodejs.org/api/modules.html
+
+'use strict';
+
+// eslint-disable-next-line no-unused-expressions
+(({ moduleResolve, moduleReadFile, moduleCompile, moduleFormat }) => {
+  const dirname = (path) => {
+    const index = path.lastIndexOf('/');
+    if (index === -1) return '.';
+    return index === 0 ? '/' : path.slice(0, index);
+  };
+
+  // Built-in modules whose require() value isn't the registered module object
+  const builtinOverrides = {
+    events: () => globalThis.__node_modules['node:events'].EventEmitter,
+    process: () => globalThis.process,
+    module: () => Module,
+  };
+
+  function loadBuiltin(name) {
+    if (Object.hasOwn(builtinOverrides, name)) {
+      return builtinOverrides[name]();
+    }
+    return globalThis.__node_modules[`node:${name}`];
+  }
+
+  function isBuiltin(specifier) {
+    const name = String(specifier).startsWith('node:') ? String(specifier).slice(5) : String(specifier);
+    return Object.hasOwn(builtinOverrides, name) || Object.hasOwn(globalThis.__node_modules, `node:${name}`);
+  }
+
+  function moduleNotFound(specifier, parent) {
+    const from = parent && parent.filename ? ` from '${parent.filename}'` : '';
+    const error = new Error(`Cannot find module '${specifier}'${from}`);
+    error.code = 'MODULE_NOT_FOUND';
+    return error;
+  }
+
+  /**
+   * A CommonJS module, as seen through `module` inside it
+   */
+  class Module {
+    constructor(id = '', parent = null) {
+      this.id = id;
+      this.filename = null;
+      this.path = dirname(id);
+      this.loaded = false;
+      this.exports = {};
+      this.children = [];
+      this.parent = parent;
+      if (parent) {
+        parent.children.push(this);
+      }
+    }
+
+    /**
+     * Resolve a specifier relative to this module and load it
+     * @param {string} specifier
+     */
+    require(specifier) {
+      if (typeof specifier !== 'string') {
+        throw new TypeError('The "id" argument must be of type string');
+      }
+      if (specifier === '') {
+        throw new TypeError("The argument 'id' must be a non-empty string");
+      }
+      return Module._load(specifier, this);
+    }
+
+    /**
+     * Resolve a specifier to a file path or built-in module name
+     * @param {string} specifier
+     * @param {Module|null} parent - The requiring module; relative to the working directory if null
+     * @returns {string}
+     */
+    static _resolveFilename(specifier, parent) {
+      specifier = String(specifier);
+      if (specifier.startsWith('node:')) {
+        if (!isBuiltin(specifier)) {
+          const error = new Error(`No such built-in module: ${specifier}`);
+          error.code = 'ERR_UNKNOWN_BUILTIN_MODULE';
+          throw error;
+        }
+        return specifier;
+      }
+      if (isBuiltin(specifier)) {
+        return `node:${specifier}`;
+      }
+      const referrer = parent && parent.filename ? parent.filename : `${process.cwd()}/`;
+      const filename = moduleResolve(specifier, referrer);
+      if (filename === undefined) {
+        throw moduleNotFound(specifier, parent);
+      }
+      return filename;
+    }
+
+    /**
+     * Load a module by specifier, returning its exports. Modules are cached by file path.
+     * @param {string} specifier
+     * @param {Module|null} parent
+     */
+    static _load(specifier, parent) {
+      const filename = Module._resolveFilename(specifier, parent);
+      if (filename.startsWith('node:')) {
+        return loadBuiltin(filename.slice(5));
+      }
+
+      const cached = Module._cache[filename];
+      if (cached !== undefined) {
+        if (parent && !parent.children.includes(cached)) {
+          parent.children.push(cached);
+        }
+        return cached.exports;
+      }
+
+      const format = moduleFormat(filename);
+      if (format === 'module') {
+        const error = new Error(`require() of ES Module ${filename} not supported. Use import() instead.`);
+        error.code = 'ERR_REQUIRE_ESM';
+        throw error;
+      }
+
+      const module = new Module(filename, parent);
+      module.filename = filename;
+      module.paths = Module._nodeModulePaths(module.path);
+      Module._cache[filename] = module;
+
+      let threw = true;
+      try {
+        if (format === 'json') {
+          try {
+            module.exports = JSON.parse(moduleReadFile(filename));
+          } catch (error) {
+            error.message = `${filename}: ${error.message}`;
+            throw error;
+          }
+        } else {
+          const wrapper = moduleCompile(moduleReadFile(filename), filename);
+          const moduleRequire = makeRequire(module);
+          wrapper.call(module.exports, module.exports, moduleRequire, module, filename, module.path);
+        }
+        threw = false;
+      } finally {
+        if (threw) {
+          delete Module._cache[filename];
+          if (parent) {
+            const index = parent.children.indexOf(module);
+            if (index !== -1) parent.children.splice(index, 1);
+          }
+        }
+      }
+      module.loaded = true;
+      return module.exports;
+    }
+
+    /**
+     * The node_modules directories searched from a directory, nearest first
+     * @param {string} from
+     * @returns {string[]}
+     */
+    static _nodeModulePaths(from) {
+      const paths = [];
+      let dir = from;
+      while (true) {
+        if (!dir.endsWith('/node_modules')) {
+          paths.push(dir === '/' ? '/node_modules' : `${dir}/node_modules`);
+        }
+        const parent = dirname(dir);
+        if (parent === dir) break;
+        dir = parent;
+      }
+      return paths;
+    }
+  }
+
+  Module._cache = Object.create(null);
+  Module.builtinModules = [
+    ...new Set(Object.keys(globalThis.__node_modules).filter((name) => !name.startsWith('node:'))
+      .concat(Object.keys(builtinOverrides))),
+  ].sort();
+  Module.isBuiltin = isBuiltin;
+  Module.createRequire = createRequire;
+  Module.Module = Module;
+
+  // Build the require function given to a module
+  function makeRequire(module) {
+    const moduleRequire = (specifier) => module.require(specifier);
+    moduleRequire.resolve = (specifier) => {
+      const filename = Module._resolveFilename(specifier, module);
+      // Built-ins resolve to themselves, keeping or omitting the node: prefix
+      return filename.startsWith('node:') ? String(specifier) : filename;
+    };
+    moduleRequire.cache = Module._cache;
+    return moduleRequire;
+  }
+
+  /**
+   * Create a require function that resolves relative to a file, as if it were called from that file
+   * @param {string|URL} filename - An absolute path or file: URL, such as import.meta.url.
+   *   A path ending in '/' is treated as a directory.
+   * @returns {Function}
+   */
+  function createRequire(filename) {
+    let path;
+    if (filename instanceof URL || (typeof filename === 'string' && filename.startsWith('file:'))) {
+      const url = new URL(filename);
+      if (url.protocol !== 'file:') {
+        throw new TypeError(`The URL must be of scheme file: ${url.href}`);
+      }
+      path = decodeURIComponent(url.pathname);
+    } else if (typeof filename === 'string' && filename.startsWith('/')) {
+      path = filename;
+    } else {
+      const error = new TypeError(
+        `The argument 'filename' must be a file URL object, file URL string, or absolute path string. Received ${String(filename)}`,
+      );
+      error.code = 'ERR_INVALID_ARG_VALUE';
+      throw error;
+    }
+
+    const module = new Module(path, null);
+    module.filename = path;
+    if (path.endsWith('/')) {
+      module.path = path.length > 1 ? path.slice(0, -1) : '/';
+    }
+    module.paths = Module._nodeModulePaths(module.path);
+    return makeRequire(module);
+  }
+
+  globalThis.__node_modules = globalThis.__node_modules || {};
+  globalThis.__node_modules['node:module'] = Module;
+  globalThis.__node_modules['module'] = Module;
+});
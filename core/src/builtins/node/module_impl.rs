@@ -0,0 +1,160 @@
+pub(crate) fn get_external_references() -> Vec<v8::ExternalReference> {
+    vec![
+        v8::ExternalReference {
+            function: v8::MapFnTo::map_fn_to(module_resolve),
+        },
+        v8::ExternalReference {
+            function: v8::MapFnTo::map_fn_to(module_read_file),
+        },
+        v8::ExternalReference {
+            function: v8::MapFnTo::map_fn_to(module_compile),
+        },
+        v8::ExternalReference {
+            function: v8::MapFnTo::map_fn_to(module_format),
+        },
+    ]
+}
+
+pub(crate) fn register_bindings(scope: &mut v8::PinScope, bindings: v8::Local<v8::Object>) {
+    let name = v8::String::new(scope, "moduleResolve").unwrap();
+    let value = v8::Function::new(scope, module_resolve).unwrap();
+    bindings.set(scope, name.into(), value.into());
+
+    let name = v8::String::new(scope, "moduleReadFile").unwrap();
+    let value = v8::Function::new(scope, module_read_file).unwrap();
+    bindings.set(scope, name.into(), value.into());
+
+    let name = v8::String::new(scope, "moduleCompile").unwrap();
+    let value = v8::Function::new(scope, module_compile).unwrap();
+    bindings.set(scope, name.into(), value.into());
+
+    let name = v8::String::new(scope, "moduleFormat").unwrap();
+    let value = v8::Function::new(scope, module_format).unwrap();
+    bindings.set(scope, name.into(), value.into());
+}
+
+/// moduleResolve(specifier, referrer): the absolute path `require(specifier)`
+/// loads from the file or directory `referrer`, or undefined if there is none
+#[inline]
+fn module_resolve(
+    scope: &mut v8::PinScope,
+    args: v8::FunctionCallbackArguments,
+    mut retval: v8::ReturnValue,
+) {
+    if !crate::error::check_arg_count(scope, &args, 2, "moduleResolve") {
+        return;
+    }
+    let Some(specifier) = crate::error::to_rust_string_or_throw(scope, args.get(0), "specifier")
+    else {
+        return;
+    };
+    let Some(referrer) = crate::error::to_rust_string_or_throw(scope, args.get(1), "referrer")
+    else {
+        return;
+    };
+
+    let isolate: &mut v8::Isolate = scope;
+    let conditions = crate::IsolateState::get(isolate)
+        .borrow()
+        .conditions
+        .require
+        .clone();
+    if let Some(resolved) = crate::module::resolve_require(&referrer, &specifier, &conditions) {
+        let resolved = v8::String::new(scope, &resolved).unwrap();
+        retval.set(resolved.into());
+    }
+}
+
+/// moduleReadFile(path): the source of a module file
+#[inline]
+fn module_read_file(
+    scope: &mut v8::PinScope,
+    args: v8::FunctionCallbackArguments,
+    mut retval: v8::ReturnValue,
+) {
+    if !crate::error::check_arg_count(scope, &args, 1, "moduleReadFile") {
+        return;
+    }
+    let Some(path) = crate::error::to_rust_string_or_throw(scope, args.get(0), "path") else {
+        return;
+    };
+
+    match crate::module::read_source_cached(&path) {
+        Ok(source) if !crate::module::check_integrity(scope, &path, source.as_bytes()) => {}
+        Ok(source) => {
+            let source = source.strip_prefix('\u{feff}').unwrap_or(&source);
+            let Some(source) = v8::String::new(scope, source) else {
+                crate::error::throw_range_error(scope, "Module source is too large");
+                return;
+            };
+            retval.set(source.into());
+        }
+        Err(e) => crate::error::throw_io_error(scope, &e),
+    }
+}
+
+/// moduleCompile(source, filename): compile a CommonJS source into its
+/// wrapper function. Syntax errors are thrown with `filename` as the location.
+#[inline]
+fn module_compile(
+    scope: &mut v8::PinScope,
+    args: v8::FunctionCallbackArguments,
+    mut retval: v8::ReturnValue,
+) {
+    if !crate::error::check_arg_count(scope, &args, 2, "moduleCompile") {
+        return;
+    }
+    let Some(source) = crate::error::to_rust_string_or_throw(scope, args.get(0), "source") else {
+        return;
+    };
+    let Some(filename) = crate::error::to_rust_string_or_throw(scope, args.get(1), "filename")
+    else {
+        return;
+    };
+
+    let wrapped = crate::commonjs::wrap(&source);
+    let Some(code) = v8::String::new(scope, &wrapped) else {
+        crate::error::throw_range_error(scope, "Module source is too large");
+        return;
+    };
+    let filename = v8::String::new(scope, &filename).unwrap();
+    let origin = crate::js_loading::create_script_origin(scope, filename, false);
+
+    // Compile and run errors are left pending so they reach the caller of require()
+    let Some(script) = v8::Script::compile(scope, code, Some(&origin)) else {
+        return;
+    };
+    if let Some(wrapper) = script.run(scope) {
+        retval.set(wrapper);
+    }
+}
+
+/// moduleFormat(path): how `require()` loads a file, "commonjs", "json" or
+/// "module". ES modules (`.mjs`, or `.js` in a `"type": "module"` package)
+/// can't be loaded synchronously.
+#[inline]
+fn module_format(
+    scope: &mut v8::PinScope,
+    args: v8::FunctionCallbackArguments,
+    mut retval: v8::ReturnValue,
+) {
+    if !crate::error::check_arg_count(scope, &args, 1, "moduleFormat") {
+        return;
+    }
+    let Some(path) = crate::error::to_rust_string_or_throw(scope, args.get(0), "path") else {
+        return;
+    };
+
+    let format = if path.ends_with(".json") {
+        "json"
+    } else if path.ends_with(".mjs")
+        || (path.ends_with(".js")
+            && crate::module::package_type(&path).as_deref() == Some("module"))
+    {
+        "module"
+    } else {
+        "commonjs"
+    };
+    let format = v8::String::new(scope, format).unwrap();
+    retval.set(format.into());
+}
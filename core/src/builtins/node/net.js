@@ -0,0 +1,693 @@
+// Node.js net API
+// TCP sockets and servers compatible with Node.js
+// https://nodejs.org/api/net.html
+
+'use strict';
+
+// eslint-disable-next-line no-unused-expressions
+(({ netConnect, netListen, netServerClose, netAttach, netSocketInfo, netWrite, netEnd, netDestroy, netSetNoDelay, netSetKeepAlive, netSetPaused, netRef }) => {
+  const { EventEmitter } = globalThis.__node_modules['node:events'];
+
+  // Wrap received bytes in a Buffer without copying, like Node.js does
+  function toBuffer(bytes) {
+    return globalThis.Buffer ? globalThis.Buffer.from(bytes.buffer, bytes.byteOffset, bytes.byteLength) : bytes;
+  }
+
+  // Convert data passed to write() or end() into bytes
+  function toBytes(data, encoding) {
+    if (typeof data === 'string') {
+      if (globalThis.Buffer && encoding && encoding !== 'utf8' && encoding !== 'utf-8') {
+        return globalThis.Buffer.from(data, encoding);
+      }
+      return new TextEncoder().encode(data);
+    }
+    if (ArrayBuffer.isView(data) || data instanceof ArrayBuffer) {
+      return data;
+    }
+    throw new TypeError('The "chunk" argument must be of type string or an instance of Buffer or Uint8Array');
+  }
+
+  // Normalize connect()/createConnection() arguments into [options, callback]
+  function normalizeConnectArgs(args) {
+    let options;
+    if (args[0] !== null && typeof args[0] === 'object') {
+      options = { ...args[0] };
+    } else {
+      options = { port: args[0] };
+      if (typeof args[1] === 'string') {
+        options.host = args[1];
+      }
+    }
+    const last = args[args.length - 1];
+    return [options, typeof last === 'function' ? last : undefined];
+  }
+
+  /**
+   * Socket class - a TCP connection
+   * Emits 'connect', 'ready', 'data', 'end', 'drain', 'finish', 'timeout', 'error' and 'close'
+   */
+  class Socket extends EventEmitter {
+    #id = null;
+    #connecting = false;
+    #destroyed = false;
+    #readEnded = false;
+    #writeEnded = false;
+    #writeFinished = false;
+    #allowHalfOpen;
+    #encoding = null;
+    #info = null;
+    #isRef = true;
+    #noDelay = null;
+    #keepAlive = null;
+    #timeout = 0;
+    #timeoutTimer = null;
+
+    constructor(options = {}) {
+      super();
+      this.#allowHalfOpen = Boolean(options.allowHalfOpen);
+      this.bytesRead = 0;
+      this.bytesWritten = 0;
+    }
+
+    /**
+     * Connect to a server
+     * connect(port[, host][, listener]) or connect(options[, listener])
+     * @returns {Socket} this
+     */
+    connect(...args) {
+      if (this.#id !== null || this.#destroyed) {
+        throw new Error('Socket is already connected');
+      }
+      const [options, listener] = normalizeConnectArgs(args);
+      if (options.path !== undefined) {
+        throw new Error('IPC connections (options.path) are not supported');
+      }
+      const host = options.host || 'localhost';
+      const port = Number(options.port);
+      if (!Number.isInteger(port) || port < 0 || port > 65535) {
+        throw new RangeError(`Port should be >= 0 and < 65536. Received ${options.port}.`);
+      }
+      if (options.noDelay) {
+        this.#noDelay = true;
+      }
+      if (options.keepAlive) {
+        this.#keepAlive = [true, options.keepAliveInitialDelay || 0];
+      }
+      if (listener) {
+        this.once('connect', listener);
+      }
+
+      this.#id = this._connectHandle(host, port, options, (event, data) => this._onEvent(event, data));
+      this.#connecting = true;
+      if (!this.#isRef) {
+        netRef(this.#id, false);
+      }
+      return this;
+    }
+
+    // Internal: open the native socket and return its ID. Overridden by tls.TLSSocket.
+    _connectHandle(host, port, options, handler) {
+      return netConnect(host, port, handler);
+    }
+
+    // Internal: the native socket ID, or null when not connected
+    get _handle() {
+      return this.#id;
+    }
+
+    // Internal: take over a socket accepted by a server
+    _attach(id) {
+      this.#id = id;
+      netAttach(id, (event, data) => this._onEvent(event, data));
+      this._onConnected();
+    }
+
+    // Internal: the connection is established
+    _onConnected() {
+      this.#connecting = false;
+      this.#info = netSocketInfo(this.#id) || null;
+      if (this.#noDelay !== null) {
+        netSetNoDelay(this.#id, this.#noDelay);
+      }
+      if (this.#keepAlive !== null) {
+        netSetKeepAlive(this.#id, this.#keepAlive[0], this.#keepAlive[1]);
+      }
+    }
+
+    // Internal: handle an event from the event loop
+    _onEvent(event, data) {
+      if (this.#destroyed) {
+        return;
+      }
+      switch (event) {
+        case 'connect':
+          this._onConnected();
+          this.#resetTimeout();
+          this.emit('connect');
+          this.emit('ready');
+          break;
+        case 'data':
+          this.bytesRead += data.byteLength;
+          this.#resetTimeout();
+          this.emit('data', this.#encoding ? toBuffer(data).toString(this.#encoding) : toBuffer(data));
+          break;
+        case 'end':
+          this.#readEnded = true;
+          this.emit('end');
+          if (!this.#allowHalfOpen && !this.#writeEnded) {
+            this.end();
+          }
+          this.#maybeClose();
+          break;
+        case 'drain':
+          this.#resetTimeout();
+          this.emit('drain');
+          break;
+        case 'finish':
+          this.#writeFinished = true;
+          this.emit('finish');
+          this.#maybeClose();
+          break;
+        case 'error':
+          // The socket has already been closed by the event loop
+          this.#id = null;
+          this.destroy(data);
+          break;
+      }
+    }
+
+    // Close once both directions are done
+    #maybeClose() {
+      if (this.#readEnded && this.#writeFinished) {
+        this.destroy();
+      }
+    }
+
+    #resetTimeout() {
+      if (this.#timeoutTimer !== null) {
+        clearTimeout(this.#timeoutTimer);
+        this.#timeoutTimer = null;
+      }
+      // Timers can't be unreferenced, so an unreferenced socket has no idle timeout
+      if (this.#timeout > 0 && this.#isRef && !this.#destroyed) {
+        this.#timeoutTimer = setTimeout(() => {
+          this.#timeoutTimer = null;
+          this.emit('timeout');
+        }, this.#timeout);
+      }
+    }
+
+    /**
+     * Send data on the socket
+     * @param {string|Buffer|Uint8Array} data
+     * @param {string} [encoding] - Encoding for string data
+     * @param {Function} [callback] - Called once the data has been handed to the OS
+     * @returns {boolean} false if the data was queued; wait for 'drain' before writing more
+     */
+    write(data, encoding, callback) {
+      if (typeof encoding === 'function') {
+        callback = encoding;
+        encoding = undefined;
+      }
+      if (this.#destroyed || this.#writeEnded || this.#id === null) {
+        const err = new Error(this.#id === null && !this.#destroyed ? 'This socket is not connected' : 'This socket has been ended');
+        err.code = 'ERR_STREAM_WRITE_AFTER_END';
+        queueMicrotask(() => {
+          if (callback) {
+            callback(err);
+          }
+          this.emit('error', err);
+        });
+        return false;
+      }
+
+      const bytes = toBytes(data, encoding);
+      let flushed;
+      try {
+        flushed = netWrite(this.#id, bytes);
+      } catch (err) {
+        queueMicrotask(() => {
+          if (callback) {
+            callback(err);
+          }
+        });
+        this.destroy(err);
+        return false;
+      }
+      this.bytesWritten += bytes.byteLength;
+      this.#resetTimeout();
+      if (callback) {
+        if (flushed) {
+          queueMicrotask(() => callback());
+        } else {
+          this.once('drain', () => callback());
+        }
+      }
+      return flushed;
+    }
+
+    /**
+     * Half-close the socket: send any final data, then stop writing
+     * @returns {Socket} this
+     */
+    end(data, encoding, callback) {
+      if (typeof data === 'function') {
+        callback = data;
+        data = undefined;
+      } else if (typeof encoding === 'function') {
+        callback = encoding;
+        encoding = undefined;
+      }
+      if (data !== undefined && data !== null) {
+        this.write(data, encoding);
+      }
+      if (callback) {
+        this.once('finish', callback);
+      }
+      if (!this.#writeEnded && !this.#destroyed && this.#id !== null) {
+        this.#writeEnded = true;
+        netEnd(this.#id);
+      }
+      return this;
+    }
+
+    /**
+     * Close the socket immediately
+     * @param {Error} [error] - Emitted as 'error' before 'close'
+     * @returns {Socket} this
+     */
+    destroy(error) {
+      if (this.#destroyed) {
+        return this;
+      }
+      this.#destroyed = true;
+      this.#connecting = false;
+      if (this.#timeoutTimer !== null) {
+        clearTimeout(this.#timeoutTimer);
+        this.#timeoutTimer = null;
+      }
+      if (this.#id !== null) {
+        netDestroy(this.#id);
+      }
+      queueMicrotask(() => {
+        if (error) {
+          this.emit('error', error);
+        }
+        this.emit('close', Boolean(error));
+      });
+      return this;
+    }
+
+    /**
+     * Decode received data as strings
+     * @param {string} encoding
+     * @returns {Socket} this
+     */
+    setEncoding(encoding) {
+      this.#encoding = encoding;
+      return this;
+    }
+
+    /**
+     * Enable or disable Nagle's algorithm
+     * @param {boolean} [noDelay=true]
+     * @returns {Socket} this
+     */
+    setNoDelay(noDelay = true) {
+      this.#noDelay = Boolean(noDelay);
+      if (this.#info !== null && !this.#destroyed) {
+        netSetNoDelay(this.#id, this.#noDelay);
+      }
+      return this;
+    }
+
+    /**
+     * Enable or disable TCP keep-alive
+     * @param {boolean} [enable=false]
+     * @param {number} [initialDelay=0] - Milliseconds of idle time before the first probe
+     * @returns {Socket} this
+     */
+    setKeepAlive(enable = false, initialDelay = 0) {
+      this.#keepAlive = [Boolean(enable), Number(initialDelay) || 0];
+      if (this.#info !== null && !this.#destroyed) {
+        netSetKeepAlive(this.#id, this.#keepAlive[0], this.#keepAlive[1]);
+      }
+      return this;
+    }
+
+    /**
+     * Emit 'timeout' after the socket has been idle for timeout milliseconds (0 disables)
+     * @returns {Socket} this
+     */
+    setTimeout(timeout, callback) {
+      this.#timeout = Number(timeout) || 0;
+      if (callback) {
+        if (this.#timeout === 0) {
+          this.removeListener('timeout', callback);
+        } else {
+          this.once('timeout', callback);
+        }
+      }
+      this.#resetTimeout();
+      return this;
+    }
+
+    /**
+     * Stop reading data
+     * @returns {Socket} this
+     */
+    pause() {
+      if (this.#id !== null && !this.#destroyed) {
+        netSetPaused(this.#id, true);
+      }
+      return this;
+    }
+
+    /**
+     * Resume reading data
+     * @returns {Socket} this
+     */
+    resume() {
+      if (this.#id !== null && !this.#destroyed) {
+        netSetPaused(this.#id, false);
+      }
+      return this;
+    }
+
+    /**
+     * Reference the socket (keeps event loop alive)
+     * @returns {Socket} this
+     */
+    ref() {
+      this.#isRef = true;
+      if (this.#id !== null && !this.#destroyed) {
+        netRef(this.#id, true);
+      }
+      this.#resetTimeout();
+      return this;
+    }
+
+    /**
+     * Unreference the socket (allows event loop to exit)
+     * @returns {Socket} this
+     */
+    unref() {
+      this.#isRef = false;
+      if (this.#id !== null && !this.#destroyed) {
+        netRef(this.#id, false);
+      }
+      this.#resetTimeout();
+      return this;
+    }
+
+    /**
+     * Returns the local address of the socket
+     * @returns {{ address: string, family: string, port: number } | {}}
+     */
+    address() {
+      if (this.#info === null) {
+        return {};
+      }
+      return { address: this.#info.localAddress, family: this.#info.localFamily, port: this.#info.localPort };
+    }
+
+    get localAddress() {
+      return this.#info ? this.#info.localAddress : undefined;
+    }
+
+    get localPort() {
+      return this.#info ? this.#info.localPort : undefined;
+    }
+
+    get localFamily() {
+      return this.#info ? this.#info.localFamily : undefined;
+    }
+
+    get remoteAddress() {
+      return this.#info ? this.#info.remoteAddress : undefined;
+    }
+
+    get remotePort() {
+      return this.#info ? this.#info.remotePort : undefined;
+    }
+
+    get remoteFamily() {
+      return this.#info ? this.#info.remoteFamily : undefined;
+    }
+
+    get connecting() {
+      return this.#connecting;
+    }
+
+    get pending() {
+      return this.#info === null;
+    }
+
+    get destroyed() {
+      return this.#destroyed;
+    }
+
+    get readyState() {
+      if (this.#connecting) {
+        return 'opening';
+      }
+      if (this.#destroyed || this.#info === null) {
+        return 'closed';
+      }
+      if (this.#readEnded && !this.#writeEnded) {
+        return 'writeOnly';
+      }
+      if (this.#writeEnded && !this.#readEnded) {
+        return 'readOnly';
+      }
+      return 'open';
+    }
+  }
+
+  /**
+   * Server class - accepts TCP connections
+   * Emits 'listening', 'connection', 'error' and 'close'
+   */
+  class Server extends EventEmitter {
+    #id = null;
+    #address = null;
+    #isRef = true;
+    #allowHalfOpen;
+    #connections = new Set();
+    #closing = false;
+
+    constructor(options, connectionListener) {
+      super();
+      if (typeof options === 'function') {
+        connectionListener = options;
+        options = {};
+      }
+      this.#allowHalfOpen = Boolean(options && options.allowHalfOpen);
+      if (connectionListener) {
+        this.on('connection', connectionListener);
+      }
+    }
+
+    /**
+     * Start listening for connections
+     * listen([port][, host][, backlog][, callback]) or listen(options[, callback])
+     * @returns {Server} this
+     */
+    listen(...args) {
+      if (this.#id !== null) {
+        throw new Error('Server is already listening');
+      }
+      const callback = typeof args[args.length - 1] === 'function' ? args.pop() : undefined;
+      let port = 0;
+      let host;
+      if (args[0] !== null && typeof args[0] === 'object') {
+        if (args[0].path !== undefined) {
+          throw new Error('IPC servers (options.path) are not supported');
+        }
+        port = args[0].port || 0;
+        host = args[0].host;
+      } else {
+        port = args[0] === undefined ? 0 : args[0];
+        if (typeof args[1] === 'string') {
+          host = args[1];
+        }
+      }
+      if (callback) {
+        this.once('listening', callback);
+      }
+
+      try {
+        const result = netListen(host || '0.0.0.0', Number(port), (socketId) => this._onConnection(socketId));
+        this.#id = result.id;
+        this.#address = { address: result.address, family: result.family, port: result.port };
+        this.#closing = false;
+        if (!this.#isRef) {
+          netRef(this.#id, false);
+        }
+        queueMicrotask(() => this.emit('listening'));
+      } catch (err) {
+        queueMicrotask(() => this.emit('error', err));
+      }
+      return this;
+    }
+
+    // Internal: wrap an accepted socket and emit 'connection'
+    _onConnection(socketId) {
+      const socket = new Socket({ allowHalfOpen: this.#allowHalfOpen });
+      socket._attach(socketId);
+      socket.server = this;
+      this.#connections.add(socket);
+      socket.once('close', () => {
+        this.#connections.delete(socket);
+        this.#maybeEmitClose();
+      });
+      this.emit('connection', socket);
+    }
+
+    #maybeEmitClose() {
+      if (this.#closing && this.#id === null && this.#connections.size === 0) {
+        this.#closing = false;
+        this.emit('close');
+      }
+    }
+
+    /**
+     * Stop accepting connections. 'close' is emitted once existing connections have ended.
+     * @param {Function} [callback] - Called on 'close', or with an error if the server was not listening
+     * @returns {Server} this
+     */
+    close(callback) {
+      if (this.#id === null) {
+        if (callback) {
+          const err = new Error('Server is not running.');
+          err.code = 'ERR_SERVER_NOT_RUNNING';
+          queueMicrotask(() => callback(err));
+        }
+        return this;
+      }
+      if (callback) {
+        this.once('close', callback);
+      }
+      netServerClose(this.#id);
+      this.#id = null;
+      this.#closing = true;
+      queueMicrotask(() => this.#maybeEmitClose());
+      return this;
+    }
+
+    /**
+     * Returns the bound address, or null if not listening
+     * @returns {{ address: string, family: string, port: number } | null}
+     */
+    address() {
+      return this.#id === null ? null : { ...this.#address };
+    }
+
+    /**
+     * Get the number of open connections
+     * @param {Function} callback - Called with (error, count)
+     * @returns {Server} this
+     */
+    getConnections(callback) {
+      const count = this.#connections.size;
+      queueMicrotask(() => callback(null, count));
+      return this;
+    }
+
+    get listening() {
+      return this.#id !== null;
+    }
+
+    /**
+     * Reference the server (keeps event loop alive)
+     * @returns {Server} this
+     */
+    ref() {
+      this.#isRef = true;
+      if (this.#id !== null) {
+        netRef(this.#id, true);
+      }
+      return this;
+    }
+
+    /**
+     * Unreference the server (allows event loop to exit)
+     * @returns {Server} this
+     */
+    unref() {
+      this.#isRef = false;
+      if (this.#id !== null) {
+        netRef(this.#id, false);
+      }
+      return this;
+    }
+  }
+
+  /**
+   * Creates a TCP server
+   * @param {Object} [options] - { allowHalfOpen }
+   * @param {Function} [connectionListener] - Listener for 'connection' events
+   * @returns {Server}
+   */
+  function createServer(options, connectionListener) {
+    return new Server(options, connectionListener);
+  }
+
+  /**
+   * Creates a socket and connects it
+   * connect(port[, host][, listener]) or connect(options[, listener])
+   * @returns {Socket}
+   */
+  function connect(...args) {
+    const [options] = normalizeConnectArgs(args);
+    const socket = new Socket(options);
+    return socket.connect(...args);
+  }
+
+  const IPV4_PATTERN = /^(25[0-5]|2[0-4]\d|1\d\d|[1-9]?\d)(\.(25[0-5]|2[0-4]\d|1\d\d|[1-9]?\d)){3}$/;
+
+  function isIPv4(input) {
+    return IPV4_PATTERN.test(String(input));
+  }
+
+  function isIPv6(input) {
+    const value = String(input);
+    if (!value.includes(':')) {
+      return false;
+    }
+    try {
+      // The URL parser validates IPv6 hosts
+      new URL(`http://[${value.split('%')[0]}]/`);
+      return true;
+    } catch {
+      return false;
+    }
+  }
+
+  function isIP(input) {
+    if (isIPv4(input)) {
+      return 4;
+    }
+    if (isIPv6(input)) {
+      return 6;
+    }
+    return 0;
+  }
+
+  // Export the net module
+  const net = {
+    createServer,
+    connect,
+    createConnection: connect,
+    Socket,
+    Server,
+    isIP,
+    isIPv4,
+    isIPv6,
+  };
+
+  // Make it available via import
+  globalThis.__node_modules = globalThis.__node_modules || {};
+  globalThis.__node_modules['node:net'] = net;
+  globalThis.__node_modules['net'] = net;
+});
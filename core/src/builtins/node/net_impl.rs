@@ -0,0 +1,561 @@
+// Node.js net API
+// TCP sockets and servers live in crate::tcp and are polled by the event loop.
+// Sockets and servers share one ID space.
+
+use crate::tcp::{ActiveTcpServer, ActiveTcpSocket};
+
+pub(crate) fn get_external_references() -> Vec<v8::ExternalReference> {
+    vec![
+        v8::ExternalReference {
+            function: v8::MapFnTo::map_fn_to(net_connect),
+        },
+        v8::ExternalReference {
+            function: v8::MapFnTo::map_fn_to(net_listen),
+        },
+        v8::ExternalReference {
+            function: v8::MapFnTo::map_fn_to(net_server_close),
+        },
+        v8::ExternalReference {
+            function: v8::MapFnTo::map_fn_to(net_attach),
+        },
+        v8::ExternalReference {
+            function: v8::MapFnTo::map_fn_to(net_socket_info),
+        },
+        v8::ExternalReference {
+            function: v8::MapFnTo::map_fn_to(net_write),
+        },
+        v8::ExternalReference {
+            function: v8::MapFnTo::map_fn_to(net_end),
+        },
+        v8::ExternalReference {
+            function: v8::MapFnTo::map_fn_to(net_destroy),
+        },
+        v8::ExternalReference {
+            function: v8::MapFnTo::map_fn_to(net_set_no_delay),
+        },
+        v8::ExternalReference {
+            function: v8::MapFnTo::map_fn_to(net_set_keep_alive),
+        },
+        v8::ExternalReference {
+            function: v8::MapFnTo::map_fn_to(net_set_paused),
+        },
+        v8::ExternalReference {
+            function: v8::MapFnTo::map_fn_to(net_ref),
+        },
+    ]
+}
+
+pub(crate) fn register_bindings(scope: &mut v8::PinScope, bindings: v8::Local<v8::Object>) {
+    let name = v8::String::new(scope, "netConnect").unwrap();
+    let value = v8::Function::new(scope, net_connect).unwrap();
+    bindings.set(scope, name.into(), value.into());
+
+    let name = v8::String::new(scope, "netListen").unwrap();
+    let value = v8::Function::new(scope, net_listen).unwrap();
+    bindings.set(scope, name.into(), value.into());
+
+    let name = v8::String::new(scope, "netServerClose").unwrap();
+    let value = v8::Function::new(scope, net_server_close).unwrap();
+    bindings.set(scope, name.into(), value.into());
+
+    let name = v8::String::new(scope, "netAttach").unwrap();
+    let value = v8::Function::new(scope, net_attach).unwrap();
+    bindings.set(scope, name.into(), value.into());
+
+    let name = v8::String::new(scope, "netSocketInfo").unwrap();
+    let value = v8::Function::new(scope, net_socket_info).unwrap();
+    bindings.set(scope, name.into(), value.into());
+
+    let name = v8::String::new(scope, "netWrite").unwrap();
+    let value = v8::Function::new(scope, net_write).unwrap();
+    bindings.set(scope, name.into(), value.into());
+
+    let name = v8::String::new(scope, "netEnd").unwrap();
+    let value = v8::Function::new(scope, net_end).unwrap();
+    bindings.set(scope, name.into(), value.into());
+
+    let name = v8::String::new(scope, "netDestroy").unwrap();
+    let value = v8::Function::new(scope, net_destroy).unwrap();
+    bindings.set(scope, name.into(), value.into());
+
+    let name = v8::String::new(scope, "netSetNoDelay").unwrap();
+    let value = v8::Function::new(scope, net_set_no_delay).unwrap();
+    bindings.set(scope, name.into(), value.into());
+
+    let name = v8::String::new(scope, "netSetKeepAlive").unwrap();
+    let value = v8::Function::new(scope, net_set_keep_alive).unwrap();
+    bindings.set(scope, name.into(), value.into());
+
+    let name = v8::String::new(scope, "netSetPaused").unwrap();
+    let value = v8::Function::new(scope, net_set_paused).unwrap();
+    bindings.set(scope, name.into(), value.into());
+
+    let name = v8::String::new(scope, "netRef").unwrap();
+    let value = v8::Function::new(scope, net_ref).unwrap();
+    bindings.set(scope, name.into(), value.into());
+}
+
+/// Read a numeric ID argument, throwing if it is not a number
+#[inline]
+pub(super) fn get_id(scope: &mut v8::PinScope, value: v8::Local<v8::Value>) -> Option<u64> {
+    if value.is_number() {
+        Some(value.number_value(scope).unwrap() as u64)
+    } else {
+        crate::error::throw_type_error(scope, "Socket ID must be a number");
+        None
+    }
+}
+
+/// Read a port argument, throwing a RangeError if it is out of range
+#[inline]
+pub(super) fn get_port(scope: &mut v8::PinScope, value: v8::Local<v8::Value>) -> Option<u16> {
+    let port = value.number_value(scope).unwrap_or(f64::NAN);
+    if port.fract() != 0.0 || !(0.0..=65535.0).contains(&port) {
+        crate::error::throw_range_error(
+            scope,
+            &format!("Port should be >= 0 and < 65536. Received {}.", port),
+        );
+        return None;
+    }
+    Some(port as u16)
+}
+
+/// Allocate the next socket or server ID
+#[inline]
+pub(super) fn next_id(state: &std::rc::Rc<std::cell::RefCell<crate::IsolateState>>) -> u64 {
+    let next_tcp_id = state.borrow().next_tcp_id.clone();
+    let mut next_id = next_tcp_id.borrow_mut();
+    let id = *next_id;
+    *next_id += 1;
+    id
+}
+
+/// Set { <prefix>Address, <prefix>Family, <prefix>Port } on an object
+fn set_address(
+    scope: &mut v8::PinScope,
+    object: v8::Local<v8::Object>,
+    prefix: &str,
+    addr: std::net::SocketAddr,
+) {
+    let key = v8::String::new(scope, &format!("{}Address", prefix)).unwrap();
+    let value = v8::String::new(scope, &addr.ip().to_string()).unwrap();
+    object.set(scope, key.into(), value.into());
+
+    let key = v8::String::new(scope, &format!("{}Family", prefix)).unwrap();
+    let family = if addr.is_ipv4() { "IPv4" } else { "IPv6" };
+    let value = v8::String::new(scope, family).unwrap();
+    object.set(scope, key.into(), value.into());
+
+    let key = v8::String::new(scope, &format!("{}Port", prefix)).unwrap();
+    let value = v8::Number::new(scope, addr.port() as f64);
+    object.set(scope, key.into(), value.into());
+}
+
+/// Run `f` on a registered socket, throwing if it has been closed
+fn with_socket<R>(
+    scope: &mut v8::PinScope,
+    socket_id: u64,
+    f: impl FnOnce(&mut ActiveTcpSocket) -> R,
+) -> Option<R> {
+    let isolate: &mut v8::Isolate = scope;
+    let state = crate::IsolateState::get(isolate);
+    let state = state.borrow();
+    let mut net = state.active_net.borrow_mut();
+    match net.tcp_sockets.get_mut(&socket_id) {
+        Some(socket) => Some(f(socket)),
+        None => {
+            drop(net);
+            drop(state);
+            crate::error::throw_error(scope, "Socket is closed");
+            None
+        }
+    }
+}
+
+/// Start connecting to a TCP server
+/// Args: host, port, callback (called with (event, data) by the event loop)
+/// Returns: socket ID
+#[inline]
+fn net_connect(
+    scope: &mut v8::PinScope,
+    args: v8::FunctionCallbackArguments,
+    mut retval: v8::ReturnValue,
+) {
+    if !crate::error::check_arg_count(scope, &args, 3, "netConnect") {
+        return;
+    }
+
+    let Some(host) = crate::error::to_rust_string_or_throw(scope, args.get(0), "host") else {
+        return;
+    };
+    let Some(port) = get_port(scope, args.get(1)) else {
+        return;
+    };
+    let callback = match crate::error::try_get_function_result(args.get(2)) {
+        Ok(callback) => v8::Global::new(scope, callback),
+        Err(msg) => {
+            crate::error::throw_type_error(scope, msg);
+            return;
+        }
+    };
+
+    let isolate: &mut v8::Isolate = scope;
+    let state = crate::IsolateState::get(isolate);
+    let socket_id = next_id(&state);
+    let mut socket = ActiveTcpSocket::connect(host, port);
+    socket.callback = Some(callback);
+    state
+        .borrow()
+        .active_net
+        .borrow_mut()
+        .tcp_sockets
+        .insert(socket_id, socket);
+
+    let result = v8::Number::new(scope, socket_id as f64);
+    retval.set(result.into());
+}
+
+/// Start a TCP server
+/// Args: host, port, callback (called with each accepted socket ID by the event loop)
+/// Returns: { id, address, family, port }
+#[inline]
+fn net_listen(
+    scope: &mut v8::PinScope,
+    args: v8::FunctionCallbackArguments,
+    mut retval: v8::ReturnValue,
+) {
+    if !crate::error::check_arg_count(scope, &args, 3, "netListen") {
+        return;
+    }
+
+    let Some(host) = crate::error::to_rust_string_or_throw(scope, args.get(0), "host") else {
+        return;
+    };
+    let Some(port) = get_port(scope, args.get(1)) else {
+        return;
+    };
+    let callback = match crate::error::try_get_function_result(args.get(2)) {
+        Ok(callback) => v8::Global::new(scope, callback),
+        Err(msg) => {
+            crate::error::throw_type_error(scope, msg);
+            return;
+        }
+    };
+
+    // IPv6 addresses need brackets to be combined with a port
+    let bind_addr = if host.contains(':') && !host.starts_with('[') {
+        format!("[{}]:{}", host, port)
+    } else {
+        format!("{}:{}", host, port)
+    };
+    let server = match ActiveTcpServer::bind(&bind_addr, callback) {
+        Ok(server) => server,
+        Err(e) => {
+            crate::error::throw_io_error(scope, &e);
+            return;
+        }
+    };
+    let local_addr = match server.local_addr() {
+        Ok(addr) => addr,
+        Err(e) => {
+            crate::error::throw_io_error(scope, &e);
+            return;
+        }
+    };
+
+    let isolate: &mut v8::Isolate = scope;
+    let state = crate::IsolateState::get(isolate);
+    let server_id = next_id(&state);
+    state
+        .borrow()
+        .active_net
+        .borrow_mut()
+        .tcp_servers
+        .insert(server_id, server);
+
+    let result = v8::Object::new(scope);
+    let key = v8::String::new(scope, "id").unwrap();
+    let value = v8::Number::new(scope, server_id as f64);
+    result.set(scope, key.into(), value.into());
+    let key = v8::String::new(scope, "address").unwrap();
+    let value = v8::String::new(scope, &local_addr.ip().to_string()).unwrap();
+    result.set(scope, key.into(), value.into());
+    let key = v8::String::new(scope, "family").unwrap();
+    let value = v8::String::new(scope, if local_addr.is_ipv4() { "IPv4" } else { "IPv6" }).unwrap();
+    result.set(scope, key.into(), value.into());
+    let key = v8::String::new(scope, "port").unwrap();
+    let value = v8::Number::new(scope, local_addr.port() as f64);
+    result.set(scope, key.into(), value.into());
+    retval.set(result.into());
+}
+
+/// Stop a TCP server from accepting connections. Accepted sockets stay open.
+/// Args: serverId
+#[inline]
+fn net_server_close(
+    scope: &mut v8::PinScope,
+    args: v8::FunctionCallbackArguments,
+    _retval: v8::ReturnValue,
+) {
+    if !crate::error::check_arg_count(scope, &args, 1, "netServerClose") {
+        return;
+    }
+
+    let Some(server_id) = get_id(scope, args.get(0)) else {
+        return;
+    };
+
+    let isolate: &mut v8::Isolate = scope;
+    let state = crate::IsolateState::get(isolate);
+    let server = state
+        .borrow()
+        .active_net
+        .borrow_mut()
+        .tcp_servers
+        .remove(&server_id);
+    drop(server);
+}
+
+/// Start delivering events for an accepted socket
+/// Args: socketId, callback (called with (event, data) by the event loop)
+#[inline]
+fn net_attach(
+    scope: &mut v8::PinScope,
+    args: v8::FunctionCallbackArguments,
+    _retval: v8::ReturnValue,
+) {
+    if !crate::error::check_arg_count(scope, &args, 2, "netAttach") {
+        return;
+    }
+
+    let Some(socket_id) = get_id(scope, args.get(0)) else {
+        return;
+    };
+    let callback = match crate::error::try_get_function_result(args.get(1)) {
+        Ok(callback) => v8::Global::new(scope, callback),
+        Err(msg) => {
+            crate::error::throw_type_error(scope, msg);
+            return;
+        }
+    };
+
+    with_socket(scope, socket_id, |socket| socket.callback = Some(callback));
+}
+
+/// Get the local and remote addresses of a connected socket
+/// Args: socketId
+/// Returns: { localAddress, localFamily, localPort, remoteAddress, remoteFamily, remotePort },
+/// or undefined if the socket isn't connected
+#[inline]
+fn net_socket_info(
+    scope: &mut v8::PinScope,
+    args: v8::FunctionCallbackArguments,
+    mut retval: v8::ReturnValue,
+) {
+    if !crate::error::check_arg_count(scope, &args, 1, "netSocketInfo") {
+        return;
+    }
+
+    let Some(socket_id) = get_id(scope, args.get(0)) else {
+        return;
+    };
+
+    let addrs = {
+        let isolate: &mut v8::Isolate = scope;
+        let state = crate::IsolateState::get(isolate);
+        let state = state.borrow();
+        let net = state.active_net.borrow();
+        net.tcp_sockets
+            .get(&socket_id)
+            .and_then(|socket| socket.stream())
+            .and_then(|stream| Some((stream.local_addr().ok()?, stream.peer_addr().ok()?)))
+    };
+    let Some((local_addr, peer_addr)) = addrs else {
+        return;
+    };
+
+    let result = v8::Object::new(scope);
+    set_address(scope, result, "local", local_addr);
+    set_address(scope, result, "remote", peer_addr);
+    retval.set(result.into());
+}
+
+/// Write data to a socket, queueing what can't be written immediately
+/// Args: socketId, data (BufferSource)
+/// Returns: true if all data was flushed, false if the caller should wait for 'drain'
+#[inline]
+fn net_write(
+    scope: &mut v8::PinScope,
+    args: v8::FunctionCallbackArguments,
+    mut retval: v8::ReturnValue,
+) {
+    if !crate::error::check_arg_count(scope, &args, 2, "netWrite") {
+        return;
+    }
+
+    let Some(socket_id) = get_id(scope, args.get(0)) else {
+        return;
+    };
+    let data = match crate::error::try_get_buffer_source_bytes(args.get(1)) {
+        Ok(bytes) => bytes,
+        Err(msg) => {
+            crate::error::throw_type_error(scope, msg);
+            return;
+        }
+    };
+
+    match with_socket(scope, socket_id, |socket| socket.write(&data)) {
+        Some(Ok(flushed)) => retval.set_bool(flushed),
+        Some(Err(e)) => crate::error::throw_io_error(scope, &e),
+        None => {}
+    }
+}
+
+/// Shut down the write side of a socket once queued data is written
+/// Args: socketId
+#[inline]
+fn net_end(
+    scope: &mut v8::PinScope,
+    args: v8::FunctionCallbackArguments,
+    _retval: v8::ReturnValue,
+) {
+    if !crate::error::check_arg_count(scope, &args, 1, "netEnd") {
+        return;
+    }
+
+    let Some(socket_id) = get_id(scope, args.get(0)) else {
+        return;
+    };
+
+    with_socket(scope, socket_id, |socket| socket.end());
+}
+
+/// Close a socket immediately, discarding queued writes
+/// Args: socketId
+#[inline]
+fn net_destroy(
+    scope: &mut v8::PinScope,
+    args: v8::FunctionCallbackArguments,
+    _retval: v8::ReturnValue,
+) {
+    if !crate::error::check_arg_count(scope, &args, 1, "netDestroy") {
+        return;
+    }
+
+    let Some(socket_id) = get_id(scope, args.get(0)) else {
+        return;
+    };
+
+    // Dropping the stream closes the connection
+    let isolate: &mut v8::Isolate = scope;
+    let state = crate::IsolateState::get(isolate);
+    let socket = state
+        .borrow()
+        .active_net
+        .borrow_mut()
+        .tcp_sockets
+        .remove(&socket_id);
+    drop(socket);
+}
+
+/// Enable or disable Nagle's algorithm on a connected socket
+/// Args: socketId, noDelay (boolean)
+#[inline]
+fn net_set_no_delay(
+    scope: &mut v8::PinScope,
+    args: v8::FunctionCallbackArguments,
+    _retval: v8::ReturnValue,
+) {
+    if !crate::error::check_arg_count(scope, &args, 2, "netSetNoDelay") {
+        return;
+    }
+
+    let Some(socket_id) = get_id(scope, args.get(0)) else {
+        return;
+    };
+    let no_delay = args.get(1).boolean_value(scope);
+
+    let result = with_socket(scope, socket_id, |socket| match socket.stream() {
+        Some(stream) => stream.set_nodelay(no_delay),
+        None => Ok(()),
+    });
+    if let Some(Err(e)) = result {
+        crate::error::throw_io_error(scope, &e);
+    }
+}
+
+/// Enable or disable TCP keep-alive on a connected socket
+/// Args: socketId, enable (boolean), initialDelay (milliseconds)
+#[inline]
+fn net_set_keep_alive(
+    scope: &mut v8::PinScope,
+    args: v8::FunctionCallbackArguments,
+    _retval: v8::ReturnValue,
+) {
+    if !crate::error::check_arg_count(scope, &args, 3, "netSetKeepAlive") {
+        return;
+    }
+
+    let Some(socket_id) = get_id(scope, args.get(0)) else {
+        return;
+    };
+    let enable = args.get(1).boolean_value(scope);
+    let initial_delay_ms = args.get(2).number_value(scope).unwrap_or(0.0).max(0.0);
+    let initial_delay_secs = (initial_delay_ms / 1000.0) as u32;
+
+    let result = with_socket(scope, socket_id, |socket| match socket.stream() {
+        Some(stream) => crate::tcp::set_keep_alive(stream, enable, initial_delay_secs),
+        None => Ok(()),
+    });
+    if let Some(Err(e)) = result {
+        crate::error::throw_io_error(scope, &e);
+    }
+}
+
+/// Stop or resume reading from a socket
+/// Args: socketId, paused (boolean)
+#[inline]
+fn net_set_paused(
+    scope: &mut v8::PinScope,
+    args: v8::FunctionCallbackArguments,
+    _retval: v8::ReturnValue,
+) {
+    if !crate::error::check_arg_count(scope, &args, 2, "netSetPaused") {
+        return;
+    }
+
+    let Some(socket_id) = get_id(scope, args.get(0)) else {
+        return;
+    };
+    let paused = args.get(1).boolean_value(scope);
+
+    with_socket(scope, socket_id, |socket| socket.paused = paused);
+}
+
+/// Set whether a socket or server keeps the event loop alive
+/// Args: id, ref (boolean)
+#[inline]
+fn net_ref(
+    scope: &mut v8::PinScope,
+    args: v8::FunctionCallbackArguments,
+    _retval: v8::ReturnValue,
+) {
+    if !crate::error::check_arg_count(scope, &args, 2, "netRef") {
+        return;
+    }
+
+    let Some(id) = get_id(scope, args.get(0)) else {
+        return;
+    };
+    let is_ref = args.get(1).boolean_value(scope);
+
+    let isolate: &mut v8::Isolate = scope;
+    let state = crate::IsolateState::get(isolate);
+    let state = state.borrow();
+    let mut net = state.active_net.borrow_mut();
+    if let Some(socket) = net.tcp_sockets.get_mut(&id) {
+        socket.is_ref = is_ref;
+    } else if let Some(server) = net.tcp_servers.get_mut(&id) {
+        server.is_ref = is_ref;
+    }
+}
@@ -160,6 +160,43 @@
     },
   };
 
+  // process is an EventEmitter, for events such as uncaughtException and
+  // unhandledRejection
+  const { EventEmitter } = globalThis.__node_modules['node:events'];
+  Object.setPrototypeOf(process, EventEmitter.prototype);
+  process.__listeners__ = new Map();
+  process._maxListeners = undefined;
+
+  // Called by the runtime with the promise rejections left unhandled after
+  // a microtask checkpoint, and the ones handled since. Returns whether a
+  // listener handled an unhandled rejection.
+  function dispatchRejection(kind, promise, reason) {
+    if (kind === 'unhandled') {
+      const event = new PromiseRejectionEvent('unhandledrejection', { promise, reason, cancelable: true });
+      event.__isTrusted__ = true;
+      const notCanceled = globalThis.dispatchEvent(event);
+      const emitted = process.emit('unhandledRejection', reason, promise);
+      return emitted || !notCanceled;
+    }
+    const event = new PromiseRejectionEvent('rejectionhandled', { promise, reason });
+    event.__isTrusted__ = true;
+    globalThis.dispatchEvent(event);
+    process.emit('rejectionHandled', promise);
+    return true;
+  }
+  Object.defineProperty(globalThis, '__dispatchRejection__', { value: dispatchRejection });
+
+  // Called by the runtime with an exception a callback threw, and by
+  // reportError(). Returns whether a listener handled it.
+  function dispatchError(error, message, filename, lineno, colno) {
+    const event = new ErrorEvent('error', { error, message, filename, lineno, colno, cancelable: true });
+    event.__isTrusted__ = true;
+    const notCanceled = globalThis.dispatchEvent(event);
+    const emitted = process.emit('uncaughtException', error, 'uncaughtException');
+    return emitted || !notCanceled;
+  }
+  Object.defineProperty(globalThis, '__dispatchError__', { value: dispatchError });
+
   // Make process available globally
   globalThis.process = process;
 });
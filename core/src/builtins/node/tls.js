@@ -0,0 +1,144 @@
+// Node.js tls API
+// TLS client sockets compatible with Node.js
+// https://nodejs.org/api/tls.html
+
+'use strict';
+
+// eslint-disable-next-line no-unused-expressions
+(({ tlsConnect, tlsInfo }) => {
+  const net = globalThis.__node_modules['node:net'];
+
+  // Normalize a string, Buffer or array of them into an array of strings
+  function toStringList(value, name) {
+    if (value === undefined || value === null) {
+      return [];
+    }
+    const list = Array.isArray(value) ? value : [value];
+    return list.map((item) => {
+      if (typeof item === 'string') {
+        return item;
+      }
+      if (ArrayBuffer.isView(item)) {
+        return new TextDecoder().decode(item);
+      }
+      throw new TypeError(`The "${name}" option must be a string, Buffer or an array of them`);
+    });
+  }
+
+  /**
+   * TLSSocket class - an encrypted TCP connection
+   * Emits 'secureConnect' once the handshake completes, in addition to the net.Socket events
+   */
+  class TLSSocket extends net.Socket {
+    #info = null;
+
+    constructor(socket, options = {}) {
+      if (socket) {
+        throw new Error('Wrapping an existing socket in a TLSSocket is not supported');
+      }
+      super(options);
+      this.encrypted = true;
+      this.authorized = false;
+    }
+
+    // Internal: open a TLS connection instead of a plain TCP one
+    _connectHandle(host, port, options, handler) {
+      if (options.rejectUnauthorized === false) {
+        throw new Error('tls: rejectUnauthorized: false is not supported');
+      }
+      return tlsConnect(host, port, {
+        // IP addresses aren't sent with SNI, but are still checked against the certificate
+        servername: options.servername,
+        ca: toStringList(options.ca, 'ca'),
+        alpnProtocols: toStringList(options.ALPNProtocols, 'ALPNProtocols'),
+      }, handler);
+    }
+
+    // Internal: handle the TLS handshake event before the net.Socket events
+    _onEvent(event, data) {
+      if (event === 'secureConnect') {
+        if (this.destroyed) {
+          return;
+        }
+        this.#info = tlsInfo(this._handle) || null;
+        this.authorized = true;
+        this.emit('secureConnect');
+        return;
+      }
+      super._onEvent(event, data);
+    }
+
+    /** The protocol selected with ALPN, false if none was, or null before the handshake */
+    get alpnProtocol() {
+      return this.#info ? this.#info.alpnProtocol : null;
+    }
+
+    /** The server name the certificate was verified against */
+    get servername() {
+      return this.#info ? this.#info.servername : null;
+    }
+
+    /**
+     * Returns the negotiated TLS version, e.g. 'TLSv1.3', or null before the handshake
+     * @returns {string|null}
+     */
+    getProtocol() {
+      return this.#info ? this.#info.protocol : null;
+    }
+
+    /**
+     * Returns the negotiated cipher suite
+     * @returns {{ name: string, standardName: string, version: string } | null}
+     */
+    getCipher() {
+      if (!this.#info || !this.#info.cipher) {
+        return null;
+      }
+      return { name: this.#info.cipher, standardName: this.#info.cipher, version: this.#info.protocol };
+    }
+  }
+
+  /**
+   * Creates a TLS socket and connects it
+   * connect(options[, callback]) or connect(port[, host][, options][, callback])
+   * options: { host, port, servername, ca, ALPNProtocols }
+   * @param {Function} [callback] - Listener for 'secureConnect'
+   * @returns {TLSSocket}
+   */
+  function connect(...args) {
+    const callback = typeof args[args.length - 1] === 'function' ? args.pop() : undefined;
+    let options;
+    if (args[0] !== null && typeof args[0] === 'object') {
+      options = { ...args[0] };
+    } else {
+      options = { port: args[0] };
+      let next = 1;
+      if (typeof args[1] === 'string') {
+        options.host = args[1];
+        next = 2;
+      }
+      if (args[next] !== null && typeof args[next] === 'object') {
+        options = { ...args[next], ...options };
+      }
+    }
+
+    const socket = new TLSSocket(null, options);
+    if (callback) {
+      socket.once('secureConnect', callback);
+    }
+    return socket.connect(options);
+  }
+
+  // Export the tls module
+  const tls = {
+    connect,
+    TLSSocket,
+    DEFAULT_MIN_VERSION: 'TLSv1.2',
+    DEFAULT_MAX_VERSION: 'TLSv1.3',
+  };
+
+  // Make it available via import
+  globalThis.__node_modules = globalThis.__node_modules || {};
+  globalThis.__node_modules['node:tls'] = tls;
+  globalThis.__node_modules['tls'] = tls;
+});
@@ -0,0 +1,201 @@
+// Node.js tls API
+// TLS client sockets are TCP sockets from crate::tcp with a crate::tls session attached,
+// so everything except connecting and the TLS details goes through the net bindings.
+
+use super::net_impl::{get_id, get_port, next_id};
+use crate::tcp::ActiveTcpSocket;
+use crate::tls::{TlsOptions, TlsSession};
+
+pub(crate) fn get_external_references() -> Vec<v8::ExternalReference> {
+    vec![
+        v8::ExternalReference {
+            function: v8::MapFnTo::map_fn_to(tls_connect),
+        },
+        v8::ExternalReference {
+            function: v8::MapFnTo::map_fn_to(tls_info),
+        },
+    ]
+}
+
+pub(crate) fn register_bindings(scope: &mut v8::PinScope, bindings: v8::Local<v8::Object>) {
+    let name = v8::String::new(scope, "tlsConnect").unwrap();
+    let value = v8::Function::new(scope, tls_connect).unwrap();
+    bindings.set(scope, name.into(), value.into());
+
+    let name = v8::String::new(scope, "tlsInfo").unwrap();
+    let value = v8::Function::new(scope, tls_info).unwrap();
+    bindings.set(scope, name.into(), value.into());
+}
+
+/// Read an optional array of strings from an options object
+fn get_string_array(
+    scope: &mut v8::PinScope,
+    options: v8::Local<v8::Object>,
+    key: &str,
+) -> Option<Vec<String>> {
+    let key_str = v8::String::new(scope, key).unwrap();
+    let value = options.get(scope, key_str.into())?;
+    if value.is_null_or_undefined() {
+        return Some(Vec::new());
+    }
+    let array = match crate::error::try_get_array_result(value) {
+        Ok(array) => array,
+        Err(_) => {
+            crate::error::throw_type_error(scope, &format!("{} must be an array", key));
+            return None;
+        }
+    };
+    let mut strings = Vec::with_capacity(array.length() as usize);
+    for i in 0..array.length() {
+        let item = array.get_index(scope, i)?;
+        strings.push(crate::error::to_rust_string_or_throw(scope, item, key)?);
+    }
+    Some(strings)
+}
+
+/// Start connecting to a TLS server
+/// Args: host, port, options ({ servername, ca, alpnProtocols }), callback (called with (event, data))
+/// Returns: socket ID
+#[inline]
+fn tls_connect(
+    scope: &mut v8::PinScope,
+    args: v8::FunctionCallbackArguments,
+    mut retval: v8::ReturnValue,
+) {
+    if !crate::error::check_arg_count(scope, &args, 4, "tlsConnect") {
+        return;
+    }
+
+    let Some(host) = crate::error::to_rust_string_or_throw(scope, args.get(0), "host") else {
+        return;
+    };
+    let Some(port) = get_port(scope, args.get(1)) else {
+        return;
+    };
+    let Ok(options) = v8::Local::<v8::Object>::try_from(args.get(2)) else {
+        crate::error::throw_type_error(scope, "options must be an object");
+        return;
+    };
+    let callback = match crate::error::try_get_function_result(args.get(3)) {
+        Ok(callback) => v8::Global::new(scope, callback),
+        Err(msg) => {
+            crate::error::throw_type_error(scope, msg);
+            return;
+        }
+    };
+
+    let key = v8::String::new(scope, "servername").unwrap();
+    let server_name = match options.get(scope, key.into()) {
+        Some(value) if !value.is_null_or_undefined() => {
+            let Some(name) = crate::error::to_rust_string_or_throw(scope, value, "servername")
+            else {
+                return;
+            };
+            Some(name)
+        }
+        _ => None,
+    };
+    let Some(ca_certs) = get_string_array(scope, options, "ca") else {
+        return;
+    };
+    let Some(alpn_protocols) = get_string_array(scope, options, "alpnProtocols") else {
+        return;
+    };
+
+    let tls_options = TlsOptions {
+        server_name,
+        ca_certs,
+        alpn_protocols,
+    };
+    let session = match TlsSession::new(&host, tls_options) {
+        Ok(session) => session,
+        Err(e) => {
+            crate::error::throw_io_error(scope, &e);
+            return;
+        }
+    };
+
+    let isolate: &mut v8::Isolate = scope;
+    let state = crate::IsolateState::get(isolate);
+    let socket_id = next_id(&state);
+    let mut socket = ActiveTcpSocket::connect(host, port).with_tls(session);
+    socket.callback = Some(callback);
+    state
+        .borrow()
+        .active_net
+        .borrow_mut()
+        .tcp_sockets
+        .insert(socket_id, socket);
+
+    let result = v8::Number::new(scope, socket_id as f64);
+    retval.set(result.into());
+}
+
+/// Get details of the TLS session of a socket
+/// Args: socketId
+/// Returns: { servername, protocol, cipher, alpnProtocol }, or undefined if the
+/// socket is closed or not a TLS socket
+#[inline]
+fn tls_info(
+    scope: &mut v8::PinScope,
+    args: v8::FunctionCallbackArguments,
+    mut retval: v8::ReturnValue,
+) {
+    if !crate::error::check_arg_count(scope, &args, 1, "tlsInfo") {
+        return;
+    }
+
+    let Some(socket_id) = get_id(scope, args.get(0)) else {
+        return;
+    };
+
+    let info = {
+        let isolate: &mut v8::Isolate = scope;
+        let state = crate::IsolateState::get(isolate);
+        let state = state.borrow();
+        let net = state.active_net.borrow();
+        net.tcp_sockets
+            .get(&socket_id)
+            .and_then(|socket| socket.tls())
+            .map(|tls| {
+                (
+                    tls.server_name().to_string(),
+                    tls.protocol(),
+                    tls.cipher(),
+                    tls.alpn_protocol(),
+                )
+            })
+    };
+    let Some((server_name, protocol, cipher, alpn_protocol)) = info else {
+        return;
+    };
+
+    let result = v8::Object::new(scope);
+    let key = v8::String::new(scope, "servername").unwrap();
+    let value = v8::String::new(scope, &server_name).unwrap();
+    result.set(scope, key.into(), value.into());
+
+    let key = v8::String::new(scope, "protocol").unwrap();
+    let value: v8::Local<v8::Value> = match protocol {
+        Some(protocol) => v8::String::new(scope, protocol).unwrap().into(),
+        None => v8::null(scope).into(),
+    };
+    result.set(scope, key.into(), value);
+
+    let key = v8::String::new(scope, "cipher").unwrap();
+    let value: v8::Local<v8::Value> = match cipher {
+        Some(cipher) => v8::String::new(scope, cipher).unwrap().into(),
+        None => v8::null(scope).into(),
+    };
+    result.set(scope, key.into(), value);
+
+    // Node.js reports false when no protocol was negotiated
+    let key = v8::String::new(scope, "alpnProtocol").unwrap();
+    let value: v8::Local<v8::Value> = match alpn_protocol {
+        Some(protocol) => v8::String::new(scope, &protocol).unwrap().into(),
+        None => v8::Boolean::new(scope, false).into(),
+    };
+    result.set(scope, key.into(), value);
+
+    retval.set(result.into());
+}
@@ -0,0 +1,167 @@
+// Blob and File
+// https://w3c.github.io/FileAPI/
+// Bytes are stored natively; Blob objects only hold a reference to them
+
+'use strict';
+
+// eslint-disable-next-line no-unused-expressions
+(({ blobCreate, blobSlice, blobSetFile, blobGetProperty, blobText, blobBytes, blobSetFactory }) => {
+  // Size of the chunks produced by Blob.prototype.stream()
+  const STREAM_CHUNK_SIZE = 65536;
+
+  // Lowercase the type, or drop it if it has characters outside U+0020..U+007E
+  function normalizeType(type) {
+    const value = String(type);
+    for (let i = 0; i < value.length; i++) {
+      const code = value.charCodeAt(i);
+      if (code < 0x20 || code > 0x7e) {
+        return '';
+      }
+    }
+    return value.toLowerCase();
+  }
+
+  // Convert blobParts into an array of Blobs, BufferSources and strings
+  function convertParts(blobParts, endings) {
+    if (blobParts === undefined) {
+      return [];
+    }
+    if (blobParts === null || (typeof blobParts !== 'object' && typeof blobParts !== 'function') ||
+        typeof blobParts[Symbol.iterator] !== 'function') {
+      throw new TypeError("Failed to construct 'Blob': The provided value cannot be converted to a sequence.");
+    }
+    const parts = [];
+    for (const part of blobParts) {
+      if (part instanceof Blob || part instanceof ArrayBuffer || ArrayBuffer.isView(part)) {
+        parts.push(part);
+      } else {
+        const string = String(part);
+        parts.push(endings === 'native' ? string.replace(/\r\n|\r|\n/g, '\n') : string);
+      }
+    }
+    return parts;
+  }
+
+  // Resolve a relative slice index against size, like Array.prototype.slice
+  function relativeIndex(value, size, fallback) {
+    if (value === undefined) {
+      return fallback;
+    }
+    const index = Math.trunc(Number(value)) || 0;
+    return index < 0 ? Math.max(size + index, 0) : Math.min(index, size);
+  }
+
+  class Blob {
+    constructor(blobParts = undefined, options = {}) {
+      const opts = options === undefined || options === null ? {} : options;
+      const endings = opts.endings === undefined ? 'transparent' : String(opts.endings);
+      if (endings !== 'transparent' && endings !== 'native') {
+        throw new TypeError(`Failed to construct 'Blob': The provided value '${endings}' is not a valid enum value of type EndingType.`);
+      }
+      const type = opts.type === undefined ? '' : normalizeType(opts.type);
+      blobCreate(this, convertParts(blobParts, endings), type);
+    }
+
+    get size() {
+      return blobGetProperty(this, 'size');
+    }
+
+    get type() {
+      return blobGetProperty(this, 'type');
+    }
+
+    slice(start = undefined, end = undefined, contentType = undefined) {
+      const size = this.size;
+      const relativeStart = relativeIndex(start, size, 0);
+      const relativeEnd = relativeIndex(end, size, size);
+      const span = Math.max(relativeEnd - relativeStart, 0);
+      const type = contentType === undefined ? '' : normalizeType(contentType);
+
+      // Slices share the native bytes of this Blob
+      const blob = Object.create(Blob.prototype);
+      blobSlice(blob, this, relativeStart, span, type);
+      return blob;
+    }
+
+    text() {
+      try {
+        return Promise.resolve(blobText(this));
+      } catch (error) {
+        return Promise.reject(error);
+      }
+    }
+
+    arrayBuffer() {
+      try {
+        return Promise.resolve(blobBytes(this).buffer);
+      } catch (error) {
+        return Promise.reject(error);
+      }
+    }
+
+    bytes() {
+      try {
+        return Promise.resolve(blobBytes(this));
+      } catch (error) {
+        return Promise.reject(error);
+      }
+    }
+
+    stream() {
+      const blob = this;
+      const size = this.size;
+      let offset = 0;
+      return new globalThis.ReadableStream({
+        pull(controller) {
+          if (offset >= size) {
+            controller.close();
+            return;
+          }
+          const end = Math.min(offset + STREAM_CHUNK_SIZE, size);
+          controller.enqueue(blobBytes(blob, offset, end));
+          offset = end;
+        },
+      });
+    }
+
+    get [Symbol.toStringTag]() {
+      return 'Blob';
+    }
+  }
+
+  class File extends Blob {
+    constructor(fileBits, fileName, options = {}) {
+      if (arguments.length < 2) {
+        throw new TypeError(`Failed to construct 'File': 2 arguments required, but only ${arguments.length} present.`);
+      }
+      super(fileBits, options);
+      const opts = options === undefined || options === null ? {} : options;
+      const lastModified = opts.lastModified === undefined
+        ? Date.now()
+        : Math.trunc(Number(opts.lastModified)) || 0;
+      blobSetFile(this, String(fileName), lastModified);
+    }
+
+    get name() {
+      return blobGetProperty(this, 'name');
+    }
+
+    get lastModified() {
+      return blobGetProperty(this, 'lastModified');
+    }
+
+    get webkitRelativePath() {
+      return '';
+    }
+
+    get [Symbol.toStringTag]() {
+      return 'File';
+    }
+  }
+
+  // Used by structuredClone to create Blob and File objects without running constructors
+  blobSetFactory((isFile) => Object.create(isFile ? File.prototype : Blob.prototype));
+
+  globalThis.Blob = Blob;
+  globalThis.File = File;
+});
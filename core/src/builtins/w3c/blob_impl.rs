@@ -0,0 +1,475 @@
+// Blob and File storage
+// Blob bytes live in a Rust-allocated ArrayBuffer backing store that is never
+// exposed to scripts. Each Blob object carries a private record pointing at a
+// range of that buffer, so slice() shares the bytes instead of copying them.
+
+// Private key for the record attached to Blob and File objects:
+// [buffer, offset, size, type] for Blob, plus [name, lastModified] for File
+const BLOB_RECORD_KEY: &str = "jstime.blob.record";
+// Private key on the global object for the function that creates empty Blob/File objects
+const BLOB_FACTORY_KEY: &str = "jstime.blob.factory";
+
+const RECORD_BUFFER: u32 = 0;
+const RECORD_OFFSET: u32 = 1;
+const RECORD_SIZE: u32 = 2;
+const RECORD_TYPE: u32 = 3;
+const RECORD_NAME: u32 = 4;
+const RECORD_LAST_MODIFIED: u32 = 5;
+
+// Serialized kind tags used by structuredClone
+const KIND_BLOB: u32 = 0;
+const KIND_FILE: u32 = 1;
+
+pub(crate) fn get_external_references() -> Vec<v8::ExternalReference> {
+    vec![
+        v8::ExternalReference {
+            function: v8::MapFnTo::map_fn_to(blob_create),
+        },
+        v8::ExternalReference {
+            function: v8::MapFnTo::map_fn_to(blob_slice),
+        },
+        v8::ExternalReference {
+            function: v8::MapFnTo::map_fn_to(blob_set_file),
+        },
+        v8::ExternalReference {
+            function: v8::MapFnTo::map_fn_to(blob_get_property),
+        },
+        v8::ExternalReference {
+            function: v8::MapFnTo::map_fn_to(blob_text),
+        },
+        v8::ExternalReference {
+            function: v8::MapFnTo::map_fn_to(blob_bytes),
+        },
+        v8::ExternalReference {
+            function: v8::MapFnTo::map_fn_to(blob_set_factory),
+        },
+    ]
+}
+
+pub(crate) fn register_bindings(scope: &mut v8::PinScope, bindings: v8::Local<v8::Object>) {
+    let name = v8::String::new(scope, "blobCreate").unwrap();
+    let value = v8::Function::new(scope, blob_create).unwrap();
+    bindings.set(scope, name.into(), value.into());
+
+    let name = v8::String::new(scope, "blobSlice").unwrap();
+    let value = v8::Function::new(scope, blob_slice).unwrap();
+    bindings.set(scope, name.into(), value.into());
+
+    let name = v8::String::new(scope, "blobSetFile").unwrap();
+    let value = v8::Function::new(scope, blob_set_file).unwrap();
+    bindings.set(scope, name.into(), value.into());
+
+    let name = v8::String::new(scope, "blobGetProperty").unwrap();
+    let value = v8::Function::new(scope, blob_get_property).unwrap();
+    bindings.set(scope, name.into(), value.into());
+
+    let name = v8::String::new(scope, "blobText").unwrap();
+    let value = v8::Function::new(scope, blob_text).unwrap();
+    bindings.set(scope, name.into(), value.into());
+
+    let name = v8::String::new(scope, "blobBytes").unwrap();
+    let value = v8::Function::new(scope, blob_bytes).unwrap();
+    bindings.set(scope, name.into(), value.into());
+
+    let name = v8::String::new(scope, "blobSetFactory").unwrap();
+    let value = v8::Function::new(scope, blob_set_factory).unwrap();
+    bindings.set(scope, name.into(), value.into());
+}
+
+#[inline]
+fn private_key<'s>(scope: &mut v8::PinScope<'s, '_>, name: &str) -> v8::Local<'s, v8::Private> {
+    let name = v8::String::new(scope, name).unwrap();
+    v8::Private::for_api(scope, Some(name))
+}
+
+/// Get the private record of a Blob or File, or None if `value` isn't one
+fn get_record<'s>(
+    scope: &mut v8::PinScope<'s, '_>,
+    value: v8::Local<'s, v8::Value>,
+) -> Option<v8::Local<'s, v8::Array>> {
+    let object = v8::Local::<v8::Object>::try_from(value).ok()?;
+    let key = private_key(scope, BLOB_RECORD_KEY);
+    let record = object.get_private(scope, key)?;
+    v8::Local::<v8::Array>::try_from(record).ok()
+}
+
+/// Get the record of `value`, throwing a TypeError if it isn't a Blob
+fn get_record_or_throw<'s>(
+    scope: &mut v8::PinScope<'s, '_>,
+    value: v8::Local<'s, v8::Value>,
+) -> Option<v8::Local<'s, v8::Array>> {
+    let record = get_record(scope, value);
+    if record.is_none() {
+        crate::error::throw_type_error(scope, "Illegal invocation: value is not a Blob");
+    }
+    record
+}
+
+fn set_record(
+    scope: &mut v8::PinScope,
+    target: v8::Local<v8::Value>,
+    record: v8::Local<v8::Array>,
+) -> bool {
+    let Ok(object) = v8::Local::<v8::Object>::try_from(target) else {
+        crate::error::throw_type_error(scope, "Blob target must be an object");
+        return false;
+    };
+    let key = private_key(scope, BLOB_RECORD_KEY);
+    object.set_private(scope, key, record.into());
+    true
+}
+
+#[inline]
+fn record_usize(scope: &mut v8::PinScope, record: v8::Local<v8::Array>, index: u32) -> usize {
+    record
+        .get_index(scope, index)
+        .and_then(|value| value.number_value(scope))
+        .unwrap_or(0.0) as usize
+}
+
+#[inline]
+fn record_value<'s>(
+    scope: &mut v8::PinScope<'s, '_>,
+    record: v8::Local<'s, v8::Array>,
+    index: u32,
+) -> v8::Local<'s, v8::Value> {
+    record
+        .get_index(scope, index)
+        .unwrap_or_else(|| v8::undefined(scope).into())
+}
+
+/// Create a record for `bytes` in a fresh backing store
+fn new_record<'s>(
+    scope: &mut v8::PinScope<'s, '_>,
+    bytes: Vec<u8>,
+    type_: v8::Local<'s, v8::Value>,
+) -> v8::Local<'s, v8::Array> {
+    let size = bytes.len();
+    let backing_store = v8::ArrayBuffer::new_backing_store_from_vec(bytes).make_shared();
+    let buffer = v8::ArrayBuffer::with_backing_store(scope, &backing_store);
+    let offset = v8::Number::new(scope, 0.0);
+    let size = v8::Number::new(scope, size as f64);
+    v8::Array::new_with_elements(scope, &[buffer.into(), offset.into(), size.into(), type_])
+}
+
+/// Run `f` with the bytes of the blob described by `record`, without copying them
+fn with_bytes<R>(
+    scope: &mut v8::PinScope,
+    record: v8::Local<v8::Array>,
+    f: impl FnOnce(&[u8]) -> R,
+) -> R {
+    let offset = record_usize(scope, record, RECORD_OFFSET);
+    let size = record_usize(scope, record, RECORD_SIZE);
+    let buffer = record
+        .get_index(scope, RECORD_BUFFER)
+        .and_then(|value| v8::Local::<v8::ArrayBuffer>::try_from(value).ok());
+
+    let Some(buffer) = buffer else {
+        return f(&[]);
+    };
+    let backing_store = buffer.get_backing_store();
+    if size == 0 || offset + size > backing_store.byte_length() {
+        return f(&[]);
+    }
+    let Some(data) = backing_store.data() else {
+        return f(&[]);
+    };
+    // The backing store is kept alive by `backing_store` for the duration of `f`
+    let bytes =
+        unsafe { std::slice::from_raw_parts((data.as_ptr() as *const u8).add(offset), size) };
+    f(bytes)
+}
+
+/// Copy the bytes of a Blob, or None if `value` isn't a Blob.
+/// Used by fetch to send Blob request bodies.
+pub(crate) fn blob_to_vec<'s>(
+    scope: &mut v8::PinScope<'s, '_>,
+    value: v8::Local<'s, v8::Value>,
+) -> Option<Vec<u8>> {
+    let record = get_record(scope, value)?;
+    Some(with_bytes(scope, record, |bytes| bytes.to_vec()))
+}
+
+/// Check whether an object is a Blob or File, for structuredClone
+pub(crate) fn is_blob<'s>(
+    scope: &mut v8::PinScope<'s, '_>,
+    object: v8::Local<'s, v8::Object>,
+) -> bool {
+    get_record(scope, object.into()).is_some()
+}
+
+fn write_string(serializer: &dyn v8::ValueSerializerHelper, value: &str) {
+    serializer.write_uint32(value.len() as u32);
+    serializer.write_raw_bytes(value.as_bytes());
+}
+
+fn read_string(deserializer: &dyn v8::ValueDeserializerHelper) -> Option<String> {
+    let mut len = 0u32;
+    if !deserializer.read_uint32(&mut len) {
+        return None;
+    }
+    let bytes = deserializer.read_raw_bytes(len as usize)?;
+    Some(String::from_utf8_lossy(bytes).into_owned())
+}
+
+/// Serialize a Blob or File for structuredClone
+pub(crate) fn write_blob<'s>(
+    scope: &mut v8::PinScope<'s, '_>,
+    object: v8::Local<'s, v8::Object>,
+    serializer: &dyn v8::ValueSerializerHelper,
+) -> bool {
+    let Some(record) = get_record(scope, object.into()) else {
+        return false;
+    };
+    let is_file = record.length() > RECORD_NAME;
+    serializer.write_uint32(if is_file { KIND_FILE } else { KIND_BLOB });
+
+    with_bytes(scope, record, |bytes| {
+        serializer.write_uint64(bytes.len() as u64);
+        serializer.write_raw_bytes(bytes);
+    });
+
+    let type_ = record_value(scope, record, RECORD_TYPE).to_rust_string_lossy(scope);
+    write_string(serializer, &type_);
+
+    if is_file {
+        let name = record_value(scope, record, RECORD_NAME).to_rust_string_lossy(scope);
+        write_string(serializer, &name);
+        let last_modified = record_value(scope, record, RECORD_LAST_MODIFIED)
+            .number_value(scope)
+            .unwrap_or(0.0);
+        serializer.write_double(last_modified);
+    }
+    true
+}
+
+/// Deserialize a Blob or File written by `write_blob`
+pub(crate) fn read_blob<'s>(
+    scope: &mut v8::PinScope<'s, '_>,
+    deserializer: &dyn v8::ValueDeserializerHelper,
+) -> Option<v8::Local<'s, v8::Object>> {
+    let mut kind = 0u32;
+    if !deserializer.read_uint32(&mut kind) {
+        return None;
+    }
+    let mut len = 0u64;
+    if !deserializer.read_uint64(&mut len) {
+        return None;
+    }
+    let bytes = deserializer.read_raw_bytes(len as usize)?.to_vec();
+    let type_ = read_string(deserializer)?;
+
+    // Create an empty Blob or File through the factory registered by blob.js
+    let context = scope.get_current_context();
+    let global = context.global(scope);
+    let factory_key = private_key(scope, BLOB_FACTORY_KEY);
+    let factory = global.get_private(scope, factory_key)?;
+    let factory = v8::Local::<v8::Function>::try_from(factory).ok()?;
+    let is_file = v8::Boolean::new(scope, kind == KIND_FILE);
+    let recv = v8::undefined(scope).into();
+    let object = factory.call(scope, recv, &[is_file.into()])?;
+    let object = v8::Local::<v8::Object>::try_from(object).ok()?;
+
+    let type_ = v8::String::new(scope, &type_).unwrap();
+    let record = new_record(scope, bytes, type_.into());
+    if kind == KIND_FILE {
+        let name = read_string(deserializer)?;
+        let mut last_modified = 0.0;
+        if !deserializer.read_double(&mut last_modified) {
+            return None;
+        }
+        let name = v8::String::new(scope, &name).unwrap();
+        let last_modified = v8::Number::new(scope, last_modified);
+        record.set_index(scope, RECORD_NAME, name.into());
+        record.set_index(scope, RECORD_LAST_MODIFIED, last_modified.into());
+    }
+
+    let key = private_key(scope, BLOB_RECORD_KEY);
+    object.set_private(scope, key, record.into());
+    Some(object)
+}
+
+// blobCreate(target, parts, type) - concatenate parts into a new backing store
+fn blob_create<'s>(
+    scope: &mut v8::PinScope<'s, '_>,
+    args: v8::FunctionCallbackArguments<'s>,
+    _rv: v8::ReturnValue,
+) {
+    let target = args.get(0);
+    let parts = match crate::error::try_get_array_result(args.get(1)) {
+        Ok(parts) => parts,
+        Err(msg) => {
+            crate::error::throw_type_error(scope, msg);
+            return;
+        }
+    };
+    let type_ = args.get(2);
+
+    let mut data = Vec::new();
+    for i in 0..parts.length() {
+        let Some(part) = parts.get_index(scope, i) else {
+            continue;
+        };
+        if let Some(record) = get_record(scope, part) {
+            with_bytes(scope, record, |bytes| data.extend_from_slice(bytes));
+        } else if part.is_array_buffer() || part.is_array_buffer_view() {
+            match crate::error::try_get_buffer_source_bytes(part) {
+                Ok(bytes) => data.extend_from_slice(&bytes),
+                Err(msg) => {
+                    crate::error::throw_type_error(scope, msg);
+                    return;
+                }
+            }
+        } else {
+            // Strings are encoded as UTF-8; lone surrogates become U+FFFD
+            let Some(string) = part.to_string(scope) else {
+                return;
+            };
+            data.extend_from_slice(string.to_rust_string_lossy(scope).as_bytes());
+        }
+    }
+
+    let record = new_record(scope, data, type_);
+    set_record(scope, target, record);
+}
+
+// blobSlice(target, source, start, span, type) - share the source's bytes
+fn blob_slice<'s>(
+    scope: &mut v8::PinScope<'s, '_>,
+    args: v8::FunctionCallbackArguments<'s>,
+    _rv: v8::ReturnValue,
+) {
+    let target = args.get(0);
+    let Some(source) = get_record_or_throw(scope, args.get(1)) else {
+        return;
+    };
+    let source_offset = record_usize(scope, source, RECORD_OFFSET);
+    let source_size = record_usize(scope, source, RECORD_SIZE);
+
+    let start = args.get(2).number_value(scope).unwrap_or(0.0).max(0.0) as usize;
+    let start = start.min(source_size);
+    let span = args.get(3).number_value(scope).unwrap_or(0.0).max(0.0) as usize;
+    let span = span.min(source_size - start);
+
+    let buffer = record_value(scope, source, RECORD_BUFFER);
+    let offset = v8::Number::new(scope, (source_offset + start) as f64);
+    let size = v8::Number::new(scope, span as f64);
+    let type_ = args.get(4);
+    let record = v8::Array::new_with_elements(scope, &[buffer, offset.into(), size.into(), type_]);
+    set_record(scope, target, record);
+}
+
+// blobSetFile(target, name, lastModified) - turn a Blob record into a File record
+fn blob_set_file<'s>(
+    scope: &mut v8::PinScope<'s, '_>,
+    args: v8::FunctionCallbackArguments<'s>,
+    _rv: v8::ReturnValue,
+) {
+    let Some(record) = get_record_or_throw(scope, args.get(0)) else {
+        return;
+    };
+    record.set_index(scope, RECORD_NAME, args.get(1));
+    record.set_index(scope, RECORD_LAST_MODIFIED, args.get(2));
+}
+
+// blobGetProperty(blob, name) - read size, type, name or lastModified
+fn blob_get_property<'s>(
+    scope: &mut v8::PinScope<'s, '_>,
+    args: v8::FunctionCallbackArguments<'s>,
+    mut rv: v8::ReturnValue,
+) {
+    let Some(record) = get_record_or_throw(scope, args.get(0)) else {
+        return;
+    };
+    let property = args.get(1).to_rust_string_lossy(scope);
+    let index = match property.as_str() {
+        "size" => RECORD_SIZE,
+        "type" => RECORD_TYPE,
+        "name" => RECORD_NAME,
+        "lastModified" => RECORD_LAST_MODIFIED,
+        _ => {
+            rv.set(v8::undefined(scope).into());
+            return;
+        }
+    };
+    rv.set(record_value(scope, record, index));
+}
+
+// blobText(blob) - decode the bytes as UTF-8
+fn blob_text<'s>(
+    scope: &mut v8::PinScope<'s, '_>,
+    args: v8::FunctionCallbackArguments<'s>,
+    mut rv: v8::ReturnValue,
+) {
+    let Some(record) = get_record_or_throw(scope, args.get(0)) else {
+        return;
+    };
+    let text = with_bytes(scope, record, |bytes| {
+        // UTF-8 decode skips a leading byte order mark
+        let bytes = bytes.strip_prefix(b"\xEF\xBB\xBF").unwrap_or(bytes);
+        String::from_utf8_lossy(bytes).into_owned()
+    });
+    match v8::String::new(scope, &text) {
+        Some(text) => rv.set(text.into()),
+        None => crate::error::throw_range_error(scope, "Blob is too large to decode as a string"),
+    }
+}
+
+// blobBytes(blob, start?, end?) - copy a range of the bytes into a new Uint8Array
+fn blob_bytes<'s>(
+    scope: &mut v8::PinScope<'s, '_>,
+    args: v8::FunctionCallbackArguments<'s>,
+    mut rv: v8::ReturnValue,
+) {
+    let Some(record) = get_record_or_throw(scope, args.get(0)) else {
+        return;
+    };
+    let size = record_usize(scope, record, RECORD_SIZE);
+    let start_arg = args.get(1);
+    let end_arg = args.get(2);
+    let start = if start_arg.is_undefined() {
+        0
+    } else {
+        start_arg.number_value(scope).unwrap_or(0.0).max(0.0) as usize
+    };
+    let end = if end_arg.is_undefined() {
+        size
+    } else {
+        end_arg.number_value(scope).unwrap_or(0.0).max(0.0) as usize
+    };
+    let end = end.min(size);
+    let start = start.min(end);
+
+    let data = with_bytes(scope, record, |bytes| {
+        if bytes.is_empty() {
+            Vec::new()
+        } else {
+            bytes[start..end].to_vec()
+        }
+    });
+    let len = data.len();
+    let backing_store = v8::ArrayBuffer::new_backing_store_from_vec(data).make_shared();
+    let buffer = v8::ArrayBuffer::with_backing_store(scope, &backing_store);
+    let uint8_array = v8::Uint8Array::new(scope, buffer, 0, len).unwrap();
+    rv.set(uint8_array.into());
+}
+
+// blobSetFactory(fn) - register the function used to create Blob/File objects
+// when deserializing. Stored on the global object so it survives snapshots.
+fn blob_set_factory(
+    scope: &mut v8::PinScope,
+    args: v8::FunctionCallbackArguments,
+    _rv: v8::ReturnValue,
+) {
+    let factory = match crate::error::try_get_function_result(args.get(0)) {
+        Ok(f) => f,
+        Err(msg) => {
+            crate::error::throw_type_error(scope, msg);
+            return;
+        }
+    };
+    let context = scope.get_current_context();
+    let global = context.global(scope);
+    let key = private_key(scope, BLOB_FACTORY_KEY);
+    global.set_private(scope, key, factory.into());
+}
@@ -0,0 +1,144 @@
+// AbortController and AbortSignal
+// https://dom.spec.whatwg.org/#interface-abortcontroller
+// https://dom.spec.whatwg.org/#interface-AbortSignal
+
+'use strict';
+
+// eslint-disable-next-line no-unused-expressions
+(({ setTimeout: setTimeoutNative }) => {
+  // Guards the AbortSignal constructor, which is not exposed to scripts
+  const kCreate = Symbol('AbortSignal.create');
+  // Aborts a signal: set by AbortSignal for AbortController and the static
+  // factories, and not reachable from scripts
+  let signalAbort;
+
+  class AbortSignal extends EventTarget {
+    #aborted = false;
+    #reason = undefined;
+    #onabort = null;
+
+    constructor(token) {
+      if (token !== kCreate) {
+        throw new TypeError('Illegal constructor');
+      }
+      super();
+    }
+
+    static abort(reason) {
+      const signal = new AbortSignal(kCreate);
+      signalAbort(signal, reason);
+      return signal;
+    }
+
+    static timeout(milliseconds) {
+      const ms = Number(milliseconds);
+      if (!Number.isFinite(ms) || ms < 0) {
+        throw new TypeError('AbortSignal.timeout: milliseconds must be a non-negative number');
+      }
+      const signal = new AbortSignal(kCreate);
+      // Unref'd timer: a pending timeout alone doesn't keep the event loop alive
+      setTimeoutNative(() => {
+        signalAbort(signal, new DOMException('The operation timed out.', 'TimeoutError'));
+      }, ms, false);
+      return signal;
+    }
+
+    static any(signals) {
+      const signal = new AbortSignal(kCreate);
+      const sources = Array.from(signals);
+      for (const source of sources) {
+        if (!(source instanceof AbortSignal)) {
+          throw new TypeError('AbortSignal.any: all members must be AbortSignal instances');
+        }
+      }
+
+      // Already aborted: take the reason of the first aborted signal
+      for (const source of sources) {
+        if (source.aborted) {
+          signalAbort(signal, source.reason);
+          return signal;
+        }
+      }
+
+      const onAbort = (event) => {
+        // Leave the listener on the signal being dispatched; it never fires again
+        for (const source of sources) {
+          if (source !== event.target) {
+            source.removeEventListener('abort', onAbort);
+          }
+        }
+        signalAbort(signal, event.target.reason);
+      };
+      for (const source of sources) {
+        source.addEventListener('abort', onAbort);
+      }
+      return signal;
+    }
+
+    get aborted() {
+      return this.#aborted;
+    }
+
+    get reason() {
+      return this.#reason;
+    }
+
+    get onabort() {
+      return this.#onabort;
+    }
+
+    set onabort(handler) {
+      if (this.#onabort !== null) {
+        this.removeEventListener('abort', this.#onabort);
+      }
+      this.#onabort = typeof handler === 'function' ? handler : null;
+      if (this.#onabort !== null) {
+        this.addEventListener('abort', this.#onabort);
+      }
+    }
+
+    throwIfAborted() {
+      if (this.#aborted) {
+        throw this.#reason;
+      }
+    }
+
+    static {
+      signalAbort = (signal, reason) => signal.#signalAbort(reason);
+    }
+
+    #signalAbort(reason) {
+      if (this.#aborted) {
+        return;
+      }
+      this.#aborted = true;
+      this.#reason = reason !== undefined
+        ? reason
+        : new DOMException('This operation was aborted', 'AbortError');
+      this.dispatchEvent(new Event('abort'));
+    }
+
+    get [Symbol.toStringTag]() {
+      return 'AbortSignal';
+    }
+  }
+
+  class AbortController {
+    #signal = new AbortSignal(kCreate);
+
+    get signal() {
+      return this.#signal;
+    }
+
+    abort(reason) {
+      signalAbort(this.#signal, reason);
+    }
+
+    get [Symbol.toStringTag]() {
+      return 'AbortController';
+    }
+  }
+
+  globalThis.AbortController = AbortController;
+  globalThis.AbortSignal = AbortSignal;
+});
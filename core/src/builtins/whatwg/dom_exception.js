@@ -0,0 +1,103 @@
+// DOMException
+// https://webidl.spec.whatwg.org/#idl-DOMException
+
+'use strict';
+
+// eslint-disable-next-line no-unused-expressions
+(() => {
+  // Legacy error codes, indexed by error name
+  const ERROR_CODES = {
+    IndexSizeError: 1,
+    HierarchyRequestError: 3,
+    WrongDocumentError: 4,
+    InvalidCharacterError: 5,
+    NoModificationAllowedError: 7,
+    NotFoundError: 8,
+    NotSupportedError: 9,
+    InUseAttributeError: 10,
+    InvalidStateError: 11,
+    SyntaxError: 12,
+    InvalidModificationError: 13,
+    NamespaceError: 14,
+    InvalidAccessError: 15,
+    TypeMismatchError: 17,
+    SecurityError: 18,
+    NetworkError: 19,
+    AbortError: 20,
+    URLMismatchError: 21,
+    QuotaExceededError: 22,
+    TimeoutError: 23,
+    InvalidNodeTypeError: 24,
+    DataCloneError: 25,
+  };
+
+  // Legacy code constants exposed on the constructor and prototype
+  const CODE_CONSTANTS = {
+    INDEX_SIZE_ERR: 1,
+    DOMSTRING_SIZE_ERR: 2,
+    HIERARCHY_REQUEST_ERR: 3,
+    WRONG_DOCUMENT_ERR: 4,
+    INVALID_CHARACTER_ERR: 5,
+    NO_DATA_ALLOWED_ERR: 6,
+    NO_MODIFICATION_ALLOWED_ERR: 7,
+    NOT_FOUND_ERR: 8,
+    NOT_SUPPORTED_ERR: 9,
+    INUSE_ATTRIBUTE_ERR: 10,
+    INVALID_STATE_ERR: 11,
+    SYNTAX_ERR: 12,
+    INVALID_MODIFICATION_ERR: 13,
+    NAMESPACE_ERR: 14,
+    INVALID_ACCESS_ERR: 15,
+    VALIDATION_ERR: 16,
+    TYPE_MISMATCH_ERR: 17,
+    SECURITY_ERR: 18,
+    NETWORK_ERR: 19,
+    ABORT_ERR: 20,
+    URL_MISMATCH_ERR: 21,
+    QUOTA_EXCEEDED_ERR: 22,
+    TIMEOUT_ERR: 23,
+    INVALID_NODE_TYPE_ERR: 24,
+    DATA_CLONE_ERR: 25,
+  };
+
+  class DOMException extends Error {
+    #name;
+
+    constructor(message = '', options = 'Error') {
+      // The second argument is either the name or { name, cause }
+      if (options !== null && typeof options === 'object') {
+        super(String(message), 'cause' in options ? { cause: options.cause } : undefined);
+        this.#name = 'name' in options ? String(options.name) : 'Error';
+      } else {
+        super(String(message));
+        this.#name = String(options);
+      }
+    }
+
+    get name() {
+      // The stack trace may be formatted before the private field is set
+      return #name in this ? this.#name : 'Error';
+    }
+
+    get code() {
+      return #name in this ? (ERROR_CODES[this.#name] || 0) : 0;
+    }
+
+    get [Symbol.toStringTag]() {
+      return 'DOMException';
+    }
+  }
+
+  for (const [key, value] of Object.entries(CODE_CONSTANTS)) {
+    const descriptor = { value, writable: false, enumerable: true, configurable: false };
+    Object.defineProperty(DOMException, key, descriptor);
+    Object.defineProperty(DOMException.prototype, key, descriptor);
+  }
+
+  Object.defineProperty(globalThis, 'DOMException', {
+    value: DOMException,
+    writable: true,
+    enumerable: false,
+    configurable: true,
+  });
+});
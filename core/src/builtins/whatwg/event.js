@@ -9,7 +9,8 @@
   eventTargetDispatchEvent,
   eventStopPropagation,
   eventStopImmediatePropagation,
-  eventPreventDefault
+  eventPreventDefault,
+  reportUncaughtError
 }) => {
   
   // Event phases
@@ -222,7 +223,98 @@
     }
   }
 
+  // PromiseRejectionEvent class
+  // https://html.spec.whatwg.org/multipage/webappapis.html#promiserejectionevent
+  class PromiseRejectionEvent extends Event {
+    #promise;
+    #reason;
+
+    constructor(type, eventInitDict) {
+      if (eventInitDict === null || typeof eventInitDict !== 'object' || !('promise' in eventInitDict)) {
+        throw new TypeError('Failed to construct \'PromiseRejectionEvent\': required member promise is undefined.');
+      }
+      super(type, eventInitDict);
+      this.#promise = eventInitDict.promise;
+      this.#reason = eventInitDict.reason;
+    }
+
+    get promise() {
+      return this.#promise;
+    }
+
+    get reason() {
+      return this.#reason;
+    }
+  }
+
+  // ErrorEvent class
+  // https://html.spec.whatwg.org/multipage/webappapis.html#errorevent
+  class ErrorEvent extends Event {
+    #message;
+    #filename;
+    #lineno;
+    #colno;
+    #error;
+
+    constructor(type, eventInitDict = {}) {
+      super(type, eventInitDict);
+      this.#message = eventInitDict.message === undefined ? '' : String(eventInitDict.message);
+      this.#filename = eventInitDict.filename === undefined ? '' : String(eventInitDict.filename);
+      this.#lineno = eventInitDict.lineno === undefined ? 0 : eventInitDict.lineno >>> 0;
+      this.#colno = eventInitDict.colno === undefined ? 0 : eventInitDict.colno >>> 0;
+      this.#error = eventInitDict.error;
+    }
+
+    get message() {
+      return this.#message;
+    }
+
+    get filename() {
+      return this.#filename;
+    }
+
+    get lineno() {
+      return this.#lineno;
+    }
+
+    get colno() {
+      return this.#colno;
+    }
+
+    get error() {
+      return this.#error;
+    }
+  }
+
+  // reportError(error): report an exception as if it was uncaught, without
+  // throwing it
+  // https://html.spec.whatwg.org/multipage/webappapis.html#dom-reporterror
+  function reportError(error) {
+    if (arguments.length === 0) {
+      throw new TypeError('Failed to execute \'reportError\': 1 argument required, but only 0 present.');
+    }
+    let message;
+    try {
+      message = `Uncaught ${error}`;
+    } catch {
+      message = 'Uncaught exception';
+    }
+    if (!globalThis.__dispatchError__(error, message)) {
+      reportUncaughtError(error);
+    }
+  }
+
   // Export to global scope
   globalThis.Event = Event;
   globalThis.EventTarget = EventTarget;
+  globalThis.PromiseRejectionEvent = PromiseRejectionEvent;
+  globalThis.ErrorEvent = ErrorEvent;
+  globalThis.reportError = reportError;
+
+  // The global object is an EventTarget, like a browser's window, for
+  // events such as error and unhandledrejection
+  Object.defineProperty(globalThis, '__listeners__', { value: new Map() });
+  for (const method of ['addEventListener', 'removeEventListener', 'dispatchEvent']) {
+    globalThis[method] = EventTarget.prototype[method].bind(globalThis);
+  }
 });
@@ -18,6 +18,9 @@ pub(crate) fn get_external_references() -> Vec<v8::ExternalReference> {
         v8::ExternalReference {
             function: v8::MapFnTo::map_fn_to(event_prevent_default),
         },
+        v8::ExternalReference {
+            function: v8::MapFnTo::map_fn_to(report_uncaught_error),
+        },
     ]
 }
 
@@ -47,6 +50,11 @@ pub(crate) fn register_bindings(scope: &mut v8::PinScope, bindings: v8::Local<v8
     let name = v8::String::new(scope, "eventPreventDefault").unwrap();
     let value = v8::Function::new(scope, event_prevent_default).unwrap();
     bindings.set(scope, name.into(), value.into());
+
+    // reportError()
+    let name = v8::String::new(scope, "reportUncaughtError").unwrap();
+    let value = v8::Function::new(scope, report_uncaught_error).unwrap();
+    bindings.set(scope, name.into(), value.into());
 }
 
 // EventTarget.addEventListener(type, listener, options)
@@ -503,3 +511,14 @@ fn event_prevent_default(
         event_obj.set(scope, key.into(), value.into());
     }
 }
+
+// reportError(error) with an error no listener handled: stop the event loop
+// with it, like an uncaught exception
+fn report_uncaught_error<'s>(
+    scope: &mut v8::PinScope<'s, '_>,
+    args: v8::FunctionCallbackArguments<'s>,
+    _rv: v8::ReturnValue,
+) {
+    let error = crate::JsError::from_value(scope, args.get(0));
+    crate::uncaught_exception::record(scope, error);
+}
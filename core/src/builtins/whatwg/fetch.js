@@ -82,10 +82,210 @@
     }
   }
 
+  // Extract a request body into something the native binding can send.
+  // Returns { source, type } where source is null, a string, a BufferSource, a Blob
+  // or a ReadableStream, and type is the default Content-Type (or null).
+  // FormData is encoded as multipart/form-data into a Blob.
+  // https://fetch.spec.whatwg.org/#concept-bodyinit-extract
+  function extractBody(body) {
+    if (body === null || body === undefined) {
+      return { source: null, type: null };
+    }
+    if (typeof body === 'string') {
+      return { source: body, type: 'text/plain;charset=UTF-8' };
+    }
+    if (body instanceof globalThis.FormData) {
+      const { body: blob, type } = body._encodeMultipart();
+      return { source: blob, type };
+    }
+    if (body instanceof URLSearchParams) {
+      return { source: body.toString(), type: 'application/x-www-form-urlencoded;charset=UTF-8' };
+    }
+    if (body instanceof ArrayBuffer || ArrayBuffer.isView(body)) {
+      // Passed to Rust as-is and copied as raw bytes
+      return { source: body, type: null };
+    }
+    if (body instanceof globalThis.Blob) {
+      // Blob bytes are copied straight from native storage
+      return { source: body, type: body.type || null };
+    }
+    if (body instanceof globalThis.ReadableStream) {
+      if (body.locked) {
+        throw new TypeError('Request body stream is locked');
+      }
+      return { source: body, type: null };
+    }
+    return { source: String(body), type: 'text/plain;charset=UTF-8' };
+  }
+
+  // Get the bytes of a body that isn't backed by a stream
+  function staticBodyBytes(body) {
+    if (body === null || body === undefined) {
+      return new Uint8Array(0);
+    }
+    if (body instanceof globalThis.Blob) {
+      return bindings.blobBytes(body);
+    }
+    if (body instanceof ArrayBuffer) {
+      return new Uint8Array(body.slice(0));
+    }
+    if (ArrayBuffer.isView(body)) {
+      return new Uint8Array(body.buffer.slice(body.byteOffset, body.byteOffset + body.byteLength));
+    }
+    return new TextEncoder().encode(String(body));
+  }
+
+  // Feed a ReadableStream request body to the native request chunk by chunk
+  async function pumpRequestBody(id, stream) {
+    const reader = stream.getReader();
+    try {
+      while (true) {
+        const { done, value } = await reader.read();
+        if (done) {
+          bindings.fetchCloseBody(id);
+          return;
+        }
+        if (!(value instanceof ArrayBuffer) && !ArrayBuffer.isView(value)) {
+          throw new TypeError('Request body stream chunks must be ArrayBuffer or ArrayBufferView');
+        }
+        if (!bindings.fetchWriteBody(id, value)) {
+          // The request finished or was aborted; stop reading
+          await reader.cancel();
+          return;
+        }
+      }
+    } catch (error) {
+      bindings.fetchCloseBody(id, String(error && error.message || error));
+    }
+  }
+
+  // Body mixin shared by Request and Response
+  // https://fetch.spec.whatwg.org/#body-mixin
+  class Body {
+    // Internal: store the body and default the Content-Type from it
+    _initBody(body) {
+      this._bodyUsed = false;
+      this._bodyStream = null;
+      const { source, type } = extractBody(body);
+      // FormData is kept encoded so the body matches the boundary in Content-Type
+      this._body = body instanceof globalThis.FormData ? source : body;
+      if (type !== null && !this.headers.has('content-type')) {
+        this.headers.set('content-type', type);
+      }
+    }
+
+    // Internal: true when the body has to be read through the body stream
+    _isStreamed() {
+      return this._body instanceof globalThis.ReadableStream;
+    }
+
+    // Internal: mark the body as used and read all of it into a Uint8Array
+    async _consumeBytes() {
+      if (this._bodyUsed) {
+        throw new TypeError('Body has already been consumed');
+      }
+      this._bodyUsed = true;
+
+      if (!this._isStreamed()) {
+        return staticBodyBytes(this._body);
+      }
+
+      const reader = this.body.getReader();
+      const chunks = [];
+      let length = 0;
+      while (true) {
+        const { done, value } = await reader.read();
+        if (done) break;
+        chunks.push(value);
+        length += value.byteLength;
+      }
+      if (chunks.length === 1) {
+        return chunks[0];
+      }
+      const bytes = new Uint8Array(length);
+      let offset = 0;
+      for (const chunk of chunks) {
+        bytes.set(chunk, offset);
+        offset += chunk.byteLength;
+      }
+      return bytes;
+    }
+
+    get body() {
+      if (this._bodyStream) {
+        return this._bodyStream;
+      }
+      if (this._body === null || this._body === undefined) {
+        return null;
+      }
+
+      if (this._body instanceof globalThis.ReadableStream) {
+        this._bodyStream = this._body;
+      } else if (this._body instanceof globalThis.Blob) {
+        this._bodyStream = this._body.stream();
+      } else {
+        // Create a ReadableStream from the static body
+        this._bodyStream = new globalThis.ReadableStream({
+          start: (controller) => {
+            controller.enqueue(staticBodyBytes(this._body));
+            controller.close();
+          }
+        });
+      }
+      return this._bodyStream;
+    }
+
+    get bodyUsed() {
+      return this._bodyUsed;
+    }
+
+    async text() {
+      // Static strings and Blobs are decoded without copying them first
+      if (!this._bodyUsed && !this._isStreamed()) {
+        if (typeof this._body === 'string') {
+          this._bodyUsed = true;
+          return this._body;
+        }
+        if (this._body instanceof globalThis.Blob) {
+          this._bodyUsed = true;
+          return bindings.blobText(this._body);
+        }
+      }
+      return new TextDecoder().decode(await this._consumeBytes());
+    }
+
+    json() {
+      return this.text().then(text => JSON.parse(text));
+    }
+
+    async arrayBuffer() {
+      const bytes = await this._consumeBytes();
+      if (bytes.byteOffset === 0 && bytes.byteLength === bytes.buffer.byteLength) {
+        return bytes.buffer;
+      }
+      return bytes.buffer.slice(bytes.byteOffset, bytes.byteOffset + bytes.byteLength);
+    }
+
+    async bytes() {
+      return this._consumeBytes();
+    }
+
+    async formData() {
+      const bytes = await this._consumeBytes();
+      return globalThis.FormData._fromBody(bytes, this.headers.get('content-type'));
+    }
+
+    async blob() {
+      const bytes = await this._consumeBytes();
+      const type = this.headers.get('content-type') || '';
+      return new globalThis.Blob([bytes], { type });
+    }
+  }
+
   // Response class
-  class Response {
+  class Response extends Body {
     constructor(body, init = {}) {
-      this._body = body;
+      super();
       this._streamId = init.streamId;
       this.status = init.status || 200;
       this.statusText = init.statusText || '';
@@ -94,8 +294,45 @@
       this.redirected = false;
       this.type = 'basic';
       this.url = init.url || '';
-      this._bodyUsed = false;
-      this._bodyStream = null;
+      this._bodyController = null;
+      this._aborted = false;
+      this._abortReason = undefined;
+      this._onBodyDone = null;
+
+      if (this._streamId === undefined || this._streamId === null) {
+        this._initBody(body);
+      } else {
+        // The body is read from the native stream of a fetch response
+        this._body = null;
+        this._bodyUsed = false;
+        this._bodyStream = null;
+      }
+    }
+
+    _isStreamed() {
+      return (this._streamId !== undefined && this._streamId !== null) || super._isStreamed();
+    }
+
+    // Internal: error the body with the abort reason and release the buffered data
+    _abortBody(reason) {
+      if (this._streamId === undefined || this._streamId === null || this._aborted) {
+        return;
+      }
+      this._aborted = true;
+      this._abortReason = reason;
+      bindings.fetchCloseStream(this._streamId);
+      if (this._bodyController) {
+        this._bodyController.error(reason);
+      }
+    }
+
+    // Internal: called once the body has been fully read or cancelled
+    _finishBody() {
+      if (this._onBodyDone) {
+        const onBodyDone = this._onBodyDone;
+        this._onBodyDone = null;
+        onBodyDone();
+      }
     }
 
     get body() {
@@ -106,68 +343,46 @@
       // If we have a stream ID, create a streaming ReadableStream
       if (this._streamId !== undefined && this._streamId !== null) {
         const streamId = this._streamId;
+        const response = this;
         this._bodyStream = new globalThis.ReadableStream({
+          start(controller) {
+            response._bodyController = controller;
+            if (response._aborted) {
+              controller.error(response._abortReason);
+            }
+          },
           async pull(controller) {
             try {
               const result = await bindings.fetchReadChunk(streamId);
               if (result.done) {
                 controller.close();
+                response._finishBody();
               } else {
                 controller.enqueue(result.value);
               }
             } catch (error) {
               controller.error(error);
             }
+          },
+          cancel() {
+            bindings.fetchCloseStream(streamId);
+            response._finishBody();
           }
         });
-      } else {
-        // Fallback: Create a ReadableStream from the body string
-        this._bodyStream = new globalThis.ReadableStream({
-          start: (controller) => {
-            if (this._body !== null && this._body !== undefined) {
-              const encoder = new TextEncoder();
-              controller.enqueue(encoder.encode(String(this._body)));
-            }
-            controller.close();
-          }
-        });
+        return this._bodyStream;
       }
-      
-      return this._bodyStream;
-    }
 
-    get bodyUsed() {
-      return this._bodyUsed;
-    }
-
-    async text() {
-      if (this._bodyUsed) {
-        return Promise.reject(new TypeError('Body has already been consumed'));
+      const stream = super.body;
+      if (stream !== null) {
+        return stream;
       }
-      this._bodyUsed = true;
-      
-      // If we have a stream ID, read from the stream
-      if (this._streamId !== undefined && this._streamId !== null) {
-        const reader = this.body.getReader();
-        const chunks = [];
-        const decoder = new TextDecoder();
-        
-        while (true) {
-          const { done, value } = await reader.read();
-          if (done) break;
-          chunks.push(decoder.decode(value, { stream: true }));
+      // A response without a body still exposes an empty stream
+      this._bodyStream = new globalThis.ReadableStream({
+        start(controller) {
+          controller.close();
         }
-        chunks.push(decoder.decode()); // Final decode without stream flag
-        
-        return chunks.join('');
-      }
-      
-      // Fallback for non-streaming responses
-      return String(this._body || '');
-    }
-
-    json() {
-      return this.text().then(text => JSON.parse(text));
+      });
+      return this._bodyStream;
     }
 
     clone() {
@@ -185,18 +400,37 @@
   }
 
   // Request class
-  class Request {
+  class Request extends Body {
     constructor(input, init = {}) {
+      super();
+      let body;
       if (input instanceof Request) {
         this.url = input.url;
         this.method = init.method || input.method;
         this.headers = new Headers(init.headers || input.headers);
-        this.body = init.body !== undefined ? init.body : input.body;
+        body = init.body !== undefined ? init.body : input._body;
+        this.signal = init.signal !== undefined ? init.signal : input.signal;
       } else {
         this.url = String(input);
         this.method = (init.method || 'GET').toUpperCase();
         this.headers = new Headers(init.headers || {});
-        this.body = init.body !== undefined ? init.body : null;
+        body = init.body !== undefined ? init.body : null;
+        this.signal = init.signal !== undefined ? init.signal : null;
+      }
+
+      const method = String(this.method).toUpperCase();
+      if (body !== null && body !== undefined && (method === 'GET' || method === 'HEAD')) {
+        throw new TypeError('Failed to construct \'Request\': Request with GET/HEAD method cannot have body.');
+      }
+
+      // Default the Content-Type from the body, like the Fetch spec's extract step
+      this._initBody(body);
+
+      if (this.signal === null || this.signal === undefined) {
+        // Every request has a signal; this one is never aborted
+        this.signal = new AbortController().signal;
+      } else if (!(this.signal instanceof AbortSignal)) {
+        throw new TypeError('Failed to construct \'Request\': member signal is not of type AbortSignal.');
       }
 
       this.mode = init.mode || 'cors';
@@ -208,16 +442,20 @@
     }
 
     clone() {
+      if (this._bodyUsed) {
+        throw new TypeError('Body has already been consumed');
+      }
       return new Request(this.url, {
         method: this.method,
         headers: this.headers,
-        body: this.body,
+        body: this._body,
         mode: this.mode,
         credentials: this.credentials,
         cache: this.cache,
         redirect: this.redirect,
         referrer: this.referrer,
-        integrity: this.integrity
+        integrity: this.integrity,
+        signal: this.signal
       });
     }
   }
@@ -226,32 +464,85 @@
   function fetch(resource, init = {}) {
     let request;
     
-    if (resource instanceof Request) {
-      request = resource;
-      if (init && Object.keys(init).length > 0) {
+    // An invalid request rejects the fetch rather than throwing
+    try {
+      if (resource instanceof Request) {
+        request = resource;
+        if (init && Object.keys(init).length > 0) {
+          request = new Request(resource, init);
+        }
+      } else {
         request = new Request(resource, init);
       }
-    } else {
-      request = new Request(resource, init);
+    } catch (error) {
+      return Promise.reject(error);
+    }
+
+    const signal = request.signal;
+    if (signal.aborted) {
+      return Promise.reject(signal.reason);
     }
 
     // Prepare headers as an array for native binding
     const headersArray = request.headers._toArray();
+    const { source } = extractBody(request._body);
+    const isStream = source instanceof globalThis.ReadableStream;
     
-    // Call native fetch binding and return the promise
-    return bindings.fetchSend(
+    // Call native fetch binding; it returns the request ID and a promise
+    const { id, promise } = bindings.fetchSend(
       request.url,
       request.method,
       headersArray,
-      request.body || null
-    ).then(responseData => {
-      // Parse response - now includes streamId for streaming
-      return new Response(null, {
-        status: responseData.status,
-        statusText: responseData.statusText,
-        headers: responseData.headers,
-        url: request.url,
-        streamId: responseData.streamId
+      isStream ? null : source,
+      isStream
+    );
+
+    if (isStream) {
+      pumpRequestBody(id, source);
+    }
+
+    return new Promise((resolve, reject) => {
+      // The response is held weakly, so one whose body is never read isn't
+      // kept alive by a long-lived signal
+      let responseRef = null;
+
+      // Before the response arrives, abort rejects the fetch and drops the request.
+      // Afterwards, it errors the body stream instead.
+      const onAbort = () => {
+        if (responseRef !== null) {
+          const response = responseRef.deref();
+          if (response !== undefined) {
+            response._abortBody(signal.reason);
+          }
+        } else {
+          bindings.fetchAbort(id);
+          reject(signal.reason);
+        }
+      };
+      signal.addEventListener('abort', onAbort);
+
+      promise.then(responseData => {
+        // Aborted after the worker answered: nothing will read the body
+        if (signal.aborted) {
+          if (responseData.streamId !== undefined && responseData.streamId !== null) {
+            bindings.fetchCloseStream(responseData.streamId);
+          }
+          return;
+        }
+        // Parse response - now includes streamId for streaming
+        const response = new Response(null, {
+          status: responseData.status,
+          statusText: responseData.statusText,
+          headers: responseData.headers,
+          url: request.url,
+          streamId: responseData.streamId
+        });
+        response._onBodyDone = () => signal.removeEventListener('abort', onAbort);
+        responseRef = new WeakRef(response);
+        resolve(response);
+      }, error => {
+        signal.removeEventListener('abort', onAbort);
+        reject(error);
       });
     });
   }
@@ -6,6 +6,18 @@ pub(crate) fn get_external_references() -> Vec<v8::ExternalReference> {
         v8::ExternalReference {
             function: v8::MapFnTo::map_fn_to(fetch_read_chunk),
         },
+        v8::ExternalReference {
+            function: v8::MapFnTo::map_fn_to(fetch_abort),
+        },
+        v8::ExternalReference {
+            function: v8::MapFnTo::map_fn_to(fetch_close_stream),
+        },
+        v8::ExternalReference {
+            function: v8::MapFnTo::map_fn_to(fetch_write_body),
+        },
+        v8::ExternalReference {
+            function: v8::MapFnTo::map_fn_to(fetch_close_body),
+        },
     ]
 }
 
@@ -17,6 +29,22 @@ pub(crate) fn register_bindings(scope: &mut v8::PinScope, bindings: v8::Local<v8
     let name = v8::String::new(scope, "fetchReadChunk").unwrap();
     let value = v8::Function::new(scope, fetch_read_chunk).unwrap();
     bindings.set(scope, name.into(), value.into());
+
+    let name = v8::String::new(scope, "fetchAbort").unwrap();
+    let value = v8::Function::new(scope, fetch_abort).unwrap();
+    bindings.set(scope, name.into(), value.into());
+
+    let name = v8::String::new(scope, "fetchCloseStream").unwrap();
+    let value = v8::Function::new(scope, fetch_close_stream).unwrap();
+    bindings.set(scope, name.into(), value.into());
+
+    let name = v8::String::new(scope, "fetchWriteBody").unwrap();
+    let value = v8::Function::new(scope, fetch_write_body).unwrap();
+    bindings.set(scope, name.into(), value.into());
+
+    let name = v8::String::new(scope, "fetchCloseBody").unwrap();
+    let value = v8::Function::new(scope, fetch_close_body).unwrap();
+    bindings.set(scope, name.into(), value.into());
 }
 
 // Helper to convert v8 string to Rust string
@@ -26,12 +54,12 @@ fn to_rust_string(scope: &mut v8::PinScope, value: v8::Local<v8::Value>) -> Stri
 }
 
 #[inline]
-fn fetch_send(
-    scope: &mut v8::PinScope,
-    args: v8::FunctionCallbackArguments,
+fn fetch_send<'s>(
+    scope: &mut v8::PinScope<'s, '_>,
+    args: v8::FunctionCallbackArguments<'s>,
     mut rv: v8::ReturnValue,
 ) {
-    // Extract arguments: url, method, headers, body
+    // Extract arguments: url, method, headers, body, streamBody
     let url = to_rust_string(scope, args.get(0));
     let method = to_rust_string(scope, args.get(1));
 
@@ -79,12 +107,33 @@ fn fetch_send(
         headers.push((key, value));
     }
 
-    // Extract body (optional)
+    // Extract body (optional). BufferSources and Blobs are copied as raw bytes; when
+    // `streamBody` is true the body is written later through fetchWriteBody.
     let body_arg = args.get(3);
-    let body = if body_arg.is_null() || body_arg.is_undefined() {
+    let stream_body = args.get(4).is_true();
+    let mut body_sender = None;
+    let body = if stream_body {
+        let (tx, rx) = std::sync::mpsc::channel();
+        body_sender = Some(tx);
+        Some(crate::fetch_worker::FetchBody::Stream(rx))
+    } else if body_arg.is_null() || body_arg.is_undefined() {
         None
+    } else if body_arg.is_array_buffer() || body_arg.is_array_buffer_view() {
+        match crate::error::try_get_buffer_source_bytes(body_arg) {
+            Ok(bytes) => Some(crate::fetch_worker::FetchBody::Bytes(bytes)),
+            Err(msg) => {
+                crate::error::throw_type_error(scope, msg);
+                headers.clear();
+                header_pool.put(headers);
+                return;
+            }
+        }
+    } else if let Some(bytes) = crate::builtins::w3c::blob_impl::blob_to_vec(scope, body_arg) {
+        Some(crate::fetch_worker::FetchBody::Bytes(bytes))
     } else {
-        Some(to_rust_string(scope, body_arg))
+        Some(crate::fetch_worker::FetchBody::Bytes(
+            to_rust_string(scope, body_arg).into_bytes(),
+        ))
     };
 
     // Create a promise
@@ -97,8 +146,28 @@ fn fetch_send(
     };
     let promise = resolver.get_promise(scope);
 
+    let state = crate::IsolateState::get(scope);
+
+    // Allocate a request ID so the fetch can be aborted later
+    let id = {
+        let state_borrow = state.borrow();
+        let mut next_id = state_borrow.next_fetch_id.borrow_mut();
+        let id = *next_id;
+        *next_id += 1;
+        id
+    };
+
+    if let Some(tx) = body_sender {
+        state
+            .borrow()
+            .fetch_body_senders
+            .borrow_mut()
+            .insert(id, tx);
+    }
+
     // Store the fetch request (headers ownership transferred)
     let fetch_request = crate::isolate_state::FetchRequest {
+        id,
         url,
         method,
         headers,
@@ -106,15 +175,129 @@ fn fetch_send(
         resolver: v8::Global::new(scope, resolver),
     };
 
-    let state = crate::IsolateState::get(scope);
     state
         .borrow()
         .pending_fetches
         .borrow_mut()
         .push(fetch_request);
 
-    // Return the promise
-    rv.set(promise.into());
+    // Return { id, promise }
+    let obj = v8::Object::new(scope);
+    let id_key = v8::String::new(scope, "id").unwrap();
+    let id_value = v8::Number::new(scope, id as f64);
+    obj.set(scope, id_key.into(), id_value.into());
+    let promise_key = v8::String::new(scope, "promise").unwrap();
+    obj.set(scope, promise_key.into(), promise.into());
+    rv.set(obj.into());
+}
+
+// Native function to abort a fetch that hasn't completed yet.
+// The JS side rejects the fetch promise; this only stops the runtime from waiting on it.
+#[inline]
+fn fetch_abort(
+    scope: &mut v8::PinScope,
+    args: v8::FunctionCallbackArguments,
+    _rv: v8::ReturnValue,
+) {
+    let Some(id) = args.get(0).number_value(scope) else {
+        crate::error::throw_type_error(scope, "Fetch ID must be a number");
+        return;
+    };
+    let id = id as u64;
+    let state = crate::IsolateState::get(scope);
+    let state_borrow = state.borrow();
+    state_borrow.fetches_to_abort.borrow_mut().push(id);
+
+    // Fail a streamed upload instead of letting it end as a truncated body
+    if let Some(tx) = state_borrow.fetch_body_senders.borrow_mut().remove(&id) {
+        let _ = tx.send(crate::fetch_worker::BodyChunk::Error(
+            "The request was aborted".to_string(),
+        ));
+    }
+}
+
+// Native function to write a chunk of a streamed request body.
+// Returns false if the request no longer accepts data (finished or aborted).
+#[inline]
+fn fetch_write_body(
+    scope: &mut v8::PinScope,
+    args: v8::FunctionCallbackArguments,
+    mut rv: v8::ReturnValue,
+) {
+    let Some(id) = args.get(0).number_value(scope) else {
+        crate::error::throw_type_error(scope, "Fetch ID must be a number");
+        return;
+    };
+    let chunk = match crate::error::try_get_buffer_source_bytes(args.get(1)) {
+        Ok(bytes) => bytes,
+        Err(_) => {
+            crate::error::throw_type_error(
+                scope,
+                "Request body stream chunks must be ArrayBuffer or ArrayBufferView",
+            );
+            return;
+        }
+    };
+
+    let state = crate::IsolateState::get(scope);
+    let senders = state.borrow().fetch_body_senders.clone();
+    let mut senders = senders.borrow_mut();
+    let accepted = match senders.get(&(id as u64)) {
+        Some(tx) => tx.send(crate::fetch_worker::BodyChunk::Data(chunk)).is_ok(),
+        None => false,
+    };
+    if !accepted {
+        senders.remove(&(id as u64));
+    }
+    drop(senders);
+    rv.set(v8::Boolean::new(scope, accepted).into());
+}
+
+// Native function to finish a streamed request body.
+// An optional error message makes the request fail instead of completing.
+#[inline]
+fn fetch_close_body(
+    scope: &mut v8::PinScope,
+    args: v8::FunctionCallbackArguments,
+    _rv: v8::ReturnValue,
+) {
+    let Some(id) = args.get(0).number_value(scope) else {
+        crate::error::throw_type_error(scope, "Fetch ID must be a number");
+        return;
+    };
+    let error_arg = args.get(1);
+    let error = if error_arg.is_null_or_undefined() {
+        None
+    } else {
+        Some(to_rust_string(scope, error_arg))
+    };
+
+    let state = crate::IsolateState::get(scope);
+    let tx = state
+        .borrow()
+        .fetch_body_senders
+        .borrow_mut()
+        .remove(&(id as u64));
+    // Dropping the sender ends the body
+    if let (Some(tx), Some(error)) = (tx, error) {
+        let _ = tx.send(crate::fetch_worker::BodyChunk::Error(error));
+    }
+}
+
+// Native function to release the buffered body of a cancelled or aborted response
+#[inline]
+fn fetch_close_stream(
+    scope: &mut v8::PinScope,
+    args: v8::FunctionCallbackArguments,
+    _rv: v8::ReturnValue,
+) {
+    let Some(stream_id) = args.get(0).number_value(scope) else {
+        crate::error::throw_type_error(scope, "Stream ID must be a number");
+        return;
+    };
+    let state = crate::IsolateState::get(scope);
+    let streaming_fetches = state.borrow().streaming_fetches.clone();
+    streaming_fetches.borrow_mut().remove(&(stream_id as u64));
 }
 
 // Native function to read a chunk from a streaming fetch
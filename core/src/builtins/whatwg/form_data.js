@@ -0,0 +1,193 @@
+// FormData
+// https://xhr.spec.whatwg.org/#interface-formdata
+// https://html.spec.whatwg.org/multipage/form-control-infrastructure.html#multipart-form-data
+
+'use strict';
+
+// eslint-disable-next-line no-unused-expressions
+(({ formDataParseMultipart }) => {
+  // Turn a value into an entry value: a string or a File
+  function toEntryValue(value, filename, argCount) {
+    if (value instanceof Blob) {
+      if (!(value instanceof File) || filename !== undefined) {
+        const name = filename !== undefined ? String(filename) : (value instanceof File ? value.name : 'blob');
+        const options = { type: value.type };
+        if (value instanceof File) {
+          options.lastModified = value.lastModified;
+        }
+        return new File([value], name, options);
+      }
+      return value;
+    }
+    if (argCount > 2) {
+      throw new TypeError("Failed to execute 'append' on 'FormData': parameter 2 is not of type 'Blob'.");
+    }
+    return String(value);
+  }
+
+  // Escape a name or filename for a Content-Disposition header
+  function escapeName(name, isFilename) {
+    const value = isFilename ? name : name.replace(/\r\n|\r|\n/g, '\r\n');
+    return value.replace(/\n/g, '%0A').replace(/\r/g, '%0D').replace(/"/g, '%22');
+  }
+
+  // Generate a boundary that is very unlikely to appear in the body
+  function generateBoundary() {
+    const bytes = crypto.getRandomValues(new Uint8Array(12));
+    let hex = '';
+    for (const byte of bytes) {
+      hex += byte.toString(16).padStart(2, '0');
+    }
+    return `----jstimeFormBoundary${hex}`;
+  }
+
+  class FormData {
+    #entries = [];
+    #boundary = null;
+
+    constructor(form = undefined) {
+      if (form !== undefined) {
+        throw new TypeError("Failed to construct 'FormData': HTML forms are not supported.");
+      }
+    }
+
+    append(name, value, filename = undefined) {
+      if (arguments.length < 2) {
+        throw new TypeError(`Failed to execute 'append' on 'FormData': 2 arguments required, but only ${arguments.length} present.`);
+      }
+      this.#entries.push([String(name), toEntryValue(value, filename, arguments.length)]);
+    }
+
+    delete(name) {
+      const key = String(name);
+      this.#entries = this.#entries.filter(([entryName]) => entryName !== key);
+    }
+
+    get(name) {
+      const key = String(name);
+      const entry = this.#entries.find(([entryName]) => entryName === key);
+      return entry ? entry[1] : null;
+    }
+
+    getAll(name) {
+      const key = String(name);
+      return this.#entries.filter(([entryName]) => entryName === key).map(([, value]) => value);
+    }
+
+    has(name) {
+      const key = String(name);
+      return this.#entries.some(([entryName]) => entryName === key);
+    }
+
+    set(name, value, filename = undefined) {
+      if (arguments.length < 2) {
+        throw new TypeError(`Failed to execute 'set' on 'FormData': 2 arguments required, but only ${arguments.length} present.`);
+      }
+      const key = String(name);
+      const entry = [key, toEntryValue(value, filename, arguments.length)];
+      const index = this.#entries.findIndex(([entryName]) => entryName === key);
+      if (index === -1) {
+        this.#entries.push(entry);
+      } else {
+        // Replace the first match in place and drop the rest
+        this.#entries[index] = entry;
+        this.#entries = this.#entries.filter(([entryName], i) => i <= index || entryName !== key);
+      }
+    }
+
+    *entries() {
+      for (const [name, value] of this.#entries) {
+        yield [name, value];
+      }
+    }
+
+    *keys() {
+      for (const [name] of this.#entries) {
+        yield name;
+      }
+    }
+
+    *values() {
+      for (const [, value] of this.#entries) {
+        yield value;
+      }
+    }
+
+    forEach(callback, thisArg) {
+      for (const [name, value] of this.#entries) {
+        callback.call(thisArg, value, name, this);
+      }
+    }
+
+    [Symbol.iterator]() {
+      return this.entries();
+    }
+
+    get [Symbol.toStringTag]() {
+      return 'FormData';
+    }
+
+    // Internal: encode as multipart/form-data, returning { body: Blob, type }.
+    // The boundary is fixed per instance so repeated encodings agree with the
+    // Content-Type header that was derived from an earlier one.
+    _encodeMultipart() {
+      if (this.#boundary === null) {
+        this.#boundary = generateBoundary();
+      }
+      const boundary = this.#boundary;
+      const parts = [];
+      for (const [name, value] of this.#entries) {
+        let header = `--${boundary}\r\nContent-Disposition: form-data; name="${escapeName(name, false)}"`;
+        if (typeof value === 'string') {
+          parts.push(`${header}\r\n\r\n`, value.replace(/\r\n|\r|\n/g, '\r\n'), '\r\n');
+        } else {
+          header += `; filename="${escapeName(value.name, true)}"`;
+          header += `\r\nContent-Type: ${value.type || 'application/octet-stream'}\r\n\r\n`;
+          parts.push(header, value, '\r\n');
+        }
+      }
+      parts.push(`--${boundary}--\r\n`);
+      const type = `multipart/form-data; boundary=${boundary}`;
+      return { body: new Blob(parts), type };
+    }
+
+    // Internal: parse a body into a new FormData according to its Content-Type
+    static _fromBody(bytes, contentType) {
+      const formData = new FormData();
+      const [essence, ...params] = String(contentType || '').split(';');
+      const mimeType = essence.trim().toLowerCase();
+
+      if (mimeType === 'multipart/form-data') {
+        let boundary = null;
+        for (const param of params) {
+          const eq = param.indexOf('=');
+          if (eq !== -1 && param.slice(0, eq).trim().toLowerCase() === 'boundary') {
+            boundary = param.slice(eq + 1).trim().replace(/^"(.*)"$/, '$1');
+          }
+        }
+        if (!boundary) {
+          throw new TypeError('Missing boundary in multipart/form-data Content-Type');
+        }
+        for (const [name, value, filename, type] of formDataParseMultipart(bytes, boundary)) {
+          if (filename === undefined) {
+            formData.append(name, value);
+          } else {
+            formData.append(name, new File([value], filename, { type: type || 'text/plain' }));
+          }
+        }
+        return formData;
+      }
+
+      if (mimeType === 'application/x-www-form-urlencoded') {
+        for (const [name, value] of new URLSearchParams(new TextDecoder().decode(bytes))) {
+          formData.append(name, value);
+        }
+        return formData;
+      }
+
+      throw new TypeError(`Could not parse content as FormData: unsupported Content-Type '${mimeType}'`);
+    }
+  }
+
+  globalThis.FormData = FormData;
+});
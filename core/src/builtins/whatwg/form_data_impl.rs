@@ -0,0 +1,198 @@
+// multipart/form-data parsing
+// https://www.rfc-editor.org/rfc/rfc7578
+// Encoding is done in form_data.js, where the body is assembled as a Blob.
+
+pub(crate) fn get_external_references() -> Vec<v8::ExternalReference> {
+    vec![v8::ExternalReference {
+        function: v8::MapFnTo::map_fn_to(form_data_parse_multipart),
+    }]
+}
+
+pub(crate) fn register_bindings(scope: &mut v8::PinScope, bindings: v8::Local<v8::Object>) {
+    let name = v8::String::new(scope, "formDataParseMultipart").unwrap();
+    let value = v8::Function::new(scope, form_data_parse_multipart).unwrap();
+    bindings.set(scope, name.into(), value.into());
+}
+
+/// A single part of a multipart/form-data body
+struct Part<'a> {
+    name: String,
+    filename: Option<String>,
+    content_type: Option<String>,
+    body: &'a [u8],
+}
+
+#[inline]
+fn find(haystack: &[u8], needle: &[u8], from: usize) -> Option<usize> {
+    if from > haystack.len() {
+        return None;
+    }
+    haystack[from..]
+        .windows(needle.len())
+        .position(|window| window == needle)
+        .map(|pos| pos + from)
+}
+
+/// Undo the escaping browsers apply to names and filenames in Content-Disposition
+fn unescape_param(value: &str) -> String {
+    value
+        .replace("%22", "\"")
+        .replace("%0D", "\r")
+        .replace("%0A", "\n")
+}
+
+/// Get a parameter such as `name` or `filename` from a Content-Disposition value
+fn disposition_param(value: &str, param: &str) -> Option<String> {
+    let mut rest = value;
+    // Skip the disposition type ("form-data")
+    let start = rest.find(';')?;
+    rest = &rest[start + 1..];
+
+    loop {
+        rest = rest.trim_start();
+        let eq = rest.find('=')?;
+        let key = rest[..eq].trim();
+        rest = &rest[eq + 1..];
+
+        let (val, remaining) = if let Some(quoted) = rest.strip_prefix('"') {
+            let end = quoted.find('"')?;
+            (&quoted[..end], &quoted[end + 1..])
+        } else {
+            let end = rest.find(';').unwrap_or(rest.len());
+            (rest[..end].trim(), &rest[end..])
+        };
+
+        if key.eq_ignore_ascii_case(param) {
+            return Some(unescape_param(val));
+        }
+
+        rest = remaining.trim_start();
+        rest = rest.strip_prefix(';')?;
+    }
+}
+
+/// Split a multipart/form-data body into its parts
+fn parse_multipart<'a>(body: &'a [u8], boundary: &str) -> Result<Vec<Part<'a>>, &'static str> {
+    if boundary.is_empty() || boundary.len() > 70 {
+        return Err("Invalid multipart boundary");
+    }
+    let delimiter = format!("--{}", boundary).into_bytes();
+    let close_delimiter = format!("\r\n--{}", boundary).into_bytes();
+
+    // The first delimiter may follow a preamble, which is ignored
+    let mut pos = match find(body, &delimiter, 0) {
+        Some(start) => start + delimiter.len(),
+        None => return Err("Missing multipart boundary"),
+    };
+
+    let mut parts = Vec::new();
+    loop {
+        // "--" after a delimiter ends the body
+        if body[pos..].starts_with(b"--") {
+            return Ok(parts);
+        }
+        // Skip transport padding, then the line break ending the delimiter
+        while pos < body.len() && (body[pos] == b' ' || body[pos] == b'\t') {
+            pos += 1;
+        }
+        if !body[pos..].starts_with(b"\r\n") {
+            return Err("Malformed multipart delimiter");
+        }
+        pos += 2;
+
+        // Headers end with an empty line
+        let (headers_end, body_start) = if body[pos..].starts_with(b"\r\n") {
+            (pos, pos + 2)
+        } else {
+            let end = find(body, b"\r\n\r\n", pos).ok_or("Malformed multipart headers")?;
+            (end, end + 4)
+        };
+        let headers = std::str::from_utf8(&body[pos..headers_end])
+            .map_err(|_| "Multipart headers must be UTF-8")?;
+
+        let mut name = None;
+        let mut filename = None;
+        let mut content_type = None;
+        for line in headers.split("\r\n") {
+            let Some((header, value)) = line.split_once(':') else {
+                continue;
+            };
+            let value = value.trim();
+            if header.trim().eq_ignore_ascii_case("content-disposition") {
+                name = disposition_param(value, "name");
+                filename = disposition_param(value, "filename");
+            } else if header.trim().eq_ignore_ascii_case("content-type") {
+                content_type = Some(value.to_string());
+            }
+        }
+        let name = name.ok_or("Multipart part is missing a name")?;
+
+        let body_end =
+            find(body, &close_delimiter, body_start).ok_or("Unterminated multipart body")?;
+        parts.push(Part {
+            name,
+            filename,
+            content_type,
+            body: &body[body_start..body_end],
+        });
+        pos = body_end + close_delimiter.len();
+    }
+}
+
+// formDataParseMultipart(bytes, boundary) - returns an array of entries:
+// [name, value] for fields and [name, bytes, filename, contentType] for files
+fn form_data_parse_multipart(
+    scope: &mut v8::PinScope,
+    args: v8::FunctionCallbackArguments,
+    mut rv: v8::ReturnValue,
+) {
+    let bytes = match crate::error::try_get_buffer_source_bytes(args.get(0)) {
+        Ok(bytes) => bytes,
+        Err(msg) => {
+            crate::error::throw_type_error(scope, msg);
+            return;
+        }
+    };
+    let Some(boundary) = crate::error::to_rust_string_or_throw(scope, args.get(1), "boundary")
+    else {
+        return;
+    };
+
+    let parts = match parse_multipart(&bytes, &boundary) {
+        Ok(parts) => parts,
+        Err(msg) => {
+            crate::error::throw_type_error(scope, msg);
+            return;
+        }
+    };
+
+    let entries = v8::Array::new(scope, parts.len() as i32);
+    for (i, part) in parts.into_iter().enumerate() {
+        let name = v8::String::new(scope, &part.name).unwrap();
+        let entry = match part.filename {
+            Some(filename) => {
+                let len = part.body.len();
+                let backing_store =
+                    v8::ArrayBuffer::new_backing_store_from_vec(part.body.to_vec()).make_shared();
+                let buffer = v8::ArrayBuffer::with_backing_store(scope, &backing_store);
+                let data = v8::Uint8Array::new(scope, buffer, 0, len).unwrap();
+                let filename = v8::String::new(scope, &filename).unwrap();
+                let content_type: v8::Local<v8::Value> = match &part.content_type {
+                    Some(content_type) => v8::String::new(scope, content_type).unwrap().into(),
+                    None => v8::undefined(scope).into(),
+                };
+                v8::Array::new_with_elements(
+                    scope,
+                    &[name.into(), data.into(), filename.into(), content_type],
+                )
+            }
+            None => {
+                let value = String::from_utf8_lossy(part.body);
+                let value = v8::String::new(scope, &value).unwrap();
+                v8::Array::new_with_elements(scope, &[name.into(), value.into()])
+            }
+        };
+        entries.set_index(scope, i as u32, entry.into());
+    }
+    rv.set(entries.into());
+}
@@ -12,8 +12,14 @@
     #queue = [];
     #error = null;
     #controller = null;
+    #underlyingSource = null;
+    #pendingReads = []; // Reads waiting for the underlying source to enqueue
+    #pulling = false;
+    #pullAgain = false;
 
     constructor(underlyingSource = {}, strategy = {}) {
+      this.#underlyingSource = underlyingSource;
+
       const controller = {
         enqueue: (chunk) => {
           if (this.#state === 'closed') {
@@ -22,6 +28,11 @@
           if (this.#state === 'errored') {
             throw new TypeError('Cannot enqueue on an errored stream');
           }
+          // Hand the chunk straight to a waiting read, if any
+          if (this.#pendingReads.length > 0) {
+            this.#pendingReads.shift().resolve({ value: chunk, done: false });
+            return;
+          }
           this.#queue.push(chunk);
         },
         close: () => {
@@ -32,6 +43,12 @@
             throw new TypeError('Cannot close an errored stream');
           }
           this.#state = 'closed';
+          // Pending reads only exist while the queue is empty
+          const pending = this.#pendingReads;
+          this.#pendingReads = [];
+          for (const read of pending) {
+            read.resolve({ value: undefined, done: true });
+          }
         },
         error: (e) => {
           if (this.#state === 'closed' || this.#state === 'errored') {
@@ -40,6 +57,11 @@
           this.#error = e;
           this.#state = 'errored';
           this.#queue = [];
+          const pending = this.#pendingReads;
+          this.#pendingReads = [];
+          for (const read of pending) {
+            read.reject(e);
+          }
         }
       };
 
@@ -76,8 +98,34 @@
       if (this.#reader !== null) {
         return Promise.reject(new TypeError('Cannot cancel a locked stream'));
       }
+      return this._cancel(reason);
+    }
+
+    // Internal method shared by cancel() and the reader's cancel()
+    _cancel(reason) {
+      if (this.#state === 'closed') {
+        return Promise.resolve();
+      }
+      if (this.#state === 'errored') {
+        return Promise.reject(this.#error);
+      }
       this.#state = 'closed';
       this.#queue = [];
+      const pending = this.#pendingReads;
+      this.#pendingReads = [];
+      for (const read of pending) {
+        read.resolve({ value: undefined, done: true });
+      }
+
+      // Let the underlying source release its resources
+      if (typeof this.#underlyingSource.cancel === 'function') {
+        try {
+          const result = this.#underlyingSource.cancel.call(this.#underlyingSource, reason);
+          return Promise.resolve(result).then(() => undefined);
+        } catch (e) {
+          return Promise.reject(e);
+        }
+      }
       return Promise.resolve();
     }
 
@@ -95,6 +143,40 @@
       return null; // No chunks available yet
     }
 
+    // Internal method for reader to wait for the next chunk when the queue is empty.
+    // Calls the underlying source's pull() and resolves once it enqueues or closes.
+    _pullFromSource() {
+      if (typeof this.#underlyingSource.pull !== 'function') {
+        // Sources without pull() have nothing more to deliver
+        return Promise.resolve({ value: undefined, done: true });
+      }
+      return new Promise((resolve, reject) => {
+        this.#pendingReads.push({ resolve, reject });
+        this.#callPull();
+      });
+    }
+
+    #callPull() {
+      if (this.#pulling) {
+        this.#pullAgain = true;
+        return;
+      }
+      this.#pulling = true;
+      Promise.resolve()
+        .then(() => this.#underlyingSource.pull.call(this.#underlyingSource, this.#controller))
+        .then(() => {
+          this.#pulling = false;
+          const pullAgain = this.#pullAgain;
+          this.#pullAgain = false;
+          if (pullAgain && this.#pendingReads.length > 0 && this.#state === 'readable') {
+            this.#callPull();
+          }
+        }, (e) => {
+          this.#pulling = false;
+          this.#controller.error(e);
+        });
+    }
+
     // Internal method to release reader
     _releaseReader() {
       this.#reader = null;
@@ -140,9 +222,8 @@
         return Promise.reject(this.#stream._getError());
       }
 
-      // No chunks available, return a pending promise
-      // In a full implementation, this would wait for chunks
-      return Promise.resolve({ value: undefined, done: true });
+      // No chunks available yet, ask the underlying source for more
+      return this.#stream._pullFromSource();
     }
 
     releaseLock() {
@@ -169,7 +250,7 @@
         return Promise.resolve();
       }
       this.#closed = true;
-      return this.#stream.cancel(reason);
+      return this.#stream._cancel(reason);
     }
   }
 
@@ -59,6 +59,9 @@ fn structured_clone(
 
 struct StructuredCloneDelegate;
 
+// Tags written before each host object so the deserializer knows what to create
+const HOST_OBJECT_BLOB: u32 = 1;
+
 impl v8::ValueSerializerImpl for StructuredCloneDelegate {
     fn throw_data_clone_error<'s>(
         &self,
@@ -68,15 +71,50 @@ impl v8::ValueSerializerImpl for StructuredCloneDelegate {
         let exception = v8::Exception::error(scope, message);
         scope.throw_exception(exception);
     }
+
+    fn has_custom_host_object(&self, _isolate: &v8::Isolate) -> bool {
+        true
+    }
+
+    fn is_host_object<'s>(
+        &self,
+        scope: &mut v8::PinScope<'s, '_>,
+        object: v8::Local<'s, v8::Object>,
+    ) -> Option<bool> {
+        Some(crate::builtins::w3c::blob_impl::is_blob(scope, object))
+    }
+
+    fn write_host_object<'s>(
+        &self,
+        scope: &mut v8::PinScope<'s, '_>,
+        object: v8::Local<'s, v8::Object>,
+        value_serializer: &dyn v8::ValueSerializerHelper,
+    ) -> Option<bool> {
+        value_serializer.write_uint32(HOST_OBJECT_BLOB);
+        if crate::builtins::w3c::blob_impl::write_blob(scope, object, value_serializer) {
+            Some(true)
+        } else {
+            crate::error::throw_error(scope, "Host object could not be cloned");
+            None
+        }
+    }
 }
 
 impl v8::ValueDeserializerImpl for StructuredCloneDelegate {
     fn read_host_object<'s>(
         &self,
         scope: &mut v8::PinScope<'s, '_>,
-        _value_deserializer: &dyn v8::ValueDeserializerHelper,
+        value_deserializer: &dyn v8::ValueDeserializerHelper,
     ) -> Option<v8::Local<'s, v8::Object>> {
-        crate::error::throw_error(scope, "Host objects are not supported in structuredClone");
+        let mut tag = 0u32;
+        if value_deserializer.read_uint32(&mut tag) && tag == HOST_OBJECT_BLOB {
+            if let Some(blob) =
+                crate::builtins::w3c::blob_impl::read_blob(scope, value_deserializer)
+            {
+                return Some(blob);
+            }
+        }
+        crate::error::throw_error(scope, "Host object could not be deserialized");
         None
     }
 }
@@ -48,6 +48,11 @@ fn set_timeout(
         delay_obj.integer_value(scope).unwrap_or(0).max(0) as u64
     };
 
+    // Internal callers (e.g. AbortSignal.timeout) pass `false` so the timer
+    // doesn't keep the event loop alive on its own
+    let is_ref_obj = args.get(2);
+    let is_ref = is_ref_obj.is_undefined() || is_ref_obj.boolean_value(scope);
+
     let callback_global = v8::Global::new(scope, callback);
     let state = crate::IsolateState::get(scope);
     let state_borrow = state.borrow();
@@ -68,6 +73,7 @@ fn set_timeout(
             id: timer_id,
             callback: callback_global,
             delay_ms,
+            is_ref,
         });
     // Return the timer ID as a number
     let timer_id_value = v8::Number::new(scope, timer_id.0 as f64);
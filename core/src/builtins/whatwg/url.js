@@ -125,8 +125,15 @@
         const search = init.startsWith('?') ? init.slice(1) : init;
         if (search) {
           search.split('&').forEach(pair => {
-            const [key, value = ''] = pair.split('=').map(decodeURIComponent);
-            this.append(key, value);
+            if (!pair) {
+              return;
+            }
+            // application/x-www-form-urlencoded: '+' is a space and only the first '=' splits
+            const eq = pair.indexOf('=');
+            const rawKey = eq === -1 ? pair : pair.slice(0, eq);
+            const rawValue = eq === -1 ? '' : pair.slice(eq + 1);
+            const decode = (part) => decodeURIComponent(part.replace(/\+/g, ' '));
+            this.append(decode(rawKey), decode(rawValue));
           });
         }
       } else if (init && typeof init === 'object') {
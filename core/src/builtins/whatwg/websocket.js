@@ -0,0 +1,391 @@
+// WebSocket
+// https://websockets.spec.whatwg.org/
+// Connections are handled natively; this file implements the WebSocket, MessageEvent and CloseEvent interfaces.
+
+'use strict';
+
+// eslint-disable-next-line no-unused-expressions
+(({ wsConnect, wsSend, wsClose, wsBufferedAmount }) => {
+  const CONNECTING = 0;
+  const OPEN = 1;
+  const CLOSING = 2;
+  const CLOSED = 3;
+
+  // Token characters allowed in a subprotocol name (RFC 7230)
+  const TOKEN_PATTERN = /^[!#$%&'*+\-.^_`|~0-9A-Za-z]+$/;
+
+  // Set while WebSocket._accept() constructs the server side of a connection
+  let accepting = false;
+
+  // https://html.spec.whatwg.org/multipage/comms.html#messageevent
+  class MessageEvent extends Event {
+    #data;
+    #origin;
+    #lastEventId;
+    #source;
+    #ports;
+
+    constructor(type, eventInitDict = {}) {
+      super(type, eventInitDict);
+      this.#data = eventInitDict.data === undefined ? null : eventInitDict.data;
+      this.#origin = eventInitDict.origin === undefined ? '' : String(eventInitDict.origin);
+      this.#lastEventId = eventInitDict.lastEventId === undefined ? '' : String(eventInitDict.lastEventId);
+      this.#source = eventInitDict.source === undefined ? null : eventInitDict.source;
+      this.#ports = Object.freeze(eventInitDict.ports === undefined ? [] : [...eventInitDict.ports]);
+    }
+
+    get data() {
+      return this.#data;
+    }
+
+    get origin() {
+      return this.#origin;
+    }
+
+    get lastEventId() {
+      return this.#lastEventId;
+    }
+
+    get source() {
+      return this.#source;
+    }
+
+    get ports() {
+      return this.#ports;
+    }
+
+    get [Symbol.toStringTag]() {
+      return 'MessageEvent';
+    }
+  }
+
+  // https://websockets.spec.whatwg.org/#the-closeevent-interface
+  class CloseEvent extends Event {
+    #wasClean;
+    #code;
+    #reason;
+
+    constructor(type, eventInitDict = {}) {
+      super(type, eventInitDict);
+      this.#wasClean = Boolean(eventInitDict.wasClean);
+      this.#code = eventInitDict.code === undefined ? 0 : Number(eventInitDict.code) & 0xffff;
+      this.#reason = eventInitDict.reason === undefined ? '' : String(eventInitDict.reason);
+    }
+
+    get wasClean() {
+      return this.#wasClean;
+    }
+
+    get code() {
+      return this.#code;
+    }
+
+    get reason() {
+      return this.#reason;
+    }
+
+    get [Symbol.toStringTag]() {
+      return 'CloseEvent';
+    }
+  }
+
+  // Parse and validate a WebSocket URL, mapping http(s) to ws(s)
+  function parseURL(url) {
+    let parsed;
+    try {
+      parsed = new URL(String(url));
+    } catch {
+      throw new DOMException(`Failed to construct 'WebSocket': The URL '${url}' is invalid.`, 'SyntaxError');
+    }
+    if (parsed.protocol === 'http:') {
+      parsed.protocol = 'ws:';
+    } else if (parsed.protocol === 'https:') {
+      parsed.protocol = 'wss:';
+    }
+    if (parsed.protocol !== 'ws:' && parsed.protocol !== 'wss:') {
+      throw new DOMException(
+        `Failed to construct 'WebSocket': The URL's scheme must be either 'http', 'https', 'ws', or 'wss'. '${parsed.protocol}' is not allowed.`,
+        'SyntaxError');
+    }
+    if (parsed.hash !== '' || String(url).includes('#')) {
+      throw new DOMException(
+        `Failed to construct 'WebSocket': The URL contains a fragment identifier ('${parsed.hash}'). Fragment identifiers are not allowed in WebSocket URLs.`,
+        'SyntaxError');
+    }
+    return parsed;
+  }
+
+  // Validate the protocols argument and return it as an array
+  function parseProtocols(protocols) {
+    const list = typeof protocols === 'string' ? [protocols] : [...protocols].map(String);
+    const seen = new Set();
+    for (const protocol of list) {
+      if (!TOKEN_PATTERN.test(protocol)) {
+        throw new DOMException(`Failed to construct 'WebSocket': The subprotocol '${protocol}' is invalid.`, 'SyntaxError');
+      }
+      if (seen.has(protocol)) {
+        throw new DOMException(`Failed to construct 'WebSocket': The subprotocol '${protocol}' is duplicated.`, 'SyntaxError');
+      }
+      seen.add(protocol);
+    }
+    return list;
+  }
+
+  // Convert data passed to send() into a string or bytes, returning its size too
+  function toPayload(data) {
+    if (data instanceof ArrayBuffer) {
+      return [new Uint8Array(data.slice(0)), data.byteLength];
+    }
+    if (ArrayBuffer.isView(data)) {
+      return [new Uint8Array(data.buffer.slice(data.byteOffset, data.byteOffset + data.byteLength)), data.byteLength];
+    }
+    const text = String(data);
+    return [text, new TextEncoder().encode(text).byteLength];
+  }
+
+  // https://websockets.spec.whatwg.org/#the-websocket-interface
+  class WebSocket extends EventTarget {
+    #id;
+    #url;
+    #readyState = CONNECTING;
+    #protocol = '';
+    #extensions = '';
+    #binaryType = 'blob';
+    // Bytes of Blobs being read, and of messages sent after closing
+    #pendingBytes = 0;
+    // Sends wait for earlier Blobs to be read, to keep messages in order
+    #sendChain = null;
+    #onopen = null;
+    #onmessage = null;
+    #onerror = null;
+    #onclose = null;
+
+    constructor(url, protocols = []) {
+      super();
+      const parsed = parseURL(url);
+      const protocolList = parseProtocols(protocols);
+      this.#url = parsed.href;
+      if (accepting) {
+        // jstime.serve attaches the connection once the handshake response is sent
+        return;
+      }
+
+      const secure = parsed.protocol === 'wss:';
+      const host = parsed.hostname.replace(/^\[(.*)\]$/, '$1');
+      const port = parsed.port === '' ? (secure ? 443 : 80) : Number(parsed.port);
+      const resource = (parsed.pathname || '/') + parsed.search;
+      this.#id = wsConnect(host, port, resource, secure, protocolList, (event, ...data) => this.#onEvent(event, data));
+    }
+
+    get url() {
+      return this.#url;
+    }
+
+    get readyState() {
+      return this.#readyState;
+    }
+
+    get bufferedAmount() {
+      return (this.#id === undefined ? 0 : wsBufferedAmount(this.#id)) + this.#pendingBytes;
+    }
+
+    get protocol() {
+      return this.#protocol;
+    }
+
+    get extensions() {
+      return this.#extensions;
+    }
+
+    get binaryType() {
+      return this.#binaryType;
+    }
+
+    set binaryType(value) {
+      // Invalid values are ignored
+      if (value === 'blob' || value === 'arraybuffer') {
+        this.#binaryType = value;
+      }
+    }
+
+    /**
+     * Send a message. Strings are sent as text; Blobs, ArrayBuffers and views as binary.
+     * @param {string|Blob|ArrayBuffer|ArrayBufferView} data
+     */
+    send(data) {
+      if (this.#readyState === CONNECTING) {
+        throw new DOMException("Failed to execute 'send' on 'WebSocket': Still in CONNECTING state.", 'InvalidStateError');
+      }
+
+      const isBlob = typeof Blob !== 'undefined' && data instanceof Blob;
+      if (this.#readyState !== OPEN) {
+        // Messages sent after closing are dropped but still count as buffered
+        this.#pendingBytes += isBlob ? data.size : toPayload(data)[1];
+        return;
+      }
+
+      if (isBlob) {
+        const size = data.size;
+        this.#pendingBytes += size;
+        this.#enqueue(data.arrayBuffer().then((buffer) => {
+          this.#pendingBytes -= size;
+          return new Uint8Array(buffer);
+        }));
+        return;
+      }
+
+      const [payload] = toPayload(data);
+      if (this.#sendChain) {
+        this.#enqueue(Promise.resolve(payload));
+      } else {
+        wsSend(this.#id, payload);
+      }
+    }
+
+    // Send a payload once every earlier message has been sent
+    #enqueue(payloadPromise) {
+      const chain = (this.#sendChain || Promise.resolve())
+        .then(() => payloadPromise)
+        .then((payload) => wsSend(this.#id, payload));
+      this.#sendChain = chain;
+      chain.then(() => {
+        if (this.#sendChain === chain) {
+          this.#sendChain = null;
+        }
+      });
+    }
+
+    /**
+     * Start closing the connection
+     * @param {number} [code] - 1000, or 3000 to 4999
+     * @param {string} [reason] - At most 123 bytes of UTF-8
+     */
+    close(code = undefined, reason = undefined) {
+      if (code !== undefined) {
+        code = Number(code) & 0xffff;
+        if (code !== 1000 && (code < 3000 || code > 4999)) {
+          throw new DOMException(
+            `Failed to execute 'close' on 'WebSocket': The close code must be either 1000, or between 3000 and 4999. ${code} is neither.`,
+            'InvalidAccessError');
+        }
+      }
+      reason = reason === undefined ? '' : String(reason);
+      if (new TextEncoder().encode(reason).byteLength > 123) {
+        throw new DOMException("Failed to execute 'close' on 'WebSocket': The close reason must not be greater than 123 UTF-8 bytes.", 'SyntaxError');
+      }
+
+      if (this.#readyState === CLOSING || this.#readyState === CLOSED) {
+        return;
+      }
+      this.#readyState = CLOSING;
+      if (this.#id !== undefined) {
+        // A reason can only be sent along with a code
+        wsClose(this.#id, code === undefined && reason !== '' ? 1000 : code, reason);
+      }
+    }
+
+    // Internal: create the server side of a connection being upgraded by jstime.serve
+    static _accept(url) {
+      accepting = true;
+      try {
+        return new WebSocket(url);
+      } finally {
+        accepting = false;
+      }
+    }
+
+    // Internal: connect a socket from _accept(). `attach` is called with the
+    // event callback and returns the WebSocket ID.
+    _attach(attach) {
+      this.#id = attach((event, ...data) => this.#onEvent(event, data));
+      if (this.#readyState === CLOSING) {
+        // Closed before the response was sent, which fails the connection
+        wsClose(this.#id, undefined, '');
+      }
+    }
+
+    // Handle an event from the event loop
+    #onEvent(event, data) {
+      switch (event) {
+        case 'open':
+          this.#readyState = OPEN;
+          this.#protocol = data[0];
+          this.#extensions = data[1];
+          this.dispatchEvent(new Event('open'));
+          break;
+        case 'message': {
+          let message = data[0];
+          if (typeof message !== 'string') {
+            message = this.#binaryType === 'arraybuffer' ? message.buffer : new Blob([message]);
+          }
+          this.dispatchEvent(new MessageEvent('message', { data: message, origin: new URL(this.#url).origin }));
+          break;
+        }
+        case 'error':
+          this.dispatchEvent(new Event('error'));
+          break;
+        case 'close':
+          this.#readyState = CLOSED;
+          this.dispatchEvent(new CloseEvent('close', { code: data[0], reason: data[1], wasClean: data[2] }));
+          break;
+      }
+    }
+
+    get onopen() {
+      return this.#onopen;
+    }
+
+    set onopen(handler) {
+      this.#onopen = this.#setHandler('open', this.#onopen, handler);
+    }
+
+    get onmessage() {
+      return this.#onmessage;
+    }
+
+    set onmessage(handler) {
+      this.#onmessage = this.#setHandler('message', this.#onmessage, handler);
+    }
+
+    get onerror() {
+      return this.#onerror;
+    }
+
+    set onerror(handler) {
+      this.#onerror = this.#setHandler('error', this.#onerror, handler);
+    }
+
+    get onclose() {
+      return this.#onclose;
+    }
+
+    set onclose(handler) {
+      this.#onclose = this.#setHandler('close', this.#onclose, handler);
+    }
+
+    // Replace an event handler attribute's listener
+    #setHandler(type, previous, handler) {
+      if (previous !== null) {
+        this.removeEventListener(type, previous);
+      }
+      const next = typeof handler === 'function' ? handler : null;
+      if (next !== null) {
+        this.addEventListener(type, next);
+      }
+      return next;
+    }
+
+    get [Symbol.toStringTag]() {
+      return 'WebSocket';
+    }
+  }
+
+  for (const [name, value] of Object.entries({ CONNECTING, OPEN, CLOSING, CLOSED })) {
+    Object.defineProperty(WebSocket, name, { value, enumerable: true });
+    Object.defineProperty(WebSocket.prototype, name, { value, enumerable: true });
+  }
+
+  globalThis.WebSocket = WebSocket;
+  globalThis.MessageEvent = MessageEvent;
+  globalThis.CloseEvent = CloseEvent;
+});
@@ -0,0 +1,231 @@
+// WebSocket API
+// Connections live in crate::websocket and are polled by the event loop.
+
+use crate::websocket::ActiveWebSocket;
+
+pub(crate) fn get_external_references() -> Vec<v8::ExternalReference> {
+    vec![
+        v8::ExternalReference {
+            function: v8::MapFnTo::map_fn_to(ws_connect),
+        },
+        v8::ExternalReference {
+            function: v8::MapFnTo::map_fn_to(ws_send),
+        },
+        v8::ExternalReference {
+            function: v8::MapFnTo::map_fn_to(ws_close),
+        },
+        v8::ExternalReference {
+            function: v8::MapFnTo::map_fn_to(ws_buffered_amount),
+        },
+    ]
+}
+
+pub(crate) fn register_bindings(scope: &mut v8::PinScope, bindings: v8::Local<v8::Object>) {
+    let name = v8::String::new(scope, "wsConnect").unwrap();
+    let value = v8::Function::new(scope, ws_connect).unwrap();
+    bindings.set(scope, name.into(), value.into());
+
+    let name = v8::String::new(scope, "wsSend").unwrap();
+    let value = v8::Function::new(scope, ws_send).unwrap();
+    bindings.set(scope, name.into(), value.into());
+
+    let name = v8::String::new(scope, "wsClose").unwrap();
+    let value = v8::Function::new(scope, ws_close).unwrap();
+    bindings.set(scope, name.into(), value.into());
+
+    let name = v8::String::new(scope, "wsBufferedAmount").unwrap();
+    let value = v8::Function::new(scope, ws_buffered_amount).unwrap();
+    bindings.set(scope, name.into(), value.into());
+}
+
+/// Read a numeric WebSocket ID argument, throwing if it is not a number
+#[inline]
+fn get_id(scope: &mut v8::PinScope, value: v8::Local<v8::Value>) -> Option<u64> {
+    if value.is_number() {
+        Some(value.number_value(scope).unwrap() as u64)
+    } else {
+        crate::error::throw_type_error(scope, "WebSocket ID must be a number");
+        None
+    }
+}
+
+/// Open a WebSocket connection
+/// Args: host, port, resource (path and query), secure, protocols (array of strings),
+/// callback (called with (event, ...data) by the event loop)
+/// Returns: WebSocket ID
+#[inline]
+fn ws_connect(
+    scope: &mut v8::PinScope,
+    args: v8::FunctionCallbackArguments,
+    mut retval: v8::ReturnValue,
+) {
+    if !crate::error::check_arg_count(scope, &args, 6, "wsConnect") {
+        return;
+    }
+
+    let Some(host) = crate::error::to_rust_string_or_throw(scope, args.get(0), "host") else {
+        return;
+    };
+    let port = args.get(1).number_value(scope).unwrap_or(0.0) as u16;
+    let Some(resource) = crate::error::to_rust_string_or_throw(scope, args.get(2), "resource")
+    else {
+        return;
+    };
+    let secure = args.get(3).boolean_value(scope);
+    let protocols_array = match crate::error::try_get_array_result(args.get(4)) {
+        Ok(array) => array,
+        Err(msg) => {
+            crate::error::throw_type_error(scope, msg);
+            return;
+        }
+    };
+    let mut protocols = Vec::with_capacity(protocols_array.length() as usize);
+    for i in 0..protocols_array.length() {
+        let Some(item) = protocols_array.get_index(scope, i) else {
+            return;
+        };
+        let Some(protocol) = crate::error::to_rust_string_or_throw(scope, item, "protocol") else {
+            return;
+        };
+        protocols.push(protocol);
+    }
+    let callback = match crate::error::try_get_function_result(args.get(5)) {
+        Ok(callback) => v8::Global::new(scope, callback),
+        Err(msg) => {
+            crate::error::throw_type_error(scope, msg);
+            return;
+        }
+    };
+
+    let websocket =
+        match ActiveWebSocket::connect(&host, port, &resource, secure, protocols, callback) {
+            Ok(websocket) => websocket,
+            Err(e) => {
+                crate::error::throw_io_error(scope, &e);
+                return;
+            }
+        };
+
+    let isolate: &mut v8::Isolate = scope;
+    let state = crate::IsolateState::get(isolate);
+    let id = {
+        let state = state.borrow();
+        let mut next_id = state.next_tcp_id.borrow_mut();
+        let id = *next_id;
+        *next_id += 1;
+        id
+    };
+    state
+        .borrow()
+        .active_net
+        .borrow_mut()
+        .websockets
+        .insert(id, websocket);
+
+    let result = v8::Number::new(scope, id as f64);
+    retval.set(result.into());
+}
+
+/// Send a message. Strings are sent as text, BufferSources as binary.
+/// Args: id, data
+#[inline]
+fn ws_send(
+    scope: &mut v8::PinScope,
+    args: v8::FunctionCallbackArguments,
+    _retval: v8::ReturnValue,
+) {
+    if !crate::error::check_arg_count(scope, &args, 2, "wsSend") {
+        return;
+    }
+
+    let Some(id) = get_id(scope, args.get(0)) else {
+        return;
+    };
+    let data = args.get(1);
+    let (text, payload) = if data.is_string() {
+        let Some(text) = crate::error::to_rust_string_or_throw(scope, data, "data") else {
+            return;
+        };
+        (true, text.into_bytes())
+    } else {
+        match crate::error::try_get_buffer_source_bytes(data) {
+            Ok(bytes) => (false, bytes),
+            Err(msg) => {
+                crate::error::throw_type_error(scope, msg);
+                return;
+            }
+        }
+    };
+
+    let isolate: &mut v8::Isolate = scope;
+    let state = crate::IsolateState::get(isolate);
+    let state = state.borrow();
+    let mut net = state.active_net.borrow_mut();
+    // Closed connections drop messages; write errors are reported by the event loop
+    if let Some(websocket) = net.websockets.get_mut(&id) {
+        let _ = websocket.send(text, &payload);
+    }
+}
+
+/// Start the closing handshake
+/// Args: id, code (number or undefined), reason
+#[inline]
+fn ws_close(
+    scope: &mut v8::PinScope,
+    args: v8::FunctionCallbackArguments,
+    _retval: v8::ReturnValue,
+) {
+    if !crate::error::check_arg_count(scope, &args, 3, "wsClose") {
+        return;
+    }
+
+    let Some(id) = get_id(scope, args.get(0)) else {
+        return;
+    };
+    let code = if args.get(1).is_undefined() {
+        None
+    } else {
+        Some(args.get(1).number_value(scope).unwrap_or(0.0) as u16)
+    };
+    let Some(reason) = crate::error::to_rust_string_or_throw(scope, args.get(2), "reason") else {
+        return;
+    };
+
+    let isolate: &mut v8::Isolate = scope;
+    let state = crate::IsolateState::get(isolate);
+    let state = state.borrow();
+    let mut net = state.active_net.borrow_mut();
+    if let Some(websocket) = net.websockets.get_mut(&id) {
+        websocket.close(code, &reason);
+    }
+}
+
+/// Get the number of bytes queued but not yet sent
+/// Args: id
+/// Returns: number (0 once the connection is closed)
+#[inline]
+fn ws_buffered_amount(
+    scope: &mut v8::PinScope,
+    args: v8::FunctionCallbackArguments,
+    mut retval: v8::ReturnValue,
+) {
+    if !crate::error::check_arg_count(scope, &args, 1, "wsBufferedAmount") {
+        return;
+    }
+
+    let Some(id) = get_id(scope, args.get(0)) else {
+        return;
+    };
+
+    let amount = {
+        let isolate: &mut v8::Isolate = scope;
+        let state = crate::IsolateState::get(isolate);
+        let state = state.borrow();
+        let net = state.active_net.borrow();
+        net.websockets
+            .get(&id)
+            .map_or(0, ActiveWebSocket::buffered_amount)
+    };
+    let result = v8::Number::new(scope, amount as f64);
+    retval.set(result.into());
+}
@@ -0,0 +1,106 @@
+//! On-disk cache of the code V8 compiles for modules and script files.
+//!
+//! Each file has one entry, `<cache dir>/code/<SHA-256 of the path>`, holding
+//! a header line followed by V8's code cache. The header records what the
+//! code was compiled from: the V8 version, the file's modification time and
+//! a hash of the source, so a changed file or a new V8 misses the cache and
+//! overwrites the entry. V8 itself rejects code caches that don't fit the
+//! source or its flags; those are replaced the same way.
+
+use crate::http_cache::{sha256_hex, write_atomic};
+use std::path::{Path, PathBuf};
+use std::time::UNIX_EPOCH;
+
+pub(crate) struct CodeCache {
+    dir: PathBuf,
+}
+
+impl CodeCache {
+    pub(crate) fn new(cache_dir: &Path) -> Self {
+        Self {
+            dir: cache_dir.join("code"),
+        }
+    }
+
+    /// The code cache for `source`, compiled from the file or URL at `path`,
+    /// if one was stored for the same source and V8 version
+    pub(crate) fn load(&self, path: &str, source: &str) -> Option<Vec<u8>> {
+        let contents = std::fs::read(self.entry_path(path)).ok()?;
+        let data = contents.strip_prefix(header(path, source).as_bytes())?;
+        Some(data.to_vec())
+    }
+
+    /// Store the code cache for `source`, replacing any entry for `path`
+    pub(crate) fn store(&self, path: &str, source: &str, data: &[u8]) {
+        let mut contents = header(path, source).into_bytes();
+        contents.extend_from_slice(data);
+        // A cache that can't be written only costs a compile next time
+        let _ = write_atomic(&self.entry_path(path), &contents);
+    }
+
+    fn entry_path(&self, path: &str) -> PathBuf {
+        self.dir.join(sha256_hex(path.as_bytes()))
+    }
+}
+
+/// What an entry was compiled from. Files that don't exist on disk, like
+/// remote modules, are told apart by the hash of their source alone.
+fn header(path: &str, source: &str) -> String {
+    let modified = std::fs::metadata(path)
+        .and_then(|metadata| metadata.modified())
+        .ok()
+        .and_then(|modified| modified.duration_since(UNIX_EPOCH).ok())
+        .map_or(0, |modified| modified.as_nanos());
+    format!(
+        "jstime-code-cache v8/{} {} {}\n",
+        v8::V8::get_version(),
+        modified,
+        sha256_hex(source.as_bytes())
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_store_and_load() {
+        let dir = std::env::temp_dir().join("jstime_test_code_cache_store");
+        let _ = std::fs::remove_dir_all(&dir);
+        let cache = CodeCache::new(&dir);
+        let path = "https://example.com/mod.js";
+
+        assert_eq!(cache.load(path, "export default 1;"), None);
+        cache.store(path, "export default 1;", b"code");
+        assert_eq!(cache.load(path, "export default 1;").unwrap(), b"code");
+
+        // A different source misses, and replaces the entry
+        assert_eq!(cache.load(path, "export default 2;"), None);
+        cache.store(path, "export default 2;", b"other");
+        assert_eq!(cache.load(path, "export default 1;"), None);
+        assert_eq!(cache.load(path, "export default 2;").unwrap(), b"other");
+        assert_eq!(std::fs::read_dir(dir.join("code")).unwrap().count(), 1);
+
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn test_modified_file_misses() {
+        let dir = std::env::temp_dir().join("jstime_test_code_cache_mtime");
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(&dir).unwrap();
+        let cache = CodeCache::new(&dir.join("cache"));
+        let file = dir.join("mod.js");
+        let path = file.to_str().unwrap();
+
+        std::fs::write(&file, "export default 1;").unwrap();
+        filetime::set_file_mtime(&file, filetime::FileTime::from_unix_time(1_000_000, 0)).unwrap();
+        cache.store(path, "export default 1;", b"code");
+        assert_eq!(cache.load(path, "export default 1;").unwrap(), b"code");
+
+        filetime::set_file_mtime(&file, filetime::FileTime::from_unix_time(2_000_000, 0)).unwrap();
+        assert_eq!(cache.load(path, "export default 1;"), None);
+
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+}
@@ -0,0 +1,531 @@
+//! CommonJS support: wrapping sources for `require()` and exposing CommonJS
+//! modules to ES module `import`.
+//!
+//! `require()` itself lives in `builtins/node/module.js`. An ES module that
+//! imports a CommonJS file gets a facade module whose default export is
+//! `module.exports`. Named exports are found by scanning the source for
+//! assignments such as `exports.name = ...`, the same way Node's
+//! cjs-module-lexer does, since they can't be known without running it.
+
+use crate::package_json::Conditions;
+
+/// The parameters every CommonJS module is wrapped with
+const WRAPPER_START: &str = "(function (exports, require, module, __filename, __dirname) { ";
+const WRAPPER_END: &str = "\n})";
+
+/// How many `module.exports = require(...)` hops to follow when looking for named exports
+const MAX_REEXPORT_DEPTH: usize = 8;
+
+/// Wrap a CommonJS source in a function taking the module parameters.
+/// The wrapper starts on the first line so line numbers are preserved.
+pub(crate) fn wrap(source: &str) -> String {
+    let source = source.strip_prefix('\u{feff}').unwrap_or(source);
+    let mut wrapped = String::with_capacity(WRAPPER_START.len() + source.len() + WRAPPER_END.len());
+    wrapped.push_str(WRAPPER_START);
+    // A hashbang is only valid at the very start of a script, so comment it out
+    if let Some(rest) = source.strip_prefix("#!") {
+        wrapped.push_str("//");
+        wrapped.push_str(rest);
+    } else {
+        wrapped.push_str(source);
+    }
+    wrapped.push_str(WRAPPER_END);
+    wrapped
+}
+
+/// Generate the ES module that `import` sees for a CommonJS file.
+/// Re-exported modules are resolved with the require `conditions`.
+pub(crate) fn facade_source(path: &str, conditions: &Conditions) -> String {
+    let mut names = Vec::new();
+    collect_exports(path, conditions, 0, &mut names);
+    names.retain(|name| name != "default" && is_exportable_name(name));
+    names.sort();
+    names.dedup();
+
+    let path_literal = js_string_literal(path);
+    let mut source = format!(
+        "const __cjs_exports = globalThis.__node_modules['node:module']._load({}, null);\n\
+         export default __cjs_exports;\n",
+        path_literal
+    );
+    if !names.is_empty() {
+        let bindings: Vec<String> = names
+            .iter()
+            .enumerate()
+            .map(|(i, name)| format!("{}: __cjs_{}", js_string_literal(name), i))
+            .collect();
+        let exports: Vec<String> = names
+            .iter()
+            .enumerate()
+            .map(|(i, name)| format!("__cjs_{} as {}", i, js_string_literal(name)))
+            .collect();
+        source.push_str(&format!(
+            "const {{ {} }} = __cjs_exports;\nexport {{ {} }};\n",
+            bindings.join(", "),
+            exports.join(", ")
+        ));
+    }
+    source
+}
+
+/// Add the names exported by the CommonJS file at `path`, following re-exports
+fn collect_exports(path: &str, conditions: &Conditions, depth: usize, names: &mut Vec<String>) {
+    let Ok(source) = crate::module::read_source_cached(path) else {
+        return;
+    };
+    let exports = detect_exports(&source);
+    names.extend(exports.names);
+    if depth >= MAX_REEXPORT_DEPTH {
+        return;
+    }
+    for specifier in exports.reexports {
+        if let Some(resolved) = crate::module::resolve_require(path, &specifier, conditions)
+            && !resolved.ends_with(".json")
+        {
+            collect_exports(&resolved, conditions, depth + 1, names);
+        }
+    }
+}
+
+/// Names a facade can export: skips control characters, quotes and backslashes
+/// rather than escaping them in the generated source
+fn is_exportable_name(name: &str) -> bool {
+    !name.is_empty()
+        && !name
+            .chars()
+            .any(|c| c.is_control() || c == '"' || c == '\\')
+}
+
+fn js_string_literal(value: &str) -> String {
+    format!("\"{}\"", value.replace('\\', "\\\\").replace('"', "\\\""))
+}
+
+/// What a CommonJS source exports, as far as can be told without running it
+#[derive(Debug, Default, PartialEq)]
+pub(crate) struct DetectedExports {
+    pub(crate) names: Vec<String>,
+    /// Specifiers of modules whose exports are re-exported wholesale
+    pub(crate) reexports: Vec<String>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Token {
+    Ident(String),
+    Str(String),
+    Punct(char),
+}
+
+/// Find exports by looking for these patterns:
+/// - `exports.name =`, `module.exports.name =` and the `['name']` forms
+/// - `Object.defineProperty(exports, 'name', ...)`
+/// - `module.exports = { name, other: ..., ...require('x') }`
+/// - `module.exports = require('x')` and TypeScript's `__exportStar(require('x'), exports)`
+pub(crate) fn detect_exports(source: &str) -> DetectedExports {
+    let tokens = tokenize(source);
+    let mut detected = DetectedExports::default();
+    let ident = |i: usize, name: &str| matches!(tokens.get(i), Some(Token::Ident(s)) if s == name);
+    let punct = |i: usize, c: char| tokens.get(i) == Some(&Token::Punct(c));
+
+    let mut i = 0;
+    while i < tokens.len() {
+        // Member accesses like `foo.exports` are not the module's exports
+        if i > 0 && punct(i - 1, '.') {
+            i += 1;
+            continue;
+        }
+
+        // `module.exports` or `exports`, followed by the rest of the statement
+        let target_end = if ident(i, "module") && punct(i + 1, '.') && ident(i + 2, "exports") {
+            Some(i + 3)
+        } else if ident(i, "exports") {
+            Some(i + 1)
+        } else {
+            None
+        };
+
+        if let Some(end) = target_end {
+            if let Some((name, next)) = member_name(&tokens, end)
+                && punct(next, '=')
+                && !punct(next + 1, '=')
+            {
+                detected.names.push(name);
+                i = next + 1;
+                continue;
+            }
+            if ident(i, "module") && punct(end, '=') && !punct(end + 1, '=') {
+                if punct(end + 1, '{') {
+                    i = object_literal_keys(&tokens, end + 2, &mut detected);
+                    continue;
+                }
+                if let Some((specifier, next)) = require_call(&tokens, end + 1) {
+                    detected.reexports.push(specifier);
+                    i = next;
+                    continue;
+                }
+            }
+        }
+
+        // Object.defineProperty(exports, 'name', ...) and the module.exports form
+        if ident(i, "Object")
+            && punct(i + 1, '.')
+            && ident(i + 2, "defineProperty")
+            && punct(i + 3, '(')
+        {
+            let mut j = i + 4;
+            if ident(j, "module") && punct(j + 1, '.') && ident(j + 2, "exports") {
+                j += 3;
+            } else if ident(j, "exports") {
+                j += 1;
+            } else {
+                i += 1;
+                continue;
+            }
+            if punct(j, ',')
+                && let Some(Token::Str(name)) = tokens.get(j + 1)
+            {
+                detected.names.push(name.clone());
+                i = j + 2;
+                continue;
+            }
+        }
+
+        // __exportStar(require('x'), exports) and __export(require('x'))
+        if (ident(i, "__exportStar") || ident(i, "__export"))
+            && punct(i + 1, '(')
+            && let Some((specifier, next)) = require_call(&tokens, i + 2)
+        {
+            detected.reexports.push(specifier);
+            i = next;
+            continue;
+        }
+
+        i += 1;
+    }
+    detected
+}
+
+/// Read `.name` or `['name']` at `i`, returning the name and the index after it
+fn member_name(tokens: &[Token], i: usize) -> Option<(String, usize)> {
+    match (tokens.get(i), tokens.get(i + 1), tokens.get(i + 2)) {
+        (Some(Token::Punct('.')), Some(Token::Ident(name)), _) => Some((name.clone(), i + 2)),
+        (Some(Token::Punct('[')), Some(Token::Str(name)), Some(Token::Punct(']'))) => {
+            Some((name.clone(), i + 3))
+        }
+        _ => None,
+    }
+}
+
+/// Read `require('x')` at `i`, returning the specifier and the index after it
+fn require_call(tokens: &[Token], i: usize) -> Option<(String, usize)> {
+    match (
+        tokens.get(i),
+        tokens.get(i + 1),
+        tokens.get(i + 2),
+        tokens.get(i + 3),
+    ) {
+        (
+            Some(Token::Ident(require)),
+            Some(Token::Punct('(')),
+            Some(Token::Str(specifier)),
+            Some(Token::Punct(')')),
+        ) if require == "require" => Some((specifier.clone(), i + 4)),
+        _ => None,
+    }
+}
+
+/// Collect the keys of an object literal starting just after its `{`.
+/// Returns the index after the closing `}`, or where scanning gave up.
+fn object_literal_keys(tokens: &[Token], mut i: usize, detected: &mut DetectedExports) -> usize {
+    loop {
+        match tokens.get(i) {
+            Some(Token::Punct('}')) => return i + 1,
+            Some(Token::Punct('.'))
+                if tokens.get(i + 1) == Some(&Token::Punct('.'))
+                    && tokens.get(i + 2) == Some(&Token::Punct('.')) =>
+            {
+                let Some((specifier, next)) = require_call(tokens, i + 3) else {
+                    return i;
+                };
+                detected.reexports.push(specifier);
+                i = next;
+            }
+            Some(Token::Ident(key)) | Some(Token::Str(key)) => {
+                let key = key.clone();
+                match tokens.get(i + 1) {
+                    // Shorthand property
+                    Some(Token::Punct(',')) | Some(Token::Punct('}')) => {
+                        detected.names.push(key);
+                        i += 1;
+                    }
+                    // `key: value` or a method `key() { ... }`
+                    Some(Token::Punct(':')) | Some(Token::Punct('(')) => {
+                        detected.names.push(key);
+                        i = skip_value(tokens, i + 1);
+                    }
+                    _ => return i,
+                }
+            }
+            _ => return i,
+        }
+        match tokens.get(i) {
+            Some(Token::Punct(',')) => i += 1,
+            Some(Token::Punct('}')) => return i + 1,
+            _ => return i,
+        }
+    }
+}
+
+/// Skip tokens up to the next `,` or `}` that isn't nested in brackets
+fn skip_value(tokens: &[Token], mut i: usize) -> usize {
+    let mut depth = 0usize;
+    while let Some(token) = tokens.get(i) {
+        match token {
+            Token::Punct('(') | Token::Punct('[') | Token::Punct('{') => depth += 1,
+            Token::Punct(')') | Token::Punct(']') => depth = depth.saturating_sub(1),
+            Token::Punct('}') if depth == 0 => return i,
+            Token::Punct('}') => depth -= 1,
+            Token::Punct(',') if depth == 0 => return i,
+            _ => {}
+        }
+        i += 1;
+    }
+    i
+}
+
+/// Split a source into identifiers, string literals and punctuation, skipping
+/// comments, template literals, regular expressions, numbers and whitespace
+fn tokenize(source: &str) -> Vec<Token> {
+    let chars: Vec<char> = source.chars().collect();
+    let mut tokens = Vec::new();
+    let mut i = 0;
+    // Braces opened inside template literal substitutions, so `}` can resume the template
+    let mut template_depths: Vec<usize> = Vec::new();
+    let mut brace_depth = 0usize;
+
+    while i < chars.len() {
+        let c = chars[i];
+        match c {
+            _ if c.is_whitespace() => i += 1,
+            '/' if chars.get(i + 1) == Some(&'/') => {
+                while i < chars.len() && chars[i] != '\n' {
+                    i += 1;
+                }
+            }
+            '/' if chars.get(i + 1) == Some(&'*') => {
+                i += 2;
+                while i < chars.len() && !(chars[i] == '*' && chars.get(i + 1) == Some(&'/')) {
+                    i += 1;
+                }
+                i += 2;
+            }
+            '/' if regex_allowed(tokens.last()) => {
+                i = skip_regex(&chars, i + 1);
+            }
+            '\'' | '"' => {
+                let (value, next) = read_string(&chars, i + 1, c);
+                tokens.push(Token::Str(value));
+                i = next;
+            }
+            '`' => {
+                i = skip_template(&chars, i + 1, &mut template_depths, brace_depth);
+            }
+            '}' if template_depths.last() == Some(&brace_depth) => {
+                template_depths.pop();
+                i = skip_template(&chars, i + 1, &mut template_depths, brace_depth);
+            }
+            _ if c == '_' || c == '$' || c.is_alphabetic() => {
+                let start = i;
+                while i < chars.len()
+                    && (chars[i] == '_' || chars[i] == '$' || chars[i].is_alphanumeric())
+                {
+                    i += 1;
+                }
+                tokens.push(Token::Ident(chars[start..i].iter().collect()));
+            }
+            _ if c.is_ascii_digit() => {
+                while i < chars.len() && (chars[i].is_alphanumeric() || chars[i] == '.') {
+                    i += 1;
+                }
+                // Numbers only matter for telling division from a regular expression
+                tokens.push(Token::Ident(String::new()));
+            }
+            _ => {
+                match c {
+                    '{' => brace_depth += 1,
+                    '}' => brace_depth = brace_depth.saturating_sub(1),
+                    _ => {}
+                }
+                tokens.push(Token::Punct(c));
+                i += 1;
+            }
+        }
+    }
+    tokens
+}
+
+/// Whether a `/` after this token starts a regular expression rather than a division
+fn regex_allowed(previous: Option<&Token>) -> bool {
+    match previous {
+        None => true,
+        Some(Token::Punct(c)) => !matches!(c, ')' | ']' | '}'),
+        Some(Token::Ident(word)) => matches!(
+            word.as_str(),
+            "return"
+                | "typeof"
+                | "instanceof"
+                | "in"
+                | "of"
+                | "new"
+                | "delete"
+                | "void"
+                | "throw"
+                | "case"
+                | "do"
+                | "else"
+                | "yield"
+                | "await"
+        ),
+        Some(Token::Str(_)) => false,
+    }
+}
+
+/// Skip a regular expression body and flags, starting after the opening `/`
+fn skip_regex(chars: &[char], mut i: usize) -> usize {
+    let mut in_class = false;
+    while i < chars.len() {
+        match chars[i] {
+            '\\' => i += 1,
+            '[' => in_class = true,
+            ']' => in_class = false,
+            '/' if !in_class => break,
+            '\n' => return i,
+            _ => {}
+        }
+        i += 1;
+    }
+    i += 1;
+    while i < chars.len() && chars[i].is_alphanumeric() {
+        i += 1;
+    }
+    i
+}
+
+/// Read a string literal's value, starting after the opening quote
+fn read_string(chars: &[char], mut i: usize, quote: char) -> (String, usize) {
+    let mut value = String::new();
+    while i < chars.len() && chars[i] != quote {
+        if chars[i] == '\\' {
+            i += 1;
+            match chars.get(i) {
+                Some('n') => value.push('\n'),
+                Some('t') => value.push('\t'),
+                Some(&c) => value.push(c),
+                None => break,
+            }
+        } else if chars[i] == '\n' {
+            break;
+        } else {
+            value.push(chars[i]);
+        }
+        i += 1;
+    }
+    (value, i + 1)
+}
+
+/// Skip template literal text, starting after a backtick or a substitution's `}`.
+/// Stops after the closing backtick, or after `${` with the brace depth recorded.
+fn skip_template(
+    chars: &[char],
+    mut i: usize,
+    template_depths: &mut Vec<usize>,
+    brace_depth: usize,
+) -> usize {
+    while i < chars.len() {
+        match chars[i] {
+            '\\' => i += 1,
+            '`' => return i + 1,
+            '$' if chars.get(i + 1) == Some(&'{') => {
+                template_depths.push(brace_depth);
+                return i + 2;
+            }
+            _ => {}
+        }
+        i += 1;
+    }
+    i
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn names(source: &str) -> Vec<String> {
+        detect_exports(source).names
+    }
+
+    #[test]
+    fn test_wrap_preserves_first_line() {
+        let wrapped = wrap("#!/usr/bin/env node\nmodule.exports = 1;");
+        assert!(wrapped.starts_with(WRAPPER_START));
+        assert!(wrapped.contains("{ ///usr/bin/env node\nmodule.exports = 1;"));
+        assert!(wrapped.ends_with(WRAPPER_END));
+
+        let wrapped = wrap("\u{feff}exports.a = 1;");
+        assert_eq!(
+            wrapped,
+            format!("{}exports.a = 1;{}", WRAPPER_START, WRAPPER_END)
+        );
+    }
+
+    #[test]
+    fn test_detect_export_assignments() {
+        let source = r#"
+            exports.a = 1;
+            module.exports.b = function () {};
+            exports['c-d'] = 3;
+            Object.defineProperty(exports, 'e', { enumerable: true, get() { return 5; } });
+            Object.defineProperty(module.exports, "f", { value: 6 });
+            if (exports.g === 7) {}
+            other.exports.h = 8;
+        "#;
+        assert_eq!(names(source), vec!["a", "b", "c-d", "e", "f"]);
+    }
+
+    #[test]
+    fn test_detect_object_literal_keys() {
+        let source = r#"
+            module.exports = {
+                a,
+                b: call(1, { nested: true }),
+                'c': [1, 2],
+                d() { return { e: 1 }; },
+                ...require('./more'),
+            };
+        "#;
+        let detected = detect_exports(source);
+        assert_eq!(detected.names, vec!["a", "b", "c", "d"]);
+        assert_eq!(detected.reexports, vec!["./more"]);
+    }
+
+    #[test]
+    fn test_detect_reexports() {
+        let source = r#"
+            module.exports = require('./impl');
+            __exportStar(require("./types"), exports);
+        "#;
+        assert_eq!(detect_exports(source).reexports, vec!["./impl", "./types"]);
+    }
+
+    #[test]
+    fn test_ignores_strings_comments_and_regexes() {
+        let source = r#"
+            // exports.a = 1;
+            /* exports.b = 2; */
+            const s = "exports.c = 3";
+            const t = `${exports.d} exports.e = 5`;
+            const r = /exports.f = 6/;
+            const q = 4 / 2; exports.g = q / 1;
+        "#;
+        assert_eq!(names(source), vec!["g"]);
+    }
+}
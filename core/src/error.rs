@@ -2,7 +2,40 @@
 //!
 //! This module provides helper functions to create V8 exceptions with consistent
 //! error messages across all builtin implementations, as well as formatting
-//! exceptions with source information and stack traces.
+//! exceptions with source information and stack traces, and the error type
+//! `JSTime` returns.
+
+/// An error from running JavaScript with `JSTime`
+#[derive(Debug)]
+pub enum Error {
+    /// An exception that wasn't caught, or a promise rejection that wasn't
+    /// handled. It displays with where it was thrown and its stack trace.
+    Exception(crate::JsError),
+    /// The run was stopped with a `TerminationHandle`, or when the
+    /// execution timeout passed
+    Terminated,
+    /// The run was stopped as the JavaScript heap reached its maximum size
+    HeapLimitExceeded,
+}
+
+impl std::fmt::Display for Error {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Error::Exception(error) => error.fmt(f),
+            Error::Terminated => f.write_str("Execution terminated"),
+            Error::HeapLimitExceeded => f.write_str("JavaScript heap limit exceeded"),
+        }
+    }
+}
+
+impl std::error::Error for Error {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Error::Exception(error) => Some(error),
+            _ => None,
+        }
+    }
+}
 
 /// ANSI color codes for terminal output
 mod colors {
@@ -55,13 +88,25 @@ pub(crate) fn throw_error(scope: &mut v8::PinScope, message: &str) {
     scope.throw_exception(exception);
 }
 
+/// Throws a SyntaxError with the given message.
+///
+/// # Examples
+/// ```ignore
+/// throw_syntax_error(scope, "Unexpected token");
+/// ```
+#[inline]
+pub(crate) fn throw_syntax_error(scope: &mut v8::PinScope, message: &str) {
+    let message = v8::String::new(scope, message).unwrap();
+    let exception = v8::Exception::syntax_error(scope, message);
+    scope.throw_exception(exception);
+}
+
 /// Throws a RangeError with the given message.
 ///
 /// # Examples
 /// ```ignore
 /// throw_range_error(scope, "Index out of bounds");
 /// ```
-#[allow(dead_code)] // Not currently used but provided for consistency
 #[inline]
 pub(crate) fn throw_range_error(scope: &mut v8::PinScope, message: &str) {
     let message = v8::String::new(scope, message).unwrap();
@@ -69,6 +114,66 @@ pub(crate) fn throw_range_error(scope: &mut v8::PinScope, message: &str) {
     scope.throw_exception(exception);
 }
 
+/// The Node.js error code (e.g. "ECONNREFUSED") for an I/O error.
+pub(crate) fn io_error_code(error: &std::io::Error) -> &'static str {
+    use std::io::ErrorKind;
+    // TLS failures carry their own code
+    if let Some(tls_error) = error
+        .get_ref()
+        .and_then(|inner| inner.downcast_ref::<crate::tls::TlsError>())
+    {
+        return tls_error.code;
+    }
+    match error.kind() {
+        ErrorKind::ConnectionRefused => "ECONNREFUSED",
+        ErrorKind::ConnectionReset => "ECONNRESET",
+        ErrorKind::ConnectionAborted => "ECONNABORTED",
+        ErrorKind::BrokenPipe | ErrorKind::WriteZero => "EPIPE",
+        ErrorKind::TimedOut => "ETIMEDOUT",
+        ErrorKind::AddrInUse => "EADDRINUSE",
+        ErrorKind::AddrNotAvailable => "EADDRNOTAVAIL",
+        ErrorKind::NotFound => "ENOTFOUND",
+        ErrorKind::PermissionDenied => "EACCES",
+        ErrorKind::HostUnreachable => "EHOSTUNREACH",
+        ErrorKind::NetworkUnreachable => "ENETUNREACH",
+        ErrorKind::InvalidInput => "EINVAL",
+        _ => "EIO",
+    }
+}
+
+/// Creates an Error for an I/O error, with a Node.js style `code` property.
+///
+/// # Examples
+/// ```ignore
+/// let error = create_io_error(scope, &e);
+/// callback.call(scope, recv, &[error]);
+/// ```
+pub(crate) fn create_io_error<'s>(
+    scope: &mut v8::PinScope<'s, '_>,
+    error: &std::io::Error,
+) -> v8::Local<'s, v8::Value> {
+    let message = v8::String::new(scope, &error.to_string()).unwrap();
+    let exception = v8::Exception::error(scope, message);
+    if let Ok(object) = v8::Local::<v8::Object>::try_from(exception) {
+        let key = v8::String::new(scope, "code").unwrap();
+        let code = v8::String::new(scope, io_error_code(error)).unwrap();
+        object.set(scope, key.into(), code.into());
+    }
+    exception
+}
+
+/// Throws an Error for an I/O error, with a Node.js style `code` property.
+///
+/// # Examples
+/// ```ignore
+/// throw_io_error(scope, &e);
+/// ```
+#[inline]
+pub(crate) fn throw_io_error(scope: &mut v8::PinScope, error: &std::io::Error) {
+    let exception = create_io_error(scope, error);
+    scope.throw_exception(exception);
+}
+
 /// Attempts to convert a v8::Value to a Rust string.
 /// Returns an error message if conversion fails.
 ///
@@ -202,6 +307,46 @@ pub(crate) fn try_get_array_result<'s>(
     v8::Local::<v8::Array>::try_from(value).map_err(|_| "Value must be an array")
 }
 
+/// Copies the bytes of an `ArrayBuffer`, typed array or `DataView` into a vector.
+/// Returns Err with an error message if the value is not a BufferSource.
+///
+/// # Examples
+/// ```ignore
+/// let bytes = match try_get_buffer_source_bytes(value) {
+///     Ok(b) => b,
+///     Err(msg) => {
+///         crate::error::throw_type_error(scope, msg);
+///         return;
+///     }
+/// };
+/// ```
+pub(crate) fn try_get_buffer_source_bytes(
+    value: v8::Local<v8::Value>,
+) -> Result<Vec<u8>, &'static str> {
+    if let Ok(view) = v8::Local::<v8::ArrayBufferView>::try_from(value) {
+        let mut bytes = vec![0u8; view.byte_length()];
+        view.copy_contents(&mut bytes);
+        return Ok(bytes);
+    }
+    if let Ok(array_buffer) = v8::Local::<v8::ArrayBuffer>::try_from(value) {
+        let byte_length = array_buffer.byte_length();
+        let mut bytes = vec![0u8; byte_length];
+        if byte_length > 0
+            && let Some(data) = array_buffer.get_backing_store().data()
+        {
+            unsafe {
+                std::ptr::copy_nonoverlapping(
+                    data.as_ptr() as *const u8,
+                    bytes.as_mut_ptr(),
+                    byte_length,
+                );
+            }
+        }
+        return Ok(bytes);
+    }
+    Err("Value must be an ArrayBuffer, TypedArray or DataView")
+}
+
 /// Format an exception value (from a promise rejection or similar) with enhanced formatting.
 /// This is used when we have the exception value but not an active TryCatch with message details.
 pub(crate) fn format_exception_value(
@@ -273,6 +418,11 @@ pub(crate) fn format_exception(
 ) -> String {
     let use_colors = should_use_colors();
 
+    // A terminated script has no exception to report
+    if tc.has_terminated() {
+        return "Execution terminated".to_string();
+    }
+
     // Get the exception value
     let exception = match tc.exception() {
         Some(e) => e,
@@ -292,72 +442,9 @@ pub(crate) fn format_exception(
     if let Some(message) = tc.message() {
         let mut output = String::new();
 
-        // Get file name and line number
-        let (resource_name, line_number) = {
-            let isolate: &v8::Isolate = tc;
-            (
-                message
-                    .get_script_resource_name(tc)
-                    .and_then(|v| v.to_string(tc))
-                    .map(|s| s.to_rust_string_lossy(isolate)),
-                message.get_line_number(tc),
-            )
-        };
-
-        // Get source line if available
-        let source_line = {
-            let isolate: &v8::Isolate = tc;
-            message
-                .get_source_line(tc)
-                .map(|s| s.to_string(tc).unwrap().to_rust_string_lossy(isolate))
-        };
-
-        // Get column information
-        let start_column = message.get_start_column();
-        let end_column = message.get_end_column();
-
-        // Format the output with colors
-        if let (Some(file), Some(line)) = (resource_name.as_ref(), line_number) {
-            if use_colors {
-                output.push_str(&format!(
-                    "{}{}:{}{}  \n",
-                    colors::CYAN,
-                    file,
-                    line,
-                    colors::RESET
-                ));
-            } else {
-                output.push_str(&format!("{}:{}\n", file, line));
-            }
-
-            // Add source line if available
-            if let Some(ref source) = source_line {
-                output.push_str(source);
-                output.push('\n');
-
-                // Add caret indicator
-                // Add spaces for indentation
-                for _ in 0..start_column {
-                    output.push(' ');
-                }
-
-                // Add carets in red
-                let caret_count = (end_column - start_column).max(1);
-
-                if use_colors {
-                    output.push_str(colors::RED);
-                }
-
-                for _ in 0..caret_count {
-                    output.push('^');
-                }
-
-                if use_colors {
-                    output.push_str(colors::RESET);
-                }
-
-                output.push('\n');
-            }
+        let location = error_location(tc, message);
+        if let Some(ref location) = location {
+            push_location(&mut output, location, use_colors);
         }
 
         // Add the error message with color
@@ -394,62 +481,8 @@ pub(crate) fn format_exception(
                     // otherwise append it
                     if stack.starts_with(&exception_string) || stack.contains(&exception_string) {
                         output = String::new();
-                        let (file_opt, line_opt) = {
-                            let isolate: &v8::Isolate = tc;
-                            (
-                                message
-                                    .get_script_resource_name(tc)
-                                    .and_then(|v| v.to_string(tc))
-                                    .map(|s| s.to_rust_string_lossy(isolate)),
-                                message.get_line_number(tc),
-                            )
-                        };
-
-                        if let (Some(file), Some(line)) = (file_opt, line_opt) {
-                            if use_colors {
-                                output.push_str(&format!(
-                                    "{}{}:{}{}  \n",
-                                    colors::CYAN,
-                                    file,
-                                    line,
-                                    colors::RESET
-                                ));
-                            } else {
-                                output.push_str(&format!("{}:{}\n", file, line));
-                            }
-
-                            let source_opt = {
-                                let isolate: &v8::Isolate = tc;
-                                message
-                                    .get_source_line(tc)
-                                    .map(|s| s.to_string(tc).unwrap().to_rust_string_lossy(isolate))
-                            };
-
-                            if let Some(source) = source_opt {
-                                output.push_str(&source);
-                                output.push('\n');
-
-                                // Add spaces for indentation
-                                for _ in 0..start_column {
-                                    output.push(' ');
-                                }
-
-                                let caret_count = (end_column - start_column).max(1);
-
-                                if use_colors {
-                                    output.push_str(colors::RED);
-                                }
-
-                                for _ in 0..caret_count {
-                                    output.push('^');
-                                }
-
-                                if use_colors {
-                                    output.push_str(colors::RESET);
-                                }
-
-                                output.push('\n');
-                            }
+                        if let Some(ref location) = location {
+                            push_location(&mut output, location, use_colors);
                             output.push('\n');
                         }
 
@@ -481,3 +514,251 @@ pub(crate) fn format_exception(
         exception_string
     }
 }
+
+/// Formats `Error.prototype.stack` like V8 does, but reports frames in
+/// modules with a source map at their original position. A user-defined
+/// `Error.prepareStackTrace` is called instead, as in Node.js.
+pub(crate) fn prepare_stack_trace_callback<'s>(
+    scope: &mut v8::PinScope<'s, '_>,
+    error: v8::Local<'s, v8::Value>,
+    call_sites: v8::Local<'s, v8::Array>,
+) -> v8::Local<'s, v8::Value> {
+    let context = scope.get_current_context();
+    let global = context.global(scope);
+    let error_key = v8::String::new(scope, "Error").unwrap();
+    let prepare_key = v8::String::new(scope, "prepareStackTrace").unwrap();
+    if let Some(error_class) = global.get(scope, error_key.into())
+        && let Ok(error_class) = v8::Local::<v8::Object>::try_from(error_class)
+        && let Some(prepare) = error_class.get(scope, prepare_key.into())
+        && let Ok(prepare) = v8::Local::<v8::Function>::try_from(prepare)
+    {
+        return prepare
+            .call(scope, error_class.into(), &[error, call_sites.into()])
+            .unwrap_or_else(|| v8::undefined(scope).into());
+    }
+
+    let mut stack = error_header(scope, error);
+    for i in 0..call_sites.length() {
+        let Some(call_site) = call_sites
+            .get_index(scope, i)
+            .and_then(|site| v8::Local::<v8::Object>::try_from(site).ok())
+        else {
+            continue;
+        };
+        let Some(mut frame) = call_site_method(scope, call_site, "toString")
+            .and_then(|frame| frame.to_string(scope))
+            .map(|frame| frame.to_rust_string_lossy(scope))
+        else {
+            continue;
+        };
+        if let Some(original) = original_frame_position(scope, call_site) {
+            // Replace the generated position, which ends the frame
+            let (generated, position) = original;
+            if let Some(index) = frame.rfind(&generated) {
+                frame.replace_range(index..index + generated.len(), &position);
+            }
+        }
+        stack.push_str("\n    at ");
+        stack.push_str(&frame);
+    }
+    v8::String::new(scope, &stack).unwrap().into()
+}
+
+/// The first line of a stack trace, as `Error.prototype.toString` would
+/// format it
+fn error_header(scope: &mut v8::PinScope, error: v8::Local<v8::Value>) -> String {
+    let to_string = |scope: &mut v8::PinScope, value: v8::Local<v8::Value>| {
+        value
+            .to_string(scope)
+            .map(|s| s.to_rust_string_lossy(scope))
+            .unwrap_or_default()
+    };
+    let Ok(object) = v8::Local::<v8::Object>::try_from(error) else {
+        return to_string(scope, error);
+    };
+
+    let name_key = v8::String::new(scope, "name").unwrap();
+    let message_key = v8::String::new(scope, "message").unwrap();
+    let name = match object.get(scope, name_key.into()) {
+        Some(name) if !name.is_undefined() => to_string(scope, name),
+        _ => "Error".to_string(),
+    };
+    let message = match object.get(scope, message_key.into()) {
+        Some(message) if !message.is_undefined() => to_string(scope, message),
+        _ => String::new(),
+    };
+    match (name.is_empty(), message.is_empty()) {
+        (true, _) => message,
+        (_, true) => name,
+        _ => format!("{}: {}", name, message),
+    }
+}
+
+/// Call a method of a CallSite without arguments
+fn call_site_method<'s>(
+    scope: &mut v8::PinScope<'s, '_>,
+    call_site: v8::Local<'s, v8::Object>,
+    name: &str,
+) -> Option<v8::Local<'s, v8::Value>> {
+    let key = v8::String::new(scope, name).unwrap();
+    let method = call_site.get(scope, key.into())?;
+    let method = v8::Local::<v8::Function>::try_from(method).ok()?;
+    method.call(scope, call_site.into(), &[])
+}
+
+/// The generated `file:line:column` of a call site, and the original one
+/// to report instead, if its module has a source map
+fn original_frame_position<'s>(
+    scope: &mut v8::PinScope<'s, '_>,
+    call_site: v8::Local<'s, v8::Object>,
+) -> Option<(String, String)> {
+    let file = call_site_method(scope, call_site, "getFileName")?;
+    if !file.is_string() {
+        return None;
+    }
+    let file = file.to_string(scope)?.to_rust_string_lossy(scope);
+    {
+        let isolate: &mut v8::Isolate = scope;
+        let state = crate::IsolateState::get(isolate);
+        if state.borrow().module_map.source_map(&file).is_none() {
+            return None;
+        }
+    }
+
+    let line = call_site_method(scope, call_site, "getLineNumber")?.uint32_value(scope)?;
+    let column = call_site_method(scope, call_site, "getColumnNumber")?.uint32_value(scope)?;
+    let isolate: &mut v8::Isolate = scope;
+    let original =
+        crate::sourcemap::original_position(isolate, &file, line, column.checked_sub(1)?)?;
+    Some((
+        format!("{}:{}:{}", file, line, column),
+        format!(
+            "{}:{}:{}",
+            original.source,
+            original.line,
+            original.column + 1
+        ),
+    ))
+}
+
+/// Where an exception was thrown, in the original source if the module
+/// has a source map
+pub(crate) struct ErrorLocation {
+    pub(crate) file: String,
+    pub(crate) line: usize,
+    pub(crate) source_line: Option<String>,
+    pub(crate) start_column: usize,
+    pub(crate) end_column: usize,
+}
+
+/// Get the location of an exception from its message
+pub(crate) fn error_location(
+    tc: &mut v8::PinnedRef<'_, v8::TryCatch<v8::HandleScope>>,
+    message: v8::Local<v8::Message>,
+) -> Option<ErrorLocation> {
+    // Get file name and line number
+    let (file, line) = {
+        let isolate: &v8::Isolate = tc;
+        (
+            message
+                .get_script_resource_name(tc)
+                .and_then(|v| v.to_string(tc))
+                .map(|s| s.to_rust_string_lossy(isolate))?,
+            message.get_line_number(tc)?,
+        )
+    };
+
+    // Get source line if available
+    let source_line = {
+        let isolate: &v8::Isolate = tc;
+        message
+            .get_source_line(tc)
+            .map(|s| s.to_string(tc).unwrap().to_rust_string_lossy(isolate))
+    };
+
+    // Get column information
+    let start_column = message.get_start_column();
+    let end_column = message.get_end_column();
+
+    // Report the original position if the module has a source map
+    let isolate: &mut v8::Isolate = tc;
+    if let Some(original) =
+        crate::sourcemap::original_position(isolate, &file, line as u32, start_column as u32)
+    {
+        let original_column = original.column as usize;
+        // The span may not be the same length in the original source, so
+        // keep it within the line
+        let line_length = original
+            .source_line
+            .as_deref()
+            .map_or(0, |source| source.encode_utf16().count());
+        let width = end_column
+            .saturating_sub(start_column)
+            .min(line_length.saturating_sub(original_column))
+            .max(1);
+        return Some(ErrorLocation {
+            file: original.source,
+            line: original.line as usize,
+            source_line: original.source_line,
+            start_column: original_column,
+            end_column: original_column + width,
+        });
+    }
+
+    Some(ErrorLocation {
+        file,
+        line,
+        source_line,
+        start_column,
+        end_column,
+    })
+}
+
+/// Add the file and line of an exception to `output`, followed by the
+/// source line with carets under the error
+fn push_location(output: &mut String, location: &ErrorLocation, use_colors: bool) {
+    // Format the output with colors
+    if use_colors {
+        output.push_str(&format!(
+            "{}{}:{}{}  \n",
+            colors::CYAN,
+            location.file,
+            location.line,
+            colors::RESET
+        ));
+    } else {
+        output.push_str(&format!("{}:{}\n", location.file, location.line));
+    }
+
+    // Add source line if available
+    if let Some(ref source) = location.source_line {
+        output.push_str(source);
+        output.push('\n');
+
+        // Add caret indicator
+        // Add spaces for indentation
+        for _ in 0..location.start_column {
+            output.push(' ');
+        }
+
+        // Add carets in red
+        let caret_count = location
+            .end_column
+            .saturating_sub(location.start_column)
+            .max(1);
+
+        if use_colors {
+            output.push_str(colors::RED);
+        }
+
+        for _ in 0..caret_count {
+            output.push('^');
+        }
+
+        if use_colors {
+            output.push_str(colors::RESET);
+        }
+
+        output.push('\n');
+    }
+}
@@ -1,3 +1,4 @@
+use crate::fetch_worker::{FetchCompletion, FetchJob, FetchWorkerPool};
 use smallvec::SmallVec;
 use std::cell::RefCell;
 use std::collections::BTreeMap;
@@ -8,13 +9,11 @@ use std::time::{Duration, Instant};
 #[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
 pub(crate) struct TimerId(pub(crate) u64);
 
-/// Type alias for fetch response data: (status, status_text, headers, body_data)
-type FetchResponseData = (u16, String, Vec<(String, String)>, Vec<u8>);
-
 struct Timer {
     callback: v8::Global<v8::Function>,
     fire_at: Instant,
     interval: Option<Duration>,
+    is_ref: bool, // If false, the timer doesn't keep the event loop alive
 }
 
 /// Pending timer to be added
@@ -23,6 +22,7 @@ pub(crate) enum PendingTimer {
         id: TimerId,
         callback: v8::Global<v8::Function>,
         delay_ms: u64,
+        is_ref: bool,
     },
     Interval {
         id: TimerId,
@@ -37,8 +37,13 @@ pub(crate) struct EventLoop {
     timers_to_clear: Rc<RefCell<Vec<TimerId>>>,
     timers_to_add: Rc<RefCell<Vec<PendingTimer>>>,
     pending_fetches: Rc<RefCell<Vec<crate::isolate_state::FetchRequest>>>,
+    fetches_to_abort: Rc<RefCell<Vec<u64>>>,
+    // Fetches dispatched to worker threads, keyed by request ID
+    in_flight_fetches: rustc_hash::FxHashMap<u64, v8::Global<v8::PromiseResolver>>,
+    fetch_pool: Option<FetchWorkerPool>,
     active_dgram_sockets:
         Rc<RefCell<rustc_hash::FxHashMap<u64, crate::isolate_state::ActiveDgramSocket>>>,
+    active_net: Rc<RefCell<crate::isolate_state::ActiveNet>>,
 }
 
 impl EventLoop {
@@ -47,9 +52,11 @@ impl EventLoop {
         timers_to_add: Rc<RefCell<Vec<PendingTimer>>>,
         _next_timer_id: Rc<RefCell<u64>>,
         pending_fetches: Rc<RefCell<Vec<crate::isolate_state::FetchRequest>>>,
+        fetches_to_abort: Rc<RefCell<Vec<u64>>>,
         active_dgram_sockets: Rc<
             RefCell<rustc_hash::FxHashMap<u64, crate::isolate_state::ActiveDgramSocket>>,
         >,
+        active_net: Rc<RefCell<crate::isolate_state::ActiveNet>>,
     ) -> Self {
         Self {
             timers: BTreeMap::new(),
@@ -57,7 +64,11 @@ impl EventLoop {
             timers_to_clear,
             timers_to_add,
             pending_fetches,
+            fetches_to_abort,
+            in_flight_fetches: rustc_hash::FxHashMap::default(),
+            fetch_pool: None,
             active_dgram_sockets,
+            active_net,
         }
     }
 
@@ -77,6 +88,7 @@ impl EventLoop {
                     id,
                     callback,
                     delay_ms,
+                    is_ref,
                 } => {
                     let fire_at = Instant::now() + Duration::from_millis(delay_ms);
                     self.timers.insert(
@@ -85,6 +97,7 @@ impl EventLoop {
                             callback,
                             fire_at,
                             interval: None,
+                            is_ref,
                         },
                     );
                     self.timer_queue.entry(fire_at).or_default().push(id);
@@ -102,6 +115,7 @@ impl EventLoop {
                             callback,
                             fire_at,
                             interval: Some(interval),
+                            is_ref: true,
                         },
                     );
                     self.timer_queue.entry(fire_at).or_default().push(id);
@@ -133,11 +147,18 @@ impl EventLoop {
         }
     }
 
-    /// Check if there are any pending timers, fetch requests, or active dgram sockets
+    /// Check if there are any pending timers, fetch requests, active dgram sockets or servers
     pub(crate) fn has_pending_timers(&self) -> bool {
-        !self.timers.is_empty()
+        self.has_ref_timers()
             || !self.pending_fetches.borrow().is_empty()
+            || !self.in_flight_fetches.is_empty()
             || self.has_ref_dgram_sockets()
+            || self.has_ref_net()
+    }
+
+    /// Check if there are any timers that are keeping the event loop alive
+    fn has_ref_timers(&self) -> bool {
+        self.timers.values().any(|timer| timer.is_ref)
     }
 
     /// Check if there are any dgram sockets that are keeping the event loop alive
@@ -148,6 +169,30 @@ impl EventLoop {
             .any(|socket| socket.is_ref)
     }
 
+    /// Check if there are any servers or sockets that are keeping the event loop alive.
+    /// WebSockets always do until they close.
+    fn has_ref_net(&self) -> bool {
+        let net = self.active_net.borrow();
+        net.http_servers.values().any(|server| server.is_ref)
+            || net.tcp_servers.values().any(|server| server.is_ref)
+            || net.tcp_sockets.values().any(|socket| socket.is_ref)
+            || !net.websockets.is_empty()
+    }
+
+    /// Drop the timers, fetches and sockets pending, when the JavaScript
+    /// that started them was terminated
+    pub(crate) fn clear(&mut self) {
+        self.timers.clear();
+        self.timer_queue.clear();
+        self.timers_to_clear.borrow_mut().clear();
+        self.timers_to_add.borrow_mut().clear();
+        self.pending_fetches.borrow_mut().clear();
+        self.fetches_to_abort.borrow_mut().clear();
+        self.in_flight_fetches.clear();
+        self.active_dgram_sockets.borrow_mut().clear();
+        *self.active_net.borrow_mut() = Default::default();
+    }
+
     /// Get the next timer fire time
     fn next_fire_time(&self) -> Option<Instant> {
         self.timer_queue.keys().next().copied()
@@ -269,7 +314,12 @@ impl EventLoop {
                     // Call the callback with (data, rinfo)
                     let callback_local = v8::Local::new(scope, &callback);
                     let recv = v8::undefined(scope).into();
-                    let _ = callback_local.call(scope, recv, &[uint8_array.into(), rinfo.into()]);
+                    crate::uncaught_exception::call(
+                        scope,
+                        callback_local,
+                        recv,
+                        &[uint8_array.into(), rinfo.into()],
+                    );
                 }
                 Err(e) => {
                     // For non-blocking sockets, WouldBlock means no data available - this is normal
@@ -282,7 +332,293 @@ impl EventLoop {
         }
     }
 
-    /// Process pending fetch requests
+    /// Hand requests received by HTTP servers to their JS handlers
+    #[inline]
+    fn poll_servers(&self, scope: &mut v8::PinScope) {
+        let server_ids: SmallVec<[u64; 4]> = self
+            .active_net
+            .borrow()
+            .http_servers
+            .keys()
+            .copied()
+            .collect();
+        if server_ids.is_empty() {
+            return;
+        }
+
+        let isolate: &mut v8::Isolate = scope;
+        let state = crate::IsolateState::get(isolate);
+        let server_responses = state.borrow().server_responses.clone();
+        let next_request_id = state.borrow().next_server_request_id.clone();
+
+        for server_id in server_ids {
+            loop {
+                // Re-validate the server each time, since a handler may have closed it
+                let next = {
+                    let net = self.active_net.borrow();
+                    match net.http_servers.get(&server_id) {
+                        Some(active) => active
+                            .server
+                            .try_recv()
+                            .map(|request| (request, active.callback.clone())),
+                        None => None,
+                    }
+                };
+                let Some((request, callback)) = next else {
+                    break;
+                };
+
+                let request_id = {
+                    let mut next_id = next_request_id.borrow_mut();
+                    let id = *next_id;
+                    *next_id += 1;
+                    id
+                };
+                server_responses
+                    .borrow_mut()
+                    .insert(request_id, request.responder);
+
+                let id_value = v8::Number::new(scope, request_id as f64);
+                let method = v8::String::new(scope, &request.method).unwrap();
+                let target = v8::String::new(scope, &request.target).unwrap();
+
+                let headers = v8::Array::new(scope, request.headers.len() as i32);
+                for (i, (key, value)) in request.headers.iter().enumerate() {
+                    let key = v8::String::new(scope, key).unwrap();
+                    let value = v8::String::new(scope, value).unwrap();
+                    let entry = v8::Array::new_with_elements(scope, &[key.into(), value.into()]);
+                    headers.set_index(scope, i as u32, entry.into());
+                }
+
+                let body: v8::Local<v8::Value> = if request.body.is_empty() {
+                    v8::null(scope).into()
+                } else {
+                    let len = request.body.len();
+                    let backing_store =
+                        v8::ArrayBuffer::new_backing_store_from_vec(request.body).make_shared();
+                    let array_buffer = v8::ArrayBuffer::with_backing_store(scope, &backing_store);
+                    v8::Uint8Array::new(scope, array_buffer, 0, len)
+                        .unwrap()
+                        .into()
+                };
+
+                let remote_address =
+                    v8::String::new(scope, &request.remote_addr.ip().to_string()).unwrap();
+                let remote_port = v8::Number::new(scope, request.remote_addr.port() as f64);
+
+                // Call the handler with (id, method, target, headers, body, remoteAddress, remotePort)
+                let callback_local = v8::Local::new(scope, &callback);
+                let recv = v8::undefined(scope).into();
+                crate::uncaught_exception::call(
+                    scope,
+                    callback_local,
+                    recv,
+                    &[
+                        id_value.into(),
+                        method.into(),
+                        target.into(),
+                        headers.into(),
+                        body,
+                        remote_address.into(),
+                        remote_port.into(),
+                    ],
+                );
+            }
+        }
+    }
+
+    /// Accept connections on node:net servers, then poll every TCP socket and
+    /// hand the resulting events to JS
+    #[inline]
+    fn poll_tcp(&self, scope: &mut v8::PinScope) {
+        let (server_ids, socket_ids) = {
+            let net = self.active_net.borrow();
+            if net.tcp_servers.is_empty() && net.tcp_sockets.is_empty() {
+                return;
+            }
+            let server_ids: SmallVec<[u64; 4]> = net.tcp_servers.keys().copied().collect();
+            let socket_ids: SmallVec<[u64; 16]> = net.tcp_sockets.keys().copied().collect();
+            (server_ids, socket_ids)
+        };
+
+        let isolate: &mut v8::Isolate = scope;
+        let state = crate::IsolateState::get(isolate);
+        let next_tcp_id = state.borrow().next_tcp_id.clone();
+
+        for server_id in server_ids {
+            loop {
+                // Re-validate the server each time, since a callback may have closed it
+                let accepted = {
+                    let mut net = self.active_net.borrow_mut();
+                    let Some(server) = net.tcp_servers.get(&server_id) else {
+                        break;
+                    };
+                    match server.accept() {
+                        Ok(Some(stream)) => {
+                            let callback = server.callback.clone();
+                            let socket_id = {
+                                let mut next_id = next_tcp_id.borrow_mut();
+                                let id = *next_id;
+                                *next_id += 1;
+                                id
+                            };
+                            net.tcp_sockets
+                                .insert(socket_id, crate::tcp::ActiveTcpSocket::accepted(stream));
+                            (socket_id, callback)
+                        }
+                        Ok(None) => break,
+                        Err(e) => {
+                            // Accept failures (e.g. too many open files) leave the server usable
+                            eprintln!("net accept error: {}", e);
+                            break;
+                        }
+                    }
+                };
+
+                // Call the server callback with the new socket's ID
+                let (socket_id, callback) = accepted;
+                let id_value = v8::Number::new(scope, socket_id as f64);
+                let callback_local = v8::Local::new(scope, &callback);
+                let recv = v8::undefined(scope).into();
+                crate::uncaught_exception::call(scope, callback_local, recv, &[id_value.into()]);
+            }
+        }
+
+        // Reuse buffer across all sockets
+        let mut buf = vec![0u8; 65536];
+
+        for socket_id in socket_ids {
+            let mut events = SmallVec::<[crate::tcp::SocketEvent; 4]>::new();
+            let callback = {
+                let mut net = self.active_net.borrow_mut();
+                let Some(socket) = net.tcp_sockets.get_mut(&socket_id) else {
+                    continue;
+                };
+                // Accepted sockets are left alone until JS attaches a callback
+                let Some(callback) = socket.callback.clone() else {
+                    continue;
+                };
+                socket.poll(&mut buf, &mut events);
+                if matches!(events.last(), Some(crate::tcp::SocketEvent::Error(_))) {
+                    net.tcp_sockets.remove(&socket_id);
+                }
+                callback
+            };
+
+            // Call the callback with (event, data) for each event, in order
+            let callback_local = v8::Local::new(scope, &callback);
+            for event in events {
+                let (name, data): (&str, v8::Local<v8::Value>) = match event {
+                    crate::tcp::SocketEvent::Connect => ("connect", v8::undefined(scope).into()),
+                    crate::tcp::SocketEvent::SecureConnect => {
+                        ("secureConnect", v8::undefined(scope).into())
+                    }
+                    crate::tcp::SocketEvent::Data(data) => {
+                        let len = data.len();
+                        let backing_store =
+                            v8::ArrayBuffer::new_backing_store_from_vec(data).make_shared();
+                        let array_buffer =
+                            v8::ArrayBuffer::with_backing_store(scope, &backing_store);
+                        let bytes = v8::Uint8Array::new(scope, array_buffer, 0, len).unwrap();
+                        ("data", bytes.into())
+                    }
+                    crate::tcp::SocketEvent::End => ("end", v8::undefined(scope).into()),
+                    crate::tcp::SocketEvent::Drain => ("drain", v8::undefined(scope).into()),
+                    crate::tcp::SocketEvent::Finish => ("finish", v8::undefined(scope).into()),
+                    crate::tcp::SocketEvent::Error(e) => {
+                        ("error", crate::error::create_io_error(scope, &e))
+                    }
+                };
+                let name = v8::String::new(scope, name).unwrap();
+                let recv = v8::undefined(scope).into();
+                crate::uncaught_exception::call(scope, callback_local, recv, &[name.into(), data]);
+            }
+        }
+    }
+
+    /// Poll every WebSocket and hand the resulting events to JS
+    #[inline]
+    fn poll_websockets(&self, scope: &mut v8::PinScope) {
+        let ids: SmallVec<[u64; 8]> = self
+            .active_net
+            .borrow()
+            .websockets
+            .keys()
+            .copied()
+            .collect();
+        if ids.is_empty() {
+            return;
+        }
+
+        // Reuse buffer across all WebSockets
+        let mut buf = vec![0u8; 65536];
+
+        for id in ids {
+            let mut events = SmallVec::<[crate::websocket::WebSocketEvent; 4]>::new();
+            let callback = {
+                let mut net = self.active_net.borrow_mut();
+                let Some(websocket) = net.websockets.get_mut(&id) else {
+                    continue;
+                };
+                websocket.poll(&mut buf, &mut events);
+                let callback = websocket.callback.clone();
+                if websocket.is_closed() {
+                    net.websockets.remove(&id);
+                }
+                callback
+            };
+
+            // Call the callback with (event, ...data) for each event, in order
+            let callback_local = v8::Local::new(scope, &callback);
+            for event in events {
+                let mut args: SmallVec<[v8::Local<v8::Value>; 4]> = SmallVec::new();
+                let name = match event {
+                    crate::websocket::WebSocketEvent::Open {
+                        protocol,
+                        extensions,
+                    } => {
+                        args.push(v8::String::new(scope, &protocol).unwrap().into());
+                        args.push(v8::String::new(scope, &extensions).unwrap().into());
+                        "open"
+                    }
+                    crate::websocket::WebSocketEvent::Text(text) => {
+                        args.push(v8::String::new(scope, &text).unwrap().into());
+                        "message"
+                    }
+                    crate::websocket::WebSocketEvent::Binary(data) => {
+                        let len = data.len();
+                        let backing_store =
+                            v8::ArrayBuffer::new_backing_store_from_vec(data).make_shared();
+                        let array_buffer =
+                            v8::ArrayBuffer::with_backing_store(scope, &backing_store);
+                        let bytes = v8::Uint8Array::new(scope, array_buffer, 0, len).unwrap();
+                        args.push(bytes.into());
+                        "message"
+                    }
+                    crate::websocket::WebSocketEvent::Error(message) => {
+                        args.push(v8::String::new(scope, &message).unwrap().into());
+                        "error"
+                    }
+                    crate::websocket::WebSocketEvent::Close {
+                        code,
+                        reason,
+                        was_clean,
+                    } => {
+                        args.push(v8::Number::new(scope, code as f64).into());
+                        args.push(v8::String::new(scope, &reason).unwrap().into());
+                        args.push(v8::Boolean::new(scope, was_clean).into());
+                        "close"
+                    }
+                };
+                let name = v8::String::new(scope, name).unwrap();
+                args.insert(0, name.into());
+                let recv = v8::undefined(scope).into();
+                crate::uncaught_exception::call(scope, callback_local, recv, &args);
+            }
+        }
+    }
+
+    /// Dispatch pending fetch requests to the worker pool
     #[inline]
     fn process_fetches(&mut self, scope: &mut v8::PinScope) {
         let mut fetches_borrow = self.pending_fetches.borrow_mut();
@@ -293,246 +629,274 @@ impl EventLoop {
             fetches_borrow.drain(..).collect();
         drop(fetches_borrow);
 
-        // Get the HTTP agent, next_stream_id, and header pool from isolate state
+        // Get the HTTP agent from isolate state; it is shared with the workers
         let isolate: &mut v8::Isolate = scope;
         let state = crate::IsolateState::get(isolate);
         let agent = state.borrow().http_agent.clone();
-        let next_stream_id_ref = state.borrow().next_stream_id.clone();
-        let header_pool = state.borrow().header_vec_pool.clone();
-
-        for fetch_request in fetches {
-            // Execute the HTTP request and get the response (but don't read body yet)
-            let result = Self::execute_fetch_streaming(
-                &agent,
-                &fetch_request.url,
-                &fetch_request.method,
-                &fetch_request.headers,
-                fetch_request.body.as_deref(),
-            );
-
-            // Return the request headers to the pool (cleared for reuse)
-            let mut headers = fetch_request.headers;
-            headers.clear();
-            header_pool.put(headers);
-
-            // Resolve the promise with the result
-            let resolver = v8::Local::new(scope, &fetch_request.resolver);
-
-            match result {
-                Ok((status, status_text, response_headers, body_data)) => {
-                    // Allocate a stream ID for this fetch
-                    let stream_id = {
-                        let mut next_id = next_stream_id_ref.borrow_mut();
-                        let id = *next_id;
-                        *next_id += 1;
-                        id
-                    };
 
-                    // Store the body data for streaming
-                    let streaming_fetch = crate::isolate_state::StreamingFetch {
-                        stream_id,
-                        body_data,
-                        offset: 0,
-                    };
+        let pool = self.fetch_pool.get_or_insert_with(FetchWorkerPool::new);
 
-                    {
-                        let isolate: &mut v8::Isolate = scope;
-                        let state = crate::IsolateState::get(isolate);
-                        let streaming_fetches = state.borrow().streaming_fetches.clone();
-                        streaming_fetches
-                            .borrow_mut()
-                            .insert(stream_id, streaming_fetch);
-                    }
-
-                    // Create response object
-                    let obj = v8::Object::new(scope);
+        for fetch_request in fetches {
+            self.in_flight_fetches
+                .insert(fetch_request.id, fetch_request.resolver);
+            pool.submit(FetchJob {
+                id: fetch_request.id,
+                agent: agent.clone(),
+                url: fetch_request.url,
+                method: fetch_request.method,
+                headers: fetch_request.headers,
+                body: fetch_request.body,
+            });
+        }
+    }
 
-                    // Get or create cached string keys
-                    let isolate: &mut v8::Isolate = scope;
-                    let state = crate::IsolateState::get(isolate);
-                    let cache = state.borrow().string_cache.clone();
-                    let mut cache_borrow = cache.borrow_mut();
+    /// Resolve the promises of fetches that completed on worker threads
+    #[inline]
+    fn poll_fetch_completions(&mut self, scope: &mut v8::PinScope) {
+        let Some(pool) = self.fetch_pool.as_mut() else {
+            return;
+        };
+        if pool.in_flight() == 0 {
+            return;
+        }
 
-                    // Set streamId
-                    let stream_id_key = v8::String::new(scope, "streamId").unwrap();
-                    let stream_id_value = v8::Number::new(scope, stream_id as f64);
-                    obj.set(scope, stream_id_key.into(), stream_id_value.into());
+        let mut completions: SmallVec<[FetchCompletion; 4]> = SmallVec::new();
+        while let Some(completion) = pool.try_take() {
+            completions.push(completion);
+        }
 
-                    // Set status (using cached string)
-                    let status_key =
-                        crate::get_or_create_cached_string!(scope, cache_borrow, status, "status");
-                    let status_value = v8::Integer::new(scope, status as i32);
-                    obj.set(scope, status_key.into(), status_value.into());
+        let isolate: &mut v8::Isolate = scope;
+        let state = crate::IsolateState::get(isolate);
+        let body_senders = state.borrow().fetch_body_senders.clone();
+        let header_pool = state.borrow().header_vec_pool.clone();
 
-                    // Set statusText (using cached string)
-                    let status_text_key = crate::get_or_create_cached_string!(
-                        scope,
-                        cache_borrow,
-                        status_text,
-                        "statusText"
-                    );
-                    let status_text_value = v8::String::new(scope, &status_text).unwrap();
-                    obj.set(scope, status_text_key.into(), status_text_value.into());
+        for completion in completions {
+            // The server may respond before a streamed request body is finished
+            body_senders.borrow_mut().remove(&completion.id);
 
-                    // Set headers (using cached string)
-                    let headers_key = crate::get_or_create_cached_string!(
-                        scope,
-                        cache_borrow,
-                        headers,
-                        "headers"
-                    );
+            let Some(resolver) = self.in_flight_fetches.remove(&completion.id) else {
+                // The fetch was aborted; its promise was already rejected from JS
+                let mut headers = completion.request_headers;
+                headers.clear();
+                header_pool.put(headers);
+                continue;
+            };
+            Self::resolve_fetch(scope, &resolver, completion);
+        }
+    }
 
-                    drop(cache_borrow);
-                    let headers_len = response_headers.len() as i32;
-                    let headers_array = v8::Array::new(scope, headers_len);
-                    for (i, (key, value)) in response_headers.iter().enumerate() {
-                        let entry = v8::Array::new(scope, 2);
-                        let key_str = v8::String::new(scope, key).unwrap();
-                        let value_str = v8::String::new(scope, value).unwrap();
-                        entry.set_index(scope, 0, key_str.into());
-                        entry.set_index(scope, 1, value_str.into());
-                        headers_array.set_index(scope, i as u32, entry.into());
-                    }
-                    obj.set(scope, headers_key.into(), headers_array.into());
+    /// Drop fetches that were aborted from JS.
+    /// Queued requests are never sent; requests already running on a worker
+    /// finish there, but their result is discarded.
+    #[inline]
+    fn abort_marked_fetches(&mut self, scope: &mut v8::PinScope) {
+        let mut to_abort_borrow = self.fetches_to_abort.borrow_mut();
+        if to_abort_borrow.is_empty() {
+            return;
+        }
+        let to_abort: SmallVec<[u64; 4]> = to_abort_borrow.drain(..).collect();
+        drop(to_abort_borrow);
 
-                    // Return response headers to pool (cleared for reuse)
-                    let mut resp_headers = response_headers;
-                    resp_headers.clear();
-                    header_pool.put(resp_headers);
+        let isolate: &mut v8::Isolate = scope;
+        let header_pool = crate::IsolateState::get(isolate)
+            .borrow()
+            .header_vec_pool
+            .clone();
 
-                    let _ = resolver.resolve(scope, obj.into());
-                }
-                Err(err) => {
-                    let error_msg = v8::String::new(scope, &err).unwrap();
-                    let error = v8::Exception::error(scope, error_msg);
-                    let _ = resolver.reject(scope, error);
-                }
+        for id in to_abort {
+            if self.in_flight_fetches.remove(&id).is_some() {
+                continue;
+            }
+            let mut pending = self.pending_fetches.borrow_mut();
+            if let Some(index) = pending.iter().position(|request| request.id == id) {
+                let mut headers = pending.remove(index).headers;
+                headers.clear();
+                header_pool.put(headers);
             }
         }
     }
 
-    /// Execute an HTTP request using ureq (streaming version)
-    /// Returns (status, status_text, headers, body_data)
-    fn execute_fetch_streaming(
-        agent: &ureq::Agent,
-        url: &str,
-        method: &str,
-        headers: &[(String, String)],
-        body: Option<&str>,
-    ) -> Result<FetchResponseData, String> {
-        // Build and execute the request based on method
-        let response = match method {
-            "GET" => {
-                let mut req = agent.get(url);
-                for (key, value) in headers {
-                    req = req.header(key, value);
-                }
-                req.call()
-            }
-            "HEAD" => {
-                let mut req = agent.head(url);
-                for (key, value) in headers {
-                    req = req.header(key, value);
-                }
-                req.call()
-            }
-            "DELETE" => {
-                let mut req = agent.delete(url);
-                for (key, value) in headers {
-                    req = req.header(key, value);
-                }
-                req.call()
-            }
-            "POST" => {
-                let mut req = agent.post(url);
-                for (key, value) in headers {
-                    req = req.header(key, value);
-                }
-                req.send(body.unwrap_or(""))
-            }
-            "PUT" => {
-                let mut req = agent.put(url);
-                for (key, value) in headers {
-                    req = req.header(key, value);
-                }
-                req.send(body.unwrap_or(""))
-            }
-            "PATCH" => {
-                let mut req = agent.patch(url);
-                for (key, value) in headers {
-                    req = req.header(key, value);
-                }
-                req.send(body.unwrap_or(""))
-            }
-            _ => return Err(format!("Unsupported HTTP method: {}", method)),
-        };
+    /// Block until a fetch completes or `timeout` elapses (`None` waits for the next completion)
+    fn wait_for_fetches(&mut self, timeout: Option<Duration>) {
+        if let Some(pool) = self.fetch_pool.as_mut() {
+            pool.wait(timeout);
+        }
+    }
 
-        match response {
-            Ok(mut response) => {
-                let status_code = response.status();
-                let status = status_code.as_u16();
-                let status_text = status_code
-                    .canonical_reason()
-                    .unwrap_or("Unknown")
-                    .to_string();
-
-                // Get headers - ureq 3.x uses http crate's HeaderMap
-                let headers_map = response.headers();
-                let mut response_headers = Vec::with_capacity(headers_map.len());
-                for (name, value) in headers_map {
-                    if let Ok(value_str) = value.to_str() {
-                        response_headers.push((name.as_str().to_string(), value_str.to_string()));
-                    }
+    /// Settle a fetch promise with the result produced by a worker
+    fn resolve_fetch(
+        scope: &mut v8::PinScope,
+        resolver: &v8::Global<v8::PromiseResolver>,
+        completion: FetchCompletion,
+    ) {
+        // Get next_stream_id and header pool from isolate state
+        let isolate: &mut v8::Isolate = scope;
+        let state = crate::IsolateState::get(isolate);
+        let next_stream_id_ref = state.borrow().next_stream_id.clone();
+        let header_pool = state.borrow().header_vec_pool.clone();
+
+        // Return the request headers to the pool (cleared for reuse)
+        let mut headers = completion.request_headers;
+        headers.clear();
+        header_pool.put(headers);
+
+        let resolver = v8::Local::new(scope, resolver);
+
+        match completion.result {
+            Ok((status, status_text, response_headers, body_data)) => {
+                // Allocate a stream ID for this fetch
+                let stream_id = {
+                    let mut next_id = next_stream_id_ref.borrow_mut();
+                    let id = *next_id;
+                    *next_id += 1;
+                    id
+                };
+
+                // Store the body data for streaming
+                let streaming_fetch = crate::isolate_state::StreamingFetch {
+                    stream_id,
+                    body_data,
+                    offset: 0,
+                };
+
+                {
+                    let streaming_fetches = state.borrow().streaming_fetches.clone();
+                    streaming_fetches
+                        .borrow_mut()
+                        .insert(stream_id, streaming_fetch);
                 }
 
-                // Read the body into a vector
-                match response.body_mut().read_to_vec() {
-                    Ok(body_data) => Ok((status, status_text, response_headers, body_data)),
-                    Err(e) => Err(format!("Failed to read response body: {}", e)),
+                // Create response object
+                let obj = v8::Object::new(scope);
+
+                // Get or create cached string keys
+                let cache = state.borrow().string_cache.clone();
+                let mut cache_borrow = cache.borrow_mut();
+
+                // Set streamId
+                let stream_id_key = v8::String::new(scope, "streamId").unwrap();
+                let stream_id_value = v8::Number::new(scope, stream_id as f64);
+                obj.set(scope, stream_id_key.into(), stream_id_value.into());
+
+                // Set status (using cached string)
+                let status_key =
+                    crate::get_or_create_cached_string!(scope, cache_borrow, status, "status");
+                let status_value = v8::Integer::new(scope, status as i32);
+                obj.set(scope, status_key.into(), status_value.into());
+
+                // Set statusText (using cached string)
+                let status_text_key = crate::get_or_create_cached_string!(
+                    scope,
+                    cache_borrow,
+                    status_text,
+                    "statusText"
+                );
+                let status_text_value = v8::String::new(scope, &status_text).unwrap();
+                obj.set(scope, status_text_key.into(), status_text_value.into());
+
+                // Set headers (using cached string)
+                let headers_key =
+                    crate::get_or_create_cached_string!(scope, cache_borrow, headers, "headers");
+
+                drop(cache_borrow);
+                let headers_len = response_headers.len() as i32;
+                let headers_array = v8::Array::new(scope, headers_len);
+                for (i, (key, value)) in response_headers.iter().enumerate() {
+                    let entry = v8::Array::new(scope, 2);
+                    let key_str = v8::String::new(scope, key).unwrap();
+                    let value_str = v8::String::new(scope, value).unwrap();
+                    entry.set_index(scope, 0, key_str.into());
+                    entry.set_index(scope, 1, value_str.into());
+                    headers_array.set_index(scope, i as u32, entry.into());
                 }
+                obj.set(scope, headers_key.into(), headers_array.into());
+
+                // Return response headers to pool (cleared for reuse)
+                let mut resp_headers = response_headers;
+                resp_headers.clear();
+                header_pool.put(resp_headers);
+
+                let _ = resolver.resolve(scope, obj.into());
+            }
+            Err(err) => {
+                let error_msg = v8::String::new(scope, &err).unwrap();
+                let error = v8::Exception::error(scope, error_msg);
+                let _ = resolver.reject(scope, error);
             }
-            Err(err) => Err(format!("Network error: {}", err)),
         }
     }
 
-    /// Run the event loop until there are no more pending operations
-    pub(crate) fn run(&mut self, scope: &mut v8::PinScope) {
+    /// Run the event loop until there are no more pending operations, or a
+    /// promise rejection or an exception a callback throws isn't handled
+    pub(crate) fn run(&mut self, scope: &mut v8::PinScope) -> Result<(), crate::JsError> {
         // First, add any pending timers
         self.add_pending_timers();
 
         while self.has_pending_timers() {
-            // Process pending fetch requests
+            // Stop once the JavaScript is terminated
+            crate::termination::check(scope)?;
+
+            // Reload the modules that changed, when hot reloading
+            crate::hot_reload::poll(scope);
+
+            // Drop fetches aborted since the last iteration
+            self.abort_marked_fetches(scope);
+
+            // Dispatch pending fetch requests to worker threads
             self.process_fetches(scope);
 
+            // Resolve fetches that finished on worker threads
+            self.poll_fetch_completions(scope);
+
             // Poll dgram sockets for incoming data
             self.poll_dgram_sockets(scope);
 
-            // Process all microtasks
+            // Hand incoming HTTP requests to their handlers
+            self.poll_servers(scope);
+
+            // Accept TCP connections and poll TCP sockets
+            self.poll_tcp(scope);
+
+            // Poll WebSocket connections
+            self.poll_websockets(scope);
+
+            // Process all microtasks, then stop at an exception the callbacks
+            // above threw or a rejection they left, if unhandled
             scope.perform_microtask_checkpoint();
+            take_unhandled(scope)?;
 
             // Check again if we have pending operations after processing fetches
             if !self.has_pending_timers() {
                 break;
             }
 
-            // Determine sleep duration
-            let has_dgram_sockets = self.has_ref_dgram_sockets();
-            let sleep_duration = if has_dgram_sockets {
-                // When dgram sockets are active, use a short poll interval
-                // This allows us to check for incoming UDP data frequently
+            // Determine how long we may wait before the next timer or socket poll
+            let has_sockets = self.has_ref_dgram_sockets() || !self.active_net.borrow().is_empty();
+            let wait_duration = if has_sockets {
+                // When sockets or servers are active, use a short poll interval
+                // This allows us to check for incoming data and connections frequently
                 Some(Duration::from_millis(10))
-            } else if let Some(next_time) = self.next_fire_time() {
-                let now = Instant::now();
-                if next_time > now {
-                    Some(next_time - now)
-                } else {
-                    None
-                }
             } else {
-                None
+                self.next_fire_time()
+                    .map(|next_time| next_time.saturating_duration_since(Instant::now()))
+            };
+            // Wake up in time to check for changed modules, when hot reloading
+            let wait_duration = match (wait_duration, crate::hot_reload::poll_interval(scope)) {
+                (Some(wait), Some(poll)) => Some(wait.min(poll)),
+                (wait, poll) => wait.or(poll),
+            };
+            // And in time to stop when the execution timeout passes
+            let wait_duration = match (wait_duration, crate::termination::time_left(scope)) {
+                (Some(wait), Some(left)) => Some(wait.min(left)),
+                (wait, left) => wait.or(left),
             };
 
-            if let Some(duration) = sleep_duration {
+            if !self.in_flight_fetches.is_empty() {
+                // Wake up as soon as a fetch completes rather than sleeping blindly
+                self.wait_for_fetches(wait_duration);
+            } else if let Some(duration) = wait_duration
+                && !duration.is_zero()
+            {
                 std::thread::sleep(duration);
             }
 
@@ -542,7 +906,7 @@ impl EventLoop {
             for (timer_id, callback, is_interval) in ready_timers {
                 let callback_local = v8::Local::new(scope, &callback);
                 let recv = v8::undefined(scope).into();
-                let _ = callback_local.call(scope, recv, &[]);
+                crate::uncaught_exception::call(scope, callback_local, recv, &[]);
 
                 if is_interval {
                     // Reschedule interval timers
@@ -558,26 +922,58 @@ impl EventLoop {
 
             // Add any timers that were queued during callbacks
             self.add_pending_timers();
+
+            // Drop fetches aborted during callbacks so they don't keep the loop alive
+            self.abort_marked_fetches(scope);
+
+            // Stop at an exception a timer threw, if unhandled. The rejections
+            // the timers left are dropped with it, so no later run reports them.
+            if let Err(error) = crate::uncaught_exception::take(scope) {
+                crate::promise_rejection::discard(scope);
+                return Err(error);
+            }
         }
 
         // Final microtask checkpoint
+        crate::termination::check(scope)?;
         scope.perform_microtask_checkpoint();
+        take_unhandled(scope)
     }
 
     /// Process ready timers without blocking (suitable for REPL)
     /// This method executes timers that are ready to fire and returns immediately
-    pub(crate) fn tick(&mut self, scope: &mut v8::PinScope) {
+    pub(crate) fn tick(&mut self, scope: &mut v8::PinScope) -> Result<(), crate::JsError> {
         // Add any pending timers
         self.add_pending_timers();
 
-        // Process pending fetch requests
+        // Reload the modules that changed, when hot reloading
+        crate::hot_reload::poll(scope);
+
+        // Drop fetches aborted since the last tick
+        self.abort_marked_fetches(scope);
+
+        // Dispatch pending fetch requests to worker threads
         self.process_fetches(scope);
 
+        // Resolve fetches that finished on worker threads
+        self.poll_fetch_completions(scope);
+
         // Poll dgram sockets for incoming data
         self.poll_dgram_sockets(scope);
 
-        // Process all microtasks
+        // Hand incoming HTTP requests to their handlers
+        self.poll_servers(scope);
+
+        // Accept TCP connections and poll TCP sockets
+        self.poll_tcp(scope);
+
+        // Poll WebSocket connections
+        self.poll_websockets(scope);
+
+        // Process all microtasks, then stop at an exception or a rejection
+        // that nothing handled. The timers run on the next tick.
         scope.perform_microtask_checkpoint();
+        take_unhandled(scope)?;
 
         // Collect and execute ready timers (without sleeping)
         let ready_timers = self.collect_ready_timers();
@@ -585,7 +981,7 @@ impl EventLoop {
         for (timer_id, callback, is_interval) in ready_timers {
             let callback_local = v8::Local::new(scope, &callback);
             let recv = v8::undefined(scope).into();
-            let _ = callback_local.call(scope, recv, &[]);
+            crate::uncaught_exception::call(scope, callback_local, recv, &[]);
 
             if is_interval {
                 // Reschedule interval timers
@@ -604,6 +1000,21 @@ impl EventLoop {
 
         // Final microtask checkpoint
         scope.perform_microtask_checkpoint();
+        take_unhandled(scope)
+    }
+}
+
+/// Dispatch the promise rejections left unhandled, and take the exception
+/// callbacks threw that nothing handled, returning the first of them. Both
+/// are consumed either way, so none is left for the next run. The exception
+/// comes first, as the callbacks ran before the microtasks that rejected.
+fn take_unhandled(scope: &mut v8::PinScope) -> Result<(), crate::JsError> {
+    let rejection = crate::promise_rejection::dispatch(scope);
+    let exception = crate::uncaught_exception::take(scope);
+    match (exception, rejection) {
+        (Err(error), _) => Err(error),
+        (Ok(()), Err(error)) => Err(error),
+        (Ok(()), Ok(())) => Ok(()),
     }
 }
 
@@ -614,7 +1025,9 @@ impl Default for EventLoop {
             Rc::new(RefCell::new(Vec::new())),
             Rc::new(RefCell::new(1)),
             Rc::new(RefCell::new(Vec::new())),
+            Rc::new(RefCell::new(Vec::new())),
             Rc::new(RefCell::new(rustc_hash::FxHashMap::default())),
+            Rc::new(RefCell::new(crate::isolate_state::ActiveNet::default())),
         )
     }
 }
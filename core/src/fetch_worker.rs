@@ -0,0 +1,302 @@
+//! Worker threads for executing HTTP requests off the isolate thread.
+//!
+//! `fetch()` calls are queued by `fetch_send` and dispatched here by the event
+//! loop. Each request runs on one of a small pool of worker threads, and the
+//! result is sent back over a channel so the event loop can resolve the
+//! matching promise on the isolate thread. This lets several fetches overlap
+//! and keeps timers firing while a slow server responds.
+
+use std::collections::VecDeque;
+use std::io::Read;
+use std::sync::mpsc::{self, Receiver, Sender};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+/// Maximum number of worker threads used for HTTP requests.
+/// Workers are spawned lazily, so short scripts that fetch once only pay for one thread.
+const MAX_FETCH_WORKERS: usize = 16;
+
+/// Response data produced by a worker: (status, status_text, headers, body_data)
+pub(crate) type FetchResponseData = (u16, String, Vec<(String, String)>, Vec<u8>);
+
+/// A request body, passed to ureq as raw bytes
+pub(crate) enum FetchBody {
+    /// A body whose bytes are known up front (strings, BufferSources)
+    Bytes(Vec<u8>),
+    /// A body fed chunk by chunk from a JS ReadableStream, sent with chunked transfer encoding
+    Stream(Receiver<BodyChunk>),
+}
+
+/// A message from the isolate thread to a worker uploading a streamed body
+pub(crate) enum BodyChunk {
+    Data(Vec<u8>),
+    /// The source stream errored or the fetch was aborted; the request fails
+    Error(String),
+}
+
+/// Adapts the body chunk channel to `Read` so ureq can stream it.
+/// The body ends when the sender is dropped.
+struct BodyChunkReader {
+    rx: Receiver<BodyChunk>,
+    current: Vec<u8>,
+    offset: usize,
+}
+
+impl Read for BodyChunkReader {
+    fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+        while self.offset >= self.current.len() {
+            match self.rx.recv() {
+                Ok(BodyChunk::Data(data)) => {
+                    self.current = data;
+                    self.offset = 0;
+                }
+                Ok(BodyChunk::Error(message)) => return Err(std::io::Error::other(message)),
+                Err(_) => return Ok(0),
+            }
+        }
+        let len = buf.len().min(self.current.len() - self.offset);
+        buf[..len].copy_from_slice(&self.current[self.offset..self.offset + len]);
+        self.offset += len;
+        Ok(len)
+    }
+}
+
+/// A fetch request handed to a worker thread
+pub(crate) struct FetchJob {
+    pub(crate) id: u64,
+    pub(crate) agent: ureq::Agent,
+    pub(crate) url: String,
+    pub(crate) method: String,
+    pub(crate) headers: Vec<(String, String)>,
+    pub(crate) body: Option<FetchBody>,
+}
+
+/// The outcome of a fetch request, sent back to the isolate thread
+pub(crate) struct FetchCompletion {
+    pub(crate) id: u64,
+    /// The request headers vector, returned so it can be recycled into the header pool
+    pub(crate) request_headers: Vec<(String, String)>,
+    pub(crate) result: Result<FetchResponseData, String>,
+}
+
+/// A lazily grown pool of threads executing fetch jobs.
+pub(crate) struct FetchWorkerPool {
+    job_tx: Option<Sender<FetchJob>>,
+    job_rx: Arc<Mutex<Receiver<FetchJob>>>,
+    completion_tx: Sender<FetchCompletion>,
+    completion_rx: Receiver<FetchCompletion>,
+    // Completions received while waiting, not yet handed to the event loop
+    ready: VecDeque<FetchCompletion>,
+    worker_count: usize,
+    in_flight: usize,
+}
+
+impl FetchWorkerPool {
+    pub(crate) fn new() -> Self {
+        let (job_tx, job_rx) = mpsc::channel();
+        let (completion_tx, completion_rx) = mpsc::channel();
+        Self {
+            job_tx: Some(job_tx),
+            job_rx: Arc::new(Mutex::new(job_rx)),
+            completion_tx,
+            completion_rx,
+            ready: VecDeque::new(),
+            worker_count: 0,
+            in_flight: 0,
+        }
+    }
+
+    /// Number of jobs submitted whose completion has not been taken yet
+    #[inline]
+    pub(crate) fn in_flight(&self) -> usize {
+        self.in_flight
+    }
+
+    /// Queue a job for execution on a worker thread
+    pub(crate) fn submit(&mut self, job: FetchJob) {
+        self.in_flight += 1;
+
+        // Grow the pool while every existing worker may be busy
+        if self.worker_count < self.in_flight && self.worker_count < MAX_FETCH_WORKERS {
+            self.spawn_worker();
+        }
+
+        if let Some(tx) = &self.job_tx
+            && let Err(mpsc::SendError(job)) = tx.send(job)
+        {
+            // All workers are gone; report the failure through the normal path
+            self.ready.push_back(FetchCompletion {
+                id: job.id,
+                request_headers: job.headers,
+                result: Err("Network error: fetch worker pool is unavailable".to_string()),
+            });
+        }
+    }
+
+    fn spawn_worker(&mut self) {
+        let job_rx = self.job_rx.clone();
+        let completion_tx = self.completion_tx.clone();
+        let spawned = std::thread::Builder::new()
+            .name("jstime-fetch".to_string())
+            .spawn(move || {
+                loop {
+                    // Hold the lock only while waiting for the next job
+                    let job = match job_rx.lock() {
+                        Ok(rx) => rx.recv(),
+                        Err(_) => break,
+                    };
+                    let Ok(job) = job else {
+                        // The pool was dropped
+                        break;
+                    };
+
+                    let result = execute_fetch_streaming(
+                        &job.agent,
+                        &job.url,
+                        &job.method,
+                        &job.headers,
+                        job.body,
+                    );
+
+                    let completion = FetchCompletion {
+                        id: job.id,
+                        request_headers: job.headers,
+                        result,
+                    };
+                    if completion_tx.send(completion).is_err() {
+                        break;
+                    }
+                }
+            });
+
+        if spawned.is_ok() {
+            self.worker_count += 1;
+        }
+    }
+
+    /// Take the next completed fetch without blocking
+    pub(crate) fn try_take(&mut self) -> Option<FetchCompletion> {
+        let completion = self
+            .ready
+            .pop_front()
+            .or_else(|| self.completion_rx.try_recv().ok())?;
+        self.in_flight -= 1;
+        Some(completion)
+    }
+
+    /// Block until a fetch completes or `timeout` elapses.
+    /// A `None` timeout waits until the next completion.
+    /// The completion is kept and later returned by `try_take`.
+    pub(crate) fn wait(&mut self, timeout: Option<Duration>) {
+        if !self.ready.is_empty() {
+            return;
+        }
+        let completion = match timeout {
+            Some(timeout) => self.completion_rx.recv_timeout(timeout).ok(),
+            None => self.completion_rx.recv().ok(),
+        };
+        if let Some(completion) = completion {
+            self.ready.push_back(completion);
+        }
+    }
+}
+
+impl Drop for FetchWorkerPool {
+    fn drop(&mut self) {
+        // Closing the job channel makes idle workers exit.
+        // Busy workers finish their current request and then exit; we don't join
+        // them so a slow server can't block dropping the runtime.
+        self.job_tx.take();
+    }
+}
+
+/// Add request headers to a ureq request builder
+fn with_headers<B>(
+    mut req: ureq::RequestBuilder<B>,
+    headers: &[(String, String)],
+) -> ureq::RequestBuilder<B> {
+    for (key, value) in headers {
+        req = req.header(key, value);
+    }
+    req
+}
+
+/// Send a request body without converting it
+fn send_body(
+    req: ureq::RequestBuilder<ureq::typestate::WithBody>,
+    body: FetchBody,
+) -> Result<ureq::http::Response<ureq::Body>, ureq::Error> {
+    match body {
+        FetchBody::Bytes(bytes) => req.send(&bytes[..]),
+        FetchBody::Stream(rx) => {
+            // No Content-Length is known, so ureq uses chunked transfer encoding
+            let reader = BodyChunkReader {
+                rx,
+                current: Vec::new(),
+                offset: 0,
+            };
+            req.send(ureq::SendBody::from_owned_reader(reader))
+        }
+    }
+}
+
+/// Execute an HTTP request using ureq (streaming version)
+/// Returns (status, status_text, headers, body_data)
+pub(crate) fn execute_fetch_streaming(
+    agent: &ureq::Agent,
+    url: &str,
+    method: &str,
+    headers: &[(String, String)],
+    body: Option<FetchBody>,
+) -> Result<FetchResponseData, String> {
+    // Build and execute the request based on method
+    let response = match method {
+        // Request rejects a body for these, as the Fetch spec requires
+        "GET" => with_headers(agent.get(url), headers).call(),
+        "HEAD" => with_headers(agent.head(url), headers).call(),
+        "DELETE" => {
+            let req = with_headers(agent.delete(url), headers);
+            match body {
+                Some(body) => send_body(req.force_send_body(), body),
+                None => req.call(),
+            }
+        }
+        "POST" | "PUT" | "PATCH" => {
+            let req = match method {
+                "POST" => agent.post(url),
+                "PUT" => agent.put(url),
+                _ => agent.patch(url),
+            };
+            let req = with_headers(req, headers);
+            send_body(req, body.unwrap_or(FetchBody::Bytes(Vec::new())))
+        }
+        _ => return Err(format!("Unsupported HTTP method: {}", method)),
+    };
+
+    match response {
+        Ok(mut response) => {
+            let status_code = response.status();
+            let status = status_code.as_u16();
+            let status_text = status_code
+                .canonical_reason()
+                .unwrap_or("Unknown")
+                .to_string();
+
+            // Get headers - ureq 3.x uses http crate's HeaderMap
+            let headers_map = response.headers();
+            let mut response_headers = Vec::with_capacity(headers_map.len());
+            for (name, value) in headers_map {
+                if let Ok(value_str) = value.to_str() {
+                    response_headers.push((name.as_str().to_string(), value_str.to_string()));
+                }
+            }
+
+            // Read the body into a vector
+            match response.body_mut().read_to_vec() {
+                Ok(body_data) => Ok((status, status_text, response_headers, body_data)),
+                Err(e) => Err(format!("Failed to read response body: {}", e)),
+            }
+        }
+        Err(err) => Err(format!("Network error: {}", err)),
+    }
+}
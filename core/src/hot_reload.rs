@@ -0,0 +1,280 @@
+//! Hot module reloading: modules whose files change are loaded again while
+//! the program keeps running, with `import.meta.hot` to carry state across.
+//!
+//! A change invalidates the module and the modules that import it, up to
+//! the modules that accept updates of themselves with
+//! `import.meta.hot.accept()`, or up to the entry modules if none do. Those
+//! boundaries are loaded again, which loads the invalidated modules they
+//! import, and their accept callbacks are called with the new namespace.
+//! First, the dispose callbacks of the invalidated modules are called with
+//! `import.meta.hot.data`, which the module's next instance gets.
+
+use crate::IsolateState;
+use rustc_hash::{FxHashMap, FxHashSet};
+use std::collections::VecDeque;
+use std::time::{Duration, Instant};
+
+/// How often the event loop checks module files for changes
+pub(crate) const POLL_INTERVAL: Duration = Duration::from_millis(100);
+
+pub(crate) struct HotModules {
+    // Callbacks passed to import.meta.hot.accept(), by module path. A module
+    // that accepted without a callback has none.
+    accepted: FxHashMap<String, Vec<v8::Global<v8::Function>>>,
+    // Callbacks passed to import.meta.hot.dispose(), by module path
+    disposers: FxHashMap<String, Vec<v8::Global<v8::Function>>>,
+    // import.meta.hot.data of each module, kept across reloads
+    data: FxHashMap<String, v8::Global<v8::Object>>,
+    last_poll: Instant,
+}
+
+impl HotModules {
+    pub(crate) fn new() -> Self {
+        Self {
+            accepted: FxHashMap::default(),
+            disposers: FxHashMap::default(),
+            data: FxHashMap::default(),
+            last_poll: Instant::now(),
+        }
+    }
+
+    /// Drop the callbacks of the module at `path`, which won't be called as
+    /// it's loaded again without an update. Its data is kept.
+    pub(crate) fn forget(&mut self, path: &str) {
+        self.accepted.remove(path);
+        self.disposers.remove(path);
+    }
+}
+
+/// Add `import.meta.hot` for the module at `path`, if hot reloading is on
+pub(crate) fn init_import_meta(scope: &mut v8::PinScope, meta: v8::Local<v8::Object>, path: &str) {
+    let isolate: &mut v8::Isolate = scope;
+    let state = IsolateState::get(isolate);
+    let data = match state.borrow().hot_reload.as_ref() {
+        Some(hot) => hot.data.get(path).cloned(),
+        None => return,
+    };
+    let data = match data {
+        Some(data) => v8::Local::new(scope, data),
+        None => {
+            let data = v8::Object::new(scope);
+            let global = v8::Global::new(scope, data);
+            if let Some(hot) = state.borrow_mut().hot_reload.as_mut() {
+                hot.data.insert(path.to_string(), global);
+            }
+            data
+        }
+    };
+
+    let cache = state.borrow().string_cache.clone();
+    let mut cache_borrow = cache.borrow_mut();
+    let hot_key = crate::get_or_create_cached_string!(scope, cache_borrow, hot, "hot");
+    let accept_key = crate::get_or_create_cached_string!(scope, cache_borrow, accept, "accept");
+    let dispose_key = crate::get_or_create_cached_string!(scope, cache_borrow, dispose, "dispose");
+    let data_key = crate::get_or_create_cached_string!(scope, cache_borrow, data, "data");
+    drop(cache_borrow);
+
+    // The callbacks register for this module, so they carry the path as data
+    let hot = v8::Object::new(scope);
+    let path_value = v8::String::new(scope, path).unwrap();
+    if let Some(accept_fn) = v8::Function::builder(hot_accept)
+        .data(path_value.into())
+        .build(scope)
+    {
+        hot.set(scope, accept_key.into(), accept_fn.into());
+    }
+    if let Some(dispose_fn) = v8::Function::builder(hot_dispose)
+        .data(path_value.into())
+        .build(scope)
+    {
+        hot.set(scope, dispose_key.into(), dispose_fn.into());
+    }
+    hot.set(scope, data_key.into(), data.into());
+    meta.set(scope, hot_key.into(), hot.into());
+}
+
+/// import.meta.hot.accept(callback?): reload this module by itself when it
+/// changes, instead of the modules that import it
+fn hot_accept(
+    scope: &mut v8::PinScope,
+    args: v8::FunctionCallbackArguments,
+    _retval: v8::ReturnValue,
+) {
+    let path = args.data().to_rust_string_lossy(scope);
+    let callback = args.get(0);
+    let callback = if callback.is_undefined() {
+        None
+    } else if let Ok(callback) = v8::Local::<v8::Function>::try_from(callback) {
+        Some(v8::Global::new(scope, callback))
+    } else {
+        crate::error::throw_type_error(
+            scope,
+            "import.meta.hot.accept() only accepts updates of the module itself, with an optional callback",
+        );
+        return;
+    };
+
+    let isolate: &mut v8::Isolate = scope;
+    let state = IsolateState::get(isolate);
+    if let Some(hot) = state.borrow_mut().hot_reload.as_mut() {
+        hot.accepted.entry(path).or_default().extend(callback);
+    }
+}
+
+/// import.meta.hot.dispose(callback): call `callback` with
+/// import.meta.hot.data before this module is replaced
+fn hot_dispose(
+    scope: &mut v8::PinScope,
+    args: v8::FunctionCallbackArguments,
+    _retval: v8::ReturnValue,
+) {
+    if !crate::error::check_arg_count(scope, &args, 1, "import.meta.hot.dispose") {
+        return;
+    }
+    let path = args.data().to_rust_string_lossy(scope);
+    let Ok(callback) = v8::Local::<v8::Function>::try_from(args.get(0)) else {
+        crate::error::throw_type_error(
+            scope,
+            "import.meta.hot.dispose() callback must be a function",
+        );
+        return;
+    };
+    let callback = v8::Global::new(scope, callback);
+
+    let isolate: &mut v8::Isolate = scope;
+    let state = IsolateState::get(isolate);
+    if let Some(hot) = state.borrow_mut().hot_reload.as_mut() {
+        hot.disposers.entry(path).or_default().push(callback);
+    }
+}
+
+/// Reload the modules that changed since the last check, if hot reloading is
+/// on and POLL_INTERVAL has passed. Errors are reported on stderr, as
+/// there's no caller to return them to.
+pub(crate) fn poll(scope: &mut v8::PinScope) {
+    let isolate: &mut v8::Isolate = scope;
+    let state = IsolateState::get(isolate);
+    let changed = {
+        let mut state = state.borrow_mut();
+        let state = &mut *state;
+        let Some(hot) = state.hot_reload.as_mut() else {
+            return;
+        };
+        if hot.last_poll.elapsed() < POLL_INTERVAL {
+            return;
+        }
+        hot.last_poll = Instant::now();
+        state.module_map.changed()
+    };
+    if !changed.is_empty()
+        && let Err(e) = reload(scope, &changed)
+    {
+        eprintln!("{e}");
+    }
+}
+
+/// How long the event loop may wait before it should poll, if hot reloading
+/// is on
+pub(crate) fn poll_interval(scope: &mut v8::PinScope) -> Option<Duration> {
+    let isolate: &mut v8::Isolate = scope;
+    let state = IsolateState::get(isolate);
+    let state = state.borrow();
+    state.hot_reload.as_ref().map(|_| POLL_INTERVAL)
+}
+
+/// Invalidate the `changed` modules and the modules that import them, and
+/// load the boundaries of the update again. Returns the invalidated paths,
+/// or the errors of the dispose and accept callbacks and of the loads.
+pub(crate) fn reload(scope: &mut v8::PinScope, changed: &[String]) -> Result<Vec<String>, String> {
+    let isolate: &mut v8::Isolate = scope;
+    let state = IsolateState::get(isolate);
+
+    // Walk up the importers of each changed module to the boundaries
+    let (invalidated, boundaries) = {
+        let state = state.borrow();
+        let accepts = |path: &str| {
+            state
+                .hot_reload
+                .as_ref()
+                .is_some_and(|hot| hot.accepted.contains_key(path))
+        };
+        let mut seen = FxHashSet::default();
+        let mut invalidated = Vec::new();
+        let mut boundaries = Vec::new();
+        let mut queue: VecDeque<String> = changed.iter().cloned().collect();
+        while let Some(path) = queue.pop_front() {
+            if !seen.insert(path.clone()) {
+                continue;
+            }
+            let importers: Vec<String> = state.module_map.importers(&path).cloned().collect();
+            if accepts(&path) || importers.is_empty() {
+                boundaries.push(path.clone());
+            } else {
+                queue.extend(importers);
+            }
+            invalidated.push(path);
+        }
+        (invalidated, boundaries)
+    };
+
+    // The old instances' callbacks; the new ones register their own
+    let mut disposers = Vec::new();
+    let mut accepted = FxHashMap::default();
+    if let Some(hot) = state.borrow_mut().hot_reload.as_mut() {
+        for path in &invalidated {
+            if let Some(callbacks) = hot.disposers.remove(path)
+                && let Some(data) = hot.data.get(path)
+            {
+                disposers.push((callbacks, data.clone()));
+            }
+            if let Some(callbacks) = hot.accepted.remove(path) {
+                accepted.insert(path.clone(), callbacks);
+            }
+        }
+    }
+
+    let mut errors = Vec::new();
+    v8::tc_scope!(let tc, scope);
+    for (callbacks, data) in disposers {
+        let data = v8::Local::new(tc, data);
+        for callback in callbacks {
+            call(tc, &callback, data.into(), &mut errors);
+        }
+    }
+
+    for path in &invalidated {
+        state.borrow_mut().module_map.remove(path);
+    }
+
+    for path in &boundaries {
+        match crate::module::load_module(tc, path) {
+            Ok(namespace) => {
+                for callback in accepted.remove(path).unwrap_or_default() {
+                    call(tc, &callback, namespace.into(), &mut errors);
+                }
+            }
+            Err(e) => errors.push(e),
+        }
+    }
+
+    if errors.is_empty() {
+        Ok(invalidated)
+    } else {
+        Err(errors.join("\n"))
+    }
+}
+
+/// Call `callback` with `arg`, adding the exception it throws to `errors`
+fn call(
+    tc: &mut v8::PinnedRef<'_, v8::TryCatch<v8::HandleScope>>,
+    callback: &v8::Global<v8::Function>,
+    arg: v8::Local<v8::Value>,
+    errors: &mut Vec<String>,
+) {
+    let callback = v8::Local::new(tc, callback);
+    let recv = v8::undefined(tc).into();
+    if callback.call(tc, recv, &[arg]).is_none() && tc.has_caught() {
+        errors.push(crate::error::format_exception(tc));
+        tc.reset();
+    }
+}
@@ -0,0 +1,200 @@
+//! On-disk cache for modules imported from http(s) URLs.
+//!
+//! The cache is content-addressed: sources are stored under the SHA-256 of
+//! their contents in `<cache dir>/remote/blobs/`, and
+//! `<cache dir>/remote/urls/<SHA-256 of the URL>` records which blob each URL
+//! downloaded to. A blob that no longer matches its hash is downloaded again.
+
+use ring::digest;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicU64, Ordering};
+
+/// Whether a specifier is an http(s) URL
+pub(crate) fn is_remote_url(specifier: &str) -> bool {
+    specifier.starts_with("https://") || specifier.starts_with("http://")
+}
+
+/// Whether a specifier is a URL like `node:fs` or `https://...`
+pub(crate) fn has_url_scheme(target: &str) -> bool {
+    match target.find(':') {
+        Some(colon) => {
+            let scheme = &target[..colon];
+            scheme.starts_with(|c: char| c.is_ascii_alphabetic())
+                && scheme
+                    .chars()
+                    .all(|c| c.is_ascii_alphanumeric() || matches!(c, '+' | '-' | '.'))
+        }
+        None => false,
+    }
+}
+
+/// The cache directory used when none is configured: `$JSTIME_CACHE_DIR`,
+/// or `jstime` in the user's cache directory
+pub(crate) fn default_cache_dir() -> PathBuf {
+    if let Some(dir) = std::env::var_os("JSTIME_CACHE_DIR") {
+        return PathBuf::from(dir);
+    }
+    std::env::var_os("XDG_CACHE_HOME")
+        .map(PathBuf::from)
+        .or_else(|| std::env::var_os("HOME").map(|home| PathBuf::from(home).join(".cache")))
+        .unwrap_or_else(std::env::temp_dir)
+        .join("jstime")
+}
+
+/// Lowercase hex SHA-256 digest of `data`
+pub(crate) fn sha256_hex(data: &[u8]) -> String {
+    let digest = digest::digest(&digest::SHA256, data);
+    digest
+        .as_ref()
+        .iter()
+        .map(|b| format!("{:02x}", b))
+        .collect()
+}
+
+pub(crate) struct HttpCache {
+    dir: PathBuf,
+    // Only use modules already in the cache, never downloading
+    cached_only: bool,
+    agent: ureq::Agent,
+}
+
+impl HttpCache {
+    pub(crate) fn new(cache_dir: &Path, cached_only: bool, agent: ureq::Agent) -> Self {
+        Self {
+            dir: cache_dir.join("remote"),
+            cached_only,
+            agent,
+        }
+    }
+
+    /// The source of the module at `url`, from the cache if it's there,
+    /// otherwise downloaded and added to the cache
+    pub(crate) fn load(&self, url: &str) -> Result<String, String> {
+        if let Some(source) = self.read_cached(url) {
+            return Ok(source);
+        }
+        if self.cached_only {
+            return Err(format!(
+                "Cannot load '{}': it isn't cached, and only cached modules can be used",
+                url
+            ));
+        }
+
+        let source = self.download(url)?;
+        // A cache that can't be written only costs a download next time
+        let _ = self.store(url, &source);
+        Ok(source)
+    }
+
+    fn read_cached(&self, url: &str) -> Option<String> {
+        let hash = std::fs::read_to_string(self.url_path(url)).ok()?;
+        let hash = hash.trim();
+        let source = std::fs::read(self.blob_path(hash)).ok()?;
+        if sha256_hex(&source) != hash {
+            return None;
+        }
+        String::from_utf8(source).ok()
+    }
+
+    fn download(&self, url: &str) -> Result<String, String> {
+        let mut response = self
+            .agent
+            .get(url)
+            .call()
+            .map_err(|e| format!("Failed to fetch '{}': {}", url, e))?;
+        let status = response.status();
+        if !status.is_success() {
+            return Err(format!("Failed to fetch '{}': HTTP {}", url, status));
+        }
+        response
+            .body_mut()
+            .read_to_string()
+            .map_err(|e| format!("Failed to read '{}': {}", url, e))
+    }
+
+    fn store(&self, url: &str, source: &str) -> std::io::Result<()> {
+        let hash = sha256_hex(source.as_bytes());
+        write_atomic(&self.blob_path(&hash), source.as_bytes())?;
+        write_atomic(&self.url_path(url), hash.as_bytes())
+    }
+
+    fn blob_path(&self, hash: &str) -> PathBuf {
+        self.dir.join("blobs").join(hash)
+    }
+
+    fn url_path(&self, url: &str) -> PathBuf {
+        self.dir.join("urls").join(sha256_hex(url.as_bytes()))
+    }
+}
+
+/// Write a file through a temporary file and a rename, so that other
+/// threads and processes never read a partly written file
+pub(crate) fn write_atomic(path: &Path, contents: &[u8]) -> std::io::Result<()> {
+    static NEXT_TEMP_ID: AtomicU64 = AtomicU64::new(0);
+
+    let dir = path.parent().unwrap_or_else(|| Path::new("."));
+    std::fs::create_dir_all(dir)?;
+    let temp = dir.join(format!(
+        ".tmp-{}-{}",
+        std::process::id(),
+        NEXT_TEMP_ID.fetch_add(1, Ordering::Relaxed)
+    ));
+    std::fs::write(&temp, contents)?;
+    std::fs::rename(&temp, path).inspect_err(|_| {
+        let _ = std::fs::remove_file(&temp);
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn test_cache(name: &str, cached_only: bool) -> (PathBuf, HttpCache) {
+        let dir = std::env::temp_dir().join(name);
+        let _ = std::fs::remove_dir_all(&dir);
+        let cache = HttpCache::new(&dir, cached_only, ureq::Agent::new_with_defaults());
+        (dir, cache)
+    }
+
+    #[test]
+    fn test_sha256_hex() {
+        assert_eq!(
+            sha256_hex(b"abc"),
+            "ba7816bf8f01cfea414140de5dae2223b00361a396177a9cb410ff61f20015ad"
+        );
+    }
+
+    #[test]
+    fn test_store_and_read_cached() {
+        let (dir, cache) = test_cache("jstime_test_http_cache_store", true);
+        let url = "https://example.com/mod.js";
+
+        assert!(cache.load(url).unwrap_err().contains("isn't cached"));
+        cache.store(url, "export default 1;").unwrap();
+        assert_eq!(cache.load(url).unwrap(), "export default 1;");
+
+        // The same source is stored once for every URL
+        cache
+            .store("https://example.com/copy.js", "export default 1;")
+            .unwrap();
+        assert_eq!(
+            std::fs::read_dir(dir.join("remote/blobs")).unwrap().count(),
+            1
+        );
+
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn test_corrupted_blob_is_ignored() {
+        let (dir, cache) = test_cache("jstime_test_http_cache_corrupt", true);
+        let url = "https://example.com/mod.js";
+
+        cache.store(url, "export default 1;").unwrap();
+        let blob = cache.blob_path(&sha256_hex(b"export default 1;"));
+        std::fs::write(&blob, "export default 2;").unwrap();
+        assert!(cache.load(url).is_err());
+
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+}
@@ -0,0 +1,553 @@
+//! HTTP/1.1 server backing `jstime.serve`.
+//!
+//! Each server has an accept thread, and each connection is handled on its own
+//! thread. A connection thread parses a request, hands it to the isolate thread
+//! over a channel, then writes the response chunks it receives back. The event
+//! loop polls the request channel and calls into JS, so handlers always run on
+//! the isolate thread while socket I/O never blocks it.
+//!
+//! A WebSocket upgrade ends the connection thread's work: it writes the
+//! `101 Switching Protocols` response and hands the stream to the isolate
+//! thread, where `crate::websocket` polls it like any other socket.
+
+use std::io::{BufRead, BufReader, Read, Write};
+use std::net::{SocketAddr, TcpListener, TcpStream};
+use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use std::sync::mpsc::{self, Receiver, Sender};
+use std::time::Duration;
+
+/// Upper bound on the size of the request line plus headers
+const MAX_HEAD_BYTES: u64 = 64 * 1024;
+
+/// Upper bound on the size of a request body; larger ones get a 413
+const MAX_BODY_BYTES: usize = 64 * 1024 * 1024;
+
+/// How many connections a server handles at once; more get a 503
+const MAX_CONNECTIONS: usize = 1024;
+
+/// How long a connection may go without sending anything while a request
+/// is read, or between requests
+const READ_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// A parsed request waiting for a JS handler
+pub(crate) struct IncomingRequest {
+    pub(crate) method: String,
+    pub(crate) target: String,
+    pub(crate) headers: Vec<(String, String)>,
+    pub(crate) body: Vec<u8>,
+    pub(crate) remote_addr: SocketAddr,
+    /// Where the response for this request is sent
+    pub(crate) responder: Sender<ResponseChunk>,
+}
+
+/// A piece of a response, sent from the isolate thread to a connection thread
+pub(crate) enum ResponseChunk {
+    /// Status and headers. Without a content length the body is sent chunked.
+    Head {
+        status: u16,
+        headers: Vec<(String, String)>,
+        content_length: Option<usize>,
+    },
+    Data(Vec<u8>),
+    End,
+    /// The response body failed; the connection is closed without finishing the body
+    Error,
+    /// Switch to the WebSocket protocol: write a 101 response with these headers,
+    /// then send the stream through `stream`
+    Upgrade {
+        headers: Vec<(String, String)>,
+        stream: Sender<std::io::Result<TcpStream>>,
+    },
+}
+
+/// A listening server. Dropping it stops accepting new connections;
+/// requests already handed to JS can still be answered.
+pub(crate) struct HttpServer {
+    local_addr: SocketAddr,
+    shutdown: Arc<AtomicBool>,
+    requests: Receiver<IncomingRequest>,
+}
+
+impl HttpServer {
+    pub(crate) fn bind(addr: &str) -> std::io::Result<Self> {
+        let listener = TcpListener::bind(addr)?;
+        let local_addr = listener.local_addr()?;
+        let shutdown = Arc::new(AtomicBool::new(false));
+        let (tx, rx) = mpsc::channel();
+
+        let flag = shutdown.clone();
+        std::thread::Builder::new()
+            .name("jstime-http-accept".to_string())
+            .spawn(move || accept_loop(listener, tx, flag))?;
+
+        Ok(Self {
+            local_addr,
+            shutdown,
+            requests: rx,
+        })
+    }
+
+    pub(crate) fn local_addr(&self) -> SocketAddr {
+        self.local_addr
+    }
+
+    /// Take the next request that is ready, without blocking
+    pub(crate) fn try_recv(&self) -> Option<IncomingRequest> {
+        self.requests.try_recv().ok()
+    }
+}
+
+impl Drop for HttpServer {
+    fn drop(&mut self) {
+        self.shutdown.store(true, Ordering::SeqCst);
+        // The accept thread is blocked in accept(); connect once to wake it up
+        let mut addr = self.local_addr;
+        if addr.ip().is_unspecified() {
+            addr.set_ip(match addr {
+                SocketAddr::V4(_) => std::net::Ipv4Addr::LOCALHOST.into(),
+                SocketAddr::V6(_) => std::net::Ipv6Addr::LOCALHOST.into(),
+            });
+        }
+        let _ = TcpStream::connect_timeout(&addr, Duration::from_secs(1));
+    }
+}
+
+fn accept_loop(listener: TcpListener, tx: Sender<IncomingRequest>, shutdown: Arc<AtomicBool>) {
+    let connections = Arc::new(AtomicUsize::new(0));
+    for stream in listener.incoming() {
+        if shutdown.load(Ordering::SeqCst) {
+            break;
+        }
+        let Ok(mut stream) = stream else {
+            continue;
+        };
+        if connections.fetch_add(1, Ordering::SeqCst) >= MAX_CONNECTIONS {
+            connections.fetch_sub(1, Ordering::SeqCst);
+            // Don't let a client that doesn't read hold up the accept thread
+            let _ = stream.set_write_timeout(Some(Duration::from_secs(1)));
+            let _ = write_error(&mut stream, 503);
+            continue;
+        }
+        let tx = tx.clone();
+        let count = connections.clone();
+        let spawned = std::thread::Builder::new()
+            .name("jstime-http-conn".to_string())
+            .spawn(move || {
+                let _ = handle_connection(stream, tx);
+                count.fetch_sub(1, Ordering::SeqCst);
+            });
+        if spawned.is_err() {
+            connections.fetch_sub(1, Ordering::SeqCst);
+        }
+    }
+}
+
+/// The parts of a request read before its body
+struct RequestHead {
+    method: String,
+    target: String,
+    headers: Vec<(String, String)>,
+    keep_alive: bool,
+}
+
+impl RequestHead {
+    fn header(&self, name: &str) -> Option<&str> {
+        self.all_headers(name).next()
+    }
+
+    /// The values of every header named `name`, in order
+    fn all_headers<'a>(&'a self, name: &'a str) -> impl Iterator<Item = &'a str> {
+        self.headers
+            .iter()
+            .filter(move |(key, _)| key.eq_ignore_ascii_case(name))
+            .map(|(_, value)| value.as_str())
+    }
+}
+
+/// Why a request couldn't be read
+enum ReadError {
+    /// The connection closed or failed; nothing more can be written
+    Closed,
+    /// The request was malformed; respond with this status and close
+    Status(u16),
+}
+
+impl From<std::io::Error> for ReadError {
+    fn from(_: std::io::Error) -> Self {
+        ReadError::Closed
+    }
+}
+
+fn handle_connection(stream: TcpStream, tx: Sender<IncomingRequest>) -> std::io::Result<()> {
+    let remote_addr = stream.peer_addr()?;
+    let _ = stream.set_nodelay(true);
+    // A client that stops sending doesn't hold the thread forever
+    stream.set_read_timeout(Some(READ_TIMEOUT))?;
+    let mut reader = BufReader::new(stream.try_clone()?);
+    let mut writer = stream;
+
+    loop {
+        let head = match read_head(&mut reader) {
+            Ok(Some(head)) => head,
+            Ok(None) | Err(ReadError::Closed) => return Ok(()),
+            Err(ReadError::Status(status)) => return write_error(&mut writer, status),
+        };
+
+        // Let clients that wait for permission before sending a body proceed
+        if head
+            .header("expect")
+            .is_some_and(|value| value.eq_ignore_ascii_case("100-continue"))
+        {
+            writer.write_all(b"HTTP/1.1 100 Continue\r\n\r\n")?;
+        }
+
+        let body = match read_body(&mut reader, &head) {
+            Ok(body) => body,
+            Err(ReadError::Closed) => return Ok(()),
+            Err(ReadError::Status(status)) => return write_error(&mut writer, status),
+        };
+
+        let is_head = head.method == "HEAD";
+        let keep_alive = head.keep_alive;
+        let (responder, responses) = mpsc::channel();
+        let request = IncomingRequest {
+            method: head.method,
+            target: head.target,
+            headers: head.headers,
+            body,
+            remote_addr,
+            responder,
+        };
+        if tx.send(request).is_err() {
+            // The server was closed
+            return write_error(&mut writer, 503);
+        }
+
+        let first = responses.recv();
+        if let Ok(ResponseChunk::Upgrade { headers, stream }) = first {
+            upgrade_connection(writer, &reader, headers, stream);
+            return Ok(());
+        }
+        if !write_response(&mut writer, first, &responses, is_head, keep_alive)? {
+            return Ok(());
+        }
+    }
+}
+
+/// Write the 101 response for a WebSocket upgrade and hand the stream to the isolate thread
+fn upgrade_connection(
+    mut writer: TcpStream,
+    reader: &BufReader<TcpStream>,
+    headers: Vec<(String, String)>,
+    stream: Sender<std::io::Result<TcpStream>>,
+) {
+    // Clients wait for the handshake response before sending frames, so
+    // anything already buffered is a protocol violation
+    if !reader.buffer().is_empty() {
+        let _ = write_error(&mut writer, 400);
+        let _ = stream.send(Err(std::io::Error::new(
+            std::io::ErrorKind::InvalidData,
+            "Received data before the WebSocket handshake completed",
+        )));
+        return;
+    }
+
+    let mut head = format!("HTTP/1.1 101 {}\r\n", reason_phrase(101));
+    for (name, value) in &headers {
+        if name.contains(['\r', '\n', ':']) || value.contains(['\r', '\n']) {
+            continue;
+        }
+        head.push_str(&format!("{}: {}\r\n", name, value));
+    }
+    head.push_str("\r\n");
+    let result = writer
+        .write_all(head.as_bytes())
+        .and_then(|()| writer.flush())
+        // The WebSocket is polled from now on, and may be idle for long
+        .and_then(|()| writer.set_read_timeout(None))
+        .map(|()| writer);
+    let _ = stream.send(result);
+}
+
+/// Read one line, failing if it would exceed the remaining head budget
+fn read_line(reader: &mut BufReader<TcpStream>, budget: &mut u64) -> Result<String, ReadError> {
+    let mut line = Vec::new();
+    let read = reader.by_ref().take(*budget).read_until(b'\n', &mut line)?;
+    if read == 0 {
+        return Err(ReadError::Closed);
+    }
+    if !line.ends_with(b"\n") {
+        return Err(ReadError::Status(431));
+    }
+    *budget -= read as u64;
+    line.pop();
+    if line.ends_with(b"\r") {
+        line.pop();
+    }
+    String::from_utf8(line).map_err(|_| ReadError::Status(400))
+}
+
+/// Read the request line and headers. Returns None if the connection closed between requests.
+fn read_head(reader: &mut BufReader<TcpStream>) -> Result<Option<RequestHead>, ReadError> {
+    let mut budget = MAX_HEAD_BYTES;
+
+    // Skip empty lines before the request line, as RFC 9112 allows
+    let request_line = loop {
+        match read_line(reader, &mut budget) {
+            Ok(line) if line.is_empty() => continue,
+            Ok(line) => break line,
+            Err(ReadError::Closed) => return Ok(None),
+            Err(err) => return Err(err),
+        }
+    };
+
+    let mut parts = request_line.split(' ');
+    let (Some(method), Some(target), Some(version), None) =
+        (parts.next(), parts.next(), parts.next(), parts.next())
+    else {
+        return Err(ReadError::Status(400));
+    };
+    let is_http_11 = match version {
+        "HTTP/1.1" => true,
+        "HTTP/1.0" => false,
+        _ => return Err(ReadError::Status(505)),
+    };
+    if method.is_empty() || !method.bytes().all(|b| b.is_ascii_alphabetic()) {
+        return Err(ReadError::Status(400));
+    }
+
+    let mut headers = Vec::new();
+    loop {
+        let line = read_line(reader, &mut budget)?;
+        if line.is_empty() {
+            break;
+        }
+        let Some((name, value)) = line.split_once(':') else {
+            return Err(ReadError::Status(400));
+        };
+        if name.is_empty() || name.ends_with(' ') {
+            return Err(ReadError::Status(400));
+        }
+        headers.push((name.to_ascii_lowercase(), value.trim().to_string()));
+    }
+
+    let mut head = RequestHead {
+        method: method.to_string(),
+        target: target.to_string(),
+        headers,
+        keep_alive: is_http_11,
+    };
+    if let Some(connection) = head.header("connection") {
+        let connection = connection.to_ascii_lowercase();
+        if connection.split(',').any(|token| token.trim() == "close") {
+            head.keep_alive = false;
+        } else if connection
+            .split(',')
+            .any(|token| token.trim() == "keep-alive")
+        {
+            head.keep_alive = true;
+        }
+    }
+    // A proxy in front could frame a request with both differently, so the
+    // connection isn't reused after it
+    if head.header("transfer-encoding").is_some() && head.header("content-length").is_some() {
+        head.keep_alive = false;
+    }
+    Ok(Some(head))
+}
+
+/// Read the request body, either chunked or sized by Content-Length
+fn read_body(reader: &mut BufReader<TcpStream>, head: &RequestHead) -> Result<Vec<u8>, ReadError> {
+    // Only framings every reader agrees on are accepted: a single `chunked`
+    // encoding, or a single length
+    let mut encodings = head.all_headers("transfer-encoding");
+    if let Some(encoding) = encodings.next() {
+        if encodings.next().is_some() || !encoding.eq_ignore_ascii_case("chunked") {
+            return Err(ReadError::Status(400));
+        }
+        return read_chunked_body(reader);
+    }
+
+    let mut lengths = head.all_headers("content-length");
+    let Some(length) = lengths.next() else {
+        return Ok(Vec::new());
+    };
+    if lengths.next().is_some() || length.is_empty() || !length.bytes().all(|b| b.is_ascii_digit())
+    {
+        return Err(ReadError::Status(400));
+    }
+    let length: u64 = length.parse().map_err(|_| ReadError::Status(400))?;
+    if length > MAX_BODY_BYTES as u64 {
+        return Err(ReadError::Status(413));
+    }
+    let mut body = vec![0u8; length as usize];
+    reader.read_exact(&mut body)?;
+    Ok(body)
+}
+
+fn read_chunked_body(reader: &mut BufReader<TcpStream>) -> Result<Vec<u8>, ReadError> {
+    let mut body = Vec::new();
+    loop {
+        let mut budget = MAX_HEAD_BYTES;
+        let size_line = read_line(reader, &mut budget)?;
+        // Chunk extensions after ';' are ignored
+        let size = size_line.split(';').next().unwrap_or("").trim();
+        let size = usize::from_str_radix(size, 16).map_err(|_| ReadError::Status(400))?;
+        if size == 0 {
+            // Skip trailer fields up to the final empty line
+            while !read_line(reader, &mut budget)?.is_empty() {}
+            return Ok(body);
+        }
+        let start = body.len();
+        let end = start
+            .checked_add(size)
+            .filter(|end| *end <= MAX_BODY_BYTES)
+            .ok_or(ReadError::Status(413))?;
+        body.resize(end, 0);
+        reader.read_exact(&mut body[start..])?;
+        let mut crlf = [0u8; 2];
+        reader.read_exact(&mut crlf)?;
+        if &crlf != b"\r\n" {
+            return Err(ReadError::Status(400));
+        }
+    }
+}
+
+/// Write the response produced by JS, starting from its first chunk.
+/// Returns whether the connection can be reused.
+fn write_response(
+    writer: &mut TcpStream,
+    first: Result<ResponseChunk, mpsc::RecvError>,
+    responses: &Receiver<ResponseChunk>,
+    is_head: bool,
+    keep_alive: bool,
+) -> std::io::Result<bool> {
+    let (status, headers, content_length) = match first {
+        Ok(ResponseChunk::Head {
+            status,
+            headers,
+            content_length,
+        }) => (status, headers, content_length),
+        // The handler never produced a response
+        _ => {
+            write_error(writer, 500)?;
+            return Ok(false);
+        }
+    };
+
+    let bodyless_status = (100..200).contains(&status) || status == 204 || status == 304;
+    let chunked = content_length.is_none() && !is_head && !bodyless_status;
+
+    let mut head = format!("HTTP/1.1 {} {}\r\n", status, reason_phrase(status));
+    for (name, value) in &headers {
+        // Framing headers are managed by the server
+        if name.eq_ignore_ascii_case("content-length")
+            || name.eq_ignore_ascii_case("transfer-encoding")
+            || name.eq_ignore_ascii_case("connection")
+        {
+            continue;
+        }
+        // Reject values that would let a header split the response
+        if name.contains(['\r', '\n', ':']) || value.contains(['\r', '\n']) {
+            continue;
+        }
+        head.push_str(&format!("{}: {}\r\n", name, value));
+    }
+    if let Some(length) = content_length
+        && !bodyless_status
+    {
+        head.push_str(&format!("content-length: {}\r\n", length));
+    } else if chunked {
+        head.push_str("transfer-encoding: chunked\r\n");
+    }
+    // Without a length or chunked framing, the body ends when the connection closes
+    let keep_alive =
+        keep_alive && (content_length.is_some() || chunked || is_head || bodyless_status);
+    if !keep_alive {
+        head.push_str("connection: close\r\n");
+    }
+    head.push_str("\r\n");
+    writer.write_all(head.as_bytes())?;
+
+    let send_body = !is_head && !bodyless_status;
+    loop {
+        match responses.recv() {
+            Ok(ResponseChunk::Data(data)) => {
+                if !send_body || data.is_empty() {
+                    continue;
+                }
+                if chunked {
+                    let mut frame = format!("{:x}\r\n", data.len()).into_bytes();
+                    frame.extend_from_slice(&data);
+                    frame.extend_from_slice(b"\r\n");
+                    writer.write_all(&frame)?;
+                } else {
+                    writer.write_all(&data)?;
+                }
+            }
+            Ok(ResponseChunk::End) => {
+                if chunked {
+                    writer.write_all(b"0\r\n\r\n")?;
+                }
+                writer.flush()?;
+                return Ok(keep_alive);
+            }
+            // The body failed part way; closing the connection tells the client it is incomplete
+            Ok(ResponseChunk::Error)
+            | Ok(ResponseChunk::Head { .. })
+            | Ok(ResponseChunk::Upgrade { .. })
+            | Err(_) => {
+                return Ok(false);
+            }
+        }
+    }
+}
+
+/// Write a bodyless error response and close the connection
+fn write_error(writer: &mut TcpStream, status: u16) -> std::io::Result<()> {
+    let response = format!(
+        "HTTP/1.1 {} {}\r\ncontent-length: 0\r\nconnection: close\r\n\r\n",
+        status,
+        reason_phrase(status)
+    );
+    writer.write_all(response.as_bytes())?;
+    writer.flush()
+}
+
+fn reason_phrase(status: u16) -> &'static str {
+    match status {
+        100 => "Continue",
+        101 => "Switching Protocols",
+        200 => "OK",
+        201 => "Created",
+        202 => "Accepted",
+        204 => "No Content",
+        206 => "Partial Content",
+        301 => "Moved Permanently",
+        302 => "Found",
+        303 => "See Other",
+        304 => "Not Modified",
+        307 => "Temporary Redirect",
+        308 => "Permanent Redirect",
+        400 => "Bad Request",
+        401 => "Unauthorized",
+        403 => "Forbidden",
+        404 => "Not Found",
+        405 => "Method Not Allowed",
+        409 => "Conflict",
+        410 => "Gone",
+        413 => "Content Too Large",
+        415 => "Unsupported Media Type",
+        422 => "Unprocessable Content",
+        429 => "Too Many Requests",
+        431 => "Request Header Fields Too Large",
+        500 => "Internal Server Error",
+        501 => "Not Implemented",
+        502 => "Bad Gateway",
+        503 => "Service Unavailable",
+        504 => "Gateway Timeout",
+        505 => "HTTP Version Not Supported",
+        _ => "",
+    }
+}
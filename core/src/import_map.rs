@@ -0,0 +1,387 @@
+//! Import maps, as specified by WHATWG:
+//! https://html.spec.whatwg.org/multipage/webappapis.html#import-maps
+//!
+//! jstime resolves modules to file paths rather than URLs, so keys and
+//! addresses starting with `/`, `./`, `../` or `file://` become absolute
+//! paths, relative ones resolved against the import map's directory. Other
+//! URLs, like `node:fs` or `https://...`, are kept as written.
+
+use crate::http_cache::has_url_scheme;
+use crate::json::Json;
+use std::path::Path;
+
+/// Specifier keys and their addresses, sorted so longer keys come first.
+/// A `None` address blocks the specifier.
+type SpecifierMap = Vec<(String, Option<String>)>;
+
+/// A parsed import map, used to remap specifiers before node_modules
+/// resolution.
+#[derive(Debug, Clone, Default)]
+pub struct ImportMap {
+    imports: SpecifierMap,
+    scopes: Vec<(String, SpecifierMap)>,
+}
+
+impl ImportMap {
+    /// Parse an import map from JSON. Relative keys and addresses are
+    /// resolved against `base_dir`.
+    pub fn parse(source: &str, base_dir: impl AsRef<Path>) -> Result<ImportMap, String> {
+        let json = Json::parse(source).map_err(|e| format!("Invalid import map: {}", e))?;
+        let base_dir = std::path::absolute(base_dir.as_ref())
+            .map_err(|e| format!("Invalid import map base directory: {}", e))?;
+        let mut base = base_dir.to_string_lossy().into_owned();
+        if !base.ends_with('/') {
+            base.push('/');
+        }
+
+        let Json::Object(_) = json else {
+            return Err("Invalid import map: the top-level value must be a JSON object".into());
+        };
+        ImportMap::from_json(&json, &base)
+    }
+
+    /// Read and parse an import map file
+    pub fn from_file(path: impl AsRef<Path>) -> Result<ImportMap, String> {
+        let path = path.as_ref();
+        let source = std::fs::read_to_string(path)
+            .map_err(|e| format!("Cannot read import map '{}': {}", path.display(), e))?;
+        ImportMap::parse(&source, import_map_dir(path))
+            .map_err(|e| format!("{} ({})", e, path.display()))
+    }
+
+    /// Read the import map configured in a `jstime.json` file, given either
+    /// as an `"importMap"` path or inline as `"imports"` and `"scopes"`.
+    /// Returns `None` if the file configures no import map.
+    pub fn from_config_file(path: impl AsRef<Path>) -> Result<Option<ImportMap>, String> {
+        let path = path.as_ref();
+        let source = std::fs::read_to_string(path)
+            .map_err(|e| format!("Cannot read '{}': {}", path.display(), e))?;
+        let config = Json::parse(&source).map_err(|e| format!("{}: {}", path.display(), e))?;
+        let config_dir = import_map_dir(path);
+
+        match config.get("importMap") {
+            Some(Json::String(import_map)) => {
+                return ImportMap::from_file(config_dir.join(import_map)).map(Some);
+            }
+            Some(_) => {
+                return Err(format!(
+                    "{}: \"importMap\" must be a string",
+                    path.display()
+                ));
+            }
+            None => {}
+        }
+        if config.get("imports").is_none() && config.get("scopes").is_none() {
+            return Ok(None);
+        }
+        ImportMap::parse(&source, config_dir)
+            .map(Some)
+            .map_err(|e| format!("{} ({})", e, path.display()))
+    }
+
+    fn from_json(json: &Json, base: &str) -> Result<ImportMap, String> {
+        let imports = match json.get("imports") {
+            Some(Json::Object(imports)) => sort_and_normalize(imports, base),
+            Some(_) => return Err("Invalid import map: \"imports\" must be a JSON object".into()),
+            None => Vec::new(),
+        };
+
+        let mut scopes = Vec::new();
+        match json.get("scopes") {
+            Some(Json::Object(entries)) => {
+                for (prefix, map) in entries {
+                    let Json::Object(map) = map else {
+                        return Err(format!(
+                            "Invalid import map: the value for scope \"{}\" must be a JSON object",
+                            prefix
+                        ));
+                    };
+                    let prefix = resolve_url_like(prefix, base).unwrap_or_else(|| {
+                        // Scopes are always URLs, so a bare prefix is relative
+                        remove_dot_segments(&format!("{}{}", base, prefix))
+                    });
+                    scopes.push((prefix, sort_and_normalize(map, base)));
+                }
+            }
+            Some(_) => return Err("Invalid import map: \"scopes\" must be a JSON object".into()),
+            None => {}
+        }
+        scopes.sort_by(|(a, _), (b, _)| b.cmp(a));
+
+        Ok(ImportMap { imports, scopes })
+    }
+
+    /// Map `specifier`, imported from the module at `referrer`. Returns
+    /// `Ok(None)` if no entry matches, and an error if the matching entry
+    /// blocks the specifier or maps it outside its address.
+    pub(crate) fn resolve(
+        &self,
+        specifier: &str,
+        referrer: &str,
+    ) -> Result<Option<String>, String> {
+        let referrer_dir = &referrer[..referrer.rfind('/').map_or(0, |i| i + 1)];
+        let as_url = resolve_url_like(specifier, referrer_dir);
+        let normalized = as_url.as_deref().unwrap_or(specifier);
+
+        let scopes = self.scopes.iter().filter(|(prefix, _)| {
+            prefix == referrer || (prefix.ends_with('/') && referrer.starts_with(prefix.as_str()))
+        });
+        for (_, map) in scopes {
+            if let Some(resolved) = resolve_imports_match(specifier, normalized, map)? {
+                return Ok(Some(resolved));
+            }
+        }
+        resolve_imports_match(specifier, normalized, &self.imports)
+    }
+}
+
+/// The directory relative entries in an import map or config file are
+/// resolved against
+fn import_map_dir(path: &Path) -> &Path {
+    match path.parent() {
+        Some(dir) if !dir.as_os_str().is_empty() => dir,
+        _ => Path::new("."),
+    }
+}
+
+fn sort_and_normalize(map: &[(String, Json)], base: &str) -> SpecifierMap {
+    let mut normalized: SpecifierMap = Vec::with_capacity(map.len());
+    for (key, value) in map {
+        if key.is_empty() {
+            continue;
+        }
+        let key = resolve_url_like(key, base).unwrap_or_else(|| key.clone());
+        // Invalid addresses become null entries, which block the specifier
+        let address = match value {
+            Json::String(address) => resolve_url_like(address, base),
+            _ => None,
+        }
+        .filter(|address| !key.ends_with('/') || address.ends_with('/'));
+
+        match normalized.iter_mut().find(|(k, _)| *k == key) {
+            Some(entry) => entry.1 = address,
+            None => normalized.push((key, address)),
+        }
+    }
+    normalized.sort_by(|(a, _), (b, _)| b.cmp(a));
+    normalized
+}
+
+fn resolve_imports_match(
+    specifier: &str,
+    normalized: &str,
+    map: &SpecifierMap,
+) -> Result<Option<String>, String> {
+    for (key, address) in map {
+        let is_prefix_match = key.ends_with('/') && normalized.starts_with(key.as_str());
+        if key != normalized && !is_prefix_match {
+            continue;
+        }
+        let Some(address) = address else {
+            return Err(format!(
+                "Import of '{}' is blocked by the import map: the entry for '{}' is null or invalid",
+                specifier, key
+            ));
+        };
+        if key == normalized {
+            return Ok(Some(address.clone()));
+        }
+
+        let resolved = join(address, &normalized[key.len()..]);
+        if !resolved.starts_with(address.as_str()) {
+            return Err(format!(
+                "Cannot map '{}' with the import map: it resolves outside of '{}', the address for '{}'",
+                specifier, address, key
+            ));
+        }
+        return Ok(Some(resolved));
+    }
+    Ok(None)
+}
+
+/// Resolve a URL-like specifier (a path or a URL) against `base`, a
+/// directory ending in `/`. Bare specifiers return `None`.
+fn resolve_url_like(specifier: &str, base: &str) -> Option<String> {
+    if let Some(path) = specifier.strip_prefix("file://") {
+        return Some(remove_dot_segments(path));
+    }
+    if specifier.starts_with('/') {
+        return Some(remove_dot_segments(specifier));
+    }
+    if specifier.starts_with("./") || specifier.starts_with("../") {
+        return base
+            .starts_with('/')
+            .then(|| remove_dot_segments(&format!("{}{}", base, specifier)));
+    }
+    has_url_scheme(specifier).then(|| specifier.to_string())
+}
+
+/// Append the rest of a specifier to an address ending in `/`
+fn join(address: &str, after_prefix: &str) -> String {
+    let joined = format!("{}{}", address, after_prefix);
+    let path_start = if joined.starts_with('/') {
+        Some(0)
+    } else {
+        joined
+            .find("://")
+            .and_then(|i| joined[i + 3..].find('/').map(|j| i + 3 + j))
+    };
+    match path_start {
+        Some(start) => format!(
+            "{}{}",
+            &joined[..start],
+            remove_dot_segments(&joined[start..])
+        ),
+        None => joined,
+    }
+}
+
+/// Resolve `.` and `..` segments in an absolute path, keeping a trailing `/`
+fn remove_dot_segments(path: &str) -> String {
+    let segments: Vec<&str> = path.split('/').skip(1).collect();
+    let mut output: Vec<&str> = Vec::with_capacity(segments.len());
+    for (i, segment) in segments.iter().enumerate() {
+        match *segment {
+            "." | ".." => {
+                if *segment == ".." {
+                    output.pop();
+                }
+                if i + 1 == segments.len() {
+                    output.push("");
+                }
+            }
+            segment => output.push(segment),
+        }
+    }
+    format!("/{}", output.join("/"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn import_map(source: &str) -> ImportMap {
+        ImportMap::parse(source, "/project").unwrap()
+    }
+
+    #[test]
+    fn test_exact_and_prefix_matches() {
+        let map = import_map(
+            r#"{"imports": {
+                "lodash": "./vendor/lodash/index.js",
+                "lodash/": "./vendor/lodash/",
+                "lodash/fp": "/opt/fp.js",
+                "std/": "https://deno.land/std/",
+                "fs": "node:fs"
+            }}"#,
+        );
+        let resolve = |specifier| map.resolve(specifier, "/project/main.js").unwrap();
+
+        assert_eq!(
+            resolve("lodash").as_deref(),
+            Some("/project/vendor/lodash/index.js")
+        );
+        assert_eq!(resolve("lodash/fp").as_deref(), Some("/opt/fp.js"));
+        assert_eq!(
+            resolve("lodash/get.js").as_deref(),
+            Some("/project/vendor/lodash/get.js")
+        );
+        assert_eq!(
+            resolve("std/path/mod.ts").as_deref(),
+            Some("https://deno.land/std/path/mod.ts")
+        );
+        assert_eq!(resolve("fs").as_deref(), Some("node:fs"));
+        assert_eq!(resolve("react"), None);
+        assert_eq!(resolve("./lodash"), None);
+    }
+
+    #[test]
+    fn test_path_keys() {
+        let map = import_map(r#"{"imports": {"./src/config.js": "./config.prod.js"}}"#);
+        assert_eq!(
+            map.resolve("./config.js", "/project/src/main.js")
+                .unwrap()
+                .as_deref(),
+            Some("/project/config.prod.js")
+        );
+        assert_eq!(
+            map.resolve("../src/config.js", "/project/lib/main.js")
+                .unwrap()
+                .as_deref(),
+            Some("/project/config.prod.js")
+        );
+        assert_eq!(
+            map.resolve("./config.js", "/project/main.js").unwrap(),
+            None
+        );
+    }
+
+    #[test]
+    fn test_scopes() {
+        let map = import_map(
+            r#"{
+                "imports": {"dep": "./dep-v2.js"},
+                "scopes": {
+                    "./legacy/": {"dep": "./dep-v1.js"},
+                    "./legacy/special/": {"dep": "./dep-special.js"},
+                    "./legacy/other/": {}
+                }
+            }"#,
+        );
+        let resolve = |referrer| map.resolve("dep", referrer).unwrap().unwrap();
+
+        assert_eq!(resolve("/project/main.js"), "/project/dep-v2.js");
+        assert_eq!(resolve("/project/legacy/a.js"), "/project/dep-v1.js");
+        assert_eq!(
+            resolve("/project/legacy/special/a.js"),
+            "/project/dep-special.js"
+        );
+        // An empty scope falls back to its parent scope, then top-level imports
+        assert_eq!(resolve("/project/legacy/other/a.js"), "/project/dep-v1.js");
+    }
+
+    #[test]
+    fn test_blocked_and_invalid_entries() {
+        let map = import_map(
+            r#"{"imports": {
+                "blocked": null,
+                "bare-address": "lodash",
+                "pkg/": "./pkg/index.js",
+                "pkg/internal/": null,
+                "escape/": "./vendor/escape/",
+                "": "./empty.js"
+            }}"#,
+        );
+        let resolve = |specifier| map.resolve(specifier, "/project/main.js");
+
+        assert!(resolve("blocked").unwrap_err().contains("blocked"));
+        // Addresses must be URLs or paths
+        assert!(resolve("bare-address").is_err());
+        // Prefix keys need prefix addresses
+        assert!(resolve("pkg/a.js").is_err());
+        assert!(resolve("pkg/internal/a.js").is_err());
+        assert!(
+            resolve("escape/../../secret.js")
+                .unwrap_err()
+                .contains("outside")
+        );
+        assert_eq!(resolve("").unwrap(), None);
+    }
+
+    #[test]
+    fn test_invalid_import_maps() {
+        assert!(ImportMap::parse("[]", "/").is_err());
+        assert!(ImportMap::parse(r#"{"imports": []}"#, "/").is_err());
+        assert!(ImportMap::parse(r#"{"scopes": {"/a/": "x"}}"#, "/").is_err());
+        assert!(ImportMap::parse(r#"{"imports": {"a": "./a.js",}}"#, "/").is_err());
+        assert!(ImportMap::parse(r#"{"unknown": 1}"#, "/").is_ok());
+    }
+
+    #[test]
+    fn test_remove_dot_segments() {
+        assert_eq!(remove_dot_segments("/a/./b/../c"), "/a/c");
+        assert_eq!(remove_dot_segments("/a/b/"), "/a/b/");
+        assert_eq!(remove_dot_segments("/a/b/.."), "/a/");
+        assert_eq!(remove_dot_segments("/../a"), "/a");
+    }
+}
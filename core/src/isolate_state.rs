@@ -2,10 +2,11 @@ use std::cell::RefCell;
 use std::rc::Rc;
 
 pub(crate) struct FetchRequest {
+    pub(crate) id: u64,
     pub(crate) url: String,
     pub(crate) method: String,
     pub(crate) headers: Vec<(String, String)>,
-    pub(crate) body: Option<String>,
+    pub(crate) body: Option<crate::fetch_worker::FetchBody>,
     pub(crate) resolver: v8::Global<v8::PromiseResolver>,
 }
 
@@ -18,6 +19,34 @@ pub(crate) struct ActiveDgramSocket {
     pub(crate) is_ref: bool, // If true, keeps the event loop alive
 }
 
+/// A `jstime.serve` server that the event loop polls for incoming requests
+pub(crate) struct ActiveServer {
+    pub(crate) server: crate::http_server::HttpServer,
+    pub(crate) callback: v8::Global<v8::Function>,
+    pub(crate) is_ref: bool, // If true, keeps the event loop alive
+}
+
+/// Stream servers and sockets that the event loop polls, keyed by ID
+#[derive(Default)]
+pub(crate) struct ActiveNet {
+    // Servers started by jstime.serve
+    pub(crate) http_servers: rustc_hash::FxHashMap<u64, ActiveServer>,
+    // node:net servers and sockets, sharing one ID space
+    pub(crate) tcp_servers: rustc_hash::FxHashMap<u64, crate::tcp::ActiveTcpServer>,
+    pub(crate) tcp_sockets: rustc_hash::FxHashMap<u64, crate::tcp::ActiveTcpSocket>,
+    // WebSocket connections, using the node:net ID space
+    pub(crate) websockets: rustc_hash::FxHashMap<u64, crate::websocket::ActiveWebSocket>,
+}
+
+impl ActiveNet {
+    pub(crate) fn is_empty(&self) -> bool {
+        self.http_servers.is_empty()
+            && self.tcp_servers.is_empty()
+            && self.tcp_sockets.is_empty()
+            && self.websockets.is_empty()
+    }
+}
+
 /// Stores response body data for streaming
 #[allow(dead_code)]
 pub(crate) struct StreamingFetch {
@@ -78,6 +107,12 @@ pub(crate) struct StringCache {
 
     // Module/import strings
     pub(crate) url: Option<v8::Global<v8::String>>,
+    pub(crate) resolve: Option<v8::Global<v8::String>>,
+    pub(crate) filename: Option<v8::Global<v8::String>>,
+    pub(crate) dirname: Option<v8::Global<v8::String>>,
+    pub(crate) hot: Option<v8::Global<v8::String>>,
+    pub(crate) accept: Option<v8::Global<v8::String>>,
+    pub(crate) dispose: Option<v8::Global<v8::String>>,
 
     // Process-related strings
     pub(crate) encoding: Option<v8::Global<v8::String>>,
@@ -131,6 +166,12 @@ impl StringCache {
 
             // Module/import
             url: None,
+            resolve: None,
+            filename: None,
+            dirname: None,
+            hot: None,
+            accept: None,
+            dispose: None,
 
             // Process-related
             encoding: None,
@@ -166,9 +207,40 @@ pub(crate) struct IsolateState {
     pub(crate) timers_to_add: Rc<RefCell<Vec<crate::event_loop::PendingTimer>>>,
     pub(crate) next_timer_id: Rc<RefCell<u64>>,
     pub(crate) pending_fetches: Rc<RefCell<Vec<FetchRequest>>>,
+    pub(crate) next_fetch_id: Rc<RefCell<u64>>,
+    // IDs of fetches aborted via AbortSignal, dropped by the event loop
+    pub(crate) fetches_to_abort: Rc<RefCell<Vec<u64>>>,
+    // Senders feeding streamed request bodies to worker threads, keyed by fetch ID
+    pub(crate) fetch_body_senders: Rc<
+        RefCell<
+            rustc_hash::FxHashMap<u64, std::sync::mpsc::Sender<crate::fetch_worker::BodyChunk>>,
+        >,
+    >,
     pub(crate) string_cache: Rc<RefCell<StringCache>>,
     pub(crate) http_agent: ureq::Agent,
     pub(crate) process_argv: Vec<String>,
+    // Condition names for package.json "exports" and "imports"
+    pub(crate) conditions: crate::package_json::ResolutionConditions,
+    // Import map applied before node_modules resolution, shared with the prefetch threads
+    pub(crate) import_map: Option<std::sync::Arc<crate::ImportMap>>,
+    // Downloads and caches modules imported from http(s) URLs
+    pub(crate) http_cache: std::sync::Arc<crate::http_cache::HttpCache>,
+    // Integrity hashes that remote and node_modules modules are checked against
+    pub(crate) lockfile: Option<crate::Lockfile>,
+    // Factories that JSX elements are transformed into calls of
+    pub(crate) jsx: crate::transpile::JsxFactory,
+    // Compiled code of modules and script files, reused by later runs
+    pub(crate) code_cache: Option<crate::code_cache::CodeCache>,
+    // import.meta.hot callbacks and data, when modules are reloaded as they change
+    pub(crate) hot_reload: Option<crate::hot_reload::HotModules>,
+    // Promises rejected without a handler, dispatched after microtask checkpoints
+    pub(crate) promise_rejections: crate::promise_rejection::PromiseRejections,
+    // The first exception thrown by a callback that no listener handled
+    pub(crate) uncaught_exception: Option<crate::JsError>,
+    // Objects thrown with the errors returned to the embedder, held weakly
+    pub(crate) thrown_values: crate::js_error::ThrownValues,
+    // Termination requests and the execution deadline of the current run
+    pub(crate) termination: Option<crate::termination::Termination>,
     pub(crate) next_stream_id: Rc<RefCell<u64>>,
     pub(crate) streaming_fetches: Rc<RefCell<rustc_hash::FxHashMap<u64, StreamingFetch>>>,
     // Object pool for frequently allocated header vectors
@@ -179,22 +251,46 @@ pub(crate) struct IsolateState {
     pub(crate) active_dgram_sockets: Rc<RefCell<rustc_hash::FxHashMap<u64, ActiveDgramSocket>>>,
     // Next dgram socket ID
     pub(crate) next_dgram_socket_id: Rc<RefCell<u64>>,
+    // HTTP servers, TCP sockets and WebSockets that should be polled for I/O
+    pub(crate) active_net: Rc<RefCell<ActiveNet>>,
+    pub(crate) next_server_id: Rc<RefCell<u64>>,
+    pub(crate) next_tcp_id: Rc<RefCell<u64>>,
+    // Channels to the connections waiting for a response, keyed by request ID
+    pub(crate) server_responses: Rc<
+        RefCell<
+            rustc_hash::FxHashMap<u64, std::sync::mpsc::Sender<crate::http_server::ResponseChunk>>,
+        >,
+    >,
+    pub(crate) next_server_request_id: Rc<RefCell<u64>>,
 }
 
 impl IsolateState {
     pub(crate) fn new(
         context: v8::Global<v8::Context>,
         process_argv: Vec<String>,
+        conditions: crate::package_json::ResolutionConditions,
+        import_map: Option<crate::ImportMap>,
+        cache_dir: std::path::PathBuf,
+        cached_only: bool,
+        lockfile: Option<crate::Lockfile>,
     ) -> Rc<RefCell<IsolateState>> {
         let timers_to_clear = Rc::new(RefCell::new(Vec::new()));
         let timers_to_add = Rc::new(RefCell::new(Vec::new()));
         let next_timer_id = Rc::new(RefCell::new(1u64));
         let pending_fetches = Rc::new(RefCell::new(Vec::new()));
+        let next_fetch_id = Rc::new(RefCell::new(1u64));
+        let fetches_to_abort = Rc::new(RefCell::new(Vec::new()));
+        let fetch_body_senders = Rc::new(RefCell::new(rustc_hash::FxHashMap::default()));
         let string_cache = Rc::new(RefCell::new(StringCache::new()));
         let next_stream_id = Rc::new(RefCell::new(1u64));
         let streaming_fetches = Rc::new(RefCell::new(rustc_hash::FxHashMap::default()));
         let active_dgram_sockets = Rc::new(RefCell::new(rustc_hash::FxHashMap::default()));
         let next_dgram_socket_id = Rc::new(RefCell::new(1u64));
+        let active_net = Rc::new(RefCell::new(ActiveNet::default()));
+        let next_server_id = Rc::new(RefCell::new(1u64));
+        let next_tcp_id = Rc::new(RefCell::new(1u64));
+        let server_responses = Rc::new(RefCell::new(rustc_hash::FxHashMap::default()));
+        let next_server_request_id = Rc::new(RefCell::new(1u64));
 
         // Create object pool for header vectors with reasonable capacity limit
         let header_vec_pool = Rc::new(crate::pool::Pool::new(200));
@@ -205,6 +301,11 @@ impl IsolateState {
             .http_status_as_error(false)
             .build();
         let http_agent = ureq::Agent::new_with_config(config);
+        let http_cache = std::sync::Arc::new(crate::http_cache::HttpCache::new(
+            &cache_dir,
+            cached_only,
+            http_agent.clone(),
+        ));
 
         Rc::new(RefCell::new(IsolateState {
             context: Some(context),
@@ -214,21 +315,42 @@ impl IsolateState {
                 timers_to_add.clone(),
                 next_timer_id.clone(),
                 pending_fetches.clone(),
+                fetches_to_abort.clone(),
                 active_dgram_sockets.clone(),
+                active_net.clone(),
             ))),
             timers_to_clear,
             timers_to_add,
             next_timer_id,
             pending_fetches,
+            next_fetch_id,
+            fetches_to_abort,
+            fetch_body_senders,
             string_cache,
             http_agent,
             process_argv,
+            conditions,
+            import_map: import_map.map(std::sync::Arc::new),
+            http_cache,
+            lockfile,
+            jsx: crate::transpile::JsxFactory::default(),
+            code_cache: None,
+            hot_reload: None,
+            promise_rejections: Default::default(),
+            uncaught_exception: None,
+            thrown_values: Default::default(),
+            termination: None,
             next_stream_id,
             streaming_fetches,
             header_vec_pool,
             buffered_random: RefCell::new(crate::buffered_random::BufferedRandom::new()),
             active_dgram_sockets,
             next_dgram_socket_id,
+            active_net,
+            next_server_id,
+            next_tcp_id,
+            server_responses,
+            next_server_request_id,
         }))
     }
 
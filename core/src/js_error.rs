@@ -0,0 +1,347 @@
+//! The structured exception `JSTime` returns.
+//!
+//! A `JsError` is built from what V8 knows about an exception when it
+//! reaches Rust: its name and message, the frames of its stack trace, the
+//! source line it was thrown at and its `cause`, in turn. It displays as the
+//! formatted text jstime prints, and serializes to JSON for log pipelines.
+//!
+//! The frames are the ones V8 captured when the error was created, so they
+//! don't depend on what its `stack` property was formatted or set to.
+//!
+//! The thrown object stays in the isolate, held weakly, so a `JsError` can
+//! be sent to other threads. `JSTime::with_error_value` calls a function
+//! with it while it's alive.
+
+use crate::IsolateState;
+use crate::json::json_string;
+use rustc_hash::FxHashMap;
+use std::sync::atomic::{AtomicU64, Ordering};
+
+/// How many errors of a `cause` chain are described, so a cycle ends
+const MAX_CAUSE_DEPTH: usize = 16;
+
+/// How many frames of an error's stack trace V8 captures, as many as
+/// `Error.stackTraceLimit` formats by default
+pub(crate) const MAX_FRAMES: i32 = 10;
+
+/// An exception that JavaScript threw and nothing caught, or a promise
+/// rejection that nothing handled
+#[derive(Debug, Clone)]
+pub struct JsError {
+    /// The error's `name`, such as "TypeError". Empty if the thrown value
+    /// isn't an object.
+    pub name: String,
+    /// The error's `message`, or the thrown value as a string if it isn't
+    /// an object
+    pub message: String,
+    /// The frames of the error's stack trace, innermost first. Frames of
+    /// modules with a source map are at their original position.
+    pub frames: Vec<StackFrame>,
+    /// The line of source the exception was thrown at, if known
+    pub source_line: Option<String>,
+    /// The error's `cause`, if it has one
+    pub cause: Option<Box<JsError>>,
+    // What it displays as: where it was thrown, the message and the stack
+    formatted: String,
+    // The key of the thrown object in the isolate's thrown values
+    value_id: Option<ValueId>,
+}
+
+/// A call in the stack trace of a `JsError`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct StackFrame {
+    /// The called function, as V8 names it, if it has a name
+    pub function: Option<String>,
+    /// The file or URL of the code, if it has one
+    pub file: Option<String>,
+    /// The line, starting at 1
+    pub line: Option<usize>,
+    /// The column, starting at 1
+    pub column: Option<usize>,
+}
+
+impl JsError {
+    /// Describe the exception `tc` caught
+    pub(crate) fn from_try_catch(
+        tc: &mut v8::PinnedRef<'_, v8::TryCatch<v8::HandleScope>>,
+    ) -> Self {
+        let formatted = crate::error::format_exception(tc);
+        let Some(exception) = tc.exception().filter(|_| !tc.has_terminated()) else {
+            return JsError::from(formatted);
+        };
+        let message = tc.message();
+        let location = message.and_then(|message| crate::error::error_location(tc, message));
+        let mut error = describe(tc, exception, formatted, 0);
+
+        // A value that isn't an error has the stack of where it was thrown
+        if error.frames.is_empty()
+            && let Some(trace) = message.and_then(|message| message.get_stack_trace(tc))
+        {
+            error.frames = stack_frames(tc, trace);
+        }
+        if let Some(location) = location {
+            // Errors that aren't thrown from a call, such as syntax errors,
+            // have no stack of their own
+            if error.frames.is_empty() {
+                error.frames.push(StackFrame {
+                    function: None,
+                    file: Some(location.file),
+                    line: Some(location.line),
+                    column: Some(location.start_column + 1),
+                });
+            }
+            error.source_line = location.source_line;
+        }
+        error
+    }
+
+    /// Describe an exception that isn't caught by a TryCatch, like the
+    /// reason of a rejected promise
+    pub(crate) fn from_value(scope: &mut v8::PinScope, exception: v8::Local<v8::Value>) -> Self {
+        let formatted = crate::error::format_exception_value(scope, exception);
+        describe(scope, exception, formatted, 0)
+    }
+
+    /// Put `prefix` before the text it displays as
+    pub(crate) fn prefixed(mut self, prefix: &str) -> Self {
+        self.formatted.insert_str(0, prefix);
+        self
+    }
+
+    /// Serialize it as a JSON object, with the `name`, `message`, `frames`,
+    /// `source_line` and `cause` fields. Missing values are null.
+    pub fn to_json(&self) -> String {
+        let frames: Vec<String> = self
+            .frames
+            .iter()
+            .map(|frame| {
+                format!(
+                    "{{\"function\":{},\"file\":{},\"line\":{},\"column\":{}}}",
+                    json_optional_string(frame.function.as_deref()),
+                    json_optional_string(frame.file.as_deref()),
+                    json_optional_number(frame.line),
+                    json_optional_number(frame.column)
+                )
+            })
+            .collect();
+        format!(
+            "{{\"name\":{},\"message\":{},\"frames\":[{}],\"source_line\":{},\"cause\":{}}}",
+            json_string(&self.name),
+            json_string(&self.message),
+            frames.join(","),
+            json_optional_string(self.source_line.as_deref()),
+            self.cause
+                .as_ref()
+                .map_or_else(|| "null".to_string(), |cause| cause.to_json())
+        )
+    }
+
+    pub(crate) fn value_id(&self) -> Option<ValueId> {
+        self.value_id
+    }
+}
+
+/// An error that isn't a JavaScript exception, such as a lockfile that
+/// can't be written, with `message` as its text
+impl From<String> for JsError {
+    fn from(message: String) -> Self {
+        JsError {
+            name: "Error".to_string(),
+            message: message.clone(),
+            frames: Vec::new(),
+            source_line: None,
+            cause: None,
+            formatted: message,
+            value_id: None,
+        }
+    }
+}
+
+impl std::fmt::Display for JsError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(&self.formatted)
+    }
+}
+
+impl std::error::Error for JsError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        self.cause
+            .as_deref()
+            .map(|cause| cause as &(dyn std::error::Error + 'static))
+    }
+}
+
+/// The objects thrown with the errors `JSTime` returned, by key. They're
+/// held weakly, so they're collected once JavaScript drops them.
+pub(crate) struct ThrownValues {
+    // Tells the keys of this isolate from another's
+    isolate_id: u64,
+    next_id: u64,
+    values: FxHashMap<u64, v8::Weak<v8::Value>>,
+}
+
+impl Default for ThrownValues {
+    fn default() -> Self {
+        static NEXT_ISOLATE_ID: AtomicU64 = AtomicU64::new(0);
+        ThrownValues {
+            isolate_id: NEXT_ISOLATE_ID.fetch_add(1, Ordering::Relaxed),
+            next_id: 0,
+            values: FxHashMap::default(),
+        }
+    }
+}
+
+/// The key of a thrown object, in the isolate it was thrown in
+#[derive(Debug, Clone, Copy)]
+pub(crate) struct ValueId {
+    isolate: u64,
+    value: u64,
+}
+
+/// The object a `JsError` was thrown with, if it's still alive and was
+/// thrown in this isolate
+pub(crate) fn thrown_value<'s>(
+    scope: &mut v8::PinScope<'s, '_>,
+    id: ValueId,
+) -> Option<v8::Local<'s, v8::Value>> {
+    let isolate: &mut v8::Isolate = scope;
+    let state = IsolateState::get(isolate);
+    let state = state.borrow();
+    let thrown = &state.thrown_values;
+    if id.isolate != thrown.isolate_id {
+        return None;
+    }
+    thrown.values.get(&id.value)?.to_local(scope)
+}
+
+/// Keep a weak handle to a thrown object, returning its key
+fn keep(scope: &mut v8::PinScope, value: v8::Local<v8::Object>) -> ValueId {
+    let value: v8::Local<v8::Value> = value.into();
+    let isolate: &mut v8::Isolate = scope;
+    let weak = v8::Weak::new(isolate, value);
+    let state = IsolateState::get(isolate);
+    let mut state = state.borrow_mut();
+    let thrown = &mut state.thrown_values;
+    // Forget the ones that were collected
+    thrown.values.retain(|_, value| !value.is_empty());
+    thrown.next_id += 1;
+    thrown.values.insert(thrown.next_id, weak);
+    ValueId {
+        isolate: thrown.isolate_id,
+        value: thrown.next_id,
+    }
+}
+
+/// Describe a thrown value, and its cause up to MAX_CAUSE_DEPTH
+fn describe(
+    scope: &mut v8::PinScope,
+    exception: v8::Local<v8::Value>,
+    formatted: String,
+    depth: usize,
+) -> JsError {
+    // Getters that throw are ignored
+    v8::tc_scope!(let tc, scope);
+    let Ok(object) = v8::Local::<v8::Object>::try_from(exception) else {
+        let message = exception
+            .to_string(tc)
+            .map(|s| s.to_rust_string_lossy(tc))
+            .unwrap_or_default();
+        return JsError {
+            name: String::new(),
+            message,
+            frames: Vec::new(),
+            source_line: None,
+            cause: None,
+            formatted,
+            value_id: None,
+        };
+    };
+
+    let name = property(tc, object, "name").unwrap_or_default();
+    let message = property(tc, object, "message").unwrap_or_default();
+    let frames = v8::Exception::get_stack_trace(tc, exception)
+        .map(|trace| stack_frames(tc, trace))
+        .unwrap_or_default();
+    let cause_key = v8::String::new(tc, "cause").unwrap();
+    let cause = object
+        .get(tc, cause_key.into())
+        .filter(|cause| !cause.is_undefined() && depth < MAX_CAUSE_DEPTH)
+        .map(|cause| {
+            let formatted = crate::error::format_exception_value(tc, cause);
+            Box::new(describe(tc, cause, formatted, depth + 1))
+        });
+    let value_id = Some(keep(tc, object));
+    JsError {
+        name,
+        message,
+        frames,
+        source_line: None,
+        cause,
+        formatted,
+        value_id,
+    }
+}
+
+/// A property of an object as a string, unless it's undefined
+fn property(scope: &mut v8::PinScope, object: v8::Local<v8::Object>, name: &str) -> Option<String> {
+    let key = v8::String::new(scope, name).unwrap();
+    let value = object.get(scope, key.into())?;
+    if value.is_undefined() {
+        return None;
+    }
+    let value = value.to_string(scope)?;
+    Some(value.to_rust_string_lossy(scope))
+}
+
+/// The frames of a stack trace V8 captured. Frames of modules with a
+/// source map are at their original position.
+fn stack_frames(scope: &mut v8::PinScope, trace: v8::Local<v8::StackTrace>) -> Vec<StackFrame> {
+    let mut frames = Vec::new();
+    for index in 0..trace.get_frame_count() {
+        let Some(frame) = trace.get_frame(scope, index) else {
+            continue;
+        };
+        let function = frame
+            .get_function_name(scope)
+            .map(|name| name.to_rust_string_lossy(scope))
+            .filter(|name| !name.is_empty());
+        let file = frame
+            .get_script_name_or_source_url(scope)
+            .map(|name| name.to_rust_string_lossy(scope))
+            .filter(|name| !name.is_empty());
+        // 0 when V8 doesn't know, as for native code
+        let line = frame.get_line_number();
+        let column = frame.get_column();
+
+        let isolate: &mut v8::Isolate = scope;
+        let original = file
+            .as_deref()
+            .filter(|_| line > 0 && column > 0)
+            .and_then(|file| {
+                crate::sourcemap::original_position(isolate, file, line as u32, column as u32 - 1)
+            });
+        frames.push(match original {
+            Some(original) => StackFrame {
+                function,
+                file: Some(original.source),
+                line: Some(original.line as usize),
+                column: Some(original.column as usize + 1),
+            },
+            None => StackFrame {
+                function,
+                file,
+                line: Some(line).filter(|&line| line > 0),
+                column: Some(column).filter(|&column| column > 0),
+            },
+        });
+    }
+    frames
+}
+
+fn json_optional_string(value: Option<&str>) -> String {
+    value.map_or_else(|| "null".to_string(), json_string)
+}
+
+fn json_optional_number(value: Option<usize>) -> String {
+    value.map_or_else(|| "null".to_string(), |value| value.to_string())
+}
@@ -1,15 +1,38 @@
 mod buffered_random;
 mod builtins;
+mod code_cache;
+mod commonjs;
 mod error;
 mod event_loop;
+mod fetch_worker;
+mod hot_reload;
+mod http_cache;
+mod http_server;
+mod import_map;
 mod isolate_state;
+mod js_error;
 mod js_loading;
+mod json;
+mod lockfile;
 mod module;
+mod package_json;
 mod pool;
+mod promise_rejection;
 mod script;
 mod sourcemap;
-
+mod tcp;
+mod termination;
+mod tls;
+mod transpile;
+mod uncaught_exception;
+mod websocket;
+
+pub use error::Error;
+pub use import_map::ImportMap;
 pub(crate) use isolate_state::IsolateState;
+pub use js_error::{JsError, StackFrame};
+pub use lockfile::Lockfile;
+pub use termination::TerminationHandle;
 
 pub fn init(v8_flags: Option<Vec<String>>) {
     // Initialize ICU data before V8 initialization
@@ -65,8 +88,49 @@ pub struct Options {
     /// This allows V8's TurboFan JIT compiler to optimize the code.
     /// Default is 0 (no warmup).
     pub warmup_iterations: usize,
+    /// Condition names matched in package.json "exports" and "imports",
+    /// such as "development". `None` uses "production".
+    /// "jstime", "default", and "import" or "require" always match.
+    pub conditions: Option<Vec<String>>,
+    /// Import map applied to `import` specifiers before node_modules
+    /// resolution.
+    pub import_map: Option<ImportMap>,
+    /// Directory where modules imported from http(s) URLs, and compiled
+    /// code, are cached. `None` uses `$JSTIME_CACHE_DIR`, or `jstime` in the
+    /// user's cache directory.
+    pub cache_dir: Option<std::path::PathBuf>,
+    /// Only import http(s) modules that are already cached, never downloading.
+    pub cached_only: bool,
+    /// Lockfile that remote and node_modules modules are checked against.
+    pub lockfile: Option<Lockfile>,
+    /// Function that JSX elements in `.jsx` and `.tsx` modules are
+    /// transformed into calls of. `None` uses "React.createElement".
+    pub jsx_factory: Option<String>,
+    /// Component passed to the JSX factory for fragments, `<>...</>`.
+    /// `None` uses "React.Fragment".
+    pub jsx_fragment: Option<String>,
+    /// Store the code V8 compiles for modules and script files in the cache
+    /// directory, and reuse it while they're unchanged, so later runs start
+    /// faster.
+    pub code_cache: bool,
+    /// Reload modules while the event loop runs when their files change,
+    /// and give them `import.meta.hot` to take part in it.
+    pub hot_reload: bool,
+    /// Terminate `import`, `run_script` and `run_script_no_event_loop`
+    /// calls that run longer than this, with `Error::Terminated`.
+    /// `None` lets them run as long as they need to.
+    pub execution_timeout: Option<std::time::Duration>,
+    /// Initial size of the JavaScript heap, in bytes. 0 lets V8 choose.
+    pub initial_heap_size: usize,
+    /// Maximum size of the JavaScript heap, in bytes. JavaScript that
+    /// reaches it is terminated with `Error::HeapLimitExceeded`.
+    /// `None` uses 1 GB.
+    pub max_heap_size: Option<usize>,
 }
 
+/// Maximum heap size when `Options::max_heap_size` isn't set
+const DEFAULT_MAX_HEAP_SIZE: usize = 1024 * 1024 * 1024;
+
 impl Options {
     pub fn new(snapshot: Option<&'static [u8]>) -> Options {
         Options {
@@ -74,6 +138,18 @@ impl Options {
             taking_snapshot: false,
             process_argv: Vec::new(),
             warmup_iterations: 0,
+            conditions: None,
+            import_map: None,
+            cache_dir: None,
+            cached_only: false,
+            lockfile: None,
+            jsx_factory: None,
+            jsx_fragment: None,
+            code_cache: false,
+            hot_reload: false,
+            execution_timeout: None,
+            initial_heap_size: 0,
+            max_heap_size: None,
         }
     }
 
@@ -86,6 +162,62 @@ impl Options {
         self.warmup_iterations = iterations;
         self
     }
+
+    pub fn with_conditions(mut self, conditions: Vec<String>) -> Self {
+        self.conditions = Some(conditions);
+        self
+    }
+
+    pub fn with_import_map(mut self, import_map: ImportMap) -> Self {
+        self.import_map = Some(import_map);
+        self
+    }
+
+    pub fn with_cache_dir(mut self, cache_dir: impl Into<std::path::PathBuf>) -> Self {
+        self.cache_dir = Some(cache_dir.into());
+        self
+    }
+
+    pub fn with_cached_only(mut self, cached_only: bool) -> Self {
+        self.cached_only = cached_only;
+        self
+    }
+
+    pub fn with_lockfile(mut self, lockfile: Lockfile) -> Self {
+        self.lockfile = Some(lockfile);
+        self
+    }
+
+    pub fn with_jsx_factory(mut self, factory: impl Into<String>) -> Self {
+        self.jsx_factory = Some(factory.into());
+        self
+    }
+
+    pub fn with_jsx_fragment(mut self, fragment: impl Into<String>) -> Self {
+        self.jsx_fragment = Some(fragment.into());
+        self
+    }
+
+    pub fn with_code_cache(mut self, code_cache: bool) -> Self {
+        self.code_cache = code_cache;
+        self
+    }
+
+    pub fn with_hot_reload(mut self, hot_reload: bool) -> Self {
+        self.hot_reload = hot_reload;
+        self
+    }
+
+    pub fn with_execution_timeout(mut self, timeout: std::time::Duration) -> Self {
+        self.execution_timeout = Some(timeout);
+        self
+    }
+
+    pub fn with_heap_limits(mut self, initial_size: usize, max_size: usize) -> Self {
+        self.initial_heap_size = initial_size;
+        self.max_heap_size = Some(max_size);
+        self
+    }
 }
 
 /// JSTime Instance.
@@ -94,6 +226,9 @@ pub struct JSTime {
     isolate: Option<v8::OwnedIsolate>,
     taking_snapshot: bool,
     warmup_iterations: usize,
+    execution_timeout: Option<std::time::Duration>,
+    // Dropped after the isolate, which has its address
+    heap_limit: Box<termination::HeapLimit>,
 }
 
 impl JSTime {
@@ -101,7 +236,10 @@ impl JSTime {
     pub fn new(options: Options) -> JSTime {
         let mut create_params = v8::Isolate::create_params()
             .external_references(builtins::get_external_references().into_vec().into())
-            .heap_limits(0, 1024 * 1024 * 1024); // 1GB max heap size
+            .heap_limits(
+                options.initial_heap_size,
+                options.max_heap_size.unwrap_or(DEFAULT_MAX_HEAP_SIZE),
+            );
         if let Some(snapshot) = options.snapshot {
             create_params = create_params.snapshot_blob(snapshot.into());
         }
@@ -138,7 +276,21 @@ impl JSTime {
             v8::Global::new(isolate_ref, context)
         };
 
-        isolate.set_slot(IsolateState::new(global_context, options.process_argv));
+        let conditions = package_json::ResolutionConditions::new(options.conditions.as_deref());
+        let state = IsolateState::new(
+            global_context,
+            options.process_argv,
+            conditions,
+            options.import_map,
+            options
+                .cache_dir
+                .unwrap_or_else(http_cache::default_cache_dir),
+            options.cached_only,
+            options.lockfile,
+        );
+        state.borrow_mut().jsx =
+            transpile::JsxFactory::new(options.jsx_factory, options.jsx_fragment);
+        isolate.set_slot(state);
 
         // Create builtins in the snapshot context and set default context
         {
@@ -166,6 +318,14 @@ impl JSTime {
     }
 
     fn create(options: Options, mut isolate: v8::OwnedIsolate) -> JSTime {
+        // Report stack trace positions through source maps
+        isolate.set_prepare_stack_trace_callback(error::prepare_stack_trace_callback);
+        // Keep the frames of errors, which `JsError` reports
+        isolate.set_capture_stack_trace_for_uncaught_exceptions(true, js_error::MAX_FRAMES);
+
+        // Track promise rejections that nothing handles
+        isolate.set_promise_reject_callback(promise_rejection::promise_reject_callback);
+
         // Set up import.meta callback before creating context
         isolate.set_host_initialize_import_meta_object_callback(
             module::host_initialize_import_meta_object_callback,
@@ -183,7 +343,39 @@ impl JSTime {
             v8::Global::new(isolate_ref, context)
         };
 
-        isolate.set_slot(IsolateState::new(global_context, options.process_argv));
+        let conditions = package_json::ResolutionConditions::new(options.conditions.as_deref());
+        let cache_dir = options
+            .cache_dir
+            .unwrap_or_else(http_cache::default_cache_dir);
+        let code_cache = options
+            .code_cache
+            .then(|| code_cache::CodeCache::new(&cache_dir));
+        let state = IsolateState::new(
+            global_context,
+            options.process_argv,
+            conditions,
+            options.import_map,
+            cache_dir,
+            options.cached_only,
+            options.lockfile,
+        );
+        state.borrow_mut().jsx =
+            transpile::JsxFactory::new(options.jsx_factory, options.jsx_fragment);
+        state.borrow_mut().code_cache = code_cache;
+        state.borrow_mut().hot_reload = options.hot_reload.then(hot_reload::HotModules::new);
+        let termination_handle = TerminationHandle::new(&isolate);
+        state.borrow_mut().termination = Some(termination::Termination {
+            handle: termination_handle.clone(),
+            deadline: None,
+        });
+        isolate.set_slot(state);
+
+        // Terminate JavaScript that reaches the heap limit, rather than abort
+        let heap_limit = termination::HeapLimit::install(
+            &mut isolate,
+            termination_handle,
+            options.max_heap_size.unwrap_or(DEFAULT_MAX_HEAP_SIZE),
+        );
 
         // If snapshot data was provided, the builtins already exist within it.
         if options.snapshot.is_none() {
@@ -198,6 +390,8 @@ impl JSTime {
             isolate: Some(isolate),
             taking_snapshot: options.taking_snapshot,
             warmup_iterations: options.warmup_iterations,
+            execution_timeout: options.execution_timeout,
+            heap_limit,
         }
     }
 
@@ -210,8 +404,23 @@ impl JSTime {
         }
     }
 
+    /// Get a handle that terminates the JavaScript this instance runs, from
+    /// any thread.
+    pub fn termination_handle(&mut self) -> TerminationHandle {
+        IsolateState::get(self.isolate())
+            .borrow()
+            .termination
+            .as_ref()
+            .map(|termination| termination.handle.clone())
+            .expect("JSTime has a termination handle")
+    }
+
     /// Import a module by filename.
-    pub fn import(&mut self, filename: &str) -> Result<(), String> {
+    pub fn import(&mut self, filename: &str) -> Result<(), Error> {
+        self.guarded(|jstime| jstime.import_module(filename))
+    }
+
+    fn import_module(&mut self, filename: &str) -> Result<(), JsError> {
         // Perform JIT warmup if configured
         if self.warmup_iterations > 0 {
             self.warmup_import(filename)?;
@@ -233,26 +442,80 @@ impl JSTime {
             match loader.import(tc, &cwd, filename) {
                 Ok(_) => Ok(()),
                 Err(exception) => {
-                    // If we have caught exception details, format them properly
+                    // If we have caught exception details, describe them properly
                     if tc.has_caught() {
-                        Err(crate::error::format_exception(tc))
+                        Err(JsError::from_try_catch(tc))
                     } else {
-                        // Fallback: Format the exception value directly with enhanced formatting
-                        Err(crate::error::format_exception_value(tc, exception))
+                        // Fallback: Describe the exception value directly
+                        Err(JsError::from_value(tc, exception))
                     }
                 }
             }
         };
 
         // Run the event loop to process any pending timers
-        self.run_event_loop();
+        let result = result.and(self.run_event_loop());
 
-        result
+        // Record the modules that were added to the lockfile
+        let lockfile_result = match IsolateState::get(self.isolate())
+            .borrow_mut()
+            .lockfile
+            .as_mut()
+        {
+            Some(lockfile) => lockfile.save().map_err(JsError::from),
+            None => Ok(()),
+        };
+        result.and(lockfile_result)
+    }
+
+    /// Forget the module at `path` and every module that imports it, directly
+    /// or not, so the next import loads them from disk again. Modules that
+    /// already imported them keep the instances they have.
+    /// Returns the paths that were invalidated, starting with `path`.
+    pub fn invalidate(&mut self, path: &str) -> Vec<String> {
+        let path = std::path::Path::new(path)
+            .canonicalize()
+            .ok()
+            .and_then(|path| path.to_str().map(|path| path.to_string()))
+            .unwrap_or_else(|| path.to_string());
+        let state = IsolateState::get(self.isolate());
+        let mut state = state.borrow_mut();
+        let invalidated = state.module_map.dependents(&path);
+        for path in &invalidated {
+            state.module_map.remove(path);
+            if let Some(hot) = state.hot_reload.as_mut() {
+                hot.forget(path);
+            }
+        }
+        invalidated
+    }
+
+    /// Reload the modules whose files changed since they were loaded, by
+    /// their modification time. They're invalidated with the modules that
+    /// import them, up to the modules that accept updates with
+    /// `import.meta.hot.accept()` or the entry modules, which are imported
+    /// again. Returns the paths that were invalidated.
+    pub fn reload_changed(&mut self) -> Result<Vec<String>, Error> {
+        let changed = IsolateState::get(self.isolate())
+            .borrow()
+            .module_map
+            .changed();
+        if changed.is_empty() {
+            return Ok(Vec::new());
+        }
+
+        self.guarded(|jstime| {
+            let context = IsolateState::get(jstime.isolate()).borrow().context();
+            v8::scope!(let scope, jstime.isolate());
+            let context_local = v8::Local::new(scope, context);
+            let mut scope = v8::ContextScope::new(scope, context_local);
+            hot_reload::reload(&mut scope, &changed).map_err(JsError::from)
+        })
     }
 
     /// Warm up the JIT compiler by importing the module multiple times.
     /// This allows V8's TurboFan compiler to optimize the module code.
-    fn warmup_import(&mut self, filename: &str) -> Result<(), String> {
+    fn warmup_import(&mut self, filename: &str) -> Result<(), JsError> {
         for _ in 0..self.warmup_iterations {
             let context = IsolateState::get(self.isolate()).borrow().context();
             v8::scope!(let scope, self.isolate());
@@ -268,9 +531,9 @@ impl JSTime {
                 Ok(_) => {}
                 Err(exception) => {
                     if tc.has_caught() {
-                        return Err(crate::error::format_exception(tc));
+                        return Err(JsError::from_try_catch(tc));
                     } else {
-                        return Err(crate::error::format_exception_value(tc, exception));
+                        return Err(JsError::from_value(tc, exception));
                     }
                 }
             }
@@ -281,23 +544,25 @@ impl JSTime {
     /// Run a script and get a string representation of the result.
     /// This version runs the event loop after execution, which is suitable for file execution.
     /// For REPL usage, use `run_script_no_event_loop` instead.
-    pub fn run_script(&mut self, source: &str, filename: &str) -> Result<String, String> {
-        // Perform JIT warmup if configured
-        if self.warmup_iterations > 0 {
-            self.warmup_script(source, filename)?;
-        }
+    pub fn run_script(&mut self, source: &str, filename: &str) -> Result<String, Error> {
+        self.guarded(|jstime| {
+            // Perform JIT warmup if configured
+            if jstime.warmup_iterations > 0 {
+                jstime.warmup_script(source, filename)?;
+            }
 
-        let result = self.run_script_no_event_loop(source, filename);
+            let result = jstime.eval(source, filename);
 
-        // Run the event loop to process any pending timers
-        self.run_event_loop();
+            // Run the event loop to process any pending timers
+            let event_loop_result = jstime.run_event_loop();
 
-        result
+            result.and_then(|value| event_loop_result.map(|_| value))
+        })
     }
 
     /// Warm up the JIT compiler by running the script multiple times.
     /// This allows V8's TurboFan compiler to optimize the code before the actual execution.
-    fn warmup_script(&mut self, source: &str, filename: &str) -> Result<(), String> {
+    fn warmup_script(&mut self, source: &str, filename: &str) -> Result<(), JsError> {
         for _ in 0..self.warmup_iterations {
             let context = IsolateState::get(self.isolate()).borrow().context();
             v8::scope!(let scope, self.isolate());
@@ -315,7 +580,12 @@ impl JSTime {
         &mut self,
         source: &str,
         filename: &str,
-    ) -> Result<String, String> {
+    ) -> Result<String, Error> {
+        self.guarded(|jstime| jstime.eval(source, filename))
+    }
+
+    /// Run a script and get a string representation of the result
+    fn eval(&mut self, source: &str, filename: &str) -> Result<String, JsError> {
         let context = IsolateState::get(self.isolate()).borrow().context();
         v8::scope!(let scope, self.isolate());
         let context_local = v8::Local::new(scope, context);
@@ -331,23 +601,111 @@ impl JSTime {
 
     /// Tick the event loop to execute ready timers without blocking.
     /// This is suitable for REPL usage to allow timers to execute in the background.
+    /// Promise rejections and exceptions that nothing handled, and
+    /// terminations, are printed to stderr.
     pub fn tick_event_loop(&mut self) {
+        let result = self.guarded(|jstime| {
+            let context = IsolateState::get(jstime.isolate()).borrow().context();
+            v8::scope!(let scope, jstime.isolate());
+            let context_local = v8::Local::new(scope, context);
+            let mut scope = v8::ContextScope::new(scope, context_local);
+            let event_loop = event_loop::get_event_loop(&mut scope);
+            event_loop.borrow_mut().tick(&mut scope)
+        });
+        if let Err(e) = result {
+            eprintln!("{e}");
+        }
+    }
+
+    /// Run `f`, terminating it if it runs past the execution timeout, and
+    /// report a termination as `Error::Terminated`, or
+    /// `Error::HeapLimitExceeded` if the heap reached its limit. What the
+    /// terminated JavaScript left for the event loop is dropped, so the next
+    /// run starts afresh.
+    fn guarded<T>(&mut self, f: impl FnOnce(&mut Self) -> Result<T, JsError>) -> Result<T, Error> {
+        let handle = self.termination_handle();
+        // A termination requested, or the heap limit reached, while nothing
+        // ran is for no run
+        self.recover_heap();
+        handle.reset(self.isolate());
+        let watchdog = self.execution_timeout.map(|timeout| {
+            let state = IsolateState::get(self.isolate());
+            if let Some(termination) = state.borrow_mut().termination.as_mut() {
+                termination.deadline = Some(std::time::Instant::now() + timeout);
+            }
+            termination::Watchdog::start(handle.clone(), timeout)
+        });
+
+        let result = f(self);
+
+        drop(watchdog);
+        let state = IsolateState::get(self.isolate());
+        if let Some(termination) = state.borrow_mut().termination.as_mut() {
+            termination.deadline = None;
+        }
+        if handle.reset(self.isolate()) {
+            state.borrow_mut().uncaught_exception = None;
+            state.borrow_mut().promise_rejections = Default::default();
+            let event_loop = state.borrow().event_loop.clone();
+            event_loop.borrow_mut().clear();
+            // Once the terminated JavaScript's work is dropped, its garbage
+            // can be collected
+            return Err(if self.recover_heap() {
+                Error::HeapLimitExceeded
+            } else {
+                Error::Terminated
+            });
+        }
+        self.store_code_caches();
+        result.map_err(Error::Exception)
+    }
+
+    /// Cache the code of the modules the run compiled, now that they've run
+    fn store_code_caches(&mut self) {
         let context = IsolateState::get(self.isolate()).borrow().context();
         v8::scope!(let scope, self.isolate());
         let context_local = v8::Local::new(scope, context);
         let mut scope = v8::ContextScope::new(scope, context_local);
-        let event_loop = event_loop::get_event_loop(&mut scope);
-        event_loop.borrow_mut().tick(&mut scope);
+        module::store_code_caches(&mut scope);
+    }
+
+    /// Lower the heap limit back if JavaScript reached it, returning whether
+    /// it did
+    fn recover_heap(&mut self) -> bool {
+        let isolate = match self.isolate.as_mut() {
+            Some(i) => i,
+            None => return false,
+        };
+        self.heap_limit.recover(isolate)
+    }
+
+    /// Call `f` with the object `error` was thrown with, in this instance's
+    /// context, if it's still alive. Returns `None` if the thrown value isn't
+    /// an object, was collected, or `error` wasn't returned by this instance.
+    /// `f` uses the `v8` crate at the version jstime depends on.
+    pub fn with_error_value<R>(
+        &mut self,
+        error: &JsError,
+        f: impl for<'s, 'i> FnOnce(&mut v8::PinScope<'s, 'i>, v8::Local<'s, v8::Value>) -> R,
+    ) -> Option<R> {
+        let id = error.value_id()?;
+        let context = IsolateState::get(self.isolate()).borrow().context();
+        v8::scope!(let scope, self.isolate());
+        let context_local = v8::Local::new(scope, context);
+        let mut scope = v8::ContextScope::new(scope, context_local);
+        let value = js_error::thrown_value(&mut scope, id)?;
+        Some(f(&mut scope, value))
     }
 
-    /// Run the event loop until all pending operations are complete
-    fn run_event_loop(&mut self) {
+    /// Run the event loop until all pending operations are complete, or a
+    /// promise rejection or an exception a callback throws isn't handled
+    fn run_event_loop(&mut self) -> Result<(), JsError> {
         let context = IsolateState::get(self.isolate()).borrow().context();
         v8::scope!(let scope, self.isolate());
         let context_local = v8::Local::new(scope, context);
         let mut scope = v8::ContextScope::new(scope, context_local);
         let event_loop = event_loop::get_event_loop(&mut scope);
-        event_loop.borrow_mut().run(&mut scope);
+        event_loop.borrow_mut().run(&mut scope)
     }
 
     /// Get all global property names for REPL autocomplete.
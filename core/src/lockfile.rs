@@ -0,0 +1,227 @@
+//! `jstime.lock`: SHA-256 hashes of the modules loaded from outside the
+//! project, so a changed remote module or node_modules package is caught
+//! before it runs.
+//!
+//! ```json
+//! {
+//!   "version": "1",
+//!   "modules": {
+//!     "https://example.com/mod.js": "sha256-...",
+//!     "node_modules/pkg/index.js": "sha256-..."
+//!   }
+//! }
+//! ```
+//!
+//! Remote modules are keyed by URL. Files in node_modules are keyed by
+//! their path relative to the lockfile, or by absolute path outside of its
+//! directory.
+
+use crate::http_cache::{is_remote_url, sha256_hex};
+use crate::json::{Json, json_string};
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+
+const LOCKFILE_VERSION: &str = "1";
+
+/// A lockfile of module integrity hashes, checked as modules are loaded
+#[derive(Debug, Clone)]
+pub struct Lockfile {
+    path: PathBuf,
+    // Directory that node_modules keys are relative to
+    dir: PathBuf,
+    modules: BTreeMap<String, String>,
+    // Whether modules were added since the lockfile was read
+    dirty: bool,
+}
+
+impl Lockfile {
+    /// Open the lockfile at `path`, or start a new one if it doesn't exist.
+    /// Loaded modules must match their recorded hashes; modules that aren't
+    /// recorded yet are added.
+    pub fn open(path: impl AsRef<Path>) -> Result<Lockfile, String> {
+        let mut lockfile = Lockfile::regenerate(path);
+        lockfile.dirty = false;
+        let source = match std::fs::read_to_string(&lockfile.path) {
+            Ok(source) => source,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(lockfile),
+            Err(e) => {
+                return Err(format!(
+                    "Cannot read lockfile '{}': {}",
+                    lockfile.path.display(),
+                    e
+                ));
+            }
+        };
+
+        let invalid =
+            |reason: &str| format!("Invalid lockfile '{}': {}", lockfile.path.display(), reason);
+        let json = Json::parse(&source).map_err(|e| invalid(&e))?;
+        match json.get("version") {
+            Some(Json::String(version)) if version == LOCKFILE_VERSION => {}
+            _ => return Err(invalid("unsupported version")),
+        }
+        let modules = match json.get("modules") {
+            Some(Json::Object(modules)) => modules,
+            None => return Ok(lockfile),
+            Some(_) => return Err(invalid("\"modules\" must be an object")),
+        };
+        for (key, hash) in modules {
+            let Json::String(hash) = hash else {
+                return Err(invalid(&format!("the hash for '{}' must be a string", key)));
+            };
+            lockfile.modules.insert(key.clone(), hash.clone());
+        }
+        Ok(lockfile)
+    }
+
+    /// Start a new, empty lockfile at `path`, which replaces any existing
+    /// lockfile when it's saved
+    pub fn regenerate(path: impl AsRef<Path>) -> Lockfile {
+        let path = std::path::absolute(path.as_ref()).unwrap_or_else(|_| path.as_ref().into());
+        let dir = path
+            .parent()
+            .map(Path::to_path_buf)
+            .unwrap_or_else(|| PathBuf::from("/"));
+        Lockfile {
+            path,
+            dir,
+            modules: BTreeMap::new(),
+            dirty: true,
+        }
+    }
+
+    /// Check the source of the module at `specifier`, a path or URL, against
+    /// its recorded hash. Modules from outside the project that aren't
+    /// recorded yet are added; project modules aren't checked.
+    pub(crate) fn check(&mut self, specifier: &str, source: &[u8]) -> Result<(), String> {
+        let Some(key) = self.key(specifier) else {
+            return Ok(());
+        };
+        let hash = format!("sha256-{}", sha256_hex(source));
+        match self.modules.get(&key) {
+            Some(locked) if *locked == hash => Ok(()),
+            Some(locked) => Err(format!(
+                "Integrity check failed for '{}': its source has {}, but {} has {}. \
+                 Regenerate the lockfile if the change is expected.",
+                specifier,
+                hash,
+                self.path.display(),
+                locked
+            )),
+            None => {
+                self.modules.insert(key, hash);
+                self.dirty = true;
+                Ok(())
+            }
+        }
+    }
+
+    /// Write the lockfile, if modules were added to it
+    pub(crate) fn save(&mut self) -> Result<(), String> {
+        if !self.dirty {
+            return Ok(());
+        }
+        std::fs::write(&self.path, self.to_json())
+            .map_err(|e| format!("Cannot write lockfile '{}': {}", self.path.display(), e))?;
+        self.dirty = false;
+        Ok(())
+    }
+
+    fn key(&self, specifier: &str) -> Option<String> {
+        if is_remote_url(specifier) {
+            return Some(specifier.to_string());
+        }
+        let path = Path::new(specifier);
+        if !path.components().any(|c| c.as_os_str() == "node_modules") {
+            return None;
+        }
+        let key = path.strip_prefix(&self.dir).unwrap_or(path);
+        Some(key.to_string_lossy().into_owned())
+    }
+
+    fn to_json(&self) -> String {
+        let mut json = format!(
+            "{{\n  \"version\": {},\n  \"modules\": {{",
+            json_string(LOCKFILE_VERSION)
+        );
+        for (i, (key, hash)) in self.modules.iter().enumerate() {
+            let separator = if i == 0 { "" } else { "," };
+            json.push_str(&format!(
+                "{}\n    {}: {}",
+                separator,
+                json_string(key),
+                json_string(hash)
+            ));
+        }
+        if !self.modules.is_empty() {
+            json.push_str("\n  ");
+        }
+        json.push_str("}\n}\n");
+        json
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_check_records_and_verifies() {
+        let mut lockfile = Lockfile::regenerate("/project/jstime.lock");
+
+        // Project files aren't recorded
+        lockfile.check("/project/src/main.js", b"a").unwrap();
+        assert!(lockfile.modules.is_empty());
+
+        lockfile
+            .check("/project/node_modules/pkg/index.js", b"a")
+            .unwrap();
+        lockfile.check("https://example.com/mod.js", b"b").unwrap();
+        lockfile
+            .check("/elsewhere/node_modules/pkg/index.js", b"c")
+            .unwrap();
+        assert_eq!(
+            lockfile.modules.keys().collect::<Vec<_>>(),
+            [
+                "/elsewhere/node_modules/pkg/index.js",
+                "https://example.com/mod.js",
+                "node_modules/pkg/index.js"
+            ]
+        );
+
+        lockfile
+            .check("/project/node_modules/pkg/index.js", b"a")
+            .unwrap();
+        let err = lockfile
+            .check("https://example.com/mod.js", b"changed")
+            .unwrap_err();
+        assert!(err.contains("Integrity check failed"));
+    }
+
+    #[test]
+    fn test_save_and_open() {
+        let dir = std::env::temp_dir().join("jstime_test_lockfile_roundtrip");
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("jstime.lock");
+
+        let mut lockfile = Lockfile::open(&path).unwrap();
+        lockfile
+            .check("https://example.com/\"quoted\".js", b"source")
+            .unwrap();
+        lockfile.save().unwrap();
+
+        let mut reopened = Lockfile::open(&path).unwrap();
+        assert_eq!(reopened.modules, lockfile.modules);
+        assert!(
+            reopened
+                .check("https://example.com/\"quoted\".js", b"other")
+                .is_err()
+        );
+
+        std::fs::write(&path, r#"{"version": "2", "modules": {}}"#).unwrap();
+        assert!(Lockfile::open(&path).is_err());
+
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+}
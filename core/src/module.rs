@@ -1,8 +1,11 @@
-use crate::IsolateState;
-use rustc_hash::FxHashMap;
+use crate::http_cache::{HttpCache, is_remote_url};
+use crate::package_json::{self, Conditions};
+use crate::{ImportMap, IsolateState};
+use rustc_hash::{FxHashMap, FxHashSet};
 use std::collections::HashSet;
 use std::path::{Path, PathBuf};
-use std::sync::{OnceLock, RwLock};
+use std::sync::{Arc, OnceLock, RwLock};
+use std::time::SystemTime;
 
 // Global source code cache shared across all JSTime instances
 // Maps absolute file path to source code content
@@ -11,45 +14,236 @@ use std::sync::{OnceLock, RwLock};
 // - Startup time: Avoids repeated file I/O for commonly imported modules
 // - Memory efficiency: Source code is shared across JSTime instances
 //
-// Note: The cache does not automatically invalidate when files change.
-// This is acceptable for most use cases where modules don't change during runtime.
-// For development scenarios where file changes are expected, consider restarting
-// the application or using the clear_source_cache() function.
-static SOURCE_CACHE: OnceLock<RwLock<FxHashMap<String, String>>> = OnceLock::new();
+// Each file's entry records its modification time when it was read, and is
+// read again once that changes, so edited modules are picked up by the next
+// import. Remote modules don't change and are kept until they're invalidated.
+static SOURCE_CACHE: OnceLock<RwLock<FxHashMap<String, CachedSource>>> = OnceLock::new();
+
+struct CachedSource {
+    source: String,
+    modified: Option<SystemTime>,
+}
 
-fn get_source_cache() -> &'static RwLock<FxHashMap<String, String>> {
+fn get_source_cache() -> &'static RwLock<FxHashMap<String, CachedSource>> {
     SOURCE_CACHE.get_or_init(|| RwLock::new(FxHashMap::default()))
 }
 
-/// Clear the global source code cache.
-/// This is useful in testing or development scenarios where modules may change.
-#[allow(dead_code)]
-pub(crate) fn clear_source_cache() {
-    get_source_cache().write().unwrap().clear();
+/// The modification time of the file at `path`, if it can be read
+pub(crate) fn modified_time(path: &str) -> Option<SystemTime> {
+    std::fs::metadata(path)
+        .and_then(|metadata| metadata.modified())
+        .ok()
+}
+
+/// Drop the cached source of the file or URL at `path`, and the cached
+/// package.json files if it's one of them
+pub(crate) fn invalidate_source(path: &str) {
+    get_source_cache().write().unwrap().remove(path);
+    if Path::new(path).file_name() == Some("package.json".as_ref()) {
+        package_json::clear_cache();
+    }
 }
 
 /// Read source code from file, using cache if available
-fn read_source_cached(path: &str) -> std::io::Result<String> {
+pub(crate) fn read_source_cached(path: &str) -> std::io::Result<String> {
+    let modified = modified_time(path);
+
     // Try to read from cache first
     {
         let cache = get_source_cache().read().unwrap();
-        if let Some(source) = cache.get(path) {
-            return Ok(source.clone());
+        if let Some(cached) = cache.get(path)
+            && cached.modified == modified
+        {
+            return Ok(cached.source.clone());
         }
     }
 
-    // Not in cache, read from file
+    // Not in cache, or changed since, read from file. A change between the
+    // stat and the read is caught by the next read, as the time won't match.
     let source = std::fs::read_to_string(path)?;
 
     // Store in cache
     {
         let mut cache = get_source_cache().write().unwrap();
-        cache.insert(path.to_string(), source.clone());
+        cache.insert(
+            path.to_string(),
+            CachedSource {
+                source: source.clone(),
+                modified,
+            },
+        );
     }
 
     Ok(source)
 }
 
+/// Load the source of a module imported from an http(s) URL, through the
+/// source cache and then the on-disk HTTP cache
+fn read_remote_source(url: &str, http_cache: &HttpCache) -> Result<String, String> {
+    {
+        let cache = get_source_cache().read().unwrap();
+        if let Some(cached) = cache.get(url) {
+            return Ok(cached.source.clone());
+        }
+    }
+
+    let source = http_cache.load(url)?;
+    get_source_cache().write().unwrap().insert(
+        url.to_string(),
+        CachedSource {
+            source: source.clone(),
+            modified: None,
+        },
+    );
+    Ok(source)
+}
+
+/// Check a module's source against the lockfile, if there is one. Throws
+/// and returns false if the source doesn't match its recorded hash.
+pub(crate) fn check_integrity(scope: &mut v8::PinScope, path: &str, source: &[u8]) -> bool {
+    let isolate: &mut v8::Isolate = scope;
+    let result = match IsolateState::get(isolate).borrow_mut().lockfile.as_mut() {
+        Some(lockfile) => lockfile.check(path, source),
+        None => Ok(()),
+    };
+    match result {
+        Ok(()) => true,
+        Err(message) => {
+            crate::error::throw_error(scope, &message);
+            false
+        }
+    }
+}
+
+/// Transform a TypeScript or JSX module's source to JavaScript. Throws a
+/// SyntaxError and returns None if it can't be transformed.
+fn transpile_source(scope: &mut v8::PinScope, path: &str, source: String) -> Option<String> {
+    if !crate::transpile::is_transpiled(path) {
+        return Some(source);
+    }
+    let isolate: &mut v8::Isolate = scope;
+    let jsx = IsolateState::get(isolate).borrow().jsx.clone();
+    match crate::transpile::transpile(path, &source, &jsx) {
+        Ok(code) => Some(code),
+        Err(message) => {
+            crate::error::throw_syntax_error(scope, &message);
+            None
+        }
+    }
+}
+
+/// Extensions tried, in order, when `require()` is given a path without one
+const REQUIRE_EXTENSIONS: [&str; 3] = ["js", "json", "cjs"];
+
+/// Resolve a `require()` specifier to an absolute file path, following the
+/// CommonJS rules: the exact file, then with each of REQUIRE_EXTENSIONS
+/// added, then a directory's package.json "main" or its index file.
+/// Bare and `#` specifiers resolve through node_modules and package.json
+/// like they do for `import`, using the require conditions.
+/// A referrer ending in `/` is taken as a directory.
+/// Built-in modules are handled by the caller.
+pub(crate) fn resolve_require(
+    referrer_path: &str,
+    specifier: &str,
+    conditions: &Conditions,
+) -> Option<String> {
+    if is_package_import(specifier) || is_bare_specifier(specifier) {
+        let resolved = normalize_path(referrer_path, specifier, conditions, None).ok()?;
+        return Path::new(&resolved).is_file().then_some(resolved);
+    }
+
+    let referrer = Path::new(referrer_path);
+    let base_dir = if referrer_path.ends_with('/') || referrer.is_dir() {
+        referrer
+    } else {
+        referrer.parent().unwrap_or_else(|| Path::new("."))
+    };
+    let candidate = base_dir.join(specifier);
+    let resolved = resolve_as_file(&candidate).or_else(|| resolve_as_directory(&candidate))?;
+    let resolved = resolved.canonicalize().unwrap_or(resolved);
+    resolved.to_str().map(|s| s.to_string())
+}
+
+fn resolve_as_file(path: &Path) -> Option<PathBuf> {
+    if path.is_file() {
+        return Some(path.to_path_buf());
+    }
+    let file_name = path.file_name()?.to_str()?;
+    REQUIRE_EXTENSIONS
+        .iter()
+        .map(|ext| path.with_file_name(format!("{}.{}", file_name, ext)))
+        .find(|candidate| candidate.is_file())
+}
+
+fn resolve_as_directory(path: &Path) -> Option<PathBuf> {
+    if !path.is_dir() {
+        return None;
+    }
+    if let Some(package) = package_json::read(path)
+        && let Some(main) = &package.main
+    {
+        let main_path = path.join(main);
+        if let Some(resolved) = resolve_as_file(&main_path) {
+            return Some(resolved);
+        }
+        if let Some(resolved) = resolve_as_file(&main_path.join("index")) {
+            return Some(resolved);
+        }
+    }
+    resolve_as_file(&path.join("index"))
+}
+
+/// Resolve a relative import from a TypeScript or JSX module that doesn't
+/// name an existing file, the way TypeScript does: `./a.js` can refer to
+/// `./a.ts`, and `./a` to `./a.ts`, `./a.js` or `./a/index.ts`, trying
+/// each of transpile::RESOLVE_EXTENSIONS
+fn resolve_typescript_import(path: &Path) -> Option<PathBuf> {
+    if path.is_file() {
+        return None;
+    }
+    let sources: &[&str] = match path.extension().and_then(|ext| ext.to_str()) {
+        Some("js") => &["ts", "tsx"],
+        Some("mjs") => &["mts"],
+        Some("jsx") => &["tsx"],
+        _ => &[],
+    };
+    if let Some(source) = sources
+        .iter()
+        .map(|ext| path.with_extension(ext))
+        .find(|candidate| candidate.is_file())
+    {
+        return Some(source);
+    }
+
+    let file_name = path.file_name()?.to_str()?;
+    let extensions = crate::transpile::RESOLVE_EXTENSIONS;
+    extensions
+        .iter()
+        .map(|ext| path.with_file_name(format!("{}.{}", file_name, ext)))
+        .chain(
+            extensions
+                .iter()
+                .map(|ext| path.join(format!("index.{}", ext))),
+        )
+        .find(|candidate| candidate.is_file())
+}
+
+/// Whether a file is loaded as CommonJS: `.cjs` files, and `.js` files whose
+/// nearest package.json has `"type": "commonjs"`. Other `.js` files are ES
+/// modules when imported.
+pub(crate) fn is_commonjs(path: &str) -> bool {
+    if path.ends_with(".cjs") {
+        return true;
+    }
+    path.ends_with(".js") && package_type(path).as_deref() == Some("commonjs")
+}
+
+/// The "type" field of the package.json closest to a file, if it has one
+pub(crate) fn package_type(path: &str) -> Option<String> {
+    let (_, package) = package_json::lookup_scope(Path::new(path).parent()?)?;
+    package.type_.clone()
+}
+
 /// Check if a specifier is a package import (starts with `#`).
 /// Package imports are defined in the `imports` field of package.json.
 ///
@@ -122,286 +316,115 @@ fn parse_package_specifier(specifier: &str) -> (&str, &str) {
     }
 }
 
-/// Read and parse a package.json file, returning the main entry point if found.
-/// Handles both "main" and "exports" fields according to Node.js resolution.
-fn read_package_json_main(package_dir: &Path, subpath: &str) -> Option<PathBuf> {
-    let package_json_path = package_dir.join("package.json");
-    let content = std::fs::read_to_string(&package_json_path).ok()?;
-
-    // Parse the JSON using a simple approach
-    // We look for "main" and "exports" fields
-
-    // First check if there's a subpath - if so, we need to resolve it
-    if !subpath.is_empty() {
-        return resolve_package_subpath(package_dir, &content, subpath);
-    }
-
-    // Try to find "exports" field first (modern approach)
-    if let Some(entry) = resolve_exports_main(&content, package_dir) {
-        return Some(entry);
-    }
-
-    // Fall back to "main" field
-    if let Some(main) = extract_json_string_field(&content, "main") {
-        let main_path = package_dir.join(&main);
-        // Try the path as-is first
-        if main_path.exists() {
-            return Some(main_path);
-        }
-        // Try adding .js extension
-        let with_js = package_dir.join(format!("{}.js", main));
-        if with_js.exists() {
-            return Some(with_js);
-        }
-        // Try as directory with index.js
-        let as_dir = main_path.join("index.js");
-        if as_dir.exists() {
-            return Some(as_dir);
-        }
-    }
-
-    // Default to index.js
-    let index_path = package_dir.join("index.js");
-    if index_path.exists() {
-        return Some(index_path);
-    }
-
-    None
-}
-
-/// Resolve the "exports" field for the main entry point.
-/// Handles both simple string exports and conditional exports objects.
-fn resolve_exports_main(content: &str, package_dir: &Path) -> Option<PathBuf> {
-    // Find the exports field
-    let exports_start = content.find("\"exports\"")?;
-    let after_exports = &content[exports_start + 9..];
-    let colon_pos = after_exports.find(':')?;
-    let value_start = &after_exports[colon_pos + 1..].trim_start();
-
-    if value_start.starts_with('"') {
-        // Simple string export: "exports": "./dist/index.js"
-        if let Some(entry) = extract_quoted_string(value_start) {
-            let entry_path = package_dir.join(entry.trim_start_matches("./"));
-            if entry_path.exists() {
-                return Some(entry_path);
-            }
-        }
-    } else if value_start.starts_with('{') {
-        // Object export: "exports": { ".": "./dist/index.js" } or conditional exports
-        // Look for "." entry or "default" entry
-        if let Some(main_entry) = resolve_exports_dot_entry(value_start, package_dir) {
-            return Some(main_entry);
-        }
-    }
-
-    None
-}
-
-/// Resolve the "." entry in an exports object.
-fn resolve_exports_dot_entry(exports_obj: &str, package_dir: &Path) -> Option<PathBuf> {
-    // Look for ".": which is the main entry point
-    // This handles: { ".": "./index.js" } or { ".": { "default": "./index.js" } }
-
-    let dot_patterns = ["\".\": ", "\".\": {"];
-    for pattern in &dot_patterns {
-        if let Some(pos) = exports_obj.find(pattern) {
-            let after_dot = &exports_obj[pos + pattern.len() - 1..].trim_start();
-            if after_dot.starts_with('"') {
-                // Direct string value
-                if let Some(entry) = extract_quoted_string(after_dot) {
-                    let entry_path = package_dir.join(entry.trim_start_matches("./"));
-                    if entry_path.exists() {
-                        return Some(entry_path);
-                    }
-                }
-            } else if after_dot.starts_with('{') {
-                // Conditional exports object, look for "default" or "import"
-                if let Some(entry) = extract_conditional_export(after_dot, package_dir) {
-                    return Some(entry);
-                }
-            }
-        }
-    }
-
-    // Also check for "default" at the top level for simpler exports
-    if let Some(entry) = extract_conditional_export(exports_obj, package_dir) {
-        return Some(entry);
-    }
-
-    None
-}
-
-/// Extract the "default" or "import" entry from a conditional exports object.
-fn extract_conditional_export(obj: &str, package_dir: &Path) -> Option<PathBuf> {
-    // Priority: "import" > "default" > "require" (for ESM resolution)
-    for key in &["\"import\"", "\"default\"", "\"require\""] {
-        if let Some(pos) = obj.find(key) {
-            let after_key = &obj[pos + key.len()..];
-            let colon_pos = after_key.find(':')?;
-            let value = after_key[colon_pos + 1..].trim_start();
-            if let Some(entry) = extract_quoted_string(value) {
-                let entry_path = package_dir.join(entry.trim_start_matches("./"));
-                if entry_path.exists() {
-                    return Some(entry_path);
-                }
-            }
-        }
-    }
-    None
-}
-
-/// Resolve a subpath import from a package's exports field.
-/// This function is called once per import, not in a loop, so string allocation is acceptable.
-fn resolve_package_subpath(package_dir: &Path, content: &str, subpath: &str) -> Option<PathBuf> {
-    // First try direct file resolution (for packages without exports)
-    let direct_path = package_dir.join(subpath);
-    if direct_path.exists() {
-        return Some(direct_path);
-    }
-    // Try with .js extension
-    let with_js = package_dir.join(format!("{}.js", subpath));
-    if with_js.exists() {
-        return Some(with_js);
-    }
-    // Try as directory with index.js
-    let as_dir = direct_path.join("index.js");
-    if as_dir.exists() {
-        return Some(as_dir);
+/// Find the entry point for `subpath` (empty for the main entry) of the
+/// package in `package_dir`. Packages with an "exports" field only expose
+/// what it lists. Others fall back to "main", index.js, or the file itself.
+fn resolve_package_entry(
+    package_dir: &Path,
+    subpath: &str,
+    conditions: &Conditions,
+) -> Option<String> {
+    let package = package_json::read(package_dir);
+
+    if let Some(exports) = package.as_ref().and_then(|p| p.exports.as_ref()) {
+        let subpath = if subpath.is_empty() {
+            ".".to_string()
+        } else {
+            format!("./{}", subpath)
+        };
+        return package_json::package_exports_resolve(package_dir, &subpath, exports, conditions);
     }
 
-    // Try to resolve from exports field
-    // Note: The format! call below allocates a small string, but this is acceptable
-    // because this code path is only reached when direct resolution fails and
-    // the function is called once per import, not in an inner loop.
-    if let Some(exports_start) = content.find("\"exports\"") {
-        let after_exports = &content[exports_start..];
-        // Look for "./<subpath>" in exports
-        let subpath_pattern = format!("\"./{}", subpath);
-        if let Some(pos) = after_exports.find(&subpath_pattern) {
-            let remaining = &after_exports[pos..];
-            // Find the end of this key
-            if let Some(quote_end) = remaining[1..].find('"') {
-                let _key = &remaining[1..quote_end + 1];
-                // Now find the value after the colon
-                let after_key = &remaining[quote_end + 2..];
-                if let Some(colon_pos) = after_key.find(':') {
-                    let value = after_key[colon_pos + 1..].trim_start();
-                    if let Some(entry) = extract_quoted_string(value) {
-                        let entry_path = package_dir.join(entry.trim_start_matches("./"));
-                        if entry_path.exists() {
-                            return Some(entry_path);
-                        }
-                    } else if value.starts_with('{') {
-                        // Conditional export for subpath
-                        if let Some(entry) = extract_conditional_export(value, package_dir) {
-                            return Some(entry);
-                        }
-                    }
-                }
-            }
+    let mut candidates = Vec::with_capacity(4);
+    if !subpath.is_empty() {
+        let direct_path = package_dir.join(subpath);
+        candidates.push(direct_path.clone());
+        candidates.push(package_dir.join(format!("{}.js", subpath)));
+        candidates.push(direct_path.join("index.js"));
+    } else {
+        if let Some(main) = package.as_ref().and_then(|p| p.main.as_deref()) {
+            let main_path = package_dir.join(main);
+            candidates.push(main_path.clone());
+            candidates.push(package_dir.join(format!("{}.js", main)));
+            candidates.push(main_path.join("index.js"));
         }
+        candidates.push(package_dir.join("index.js"));
     }
 
-    None
+    candidates
+        .into_iter()
+        .find(|candidate| candidate.is_file())
+        .and_then(|path| path.to_str().map(|s| s.to_string()))
 }
 
-/// Extract a simple string value from a JSON field.
-/// This is a simple parser that looks for "field": "value" patterns.
-fn extract_json_string_field(json: &str, field: &str) -> Option<String> {
-    let pattern = format!("\"{}\"", field);
-    let field_pos = json.find(&pattern)?;
-    let after_field = &json[field_pos + pattern.len()..];
-
-    // Skip whitespace and colon
-    let value_start = after_field.trim_start();
-    if !value_start.starts_with(':') {
-        return None;
+/// The directory a referrer's relative specifiers are resolved against
+fn referrer_dir(referrer_path: &str) -> Option<&Path> {
+    let referrer = Path::new(referrer_path);
+    if referrer.is_file() {
+        referrer.parent()
+    } else {
+        Some(referrer)
     }
-    let value_start = value_start[1..].trim_start();
-
-    extract_quoted_string(value_start)
 }
 
 /// Resolve a self-referencing import.
 /// This allows a package to import itself using its own name from the `exports` field.
 ///
-/// Starting from the referrer's directory, walk up the directory tree
-/// looking for a package.json whose `name` field matches the specifier's package name.
-/// If found, resolve using the `exports` field of that package.json.
+/// The package containing the referrer is used if its `name` field matches
+/// the specifier's package name and it has an `exports` field.
 ///
 /// Examples:
 /// - In package "my-lib", `import { foo } from 'my-lib'` resolves to the package's own exports
 /// - In package "my-lib", `import { bar } from 'my-lib/utils'` resolves to the package's "./utils" export
-fn resolve_self_reference(referrer_path: &str, specifier: &str) -> Option<String> {
+fn resolve_self_reference(
+    referrer_path: &str,
+    specifier: &str,
+    conditions: &Conditions,
+) -> Option<String> {
     let (package_name, subpath) = parse_package_specifier(specifier);
+    let (package_dir, package) = package_json::lookup_scope(referrer_dir(referrer_path)?)?;
 
-    // Start from the referrer's directory
-    let referrer = Path::new(referrer_path);
-    let mut current_dir = if referrer.is_file() {
-        referrer.parent()?
+    if package.name.as_deref() != Some(package_name) {
+        return None;
+    }
+    let exports = package.exports.as_ref()?;
+    let subpath = if subpath.is_empty() {
+        ".".to_string()
     } else {
-        referrer
+        format!("./{}", subpath)
     };
-
-    // Walk up the directory tree looking for package.json with matching name
-    loop {
-        let package_json_path = current_dir.join("package.json");
-        if package_json_path.exists()
-            && let Ok(content) = std::fs::read_to_string(&package_json_path)
-            && let Some(name) = extract_json_string_field(&content, "name")
-            && name == package_name
-        {
-            // Found a matching package.json, resolve using exports field
-            if let Some(entry_path) = read_package_json_main(current_dir, subpath) {
-                return entry_path.to_str().map(|s| s.to_string());
-            }
-        }
-
-        // Move up to parent directory
-        match current_dir.parent() {
-            Some(parent) => current_dir = parent,
-            None => break,
-        }
-    }
-
-    None
+    package_json::package_exports_resolve(&package_dir, &subpath, exports, conditions)
 }
 
 /// Resolve a bare specifier by searching node_modules directories.
 /// This implements the Node.js module resolution algorithm.
 ///
 /// Starting from the referrer's directory, walk up the directory tree
-/// looking for node_modules/<package_name>.
+/// looking for node_modules/<package_name>. The first package found is used.
 ///
 /// Also supports self-referencing: a package can import itself using its own name.
-fn resolve_bare_specifier(referrer_path: &str, specifier: &str) -> Option<String> {
+pub(crate) fn resolve_bare_specifier(
+    referrer_path: &str,
+    specifier: &str,
+    conditions: &Conditions,
+) -> Option<String> {
     // First, try to resolve as a self-reference
     // This allows a package to import itself using its own name
-    if let Some(resolved) = resolve_self_reference(referrer_path, specifier) {
+    if let Some(resolved) = resolve_self_reference(referrer_path, specifier, conditions) {
         return Some(resolved);
     }
 
     let (package_name, subpath) = parse_package_specifier(specifier);
 
     // Start from the referrer's directory
-    let referrer = Path::new(referrer_path);
-    let mut current_dir = if referrer.is_file() {
-        referrer.parent()?
-    } else {
-        referrer
-    };
+    let mut current_dir = referrer_dir(referrer_path)?;
 
     // Walk up the directory tree
     loop {
-        let node_modules_dir = current_dir.join("node_modules");
-        if node_modules_dir.is_dir() {
-            let package_dir = node_modules_dir.join(package_name);
-            if package_dir.is_dir() {
-                // Found the package, resolve the entry point
-                if let Some(entry_path) = read_package_json_main(&package_dir, subpath) {
-                    return entry_path.to_str().map(|s| s.to_string());
-                }
-            }
+        let package_dir = current_dir.join("node_modules").join(package_name);
+        if package_dir.is_dir() {
+            // Found the package, resolve the entry point
+            return resolve_package_entry(&package_dir, subpath, conditions);
         }
 
         // Move up to parent directory
@@ -415,149 +438,14 @@ fn resolve_bare_specifier(referrer_path: &str, specifier: &str) -> Option<String
 }
 
 /// Resolve a package import (specifiers starting with `#`).
-/// Package imports are defined in the `imports` field of package.json.
-///
-/// Starting from the referrer's directory, walk up the directory tree
-/// looking for a package.json with an `imports` field that matches the specifier.
-fn resolve_package_import(referrer_path: &str, specifier: &str) -> Option<String> {
-    // Start from the referrer's directory
-    let referrer = Path::new(referrer_path);
-    let mut current_dir = if referrer.is_file() {
-        referrer.parent()?
-    } else {
-        referrer
-    };
-
-    // Walk up the directory tree looking for package.json with imports field
-    loop {
-        let package_json_path = current_dir.join("package.json");
-        if package_json_path.exists()
-            && let Ok(content) = std::fs::read_to_string(&package_json_path)
-            && let Some(resolved) = resolve_imports_field(&content, specifier, current_dir)
-        {
-            return Some(resolved);
-        }
-
-        // Move up to parent directory
-        match current_dir.parent() {
-            Some(parent) => current_dir = parent,
-            None => break,
-        }
-    }
-
-    None
-}
-
-/// Resolve a specifier using the `imports` field in package.json.
-/// Handles both exact matches and pattern matches (with `*` wildcards).
-fn resolve_imports_field(content: &str, specifier: &str, package_dir: &Path) -> Option<String> {
-    // Find the imports field
-    let imports_start = content.find("\"imports\"")?;
-    let after_imports = &content[imports_start + 9..];
-    let colon_pos = after_imports.find(':')?;
-    let value_start = &after_imports[colon_pos + 1..].trim_start();
-
-    if !value_start.starts_with('{') {
-        return None;
-    }
-
-    // First, try exact match for the specifier
-    let exact_pattern = format!("\"{}\"", specifier);
-    if let Some(pos) = value_start.find(&exact_pattern) {
-        let after_key = &value_start[pos + exact_pattern.len()..];
-        if let Some(colon_pos) = after_key.find(':') {
-            let value = after_key[colon_pos + 1..].trim_start();
-            if let Some(resolved) = resolve_import_target(value, package_dir) {
-                return Some(resolved);
-            }
-        }
-    }
-
-    // Try pattern matching with wildcards (e.g., "#internal/*": "./src/internal/*.js")
-    // Find all patterns in the imports field
-    let mut search_pos = 0;
-    while let Some(quote_pos) = value_start[search_pos..].find("\"#") {
-        let start = search_pos + quote_pos + 1; // Skip the opening quote
-        if let Some(end_quote) = value_start[start..].find('"') {
-            let pattern = &value_start[start..start + end_quote];
-
-            // Check if this is a wildcard pattern
-            if pattern.contains('*')
-                && let Some(matched) = match_import_pattern(pattern, specifier)
-            {
-                // Find the value for this pattern
-                let after_pattern = &value_start[start + end_quote + 1..];
-                if let Some(colon_pos) = after_pattern.find(':') {
-                    let value = after_pattern[colon_pos + 1..].trim_start();
-                    if let Some(target) = extract_quoted_string(value) {
-                        // Replace * in target with matched portion
-                        let resolved_target = target.replace('*', &matched);
-                        let resolved_path =
-                            package_dir.join(resolved_target.trim_start_matches("./"));
-                        if resolved_path.exists() {
-                            return resolved_path.to_str().map(|s| s.to_string());
-                        }
-                    }
-                }
-            }
-
-            search_pos = start + end_quote + 1;
-        } else {
-            break;
-        }
-    }
-
-    None
-}
-
-/// Match a specifier against an import pattern with wildcards.
-/// Returns the portion that matched the `*` if successful.
-fn match_import_pattern(pattern: &str, specifier: &str) -> Option<String> {
-    if let Some(star_pos) = pattern.find('*') {
-        let prefix = &pattern[..star_pos];
-        let suffix = &pattern[star_pos + 1..];
-
-        if specifier.starts_with(prefix) && specifier.ends_with(suffix) {
-            let matched_len = specifier.len() - prefix.len() - suffix.len();
-            let matched = &specifier[prefix.len()..prefix.len() + matched_len];
-            return Some(matched.to_string());
-        }
-    }
-    None
-}
-
-/// Resolve an import target value, handling both string values and conditional objects.
-fn resolve_import_target(value: &str, package_dir: &Path) -> Option<String> {
-    let value = value.trim();
-
-    if value.starts_with('"') {
-        // Simple string value
-        if let Some(target) = extract_quoted_string(value) {
-            let resolved_path = package_dir.join(target.trim_start_matches("./"));
-            if resolved_path.exists() {
-                return resolved_path.to_str().map(|s| s.to_string());
-            }
-        }
-    } else if value.starts_with('{') {
-        // Conditional import object
-        // Priority: "import" > "default" > "require"
-        for key in &["\"import\"", "\"default\"", "\"require\""] {
-            if let Some(pos) = value.find(key) {
-                let after_key = &value[pos + key.len()..];
-                if let Some(colon_pos) = after_key.find(':') {
-                    let target_value = after_key[colon_pos + 1..].trim_start();
-                    if let Some(target) = extract_quoted_string(target_value) {
-                        let resolved_path = package_dir.join(target.trim_start_matches("./"));
-                        if resolved_path.exists() {
-                            return resolved_path.to_str().map(|s| s.to_string());
-                        }
-                    }
-                }
-            }
-        }
-    }
-
-    None
+/// Package imports are defined in the `imports` field of the package.json
+/// of the package containing the referrer.
+fn resolve_package_import(
+    referrer_path: &str,
+    specifier: &str,
+    conditions: &Conditions,
+) -> Option<String> {
+    package_json::package_imports_resolve(specifier, referrer_dir(referrer_path)?, conditions)
 }
 
 /// Extract import specifiers from JavaScript source code.
@@ -641,7 +529,12 @@ fn extract_quoted_string(s: &str) -> Option<String> {
 /// Note: This function creates one thread per module in each batch. For typical projects,
 /// batches are small (usually < 20 modules per dependency level), making this approach
 /// efficient. The threads are short-lived and only perform file I/O operations.
-fn prefetch_modules_parallel(root_path: &str, _referrer_path: &str) {
+fn prefetch_modules_parallel(
+    root_path: &str,
+    conditions: &Conditions,
+    import_map: Option<Arc<ImportMap>>,
+    http_cache: Arc<HttpCache>,
+) {
     let mut to_fetch = vec![root_path.to_string()];
     let mut fetched = HashSet::new();
 
@@ -657,9 +550,17 @@ fn prefetch_modules_parallel(root_path: &str, _referrer_path: &str) {
             .filter(|path| !path.starts_with("node:")) // Skip built-in modules
             .filter(|path| fetched.insert(path.clone())) // Only process new paths
             .map(|path| {
+                let conditions = conditions.clone();
+                let import_map = import_map.clone();
+                let http_cache = http_cache.clone();
                 std::thread::spawn(move || {
-                    // Read the file (will cache it)
-                    if let Ok(source) = read_source_cached(&path) {
+                    // Read the file or download the module (will cache it)
+                    let source = if is_remote_url(&path) {
+                        read_remote_source(&path, &http_cache).ok()
+                    } else {
+                        read_source_cached(&path).ok()
+                    };
+                    if let Some(source) = source {
                         // Extract imports from this module
                         let specifiers = extract_import_specifiers(&source);
 
@@ -671,7 +572,8 @@ fn prefetch_modules_parallel(root_path: &str, _referrer_path: &str) {
                                     None // Skip built-in modules
                                 } else {
                                     // Resolve relative to the current module
-                                    Some(normalize_path(&path, &spec))
+                                    normalize_path(&path, &spec, &conditions, import_map.as_deref())
+                                        .ok()
                                 }
                             })
                             .collect();
@@ -698,6 +600,15 @@ fn prefetch_modules_parallel(root_path: &str, _referrer_path: &str) {
 pub(crate) struct ModuleMap {
     hash_to_absolute_path: FxHashMap<std::num::NonZeroI32, String>,
     absolute_path_to_module: FxHashMap<String, v8::Global<v8::Module>>,
+    // Source maps of the modules that have one, by absolute path
+    source_maps: FxHashMap<String, crate::sourcemap::SourceMap>,
+    // Modification times of the module files when they were loaded
+    modified: FxHashMap<String, Option<SystemTime>>,
+    // The modules that import each module, statically or dynamically
+    importers: FxHashMap<String, FxHashSet<String>>,
+    // Modules compiled without a usable code cache, with their sources.
+    // Their code is cached once they've run.
+    uncached: Vec<(String, String)>,
 }
 
 impl ModuleMap {
@@ -705,9 +616,17 @@ impl ModuleMap {
         Self {
             hash_to_absolute_path: FxHashMap::default(),
             absolute_path_to_module: FxHashMap::default(),
+            source_maps: FxHashMap::default(),
+            modified: FxHashMap::default(),
+            importers: FxHashMap::default(),
+            uncached: Vec::new(),
         }
     }
 
+    pub(crate) fn source_map(&self, filepath: &str) -> Option<&crate::sourcemap::SourceMap> {
+        self.source_maps.get(filepath)
+    }
+
     fn insert(&mut self, isolate: &v8::Isolate, filepath: &str, module: v8::Local<v8::Module>) {
         self.hash_to_absolute_path
             .insert(module.get_identity_hash(), filepath.to_owned());
@@ -715,6 +634,79 @@ impl ModuleMap {
         self.absolute_path_to_module
             .insert(filepath.to_owned(), module);
     }
+
+    /// The modules that import the module at `filepath`
+    pub(crate) fn importers(&self, filepath: &str) -> impl Iterator<Item = &String> {
+        self.importers.get(filepath).into_iter().flatten()
+    }
+
+    /// The module files that changed since they were loaded
+    pub(crate) fn changed(&self) -> Vec<String> {
+        let mut changed: Vec<String> = self
+            .modified
+            .iter()
+            .filter(|(path, modified)| modified_time(path) != **modified)
+            .map(|(path, _)| path.clone())
+            .collect();
+        changed.sort();
+        changed
+    }
+
+    /// `filepath` and every module that imports it, directly or not, in the
+    /// order they were reached
+    pub(crate) fn dependents(&self, filepath: &str) -> Vec<String> {
+        let mut seen = FxHashSet::default();
+        let mut dependents = Vec::new();
+        let mut queue = std::collections::VecDeque::from([filepath.to_string()]);
+        while let Some(path) = queue.pop_front() {
+            if seen.insert(path.clone()) {
+                queue.extend(self.importers(&path).cloned());
+                dependents.push(path);
+            }
+        }
+        dependents
+    }
+
+    /// Forget the module at `filepath`, so the next import loads it again.
+    /// Modules that already imported it keep the instance they have.
+    pub(crate) fn remove(&mut self, filepath: &str) {
+        // hash_to_absolute_path is kept, as the old instance can still
+        // reach import.meta or import() dynamically
+        self.absolute_path_to_module.remove(filepath);
+        self.source_maps.remove(filepath);
+        self.modified.remove(filepath);
+        // It's re-recorded as an importer when it's loaded again
+        for importers in self.importers.values_mut() {
+            importers.remove(filepath);
+        }
+        invalidate_source(filepath);
+    }
+}
+
+/// Store the code caches of the modules compiled without one. It's done
+/// once they've run, so the functions they compiled lazily are included.
+pub(crate) fn store_code_caches(scope: &mut v8::PinScope) {
+    let isolate: &mut v8::Isolate = scope;
+    let state = IsolateState::get(isolate);
+    let uncached = std::mem::take(&mut state.borrow_mut().module_map.uncached);
+    for (path, source) in uncached {
+        let module = state
+            .borrow()
+            .module_map
+            .absolute_path_to_module
+            .get(&path)
+            .map(|module| v8::Local::new(scope, module));
+        // Modules invalidated since, or that failed, aren't cached
+        let Some(module) = module.filter(|module| module.get_status() != v8::ModuleStatus::Errored)
+        else {
+            continue;
+        };
+        if let Some(data) = module.get_unbound_module_script(scope).create_code_cache()
+            && let Some(cache) = state.borrow().code_cache.as_ref()
+        {
+            cache.store(&path, &source, &data);
+        }
+    }
 }
 
 pub(crate) struct Loader {}
@@ -733,8 +725,22 @@ impl Loader {
         // Prefetch modules in parallel before starting V8 compilation
         // This populates the SOURCE_CACHE to avoid sequential I/O during module resolution
         if !specifier.starts_with("node:") {
-            let root_path = normalize_path(referrer, specifier);
-            prefetch_modules_parallel(&root_path, referrer);
+            let isolate: &mut v8::Isolate = scope;
+            let (conditions, import_map, http_cache) = {
+                let state = IsolateState::get(isolate);
+                let state = state.borrow();
+                (
+                    state.conditions.import.clone(),
+                    state.import_map.clone(),
+                    state.http_cache.clone(),
+                )
+            };
+            // Failures are left for resolve() to report
+            if let Ok(root_path) =
+                normalize_path(referrer, specifier, &conditions, import_map.as_deref())
+            {
+                prefetch_modules_parallel(&root_path, &conditions, import_map, http_cache);
+            }
         }
 
         v8::tc_scope!(let tc, scope);
@@ -748,6 +754,9 @@ impl Loader {
                             v8::PromiseState::Pending => panic!(),
                             v8::PromiseState::Fulfilled => Ok(promise.result(tc)),
                             v8::PromiseState::Rejected => {
+                                // The error is returned, rather than reported as an
+                                // unhandled rejection
+                                promise.mark_as_handled();
                                 // Throw the rejected promise value as an exception so it can be
                                 // properly formatted with source location and stack trace
                                 tc.throw_exception(promise.result(tc));
@@ -782,6 +791,51 @@ impl Loader {
     }
 }
 
+/// Load the module at the absolute `path`, like `import()` does, and return
+/// its namespace. A top-level await that hasn't settled is left to the event
+/// loop, as the namespace is usable before it finishes.
+pub(crate) fn load_module<'s>(
+    scope: &mut v8::PinScope<'s, '_>,
+    path: &str,
+) -> Result<v8::Local<'s, v8::Object>, String> {
+    v8::tc_scope!(let tc, scope);
+    let Some(module) = resolve(tc, path, path) else {
+        return Err(load_error(tc, &format!("Cannot find module '{}'", path)));
+    };
+    if module.get_status() == v8::ModuleStatus::Uninstantiated
+        && module
+            .instantiate_module(tc, module_resolve_callback)
+            .is_none()
+    {
+        return Err(load_error(tc, "Module instantiation failed"));
+    }
+    if module.get_status() == v8::ModuleStatus::Instantiated {
+        let Some(result) = module.evaluate(tc) else {
+            return Err(load_error(tc, "Module evaluation failed"));
+        };
+        // The error is returned here, rather than as an unhandled rejection
+        let promise = unsafe { v8::Local::<v8::Promise>::cast_unchecked(result) };
+        if promise.state() == v8::PromiseState::Rejected {
+            promise.mark_as_handled();
+        }
+    }
+    if module.get_status() == v8::ModuleStatus::Errored {
+        let exception = module.get_exception();
+        return Err(crate::error::format_exception_value(tc, exception));
+    }
+    let namespace = module.get_module_namespace();
+    Ok(unsafe { v8::Local::<v8::Object>::cast_unchecked(namespace) })
+}
+
+/// The caught exception, formatted, or `message` if there isn't one
+fn load_error(tc: &mut v8::PinnedRef<'_, v8::TryCatch<v8::HandleScope>>, message: &str) -> String {
+    if tc.has_caught() {
+        crate::error::format_exception(tc)
+    } else {
+        message.to_string()
+    }
+}
+
 #[inline]
 fn resolve<'a>(
     scope: &mut v8::PinScope<'a, '_>,
@@ -791,12 +845,42 @@ fn resolve<'a>(
     let isolate: &mut v8::Isolate = scope;
     let state = IsolateState::get(isolate);
 
+    let conditions = state.borrow().conditions.clone();
+    let import_map = state.borrow().import_map.clone();
+    let requested_abs_path = match normalize_path(
+        referrer,
+        specifier,
+        &conditions.import,
+        import_map.as_deref(),
+    ) {
+        Ok(path) => path,
+        Err(message) => {
+            crate::error::throw_type_error(
+                scope,
+                &format!("{} (imported from {})", message, referrer),
+            );
+            return None;
+        }
+    };
+
     // Handle node: prefix for built-in modules
-    if let Some(module_name) = specifier.strip_prefix("node:") {
+    if let Some(module_name) = requested_abs_path.strip_prefix("node:") {
         return resolve_builtin_module(scope, module_name);
     }
 
-    let requested_abs_path = normalize_path(referrer, specifier);
+    // Record the import, so the referrer is reloaded when this module changes
+    {
+        let mut state = state.borrow_mut();
+        let module_map = &mut state.module_map;
+        if module_map.absolute_path_to_module.contains_key(referrer) {
+            module_map
+                .importers
+                .entry(requested_abs_path.clone())
+                .or_default()
+                .insert(referrer.to_string());
+        }
+    }
+
     if let Some(module) = state
         .borrow()
         .module_map
@@ -806,6 +890,18 @@ fn resolve<'a>(
         return Some(v8::Local::new(scope, module));
     }
 
+    // Files are checked for changes against the time they had when loaded.
+    // It's recorded before the module is compiled, so one that fails to,
+    // like an entry with a syntax error, is reloaded once it's fixed.
+    if Path::new(&requested_abs_path).is_absolute() {
+        let modified = modified_time(&requested_abs_path);
+        state
+            .borrow_mut()
+            .module_map
+            .modified
+            .insert(requested_abs_path.clone(), modified);
+    }
+
     let requested_string = v8::String::new(scope, &requested_abs_path).unwrap();
     let origin = crate::js_loading::create_script_origin(scope, requested_string, true);
 
@@ -813,9 +909,27 @@ fn resolve<'a>(
     let is_json = requested_abs_path.ends_with(".json");
     let is_wasm = requested_abs_path.ends_with(".wasm");
 
-    let js_src = if is_wasm {
+    let js_src = if is_remote_url(&requested_abs_path) {
+        let http_cache = state.borrow().http_cache.clone();
+        match read_remote_source(&requested_abs_path, &http_cache) {
+            Ok(source) if !check_integrity(scope, &requested_abs_path, source.as_bytes()) => {
+                return None;
+            }
+            Ok(source) if is_json => format!("export default {};", source),
+            Ok(source) => transpile_source(scope, &requested_abs_path, source)?,
+            Err(e) => {
+                let msg = v8::String::new(scope, &e).unwrap();
+                let exception = v8::Exception::error(scope, msg);
+                scope.throw_exception(exception);
+                return None;
+            }
+        }
+    } else if is_wasm {
         // For WebAssembly files, read the binary content and create a synthetic module
         match std::fs::read(&requested_abs_path) {
+            Ok(wasm_bytes) if !check_integrity(scope, &requested_abs_path, &wasm_bytes) => {
+                return None;
+            }
             Ok(wasm_bytes) => {
                 // Encode binary as base64 to embed in JavaScript
                 use base64_simd::STANDARD;
@@ -873,6 +987,11 @@ export default __wasm_instance.exports;"#,
     } else if is_json {
         // For JSON files, read the content and wrap it in a module that exports it as default
         match read_source_cached(&requested_abs_path) {
+            Ok(json_content)
+                if !check_integrity(scope, &requested_abs_path, json_content.as_bytes()) =>
+            {
+                return None;
+            }
             Ok(json_content) => {
                 // Create a synthetic module that exports the JSON as the default export
                 format!("export default {};", json_content)
@@ -888,9 +1007,26 @@ export default __wasm_instance.exports;"#,
                 return None;
             }
         }
+    } else if is_commonjs(&requested_abs_path) {
+        // CommonJS is run through require() by a facade module that exports
+        // module.exports as the default, plus any named exports found in the source
+        if let Err(e) = std::fs::metadata(&requested_abs_path) {
+            let msg = v8::String::new(
+                scope,
+                &format!("Cannot read file '{}': {}", requested_abs_path, e),
+            )
+            .unwrap();
+            let exception = v8::Exception::error(scope, msg);
+            scope.throw_exception(exception);
+            return None;
+        }
+        crate::commonjs::facade_source(&requested_abs_path, &conditions.require)
     } else {
         match read_source_cached(&requested_abs_path) {
-            Ok(content) => content,
+            Ok(content) if !check_integrity(scope, &requested_abs_path, content.as_bytes()) => {
+                return None;
+            }
+            Ok(content) => transpile_source(scope, &requested_abs_path, content)?,
             Err(e) => {
                 let msg = v8::String::new(
                     scope,
@@ -904,13 +1040,53 @@ export default __wasm_instance.exports;"#,
         }
     };
 
+    // Load the source map first, so compile errors are reported in the original source
+    let http_cache = state.borrow().http_cache.clone();
+    if let Some(source_map) = crate::sourcemap::load(&requested_abs_path, &js_src, |location| {
+        if is_remote_url(location) {
+            read_remote_source(location, &http_cache).ok()
+        } else {
+            std::fs::read_to_string(location).ok()
+        }
+    }) {
+        state
+            .borrow_mut()
+            .module_map
+            .source_maps
+            .insert(requested_abs_path.clone(), source_map);
+    }
+
+    // Reuse the code compiled by an earlier run, if it's cached
+    let cached_code = state
+        .borrow()
+        .code_cache
+        .as_ref()
+        .and_then(|cache| cache.load(&requested_abs_path, &js_src));
     let code = v8::String::new(scope, &js_src).unwrap();
-    let mut source = v8::script_compiler::Source::new(code, Some(&origin));
+    let (mut source, compile_options) = match cached_code.as_deref() {
+        Some(data) => (
+            v8::script_compiler::Source::new_with_cached_data(
+                code,
+                Some(&origin),
+                v8::CachedData::new(data),
+            ),
+            v8::script_compiler::CompileOptions::ConsumeCodeCache,
+        ),
+        None => (
+            v8::script_compiler::Source::new(code, Some(&origin)),
+            v8::script_compiler::CompileOptions::NoCompileOptions,
+        ),
+    };
 
     // Compile the module - errors will be thrown as exceptions
     let (module, error_msg) = {
         v8::tc_scope!(let tc, scope);
-        let module = v8::script_compiler::compile_module(tc, &mut source);
+        let module = v8::script_compiler::compile_module2(
+            tc,
+            &mut source,
+            compile_options,
+            v8::script_compiler::NoCacheReason::NoReason,
+        );
 
         if let Some(module) = module {
             (Some(module), None)
@@ -925,9 +1101,18 @@ export default __wasm_instance.exports;"#,
 
     if let Some(module) = module {
         let isolate: &mut v8::Isolate = scope;
-        let state = IsolateState::get(isolate);
+        let mut state = state.borrow_mut();
+        // Cache the compiled code if none was, or V8 rejected what was
+        let rejected = source
+            .get_cached_data()
+            .is_none_or(|cached| cached.rejected());
+        if rejected && state.code_cache.is_some() {
+            state
+                .module_map
+                .uncached
+                .push((requested_abs_path.clone(), js_src));
+        }
         state
-            .borrow_mut()
             .module_map
             .insert(isolate, &requested_abs_path, module);
         Some(module)
@@ -998,6 +1183,28 @@ fn resolve_builtin_module<'a>(
             "#,
             module_name, module_name
         ),
+        "net" => format!(
+            r#"
+            const mod = globalThis.__node_modules['node:{}'];
+            if (!mod) {{
+                throw new Error('Built-in module not found: {}');
+            }}
+            export const {{ createServer, connect, createConnection, Socket, Server, isIP, isIPv4, isIPv6 }} = mod;
+            export default mod;
+            "#,
+            module_name, module_name
+        ),
+        "tls" => format!(
+            r#"
+            const mod = globalThis.__node_modules['node:{}'];
+            if (!mod) {{
+                throw new Error('Built-in module not found: {}');
+            }}
+            export const {{ connect, TLSSocket, DEFAULT_MIN_VERSION, DEFAULT_MAX_VERSION }} = mod;
+            export default mod;
+            "#,
+            module_name, module_name
+        ),
         "events" => format!(
             r#"
             const mod = globalThis.__node_modules['node:{}'];
@@ -1009,6 +1216,17 @@ fn resolve_builtin_module<'a>(
             "#,
             module_name, module_name
         ),
+        "module" => format!(
+            r#"
+            const mod = globalThis.__node_modules['node:{}'];
+            if (!mod) {{
+                throw new Error('Built-in module not found: {}');
+            }}
+            export const {{ createRequire, builtinModules, isBuiltin, Module }} = mod;
+            export default mod;
+            "#,
+            module_name, module_name
+        ),
         _ => format!(
             r#"
             const mod = globalThis.__node_modules['node:{}'];
@@ -1038,33 +1256,76 @@ fn resolve_builtin_module<'a>(
     module
 }
 
+/// Resolve `requested`, imported from `referrer_path`, to an absolute path.
+/// The import map is applied first, and fails if it blocks the specifier.
+/// Built-in modules, URLs and specifiers nothing matches are returned as-is.
 #[inline]
-fn normalize_path(referrer_path: &str, requested: &str) -> String {
+fn normalize_path(
+    referrer_path: &str,
+    requested: &str,
+    conditions: &Conditions,
+    import_map: Option<&ImportMap>,
+) -> Result<String, String> {
+    if let Some(import_map) = import_map
+        && let Some(mapped) = import_map.resolve(requested, referrer_path)?
+    {
+        // Mapped paths are canonicalized like relative imports, so a module
+        // reached both ways is only loaded once
+        if mapped.starts_with('/')
+            && let Ok(canonical) = Path::new(&mapped).canonicalize()
+            && let Some(canonical) = canonical.to_str()
+        {
+            return Ok(canonical.to_string());
+        }
+        return Ok(mapped);
+    }
+
+    if is_remote_url(requested) {
+        // Normalize the URL, so a module is only loaded once
+        return Ok(ada_url::Url::parse(requested, None)
+            .map(|url| url.href().to_string())
+            .unwrap_or_else(|_| requested.to_string()));
+    }
+    if requested.starts_with("node:") || requested.contains("://") {
+        return Ok(requested.to_string());
+    }
+    if is_remote_url(referrer_path) {
+        // Relative imports in remote modules resolve against the module's URL.
+        // Bare specifiers can only be resolved by the import map.
+        if (requested.starts_with("./")
+            || requested.starts_with("../")
+            || requested.starts_with('/'))
+            && let Ok(url) = ada_url::Url::parse(requested, Some(referrer_path))
+        {
+            return Ok(url.href().to_string());
+        }
+        return Ok(requested.to_string());
+    }
     let req_path = Path::new(requested);
     if req_path.is_absolute() {
-        return requested.to_string();
+        return Ok(requested.to_string());
     }
 
     // Check if this is a package import (e.g., "#utils", "#internal/helpers")
     // Package imports are resolved using the "imports" field in package.json
     if is_package_import(requested) {
-        if let Some(resolved) = resolve_package_import(referrer_path, requested) {
-            return resolved;
+        if let Some(resolved) = resolve_package_import(referrer_path, requested, conditions) {
+            return Ok(resolved);
         }
         // If we can't resolve the package import, return it as-is
         // This will cause a proper error when trying to load the file
-        return requested.to_string();
+        return Ok(requested.to_string());
     }
 
     // Check if this is a bare specifier (e.g., "lodash", "@scope/package")
     // Bare specifiers need to be resolved from node_modules
     if is_bare_specifier(requested) {
-        if let Some(resolved) = resolve_bare_specifier(referrer_path, requested) {
-            return resolved;
+        if let Some(resolved) = resolve_bare_specifier(referrer_path, requested, conditions) {
+            return Ok(resolved);
         }
         // If we can't resolve the bare specifier, return it as-is
         // This will cause a proper error when trying to load the file
-        return requested.to_string();
+        return Ok(requested.to_string());
     }
 
     // Get the parent directory of the referrer, or use current directory if no parent
@@ -1073,8 +1334,13 @@ fn normalize_path(referrer_path: &str, requested: &str) -> String {
         .unwrap_or_else(|| Path::new("."));
 
     // Join and canonicalize the path
-    let joined = ref_dir.join(req_path);
-    match joined.canonicalize() {
+    let mut joined = ref_dir.join(req_path);
+    if crate::transpile::is_transpiled(referrer_path)
+        && let Some(resolved) = resolve_typescript_import(&joined)
+    {
+        joined = resolved;
+    }
+    Ok(match joined.canonicalize() {
         Ok(normalized) => {
             // Convert the normalized path to a string
             // If conversion fails (non-UTF-8 path), fall back to the joined path string
@@ -1088,7 +1354,7 @@ fn normalize_path(referrer_path: &str, requested: &str) -> String {
             // This allows the caller to provide a better error message
             joined.display().to_string()
         }
-    }
+    })
 }
 
 fn module_resolve_callback<'a>(
@@ -1236,16 +1502,91 @@ pub(crate) unsafe extern "C" fn host_initialize_import_meta_object_callback(
         .cloned();
 
     if let Some(module_path) = module_path {
-        // Convert file path to file:// URL
-        let url = format!("file://{}", module_path);
+        // Convert file path to file:// URL; remote modules keep their URL
+        let url = if is_remote_url(&module_path) {
+            module_path.clone()
+        } else {
+            format!("file://{}", module_path)
+        };
 
-        // Use cached "url" string
+        // Use cached property name strings
         let cache = state.borrow().string_cache.clone();
         let mut cache_borrow = cache.borrow_mut();
         let url_key = crate::get_or_create_cached_string!(scope, cache_borrow, url, "url");
+        let resolve_key =
+            crate::get_or_create_cached_string!(scope, cache_borrow, resolve, "resolve");
+        let filename_key =
+            crate::get_or_create_cached_string!(scope, cache_borrow, filename, "filename");
+        let dirname_key =
+            crate::get_or_create_cached_string!(scope, cache_borrow, dirname, "dirname");
         drop(cache_borrow);
 
         let url_value = v8::String::new(scope, &url).unwrap();
         meta.set(scope, url_key.into(), url_value.into());
+
+        // import.meta.resolve() resolves relative to this module, so it carries the path as data
+        let path_value = v8::String::new(scope, &module_path).unwrap();
+        if let Some(resolve_fn) = v8::Function::builder(import_meta_resolve)
+            .data(path_value.into())
+            .build(scope)
+        {
+            meta.set(scope, resolve_key.into(), resolve_fn.into());
+        }
+
+        // filename and dirname are only meaningful for modules loaded from disk
+        let path = Path::new(&module_path);
+        if path.is_absolute() {
+            meta.set(scope, filename_key.into(), path_value.into());
+            let dirname = path.parent().unwrap_or(path).to_string_lossy();
+            let dirname_value = v8::String::new(scope, &dirname).unwrap();
+            meta.set(scope, dirname_key.into(), dirname_value.into());
+        }
+
+        crate::hot_reload::init_import_meta(scope, meta, &module_path);
+    }
+}
+
+/// import.meta.resolve(specifier): the URL an import of `specifier` from the
+/// module would load, without loading it
+fn import_meta_resolve(
+    scope: &mut v8::PinScope,
+    args: v8::FunctionCallbackArguments,
+    mut retval: v8::ReturnValue,
+) {
+    if !crate::error::check_arg_count(scope, &args, 1, "import.meta.resolve") {
+        return;
     }
+    let Some(specifier) = crate::error::to_rust_string_or_throw(scope, args.get(0), "specifier")
+    else {
+        return;
+    };
+    let referrer = args.data().to_rust_string_lossy(scope);
+
+    let isolate: &mut v8::Isolate = scope;
+    let (conditions, import_map) = {
+        let state = IsolateState::get(isolate);
+        let state = state.borrow();
+        (state.conditions.import.clone(), state.import_map.clone())
+    };
+    let resolved = match normalize_path(&referrer, &specifier, &conditions, import_map.as_deref()) {
+        Ok(resolved) if resolved.starts_with("node:") || resolved.contains("://") => resolved,
+        Ok(path) if Path::new(&path).is_absolute() => format!("file://{}", path),
+        Ok(_) => {
+            // normalize_path returns bare and `#` specifiers unchanged when nothing matches
+            crate::error::throw_error(
+                scope,
+                &format!(
+                    "Cannot find module '{}' imported from {}",
+                    specifier, referrer
+                ),
+            );
+            return;
+        }
+        Err(message) => {
+            crate::error::throw_type_error(scope, &message);
+            return;
+        }
+    };
+    let resolved = v8::String::new(scope, &resolved).unwrap();
+    retval.set(resolved.into());
 }
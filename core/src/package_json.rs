@@ -0,0 +1,529 @@
+//! package.json parsing and the package `exports` and `imports` resolution
+//! algorithms (PACKAGE_EXPORTS_RESOLVE and PACKAGE_IMPORTS_RESOLVE) from
+//! https://nodejs.org/api/esm.html#resolution-algorithm-specification
+//!
+//! Parsed package.json files are cached per directory, like the module
+//! source cache in `module.rs`.
+
+use crate::http_cache::has_url_scheme;
+use crate::json::Json;
+use rustc_hash::FxHashMap;
+use std::cmp::Ordering;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, OnceLock, RwLock};
+
+/// Condition names that match when none are configured
+pub(crate) const DEFAULT_CONDITIONS: &[&str] = &["production"];
+
+/// The package.json fields used by module resolution
+#[derive(Debug, Default)]
+pub(crate) struct PackageJson {
+    pub(crate) name: Option<String>,
+    pub(crate) main: Option<String>,
+    pub(crate) type_: Option<String>,
+    /// `None` when the field is missing or null
+    pub(crate) exports: Option<Json>,
+    pub(crate) imports: Option<Json>,
+}
+
+impl PackageJson {
+    /// Extract the resolution fields. A file that isn't valid JSON still
+    /// marks a package boundary, it just has no fields.
+    fn parse(source: &str) -> PackageJson {
+        let Ok(Json::Object(entries)) = Json::parse(source) else {
+            return PackageJson::default();
+        };
+        let mut package = PackageJson::default();
+        for (key, value) in entries {
+            match (key.as_str(), value) {
+                ("name", Json::String(s)) => package.name = Some(s),
+                ("main", Json::String(s)) => package.main = Some(s),
+                ("type", Json::String(s)) => package.type_ = Some(s),
+                ("exports", Json::Null) | ("imports", Json::Null) => {}
+                ("exports", value) => package.exports = Some(value),
+                ("imports", value) => package.imports = Some(value),
+                _ => {}
+            }
+        }
+        package
+    }
+}
+
+// Maps a directory to its parsed package.json, or None if it has none.
+// Like the source cache, this isn't invalidated when files change.
+static PACKAGE_JSON_CACHE: OnceLock<RwLock<FxHashMap<PathBuf, Option<Arc<PackageJson>>>>> =
+    OnceLock::new();
+
+fn get_package_json_cache() -> &'static RwLock<FxHashMap<PathBuf, Option<Arc<PackageJson>>>> {
+    PACKAGE_JSON_CACHE.get_or_init(|| RwLock::new(FxHashMap::default()))
+}
+
+/// Clear the package.json cache
+pub(crate) fn clear_cache() {
+    get_package_json_cache().write().unwrap().clear();
+}
+
+/// Read the package.json in `package_dir`, using the cache if available
+pub(crate) fn read(package_dir: &Path) -> Option<Arc<PackageJson>> {
+    {
+        let cache = get_package_json_cache().read().unwrap();
+        if let Some(package) = cache.get(package_dir) {
+            return package.clone();
+        }
+    }
+
+    let package = std::fs::read_to_string(package_dir.join("package.json"))
+        .ok()
+        .map(|source| Arc::new(PackageJson::parse(&source)));
+
+    let mut cache = get_package_json_cache().write().unwrap();
+    cache.insert(package_dir.to_path_buf(), package.clone());
+    package
+}
+
+/// Find the package a directory belongs to (LOOKUP_PACKAGE_SCOPE): the
+/// nearest package.json at or above `dir`, without leaving node_modules
+pub(crate) fn lookup_scope(dir: &Path) -> Option<(PathBuf, Arc<PackageJson>)> {
+    let mut current_dir = dir;
+    loop {
+        if current_dir
+            .file_name()
+            .is_some_and(|name| name == "node_modules")
+        {
+            return None;
+        }
+        if let Some(package) = read(current_dir) {
+            return Some((current_dir.to_path_buf(), package));
+        }
+        current_dir = current_dir.parent()?;
+    }
+}
+
+/// Condition names matched against conditional `exports` and `imports`
+/// entries. "default" always matches.
+#[derive(Debug, Clone)]
+pub(crate) struct Conditions(Arc<[String]>);
+
+impl Conditions {
+    fn matches(&self, key: &str) -> bool {
+        key == "default" || self.0.iter().any(|condition| condition == key)
+    }
+}
+
+/// The conditions for `import` and for `require()`. Both include "jstime"
+/// and the configured conditions, which default to DEFAULT_CONDITIONS.
+#[derive(Debug, Clone)]
+pub(crate) struct ResolutionConditions {
+    pub(crate) import: Conditions,
+    pub(crate) require: Conditions,
+}
+
+impl ResolutionConditions {
+    pub(crate) fn new(configured: Option<&[String]>) -> Self {
+        let configured: Vec<String> = match configured {
+            Some(conditions) => conditions.to_vec(),
+            None => DEFAULT_CONDITIONS.iter().map(|c| c.to_string()).collect(),
+        };
+        let with = |kind: &str| {
+            let mut names = vec!["jstime".to_string(), kind.to_string()];
+            names.extend(configured.iter().cloned());
+            Conditions(names.into())
+        };
+        Self {
+            import: with("import"),
+            require: with("require"),
+        }
+    }
+}
+
+/// Why a package target couldn't be resolved. Any of these makes the
+/// specifier unresolvable, but array fallbacks skip past InvalidTarget.
+#[derive(Debug, PartialEq)]
+enum ResolveError {
+    InvalidConfiguration,
+    InvalidTarget,
+    InvalidSpecifier,
+    NotFound,
+}
+
+/// The result of resolving one target. Null means the path is explicitly
+/// not exported, Undefined that no condition matched.
+#[derive(Debug, PartialEq)]
+enum Target {
+    Resolved(String),
+    Null,
+    Undefined,
+}
+
+/// PACKAGE_EXPORTS_RESOLVE: resolve `subpath` ("." or "./name") through a
+/// package's `exports`. Returns an absolute path.
+pub(crate) fn package_exports_resolve(
+    package_dir: &Path,
+    subpath: &str,
+    exports: &Json,
+    conditions: &Conditions,
+) -> Option<String> {
+    exports_resolve(package_dir, subpath, exports, conditions).ok()
+}
+
+fn exports_resolve(
+    package_dir: &Path,
+    subpath: &str,
+    exports: &Json,
+    conditions: &Conditions,
+) -> Result<String, ResolveError> {
+    // Keys must be either all subpaths or all conditions
+    let has_subpath_keys = match exports {
+        Json::Object(entries) => {
+            let subpath_keys = entries.iter().filter(|(k, _)| k.starts_with('.')).count();
+            if subpath_keys != 0 && subpath_keys != entries.len() {
+                return Err(ResolveError::InvalidConfiguration);
+            }
+            subpath_keys != 0
+        }
+        _ => false,
+    };
+
+    if subpath == "." {
+        let main_export = if has_subpath_keys {
+            exports.get(".")
+        } else {
+            Some(exports)
+        };
+        if let Some(main_export) = main_export
+            && let Target::Resolved(resolved) =
+                target_resolve(package_dir, main_export, None, false, conditions)?
+        {
+            return Ok(resolved);
+        }
+    } else if let Json::Object(entries) = exports
+        && has_subpath_keys
+        && let Target::Resolved(resolved) =
+            imports_exports_resolve(subpath, entries, package_dir, false, conditions)?
+    {
+        return Ok(resolved);
+    }
+
+    Err(ResolveError::NotFound)
+}
+
+/// PACKAGE_IMPORTS_RESOLVE: resolve a `#` specifier through the `imports`
+/// of the package containing `referrer_dir`. Returns an absolute path, or
+/// a `node:` specifier.
+pub(crate) fn package_imports_resolve(
+    specifier: &str,
+    referrer_dir: &Path,
+    conditions: &Conditions,
+) -> Option<String> {
+    if specifier == "#" || specifier.starts_with("#/") {
+        return None;
+    }
+    let (package_dir, package) = lookup_scope(referrer_dir)?;
+    let Some(Json::Object(imports)) = &package.imports else {
+        return None;
+    };
+    match imports_exports_resolve(specifier, imports, &package_dir, true, conditions) {
+        Ok(Target::Resolved(resolved)) => Some(resolved),
+        _ => None,
+    }
+}
+
+/// PACKAGE_IMPORTS_EXPORTS_RESOLVE: match a key exactly, or against the
+/// `*` patterns from most to least specific
+fn imports_exports_resolve(
+    match_key: &str,
+    entries: &[(String, Json)],
+    package_dir: &Path,
+    is_imports: bool,
+    conditions: &Conditions,
+) -> Result<Target, ResolveError> {
+    if !match_key.contains('*')
+        && let Some((_, target)) = entries.iter().find(|(key, _)| key == match_key)
+    {
+        return target_resolve(package_dir, target, None, is_imports, conditions);
+    }
+
+    let mut expansion_keys: Vec<&(String, Json)> = entries
+        .iter()
+        .filter(|(key, _)| key.matches('*').count() == 1)
+        .collect();
+    expansion_keys.sort_by(|a, b| pattern_key_compare(&a.0, &b.0));
+
+    for (key, target) in expansion_keys {
+        let star = key.find('*').unwrap();
+        let pattern_base = &key[..star];
+        if match_key.starts_with(pattern_base) && match_key != pattern_base {
+            let pattern_trailer = &key[star + 1..];
+            if pattern_trailer.is_empty()
+                || (match_key.ends_with(pattern_trailer) && match_key.len() >= key.len())
+            {
+                let pattern_match =
+                    &match_key[pattern_base.len()..match_key.len() - pattern_trailer.len()];
+                return target_resolve(
+                    package_dir,
+                    target,
+                    Some(pattern_match),
+                    is_imports,
+                    conditions,
+                );
+            }
+        }
+    }
+
+    Ok(Target::Null)
+}
+
+/// PATTERN_KEY_COMPARE: longer prefixes before the `*` come first, then longer keys
+fn pattern_key_compare(a: &str, b: &str) -> Ordering {
+    let base_a = a.find('*').map_or(0, |i| i + 1);
+    let base_b = b.find('*').map_or(0, |i| i + 1);
+    if base_a != base_b {
+        return base_b.cmp(&base_a);
+    }
+    if !a.contains('*') {
+        return Ordering::Greater;
+    }
+    if !b.contains('*') {
+        return Ordering::Less;
+    }
+    b.len().cmp(&a.len())
+}
+
+/// PACKAGE_TARGET_RESOLVE: resolve a target string, conditions object or
+/// array of fallbacks
+fn target_resolve(
+    package_dir: &Path,
+    target: &Json,
+    pattern_match: Option<&str>,
+    is_imports: bool,
+    conditions: &Conditions,
+) -> Result<Target, ResolveError> {
+    match target {
+        Json::String(target) => {
+            let Some(relative) = target.strip_prefix("./") else {
+                // Only `imports` may map to other packages
+                if !is_imports
+                    || target.starts_with("../")
+                    || target.starts_with('/')
+                    || has_url_scheme(target)
+                {
+                    return Err(ResolveError::InvalidTarget);
+                }
+                let specifier = match pattern_match {
+                    Some(pattern_match) => target.replace('*', pattern_match),
+                    None => target.clone(),
+                };
+                let referrer = package_dir.join("package.json");
+                return crate::module::resolve_bare_specifier(
+                    referrer.to_str().unwrap_or_default(),
+                    &specifier,
+                    conditions,
+                )
+                .map(Target::Resolved)
+                .ok_or(ResolveError::NotFound);
+            };
+
+            // Targets can't leave the package or reach into node_modules
+            if has_invalid_segment(relative) {
+                return Err(ResolveError::InvalidTarget);
+            }
+            let relative = match pattern_match {
+                Some(pattern_match) => {
+                    if has_invalid_segment(pattern_match) {
+                        return Err(ResolveError::InvalidSpecifier);
+                    }
+                    relative.replace('*', pattern_match)
+                }
+                None => relative.to_string(),
+            };
+            let resolved = package_dir.join(relative);
+            resolved
+                .to_str()
+                .map(|s| Target::Resolved(s.to_string()))
+                .ok_or(ResolveError::InvalidTarget)
+        }
+        Json::Object(entries) => {
+            if entries.iter().any(|(key, _)| is_array_index(key)) {
+                return Err(ResolveError::InvalidConfiguration);
+            }
+            for (key, value) in entries {
+                if conditions.matches(key) {
+                    match target_resolve(package_dir, value, pattern_match, is_imports, conditions)?
+                    {
+                        Target::Undefined => continue,
+                        resolved => return Ok(resolved),
+                    }
+                }
+            }
+            Ok(Target::Undefined)
+        }
+        Json::Array(items) => {
+            if items.is_empty() {
+                return Ok(Target::Null);
+            }
+            let mut last = Ok(Target::Undefined);
+            for item in items {
+                match target_resolve(package_dir, item, pattern_match, is_imports, conditions) {
+                    Ok(Target::Resolved(resolved)) => return Ok(Target::Resolved(resolved)),
+                    Ok(Target::Undefined) => {}
+                    Ok(Target::Null) => last = Ok(Target::Null),
+                    Err(ResolveError::InvalidTarget) => last = Err(ResolveError::InvalidTarget),
+                    Err(error) => return Err(error),
+                }
+            }
+            last
+        }
+        Json::Null => Ok(Target::Null),
+        _ => Err(ResolveError::InvalidTarget),
+    }
+}
+
+/// Whether a path has an empty, ".", ".." or "node_modules" segment,
+/// including percent-encoded and differently cased forms
+fn has_invalid_segment(path: &str) -> bool {
+    path.split(['/', '\\']).any(|segment| {
+        let segment = percent_decode(segment).to_ascii_lowercase();
+        segment.is_empty() || segment == "." || segment == ".." || segment == "node_modules"
+    })
+}
+
+fn percent_decode(value: &str) -> String {
+    let bytes = value.as_bytes();
+    let mut decoded = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        if bytes[i] == b'%'
+            && let Some(hex) = value.get(i + 1..i + 3)
+            && let Ok(byte) = u8::from_str_radix(hex, 16)
+        {
+            decoded.push(byte);
+            i += 3;
+        } else {
+            decoded.push(bytes[i]);
+            i += 1;
+        }
+    }
+    String::from_utf8_lossy(&decoded).into_owned()
+}
+
+fn is_array_index(key: &str) -> bool {
+    !key.is_empty()
+        && key.bytes().all(|b| b.is_ascii_digit())
+        && (key == "0" || !key.starts_with('0'))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn conditions(names: &[&str]) -> Conditions {
+        Conditions(names.iter().map(|s| s.to_string()).collect())
+    }
+
+    fn resolve(exports: &str, subpath: &str, names: &[&str]) -> Option<String> {
+        let exports = Json::parse(exports).unwrap();
+        package_exports_resolve(Path::new("/pkg"), subpath, &exports, &conditions(names))
+    }
+
+    #[test]
+    fn test_exports_main_forms() {
+        assert_eq!(resolve(r#""./main.js""#, ".", &[]).unwrap(), "/pkg/main.js");
+        assert_eq!(
+            resolve(r#"{".": "./main.js"}"#, ".", &[]).unwrap(),
+            "/pkg/main.js"
+        );
+        assert_eq!(
+            resolve(
+                r#"{"import": "./esm.js", "default": "./cjs.js"}"#,
+                ".",
+                &["import"]
+            )
+            .unwrap(),
+            "/pkg/esm.js"
+        );
+        assert_eq!(
+            resolve(
+                r#"{"import": "./esm.js", "default": "./cjs.js"}"#,
+                ".",
+                &["require"]
+            )
+            .unwrap(),
+            "/pkg/cjs.js"
+        );
+        assert_eq!(resolve(r#"{"./sub": "./sub.js"}"#, ".", &[]), None);
+        // Mixing subpath and condition keys is invalid
+        assert_eq!(
+            resolve(r#"{".": "./a.js", "import": "./b.js"}"#, ".", &["import"]),
+            None
+        );
+    }
+
+    #[test]
+    fn test_exports_nested_conditions_follow_key_order() {
+        let exports = r#"{
+            ".": {
+                "node": "./node.js",
+                "jstime": { "production": "./prod.js", "default": "./dev.js" },
+                "default": "./fallback.js"
+            }
+        }"#;
+        assert_eq!(
+            resolve(exports, ".", &["jstime", "production"]).unwrap(),
+            "/pkg/prod.js"
+        );
+        assert_eq!(resolve(exports, ".", &["jstime"]).unwrap(), "/pkg/dev.js");
+        assert_eq!(resolve(exports, ".", &[]).unwrap(), "/pkg/fallback.js");
+    }
+
+    #[test]
+    fn test_exports_patterns() {
+        let exports = r#"{
+            "./features/*.js": "./src/features/*.js",
+            "./features/internal/*": null,
+            "./*": "./dist/*.mjs"
+        }"#;
+        assert_eq!(
+            resolve(exports, "./features/a/b.js", &[]).unwrap(),
+            "/pkg/src/features/a/b.js"
+        );
+        assert_eq!(resolve(exports, "./features/internal/x", &[]), None);
+        assert_eq!(
+            resolve(exports, "./utils", &[]).unwrap(),
+            "/pkg/dist/utils.mjs"
+        );
+        assert_eq!(resolve(exports, "./../secret", &[]), None);
+        assert_eq!(resolve(exports, "./node_modules/x", &[]), None);
+    }
+
+    #[test]
+    fn test_exports_array_fallbacks() {
+        assert_eq!(
+            resolve(
+                r#"[{"worker": "./w.js"}, "not-relative", "./b.js"]"#,
+                ".",
+                &[]
+            )
+            .unwrap(),
+            "/pkg/b.js"
+        );
+        assert_eq!(resolve(r#"["bad-target"]"#, ".", &[]), None);
+        assert_eq!(resolve("[]", ".", &[]), None);
+    }
+
+    #[test]
+    fn test_invalid_targets() {
+        assert_eq!(resolve(r#""../outside.js""#, ".", &[]), None);
+        assert_eq!(resolve(r#""./a/../../b.js""#, ".", &[]), None);
+        assert_eq!(resolve(r#""./%2e%2e/b.js""#, ".", &[]), None);
+        assert_eq!(resolve(r#""./node_modules/dep/index.js""#, ".", &[]), None);
+        assert_eq!(resolve(r#""https://example.com/x.js""#, ".", &[]), None);
+        assert_eq!(resolve(r#"{"0": "./a.js"}"#, ".", &[]), None);
+    }
+
+    #[test]
+    fn test_pattern_key_order() {
+        let mut keys = vec!["./*", "./a/*.js", "./a/*", "./a/b/*"];
+        keys.sort_by(|a, b| pattern_key_compare(a, b));
+        assert_eq!(keys, vec!["./a/b/*", "./a/*.js", "./a/*", "./*"]);
+    }
+}
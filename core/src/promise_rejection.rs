@@ -0,0 +1,174 @@
+//! Tracking of promise rejections that nothing handles.
+//!
+//! V8 reports each promise rejected without a handler, and each handler
+//! added to a promise after it was rejected. Rejections still unhandled at
+//! the next microtask checkpoint are dispatched as `unhandledrejection`
+//! events on globalThis and `unhandledRejection` on process. If no listener
+//! handles one, it's an error, like an uncaught exception. A promise that
+//! gets a handler after it was dispatched is reported with
+//! `rejectionhandled` and `rejectionHandled`.
+
+use crate::{IsolateState, JsError};
+
+#[derive(Default)]
+pub(crate) struct PromiseRejections {
+    // Promises rejected without a handler since the last dispatch, with
+    // their reasons
+    unhandled: Vec<(v8::Global<v8::Promise>, v8::Global<v8::Value>)>,
+    // Promises that got a handler after they were dispatched as unhandled
+    handled: Vec<(v8::Global<v8::Promise>, v8::Global<v8::Value>)>,
+    // The promises dispatched as unhandled, held weakly so they're
+    // forgotten once they're collected
+    dispatched: Vec<v8::Weak<v8::Promise>>,
+}
+
+/// Called by V8 when a promise is rejected without a handler, or a handler
+/// is added to a rejected one
+pub(crate) extern "C" fn promise_reject_callback(message: v8::PromiseRejectMessage) {
+    v8::callback_scope!(unsafe let scope, &message);
+
+    let promise = message.get_promise();
+    let isolate: &mut v8::Isolate = scope;
+    let state = IsolateState::get(isolate);
+
+    match message.get_event() {
+        v8::PromiseRejectEvent::PromiseRejectWithNoHandler => {
+            let reason = message
+                .get_value()
+                .unwrap_or_else(|| v8::undefined(scope).into());
+            let promise = v8::Global::new(scope, promise);
+            let reason = v8::Global::new(scope, reason);
+            state
+                .borrow_mut()
+                .promise_rejections
+                .unhandled
+                .push((promise, reason));
+        }
+        v8::PromiseRejectEvent::PromiseHandlerAddedAfterReject => {
+            let mut state = state.borrow_mut();
+            let rejections = &mut state.promise_rejections;
+            // Handled before the checkpoint, so it's never reported
+            if let Some(index) = rejections
+                .unhandled
+                .iter()
+                .position(|(unhandled, _)| *unhandled == promise)
+            {
+                rejections.unhandled.remove(index);
+            } else if let Some(index) = rejections
+                .dispatched
+                .iter()
+                .position(|dispatched| dispatched.to_local(scope) == Some(promise))
+            {
+                rejections.dispatched.swap_remove(index);
+                let reason = promise.result(scope);
+                let promise = v8::Global::new(scope, promise);
+                let reason = v8::Global::new(scope, reason);
+                rejections.handled.push((promise, reason));
+            }
+        }
+        // Resolving or rejecting a settled promise isn't an error
+        _ => {}
+    }
+}
+
+/// Drop the rejections not dispatched yet, when the event loop stops with
+/// another error
+pub(crate) fn discard(isolate: &mut v8::Isolate) {
+    let state = IsolateState::get(isolate);
+    state.borrow_mut().promise_rejections.unhandled.clear();
+}
+
+/// Dispatch the rejections still unhandled since the last checkpoint, and
+/// the ones handled since they were dispatched. Returns the first rejection
+/// that no listener handled, formatted like an uncaught exception.
+pub(crate) fn dispatch(scope: &mut v8::PinScope) -> Result<(), JsError> {
+    let isolate: &mut v8::Isolate = scope;
+    let state = IsolateState::get(isolate);
+    let mut error = None;
+
+    // Listeners can reject more promises, which are dispatched in turn
+    loop {
+        let (unhandled, handled) = {
+            let mut state = state.borrow_mut();
+            let rejections = &mut state.promise_rejections;
+            (
+                std::mem::take(&mut rejections.unhandled),
+                std::mem::take(&mut rejections.handled),
+            )
+        };
+        if unhandled.is_empty() && handled.is_empty() {
+            break;
+        }
+
+        for (promise, reason) in handled {
+            let promise = v8::Local::new(scope, promise);
+            let reason = v8::Local::new(scope, reason);
+            if let Err(e) = call_dispatcher(scope, "handled", promise, reason) {
+                error.get_or_insert(e);
+            }
+        }
+
+        for (promise, reason) in unhandled {
+            let promise = v8::Local::new(scope, promise);
+            // Marked handled by the runtime, which reports the error itself
+            if promise.has_handler() {
+                continue;
+            }
+            let weak = v8::Weak::new(scope, promise);
+            {
+                let mut state = state.borrow_mut();
+                let dispatched = &mut state.promise_rejections.dispatched;
+                // Forget the ones that were collected
+                dispatched.retain(|dispatched| !dispatched.is_empty());
+                dispatched.push(weak);
+            }
+            let reason = v8::Local::new(scope, reason);
+            match call_dispatcher(scope, "unhandled", promise, reason) {
+                Ok(true) => {}
+                Ok(false) => {
+                    let rejection = JsError::from_value(scope, reason);
+                    error.get_or_insert(rejection.prefixed("Uncaught (in promise) "));
+                }
+                Err(e) => {
+                    error.get_or_insert(e);
+                }
+            }
+        }
+
+        scope.perform_microtask_checkpoint();
+    }
+
+    match error {
+        Some(error) => Err(error),
+        None => Ok(()),
+    }
+}
+
+/// Call globalThis.__dispatchRejection__, set up by the process builtin.
+/// Returns whether a listener handled the rejection, or the exception a
+/// listener threw.
+fn call_dispatcher(
+    scope: &mut v8::PinScope,
+    kind: &str,
+    promise: v8::Local<v8::Promise>,
+    reason: v8::Local<v8::Value>,
+) -> Result<bool, JsError> {
+    v8::tc_scope!(let tc, scope);
+    let context = tc.get_current_context();
+    let global = context.global(tc);
+    let key = v8::String::new(tc, "__dispatchRejection__").unwrap();
+    let Some(dispatcher) = global
+        .get(tc, key.into())
+        .and_then(|value| v8::Local::<v8::Function>::try_from(value).ok())
+    else {
+        return Ok(false);
+    };
+
+    let kind = v8::String::new(tc, kind).unwrap();
+    let recv = v8::undefined(tc).into();
+    match dispatcher.call(tc, recv, &[kind.into(), promise.into(), reason]) {
+        Some(handled) => Ok(handled.boolean_value(tc)),
+        None if tc.has_caught() => Err(JsError::from_try_catch(tc)),
+        None => Ok(false),
+    }
+}
@@ -1,26 +1,78 @@
+use crate::IsolateState;
+use crate::JsError;
 use crate::js_loading;
 
 pub(crate) fn run<'s>(
     scope: &mut v8::PinScope<'s, '_>,
     js: &str,
     filepath: &str,
-) -> Result<v8::Local<'s, v8::Value>, String> {
+) -> Result<v8::Local<'s, v8::Value>, JsError> {
     v8::tc_scope!(let tc, scope);
 
-    let filepath = v8::String::new(tc, filepath).unwrap();
-    let origin = js_loading::create_script_origin(tc, filepath, false);
+    // Reuse the code compiled by an earlier run of the same script file.
+    // Code that isn't from a file, like REPL input, isn't cached.
+    let isolate: &mut v8::Isolate = tc;
+    let state = IsolateState::get(isolate);
+    let cacheable = state.borrow().code_cache.is_some() && std::path::Path::new(filepath).is_file();
+    let cached_code = if cacheable {
+        state
+            .borrow()
+            .code_cache
+            .as_ref()
+            .and_then(|cache| cache.load(filepath, js))
+    } else {
+        None
+    };
+
+    let filepath_str = v8::String::new(tc, filepath).unwrap();
+    let origin = js_loading::create_script_origin(tc, filepath_str, false);
 
     let code = v8::String::new(tc, js).unwrap();
+    let (mut source, compile_options) = match cached_code.as_deref() {
+        Some(data) => (
+            v8::script_compiler::Source::new_with_cached_data(
+                code,
+                Some(&origin),
+                v8::CachedData::new(data),
+            ),
+            v8::script_compiler::CompileOptions::ConsumeCodeCache,
+        ),
+        None => (
+            v8::script_compiler::Source::new(code, Some(&origin)),
+            v8::script_compiler::CompileOptions::NoCompileOptions,
+        ),
+    };
+
+    let script = v8::script_compiler::compile(
+        tc,
+        &mut source,
+        compile_options,
+        v8::script_compiler::NoCacheReason::NoReason,
+    );
 
-    let script = v8::Script::compile(tc, code, Some(&origin));
     let result = script.and_then(|script| script.run(tc));
 
+    // Cache the compiled code if none was, or V8 rejected what was. It's
+    // created once the script has run, so the functions it compiled lazily
+    // are included.
+    if let Some(script) = script
+        && result.is_some()
+        && cacheable
+        && source
+            .get_cached_data()
+            .is_none_or(|cached| cached.rejected())
+        && let Some(cache) = state.borrow().code_cache.as_ref()
+        && let Some(data) = script.get_unbound_script(tc).create_code_cache()
+    {
+        cache.store(filepath, js, &data);
+    }
+
     match result {
         Some(value) => Ok(value),
         None => {
-            if tc.has_caught() {
-                // Format the error with detailed information
-                Err(crate::error::format_exception(tc))
+            if tc.has_caught() || tc.has_terminated() {
+                // Describe the error with detailed information
+                Err(JsError::from_try_catch(tc))
             } else {
                 panic!("Script execution failed without exception")
             }
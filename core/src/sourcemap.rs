@@ -1,21 +1,14 @@
 //! Source map support for better error reporting.
 //!
-//! This module provides basic infrastructure for source map support.
-//! Source maps allow mapping errors in transpiled/bundled code back to
-//! the original source for better debugging.
-//!
-//! ## Future Implementation
-//!
-//! To fully implement source maps, we would need to:
-//! 1. Add a dependency on a source map library (e.g., `sourcemap` crate)
-//! 2. Parse source map files (inline or external)
-//! 3. Map V8 error locations to original source locations
-//! 4. Cache parsed source maps for performance
-//! 5. Handle different source map versions and formats
+//! When a module is compiled, its `//# sourceMappingURL=` comment is
+//! followed to an inline (`data:` URL) or external source map, which is
+//! parsed and kept in the `ModuleMap`. Error messages and stack traces then
+//! report the original file, line and column instead of the position in
+//! the generated code.
 //!
 //! ## Source Map Format
 //!
-//! Source maps typically have this structure:
+//! Source maps (version 3) have this structure:
 //! ```json
 //! {
 //!   "version": 3,
@@ -28,33 +21,49 @@
 //! }
 //! ```
 //!
-//! ## Integration Points
-//!
-//! Source maps would integrate with error formatting in these ways:
-//! - When loading a module, check for source maps (inline or external)
-//! - Store source map data in the module map
-//! - In `format_exception`, look up source maps for the error location
-//! - Transform the file path and line/column numbers to original source
-//! - Display the original source code instead of transpiled code
-
-/// Placeholder for source map data.
-///
-/// In a full implementation, this would contain:
-/// - Parsed source map mappings
-/// - Original source content
-/// - Source file names
-#[allow(dead_code)]
+//! `mappings` has a group of segments for each line of generated code,
+//! separated by `;`. Segments are separated by `,` and are base64 VLQ
+//! encoded fields: the generated column, then optionally the source index,
+//! original line, original column and name index. Every field is relative
+//! to the same field of the previous segment; the generated column resets
+//! at the start of each line.
+
+use crate::IsolateState;
+use crate::http_cache::has_url_scheme;
+use crate::http_cache::is_remote_url;
+use crate::json::Json;
+use std::path::{Component, Path, PathBuf};
+
+/// A mapping from a generated column to its original position
+#[derive(Debug, Clone, Copy)]
+struct Mapping {
+    generated_column: u32,
+    // Source index, original line and original column, all 0-based
+    original: Option<(u32, u32, u32)>,
+}
+
+/// A parsed source map.
+#[derive(Debug)]
 pub(crate) struct SourceMap {
-    // Future: Add source map data structures here
+    sources: Vec<String>,
+    sources_content: Vec<Option<String>>,
+    // Mappings for each generated line, sorted by column
+    lines: Vec<Vec<Mapping>>,
 }
 
-impl SourceMap {
-    /// Create a new empty source map.
-    #[allow(dead_code)]
-    pub(crate) fn new() -> Self {
-        Self {}
-    }
+/// A position in an original source
+#[derive(Debug, PartialEq)]
+pub(crate) struct OriginalPosition {
+    pub(crate) source: String,
+    /// 1-based line
+    pub(crate) line: u32,
+    /// 0-based column
+    pub(crate) column: u32,
+    /// The text of the line, from `sourcesContent` or the original file
+    pub(crate) source_line: Option<String>,
+}
 
+impl SourceMap {
     /// Parse a source map from JSON content.
     ///
     /// # Arguments
@@ -64,10 +73,64 @@ impl SourceMap {
     /// # Returns
     ///
     /// A Result containing the parsed source map or an error message.
-    #[allow(dead_code)]
-    pub(crate) fn parse(_json: &str) -> Result<Self, String> {
-        // Future: Implement source map parsing
-        Err("Source map parsing not yet implemented".to_string())
+    pub(crate) fn parse(json: &str) -> Result<Self, String> {
+        // Source maps may start with a line that keeps them from being run
+        let json = json.strip_prefix(")]}'").unwrap_or(json);
+        let map = Json::parse(json)?;
+
+        if map.get("sections").is_some() {
+            return Err("Indexed source maps aren't supported".to_string());
+        }
+        match map.get("version") {
+            Some(Json::Number(version)) if *version == 3.0 => {}
+            _ => return Err("Unsupported source map version".to_string()),
+        }
+
+        let source_root = match map.get("sourceRoot") {
+            Some(Json::String(root)) if !root.is_empty() && !root.ends_with('/') => {
+                format!("{}/", root)
+            }
+            Some(Json::String(root)) => root.clone(),
+            _ => String::new(),
+        };
+        let sources = match map.get("sources") {
+            Some(Json::Array(sources)) => sources
+                .iter()
+                .map(|source| match source {
+                    Json::String(source) => format!("{}{}", source_root, source),
+                    _ => String::new(),
+                })
+                .collect(),
+            _ => return Err("Source map has no \"sources\"".to_string()),
+        };
+        let sources_content = match map.get("sourcesContent") {
+            Some(Json::Array(contents)) => contents
+                .iter()
+                .map(|content| match content {
+                    Json::String(content) => Some(content.clone()),
+                    _ => None,
+                })
+                .collect(),
+            _ => Vec::new(),
+        };
+        let lines = match map.get("mappings") {
+            Some(Json::String(mappings)) => decode_mappings(mappings)?,
+            _ => return Err("Source map has no \"mappings\"".to_string()),
+        };
+
+        Ok(Self {
+            sources,
+            sources_content,
+            lines,
+        })
+    }
+
+    /// Resolve the sources against the location of the map, a file path or
+    /// URL, so they can be read and reported.
+    fn resolve_sources(&mut self, base: &str) {
+        for source in &mut self.sources {
+            *source = resolve_reference(base, source);
+        }
     }
 
     /// Map a location in generated code to the original source.
@@ -79,27 +142,197 @@ impl SourceMap {
     ///
     /// # Returns
     ///
-    /// A tuple of (source_file, original_line, original_column) if found.
-    #[allow(dead_code)]
-    pub(crate) fn map_location(&self, _line: u32, _column: u32) -> Option<(String, u32, u32)> {
-        // Future: Implement location mapping
-        None
+    /// A tuple of (source_file, original_line, original_column) if found,
+    /// with the line and column 0-based.
+    pub(crate) fn map_location(&self, line: u32, column: u32) -> Option<(String, u32, u32)> {
+        let mappings = self.lines.get(line as usize)?;
+        // The mapping covering the column is the last one starting at or before it
+        let index = mappings.partition_point(|mapping| mapping.generated_column <= column);
+        let (source, line, column) = mappings.get(index.checked_sub(1)?)?.original?;
+        let source = self.sources.get(source as usize)?;
+        Some((source.clone(), line, column))
     }
 
     /// Get the original source content for a source file.
     ///
     /// # Arguments
     ///
-    /// * `source_file` - The source file name
+    /// * `source_file` - The source file name, as returned by `map_location`
     ///
     /// # Returns
     ///
     /// The original source content if available.
-    #[allow(dead_code)]
-    pub(crate) fn get_source_content(&self, _source_file: &str) -> Option<&str> {
-        // Future: Implement source content retrieval
-        None
+    pub(crate) fn get_source_content(&self, source_file: &str) -> Option<&str> {
+        let index = self.sources.iter().position(|s| s == source_file)?;
+        self.sources_content.get(index)?.as_deref()
+    }
+}
+
+/// Decode the `mappings` field into the mappings for each generated line.
+fn decode_mappings(mappings: &str) -> Result<Vec<Vec<Mapping>>, String> {
+    let mut lines = Vec::new();
+    // Fields other than the generated column carry over between lines. Names
+    // aren't reported, so the name index is skipped.
+    let (mut source, mut original_line, mut original_column) = (0i64, 0i64, 0i64);
+
+    for line in mappings.split(';') {
+        let mut generated_column = 0i64;
+        let mut segments = Vec::new();
+        for segment in line.split(',').filter(|s| !s.is_empty()) {
+            let fields = decode_vlq_segment(segment)?;
+            generated_column += fields[0];
+            let original = match fields.len() {
+                1 => None,
+                4 | 5 => {
+                    source += fields[1];
+                    original_line += fields[2];
+                    original_column += fields[3];
+                    Some((
+                        to_u32(source)?,
+                        to_u32(original_line)?,
+                        to_u32(original_column)?,
+                    ))
+                }
+                _ => return Err(format!("Invalid source map segment \"{}\"", segment)),
+            };
+            segments.push(Mapping {
+                generated_column: to_u32(generated_column)?,
+                original,
+            });
+        }
+        segments.sort_by_key(|mapping| mapping.generated_column);
+        lines.push(segments);
+    }
+    Ok(lines)
+}
+
+fn to_u32(value: i64) -> Result<u32, String> {
+    u32::try_from(value).map_err(|_| "Invalid source map mappings".to_string())
+}
+
+/// Decode the base64 VLQ values of one segment. Each value is a run of
+/// 6-bit digits, least significant first, where the top bit of a digit is
+/// set when another follows. The lowest bit of the value is its sign.
+fn decode_vlq_segment(segment: &str) -> Result<Vec<i64>, String> {
+    let mut values = Vec::with_capacity(5);
+    let mut value = 0i64;
+    let mut shift = 0;
+    for byte in segment.bytes() {
+        let digit = match byte {
+            b'A'..=b'Z' => byte - b'A',
+            b'a'..=b'z' => byte - b'a' + 26,
+            b'0'..=b'9' => byte - b'0' + 52,
+            b'+' => 62,
+            b'/' => 63,
+            _ => return Err(format!("Invalid source map segment \"{}\"", segment)),
+        } as i64;
+        if shift > 32 {
+            return Err(format!("Invalid source map segment \"{}\"", segment));
+        }
+        value |= (digit & 0b11111) << shift;
+        if digit & 0b100000 != 0 {
+            shift += 5;
+            continue;
+        }
+        values.push(if value & 1 == 1 {
+            -(value >> 1)
+        } else {
+            value >> 1
+        });
+        value = 0;
+        shift = 0;
+    }
+    if shift != 0 {
+        return Err(format!("Invalid source map segment \"{}\"", segment));
     }
+    Ok(values)
+}
+
+/// Resolve `reference` against `base`, a file path or URL. Sources with
+/// other schemes, like `webpack://`, are kept as they are.
+fn resolve_reference(base: &str, reference: &str) -> String {
+    if let Some(path) = reference.strip_prefix("file://") {
+        return path.to_string();
+    }
+    if has_url_scheme(reference) {
+        return reference.to_string();
+    }
+    if is_remote_url(base) {
+        return ada_url::Url::parse(reference, Some(base))
+            .map(|url| url.href().to_string())
+            .unwrap_or_else(|_| reference.to_string());
+    }
+    let dir = Path::new(base).parent().unwrap_or_else(|| Path::new("."));
+    normalize(&dir.join(reference))
+        .to_string_lossy()
+        .into_owned()
+}
+
+/// Remove `.` and `..` components without touching the file system, as
+/// the original sources don't need to exist
+fn normalize(path: &Path) -> PathBuf {
+    let mut normalized = PathBuf::new();
+    for component in path.components() {
+        match component {
+            Component::CurDir => {}
+            Component::ParentDir => {
+                normalized.pop();
+            }
+            component => normalized.push(component),
+        }
+    }
+    normalized
+}
+
+/// Find and parse the source map of the module at `path`, a file path or
+/// URL. Inline maps are decoded from their `data:` URL, and external maps
+/// are read with `read` from their resolved location. Modules without a
+/// readable, valid map return None.
+pub(crate) fn load(
+    path: &str,
+    source: &str,
+    read: impl FnOnce(&str) -> Option<String>,
+) -> Option<SourceMap> {
+    if let Some(data) = detect_inline_sourcemap(source) {
+        let json = base64_simd::STANDARD.decode_to_vec(data).ok()?;
+        let mut map = SourceMap::parse(&String::from_utf8(json).ok()?).ok()?;
+        map.resolve_sources(path);
+        return Some(map);
+    }
+
+    let location = resolve_reference(path, detect_external_sourcemap(source)?);
+    let mut map = SourceMap::parse(&read(&location)?).ok()?;
+    map.resolve_sources(&location);
+    Some(map)
+}
+
+/// Map a position in a compiled module to its original source, if the
+/// module has a source map. The line is 1-based and the column 0-based,
+/// as V8 reports them.
+pub(crate) fn original_position(
+    isolate: &mut v8::Isolate,
+    file: &str,
+    line: u32,
+    column: u32,
+) -> Option<OriginalPosition> {
+    let state = IsolateState::get(isolate);
+    let state = state.borrow();
+    let map = state.module_map.source_map(file)?;
+    let (source, line, column) = map.map_location(line.checked_sub(1)?, column)?;
+
+    let source_line = match map.get_source_content(&source) {
+        Some(content) => content.lines().nth(line as usize).map(str::to_string),
+        None if !has_url_scheme(&source) => crate::module::read_source_cached(&source)
+            .ok()
+            .and_then(|content| content.lines().nth(line as usize).map(str::to_string)),
+        None => None,
+    };
+    Some(OriginalPosition {
+        source,
+        line: line + 1,
+        column,
+        source_line,
+    })
 }
 
 /// Detect if a JavaScript file has an inline source map.
@@ -114,15 +347,14 @@ impl SourceMap {
 /// # Returns
 ///
 /// The base64-encoded source map content if found.
-#[allow(dead_code)]
 pub(crate) fn detect_inline_sourcemap(content: &str) -> Option<&str> {
-    // Look for inline source map comment
-    let prefix = "//# sourceMappingURL=data:application/json;base64,";
+    // Look for inline source map comment, which may give a charset
+    let prefix = "//# sourceMappingURL=data:application/json";
 
     for line in content.lines().rev().take(10) {
         let trimmed = line.trim();
         if let Some(data) = trimmed.strip_prefix(prefix) {
-            return Some(data);
+            return data.split_once(";base64,").map(|(_, data)| data);
         }
     }
 
@@ -141,7 +373,6 @@ pub(crate) fn detect_inline_sourcemap(content: &str) -> Option<&str> {
 /// # Returns
 ///
 /// The source map file name if found.
-#[allow(dead_code)]
 pub(crate) fn detect_external_sourcemap(content: &str) -> Option<&str> {
     // Look for external source map reference
     let prefix = "//# sourceMappingURL=";
@@ -172,6 +403,9 @@ console.log("test");
 
         let result = detect_inline_sourcemap(js);
         assert_eq!(result, Some("eyJ2ZXJzaW9uIjozfQ=="));
+
+        let js = "//# sourceMappingURL=data:application/json;charset=utf-8;base64,e30=";
+        assert_eq!(detect_inline_sourcemap(js), Some("e30="));
     }
 
     #[test]
@@ -194,4 +428,93 @@ console.log("test");
         assert_eq!(detect_inline_sourcemap(js), None);
         assert_eq!(detect_external_sourcemap(js), None);
     }
+
+    #[test]
+    fn test_decode_vlq() {
+        assert_eq!(decode_vlq_segment("AAAA").unwrap(), vec![0, 0, 0, 0]);
+        assert_eq!(decode_vlq_segment("CACDE").unwrap(), vec![1, 0, 1, -1, 2]);
+        // Values of more than one digit
+        assert_eq!(decode_vlq_segment("gB").unwrap(), vec![16]);
+        assert_eq!(decode_vlq_segment("2HktC").unwrap(), vec![123, 1234]);
+        assert!(decode_vlq_segment("g").is_err());
+        assert!(decode_vlq_segment("A!").is_err());
+    }
+
+    #[test]
+    fn test_map_location() {
+        // Line 1: column 0 -> a.ts 1:0, column 6 -> a.ts 1:4
+        // Line 2: nothing
+        // Line 3: column 2 -> b.ts 3:2, column 10 -> unmapped
+        let map = SourceMap::parse(
+            r#"{
+  "version": 3,
+  "sourceRoot": "src",
+  "sources": ["a.ts", "b.ts"],
+  "sourcesContent": ["let a = 1;", null],
+  "names": [],
+  "mappings": "AAAA,MAAI;;ECEF,Q"
+}"#,
+        )
+        .unwrap();
+
+        assert_eq!(map.map_location(0, 0), Some(("src/a.ts".to_string(), 0, 0)));
+        assert_eq!(map.map_location(0, 5), Some(("src/a.ts".to_string(), 0, 0)));
+        assert_eq!(map.map_location(0, 8), Some(("src/a.ts".to_string(), 0, 4)));
+        assert_eq!(map.map_location(1, 0), None);
+        assert_eq!(map.map_location(2, 1), None);
+        assert_eq!(map.map_location(2, 4), Some(("src/b.ts".to_string(), 2, 2)));
+        assert_eq!(map.map_location(2, 10), None);
+        assert_eq!(map.map_location(5, 0), None);
+
+        assert_eq!(map.get_source_content("src/a.ts"), Some("let a = 1;"));
+        assert_eq!(map.get_source_content("src/b.ts"), None);
+    }
+
+    #[test]
+    fn test_invalid_source_maps() {
+        assert!(SourceMap::parse("{").is_err());
+        assert!(SourceMap::parse(r#"{"version": 2, "sources": [], "mappings": ""}"#).is_err());
+        assert!(SourceMap::parse(r#"{"version": 3, "sections": []}"#).is_err());
+        assert!(SourceMap::parse(r#"{"version": 3, "sources": [], "mappings": "AA"}"#).is_err());
+    }
+
+    #[test]
+    fn test_load_resolves_sources() {
+        // {"version":3,"sources":["../src/app.ts"],"mappings":"AAAA"}
+        let js = "x\n//# sourceMappingURL=data:application/json;base64,eyJ2ZXJzaW9uIjozLCJzb3VyY2VzIjpbIi4uL3NyYy9hcHAudHMiXSwibWFwcGluZ3MiOiJBQUFBIn0=";
+        let map = load("/app/dist/app.js", js, |_| None).unwrap();
+        assert_eq!(
+            map.map_location(0, 0),
+            Some(("/app/src/app.ts".to_string(), 0, 0))
+        );
+
+        let js = "x\n//# sourceMappingURL=maps/app.js.map";
+        let map = load("/app/dist/app.js", js, |location| {
+            assert_eq!(location, "/app/dist/maps/app.js.map");
+            Some(
+                r#"{"version":3,"sources":["webpack://app/a.js","b.js"],"mappings":"AAAA,CCAA"}"#
+                    .to_string(),
+            )
+        })
+        .unwrap();
+        assert_eq!(
+            map.map_location(0, 0),
+            Some(("webpack://app/a.js".to_string(), 0, 0))
+        );
+        assert_eq!(
+            map.map_location(0, 1),
+            Some(("/app/dist/maps/b.js".to_string(), 0, 0))
+        );
+
+        let js = "x\n//# sourceMappingURL=app.js.map";
+        let map = load("https://example.com/lib/app.js", js, |location| {
+            assert_eq!(location, "https://example.com/lib/app.js.map");
+            Some(r#"{"version":3,"sources":["../src/app.ts"],"mappings":"AAAA"}"#.to_string())
+        })
+        .unwrap();
+        assert_eq!(
+            map.map_location(0, 0),
+            Some(("https://example.com/src/app.ts".to_string(), 0, 0))
+        );
+    }
 }
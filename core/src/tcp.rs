@@ -0,0 +1,432 @@
+//! Non-blocking TCP sockets and listeners backing `node:net`.
+//!
+//! Sockets are owned by the isolate state and polled by the event loop, like
+//! dgram sockets. Polling a socket flushes queued writes and reads whatever is
+//! available, producing [`SocketEvent`]s for the event loop to hand to JS.
+//! `std` has no non-blocking connect, so connecting (including DNS lookup)
+//! happens on a short-lived thread and the socket is polled once it connects.
+//! TLS sockets wrap the stream in a [`TlsSession`] that encrypts and decrypts
+//! as the socket is polled.
+
+use crate::tls::TlsSession;
+use smallvec::SmallVec;
+use std::io::{ErrorKind, Read, Write};
+use std::net::{Shutdown, SocketAddr, TcpListener, TcpStream, ToSocketAddrs};
+use std::sync::mpsc::{self, Receiver, TryRecvError};
+
+/// Upper bound on reads per socket per poll, so one busy socket can't starve the others
+pub(crate) const MAX_READS_PER_POLL: usize = 16;
+
+/// Something that happened on a socket, reported to JS in order
+pub(crate) enum SocketEvent {
+    /// The connection was established
+    Connect,
+    /// The TLS handshake completed and the peer's certificate was accepted
+    SecureConnect,
+    Data(Vec<u8>),
+    /// The peer closed its side of the connection
+    End,
+    /// The write queue was emptied after a write returned false
+    Drain,
+    /// The write side was shut down after `end()`
+    Finish,
+    /// The socket failed and has been closed
+    Error(std::io::Error),
+}
+
+enum SocketState {
+    /// Waiting for the connect thread, or for a stream being handed off
+    Connecting(Receiver<std::io::Result<TcpStream>>),
+    Open(TcpStream),
+}
+
+/// A `net.Socket` registered with the event loop
+pub(crate) struct ActiveTcpSocket {
+    state: SocketState,
+    /// Called with (event, data). Accepted sockets have none until JS attaches one.
+    pub(crate) callback: Option<v8::Global<v8::Function>>,
+    pub(crate) is_ref: bool, // If true, keeps the event loop alive
+    /// Stop reading while paused, leaving data in the kernel buffer
+    pub(crate) paused: bool,
+    /// Bytes that didn't fit in the socket buffer yet
+    write_queue: Vec<u8>,
+    needs_drain: bool,
+    ending: bool,
+    write_closed: bool,
+    read_closed: bool,
+    tls: Option<TlsSession>,
+}
+
+impl ActiveTcpSocket {
+    /// Start connecting to host:port in the background. Events are delivered
+    /// to JS once a callback is set.
+    pub(crate) fn connect(host: String, port: u16) -> Self {
+        let (tx, rx) = mpsc::channel();
+        let spawned = std::thread::Builder::new()
+            .name("jstime-net-connect".to_string())
+            .spawn({
+                let tx = tx.clone();
+                move || {
+                    let _ = tx.send(connect_blocking(&host, port));
+                }
+            });
+        if let Err(e) = spawned {
+            let _ = tx.send(Err(e));
+        }
+
+        Self::new(SocketState::Connecting(rx))
+    }
+
+    /// Wrap a socket accepted by a server. It must already be non-blocking.
+    pub(crate) fn accepted(stream: TcpStream) -> Self {
+        Self::new(SocketState::Open(stream))
+    }
+
+    /// Wrap a stream that another thread hands over once it is ready, such as an
+    /// HTTP connection being upgraded. Polling reports `Connect` when it arrives.
+    pub(crate) fn handed_off(rx: Receiver<std::io::Result<TcpStream>>) -> Self {
+        Self::new(SocketState::Connecting(rx))
+    }
+
+    fn new(state: SocketState) -> Self {
+        Self {
+            state,
+            callback: None,
+            is_ref: true, // By default, sockets keep the event loop alive
+            paused: false,
+            write_queue: Vec::new(),
+            needs_drain: false,
+            ending: false,
+            write_closed: false,
+            read_closed: false,
+            tls: None,
+        }
+    }
+
+    /// Encrypt the connection with TLS once it is established
+    pub(crate) fn with_tls(mut self, session: TlsSession) -> Self {
+        self.tls = Some(session);
+        self
+    }
+
+    /// The TLS session, for TLS sockets
+    pub(crate) fn tls(&self) -> Option<&TlsSession> {
+        self.tls.as_ref()
+    }
+
+    /// The underlying stream, once connected
+    pub(crate) fn stream(&self) -> Option<&TcpStream> {
+        match &self.state {
+            SocketState::Open(stream) => Some(stream),
+            SocketState::Connecting(_) => None,
+        }
+    }
+
+    /// Write as much as possible now and queue the rest.
+    /// Returns true if everything was written, false if the caller should wait for a drain.
+    pub(crate) fn write(&mut self, data: &[u8]) -> std::io::Result<bool> {
+        if self.ending || self.write_closed {
+            return Err(std::io::Error::new(
+                ErrorKind::BrokenPipe,
+                "This socket has been ended by the other party",
+            ));
+        }
+        let mut written = 0;
+        if self.write_queue.is_empty()
+            && self.tls.is_none()
+            && let SocketState::Open(stream) = &mut self.state
+        {
+            written = write_nonblocking(stream, data)?;
+        }
+        self.write_queue.extend_from_slice(&data[written..]);
+        if let (Some(tls), SocketState::Open(stream)) = (&mut self.tls, &mut self.state) {
+            tls.flush(stream, &mut self.write_queue)?;
+        }
+        if self.has_pending_writes() {
+            self.needs_drain = true;
+            Ok(false)
+        } else {
+            Ok(true)
+        }
+    }
+
+    /// The number of bytes waiting to be written
+    pub(crate) fn buffered_amount(&self) -> usize {
+        self.write_queue.len()
+    }
+
+    /// True if plaintext or encrypted data is still waiting to be written
+    fn has_pending_writes(&self) -> bool {
+        !self.write_queue.is_empty() || self.tls.as_ref().is_some_and(TlsSession::wants_write)
+    }
+
+    /// Shut down the write side once queued data has been written
+    pub(crate) fn end(&mut self) {
+        self.ending = true;
+    }
+
+    /// Advance the socket: finish connecting, flush writes and read available data.
+    /// After an `Error` event the socket must be dropped.
+    pub(crate) fn poll(&mut self, buf: &mut [u8], events: &mut SmallVec<[SocketEvent; 4]>) {
+        if let SocketState::Connecting(rx) = &self.state {
+            let stream = match rx.try_recv() {
+                Ok(Ok(stream)) => stream,
+                Ok(Err(e)) => {
+                    events.push(SocketEvent::Error(e));
+                    return;
+                }
+                Err(TryRecvError::Empty) => return,
+                Err(TryRecvError::Disconnected) => {
+                    events.push(SocketEvent::Error(std::io::Error::other(
+                        "connection was closed before it was established",
+                    )));
+                    return;
+                }
+            };
+            if let Err(e) = stream.set_nonblocking(true) {
+                events.push(SocketEvent::Error(e));
+                return;
+            }
+            self.state = SocketState::Open(stream);
+            events.push(SocketEvent::Connect);
+        }
+        let SocketState::Open(stream) = &mut self.state else {
+            return;
+        };
+
+        // Flush queued writes
+        if let Err(e) = flush_writes(stream, &mut self.tls, &mut self.write_queue) {
+            events.push(SocketEvent::Error(e));
+            return;
+        }
+
+        // Read whatever is available
+        if !self.read_closed && !self.paused {
+            let result = match &mut self.tls {
+                Some(tls) => tls.read(stream, buf, events),
+                None => read_nonblocking(stream, buf, events),
+            };
+            match result {
+                Ok(true) => {
+                    self.read_closed = true;
+                    events.push(SocketEvent::End);
+                }
+                Ok(false) => {}
+                Err(e) => {
+                    events.push(SocketEvent::Error(e));
+                    return;
+                }
+            }
+            // Reading may have advanced the TLS handshake, which then has more to send
+            if self.tls.is_some()
+                && let Err(e) = flush_writes(stream, &mut self.tls, &mut self.write_queue)
+            {
+                events.push(SocketEvent::Error(e));
+                return;
+            }
+        }
+
+        // Checked field by field, since `stream` still borrows the state
+        if !self.write_queue.is_empty() || self.tls.as_ref().is_some_and(TlsSession::wants_write) {
+            return;
+        }
+        if self.needs_drain {
+            self.needs_drain = false;
+            events.push(SocketEvent::Drain);
+        }
+        // A TLS socket can't close its write side until the handshake is done
+        if self.ending && !self.write_closed && self.tls.as_ref().is_none_or(TlsSession::is_secure)
+        {
+            if let Some(tls) = &mut self.tls {
+                tls.close();
+                if let Err(e) = tls.flush(stream, &mut self.write_queue) {
+                    events.push(SocketEvent::Error(e));
+                    return;
+                }
+                if tls.wants_write() {
+                    return;
+                }
+            }
+            self.write_closed = true;
+            // The peer may already have closed the connection; that's not an error here
+            let _ = stream.shutdown(Shutdown::Write);
+            events.push(SocketEvent::Finish);
+        }
+    }
+}
+
+/// A `net.Server` registered with the event loop
+pub(crate) struct ActiveTcpServer {
+    listener: TcpListener,
+    /// Called with the ID of each accepted socket
+    pub(crate) callback: v8::Global<v8::Function>,
+    pub(crate) is_ref: bool, // If true, keeps the event loop alive
+}
+
+impl ActiveTcpServer {
+    pub(crate) fn bind(addr: &str, callback: v8::Global<v8::Function>) -> std::io::Result<Self> {
+        let listener = TcpListener::bind(addr)?;
+        listener.set_nonblocking(true)?;
+        Ok(Self {
+            listener,
+            callback,
+            is_ref: true, // By default, servers keep the event loop alive
+        })
+    }
+
+    pub(crate) fn local_addr(&self) -> std::io::Result<SocketAddr> {
+        self.listener.local_addr()
+    }
+
+    /// Accept a pending connection without blocking
+    pub(crate) fn accept(&self) -> std::io::Result<Option<TcpStream>> {
+        match self.listener.accept() {
+            Ok((stream, _)) => {
+                stream.set_nonblocking(true)?;
+                Ok(Some(stream))
+            }
+            Err(e) if e.kind() == ErrorKind::WouldBlock => Ok(None),
+            Err(e) => Err(e),
+        }
+    }
+}
+
+/// Resolve host and connect to the first address that accepts
+fn connect_blocking(host: &str, port: u16) -> std::io::Result<TcpStream> {
+    let addrs: Vec<SocketAddr> = (host, port)
+        .to_socket_addrs()
+        .map_err(|e| {
+            std::io::Error::new(
+                ErrorKind::NotFound,
+                format!("getaddrinfo ENOTFOUND {}: {}", host, e),
+            )
+        })?
+        .collect();
+    let mut last_error = std::io::Error::new(
+        ErrorKind::NotFound,
+        format!("getaddrinfo ENOTFOUND {}", host),
+    );
+    for addr in addrs {
+        match TcpStream::connect(addr) {
+            Ok(stream) => return Ok(stream),
+            Err(e) => last_error = e,
+        }
+    }
+    Err(last_error)
+}
+
+/// Write queued data, through the TLS session if there is one
+fn flush_writes(
+    stream: &mut TcpStream,
+    tls: &mut Option<TlsSession>,
+    queue: &mut Vec<u8>,
+) -> std::io::Result<()> {
+    match tls {
+        Some(tls) => tls.flush(stream, queue),
+        None => {
+            if !queue.is_empty() {
+                let written = write_nonblocking(stream, queue)?;
+                queue.drain(..written);
+            }
+            Ok(())
+        }
+    }
+}
+
+/// Read until the socket would block, pushing a `Data` event per read.
+/// Returns true when the peer has closed its side of the connection.
+fn read_nonblocking(
+    stream: &mut TcpStream,
+    buf: &mut [u8],
+    events: &mut SmallVec<[SocketEvent; 4]>,
+) -> std::io::Result<bool> {
+    for _ in 0..MAX_READS_PER_POLL {
+        match stream.read(buf) {
+            Ok(0) => return Ok(true),
+            Ok(n) => events.push(SocketEvent::Data(buf[..n].to_vec())),
+            Err(e) if e.kind() == ErrorKind::WouldBlock => return Ok(false),
+            Err(e) if e.kind() == ErrorKind::Interrupted => continue,
+            Err(e) => return Err(e),
+        }
+    }
+    Ok(false)
+}
+
+/// Write until the socket would block. Returns how many bytes were written.
+fn write_nonblocking(stream: &mut TcpStream, data: &[u8]) -> std::io::Result<usize> {
+    let mut written = 0;
+    while written < data.len() {
+        match stream.write(&data[written..]) {
+            Ok(0) => return Err(ErrorKind::WriteZero.into()),
+            Ok(n) => written += n,
+            Err(e) if e.kind() == ErrorKind::WouldBlock => break,
+            Err(e) if e.kind() == ErrorKind::Interrupted => continue,
+            Err(e) => return Err(e),
+        }
+    }
+    Ok(written)
+}
+
+/// Enable or disable TCP keep-alive probes, optionally setting the idle time before the first probe
+#[cfg(unix)]
+pub(crate) fn set_keep_alive(
+    stream: &TcpStream,
+    enable: bool,
+    initial_delay_secs: u32,
+) -> std::io::Result<()> {
+    use std::os::unix::io::AsRawFd;
+
+    fn set_option(
+        fd: libc::c_int,
+        level: libc::c_int,
+        name: libc::c_int,
+        value: libc::c_int,
+    ) -> std::io::Result<()> {
+        // SAFETY: fd is a valid socket owned by the caller, and value outlives the call
+        let result = unsafe {
+            libc::setsockopt(
+                fd,
+                level,
+                name,
+                &value as *const libc::c_int as *const libc::c_void,
+                std::mem::size_of::<libc::c_int>() as libc::socklen_t,
+            )
+        };
+        if result == 0 {
+            Ok(())
+        } else {
+            Err(std::io::Error::last_os_error())
+        }
+    }
+
+    let fd = stream.as_raw_fd();
+    set_option(
+        fd,
+        libc::SOL_SOCKET,
+        libc::SO_KEEPALIVE,
+        enable as libc::c_int,
+    )?;
+    if enable && initial_delay_secs > 0 {
+        #[cfg(any(target_os = "macos", target_os = "ios"))]
+        let idle_option = libc::TCP_KEEPALIVE;
+        #[cfg(not(any(target_os = "macos", target_os = "ios")))]
+        let idle_option = libc::TCP_KEEPIDLE;
+        set_option(
+            fd,
+            libc::IPPROTO_TCP,
+            idle_option,
+            initial_delay_secs as libc::c_int,
+        )?;
+    }
+    Ok(())
+}
+
+/// Keep-alive can't be configured through `std` on this platform, so this is a no-op
+#[cfg(not(unix))]
+pub(crate) fn set_keep_alive(
+    _stream: &TcpStream,
+    _enable: bool,
+    _initial_delay_secs: u32,
+) -> std::io::Result<()> {
+    Ok(())
+}
@@ -0,0 +1,194 @@
+//! Stopping JavaScript that runs too long, from another thread or when the
+//! execution timeout passes, or that uses too much memory.
+//!
+//! V8 terminates the running JavaScript when asked, but only until control
+//! returns to Rust, so the request is also kept in a flag: the event loop
+//! calls no more callbacks once it's set, and `JSTime` reports the run as
+//! `Error::Terminated` and clears it, so the isolate can run scripts again.
+//!
+//! When the heap nears its limit, V8 would abort the process. Instead, the
+//! JavaScript is terminated, with the limit raised so it can unwind, and the
+//! run is reported as `Error::HeapLimitExceeded`.
+
+use crate::IsolateState;
+use std::ffi::c_void;
+use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::mpsc;
+use std::thread::JoinHandle;
+use std::time::{Duration, Instant};
+
+/// Terminates the JavaScript running on a `JSTime`, from any thread
+#[derive(Clone)]
+pub struct TerminationHandle {
+    isolate: v8::IsolateHandle,
+    requested: Arc<AtomicBool>,
+}
+
+impl TerminationHandle {
+    pub(crate) fn new(isolate: &v8::Isolate) -> Self {
+        Self {
+            isolate: isolate.thread_safe_handle(),
+            requested: Arc::new(AtomicBool::new(false)),
+        }
+    }
+
+    /// Terminate the script, module or event loop the `JSTime` is running,
+    /// which then returns `Error::Terminated`. Returns false if the
+    /// `JSTime` was dropped.
+    pub fn terminate(&self) -> bool {
+        self.requested.store(true, Ordering::SeqCst);
+        self.isolate.terminate_execution()
+    }
+
+    pub(crate) fn is_requested(&self) -> bool {
+        self.requested.load(Ordering::SeqCst)
+    }
+
+    /// Clear a termination, so the isolate runs JavaScript again. Returns
+    /// whether one was requested.
+    pub(crate) fn reset(&self, isolate: &mut v8::Isolate) -> bool {
+        let requested = self.requested.swap(false, Ordering::SeqCst);
+        if requested {
+            isolate.cancel_terminate_execution();
+        }
+        requested
+    }
+}
+
+/// Termination state of an isolate
+pub(crate) struct Termination {
+    pub(crate) handle: TerminationHandle,
+    // When the watchdog terminates the current run, if it has a timeout
+    pub(crate) deadline: Option<Instant>,
+}
+
+/// Terminates the run it was started for when `timeout` passes, unless it's
+/// dropped first
+pub(crate) struct Watchdog {
+    done: Option<mpsc::Sender<()>>,
+    thread: Option<JoinHandle<()>>,
+}
+
+impl Watchdog {
+    pub(crate) fn start(handle: TerminationHandle, timeout: Duration) -> Self {
+        let (done, receiver) = mpsc::channel::<()>();
+        let thread = std::thread::spawn(move || {
+            if let Err(mpsc::RecvTimeoutError::Timeout) = receiver.recv_timeout(timeout) {
+                handle.terminate();
+            }
+        });
+        Self {
+            done: Some(done),
+            thread: Some(thread),
+        }
+    }
+}
+
+impl Drop for Watchdog {
+    fn drop(&mut self) {
+        // Disconnecting wakes the thread up, and joining it makes sure it
+        // can't terminate a later run
+        drop(self.done.take());
+        if let Some(thread) = self.thread.take() {
+            let _ = thread.join();
+        }
+    }
+}
+
+/// What the near heap limit callback of an isolate needs. Boxed by `JSTime`,
+/// so its address doesn't change while V8 has it.
+pub(crate) struct HeapLimit {
+    handle: TerminationHandle,
+    max_size: usize,
+    exceeded: AtomicBool,
+}
+
+impl HeapLimit {
+    /// Terminate the JavaScript `isolate` runs when its heap nears
+    /// `max_size`, the limit it was created with
+    pub(crate) fn install(
+        isolate: &mut v8::Isolate,
+        handle: TerminationHandle,
+        max_size: usize,
+    ) -> Box<Self> {
+        let heap_limit = Box::new(Self {
+            handle,
+            max_size,
+            exceeded: AtomicBool::new(false),
+        });
+        isolate.add_near_heap_limit_callback(near_heap_limit_callback, heap_limit.as_data());
+        heap_limit
+    }
+
+    /// If the limit was reached, collect the garbage the terminated
+    /// JavaScript left and lower the limit back. Returns whether it was
+    /// reached.
+    pub(crate) fn recover(&self, isolate: &mut v8::Isolate) -> bool {
+        if !self.exceeded.swap(false, Ordering::SeqCst) {
+            return false;
+        }
+        // Removing the callback restores the limit, so it's added again
+        isolate.remove_near_heap_limit_callback(near_heap_limit_callback, self.max_size);
+        isolate.low_memory_notification();
+        isolate.add_near_heap_limit_callback(near_heap_limit_callback, self.as_data());
+        true
+    }
+
+    fn as_data(&self) -> *mut c_void {
+        self as *const Self as *mut c_void
+    }
+}
+
+/// Called by V8 when the heap is close to its limit
+extern "C" fn near_heap_limit_callback(
+    data: *mut c_void,
+    current_heap_limit: usize,
+    initial_heap_limit: usize,
+) -> usize {
+    // SAFETY: data is the HeapLimit of the JSTime that owns the isolate,
+    // which drops it after the isolate
+    let heap_limit = unsafe { &*(data as *const HeapLimit) };
+    // The limit is raised once per run, until `recover` lowers it back, so
+    // JavaScript that keeps allocating as it unwinds can't grow the heap
+    // without bound
+    if heap_limit.exceeded.swap(true, Ordering::SeqCst) {
+        return current_heap_limit;
+    }
+    heap_limit.handle.terminate();
+    // Room for the terminated JavaScript to unwind
+    current_heap_limit.saturating_add(initial_heap_limit)
+}
+
+/// Whether the current run was asked to terminate
+pub(crate) fn is_requested(scope: &mut v8::PinScope) -> bool {
+    let isolate: &mut v8::Isolate = scope;
+    let state = IsolateState::get(isolate);
+    let state = state.borrow();
+    state
+        .termination
+        .as_ref()
+        .is_some_and(|termination| termination.handle.is_requested())
+}
+
+/// Stop the event loop if the current run was asked to terminate
+pub(crate) fn check(scope: &mut v8::PinScope) -> Result<(), crate::JsError> {
+    if is_requested(scope) {
+        Err(crate::JsError::from("Execution terminated".to_string()))
+    } else {
+        Ok(())
+    }
+}
+
+/// How long the event loop may wait before the execution timeout passes,
+/// if there is one
+pub(crate) fn time_left(scope: &mut v8::PinScope) -> Option<Duration> {
+    let isolate: &mut v8::Isolate = scope;
+    let state = IsolateState::get(isolate);
+    let state = state.borrow();
+    state
+        .termination
+        .as_ref()
+        .and_then(|termination| termination.deadline)
+        .map(|deadline| deadline.saturating_duration_since(Instant::now()))
+}
@@ -0,0 +1,280 @@
+//! TLS client sessions layered on top of [`crate::tcp`] sockets, backing `node:tls`.
+//!
+//! A [`TlsSession`] owns a rustls client connection. The TCP socket hands it
+//! the non-blocking stream when polled: the session writes TLS records for
+//! queued plaintext and decrypts whatever records have arrived.
+
+use crate::tcp::{MAX_READS_PER_POLL, SocketEvent};
+use rustls::pki_types::pem::PemObject;
+use rustls::pki_types::{CertificateDer, ServerName};
+use rustls::{CertificateError, ClientConfig, ClientConnection, RootCertStore};
+use smallvec::SmallVec;
+use std::io::{ErrorKind, Read, Write};
+use std::net::TcpStream;
+use std::sync::{Arc, OnceLock};
+
+/// Options for a TLS client connection
+#[derive(Default)]
+pub(crate) struct TlsOptions {
+    /// Name sent with SNI and checked against the certificate. Defaults to the host.
+    pub(crate) server_name: Option<String>,
+    /// PEM encoded CA certificates that replace the built-in Mozilla roots
+    pub(crate) ca_certs: Vec<String>,
+    /// Protocols offered with ALPN, most preferred first
+    pub(crate) alpn_protocols: Vec<String>,
+}
+
+/// A TLS failure, carrying the Node.js style `code` reported to JS.
+/// Wrapped in an `io::Error` so it travels through the socket's error path.
+#[derive(Debug)]
+pub(crate) struct TlsError {
+    pub(crate) code: &'static str,
+    message: String,
+}
+
+impl std::fmt::Display for TlsError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(&self.message)
+    }
+}
+
+impl std::error::Error for TlsError {}
+
+impl TlsError {
+    fn io(code: &'static str, message: String) -> std::io::Error {
+        std::io::Error::new(ErrorKind::InvalidData, TlsError { code, message })
+    }
+}
+
+/// Convert a rustls error into an I/O error with a Node.js style code
+fn tls_error(error: rustls::Error) -> std::io::Error {
+    let code = match &error {
+        rustls::Error::InvalidCertificate(cert_error) => match cert_error {
+            CertificateError::UnknownIssuer => "UNABLE_TO_GET_ISSUER_CERT_LOCALLY",
+            CertificateError::NotValidForName | CertificateError::NotValidForNameContext { .. } => {
+                "ERR_TLS_CERT_ALTNAME_INVALID"
+            }
+            CertificateError::Expired | CertificateError::ExpiredContext { .. } => {
+                "CERT_HAS_EXPIRED"
+            }
+            CertificateError::NotValidYet | CertificateError::NotValidYetContext { .. } => {
+                "CERT_NOT_YET_VALID"
+            }
+            CertificateError::Revoked => "CERT_REVOKED",
+            _ => "ERR_TLS_CERT_INVALID",
+        },
+        rustls::Error::NoApplicationProtocol => "ERR_SSL_NO_APPLICATION_PROTOCOL",
+        _ => "ERR_SSL_PROTOCOL_ERROR",
+    };
+    TlsError::io(code, error.to_string())
+}
+
+/// The Mozilla root store, built once and shared by all connections
+fn default_roots() -> Arc<RootCertStore> {
+    static ROOTS: OnceLock<Arc<RootCertStore>> = OnceLock::new();
+    ROOTS
+        .get_or_init(|| {
+            Arc::new(RootCertStore {
+                roots: webpki_roots::TLS_SERVER_ROOTS.to_vec(),
+            })
+        })
+        .clone()
+}
+
+/// A client-side TLS session for one socket
+pub(crate) struct TlsSession {
+    conn: ClientConnection,
+    server_name: String,
+    handshake_done: bool,
+    close_notify_sent: bool,
+}
+
+impl TlsSession {
+    /// Create a session for connecting to `host`. Fails on invalid options,
+    /// such as a malformed CA certificate or server name.
+    pub(crate) fn new(host: &str, options: TlsOptions) -> std::io::Result<Self> {
+        let roots = if options.ca_certs.is_empty() {
+            default_roots()
+        } else {
+            let mut store = RootCertStore::empty();
+            for pem in &options.ca_certs {
+                let mut found = false;
+                for cert in CertificateDer::pem_slice_iter(pem.as_bytes()) {
+                    let cert = cert.map_err(|e| {
+                        std::io::Error::new(
+                            ErrorKind::InvalidInput,
+                            format!("Invalid CA certificate: {}", e),
+                        )
+                    })?;
+                    store.add(cert).map_err(|e| {
+                        std::io::Error::new(
+                            ErrorKind::InvalidInput,
+                            format!("Invalid CA certificate: {}", e),
+                        )
+                    })?;
+                    found = true;
+                }
+                if !found {
+                    return Err(std::io::Error::new(
+                        ErrorKind::InvalidInput,
+                        "Invalid CA certificate: no PEM certificate found",
+                    ));
+                }
+            }
+            Arc::new(store)
+        };
+
+        let provider = Arc::new(rustls::crypto::ring::default_provider());
+        let mut config = ClientConfig::builder_with_provider(provider)
+            .with_safe_default_protocol_versions()
+            .map_err(tls_error)?
+            .with_root_certificates(roots)
+            .with_no_client_auth();
+        config.alpn_protocols = options
+            .alpn_protocols
+            .into_iter()
+            .map(String::into_bytes)
+            .collect();
+
+        let server_name = options.server_name.unwrap_or_else(|| host.to_string());
+        // IPv6 literals may arrive in URL form
+        let name = server_name.trim_start_matches('[').trim_end_matches(']');
+        let name = ServerName::try_from(name.to_string()).map_err(|_| {
+            std::io::Error::new(
+                ErrorKind::InvalidInput,
+                format!("Invalid server name: {}", server_name),
+            )
+        })?;
+        let conn = ClientConnection::new(Arc::new(config), name).map_err(tls_error)?;
+
+        Ok(Self {
+            conn,
+            server_name,
+            handshake_done: false,
+            close_notify_sent: false,
+        })
+    }
+
+    /// The name the certificate was verified against
+    pub(crate) fn server_name(&self) -> &str {
+        &self.server_name
+    }
+
+    /// True once the handshake has completed and the certificate was accepted
+    pub(crate) fn is_secure(&self) -> bool {
+        self.handshake_done
+    }
+
+    /// The protocol selected with ALPN, if any
+    pub(crate) fn alpn_protocol(&self) -> Option<String> {
+        self.conn
+            .alpn_protocol()
+            .map(|protocol| String::from_utf8_lossy(protocol).into_owned())
+    }
+
+    /// The negotiated TLS version, e.g. "TLSv1.3"
+    pub(crate) fn protocol(&self) -> Option<&'static str> {
+        match self.conn.protocol_version()? {
+            rustls::ProtocolVersion::TLSv1_2 => Some("TLSv1.2"),
+            rustls::ProtocolVersion::TLSv1_3 => Some("TLSv1.3"),
+            _ => None,
+        }
+    }
+
+    /// The negotiated cipher suite, e.g. "TLS13_AES_128_GCM_SHA256"
+    pub(crate) fn cipher(&self) -> Option<&'static str> {
+        self.conn
+            .negotiated_cipher_suite()
+            .and_then(|suite| suite.suite().as_str())
+    }
+
+    /// True if encrypted data is waiting to be written to the socket
+    pub(crate) fn wants_write(&self) -> bool {
+        self.conn.wants_write()
+    }
+
+    /// Queue a close_notify alert, sent by the next flush
+    pub(crate) fn close(&mut self) {
+        if !self.close_notify_sent {
+            self.close_notify_sent = true;
+            self.conn.send_close_notify();
+        }
+    }
+
+    /// Encrypt queued plaintext (once the handshake is done) and write TLS
+    /// records until the socket would block. Consumed plaintext is removed from `queue`.
+    pub(crate) fn flush(
+        &mut self,
+        stream: &mut TcpStream,
+        queue: &mut Vec<u8>,
+    ) -> std::io::Result<()> {
+        loop {
+            if self.handshake_done && !queue.is_empty() {
+                let accepted = self.conn.writer().write(queue)?;
+                queue.drain(..accepted);
+            }
+            if !self.conn.wants_write() {
+                return Ok(());
+            }
+            match self.conn.write_tls(stream) {
+                Ok(0) => return Err(ErrorKind::WriteZero.into()),
+                Ok(_) => {}
+                Err(e) if e.kind() == ErrorKind::WouldBlock => return Ok(()),
+                Err(e) if e.kind() == ErrorKind::Interrupted => {}
+                Err(e) => return Err(e),
+            }
+        }
+    }
+
+    /// Read TLS records from the socket and decrypt them, pushing `SecureConnect`
+    /// once the handshake completes and `Data` for decrypted plaintext.
+    /// Returns true when the peer has closed the connection.
+    pub(crate) fn read(
+        &mut self,
+        stream: &mut TcpStream,
+        buf: &mut [u8],
+        events: &mut SmallVec<[SocketEvent; 4]>,
+    ) -> std::io::Result<bool> {
+        for _ in 0..MAX_READS_PER_POLL {
+            let eof = match self.conn.read_tls(stream) {
+                Ok(0) => true,
+                Ok(_) => false,
+                Err(e) if e.kind() == ErrorKind::WouldBlock => return Ok(false),
+                Err(e) if e.kind() == ErrorKind::Interrupted => continue,
+                Err(e) => return Err(e),
+            };
+            if let Err(e) = self.conn.process_new_packets() {
+                // Try to tell the peer why we're giving up before the socket is closed
+                let _ = self.conn.write_tls(stream);
+                return Err(tls_error(e));
+            }
+            if !self.handshake_done && !self.conn.is_handshaking() {
+                self.handshake_done = true;
+                events.push(SocketEvent::SecureConnect);
+            }
+
+            loop {
+                match self.conn.reader().read(buf) {
+                    // A close_notify from the peer
+                    Ok(0) => return Ok(true),
+                    Ok(n) => events.push(SocketEvent::Data(buf[..n].to_vec())),
+                    Err(e) if e.kind() == ErrorKind::WouldBlock => break,
+                    // The peer closed the connection without a close_notify.
+                    // Treat it as a normal close, like most clients.
+                    Err(e) if e.kind() == ErrorKind::UnexpectedEof => return Ok(true),
+                    Err(e) => return Err(e),
+                }
+            }
+            if eof {
+                if self.conn.is_handshaking() {
+                    return Err(TlsError::io(
+                        "ECONNRESET",
+                        "Client network socket disconnected before secure TLS connection was established".to_string(),
+                    ));
+                }
+                return Ok(true);
+            }
+        }
+        Ok(false)
+    }
+}
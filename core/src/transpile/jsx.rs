@@ -0,0 +1,541 @@
+//! JSX transform: elements become calls to a factory function, as in
+//! `React.createElement(type, props, ...children)`. The line breaks inside
+//! an element are kept between the arguments, so the code after it stays
+//! on the same line.
+
+use super::Error;
+use super::lexer::{Lexer, TokenKind, is_ident_part, is_ident_start};
+
+/// Named character references, as in `&nbsp;`, for the most common
+/// characters. Others can be written as numeric references.
+const ENTITIES: [(&str, char); 36] = [
+    ("amp", '&'),
+    ("lt", '<'),
+    ("gt", '>'),
+    ("quot", '"'),
+    ("apos", '\''),
+    ("nbsp", '\u{a0}'),
+    ("copy", '©'),
+    ("reg", '®'),
+    ("trade", '™'),
+    ("hellip", '…'),
+    ("mdash", '—'),
+    ("ndash", '–'),
+    ("lsquo", '‘'),
+    ("rsquo", '’'),
+    ("ldquo", '“'),
+    ("rdquo", '”'),
+    ("laquo", '«'),
+    ("raquo", '»'),
+    ("bull", '•'),
+    ("middot", '·'),
+    ("times", '×'),
+    ("divide", '÷'),
+    ("deg", '°'),
+    ("plusmn", '±'),
+    ("larr", '←'),
+    ("rarr", '→'),
+    ("uarr", '↑'),
+    ("darr", '↓'),
+    ("euro", '€'),
+    ("pound", '£'),
+    ("yen", '¥'),
+    ("cent", '¢'),
+    ("sect", '§'),
+    ("para", '¶'),
+    ("shy", '\u{ad}'),
+    ("zwj", '\u{200d}'),
+];
+
+/// Transform the JSX in `src`. `@jsx` and `@jsxFrag` pragmas in the
+/// file's leading comments override `factory` and `fragment`.
+pub(crate) fn transform_jsx(src: &str, factory: &str, fragment: &str) -> Result<String, Error> {
+    let mut transform = JsxTransform {
+        src,
+        lexer: Lexer::new(src),
+        out: String::with_capacity(src.len()),
+        copied: 0,
+        factory: pragma(src, "@jsx").unwrap_or(factory),
+        fragment: pragma(src, "@jsxFrag").unwrap_or(fragment),
+    };
+    transform.transform_code(false)?;
+    transform.out.push_str(&src[transform.copied..]);
+    Ok(transform.out)
+}
+
+/// The value of a pragma comment, like `/** @jsx h */`, before any code
+fn pragma<'a>(src: &'a str, name: &str) -> Option<&'a str> {
+    let mut rest = src;
+    loop {
+        rest = rest.trim_start();
+        let comment = if let Some(block) = rest.strip_prefix("/*") {
+            let end = block.find("*/")?;
+            rest = &block[end + 2..];
+            &block[..end]
+        } else if let Some(line) = rest.strip_prefix("//") {
+            let end = line.find('\n').unwrap_or(line.len());
+            rest = &line[end..];
+            &line[..end]
+        } else {
+            return None;
+        };
+        let mut words = comment.split(|c: char| c.is_whitespace() || c == '*');
+        if words.any(|word| word == name) {
+            return words.find(|word| !word.is_empty());
+        }
+    }
+}
+
+struct JsxTransform<'a> {
+    src: &'a str,
+    lexer: Lexer<'a>,
+    out: String,
+    // How much of the source the output accounts for
+    copied: usize,
+    factory: &'a str,
+    fragment: &'a str,
+}
+
+impl<'a> JsxTransform<'a> {
+    /// Copy code to the output, transforming the elements in it. In an
+    /// expression container, stops at its closing `}` and returns its
+    /// position.
+    fn transform_code(&mut self, in_container: bool) -> Result<usize, Error> {
+        let mut depth = 0;
+        loop {
+            let at_expression_start = self.lexer.at_expression_start();
+            let token = self.lexer.next_token()?;
+            match (token.kind, &self.src[token.start..token.end]) {
+                (TokenKind::Eof, _) if in_container => {
+                    return Err(("Unterminated JSX expression".to_string(), token.start));
+                }
+                (TokenKind::Eof, _) => return Ok(token.start),
+                (TokenKind::Punct, "{") => depth += 1,
+                (TokenKind::Punct, "}") if depth == 0 && in_container => {
+                    self.copy_to(token.start);
+                    return Ok(token.start);
+                }
+                (TokenKind::Punct, "}") => depth -= 1,
+                (TokenKind::Punct, "<")
+                    if at_expression_start && self.starts_element(token.end) =>
+                {
+                    self.copy_to(token.start);
+                    let end = self.element(token.start)?;
+                    self.lexer.reset(end, true);
+                }
+                _ => {}
+            }
+        }
+    }
+
+    fn copy_to(&mut self, pos: usize) {
+        self.out.push_str(&self.src[self.copied..pos]);
+        self.copied = pos;
+    }
+
+    /// Account for the source up to `pos` with its line breaks only
+    fn sync(&mut self, pos: usize) {
+        let newlines = self.src[self.copied..pos].matches('\n').count();
+        self.out.extend(std::iter::repeat_n('\n', newlines));
+        self.copied = pos;
+    }
+
+    fn char_at(&self, pos: usize) -> Option<char> {
+        self.src[pos..].chars().next()
+    }
+
+    fn skip_whitespace(&self, mut pos: usize) -> usize {
+        while let Some(c) = self.char_at(pos) {
+            if !c.is_whitespace() {
+                break;
+            }
+            pos += c.len_utf8();
+        }
+        pos
+    }
+
+    /// Whether the `<` ending at `pos` starts an element. In TSX, `<T,>`
+    /// and `<T extends U>` start the type parameters of an arrow function.
+    fn starts_element(&self, pos: usize) -> bool {
+        match self.char_at(pos) {
+            Some('>') => true,
+            Some(c) if is_ident_start(c) => {
+                let (_, after) = self.read_name(pos);
+                let after = self.skip_whitespace(after);
+                let rest = &self.src[after..];
+                if rest.starts_with(',') {
+                    return false;
+                }
+                match rest.strip_prefix("extends") {
+                    Some(bound) => {
+                        !bound.starts_with(|c: char| c.is_whitespace())
+                            || matches!(bound.trim_start().chars().next(), Some('=' | '>' | '/'))
+                    }
+                    None => true,
+                }
+            }
+            _ => false,
+        }
+    }
+
+    /// Read a tag or attribute name: identifier characters and `-`, with
+    /// `.` or `:` separating parts
+    fn read_name(&self, start: usize) -> (&'a str, usize) {
+        let mut pos = start;
+        while let Some(c) = self.char_at(pos) {
+            if !(is_ident_part(c) || c == '-' || c == '.' || c == ':') {
+                break;
+            }
+            pos += c.len_utf8();
+        }
+        (&self.src[start..pos], pos)
+    }
+
+    fn expect_char(&self, pos: usize, expected: char) -> Result<usize, Error> {
+        let pos = self.skip_whitespace(pos);
+        if self.char_at(pos) == Some(expected) {
+            Ok(pos + 1)
+        } else {
+            Err((format!("Expected '{}' in JSX", expected), pos))
+        }
+    }
+
+    /// Transform the element starting with the `<` at `start`, returning
+    /// the position after it
+    fn element(&mut self, start: usize) -> Result<usize, Error> {
+        let mut pos = self.skip_whitespace(start + 1);
+        let (name, after) = self.read_name(pos);
+        pos = after;
+
+        self.sync(start);
+        self.out.push_str(self.factory);
+        self.out.push('(');
+        if name.is_empty() {
+            self.out.push_str(self.fragment);
+            self.out.push_str(", null");
+            pos = self.expect_char(pos, '>')?;
+        } else {
+            self.out.push_str(&tag_expression(name));
+            let (after, self_closing) = self.attributes(pos)?;
+            pos = after;
+            if self_closing {
+                self.sync(pos);
+                self.out.push(')');
+                return Ok(pos);
+            }
+        }
+
+        pos = self.children(pos)?;
+
+        // The closing tag
+        let (closing, after) = self.read_name(self.skip_whitespace(pos));
+        if closing != name {
+            let expected = if name.is_empty() { "</>" } else { name };
+            return Err((
+                format!("Expected a closing tag for '{}' in JSX", expected),
+                pos,
+            ));
+        }
+        let end = self.expect_char(after, '>')?;
+        self.sync(end);
+        self.out.push(')');
+        Ok(end)
+    }
+
+    /// Transform the attributes of an element into its props, returning
+    /// the position after its start tag, and whether it was self-closing
+    fn attributes(&mut self, mut pos: usize) -> Result<(usize, bool), Error> {
+        let mut has_props = false;
+        loop {
+            pos = self.skip_whitespace(pos);
+            let rest = &self.src[pos..];
+            if rest.starts_with("/>") || rest.starts_with('>') {
+                self.out.push_str(if has_props { " }" } else { ", null" });
+                return Ok(if rest.starts_with('>') {
+                    (pos + 1, false)
+                } else {
+                    (pos + 2, true)
+                });
+            }
+
+            self.sync(pos);
+            self.out.push_str(if has_props { ", " } else { ", { " });
+            has_props = true;
+
+            if rest.starts_with('{') {
+                // A spread: `{...props}`
+                let inner = self.skip_whitespace(pos + 1);
+                if !self.src[inner..].starts_with("...") {
+                    return Err(("Expected '...' in JSX attribute".to_string(), inner));
+                }
+                pos = self.expression_container(pos)?;
+                continue;
+            }
+
+            let (name, after) = self.read_name(pos);
+            if name.is_empty() {
+                return Err(("Unexpected character in JSX element".to_string(), pos));
+            }
+            self.out.push_str(&property_key(name));
+            self.out.push_str(": ");
+            let value = self.skip_whitespace(after);
+            if !self.src[value..].starts_with('=') {
+                self.out.push_str("true");
+                pos = after;
+                continue;
+            }
+            let value = self.skip_whitespace(value + 1);
+            pos = match self.char_at(value) {
+                Some(quote @ ('"' | '\'')) => {
+                    let Some(len) = self.src[value + 1..].find(quote) else {
+                        return Err(("Unterminated string in JSX".to_string(), value));
+                    };
+                    let text = &self.src[value + 1..value + 1 + len];
+                    self.sync(value);
+                    self.out.push_str(&string_literal(&decode_entities(text)));
+                    value + len + 2
+                }
+                Some('{') => self.expression_container(value)?,
+                Some('<') => {
+                    self.sync(value);
+                    self.element(value)?
+                }
+                _ => return Err(("Expected a JSX attribute value".to_string(), value)),
+            };
+        }
+    }
+
+    /// Transform the children of an element up to its closing tag,
+    /// returning the position after its `</`
+    fn children(&mut self, mut pos: usize) -> Result<usize, Error> {
+        loop {
+            let Some(c) = self.char_at(pos) else {
+                return Err(("Unterminated JSX element".to_string(), pos));
+            };
+            match c {
+                '<' => {
+                    let after = self.skip_whitespace(pos + 1);
+                    if self.src[after..].starts_with('/') {
+                        return Ok(after + 1);
+                    }
+                    self.sync(pos);
+                    self.out.push_str(", ");
+                    pos = self.element(pos)?;
+                }
+                '{' => {
+                    let inner = &self.src[pos + 1..];
+                    let first = Lexer::new(inner).next_token()?;
+                    if first.kind == TokenKind::Punct && &inner[first.start..first.end] == "}" {
+                        // An empty expression, or a comment
+                        pos += 1 + first.end;
+                        continue;
+                    }
+                    self.sync(pos);
+                    self.out.push_str(", ");
+                    pos = self.expression_container(pos)?;
+                }
+                _ => {
+                    let end = self.src[pos..]
+                        .find(['<', '{'])
+                        .map_or(self.src.len(), |i| pos + i);
+                    let text = clean_text(&self.src[pos..end]);
+                    if !text.is_empty() {
+                        self.sync(pos);
+                        self.out.push_str(", ");
+                        self.out.push_str(&string_literal(&decode_entities(&text)));
+                    }
+                    pos = end;
+                }
+            }
+        }
+    }
+
+    /// Copy the expression in the `{ }` at `pos` to the output, returning
+    /// the position after it
+    fn expression_container(&mut self, pos: usize) -> Result<usize, Error> {
+        self.sync(pos);
+        // Read the `{` with the lexer, so it matches the `}`
+        self.lexer.reset(pos, false);
+        self.lexer.next_token()?;
+        self.copied = pos + 1;
+        let close = self.transform_code(true)?;
+        self.copied = close + 1;
+        Ok(close + 1)
+    }
+}
+
+/// The first argument to the factory: a string for intrinsic elements
+/// like `div`, otherwise the component's name or member expression
+fn tag_expression(name: &str) -> String {
+    let intrinsic = name.starts_with(|c: char| c.is_ascii_lowercase()) && !name.contains('.')
+        || name.contains('-')
+        || name.contains(':');
+    if intrinsic {
+        string_literal(name)
+    } else {
+        name.to_string()
+    }
+}
+
+fn property_key(name: &str) -> String {
+    let is_identifier = name.starts_with(is_ident_start) && name.chars().all(is_ident_part);
+    if is_identifier {
+        name.to_string()
+    } else {
+        string_literal(name)
+    }
+}
+
+/// The text of a JSX child with its whitespace collapsed like React does:
+/// lines are trimmed, empty lines are dropped, and the rest are joined with
+/// a space
+fn clean_text(text: &str) -> String {
+    let lines: Vec<&str> = text.split('\n').collect();
+    let last_non_empty = lines
+        .iter()
+        .rposition(|line| line.contains(|c: char| !c.is_whitespace()));
+    let mut cleaned = String::new();
+    for (i, line) in lines.iter().enumerate() {
+        let mut line = line.trim_end_matches('\r');
+        if i > 0 {
+            line = line.trim_start();
+        }
+        if i < lines.len() - 1 {
+            line = line.trim_end();
+        }
+        if !line.is_empty() {
+            cleaned.push_str(line);
+            if Some(i) != last_non_empty {
+                cleaned.push(' ');
+            }
+        }
+    }
+    cleaned
+}
+
+/// Replace the character references in JSX text, like `&amp;` and `&#169;`
+fn decode_entities(text: &str) -> String {
+    let mut decoded = String::with_capacity(text.len());
+    let mut rest = text;
+    while let Some(amp) = rest.find('&') {
+        decoded.push_str(&rest[..amp]);
+        rest = &rest[amp..];
+        let reference = rest[1..].find(';').map(|end| &rest[1..end + 1]);
+        let c = reference.and_then(|name| {
+            if let Some(hex) = name.strip_prefix("#x").or_else(|| name.strip_prefix("#X")) {
+                u32::from_str_radix(hex, 16).ok().and_then(char::from_u32)
+            } else if let Some(decimal) = name.strip_prefix('#') {
+                decimal.parse().ok().and_then(char::from_u32)
+            } else {
+                ENTITIES
+                    .iter()
+                    .find(|(entity, _)| *entity == name)
+                    .map(|(_, c)| *c)
+            }
+        });
+        match (c, reference) {
+            (Some(c), Some(reference)) => {
+                decoded.push(c);
+                rest = &rest[reference.len() + 2..];
+            }
+            _ => {
+                decoded.push('&');
+                rest = &rest[1..];
+            }
+        }
+    }
+    decoded.push_str(rest);
+    decoded
+}
+
+/// A double-quoted JavaScript string literal
+fn string_literal(value: &str) -> String {
+    let mut literal = String::with_capacity(value.len() + 2);
+    literal.push('"');
+    for c in value.chars() {
+        match c {
+            '"' => literal.push_str("\\\""),
+            '\\' => literal.push_str("\\\\"),
+            '\n' => literal.push_str("\\n"),
+            '\r' => literal.push_str("\\r"),
+            '\u{2028}' => literal.push_str("\\u2028"),
+            '\u{2029}' => literal.push_str("\\u2029"),
+            c if (c as u32) < 0x20 => literal.push_str(&format!("\\u{:04x}", c as u32)),
+            c => literal.push(c),
+        }
+    }
+    literal.push('"');
+    literal
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn transform(src: &str) -> String {
+        transform_jsx(src, "h", "Fragment").unwrap()
+    }
+
+    #[test]
+    fn test_elements_and_props() {
+        assert_eq!(
+            transform(r#"const a = <div id="x" hidden {...rest} n={1 + 2} />;"#),
+            r#"const a = h("div", { id: "x", hidden: true, ...rest, n: 1 + 2 });"#
+        );
+        assert_eq!(
+            transform("x = <Foo.Bar data-x='y'>hi</Foo.Bar>"),
+            r#"x = h(Foo.Bar, { "data-x": "y" }, "hi")"#
+        );
+        assert_eq!(
+            transform("x = <><A /></>"),
+            "x = h(Fragment, null, h(A, null))"
+        );
+    }
+
+    #[test]
+    fn test_children_keep_lines() {
+        let src = "const a = (\n  <ul>\n    {items.map(i => <li key={i}>{i}</li>)}\n    a &amp; b\n    {/* comment */}\n  </ul>\n);\nafter();";
+        let out = transform(src);
+        assert_eq!(out.lines().count(), src.lines().count());
+        assert_eq!(out.lines().last(), Some("after();"));
+        assert!(out.contains(r#"h("ul", null"#), "{out}");
+        assert!(
+            out.contains(r#"items.map(i => h("li", { key: i }, i))"#),
+            "{out}"
+        );
+        assert!(out.contains(r#""a & b""#), "{out}");
+    }
+
+    #[test]
+    fn test_not_jsx() {
+        let src = "if (a < b && c > d) x = y < z;\nconst f = <T,>(x: T) => x;";
+        assert_eq!(transform(src), src);
+        let src = "const s = `<div>${'<b>'}</div>`; const r = /<a>/;";
+        assert_eq!(transform(src), src);
+    }
+
+    #[test]
+    fn test_pragmas() {
+        let out = transform_jsx(
+            "/** @jsx preact.h\n * @jsxFrag preact.Fragment */\n<><a/></>",
+            "h",
+            "F",
+        )
+        .unwrap();
+        assert!(out.ends_with(r#"preact.h(preact.Fragment, null, preact.h("a", null))"#));
+    }
+
+    #[test]
+    fn test_clean_text() {
+        assert_eq!(clean_text("  a  "), "  a  ");
+        assert_eq!(clean_text("\n    a\n    b  \n  "), "a b");
+        assert_eq!(clean_text("\n   \n"), "");
+        assert_eq!(decode_entities("&lt;&#65;&#x42;&bogus;&"), "<AB&bogus;&");
+    }
+
+    #[test]
+    fn test_mismatched_closing_tag() {
+        assert!(transform_jsx("<a></b>", "h", "F").is_err());
+        assert!(transform_jsx("<a>", "h", "F").is_err());
+    }
+}
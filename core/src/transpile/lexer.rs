@@ -0,0 +1,438 @@
+//! A JavaScript tokenizer shared by the type stripper and the JSX transform.
+//!
+//! It only knows as much of the grammar as tokenizing needs: whether a `/`
+//! starts a regular expression is decided by the previous token, and
+//! template literals are split at their `${ }` substitutions. `>` is always
+//! a token of its own, so `>>` can close two lists of type arguments.
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum TokenKind {
+    /// Identifiers, keywords and `#private` names
+    Ident,
+    Punct,
+    Number,
+    String,
+    Regex,
+    /// A template literal without substitutions
+    Template,
+    /// The start of a template literal, up to its first `${`
+    TemplateHead,
+    /// The part of a template literal between two substitutions
+    TemplateMiddle,
+    /// The end of a template literal, after its last substitution
+    TemplateTail,
+    Eof,
+}
+
+#[derive(Debug, Clone, Copy)]
+pub(crate) struct Token {
+    pub(crate) kind: TokenKind,
+    pub(crate) start: usize,
+    pub(crate) end: usize,
+    /// Whether a line break comes between this token and the previous one
+    pub(crate) newline_before: bool,
+}
+
+/// Keywords after which a `/` starts a regular expression
+const KEYWORDS_BEFORE_EXPRESSION: [&str; 17] = [
+    "return",
+    "typeof",
+    "instanceof",
+    "in",
+    "of",
+    "new",
+    "delete",
+    "void",
+    "throw",
+    "case",
+    "do",
+    "else",
+    "yield",
+    "await",
+    "extends",
+    "as",
+    "satisfies",
+];
+
+/// Punctuators, longest first. `>` is never combined with what follows
+/// it, and `?.` is read separately.
+const PUNCTUATORS: &[&str] = &[
+    "...", "===", "!==", "**=", "<<=", "&&=", "||=", "??=", "=>", "==", "!=", "<=", "&&", "||",
+    "??", "++", "--", "+=", "-=", "*=", "/=", "%=", "&=", "|=", "^=", "**", "<<", "{", "}", "(",
+    ")", "[", "]", ";", ",", "<", ">", "+", "-", "*", "/", "%", "&", "|", "^", "!", "~", "?", ":",
+    "=", ".", "@",
+];
+
+pub(crate) fn is_ident_start(c: char) -> bool {
+    c.is_ascii_alphabetic() || c == '_' || c == '$' || (!c.is_ascii() && c.is_alphabetic())
+}
+
+pub(crate) fn is_ident_part(c: char) -> bool {
+    is_ident_start(c) || c.is_ascii_digit() || c == '\u{200c}' || c == '\u{200d}'
+}
+
+fn is_line_terminator(c: char) -> bool {
+    matches!(c, '\n' | '\r' | '\u{2028}' | '\u{2029}')
+}
+
+pub(crate) struct Lexer<'a> {
+    src: &'a str,
+    pos: usize,
+    // Open braces, to find the `}` that ends a template substitution
+    brace_depth: usize,
+    // brace_depth at each open template substitution
+    templates: Vec<usize>,
+    regex_allowed: bool,
+}
+
+impl<'a> Lexer<'a> {
+    pub(crate) fn new(src: &'a str) -> Self {
+        let mut lexer = Lexer {
+            src,
+            pos: 0,
+            brace_depth: 0,
+            templates: Vec::new(),
+            regex_allowed: true,
+        };
+        // A hashbang line is a comment
+        if src.starts_with("#!") {
+            lexer.pos = src.find('\n').unwrap_or(src.len());
+        }
+        lexer
+    }
+
+    /// Continue from `pos`, after something the lexer didn't read, such as
+    /// a JSX element. `expression_ended` is whether that was an expression,
+    /// so that a `/` after it is a division.
+    pub(crate) fn reset(&mut self, pos: usize, expression_ended: bool) {
+        self.pos = pos;
+        self.regex_allowed = !expression_ended;
+    }
+
+    /// Whether the next token is at the start of an expression, where a
+    /// `/` starts a regular expression and a `<` starts a JSX element
+    pub(crate) fn at_expression_start(&self) -> bool {
+        self.regex_allowed
+    }
+
+    /// Tokenize the whole source
+    pub(crate) fn tokenize(src: &'a str) -> Result<Vec<Token>, (String, usize)> {
+        let mut lexer = Lexer::new(src);
+        let mut tokens = Vec::new();
+        loop {
+            let token = lexer.next_token()?;
+            tokens.push(token);
+            if token.kind == TokenKind::Eof {
+                return Ok(tokens);
+            }
+        }
+    }
+
+    pub(crate) fn next_token(&mut self) -> Result<Token, (String, usize)> {
+        let newline_before = self.skip_trivia()?;
+        let start = self.pos;
+        let Some(c) = self.peek() else {
+            return Ok(Token {
+                kind: TokenKind::Eof,
+                start,
+                end: start,
+                newline_before,
+            });
+        };
+
+        let kind = if is_ident_start(c) || c == '#' || c == '\\' {
+            self.bump();
+            while let Some(c) = self.peek() {
+                if !is_ident_part(c) && c != '\\' {
+                    break;
+                }
+                self.bump();
+            }
+            let word = &self.src[start..self.pos];
+            self.regex_allowed = KEYWORDS_BEFORE_EXPRESSION.contains(&word);
+            TokenKind::Ident
+        } else if c.is_ascii_digit()
+            || (c == '.' && self.peek_at(1).is_some_and(|c| c.is_ascii_digit()))
+        {
+            self.read_number();
+            self.regex_allowed = false;
+            TokenKind::Number
+        } else if c == '"' || c == '\'' {
+            self.read_string(c)?;
+            self.regex_allowed = false;
+            TokenKind::String
+        } else if c == '`' {
+            self.bump();
+            self.read_template(start)?
+        } else if c == '}' && self.templates.last() == Some(&self.brace_depth) {
+            self.templates.pop();
+            self.bump();
+            match self.read_template(start)? {
+                TokenKind::TemplateHead => TokenKind::TemplateMiddle,
+                _ => TokenKind::TemplateTail,
+            }
+        } else if c == '/' && self.regex_allowed {
+            self.read_regex(start)?;
+            self.regex_allowed = false;
+            TokenKind::Regex
+        } else {
+            self.read_punctuator(start)?;
+            let punct = &self.src[start..self.pos];
+            match punct {
+                "{" => self.brace_depth += 1,
+                "}" => self.brace_depth = self.brace_depth.saturating_sub(1),
+                _ => {}
+            }
+            self.regex_allowed = !matches!(punct, ")" | "]" | "}" | "++" | "--");
+            TokenKind::Punct
+        };
+
+        Ok(Token {
+            kind,
+            start,
+            end: self.pos,
+            newline_before,
+        })
+    }
+
+    fn peek(&self) -> Option<char> {
+        self.src[self.pos..].chars().next()
+    }
+
+    fn peek_at(&self, n: usize) -> Option<char> {
+        self.src[self.pos..].chars().nth(n)
+    }
+
+    fn bump(&mut self) {
+        if let Some(c) = self.peek() {
+            self.pos += c.len_utf8();
+        }
+    }
+
+    /// Skip whitespace and comments, returning whether they held a line break
+    fn skip_trivia(&mut self) -> Result<bool, (String, usize)> {
+        let mut newline = false;
+        while let Some(c) = self.peek() {
+            if is_line_terminator(c) {
+                newline = true;
+                self.bump();
+            } else if c.is_whitespace() || c == '\u{feff}' {
+                self.bump();
+            } else if self.src[self.pos..].starts_with("//") {
+                while self.peek().is_some_and(|c| !is_line_terminator(c)) {
+                    self.bump();
+                }
+            } else if self.src[self.pos..].starts_with("/*") {
+                let start = self.pos;
+                let Some(end) = self.src[self.pos + 2..].find("*/") else {
+                    return Err(("Unterminated comment".to_string(), start));
+                };
+                let comment = &self.src[self.pos..self.pos + 2 + end];
+                newline |= comment.chars().any(is_line_terminator);
+                self.pos += end + 4;
+            } else {
+                break;
+            }
+        }
+        Ok(newline)
+    }
+
+    fn read_number(&mut self) {
+        let rest = &self.src[self.pos..];
+        // Hex literals have no exponent, so `0xE+1` is an addition
+        let is_hex = rest.starts_with("0x") || rest.starts_with("0X");
+        let mut prev = '\0';
+        while let Some(c) = self.peek() {
+            let exponent_sign = (c == '+' || c == '-') && matches!(prev, 'e' | 'E') && !is_hex;
+            if !(c.is_ascii_alphanumeric() || c == '.' || c == '_' || exponent_sign) {
+                break;
+            }
+            prev = c;
+            self.bump();
+        }
+    }
+
+    /// Skip an escape sequence, including a line continuation
+    fn skip_escape(&mut self) {
+        self.bump();
+        if self.src[self.pos..].starts_with("\r\n") {
+            self.pos += 2;
+        } else {
+            self.bump();
+        }
+    }
+
+    fn read_string(&mut self, quote: char) -> Result<(), (String, usize)> {
+        let start = self.pos;
+        self.bump();
+        loop {
+            match self.peek() {
+                None => return Err(("Unterminated string literal".to_string(), start)),
+                Some(c) if c == quote => {
+                    self.bump();
+                    return Ok(());
+                }
+                Some('\\') => self.skip_escape(),
+                Some('\n' | '\r') => {
+                    return Err(("Unterminated string literal".to_string(), start));
+                }
+                Some(_) => self.bump(),
+            }
+        }
+    }
+
+    /// Read the rest of a template literal, after a '`' or the `}` that
+    /// ends a substitution
+    fn read_template(&mut self, start: usize) -> Result<TokenKind, (String, usize)> {
+        loop {
+            match self.peek() {
+                None => return Err(("Unterminated template literal".to_string(), start)),
+                Some('`') => {
+                    self.bump();
+                    self.regex_allowed = false;
+                    return Ok(TokenKind::Template);
+                }
+                Some('\\') => self.skip_escape(),
+                Some('$') if self.peek_at(1) == Some('{') => {
+                    self.pos += 2;
+                    self.templates.push(self.brace_depth);
+                    self.regex_allowed = true;
+                    return Ok(TokenKind::TemplateHead);
+                }
+                Some(_) => self.bump(),
+            }
+        }
+    }
+
+    fn read_regex(&mut self, start: usize) -> Result<(), (String, usize)> {
+        self.bump();
+        let mut in_class = false;
+        loop {
+            match self.peek() {
+                None | Some('\n' | '\r') => {
+                    return Err(("Unterminated regular expression literal".to_string(), start));
+                }
+                Some('\\') => self.skip_escape(),
+                Some('[') => {
+                    in_class = true;
+                    self.bump();
+                }
+                Some(']') => {
+                    in_class = false;
+                    self.bump();
+                }
+                Some('/') if !in_class => {
+                    self.bump();
+                    break;
+                }
+                Some(_) => self.bump(),
+            }
+        }
+        // Flags
+        while self.peek().is_some_and(is_ident_part) {
+            self.bump();
+        }
+        Ok(())
+    }
+
+    fn read_punctuator(&mut self, start: usize) -> Result<(), (String, usize)> {
+        let rest = &self.src[self.pos..];
+        // `?.` followed by a digit is a conditional and a number
+        if rest.starts_with("?.") && !rest[2..].starts_with(|c: char| c.is_ascii_digit()) {
+            self.pos += 2;
+            return Ok(());
+        }
+        match PUNCTUATORS.iter().find(|punct| rest.starts_with(**punct)) {
+            Some(punct) => {
+                self.pos += punct.len();
+                Ok(())
+            }
+            None => Err((
+                format!("Unexpected character '{}'", self.peek().unwrap_or(' ')),
+                start,
+            )),
+        }
+    }
+}
+
+/// The 1-based line and column of a byte offset, counting columns in
+/// UTF-16 code units like V8 does
+pub(crate) fn line_column(src: &str, offset: usize) -> (usize, usize) {
+    let before = &src[..offset.min(src.len())];
+    let line_start = before.rfind('\n').map(|i| i + 1).unwrap_or(0);
+    let line = before.matches('\n').count() + 1;
+    let column = before[line_start..].encode_utf16().count() + 1;
+    (line, column)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn kinds_and_text(src: &str) -> Vec<(TokenKind, &str)> {
+        Lexer::tokenize(src)
+            .unwrap()
+            .into_iter()
+            .filter(|t| t.kind != TokenKind::Eof)
+            .map(|t| (t.kind, &src[t.start..t.end]))
+            .collect()
+    }
+
+    #[test]
+    fn test_regex_or_division() {
+        assert_eq!(
+            kinds_and_text("a / b / c"),
+            [
+                (TokenKind::Ident, "a"),
+                (TokenKind::Punct, "/"),
+                (TokenKind::Ident, "b"),
+                (TokenKind::Punct, "/"),
+                (TokenKind::Ident, "c"),
+            ]
+        );
+        assert_eq!(
+            kinds_and_text("return /a[/]b/g"),
+            [(TokenKind::Ident, "return"), (TokenKind::Regex, "/a[/]b/g")]
+        );
+    }
+
+    #[test]
+    fn test_template_substitutions() {
+        assert_eq!(
+            kinds_and_text("`a${ {b: 1}.b }c${d}e`"),
+            [
+                (TokenKind::TemplateHead, "`a${"),
+                (TokenKind::Punct, "{"),
+                (TokenKind::Ident, "b"),
+                (TokenKind::Punct, ":"),
+                (TokenKind::Number, "1"),
+                (TokenKind::Punct, "}"),
+                (TokenKind::Punct, "."),
+                (TokenKind::Ident, "b"),
+                (TokenKind::TemplateMiddle, "}c${"),
+                (TokenKind::Ident, "d"),
+                (TokenKind::TemplateTail, "}e`"),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_greater_than_is_never_combined() {
+        let tokens = kinds_and_text("a >>= b => c");
+        let text: Vec<&str> = tokens.iter().map(|(_, text)| *text).collect();
+        assert_eq!(text, ["a", ">", ">", "=", "b", "=>", "c"]);
+    }
+
+    #[test]
+    fn test_newline_before_and_comments() {
+        let tokens = Lexer::tokenize("a /* x\n */ b // c\nd").unwrap();
+        let newlines: Vec<bool> = tokens.iter().map(|t| t.newline_before).collect();
+        assert_eq!(newlines, [false, true, true, false]);
+    }
+
+    #[test]
+    fn test_line_column() {
+        assert_eq!(line_column("ab\ncd", 4), (2, 2));
+        assert_eq!(line_column("é😀x", "é😀".len()), (1, 4));
+    }
+}
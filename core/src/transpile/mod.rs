@@ -0,0 +1,117 @@
+//! TypeScript and JSX support. Modules with a `.ts`, `.mts`, `.tsx` or
+//! `.jsx` extension are transformed to JavaScript before they're compiled:
+//! types are replaced with whitespace, and JSX elements with calls to a
+//! factory function. Neither moves code to another line, and type
+//! stripping keeps every column, so errors point into the original source.
+
+mod jsx;
+mod lexer;
+mod strip;
+
+/// A message and the byte offset in the source it applies to
+pub(crate) type Error = (String, usize);
+
+/// Extensions tried, in order, for an import without one from a TypeScript
+/// or JSX module
+pub(crate) const RESOLVE_EXTENSIONS: [&str; 6] = ["ts", "tsx", "mts", "js", "jsx", "mjs"];
+
+/// The functions JSX elements and fragments are transformed into calls of
+#[derive(Debug, Clone)]
+pub(crate) struct JsxFactory {
+    factory: String,
+    fragment: String,
+}
+
+impl JsxFactory {
+    /// The factories to use, defaulting to React's
+    pub(crate) fn new(factory: Option<String>, fragment: Option<String>) -> Self {
+        Self {
+            factory: factory.unwrap_or_else(|| "React.createElement".to_string()),
+            fragment: fragment.unwrap_or_else(|| "React.Fragment".to_string()),
+        }
+    }
+}
+
+impl Default for JsxFactory {
+    fn default() -> Self {
+        Self::new(None, None)
+    }
+}
+
+/// Whether the module at `path` is TypeScript or JSX, which is transpiled
+/// before it's compiled
+pub(crate) fn is_transpiled(path: &str) -> bool {
+    syntax(path).is_some()
+}
+
+/// Whether a module at `path` has types and JSX, by its extension
+fn syntax(path: &str) -> Option<(bool, bool)> {
+    let extension = path.rsplit_once('.')?.1;
+    match extension {
+        "ts" | "mts" => Some((true, false)),
+        "tsx" => Some((true, true)),
+        "jsx" => Some((false, true)),
+        _ => None,
+    }
+}
+
+/// Transform the TypeScript or JSX source of the module at `path` to
+/// JavaScript. JavaScript modules are returned unchanged.
+pub(crate) fn transpile(path: &str, source: &str, jsx: &JsxFactory) -> Result<String, String> {
+    let Some((types, elements)) = syntax(path) else {
+        return Ok(source.to_string());
+    };
+    let error = |code: &str, (message, offset): Error| {
+        let (line, column) = lexer::line_column(code, offset);
+        format!("{} ({}:{}:{})", message, path, line, column)
+    };
+
+    let mut code = source.to_string();
+    if elements {
+        code =
+            jsx::transform_jsx(&code, &jsx.factory, &jsx.fragment).map_err(|e| error(&code, e))?;
+    }
+    if types {
+        code = strip::strip_types(&code).map_err(|e| error(&code, e))?;
+    }
+    Ok(code)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_transpile_by_extension() {
+        let jsx = JsxFactory::default();
+        let source = "const a: number = 1;";
+        assert_eq!(transpile("/a.js", source, &jsx).unwrap(), source);
+        assert_eq!(
+            transpile("/a.mts", source, &jsx).unwrap(),
+            "const a         = 1;"
+        );
+
+        let source = "const e = <App title={t as string} />;";
+        assert_eq!(
+            transpile("/a.tsx", source, &jsx).unwrap(),
+            "const e = React.createElement(App, { title: t           });"
+        );
+        // JSX modules keep their types, which V8 then reports
+        assert_eq!(
+            transpile("/a.jsx", "let x: T = <b />;", &jsx).unwrap(),
+            "let x: T = React.createElement(\"b\", null);"
+        );
+    }
+
+    #[test]
+    fn test_errors_have_positions() {
+        let err = transpile(
+            "/src/a.ts",
+            "let a = 1;\n  enum E {}",
+            &JsxFactory::default(),
+        )
+        .unwrap_err();
+        assert!(err.starts_with("Enums aren't supported"), "{err}");
+        assert!(err.ends_with("(/src/a.ts:2:3)"), "{err}");
+    }
+}
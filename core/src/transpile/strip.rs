@@ -0,0 +1,1433 @@
+//! Type stripping: TypeScript syntax is replaced with whitespace, so the
+//! JavaScript that's left keeps the line and column of every token.
+//!
+//! Only syntax that can be erased is supported. Enums, namespaces,
+//! parameter properties and `import x = require()` generate code, so they
+//! are reported as errors. Imports of types must use `import type` or
+//! `type` specifiers, since nothing else tells them apart from values.
+
+use super::Error;
+use super::lexer::{Lexer, Token, TokenKind};
+
+type Result<T> = std::result::Result<T, Error>;
+
+/// Modifiers that only exist in TypeScript
+const TS_MODIFIERS: [&str; 7] = [
+    "public",
+    "private",
+    "protected",
+    "readonly",
+    "override",
+    "declare",
+    "abstract",
+];
+
+/// Modifiers that are kept
+const JS_MODIFIERS: [&str; 5] = ["static", "async", "get", "set", "accessor"];
+
+/// Keywords after which an operand is expected
+const OPERATOR_KEYWORDS: [&str; 15] = [
+    "typeof",
+    "instanceof",
+    "in",
+    "of",
+    "new",
+    "delete",
+    "void",
+    "throw",
+    "return",
+    "case",
+    "yield",
+    "await",
+    "extends",
+    "else",
+    "async",
+];
+
+const NOT_ERASABLE: &str = "only TypeScript syntax that can be erased is supported";
+
+/// Strip the types from TypeScript source
+pub(crate) fn strip_types(src: &str) -> Result<String> {
+    let tokens = Lexer::tokenize(src)?;
+    let mut stripper = Stripper {
+        src,
+        tokens,
+        i: 0,
+        erased: Vec::new(),
+    };
+    stripper.parse_statements()?;
+    let token = stripper.peek();
+    if token.kind != TokenKind::Eof {
+        return Err((
+            format!("Unexpected '{}'", stripper.text(token)),
+            token.start,
+        ));
+    }
+    Ok(blank(src, stripper.erased))
+}
+
+/// Replace the `ranges` of `src` with spaces, keeping line breaks. Each
+/// character becomes as many spaces as its UTF-16 length, which is how V8
+/// counts columns.
+fn blank(src: &str, mut ranges: Vec<(usize, usize)>) -> String {
+    ranges.sort_unstable();
+    let mut out = String::with_capacity(src.len());
+    let mut copied = 0;
+    for (start, end) in ranges {
+        let start = start.max(copied);
+        if end <= start {
+            continue;
+        }
+        out.push_str(&src[copied..start]);
+        for c in src[start..end].chars() {
+            if matches!(c, '\n' | '\r' | '\u{2028}' | '\u{2029}') {
+                out.push(c);
+            } else {
+                out.extend(std::iter::repeat_n(' ', c.len_utf16()));
+            }
+        }
+        copied = end;
+    }
+    out.push_str(&src[copied..]);
+    out
+}
+
+struct Stripper<'a> {
+    src: &'a str,
+    tokens: Vec<Token>,
+    // Index of the current token
+    i: usize,
+    // Byte ranges to replace with whitespace
+    erased: Vec<(usize, usize)>,
+}
+
+impl<'a> Stripper<'a> {
+    fn peek(&self) -> Token {
+        self.peek_at(0)
+    }
+
+    fn peek_at(&self, n: usize) -> Token {
+        let last = self.tokens.len() - 1;
+        self.tokens[(self.i + n).min(last)]
+    }
+
+    fn text(&self, token: Token) -> &'a str {
+        &self.src[token.start..token.end]
+    }
+
+    /// Whether the current token is the punctuator or word `text`
+    fn is(&self, text: &str) -> bool {
+        self.is_at(0, text)
+    }
+
+    fn is_at(&self, n: usize, text: &str) -> bool {
+        let token = self.peek_at(n);
+        matches!(token.kind, TokenKind::Punct | TokenKind::Ident) && self.text(token) == text
+    }
+
+    /// Whether the token after the current one is a word on the same line
+    fn next_is_word(&self) -> bool {
+        let next = self.peek_at(1);
+        next.kind == TokenKind::Ident && !next.newline_before
+    }
+
+    fn advance(&mut self) -> Token {
+        let token = self.peek();
+        if token.kind != TokenKind::Eof {
+            self.i += 1;
+        }
+        token
+    }
+
+    fn eat(&mut self, text: &str) -> bool {
+        let found = self.is(text);
+        if found {
+            self.i += 1;
+        }
+        found
+    }
+
+    fn expect(&mut self, text: &str) -> Result<()> {
+        if self.eat(text) {
+            Ok(())
+        } else {
+            Err(self.unexpected(&format!("'{}'", text)))
+        }
+    }
+
+    fn expect_ident(&mut self) -> Result<()> {
+        if self.peek().kind == TokenKind::Ident {
+            self.i += 1;
+            Ok(())
+        } else {
+            Err(self.unexpected("an identifier"))
+        }
+    }
+
+    fn unexpected(&self, expected: &str) -> Error {
+        let token = self.peek();
+        let found = match token.kind {
+            TokenKind::Eof => "end of input".to_string(),
+            _ => format!("'{}'", self.text(token)),
+        };
+        (
+            format!("Expected {} but found {}", expected, found),
+            token.start,
+        )
+    }
+
+    fn not_erasable(&self, what: &str) -> Error {
+        (
+            format!("{} aren't supported: {}", what, NOT_ERASABLE),
+            self.peek().start,
+        )
+    }
+
+    /// Erase the tokens from index `start` up to the current one
+    fn erase_since(&mut self, start: usize) {
+        if start < self.i {
+            let from = self.tokens[start].start;
+            let to = self.tokens[self.i - 1].end;
+            self.erased.push((from, to));
+        }
+    }
+
+    /// Erase the current token and move past it
+    fn erase_token(&mut self) {
+        let start = self.i;
+        self.advance();
+        self.erase_since(start);
+    }
+
+    /// Whether a token ended an operand, so that what follows it is an
+    /// operator rather than the start of an expression
+    fn ends_operand(&self, token: Token) -> bool {
+        match token.kind {
+            TokenKind::Ident => !OPERATOR_KEYWORDS.contains(&self.text(token)),
+            TokenKind::Punct => matches!(self.text(token), ")" | "]" | "}"),
+            TokenKind::Number
+            | TokenKind::String
+            | TokenKind::Regex
+            | TokenKind::Template
+            | TokenKind::TemplateTail => true,
+            _ => false,
+        }
+    }
+
+    /// Index of the token closing the bracket at index `open`
+    fn matching_close(&self, open: usize) -> Result<usize> {
+        let mut depth = 0isize;
+        for (i, token) in self.tokens.iter().enumerate().skip(open) {
+            match token.kind {
+                TokenKind::Punct => match self.text(*token) {
+                    "(" | "[" | "{" => depth += 1,
+                    ")" | "]" | "}" => depth -= 1,
+                    _ => {}
+                },
+                TokenKind::TemplateHead => depth += 1,
+                TokenKind::TemplateTail => depth -= 1,
+                TokenKind::Eof => break,
+                _ => {}
+            }
+            if depth == 0 {
+                return Ok(i);
+            }
+        }
+        Err(("Unclosed bracket".to_string(), self.tokens[open].start))
+    }
+
+    /// Move past the bracketed tokens starting at the current one
+    fn skip_balanced(&mut self) -> Result<()> {
+        self.i = self.matching_close(self.i)? + 1;
+        Ok(())
+    }
+
+    /// Move past a list of type parameters or arguments in angle brackets
+    fn skip_angle_brackets(&mut self) -> Result<()> {
+        let start = self.peek().start;
+        let mut depth = 0;
+        loop {
+            let token = self.peek();
+            match (token.kind, self.text(token)) {
+                (TokenKind::Eof, _) | (TokenKind::Punct, ";") => {
+                    return Err(("Unclosed '<'".to_string(), start));
+                }
+                (TokenKind::Punct, "<") => depth += 1,
+                (TokenKind::Punct, ">") => {
+                    depth -= 1;
+                    if depth == 0 {
+                        self.i += 1;
+                        return Ok(());
+                    }
+                }
+                (TokenKind::Punct, "(" | "[" | "{") | (TokenKind::TemplateHead, _) => {
+                    self.skip_balanced()?;
+                    continue;
+                }
+                _ => {}
+            }
+            self.i += 1;
+        }
+    }
+
+    /// Erase type parameters, if there are any
+    fn strip_type_parameters(&mut self) -> Result<()> {
+        if self.is("<") {
+            let start = self.i;
+            self.skip_angle_brackets()?;
+            self.erase_since(start);
+        }
+        Ok(())
+    }
+
+    /// Erase a `: Type` annotation, if there is one
+    fn strip_annotation(&mut self) -> Result<()> {
+        if self.is(":") {
+            let start = self.i;
+            self.i += 1;
+            self.parse_type()?;
+            self.erase_since(start);
+        }
+        Ok(())
+    }
+
+    // Statements
+
+    fn parse_statements(&mut self) -> Result<()> {
+        loop {
+            let token = self.peek();
+            match token.kind {
+                TokenKind::Eof | TokenKind::TemplateMiddle | TokenKind::TemplateTail => {
+                    return Ok(());
+                }
+                TokenKind::Punct if matches!(self.text(token), ")" | "]" | "}") => return Ok(()),
+                _ => self.parse_statement()?,
+            }
+        }
+    }
+
+    fn parse_block(&mut self) -> Result<()> {
+        self.expect("{")?;
+        self.parse_statements()?;
+        self.expect("}")
+    }
+
+    fn parse_statement(&mut self) -> Result<()> {
+        self.parse_statement_from(self.i)
+    }
+
+    /// Parse a statement, where `start` is the index of its first token,
+    /// which comes before the current one after `export`
+    fn parse_statement_from(&mut self, start: usize) -> Result<()> {
+        let token = self.peek();
+        let text = self.text(token).to_string();
+        if token.kind == TokenKind::Punct {
+            match text.as_str() {
+                "{" => return self.parse_block(),
+                ";" => {
+                    self.i += 1;
+                    return Ok(());
+                }
+                _ => {}
+            }
+        }
+        if token.kind == TokenKind::Ident && self.parse_keyword_statement(start, &text)? {
+            return Ok(());
+        }
+
+        self.parse_expression(&[])?;
+        self.eat(";");
+        Ok(())
+    }
+
+    /// Parse a statement that starts with the word `word`, returning false
+    /// if it's an expression statement
+    fn parse_keyword_statement(&mut self, start: usize, word: &str) -> Result<bool> {
+        let next = self.peek_at(1);
+        let next_text = self.text(next);
+        match word {
+            "import" if !matches!(next_text, "(" | ".") => self.parse_import(start)?,
+            "export" => self.parse_export(start)?,
+            "function" => self.parse_function(start, true)?,
+            "async" if next_text == "function" && !next.newline_before => {
+                self.i += 1;
+                self.parse_function(start, true)?;
+            }
+            "class" => self.parse_class()?,
+            "abstract" if next_text == "class" && !next.newline_before => {
+                self.erase_token();
+                self.parse_class()?;
+            }
+            "const" if next_text == "enum" => return Err(self.not_erasable("Enums")),
+            "let" | "const" | "var"
+                if next.kind == TokenKind::Ident || matches!(next_text, "{" | "[") =>
+            {
+                self.i += 1;
+                self.parse_var_declarations(&[])?;
+                self.eat(";");
+            }
+            "if" | "while" | "with" | "switch" => {
+                self.i += 1;
+                self.parse_parenthesized()?;
+                self.parse_statement()?;
+            }
+            "for" => {
+                self.i += 1;
+                self.eat("await");
+                self.expect("(")?;
+                if matches!(self.text(self.peek()), "let" | "const" | "var")
+                    && (self.next_is_word() || self.is_at(1, "{") || self.is_at(1, "["))
+                {
+                    self.i += 1;
+                    self.parse_var_declarations(&["of", "in"])?;
+                }
+                self.parse_until(")")?;
+                self.parse_statement()?;
+            }
+            "do" => {
+                self.i += 1;
+                self.parse_statement()?;
+                self.expect("while")?;
+                self.parse_parenthesized()?;
+                self.eat(";");
+            }
+            "else" | "try" | "finally" => {
+                self.i += 1;
+                self.parse_statement()?;
+            }
+            "catch" => {
+                self.i += 1;
+                if self.is("(") {
+                    self.parse_parameters()?;
+                }
+                self.parse_block()?;
+            }
+            "return" | "throw" => {
+                self.i += 1;
+                let token = self.peek();
+                if !token.newline_before && !matches!(self.text(token), ";" | "}") {
+                    self.parse_expression(&[])?;
+                }
+                self.eat(";");
+            }
+            "break" | "continue" => {
+                self.i += 1;
+                if self.peek().kind == TokenKind::Ident && !self.peek().newline_before {
+                    self.i += 1;
+                }
+                self.eat(";");
+            }
+            "case" => {
+                self.i += 1;
+                self.parse_expression(&[":"])?;
+                self.expect(":")?;
+            }
+            "default" if next_text == ":" => self.i += 2,
+            "interface" if self.next_is_word() => {
+                self.skip_interface()?;
+                self.erase_since(start);
+            }
+            "type" if self.next_is_word() && (self.is_at(2, "=") || self.is_at(2, "<")) => {
+                self.skip_type_alias()?;
+                self.erase_since(start);
+            }
+            "declare" if self.next_is_word() => {
+                self.skip_declaration()?;
+                self.erase_since(start);
+            }
+            "enum" if self.next_is_word() => return Err(self.not_erasable("Enums")),
+            "namespace" | "module"
+                if !next.newline_before
+                    && (next.kind == TokenKind::Ident || next.kind == TokenKind::String) =>
+            {
+                return Err(self.not_erasable("Namespaces"));
+            }
+            _ if next_text == ":" && next.kind == TokenKind::Punct => {
+                // A label
+                self.i += 2;
+            }
+            _ => return Ok(false),
+        }
+        Ok(true)
+    }
+
+    fn parse_import(&mut self, start: usize) -> Result<()> {
+        self.i += 1;
+        if self.is("type") {
+            let next = self.peek_at(1);
+            let type_only = match (next.kind, self.text(next)) {
+                (TokenKind::Punct, "{" | "*") => true,
+                // `import type from 'x'` imports a default export named type
+                (TokenKind::Ident, "from") => self.is_at(2, "from"),
+                (TokenKind::Ident, _) => true,
+                _ => false,
+            };
+            if type_only {
+                self.skip_module_clause()?;
+                self.erase_since(start);
+                return Ok(());
+            }
+        }
+        if self.peek().kind == TokenKind::Ident && self.is_at(1, "=") {
+            return Err(self.not_erasable("Import assignments"));
+        }
+        loop {
+            let token = self.peek();
+            match token.kind {
+                TokenKind::String => break,
+                TokenKind::Eof => return Err(self.unexpected("a module specifier")),
+                TokenKind::Punct if self.text(token) == "{" => self.parse_specifiers()?,
+                TokenKind::Punct if self.text(token) == ";" => {
+                    return Err(self.unexpected("a module specifier"));
+                }
+                _ => self.i += 1,
+            }
+        }
+        self.i += 1;
+        self.skip_import_attributes()?;
+        self.eat(";");
+        Ok(())
+    }
+
+    fn parse_export(&mut self, start: usize) -> Result<()> {
+        self.i += 1;
+        let token = self.peek();
+        match self.text(token) {
+            "type" if self.is_at(1, "{") || self.is_at(1, "*") => {
+                self.skip_module_clause()?;
+                self.erase_since(start);
+            }
+            "=" => return Err(self.not_erasable("Export assignments")),
+            "import" if self.next_is_word() && self.is_at(2, "=") => {
+                return Err(self.not_erasable("Import assignments"));
+            }
+            "as" if self.next_is_word() => {
+                // `export as namespace X` only describes a global
+                while !self.is(";") && !self.peek_at(1).newline_before {
+                    if self.peek().kind == TokenKind::Eof {
+                        break;
+                    }
+                    self.i += 1;
+                }
+                self.advance();
+                self.eat(";");
+                self.erase_since(start);
+            }
+            "default" => {
+                self.i += 1;
+                let word = self.text(self.peek()).to_string();
+                let next = self.peek_at(1);
+                if word == "interface" && self.next_is_word() {
+                    self.skip_interface()?;
+                    self.erase_since(start);
+                } else if matches!(word.as_str(), "function" | "class" | "abstract")
+                    || (word == "async" && self.is_at(1, "function") && !next.newline_before)
+                {
+                    self.parse_statement_from(start)?;
+                } else {
+                    self.parse_expression(&[])?;
+                    self.eat(";");
+                }
+            }
+            "{" => {
+                self.parse_specifiers()?;
+                if self.eat("from") {
+                    self.advance();
+                    self.skip_import_attributes()?;
+                }
+                self.eat(";");
+            }
+            "*" => {
+                while self.peek().kind != TokenKind::String {
+                    if self.peek().kind == TokenKind::Eof {
+                        return Err(self.unexpected("a module specifier"));
+                    }
+                    self.i += 1;
+                }
+                self.i += 1;
+                self.skip_import_attributes()?;
+                self.eat(";");
+            }
+            _ => self.parse_statement_from(start)?,
+        }
+        Ok(())
+    }
+
+    /// Move past the rest of an `import type` or `export type` statement
+    fn skip_module_clause(&mut self) -> Result<()> {
+        loop {
+            let token = self.peek();
+            match token.kind {
+                TokenKind::String => {
+                    self.i += 1;
+                    self.skip_import_attributes()?;
+                    break;
+                }
+                TokenKind::Eof => break,
+                TokenKind::Punct if self.text(token) == ";" => break,
+                TokenKind::Punct if self.text(token) == "{" => {
+                    self.skip_balanced()?;
+                    // `export type { A };` has no module specifier
+                    if !self.is("from") {
+                        break;
+                    }
+                }
+                _ => self.i += 1,
+            }
+        }
+        self.eat(";");
+        Ok(())
+    }
+
+    fn skip_import_attributes(&mut self) -> Result<()> {
+        if (self.is("with") || self.is("assert")) && !self.peek().newline_before {
+            self.i += 1;
+            self.skip_balanced()?;
+        }
+        Ok(())
+    }
+
+    /// Parse `{ a, type B, c as d }`, erasing the `type` specifiers
+    fn parse_specifiers(&mut self) -> Result<()> {
+        self.expect("{")?;
+        loop {
+            if self.eat("}") {
+                return Ok(());
+            }
+            let start = self.i;
+            let is_type =
+                self.is("type") && !matches!(self.text(self.peek_at(1)), "," | "}" | "as");
+            if is_type {
+                self.i += 1;
+            }
+            self.advance();
+            if self.eat("as") {
+                self.advance();
+            }
+            if !self.eat(",") && !self.is("}") {
+                return Err(self.unexpected("',' or '}'"));
+            }
+            if is_type {
+                self.erase_since(start);
+            }
+        }
+    }
+
+    /// Parse variable declarations after `let`, `const` or `var`, up to the
+    /// end of the statement or one of `stops`
+    fn parse_var_declarations(&mut self, stops: &[&str]) -> Result<()> {
+        let mut initializer_stops = vec![","];
+        initializer_stops.extend_from_slice(stops);
+        loop {
+            match self.text(self.peek()) {
+                "{" => self.parse_object()?,
+                "[" => self.parse_array()?,
+                _ => self.expect_ident()?,
+            }
+            // Definite assignment: `let x!: number`
+            if self.is("!") && self.is_at(1, ":") {
+                self.erase_token();
+            }
+            self.strip_annotation()?;
+            if self.eat("=") {
+                self.parse_expression(&initializer_stops)?;
+            }
+            if !self.eat(",") {
+                return Ok(());
+            }
+        }
+    }
+
+    /// Move past `interface Name<T> extends A, B { ... }`
+    fn skip_interface(&mut self) -> Result<()> {
+        self.i += 1;
+        loop {
+            let token = self.peek();
+            match (token.kind, self.text(token)) {
+                (TokenKind::Punct, "{") => return self.skip_balanced(),
+                (TokenKind::Punct, "(" | "[") => self.skip_balanced()?,
+                (TokenKind::Eof, _) => return Err(self.unexpected("'{'")),
+                _ => self.i += 1,
+            }
+        }
+    }
+
+    /// Move past `type Name<T> = Type;`
+    fn skip_type_alias(&mut self) -> Result<()> {
+        self.i += 2;
+        if self.is("<") {
+            self.skip_angle_brackets()?;
+        }
+        self.expect("=")?;
+        self.parse_type()?;
+        self.eat(";");
+        Ok(())
+    }
+
+    /// Move past an ambient declaration, starting at `declare`
+    fn skip_declaration(&mut self) -> Result<()> {
+        self.i += 1;
+        let word = self.text(self.peek()).to_string();
+        match word.as_str() {
+            "const" | "let" | "var" => {
+                self.i += 1;
+                self.parse_var_declarations(&[])?;
+                self.eat(";");
+            }
+            "async" | "function" => {
+                self.eat("async");
+                self.expect("function")?;
+                self.eat("*");
+                self.expect_ident()?;
+                self.skip_signature()?;
+                self.eat(";");
+            }
+            "type" => self.skip_type_alias()?,
+            "interface" => self.skip_interface()?,
+            _ => loop {
+                // Classes, enums, namespaces, modules and `global`
+                let token = self.peek();
+                match (token.kind, self.text(token)) {
+                    (TokenKind::Punct, "{") => {
+                        self.skip_balanced()?;
+                        break;
+                    }
+                    // `declare module 'x';`
+                    (TokenKind::Punct, ";") => {
+                        self.i += 1;
+                        break;
+                    }
+                    (TokenKind::Punct, "(" | "[") => self.skip_balanced()?,
+                    (TokenKind::Punct, "<") => self.skip_angle_brackets()?,
+                    (TokenKind::Eof, _) => return Err(self.unexpected("'{'")),
+                    _ => self.i += 1,
+                }
+            },
+        }
+        Ok(())
+    }
+
+    /// Move past type parameters, parameters and a return type
+    fn skip_signature(&mut self) -> Result<()> {
+        if self.is("<") {
+            self.skip_angle_brackets()?;
+        }
+        if !self.is("(") {
+            return Err(self.unexpected("'('"));
+        }
+        self.skip_balanced()?;
+        if self.eat(":") {
+            self.parse_type()?;
+        }
+        Ok(())
+    }
+
+    // Functions and classes
+
+    /// Parse a function from `function`. A declaration without a body is an
+    /// overload, and is erased from `start`.
+    fn parse_function(&mut self, start: usize, declaration: bool) -> Result<()> {
+        self.expect("function")?;
+        self.eat("*");
+        if self.peek().kind == TokenKind::Ident {
+            self.i += 1;
+        }
+        self.parse_signature()?;
+        if self.is("{") {
+            self.parse_block()
+        } else if declaration {
+            self.eat(";");
+            self.erase_since(start);
+            Ok(())
+        } else {
+            Err(self.unexpected("'{'"))
+        }
+    }
+
+    /// Parse type parameters, parameters and a return type, erasing the types
+    fn parse_signature(&mut self) -> Result<()> {
+        self.strip_type_parameters()?;
+        self.parse_parameters()?;
+        self.strip_annotation()
+    }
+
+    fn parse_parameters(&mut self) -> Result<()> {
+        self.expect("(")?;
+        loop {
+            if self.eat(")") {
+                return Ok(());
+            }
+            let start = self.i;
+            if self.is("this") && self.is_at(1, ":") {
+                self.i += 1;
+                self.strip_annotation()?;
+                self.eat(",");
+                self.erase_since(start);
+                continue;
+            }
+            let word = self.text(self.peek());
+            if TS_MODIFIERS.contains(&word)
+                && !self.peek_at(1).newline_before
+                && (self.peek_at(1).kind == TokenKind::Ident
+                    || self.is_at(1, "{")
+                    || self.is_at(1, "["))
+            {
+                return Err(self.not_erasable("Parameter properties"));
+            }
+            self.eat("...");
+            match self.text(self.peek()) {
+                "{" => self.parse_object()?,
+                "[" => self.parse_array()?,
+                _ => self.expect_ident()?,
+            }
+            if self.is("?") {
+                self.erase_token();
+            }
+            self.strip_annotation()?;
+            if self.eat("=") {
+                self.parse_expression(&[","])?;
+            }
+            if !self.eat(",") && !self.is(")") {
+                return Err(self.unexpected("',' or ')'"));
+            }
+        }
+    }
+
+    fn parse_class(&mut self) -> Result<()> {
+        self.expect("class")?;
+        if self.peek().kind == TokenKind::Ident && !self.is("extends") && !self.is("implements") {
+            self.i += 1;
+        }
+        self.strip_type_parameters()?;
+        if self.eat("extends") {
+            loop {
+                self.parse_expression(&["implements", "{", "<"])?;
+                if !self.is("<") {
+                    break;
+                }
+                self.strip_type_parameters()?;
+            }
+        }
+        if self.is("implements") {
+            let start = self.i;
+            self.i += 1;
+            loop {
+                self.parse_type()?;
+                if !self.eat(",") {
+                    break;
+                }
+            }
+            self.erase_since(start);
+        }
+        self.parse_class_body()
+    }
+
+    fn parse_class_body(&mut self) -> Result<()> {
+        self.expect("{")?;
+        loop {
+            if self.eat("}") {
+                return Ok(());
+            }
+            if self.eat(";") {
+                continue;
+            }
+            if self.is("static") && self.is_at(1, "{") {
+                self.i += 1;
+                self.parse_block()?;
+                continue;
+            }
+            let start = self.i;
+            while self.eat("@") {
+                self.parse_decorator()?;
+            }
+            // Declared and abstract members have no code
+            let mut erase_member = false;
+            loop {
+                let word = self.text(self.peek());
+                let next = self.peek_at(1);
+                let is_modifier = !next.newline_before
+                    && (matches!(
+                        next.kind,
+                        TokenKind::Ident | TokenKind::String | TokenKind::Number
+                    ) || matches!(self.text(next), "[" | "*"));
+                if is_modifier && TS_MODIFIERS.contains(&word) {
+                    erase_member |= matches!(word, "declare" | "abstract");
+                    self.erase_token();
+                } else if is_modifier && JS_MODIFIERS.contains(&word) {
+                    self.i += 1;
+                } else if !self.eat("*") {
+                    break;
+                }
+            }
+
+            // Index signatures: `[key: string]: Type;`
+            if self.is("[") && self.peek_at(1).kind == TokenKind::Ident && self.is_at(2, ":") {
+                self.skip_balanced()?;
+                self.strip_annotation()?;
+                self.eat(";");
+                self.erase_since(start);
+                continue;
+            }
+
+            match self.peek().kind {
+                TokenKind::Ident | TokenKind::String | TokenKind::Number => self.i += 1,
+                TokenKind::Punct if self.is("[") => {
+                    self.i += 1;
+                    self.parse_until("]")?;
+                }
+                _ => return Err(self.unexpected("a class member")),
+            }
+            if self.is("?") || self.is("!") {
+                self.erase_token();
+            }
+            if self.is("(") || self.is("<") {
+                self.parse_signature()?;
+                if self.is("{") && !erase_member {
+                    self.parse_block()?;
+                } else {
+                    // An overload or an abstract method
+                    self.eat(";");
+                    erase_member = true;
+                }
+            } else {
+                self.strip_annotation()?;
+                if self.eat("=") {
+                    self.parse_expression(&[";"])?;
+                }
+                self.eat(";");
+            }
+            if erase_member {
+                self.erase_since(start);
+            }
+        }
+    }
+
+    /// Parse a decorator after its `@`
+    fn parse_decorator(&mut self) -> Result<()> {
+        if self.is("(") {
+            return self.parse_parenthesized();
+        }
+        self.expect_ident()?;
+        while self.eat(".") {
+            self.expect_ident()?;
+        }
+        if self.is("(") {
+            self.parse_parenthesized()?;
+        }
+        Ok(())
+    }
+
+    // Expressions
+
+    /// Parse `( ... )`
+    fn parse_parenthesized(&mut self) -> Result<()> {
+        self.expect("(")?;
+        self.parse_until(")")
+    }
+
+    /// Parse expressions up to and including `close`
+    fn parse_until(&mut self, close: &str) -> Result<()> {
+        loop {
+            if self.eat(close) {
+                return Ok(());
+            }
+            if self.peek().kind == TokenKind::Eof {
+                return Err(self.unexpected(&format!("'{}'", close)));
+            }
+            let before = self.i;
+            self.parse_expression(&[])?;
+            if self.i == before {
+                // A `;` in a for statement, or a closing bracket that
+                // doesn't match
+                if !self.eat(";") {
+                    return Err(self.unexpected(&format!("'{}'", close)));
+                }
+            }
+        }
+    }
+
+    fn parse_array(&mut self) -> Result<()> {
+        self.expect("[")?;
+        self.parse_until("]")
+    }
+
+    /// Parse an object literal or pattern
+    fn parse_object(&mut self) -> Result<()> {
+        self.expect("{")?;
+        loop {
+            if self.eat("}") {
+                return Ok(());
+            }
+            if self.eat(",") {
+                continue;
+            }
+            if self.eat("...") {
+                self.parse_expression(&[","])?;
+                continue;
+            }
+            loop {
+                let word = self.text(self.peek());
+                let next = self.peek_at(1);
+                let is_modifier = matches!(word, "get" | "set" | "async")
+                    && !next.newline_before
+                    && (matches!(
+                        next.kind,
+                        TokenKind::Ident | TokenKind::String | TokenKind::Number
+                    ) || matches!(self.text(next), "[" | "*"));
+                if is_modifier || self.is("*") {
+                    self.i += 1;
+                } else {
+                    break;
+                }
+            }
+            match self.peek().kind {
+                TokenKind::Ident | TokenKind::String | TokenKind::Number => self.i += 1,
+                TokenKind::Punct if self.is("[") => {
+                    self.i += 1;
+                    self.parse_until("]")?;
+                }
+                _ => return Err(self.unexpected("a property name")),
+            }
+            if self.is("(") || self.is("<") {
+                self.parse_signature()?;
+                self.parse_block()?;
+            } else if self.eat(":") || self.eat("=") {
+                self.parse_expression(&[","])?;
+            }
+        }
+    }
+
+    /// Whether a line break before `token`, after an operand, ends the
+    /// statement: that's where JavaScript inserts a semicolon, and where
+    /// TypeScript ends an expression before `as` and `satisfies`
+    fn ends_statement(&self, token: Token) -> bool {
+        match token.kind {
+            TokenKind::Ident => !matches!(self.text(token), "in" | "instanceof"),
+            TokenKind::Number | TokenKind::String => true,
+            TokenKind::Punct => matches!(self.text(token), "++" | "--" | "!" | "@"),
+            _ => false,
+        }
+    }
+
+    /// Parse an expression, up to the end of the statement, a closing
+    /// bracket, or one of `stops`
+    fn parse_expression(&mut self, stops: &[&str]) -> Result<()> {
+        // Whether the previous token ended an operand
+        let mut after_operand = false;
+        loop {
+            let token = self.peek();
+            let text = self.text(token);
+            match token.kind {
+                TokenKind::Eof | TokenKind::TemplateMiddle | TokenKind::TemplateTail => {
+                    return Ok(());
+                }
+                TokenKind::Punct if matches!(text, ")" | "]" | "}" | ";") => return Ok(()),
+                TokenKind::Punct | TokenKind::Ident if stops.contains(&text) => return Ok(()),
+                _ if after_operand && token.newline_before && self.ends_statement(token) => {
+                    return Ok(());
+                }
+                _ => {}
+            }
+
+            match (token.kind, text) {
+                (TokenKind::TemplateHead, _) => {
+                    self.i += 1;
+                    loop {
+                        self.parse_expression(&[])?;
+                        match self.advance().kind {
+                            TokenKind::TemplateMiddle => {}
+                            TokenKind::TemplateTail => break,
+                            _ => {
+                                self.i -= 1;
+                                return Err(self.unexpected("'}'"));
+                            }
+                        }
+                    }
+                    after_operand = true;
+                }
+                (TokenKind::Ident, "function") => {
+                    self.parse_function(self.i, false)?;
+                    after_operand = true;
+                }
+                (TokenKind::Ident, "class") => {
+                    self.parse_class()?;
+                    after_operand = true;
+                }
+                (TokenKind::Ident, "as" | "satisfies") if after_operand => {
+                    let start = self.i;
+                    self.i += 1;
+                    self.parse_type()?;
+                    self.erase_since(start);
+                }
+                (TokenKind::Punct, "(") => {
+                    if !after_operand && self.is_arrow_parameters() {
+                        self.parse_parameters()?;
+                        self.strip_annotation()?;
+                    } else {
+                        self.parse_parenthesized()?;
+                    }
+                    after_operand = true;
+                }
+                (TokenKind::Punct, "[") => {
+                    self.parse_array()?;
+                    after_operand = true;
+                }
+                (TokenKind::Punct, "{") => {
+                    self.parse_object()?;
+                    after_operand = true;
+                }
+                (TokenKind::Punct, "=>") => {
+                    self.i += 1;
+                    after_operand = self.is("{");
+                    if after_operand {
+                        self.parse_block()?;
+                    }
+                }
+                (TokenKind::Punct, "<") if after_operand => {
+                    let previous = self.tokens[self.i - 1];
+                    if !(previous.kind == TokenKind::Ident && self.strip_type_arguments()) {
+                        self.i += 1;
+                        after_operand = false;
+                    }
+                }
+                (TokenKind::Punct, "<") => {
+                    self.strip_generic_arrow_or_assertion()?;
+                }
+                // Non-null assertion: `value!`
+                (TokenKind::Punct, "!") if after_operand && !token.newline_before => {
+                    self.erase_token();
+                }
+                (TokenKind::Punct, "++" | "--") => self.i += 1,
+                _ => {
+                    self.i += 1;
+                    after_operand = self.ends_operand(token);
+                }
+            }
+        }
+    }
+
+    /// Whether the `(` at the current token starts the parameters of an
+    /// arrow function: it's followed by `=>`, maybe after a return type
+    fn is_arrow_parameters(&mut self) -> bool {
+        let Ok(close) = self.matching_close(self.i) else {
+            return false;
+        };
+        let saved = self.i;
+        self.i = close + 1;
+        let is_arrow =
+            self.is("=>") || (self.eat(":") && self.parse_type().is_ok() && self.is("=>"));
+        self.i = saved;
+        is_arrow
+    }
+
+    /// Erase type arguments after an identifier, as in `f<T>(x)` and
+    /// `new Map<K, V>()`, returning false if the `<` is a comparison
+    fn strip_type_arguments(&mut self) -> bool {
+        let start = self.i;
+        let parsed = self.parse_type_arguments().is_ok() && {
+            let next = self.peek();
+            next.newline_before
+                || matches!(
+                    next.kind,
+                    TokenKind::Eof | TokenKind::Template | TokenKind::TemplateHead
+                )
+                || (next.kind == TokenKind::Punct
+                    && matches!(self.text(next), "(" | ")" | "]" | "}" | ";" | ","))
+        };
+        if parsed {
+            self.erase_since(start);
+        } else {
+            self.i = start;
+        }
+        parsed
+    }
+
+    /// At a `<` that starts an expression: erase the type parameters of a
+    /// generic arrow function, `<T>(x: T) => x`, or a type assertion,
+    /// `<T>value`
+    fn strip_generic_arrow_or_assertion(&mut self) -> Result<()> {
+        let start = self.i;
+        if self.skip_angle_brackets().is_ok() && self.is("(") && self.is_arrow_parameters() {
+            self.erase_since(start);
+            return Ok(());
+        }
+        self.i = start + 1;
+        self.parse_type()?;
+        self.expect(">")?;
+        self.erase_since(start);
+        Ok(())
+    }
+
+    // Types
+
+    fn parse_type(&mut self) -> Result<()> {
+        self.parse_union_type()?;
+        // Conditional types: `A extends B ? C : D`
+        if self.is("extends") && !self.peek().newline_before {
+            self.i += 1;
+            self.parse_union_type()?;
+            self.expect("?")?;
+            self.parse_type()?;
+            self.expect(":")?;
+            self.parse_type()?;
+        }
+        Ok(())
+    }
+
+    fn parse_union_type(&mut self) -> Result<()> {
+        if self.is("|") || self.is("&") {
+            self.i += 1;
+        }
+        loop {
+            self.parse_type_operator()?;
+            if !self.eat("|") && !self.eat("&") {
+                return Ok(());
+            }
+        }
+    }
+
+    fn parse_type_operator(&mut self) -> Result<()> {
+        let word = self.text(self.peek());
+        let next = self.peek_at(1);
+        let operand_follows = !matches!(
+            next.kind,
+            TokenKind::Punct if matches!(self.text(next), "," | ")" | "]" | ">" | ";" | "=" | "|" | "&")
+        );
+        match word {
+            "keyof" | "unique" | "readonly" if operand_follows => {
+                self.i += 1;
+                self.parse_type_operator()
+            }
+            "infer" if next.kind == TokenKind::Ident => {
+                self.i += 2;
+                Ok(())
+            }
+            _ => {
+                self.parse_primary_type()?;
+                // Array and indexed access types
+                while self.is("[") && !self.peek().newline_before {
+                    self.i += 1;
+                    if !self.is("]") {
+                        self.parse_type()?;
+                    }
+                    self.expect("]")?;
+                }
+                Ok(())
+            }
+        }
+    }
+
+    fn parse_primary_type(&mut self) -> Result<()> {
+        let token = self.peek();
+        match (token.kind, self.text(token)) {
+            (TokenKind::String | TokenKind::Number | TokenKind::Template, _) => self.i += 1,
+            (TokenKind::TemplateHead, _) => {
+                self.i += 1;
+                loop {
+                    self.parse_type()?;
+                    match self.advance().kind {
+                        TokenKind::TemplateMiddle => {}
+                        TokenKind::TemplateTail => break,
+                        _ => return Err(self.unexpected("'}'")),
+                    }
+                }
+            }
+            (TokenKind::Punct, "(") => {
+                let close = self.matching_close(self.i)?;
+                if self
+                    .tokens
+                    .get(close + 1)
+                    .is_some_and(|t| self.text(*t) == "=>")
+                {
+                    // A function type
+                    self.i = close + 2;
+                    self.parse_type()?;
+                } else {
+                    self.i += 1;
+                    self.parse_type()?;
+                    self.expect(")")?;
+                }
+            }
+            (TokenKind::Punct, "<") => {
+                // A generic function type
+                self.skip_angle_brackets()?;
+                self.parse_primary_type()?;
+            }
+            // Object, mapped and tuple types
+            (TokenKind::Punct, "{" | "[") => self.skip_balanced()?,
+            (TokenKind::Punct, "-") => {
+                self.i += 1;
+                if self.advance().kind != TokenKind::Number {
+                    return Err(("Expected a number".to_string(), token.start));
+                }
+            }
+            (TokenKind::Ident, "new") => {
+                self.i += 1;
+                self.parse_primary_type()?;
+            }
+            (TokenKind::Ident, "abstract") if self.is_at(1, "new") => {
+                self.i += 1;
+                self.parse_primary_type()?;
+            }
+            (TokenKind::Ident, "typeof" | "import") => {
+                if self.eat("typeof") && !self.is("import") {
+                    self.expect_ident()?;
+                } else {
+                    self.expect("import")?;
+                    if !self.is("(") {
+                        return Err(self.unexpected("'('"));
+                    }
+                    self.skip_balanced()?;
+                }
+                self.parse_type_reference_rest()?;
+            }
+            (TokenKind::Ident, "asserts") if self.next_is_word() => {
+                self.i += 2;
+                if self.eat("is") {
+                    self.parse_type()?;
+                }
+            }
+            (TokenKind::Ident, _) => {
+                self.i += 1;
+                self.parse_type_reference_rest()?;
+                // Type predicates: `value is Type`
+                if self.is("is") && !self.peek().newline_before {
+                    self.i += 1;
+                    self.parse_type()?;
+                }
+            }
+            _ => return Err(self.unexpected("a type")),
+        }
+        Ok(())
+    }
+
+    /// Parse the rest of a qualified name and its type arguments
+    fn parse_type_reference_rest(&mut self) -> Result<()> {
+        while self.eat(".") {
+            self.expect_ident()?;
+        }
+        if self.is("<") && !self.peek().newline_before {
+            self.parse_type_arguments()?;
+        }
+        Ok(())
+    }
+
+    fn parse_type_arguments(&mut self) -> Result<()> {
+        self.expect("<")?;
+        loop {
+            self.parse_type()?;
+            if !self.eat(",") {
+                break;
+            }
+        }
+        self.expect(">")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Strip `src`, checking that every line keeps its length
+    fn strip(src: &str) -> String {
+        let stripped = strip_types(src).unwrap();
+        for (line, original) in stripped.lines().zip(src.lines()) {
+            assert_eq!(line.len(), original.len(), "{:?}", line);
+        }
+        stripped
+            .split('\n')
+            .map(str::trim_end)
+            .collect::<Vec<_>>()
+            .join("\n")
+    }
+
+    #[test]
+    fn test_annotations() {
+        assert_eq!(
+            strip("let x: number = 1, y: Array<string> = [];"),
+            "let x         = 1, y                = [];"
+        );
+        assert_eq!(
+            strip("function f(a: string, b?: number): void {}"),
+            "function f(a        , b         )       {}"
+        );
+        assert_eq!(
+            strip("const f = (a: A<B>): R => a;"),
+            "const f = (a      )    => a;"
+        );
+        assert_eq!(
+            strip("const g = async <T,>(a: T) => a;"),
+            "const g = async     (a   ) => a;"
+        );
+    }
+
+    #[test]
+    fn test_declarations_are_erased() {
+        let src = "interface A {\n  x: number;\n}\ntype B<T> = T | null;\nexport type C = { a: 1 };\ndeclare const d: string;\nlet e = 1;";
+        assert_eq!(strip(src), "\n\n\n\n\n\nlet e = 1;");
+    }
+
+    #[test]
+    fn test_expressions() {
+        assert_eq!(strip("x = y as unknown as T;"), "x = y                ;");
+        assert_eq!(strip("x = y satisfies T;"), "x = y            ;");
+        assert_eq!(strip("a!.b = c!;"), "a .b = c ;");
+        assert_eq!(
+            strip("f<string>(x); new Map<K, V>();"),
+            "f        (x); new Map      ();"
+        );
+        assert_eq!(
+            strip("if (a < b && c > (d)) {}"),
+            "if (a < b && c > (d)) {}"
+        );
+        assert_eq!(
+            strip("for (let i = 0; i < n; i++) {}"),
+            "for (let i = 0; i < n; i++) {}"
+        );
+        assert_eq!(strip("x = a ? (b) : c;"), "x = a ? (b) : c;");
+        assert_eq!(strip("x = <T>y;"), "x =    y;");
+    }
+
+    #[test]
+    fn test_classes() {
+        let src = "abstract class A<T> extends B<T> implements C, D {\n  private x?: number = 1;\n  declare y: string;\n  abstract z(): void;\n  static readonly w = 2;\n  m(a: T): T { return a; }\n  [key: string]: unknown;\n}";
+        let expected = "         class A    extends B                    {\n          x          = 1;\n\n\n  static          w = 2;\n  m(a   )    { return a; }\n\n}";
+        assert_eq!(strip(src), expected);
+    }
+
+    #[test]
+    fn test_overloads_and_imports() {
+        let src = "import type { A } from './a';\nimport { type B, c } from './b';\nexport function f(a: string): void;\nexport function f(a: any) {}\nexport type { D } from './d';";
+        let expected = "\nimport {         c } from './b';\n\nexport function f(a     ) {}\n";
+        assert_eq!(strip(src), expected);
+    }
+
+    #[test]
+    fn test_asi_before_type_declarations() {
+        let src = "let a = b\ninterface I {}\nconst c = d as T\ntype U = string";
+        assert_eq!(strip(src), "let a = b\n\nconst c = d\n");
+    }
+
+    #[test]
+    fn test_strings_and_templates_are_kept() {
+        let src = "const s: string = `a ${b as string} c` + 'd: e' + /f: g/.source;";
+        let expected = "const s         = `a ${b          } c` + 'd: e' + /f: g/.source;";
+        assert_eq!(strip(src), expected);
+    }
+
+    #[test]
+    fn test_unsupported_syntax() {
+        let err = strip_types("let a = 1;\nenum E { A }").unwrap_err();
+        assert!(err.0.starts_with("Enums aren't supported"));
+        assert_eq!(err.1, 11);
+        assert!(strip_types("namespace N {}").is_err());
+        assert!(strip_types("class A { constructor(private x: number) {} }").is_err());
+        assert!(strip_types("import fs = require('fs');").is_err());
+    }
+
+    #[test]
+    fn test_non_ascii_keeps_utf16_columns() {
+        let stripped = strip_types("let é: 'ü😀' = 1;").unwrap();
+        assert_eq!(stripped, "let é        = 1;");
+    }
+}
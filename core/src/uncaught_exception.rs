@@ -0,0 +1,95 @@
+//! Reporting of exceptions that nothing catches.
+//!
+//! An exception thrown by a callback the event loop calls, such as a timer
+//! or a socket's message handler, is reported like `reportError()` does: it's
+//! dispatched as an `error` event on globalThis and `uncaughtException` on
+//! process. If no listener handles it, the event loop stops with the
+//! exception, formatted like one a script throws.
+
+use crate::{IsolateState, JsError};
+
+/// Call `callback` with `args`, reporting the exception it throws
+pub(crate) fn call(
+    scope: &mut v8::PinScope,
+    callback: v8::Local<v8::Function>,
+    recv: v8::Local<v8::Value>,
+    args: &[v8::Local<v8::Value>],
+) {
+    // Nothing runs once the JavaScript is terminated
+    if crate::termination::is_requested(scope) {
+        return;
+    }
+    v8::tc_scope!(let tc, scope);
+    if callback.call(tc, recv, args).is_some() || !tc.has_caught() || tc.has_terminated() {
+        return;
+    }
+    let Some(exception) = tc.exception() else {
+        return;
+    };
+    let error = JsError::from_try_catch(tc);
+
+    // The event's message and location, from where the exception was thrown
+    let mut event_args = [
+        exception,
+        v8::undefined(tc).into(),
+        v8::undefined(tc).into(),
+        v8::undefined(tc).into(),
+        v8::undefined(tc).into(),
+    ];
+    if let Some(message) = tc.message() {
+        event_args[1] = message.get(tc).into();
+        if let Some(location) = crate::error::error_location(tc, message) {
+            event_args[2] = v8::String::new(tc, &location.file).unwrap().into();
+            event_args[3] = v8::Number::new(tc, location.line as f64).into();
+            event_args[4] = v8::Number::new(tc, (location.start_column + 1) as f64).into();
+        }
+    }
+    tc.reset();
+
+    match dispatch(tc, &event_args) {
+        Ok(true) => {}
+        Ok(false) => record(tc, error),
+        Err(e) => record(tc, e),
+    }
+}
+
+/// Record an exception no listener handled. The first one is returned by
+/// `take`, which stops the event loop.
+pub(crate) fn record(isolate: &mut v8::Isolate, error: JsError) {
+    let state = IsolateState::get(isolate);
+    state.borrow_mut().uncaught_exception.get_or_insert(error);
+}
+
+/// Return the exception recorded since the last call, if any
+pub(crate) fn take(scope: &mut v8::PinScope) -> Result<(), JsError> {
+    let isolate: &mut v8::Isolate = scope;
+    let state = IsolateState::get(isolate);
+    let error = state.borrow_mut().uncaught_exception.take();
+    match error {
+        Some(error) => Err(error),
+        None => Ok(()),
+    }
+}
+
+/// Call globalThis.__dispatchError__, set up by the process builtin, with
+/// the exception, message, file name, line and column. Returns whether a
+/// listener handled the exception, or the exception a listener threw.
+fn dispatch(scope: &mut v8::PinScope, args: &[v8::Local<v8::Value>]) -> Result<bool, JsError> {
+    v8::tc_scope!(let tc, scope);
+    let context = tc.get_current_context();
+    let global = context.global(tc);
+    let key = v8::String::new(tc, "__dispatchError__").unwrap();
+    let Some(dispatcher) = global
+        .get(tc, key.into())
+        .and_then(|value| v8::Local::<v8::Function>::try_from(value).ok())
+    else {
+        return Ok(false);
+    };
+
+    let recv = v8::undefined(tc).into();
+    match dispatcher.call(tc, recv, args) {
+        Some(handled) => Ok(handled.boolean_value(tc)),
+        None if tc.has_caught() => Err(JsError::from_try_catch(tc)),
+        None => Ok(false),
+    }
+}
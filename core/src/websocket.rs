@@ -0,0 +1,680 @@
+//! WebSocket connections (RFC 6455) backing the WHATWG `WebSocket`.
+//!
+//! A WebSocket runs over an [`ActiveTcpSocket`], with a TLS session for
+//! `wss:` URLs, which it polls itself. Polling sends the opening handshake,
+//! checks the server's response and then decodes frames into
+//! [`WebSocketEvent`]s for the event loop to hand to JS.
+//!
+//! Server-side WebSockets come from `jstime.serve` connections upgraded with
+//! `jstime.upgradeWebSocket`. The HTTP server answers the handshake and hands
+//! the stream over; from then on the two roles differ only in masking.
+
+use crate::tcp::{ActiveTcpSocket, SocketEvent};
+use crate::tls::{TlsOptions, TlsSession};
+use smallvec::SmallVec;
+
+/// Appended to the client's key to compute Sec-WebSocket-Accept
+const ACCEPT_GUID: &str = "258EAFA5-E914-47DA-95CA-C5AB0DC85B11";
+
+/// Largest handshake response we're willing to buffer
+const MAX_HANDSHAKE_SIZE: usize = 16 * 1024;
+
+/// Largest message we're willing to assemble, to bound memory use
+const MAX_MESSAGE_SIZE: usize = 64 * 1024 * 1024;
+
+const OPCODE_CONTINUATION: u8 = 0x0;
+const OPCODE_TEXT: u8 = 0x1;
+const OPCODE_BINARY: u8 = 0x2;
+const OPCODE_CLOSE: u8 = 0x8;
+const OPCODE_PING: u8 = 0x9;
+const OPCODE_PONG: u8 = 0xA;
+
+/// Close codes used when this side fails the connection
+const CLOSE_PROTOCOL_ERROR: u16 = 1002;
+const CLOSE_NO_STATUS: u16 = 1005;
+const CLOSE_ABNORMAL: u16 = 1006;
+const CLOSE_INVALID_DATA: u16 = 1007;
+const CLOSE_TOO_BIG: u16 = 1009;
+
+/// Something that happened on a WebSocket, reported to JS in order
+pub(crate) enum WebSocketEvent {
+    /// The opening handshake completed
+    Open {
+        protocol: String,
+        extensions: String,
+    },
+    Text(String),
+    Binary(Vec<u8>),
+    /// The connection failed. A `Close` event always follows.
+    Error(String),
+    /// The connection is closed and the WebSocket must be dropped
+    Close {
+        code: u16,
+        reason: String,
+        was_clean: bool,
+    },
+}
+
+enum State {
+    /// Waiting for the server's handshake response
+    Handshake {
+        expected_accept: String,
+        protocols: Vec<String>,
+    },
+    /// Waiting for the HTTP server to hand over an upgraded connection
+    Accepting {
+        protocol: String,
+    },
+    Open,
+    Closed,
+}
+
+/// A `WebSocket` registered with the event loop
+pub(crate) struct ActiveWebSocket {
+    socket: ActiveTcpSocket,
+    /// Called with (event, ...data) for each event
+    pub(crate) callback: v8::Global<v8::Function>,
+    state: State,
+    /// True for the server side of a connection, which receives masked frames
+    /// and sends unmasked ones
+    is_server: bool,
+    /// Received bytes that haven't been decoded yet
+    read_buf: Vec<u8>,
+    /// Opcode and payload of a fragmented message being received
+    message: Option<(u8, Vec<u8>)>,
+    close_sent: bool,
+    /// Code and reason from the server's close frame
+    close_received: Option<(u16, String)>,
+    /// Events produced outside of `poll`, delivered by the next poll
+    pending: Vec<WebSocketEvent>,
+}
+
+impl ActiveWebSocket {
+    /// Start connecting to a WebSocket server.
+    /// `resource` is the path and query of the URL; `protocols` are offered as subprotocols.
+    pub(crate) fn connect(
+        host: &str,
+        port: u16,
+        resource: &str,
+        secure: bool,
+        protocols: Vec<String>,
+        callback: v8::Global<v8::Function>,
+    ) -> std::io::Result<Self> {
+        let mut socket = ActiveTcpSocket::connect(host.to_string(), port);
+        if secure {
+            socket = socket.with_tls(TlsSession::new(host, TlsOptions::default())?);
+        }
+
+        let mut nonce = [0u8; 16];
+        getrandom::fill(&mut nonce).map_err(std::io::Error::other)?;
+        let key = base64_simd::STANDARD.encode_to_string(nonce);
+        let expected_accept = accept_key(&key);
+
+        let default_port = if secure { 443 } else { 80 };
+        let host_header = match (host.contains(':'), port == default_port) {
+            (true, true) => format!("[{}]", host),
+            (true, false) => format!("[{}]:{}", host, port),
+            (false, true) => host.to_string(),
+            (false, false) => format!("{}:{}", host, port),
+        };
+        let mut request = format!(
+            "GET {} HTTP/1.1\r\n\
+             Host: {}\r\n\
+             Upgrade: websocket\r\n\
+             Connection: Upgrade\r\n\
+             Sec-WebSocket-Key: {}\r\n\
+             Sec-WebSocket-Version: 13\r\n",
+            resource, host_header, key
+        );
+        if !protocols.is_empty() {
+            request.push_str(&format!(
+                "Sec-WebSocket-Protocol: {}\r\n",
+                protocols.join(", ")
+            ));
+        }
+        request.push_str("\r\n");
+        // Queued until the socket connects
+        socket.write(request.as_bytes())?;
+
+        Ok(Self {
+            socket,
+            callback,
+            state: State::Handshake {
+                expected_accept,
+                protocols,
+            },
+            is_server: false,
+            read_buf: Vec::new(),
+            message: None,
+            close_sent: false,
+            close_received: None,
+            pending: Vec::new(),
+        })
+    }
+
+    /// Wrap the server side of an upgraded HTTP connection. `socket` reports
+    /// `Connect` once the handshake response has been written, which opens
+    /// the WebSocket with the chosen subprotocol.
+    pub(crate) fn accept(
+        socket: ActiveTcpSocket,
+        protocol: String,
+        callback: v8::Global<v8::Function>,
+    ) -> Self {
+        Self {
+            socket,
+            callback,
+            state: State::Accepting { protocol },
+            is_server: true,
+            read_buf: Vec::new(),
+            message: None,
+            close_sent: false,
+            close_received: None,
+            pending: Vec::new(),
+        }
+    }
+
+    /// True once the `Close` event has been produced
+    pub(crate) fn is_closed(&self) -> bool {
+        matches!(self.state, State::Closed)
+    }
+
+    /// The number of bytes of queued messages not yet sent
+    pub(crate) fn buffered_amount(&self) -> usize {
+        self.socket.buffered_amount()
+    }
+
+    /// Send a text or binary message. Messages sent after closing are dropped.
+    pub(crate) fn send(&mut self, text: bool, payload: &[u8]) -> std::io::Result<()> {
+        if !matches!(self.state, State::Open) || self.close_sent {
+            return Ok(());
+        }
+        let opcode = if text { OPCODE_TEXT } else { OPCODE_BINARY };
+        self.write_frame(opcode, payload)
+    }
+
+    /// Start the closing handshake. Closing before the connection is open
+    /// fails it, producing `Error` and `Close` events on the next poll.
+    pub(crate) fn close(&mut self, code: Option<u16>, reason: &str) {
+        match self.state {
+            State::Handshake { .. } | State::Accepting { .. } => {
+                self.socket.end();
+                self.pending.push(WebSocketEvent::Error(
+                    "WebSocket was closed before the connection was established".to_string(),
+                ));
+                self.pending.push(WebSocketEvent::Close {
+                    code: CLOSE_ABNORMAL,
+                    reason: String::new(),
+                    was_clean: false,
+                });
+            }
+            State::Open if !self.close_sent => {
+                let mut payload = Vec::with_capacity(2 + reason.len());
+                if let Some(code) = code {
+                    payload.extend_from_slice(&code.to_be_bytes());
+                    payload.extend_from_slice(reason.as_bytes());
+                }
+                self.close_sent = true;
+                // A failed write is reported by the next poll
+                let _ = self.write_frame(OPCODE_CLOSE, &payload);
+            }
+            _ => {}
+        }
+    }
+
+    /// Advance the connection, pushing events for JS.
+    /// Once `is_closed()` returns true the WebSocket must be dropped.
+    pub(crate) fn poll(&mut self, buf: &mut [u8], events: &mut SmallVec<[WebSocketEvent; 4]>) {
+        if !self.pending.is_empty() {
+            events.extend(self.pending.drain(..));
+            self.state = State::Closed;
+            return;
+        }
+        if self.is_closed() {
+            return;
+        }
+
+        let mut socket_events = SmallVec::<[SocketEvent; 4]>::new();
+        self.socket.poll(buf, &mut socket_events);
+        let mut ended = false;
+        for event in socket_events {
+            match event {
+                SocketEvent::Data(data) => self.read_buf.extend_from_slice(&data),
+                SocketEvent::End => ended = true,
+                SocketEvent::Error(e) => {
+                    self.fail_with(events, e.to_string());
+                    return;
+                }
+                SocketEvent::Connect => {
+                    if let State::Accepting { protocol } = &mut self.state {
+                        events.push(WebSocketEvent::Open {
+                            protocol: std::mem::take(protocol),
+                            extensions: String::new(),
+                        });
+                        self.state = State::Open;
+                    }
+                }
+                SocketEvent::SecureConnect | SocketEvent::Drain | SocketEvent::Finish => {}
+            }
+        }
+
+        if matches!(self.state, State::Handshake { .. }) {
+            self.read_handshake(events);
+        }
+        if matches!(self.state, State::Open) {
+            self.read_frames(events);
+        }
+
+        if ended && !self.is_closed() {
+            // The peer closed the connection. That's clean only after a closing handshake.
+            let (code, reason, was_clean) = match self.close_received.take() {
+                Some((code, reason)) if self.close_sent => (code, reason, true),
+                _ => (CLOSE_ABNORMAL, String::new(), false),
+            };
+            if !was_clean && matches!(self.state, State::Handshake { .. }) {
+                events.push(WebSocketEvent::Error(
+                    "Connection closed before receiving a handshake response".to_string(),
+                ));
+            }
+            events.push(WebSocketEvent::Close {
+                code,
+                reason,
+                was_clean,
+            });
+            self.state = State::Closed;
+        }
+    }
+
+    /// Check the server's handshake response once it has fully arrived
+    fn read_handshake(&mut self, events: &mut SmallVec<[WebSocketEvent; 4]>) {
+        let Some(end) = self.read_buf.windows(4).position(|w| w == b"\r\n\r\n") else {
+            if self.read_buf.len() > MAX_HANDSHAKE_SIZE {
+                self.fail_with(events, "Handshake response is too large".to_string());
+            }
+            return;
+        };
+        let State::Handshake {
+            expected_accept,
+            protocols,
+        } = &self.state
+        else {
+            return;
+        };
+
+        let head = String::from_utf8_lossy(&self.read_buf[..end]).into_owned();
+        let result = check_handshake(&head, expected_accept, protocols);
+        self.read_buf.drain(..end + 4);
+        match result {
+            Ok(protocol) => {
+                self.state = State::Open;
+                events.push(WebSocketEvent::Open {
+                    protocol,
+                    extensions: String::new(),
+                });
+            }
+            Err(message) => self.fail_with(events, message),
+        }
+    }
+
+    /// Decode complete frames from the read buffer
+    fn read_frames(&mut self, events: &mut SmallVec<[WebSocketEvent; 4]>) {
+        let mut offset = 0;
+        while matches!(self.state, State::Open) {
+            let frame = match decode_frame(&self.read_buf[offset..], self.is_server) {
+                Ok(Some(frame)) => frame,
+                Ok(None) => break,
+                Err((code, message)) => {
+                    self.fail(events, code, message);
+                    break;
+                }
+            };
+            let payload_start = offset + frame.header_len;
+            let payload_end = payload_start + frame.payload_len;
+            offset = payload_end;
+
+            // Nothing but the close frame matters once the peer has closed
+            if self.close_received.is_some() {
+                continue;
+            }
+            let mut payload = self.read_buf[payload_start..payload_end].to_vec();
+            if let Some(mask) = frame.mask {
+                apply_mask(&mut payload, mask);
+            }
+            match frame.opcode {
+                OPCODE_PING => {
+                    if !self.close_sent {
+                        let _ = self.write_frame(OPCODE_PONG, &payload);
+                    }
+                }
+                OPCODE_PONG => {}
+                OPCODE_CLOSE => self.receive_close(events, &payload),
+                OPCODE_TEXT | OPCODE_BINARY | OPCODE_CONTINUATION => {
+                    self.receive_data(events, frame.opcode, frame.fin, payload);
+                }
+                opcode => self.fail(
+                    events,
+                    CLOSE_PROTOCOL_ERROR,
+                    format!("Unknown opcode {}", opcode),
+                ),
+            }
+        }
+        self.read_buf.drain(..offset.min(self.read_buf.len()));
+    }
+
+    /// Assemble message fragments and deliver complete messages
+    fn receive_data(
+        &mut self,
+        events: &mut SmallVec<[WebSocketEvent; 4]>,
+        opcode: u8,
+        fin: bool,
+        payload: Vec<u8>,
+    ) {
+        let (opcode, data) = match (opcode, self.message.take()) {
+            (OPCODE_CONTINUATION, Some((opcode, mut data))) => {
+                data.extend_from_slice(&payload);
+                (opcode, data)
+            }
+            (OPCODE_CONTINUATION, None) => {
+                self.fail(
+                    events,
+                    CLOSE_PROTOCOL_ERROR,
+                    "Unexpected continuation frame".to_string(),
+                );
+                return;
+            }
+            (_, Some(_)) => {
+                self.fail(
+                    events,
+                    CLOSE_PROTOCOL_ERROR,
+                    "Expected a continuation frame".to_string(),
+                );
+                return;
+            }
+            (opcode, None) => (opcode, payload),
+        };
+        if data.len() > MAX_MESSAGE_SIZE {
+            self.fail(events, CLOSE_TOO_BIG, "Message is too large".to_string());
+            return;
+        }
+        if !fin {
+            self.message = Some((opcode, data));
+            return;
+        }
+
+        if opcode == OPCODE_TEXT {
+            match String::from_utf8(data) {
+                Ok(text) => events.push(WebSocketEvent::Text(text)),
+                Err(_) => self.fail(
+                    events,
+                    CLOSE_INVALID_DATA,
+                    "Text message is not valid UTF-8".to_string(),
+                ),
+            }
+        } else {
+            events.push(WebSocketEvent::Binary(data));
+        }
+    }
+
+    /// Handle the peer's close frame, answering it if we haven't sent ours
+    fn receive_close(&mut self, events: &mut SmallVec<[WebSocketEvent; 4]>, payload: &[u8]) {
+        let (code, reason) = match payload.len() {
+            0 => (CLOSE_NO_STATUS, String::new()),
+            1 => {
+                self.fail(
+                    events,
+                    CLOSE_PROTOCOL_ERROR,
+                    "Invalid close frame".to_string(),
+                );
+                return;
+            }
+            _ => {
+                let code = u16::from_be_bytes([payload[0], payload[1]]);
+                if !is_valid_close_code(code) {
+                    self.fail(
+                        events,
+                        CLOSE_PROTOCOL_ERROR,
+                        format!("Invalid close code {}", code),
+                    );
+                    return;
+                }
+                match std::str::from_utf8(&payload[2..]) {
+                    Ok(reason) => (code, reason.to_string()),
+                    Err(_) => {
+                        self.fail(
+                            events,
+                            CLOSE_INVALID_DATA,
+                            "Close reason is not valid UTF-8".to_string(),
+                        );
+                        return;
+                    }
+                }
+            }
+        };
+
+        if !self.close_sent {
+            self.close_sent = true;
+            let reply = if code == CLOSE_NO_STATUS {
+                Vec::new()
+            } else {
+                code.to_be_bytes().to_vec()
+            };
+            let _ = self.write_frame(OPCODE_CLOSE, &reply);
+        }
+        self.close_received = Some((code, reason));
+        // The peer closes its side of the TCP connection in turn, which produces the Close event
+        self.socket.end();
+    }
+
+    /// Fail the connection for a protocol violation, telling the peer why
+    fn fail(&mut self, events: &mut SmallVec<[WebSocketEvent; 4]>, code: u16, message: String) {
+        if matches!(self.state, State::Open) && !self.close_sent {
+            self.close_sent = true;
+            let _ = self.write_frame(OPCODE_CLOSE, &code.to_be_bytes());
+        }
+        self.fail_with(events, message);
+    }
+
+    /// Close the connection abnormally, reporting `message` as the error
+    fn fail_with(&mut self, events: &mut SmallVec<[WebSocketEvent; 4]>, message: String) {
+        self.socket.end();
+        events.push(WebSocketEvent::Error(message));
+        events.push(WebSocketEvent::Close {
+            code: CLOSE_ABNORMAL,
+            reason: String::new(),
+            was_clean: false,
+        });
+        self.state = State::Closed;
+    }
+
+    /// Write a frame, masked if this is the client side as RFC 6455 requires
+    fn write_frame(&mut self, opcode: u8, payload: &[u8]) -> std::io::Result<()> {
+        let mask_bit = if self.is_server { 0 } else { 0x80 };
+        let mut frame = Vec::with_capacity(payload.len() + 14);
+        frame.push(0x80 | opcode);
+        match payload.len() {
+            len if len < 126 => frame.push(mask_bit | len as u8),
+            len if len <= u16::MAX as usize => {
+                frame.push(mask_bit | 126);
+                frame.extend_from_slice(&(len as u16).to_be_bytes());
+            }
+            len => {
+                frame.push(mask_bit | 127);
+                frame.extend_from_slice(&(len as u64).to_be_bytes());
+            }
+        }
+
+        if self.is_server {
+            frame.extend_from_slice(payload);
+        } else {
+            let mut mask = [0u8; 4];
+            getrandom::fill(&mut mask).map_err(std::io::Error::other)?;
+            frame.extend_from_slice(&mask);
+            let start = frame.len();
+            frame.extend_from_slice(payload);
+            apply_mask(&mut frame[start..], mask);
+        }
+        self.socket.write(&frame).map(|_| ())
+    }
+}
+
+/// A decoded frame header; the payload follows the header in the buffer
+struct Frame {
+    fin: bool,
+    opcode: u8,
+    mask: Option<[u8; 4]>,
+    header_len: usize,
+    payload_len: usize,
+}
+
+/// Decode the frame at the start of `buf`. Frames from clients must be masked
+/// and frames from servers must not be.
+/// Returns None if the frame hasn't fully arrived yet, or a close code and message
+/// if the frame is invalid.
+fn decode_frame(buf: &[u8], from_client: bool) -> Result<Option<Frame>, (u16, String)> {
+    if buf.len() < 2 {
+        return Ok(None);
+    }
+    let fin = buf[0] & 0x80 != 0;
+    let opcode = buf[0] & 0x0F;
+    if buf[0] & 0x70 != 0 {
+        return Err((
+            CLOSE_PROTOCOL_ERROR,
+            "Reserved bits must be zero".to_string(),
+        ));
+    }
+    let masked = buf[1] & 0x80 != 0;
+    if masked != from_client {
+        let message = if from_client {
+            "Client frames must be masked"
+        } else {
+            "Server frames must not be masked"
+        };
+        return Err((CLOSE_PROTOCOL_ERROR, message.to_string()));
+    }
+
+    let (header_len, payload_len) = match buf[1] & 0x7F {
+        126 => {
+            if buf.len() < 4 {
+                return Ok(None);
+            }
+            (4, u16::from_be_bytes([buf[2], buf[3]]) as u64)
+        }
+        127 => {
+            if buf.len() < 10 {
+                return Ok(None);
+            }
+            let mut len = [0u8; 8];
+            len.copy_from_slice(&buf[2..10]);
+            (10, u64::from_be_bytes(len))
+        }
+        len => (2, len as u64),
+    };
+
+    if opcode >= OPCODE_CLOSE && (!fin || payload_len > 125) {
+        return Err((CLOSE_PROTOCOL_ERROR, "Invalid control frame".to_string()));
+    }
+    if payload_len > MAX_MESSAGE_SIZE as u64 {
+        return Err((CLOSE_TOO_BIG, "Message is too large".to_string()));
+    }
+    let payload_len = payload_len as usize;
+    let (mask, header_len) = if masked {
+        if buf.len() < header_len + 4 {
+            return Ok(None);
+        }
+        let mut mask = [0u8; 4];
+        mask.copy_from_slice(&buf[header_len..header_len + 4]);
+        (Some(mask), header_len + 4)
+    } else {
+        (None, header_len)
+    };
+    if buf.len() < header_len + payload_len {
+        return Ok(None);
+    }
+    Ok(Some(Frame {
+        fin,
+        opcode,
+        mask,
+        header_len,
+        payload_len,
+    }))
+}
+
+/// Mask or unmask a payload in place
+fn apply_mask(payload: &mut [u8], mask: [u8; 4]) {
+    for (i, byte) in payload.iter_mut().enumerate() {
+        *byte ^= mask[i % 4];
+    }
+}
+
+/// Compute the Sec-WebSocket-Accept value for a Sec-WebSocket-Key
+pub(crate) fn accept_key(key: &str) -> String {
+    let mut context = ring::digest::Context::new(&ring::digest::SHA1_FOR_LEGACY_USE_ONLY);
+    context.update(key.as_bytes());
+    context.update(ACCEPT_GUID.as_bytes());
+    base64_simd::STANDARD.encode_to_string(context.finish().as_ref())
+}
+
+/// Validate the handshake response head (status line and headers).
+/// Returns the subprotocol chosen by the server, or an error message.
+fn check_handshake(
+    head: &str,
+    expected_accept: &str,
+    protocols: &[String],
+) -> Result<String, String> {
+    let mut lines = head.split("\r\n");
+    let status_line = lines.next().unwrap_or_default();
+    let status = status_line.split(' ').nth(1).unwrap_or_default();
+    if status != "101" {
+        return Err(format!(
+            "Unexpected response code: {}",
+            if status.is_empty() { "none" } else { status }
+        ));
+    }
+
+    let mut upgrade = false;
+    let mut connection = false;
+    let mut accept = None;
+    let mut protocol = None;
+    for line in lines {
+        let Some((name, value)) = line.split_once(':') else {
+            continue;
+        };
+        let value = value.trim();
+        match name.trim().to_ascii_lowercase().as_str() {
+            "upgrade" => upgrade = value.eq_ignore_ascii_case("websocket"),
+            "connection" => {
+                connection = value
+                    .split(',')
+                    .any(|token| token.trim().eq_ignore_ascii_case("upgrade"))
+            }
+            "sec-websocket-accept" => accept = Some(value.to_string()),
+            "sec-websocket-protocol" => protocol = Some(value.to_string()),
+            "sec-websocket-extensions" if !value.is_empty() => {
+                return Err("Server sent an extension that wasn't requested".to_string());
+            }
+            _ => {}
+        }
+    }
+
+    if !upgrade {
+        return Err("Missing 'Upgrade: websocket' header".to_string());
+    }
+    if !connection {
+        return Err("Missing 'Connection: Upgrade' header".to_string());
+    }
+    if accept.as_deref() != Some(expected_accept) {
+        return Err("Incorrect 'Sec-WebSocket-Accept' header value".to_string());
+    }
+    match protocol {
+        Some(protocol) if !protocols.contains(&protocol) => Err(format!(
+            "Server sent a subprotocol that wasn't requested: {}",
+            protocol
+        )),
+        Some(protocol) => Ok(protocol),
+        None => Ok(String::new()),
+    }
+}
+
+/// True for close codes a peer may send (RFC 6455 section 7.4)
+fn is_valid_close_code(code: u16) -> bool {
+    matches!(code, 1000..=1003 | 1007..=1011 | 3000..=4999)
+}
@@ -8,6 +8,8 @@
 // don't want to run any tests until the runtime is
 // rip roaring and ready to execute JS
 
+use std::fs;
+use std::path::Path;
 use std::sync::Once;
 
 use jstime_core as jstime;
@@ -40,7 +42,7 @@ pub fn setup() -> SetupGuard {
 /// assert_eq!(result.unwrap(), "2");
 /// ```
 #[allow(dead_code)]
-pub fn run_test_script(code: &str) -> Result<String, String> {
+pub fn run_test_script(code: &str) -> Result<String, jstime::Error> {
     let _setup_guard = setup();
     let options = jstime::Options::default();
     let mut jstime = jstime::JSTime::new(options);
@@ -73,7 +75,25 @@ pub fn assert_api_exists(api_name: &str) -> bool {
 /// assert_eq!(get_type_of("setTimeout"), Ok("function".to_string()));
 /// ```
 #[allow(dead_code)]
-pub fn get_type_of(api_name: &str) -> Result<String, String> {
+pub fn get_type_of(api_name: &str) -> Result<String, jstime::Error> {
     let script = format!("typeof {};", api_name);
     run_test_script(&script)
 }
+
+/// Helper to create a directory structure
+#[allow(dead_code)]
+pub fn create_dir_all(path: &Path) {
+    fs::create_dir_all(path).expect("Failed to create directory");
+}
+
+/// Helper to write a file
+#[allow(dead_code)]
+pub fn write_file(path: &Path, content: &str) {
+    fs::write(path, content).expect("Failed to write file");
+}
+
+/// Helper to clean up test directory
+#[allow(dead_code)]
+pub fn cleanup(path: &Path) {
+    let _ = fs::remove_dir_all(path);
+}
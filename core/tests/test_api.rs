@@ -31,7 +31,7 @@ mod api {
         let mut jstime = jstime::JSTime::new(options);
         let err = match jstime.run_script("a", "jstime") {
             Ok(_result) => panic!(),
-            Err(e) => e,
+            Err(e) => e.to_string(),
         };
 
         // New format includes file:line, source code, caret indicator, error message, and stack
@@ -45,7 +45,7 @@ mod api {
 
         let err = match jstime.run_script("}", "jstime") {
             Ok(_result) => panic!(),
-            Err(e) => e,
+            Err(e) => e.to_string(),
         };
         // Syntax errors now include file:line, source code, caret indicator, and error message
         assert!(err.contains("jstime:1"));
@@ -425,6 +425,81 @@ mod tests {
         assert!(result.is_ok());
     }
 
+    #[test]
+    fn import_meta_filename_and_dirname() {
+        let _setup_guard = common::setup();
+
+        let test_dir = std::env::temp_dir().join("test_import_meta_paths");
+        std::fs::create_dir_all(&test_dir).unwrap();
+        let test_file = test_dir.join("paths.js");
+        std::fs::write(
+            &test_file,
+            "globalThis.testResult = [import.meta.filename, import.meta.dirname, \
+             import.meta.url === 'file://' + import.meta.filename].join('|');",
+        )
+        .unwrap();
+
+        let options = jstime::Options::default();
+        let mut jstime = jstime::JSTime::new(options);
+        let result = jstime.import(test_file.to_str().unwrap());
+        let paths = jstime.run_script("globalThis.testResult", "test");
+
+        std::fs::remove_dir_all(&test_dir).ok();
+
+        assert!(result.is_ok(), "{:?}", result);
+        let expected = format!(
+            "{}|{}|true",
+            test_file.to_str().unwrap(),
+            test_dir.to_str().unwrap()
+        );
+        assert_eq!(paths.unwrap(), expected);
+    }
+
+    #[test]
+    fn import_meta_resolve() {
+        let _setup_guard = common::setup();
+
+        let test_dir = std::env::temp_dir().join("test_import_meta_resolve");
+        let pkg_dir = test_dir.join("node_modules").join("meta-pkg");
+        std::fs::create_dir_all(&pkg_dir).unwrap();
+        std::fs::write(
+            pkg_dir.join("package.json"),
+            r#"{"name": "meta-pkg", "main": "main.js"}"#,
+        )
+        .unwrap();
+        std::fs::write(pkg_dir.join("main.js"), "export default 1;").unwrap();
+        std::fs::write(test_dir.join("asset.txt"), "asset").unwrap();
+        let test_file = test_dir.join("resolve.js");
+        std::fs::write(
+            &test_file,
+            r#"
+let missing;
+try {
+  import.meta.resolve('not-installed');
+} catch (e) {
+  missing = e.message.includes("Cannot find module 'not-installed'");
+}
+globalThis.testResult = [
+  import.meta.resolve('./asset.txt') === 'file://' + import.meta.dirname + '/asset.txt',
+  import.meta.resolve('meta-pkg').endsWith('/node_modules/meta-pkg/main.js'),
+  import.meta.resolve('node:fs/promises'),
+  missing,
+].join(',');
+"#,
+        )
+        .unwrap();
+
+        let options = jstime::Options::default();
+        let mut jstime = jstime::JSTime::new(options);
+        let result = jstime.import(test_file.to_str().unwrap());
+        let resolved = jstime.run_script("globalThis.testResult", "test");
+
+        std::fs::remove_dir_all(&test_dir).ok();
+
+        assert!(result.is_ok(), "{:?}", result);
+        assert_eq!(resolved.unwrap(), "true,true,node:fs/promises,true");
+    }
+
     #[test]
     fn structuredclone_exists() {
         let result = common::get_type_of("structuredClone");
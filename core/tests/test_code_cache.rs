@@ -0,0 +1,201 @@
+use jstime_core as jstime;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+mod common;
+
+/// Import `entry` with the code cache in `cache_dir`, and return
+/// globalThis.testResult
+fn import_cached(entry: &Path, cache_dir: &Path) -> Result<String, jstime::Error> {
+    let options = jstime::Options::default()
+        .with_cache_dir(cache_dir)
+        .with_code_cache(true);
+    let mut jstime = jstime::JSTime::new(options);
+    jstime.import(entry.to_str().unwrap())?;
+    jstime.run_script("globalThis.testResult", "test")
+}
+
+/// The code cache entries in `cache_dir` with their contents, sorted by name
+fn entries(cache_dir: &Path) -> Vec<(PathBuf, Vec<u8>)> {
+    let mut entries: Vec<_> = fs::read_dir(cache_dir.join("code"))
+        .map(|dir| {
+            dir.filter_map(|entry| entry.ok())
+                .map(|entry| entry.path())
+                .map(|path| {
+                    let contents = fs::read(&path).unwrap();
+                    (path, contents)
+                })
+                .collect()
+        })
+        .unwrap_or_default();
+    entries.sort();
+    entries
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_modules_are_cached_and_reused() {
+        let _setup_guard = common::setup();
+
+        let temp_dir = std::env::temp_dir().join("jstime_test_code_cache_modules");
+        common::cleanup(&temp_dir);
+        common::create_dir_all(&temp_dir);
+        let cache_dir = temp_dir.join("cache");
+
+        common::write_file(
+            &temp_dir.join("dep.js"),
+            "export const value = (n) => n * 2;",
+        );
+        common::write_file(
+            &temp_dir.join("main.js"),
+            "import { value } from './dep.js';\nglobalThis.testResult = value(21);",
+        );
+
+        // Nothing is cached without the option
+        let mut jstime = jstime::JSTime::new(jstime::Options::default().with_cache_dir(&cache_dir));
+        jstime
+            .import(temp_dir.join("main.js").to_str().unwrap())
+            .unwrap();
+        assert!(entries(&cache_dir).is_empty());
+
+        assert_eq!(
+            import_cached(&temp_dir.join("main.js"), &cache_dir).unwrap(),
+            "42"
+        );
+        let cached = entries(&cache_dir);
+        assert_eq!(cached.len(), 2);
+        let modified: Vec<_> = cached
+            .iter()
+            .map(|(path, _)| fs::metadata(path).unwrap().modified().unwrap())
+            .collect();
+
+        // Accepted caches aren't written again
+        assert_eq!(
+            import_cached(&temp_dir.join("main.js"), &cache_dir).unwrap(),
+            "42"
+        );
+        assert_eq!(entries(&cache_dir), cached);
+        for ((path, _), modified) in cached.iter().zip(modified) {
+            assert_eq!(fs::metadata(path).unwrap().modified().unwrap(), modified);
+        }
+
+        common::cleanup(&temp_dir);
+    }
+
+    #[test]
+    fn test_changed_module_is_recompiled() {
+        let _setup_guard = common::setup();
+
+        let temp_dir = std::env::temp_dir().join("jstime_test_code_cache_changed");
+        common::cleanup(&temp_dir);
+        common::create_dir_all(&temp_dir);
+        let cache_dir = temp_dir.join("cache");
+        let main = temp_dir.join("main.js");
+
+        common::write_file(&main, "globalThis.testResult = 'first';");
+        assert_eq!(import_cached(&main, &cache_dir).unwrap(), "first");
+        let cached = entries(&cache_dir);
+
+        // The same length, so only the hash and mtime tell it apart
+        common::write_file(&main, "globalThis.testResult = 'other';");
+        assert_eq!(import_cached(&main, &cache_dir).unwrap(), "other");
+        let recached = entries(&cache_dir);
+        assert_eq!(recached.len(), 1);
+        assert_ne!(recached, cached);
+
+        common::cleanup(&temp_dir);
+    }
+
+    #[test]
+    fn test_rejected_cache_is_replaced() {
+        let _setup_guard = common::setup();
+
+        let temp_dir = std::env::temp_dir().join("jstime_test_code_cache_rejected");
+        common::cleanup(&temp_dir);
+        common::create_dir_all(&temp_dir);
+        let cache_dir = temp_dir.join("cache");
+        let main = temp_dir.join("main.js");
+
+        common::write_file(
+            &main,
+            "globalThis.testResult = [1, 2, 3].map((n) => n + 1).join();",
+        );
+        assert_eq!(import_cached(&main, &cache_dir).unwrap(), "2,3,4");
+
+        // Keep the header line so the entry is used, but corrupt the code
+        let (path, contents) = entries(&cache_dir).remove(0);
+        let header_end = contents.iter().position(|&b| b == b'\n').unwrap() + 1;
+        let mut corrupted = contents[..header_end].to_vec();
+        corrupted.extend(std::iter::repeat_n(0xAB, contents.len() - header_end));
+        fs::write(&path, &corrupted).unwrap();
+
+        assert_eq!(import_cached(&main, &cache_dir).unwrap(), "2,3,4");
+        assert_ne!(fs::read(&path).unwrap(), corrupted);
+
+        // An entry that isn't a code cache at all is also ignored
+        fs::write(&path, "not a code cache").unwrap();
+        assert_eq!(import_cached(&main, &cache_dir).unwrap(), "2,3,4");
+
+        common::cleanup(&temp_dir);
+    }
+
+    #[test]
+    fn test_script_files_are_cached() {
+        let _setup_guard = common::setup();
+
+        let temp_dir = std::env::temp_dir().join("jstime_test_code_cache_scripts");
+        common::cleanup(&temp_dir);
+        common::create_dir_all(&temp_dir);
+        let cache_dir = temp_dir.join("cache");
+        let script = temp_dir.join("script.js");
+        let source = "function add(a, b) { return a + b; }\nadd(40, 2);";
+        common::write_file(&script, source);
+
+        let options = jstime::Options::default()
+            .with_cache_dir(&cache_dir)
+            .with_code_cache(true);
+        let mut jstime = jstime::JSTime::new(options);
+        let result = jstime.run_script(source, script.to_str().unwrap());
+        assert_eq!(result.unwrap(), "42");
+        assert_eq!(entries(&cache_dir).len(), 1);
+
+        // Code that isn't from a file isn't cached
+        let result = jstime.run_script("1 + 1", "test");
+        assert_eq!(result.unwrap(), "2");
+        assert_eq!(entries(&cache_dir).len(), 1);
+
+        let result = jstime.run_script(source, script.to_str().unwrap());
+        assert_eq!(result.unwrap(), "42");
+
+        common::cleanup(&temp_dir);
+    }
+
+    #[test]
+    fn test_failed_runs_are_not_cached() {
+        let _setup_guard = common::setup();
+
+        let temp_dir = std::env::temp_dir().join("jstime_test_code_cache_failed");
+        common::cleanup(&temp_dir);
+        common::create_dir_all(&temp_dir);
+        let cache_dir = temp_dir.join("cache");
+
+        // The cache is created once the code has run, which this doesn't
+        let main = temp_dir.join("main.js");
+        common::write_file(&main, "throw new Error('fails');");
+        assert!(import_cached(&main, &cache_dir).is_err());
+        let script = temp_dir.join("script.js");
+        let source = "throw new Error('fails');";
+        common::write_file(&script, source);
+        let options = jstime::Options::default()
+            .with_cache_dir(&cache_dir)
+            .with_code_cache(true);
+        let mut jstime = jstime::JSTime::new(options);
+        assert!(jstime.run_script(source, script.to_str().unwrap()).is_err());
+        assert!(entries(&cache_dir).is_empty());
+
+        common::cleanup(&temp_dir);
+    }
+}
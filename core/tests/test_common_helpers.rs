@@ -22,7 +22,7 @@ mod tests {
     fn test_run_test_script_error() {
         let result = common::run_test_script("undefinedVariable");
         assert!(result.is_err());
-        assert!(result.unwrap_err().contains("ReferenceError"));
+        assert!(result.unwrap_err().to_string().contains("ReferenceError"));
     }
 
     #[test]
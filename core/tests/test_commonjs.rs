@@ -0,0 +1,403 @@
+use jstime_core as jstime;
+use std::path::Path;
+
+mod common;
+
+/// Import `entry` in a fresh runtime and return globalThis.testResult
+fn import_and_get_result(entry: &Path) -> Result<String, jstime::Error> {
+    let options = jstime::Options::default();
+    let mut jstime = jstime::JSTime::new(options);
+    jstime.import(entry.to_str().unwrap())?;
+    jstime.run_script("globalThis.testResult", "test")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_import_cjs_default_and_named_exports() {
+        let _setup_guard = common::setup();
+
+        let temp_dir = std::env::temp_dir().join("jstime_test_cjs_named");
+        common::cleanup(&temp_dir);
+        common::create_dir_all(&temp_dir);
+
+        common::write_file(
+            &temp_dir.join("lib.cjs"),
+            r#"
+exports.add = (a, b) => a + b;
+exports.name = 'lib';
+Object.defineProperty(exports, 'version', { enumerable: true, get: () => 2 });
+"#,
+        );
+        common::write_file(
+            &temp_dir.join("test.js"),
+            r#"
+import lib, { add, name, version } from './lib.cjs';
+globalThis.testResult = [add(1, 2), name, version, typeof lib.add].join(',');
+"#,
+        );
+
+        let result = import_and_get_result(&temp_dir.join("test.js"));
+        assert_eq!(result.unwrap(), "3,lib,2,function");
+
+        common::cleanup(&temp_dir);
+    }
+
+    #[test]
+    fn test_module_exports_object_literal() {
+        let _setup_guard = common::setup();
+
+        let temp_dir = std::env::temp_dir().join("jstime_test_cjs_literal");
+        common::cleanup(&temp_dir);
+        common::create_dir_all(&temp_dir);
+
+        common::write_file(
+            &temp_dir.join("lib.cjs"),
+            r#"
+function greet(who) { return `hello ${who}`; }
+const answer = 42;
+module.exports = { greet, answer, 'quoted-key': true };
+"#,
+        );
+        common::write_file(
+            &temp_dir.join("test.js"),
+            r#"
+import * as ns from './lib.cjs';
+globalThis.testResult = [ns.greet('cjs'), ns.answer, ns['quoted-key'], ns.default.answer].join(',');
+"#,
+        );
+
+        let result = import_and_get_result(&temp_dir.join("test.js"));
+        assert_eq!(result.unwrap(), "hello cjs,42,true,42");
+
+        common::cleanup(&temp_dir);
+    }
+
+    #[test]
+    fn test_require_relative_json_and_builtins() {
+        let _setup_guard = common::setup();
+
+        let temp_dir = std::env::temp_dir().join("jstime_test_cjs_require");
+        common::cleanup(&temp_dir);
+        common::create_dir_all(&temp_dir.join("util"));
+
+        common::write_file(&temp_dir.join("data.json"), r#"{"value": 7}"#);
+        common::write_file(
+            &temp_dir.join("util").join("index.js"),
+            "module.exports = function double(x) { return x * 2; };",
+        );
+        common::write_file(
+            &temp_dir.join("main.cjs"),
+            r#"
+const double = require('./util');
+const data = require('./data.json');
+const EventEmitter = require('events');
+const { Buffer } = require('node:buffer');
+module.exports = [
+  double(data.value),
+  typeof EventEmitter,
+  Buffer.from('hi').toString('hex'),
+  require('process') === process,
+  require('./data.json') === data,
+].join(',');
+"#,
+        );
+        common::write_file(
+            &temp_dir.join("test.js"),
+            r#"
+import result from './main.cjs';
+globalThis.testResult = result;
+"#,
+        );
+
+        let result = import_and_get_result(&temp_dir.join("test.js"));
+        assert_eq!(result.unwrap(), "14,function,6869,true,true");
+
+        common::cleanup(&temp_dir);
+    }
+
+    #[test]
+    fn test_module_scope_variables() {
+        let _setup_guard = common::setup();
+
+        let temp_dir = std::env::temp_dir().join("jstime_test_cjs_scope");
+        common::cleanup(&temp_dir);
+        common::create_dir_all(&temp_dir);
+
+        common::write_file(
+            &temp_dir.join("scope.cjs"),
+            r#"#!/usr/bin/env jstime
+module.exports = {
+  filename: __filename.endsWith('/scope.cjs'),
+  dirname: __filename.startsWith(__dirname + '/'),
+  id: module.id === __filename,
+  thisIsExports: this === exports,
+  loaded: module.loaded,
+  resolve: require.resolve('./scope.cjs') === __filename,
+  cached: require.cache[__filename] === module,
+};
+"#,
+        );
+        common::write_file(
+            &temp_dir.join("test.js"),
+            r#"
+import scope from './scope.cjs';
+globalThis.testResult = JSON.stringify(scope);
+"#,
+        );
+
+        let result = import_and_get_result(&temp_dir.join("test.js"));
+        assert_eq!(
+            result.unwrap(),
+            r#"{"filename":true,"dirname":true,"id":true,"thisIsExports":true,"loaded":false,"resolve":true,"cached":true}"#
+        );
+
+        common::cleanup(&temp_dir);
+    }
+
+    #[test]
+    fn test_type_commonjs_package_in_node_modules() {
+        let _setup_guard = common::setup();
+
+        let temp_dir = std::env::temp_dir().join("jstime_test_cjs_package");
+        common::cleanup(&temp_dir);
+        let pkg_dir = temp_dir.join("node_modules").join("cjs-pkg");
+        common::create_dir_all(&pkg_dir.join("lib"));
+
+        common::write_file(
+            &pkg_dir.join("package.json"),
+            r#"{"name": "cjs-pkg", "type": "commonjs", "main": "lib/index.js"}"#,
+        );
+        common::write_file(
+            &pkg_dir.join("lib").join("index.js"),
+            "module.exports = require('./impl');",
+        );
+        common::write_file(
+            &pkg_dir.join("lib").join("impl.js"),
+            r#"
+let count = 0;
+exports.increment = () => ++count;
+"#,
+        );
+        common::write_file(
+            &temp_dir.join("test.js"),
+            r#"
+import pkg, { increment } from 'cjs-pkg';
+increment();
+globalThis.testResult = `${increment()},${pkg.increment()}`;
+"#,
+        );
+
+        let result = import_and_get_result(&temp_dir.join("test.js"));
+        assert_eq!(result.unwrap(), "2,3");
+
+        common::cleanup(&temp_dir);
+    }
+
+    #[test]
+    fn test_circular_require() {
+        let _setup_guard = common::setup();
+
+        let temp_dir = std::env::temp_dir().join("jstime_test_cjs_circular");
+        common::cleanup(&temp_dir);
+        common::create_dir_all(&temp_dir);
+
+        common::write_file(
+            &temp_dir.join("a.cjs"),
+            r#"
+exports.done = false;
+const b = require('./b.cjs');
+exports.sawB = b.done;
+exports.done = true;
+"#,
+        );
+        common::write_file(
+            &temp_dir.join("b.cjs"),
+            r#"
+const a = require('./a.cjs');
+exports.sawPartialA = a.done === false;
+exports.done = true;
+"#,
+        );
+        common::write_file(
+            &temp_dir.join("test.js"),
+            r#"
+import a from './a.cjs';
+import b from './b.cjs';
+globalThis.testResult = `${a.sawB},${b.sawPartialA},${a.done}`;
+"#,
+        );
+
+        let result = import_and_get_result(&temp_dir.join("test.js"));
+        assert_eq!(result.unwrap(), "true,true,true");
+
+        common::cleanup(&temp_dir);
+    }
+
+    #[test]
+    fn test_require_errors() {
+        let _setup_guard = common::setup();
+
+        let temp_dir = std::env::temp_dir().join("jstime_test_cjs_errors");
+        common::cleanup(&temp_dir);
+        common::create_dir_all(&temp_dir);
+
+        common::write_file(&temp_dir.join("esm.mjs"), "export const x = 1;");
+        common::write_file(&temp_dir.join("broken.cjs"), "exports.x = ;");
+        common::write_file(&temp_dir.join("throws.cjs"), "throw new Error('boom');");
+        common::write_file(
+            &temp_dir.join("main.cjs"),
+            r#"
+const codes = [];
+for (const specifier of ['./missing', 'missing-package', './esm.mjs', './broken.cjs', './throws.cjs', './throws.cjs']) {
+  try {
+    require(specifier);
+    codes.push('ok');
+  } catch (error) {
+    codes.push(error.code || error.name + ':' + error.message);
+  }
+}
+module.exports = codes.join(',');
+"#,
+        );
+        common::write_file(
+            &temp_dir.join("test.js"),
+            r#"
+import result from './main.cjs';
+globalThis.testResult = result;
+"#,
+        );
+
+        let result = import_and_get_result(&temp_dir.join("test.js"));
+        assert_eq!(
+            result.unwrap(),
+            "MODULE_NOT_FOUND,MODULE_NOT_FOUND,ERR_REQUIRE_ESM,SyntaxError:Unexpected token ';',Error:boom,Error:boom"
+        );
+
+        common::cleanup(&temp_dir);
+    }
+
+    #[test]
+    fn test_typeless_js_is_still_esm() {
+        let _setup_guard = common::setup();
+
+        let temp_dir = std::env::temp_dir().join("jstime_test_cjs_typeless");
+        common::cleanup(&temp_dir);
+        common::create_dir_all(&temp_dir);
+
+        common::write_file(&temp_dir.join("esm.js"), "export const value = 'esm';");
+        common::write_file(
+            &temp_dir.join("test.js"),
+            r#"
+import { value } from './esm.js';
+globalThis.testResult = value;
+"#,
+        );
+
+        let result = import_and_get_result(&temp_dir.join("test.js"));
+        assert_eq!(result.unwrap(), "esm");
+
+        common::cleanup(&temp_dir);
+    }
+
+    #[test]
+    fn test_create_require() {
+        let _setup_guard = common::setup();
+
+        let temp_dir = std::env::temp_dir().join("jstime_test_create_require");
+        common::cleanup(&temp_dir);
+        let pkg_dir = temp_dir.join("node_modules").join("cjs-dep");
+        common::create_dir_all(&pkg_dir);
+        common::create_dir_all(&temp_dir.join("config"));
+
+        common::write_file(&pkg_dir.join("package.json"), r#"{"name": "cjs-dep"}"#);
+        common::write_file(&pkg_dir.join("index.js"), "exports.kind = 'dep';");
+        common::write_file(
+            &temp_dir.join("config").join("settings.json"),
+            r#"{"mode": "test"}"#,
+        );
+        common::write_file(
+            &temp_dir.join("test.js"),
+            r#"
+import { createRequire } from 'node:module';
+const require = createRequire(import.meta.url);
+const fromDir = createRequire(import.meta.dirname + '/config/');
+let invalid;
+try {
+  createRequire('relative.js');
+} catch (e) {
+  invalid = e.code;
+}
+globalThis.testResult = [
+  require('cjs-dep').kind,
+  require('./config/settings.json').mode,
+  fromDir('./settings.json') === require('./config/settings.json'),
+  require.resolve('./config/settings.json') === import.meta.dirname + '/config/settings.json',
+  typeof require('node:events'),
+  invalid,
+].join(',');
+"#,
+        );
+
+        let result = import_and_get_result(&temp_dir.join("test.js"));
+        assert_eq!(
+            result.unwrap(),
+            "dep,test,true,true,function,ERR_INVALID_ARG_VALUE"
+        );
+
+        common::cleanup(&temp_dir);
+    }
+
+    #[test]
+    fn test_require_uses_require_condition() {
+        let _setup_guard = common::setup();
+
+        let temp_dir = std::env::temp_dir().join("jstime_test_cjs_conditions");
+        common::cleanup(&temp_dir);
+        let pkg_dir = temp_dir.join("node_modules").join("dual-pkg");
+        common::create_dir_all(&pkg_dir);
+
+        common::write_file(
+            &pkg_dir.join("package.json"),
+            r#"{
+  "name": "dual-pkg",
+  "exports": {
+    ".": { "import": "./index.mjs", "require": "./index.cjs" },
+    "./package.json": "./package.json"
+  }
+}"#,
+        );
+        common::write_file(&pkg_dir.join("index.mjs"), "export const format = 'esm';");
+        common::write_file(&pkg_dir.join("index.cjs"), "exports.format = 'cjs';");
+        common::write_file(
+            &temp_dir.join("main.cjs"),
+            "module.exports = require('dual-pkg').format + ',' + require('dual-pkg/package.json').name;",
+        );
+        common::write_file(
+            &temp_dir.join("test.js"),
+            r#"
+import { format } from 'dual-pkg';
+import required from './main.cjs';
+globalThis.testResult = `${format},${required}`;
+"#,
+        );
+
+        let result = import_and_get_result(&temp_dir.join("test.js"));
+        assert_eq!(result.unwrap(), "esm,cjs,dual-pkg");
+
+        common::cleanup(&temp_dir);
+    }
+
+    #[test]
+    fn test_node_module_builtin() {
+        let _setup_guard = common::setup();
+        let result = common::run_test_script(
+            "const Module = globalThis.__node_modules['node:module']; \
+             [typeof Module, Module.isBuiltin('node:events'), Module.isBuiltin('events'), \
+              Module.isBuiltin('left-pad'), Module.builtinModules.includes('buffer')].join(',')",
+        );
+        assert_eq!(result.unwrap(), "function,true,true,false,true");
+    }
+}
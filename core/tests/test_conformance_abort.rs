@@ -0,0 +1,277 @@
+use jstime_core as jstime;
+
+mod common;
+
+#[cfg(test)]
+mod conformance_abort {
+    use super::*;
+
+    // DOMException Tests
+    #[test]
+    fn dom_exception_exists() {
+        let result = common::get_type_of("DOMException");
+        assert_eq!(result.unwrap(), "function");
+    }
+
+    #[test]
+    fn dom_exception_name_message_code() {
+        let result = common::run_test_script(
+            "const e = new DOMException('stopped', 'AbortError'); \
+             JSON.stringify({ name: e.name, message: e.message, code: e.code, \
+               isError: e instanceof Error, str: String(e) });",
+        );
+        let json: serde_json::Value = serde_json::from_str(&result.unwrap()).unwrap();
+        assert_eq!(json["name"], "AbortError");
+        assert_eq!(json["message"], "stopped");
+        assert_eq!(json["code"], 20);
+        assert_eq!(json["isError"], true);
+        assert_eq!(json["str"], "AbortError: stopped");
+    }
+
+    #[test]
+    fn dom_exception_defaults() {
+        let result = common::run_test_script(
+            "const e = new DOMException(); \
+             JSON.stringify({ name: e.name, message: e.message, code: e.code });",
+        );
+        let json: serde_json::Value = serde_json::from_str(&result.unwrap()).unwrap();
+        assert_eq!(json["name"], "Error");
+        assert_eq!(json["message"], "");
+        assert_eq!(json["code"], 0);
+    }
+
+    #[test]
+    fn dom_exception_options_object() {
+        let result = common::run_test_script(
+            "const e = new DOMException('late', { name: 'TimeoutError', cause: 42 }); \
+             `${e.name}:${e.code}:${e.cause}`;",
+        );
+        assert_eq!(result.unwrap(), "TimeoutError:23:42");
+    }
+
+    #[test]
+    fn dom_exception_constants() {
+        let result = common::run_test_script(
+            "`${DOMException.ABORT_ERR}:${DOMException.TIMEOUT_ERR}:${new DOMException().ABORT_ERR}`;",
+        );
+        assert_eq!(result.unwrap(), "20:23:20");
+    }
+
+    // AbortController Tests
+    #[test]
+    fn abort_controller_exists() {
+        let result = common::get_type_of("AbortController");
+        assert_eq!(result.unwrap(), "function");
+    }
+
+    #[test]
+    fn abort_signal_exists() {
+        let result = common::get_type_of("AbortSignal");
+        assert_eq!(result.unwrap(), "function");
+    }
+
+    #[test]
+    fn abort_signal_illegal_constructor() {
+        let result = common::run_test_script(
+            "try { new AbortSignal(); 'no error'; } catch (e) { e instanceof TypeError; }",
+        );
+        assert_eq!(result.unwrap(), "true");
+    }
+
+    #[test]
+    fn abort_signal_cannot_be_aborted_without_controller() {
+        let result = common::run_test_script(
+            "const c = new AbortController(); \
+             const names = Object.getOwnPropertyNames(AbortSignal.prototype).filter(n => n.startsWith('_')); \
+             const symbols = Object.getOwnPropertySymbols(AbortSignal.prototype).filter(s => s !== Symbol.toStringTag); \
+             `${names.length}:${symbols.length}:${c.signal.aborted}`;",
+        );
+        assert_eq!(result.unwrap(), "0:0:false");
+    }
+
+    #[test]
+    fn abort_controller_signal_initial_state() {
+        let result = common::run_test_script(
+            "const c = new AbortController(); \
+             `${c.signal instanceof AbortSignal}:${c.signal instanceof EventTarget}:${c.signal.aborted}:${c.signal.reason}`;",
+        );
+        assert_eq!(result.unwrap(), "true:true:false:undefined");
+    }
+
+    #[test]
+    fn abort_controller_abort_default_reason() {
+        let result = common::run_test_script(
+            "const c = new AbortController(); \
+             c.abort(); \
+             `${c.signal.aborted}:${c.signal.reason instanceof DOMException}:${c.signal.reason.name}`;",
+        );
+        assert_eq!(result.unwrap(), "true:true:AbortError");
+    }
+
+    #[test]
+    fn abort_controller_abort_custom_reason() {
+        let result = common::run_test_script(
+            "const c = new AbortController(); \
+             c.abort('because'); \
+             c.signal.reason;",
+        );
+        assert_eq!(result.unwrap(), "because");
+    }
+
+    #[test]
+    fn abort_event_fires_once() {
+        let result = common::run_test_script(
+            "const c = new AbortController(); \
+             let count = 0; \
+             let handler = 0; \
+             c.signal.addEventListener('abort', (e) => { count++; }); \
+             c.signal.onabort = (e) => { handler += e.type === 'abort' ? 1 : 0; }; \
+             c.abort(); \
+             c.abort(); \
+             `${count}:${handler}`;",
+        );
+        assert_eq!(result.unwrap(), "1:1");
+    }
+
+    #[test]
+    fn abort_reason_not_replaced() {
+        let result = common::run_test_script(
+            "const c = new AbortController(); \
+             c.abort('first'); \
+             c.abort('second'); \
+             c.signal.reason;",
+        );
+        assert_eq!(result.unwrap(), "first");
+    }
+
+    #[test]
+    fn abort_signal_throw_if_aborted() {
+        let result = common::run_test_script(
+            "const c = new AbortController(); \
+             c.signal.throwIfAborted(); \
+             c.abort(new RangeError('stop')); \
+             try { c.signal.throwIfAborted(); 'no error'; } catch (e) { e.message; }",
+        );
+        assert_eq!(result.unwrap(), "stop");
+    }
+
+    #[test]
+    fn abort_signal_static_abort() {
+        let result = common::run_test_script(
+            "const s = AbortSignal.abort(); \
+             `${s.aborted}:${s.reason.name}`;",
+        );
+        assert_eq!(result.unwrap(), "true:AbortError");
+    }
+
+    #[test]
+    fn abort_signal_timeout() {
+        let _setup_guard = common::setup();
+        let options = jstime::Options::default();
+        let mut jstime = jstime::JSTime::new(options);
+        jstime
+            .run_script(
+                "globalThis.result = null; \
+                 const s = AbortSignal.timeout(10); \
+                 s.addEventListener('abort', () => { \
+                   globalThis.result = `${s.aborted}:${s.reason.name}:${s.reason.code}`; \
+                 }); \
+                 setTimeout(() => {}, 50);",
+                "conformance_abort",
+            )
+            .unwrap();
+        let result = jstime.run_script("globalThis.result;", "conformance_abort");
+        assert_eq!(result.unwrap(), "true:TimeoutError:23");
+    }
+
+    #[test]
+    fn abort_signal_timeout_does_not_keep_loop_alive() {
+        let _setup_guard = common::setup();
+        let options = jstime::Options::default();
+        let mut jstime = jstime::JSTime::new(options);
+        let start = std::time::Instant::now();
+        jstime
+            .run_script("AbortSignal.timeout(5000);", "conformance_abort")
+            .unwrap();
+        assert!(start.elapsed() < std::time::Duration::from_secs(2));
+    }
+
+    #[test]
+    fn abort_signal_any() {
+        let result = common::run_test_script(
+            "const a = new AbortController(); \
+             const b = new AbortController(); \
+             const s = AbortSignal.any([a.signal, b.signal]); \
+             const before = s.aborted; \
+             b.abort('from b'); \
+             a.abort('from a'); \
+             `${before}:${s.aborted}:${s.reason}`;",
+        );
+        assert_eq!(result.unwrap(), "false:true:from b");
+    }
+
+    #[test]
+    fn abort_signal_any_already_aborted() {
+        let result = common::run_test_script(
+            "const s = AbortSignal.any([new AbortController().signal, AbortSignal.abort('done')]); \
+             `${s.aborted}:${s.reason}`;",
+        );
+        assert_eq!(result.unwrap(), "true:done");
+    }
+
+    #[test]
+    fn abort_signal_any_rejects_non_signals() {
+        let result = common::run_test_script(
+            "try { AbortSignal.any([{}]); 'no error'; } catch (e) { e instanceof TypeError; }",
+        );
+        assert_eq!(result.unwrap(), "true");
+    }
+
+    // Request integration
+    #[test]
+    fn request_has_signal() {
+        let result = common::run_test_script(
+            "const r = new Request('http://example.com'); \
+             `${r.signal instanceof AbortSignal}:${r.signal.aborted}`;",
+        );
+        assert_eq!(result.unwrap(), "true:false");
+    }
+
+    #[test]
+    fn request_uses_init_signal() {
+        let result = common::run_test_script(
+            "const c = new AbortController(); \
+             const r = new Request('http://example.com', { signal: c.signal }); \
+             const copy = new Request(r); \
+             `${r.signal === c.signal}:${copy.signal === c.signal}:${r.clone().signal === c.signal}`;",
+        );
+        assert_eq!(result.unwrap(), "true:true:true");
+    }
+
+    #[test]
+    fn request_rejects_invalid_signal() {
+        let result = common::run_test_script(
+            "try { new Request('http://example.com', { signal: {} }); 'no error'; } \
+             catch (e) { e instanceof TypeError; }",
+        );
+        assert_eq!(result.unwrap(), "true");
+    }
+
+    #[test]
+    fn fetch_with_aborted_signal_rejects() {
+        let _setup_guard = common::setup();
+        let options = jstime::Options::default();
+        let mut jstime = jstime::JSTime::new(options);
+        jstime
+            .run_script(
+                "globalThis.result = null; \
+                 fetch('http://example.com', { signal: AbortSignal.abort() }) \
+                   .then(() => { globalThis.result = 'resolved'; }) \
+                   .catch(e => { globalThis.result = `${e instanceof DOMException}:${e.name}`; });",
+                "conformance_abort",
+            )
+            .unwrap();
+        let result = jstime.run_script("globalThis.result;", "conformance_abort");
+        assert_eq!(result.unwrap(), "true:AbortError");
+    }
+}
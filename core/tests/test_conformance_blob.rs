@@ -0,0 +1,266 @@
+use jstime_core as jstime;
+
+mod common;
+
+#[cfg(test)]
+mod conformance_blob {
+    use super::*;
+
+    // Run a script that settles asynchronously and read back globalThis.result
+    fn run_async(script: &str) -> Result<String, jstime::Error> {
+        let _setup_guard = common::setup();
+        let options = jstime::Options::default();
+        let mut jstime = jstime::JSTime::new(options);
+        jstime.run_script(
+            &format!("globalThis.result = null; {}", script),
+            "conformance_blob",
+        )?;
+        jstime.run_script("globalThis.result;", "conformance_blob")
+    }
+
+    // Blob Tests
+    #[test]
+    fn blob_exists() {
+        let result = common::get_type_of("Blob");
+        assert_eq!(result.unwrap(), "function");
+    }
+
+    #[test]
+    fn blob_empty() {
+        let result = common::run_test_script(
+            "const b = new Blob(); `${b.size}:${JSON.stringify(b.type)}:${Object.prototype.toString.call(b)}`;",
+        );
+        assert_eq!(result.unwrap(), "0:\"\":[object Blob]");
+    }
+
+    #[test]
+    fn blob_size_counts_utf8_bytes() {
+        let result = common::run_test_script("new Blob(['héllo', ' ', '€']).size;");
+        assert_eq!(result.unwrap(), "10");
+    }
+
+    #[test]
+    fn blob_mixed_parts() {
+        let result = run_async(
+            "const inner = new Blob(['b']); \
+             const parts = ['a', inner, new Uint8Array([99]), new Uint16Array([0x6564]).buffer, \
+               new DataView(new Uint8Array([0, 102, 0]).buffer, 1, 1)]; \
+             new Blob(parts).text().then(t => { globalThis.result = t; });",
+        );
+        assert_eq!(result.unwrap(), "abcdef");
+    }
+
+    #[test]
+    fn blob_parts_are_copied() {
+        let result = run_async(
+            "const bytes = new Uint8Array([1, 2, 3]); \
+             const b = new Blob([bytes]); \
+             bytes[0] = 9; \
+             b.bytes().then(a => { globalThis.result = a.join(','); });",
+        );
+        assert_eq!(result.unwrap(), "1,2,3");
+    }
+
+    #[test]
+    fn blob_type_normalized() {
+        let result = common::run_test_script(
+            "`${new Blob([], { type: 'Text/HTML' }).type}|${new Blob([], { type: 'a\\u00e9' }).type}`;",
+        );
+        assert_eq!(result.unwrap(), "text/html|");
+    }
+
+    #[test]
+    fn blob_rejects_non_sequence_parts() {
+        let result = common::run_test_script(
+            "try { new Blob('abc'); 'no error'; } catch (e) { e instanceof TypeError; }",
+        );
+        assert_eq!(result.unwrap(), "true");
+    }
+
+    #[test]
+    fn blob_endings_native() {
+        let result = run_async(
+            "new Blob(['a\\r\\nb\\rc'], { endings: 'native' }).text() \
+               .then(t => { globalThis.result = JSON.stringify(t); });",
+        );
+        assert_eq!(result.unwrap(), "\"a\\nb\\nc\"");
+    }
+
+    #[test]
+    fn blob_slice() {
+        let result = run_async(
+            "const b = new Blob(['hello world'], { type: 'text/plain' }); \
+             const s = b.slice(6); \
+             Promise.all([s.text(), b.slice(-5, -2).text(), b.slice(3, 1).text()]) \
+               .then(([a, c, d]) => { globalThis.result = `${a}:${c}:${JSON.stringify(d)}:${s.type === ''}`; });",
+        );
+        assert_eq!(result.unwrap(), "world:wor:\"\":true");
+    }
+
+    #[test]
+    fn blob_slice_of_slice_with_type() {
+        let result = run_async(
+            "const s = new Blob(['0123456789']).slice(2, 8).slice(1, 3, 'X/Y'); \
+             s.text().then(t => { globalThis.result = `${t}:${s.size}:${s.type}:${s instanceof Blob}`; });",
+        );
+        assert_eq!(result.unwrap(), "34:2:x/y:true");
+    }
+
+    #[test]
+    fn blob_array_buffer() {
+        let result = run_async(
+            "new Blob([new Uint8Array([1, 2, 255])]).arrayBuffer().then(buf => { \
+               globalThis.result = `${buf instanceof ArrayBuffer}:${new Uint8Array(buf).join(',')}`; \
+             });",
+        );
+        assert_eq!(result.unwrap(), "true:1,2,255");
+    }
+
+    #[test]
+    fn blob_text_strips_bom() {
+        let result = run_async(
+            "new Blob([new Uint8Array([0xef, 0xbb, 0xbf, 0x68, 0x69])]).text() \
+               .then(t => { globalThis.result = `${t}:${t.length}`; });",
+        );
+        assert_eq!(result.unwrap(), "hi:2");
+    }
+
+    #[test]
+    fn blob_stream() {
+        let result = run_async(
+            "const data = new Uint8Array(150000).map((_, i) => i % 251); \
+             const reader = new Blob([data]).stream().getReader(); \
+             (async () => { \
+               let chunks = 0; let total = 0; let ok = true; \
+               while (true) { \
+                 const { done, value } = await reader.read(); \
+                 if (done) break; \
+                 for (let i = 0; i < value.length; i++) { \
+                   if (value[i] !== (total + i) % 251) ok = false; \
+                 } \
+                 chunks++; total += value.length; \
+               } \
+               globalThis.result = `${chunks}:${total}:${ok}`; \
+             })();",
+        );
+        assert_eq!(result.unwrap(), "3:150000:true");
+    }
+
+    #[test]
+    fn blob_methods_illegal_invocation() {
+        let result = common::run_test_script(
+            "try { Blob.prototype.slice.call({}); 'no error'; } catch (e) { e instanceof TypeError; }",
+        );
+        assert_eq!(result.unwrap(), "true");
+    }
+
+    // File Tests
+    #[test]
+    fn file_exists() {
+        let result = common::get_type_of("File");
+        assert_eq!(result.unwrap(), "function");
+    }
+
+    #[test]
+    fn file_properties() {
+        let result = common::run_test_script(
+            "const f = new File(['abc'], 'a.txt', { type: 'text/plain', lastModified: 42 }); \
+             `${f.name}:${f.lastModified}:${f.size}:${f.type}:${f instanceof Blob}:${Object.prototype.toString.call(f)}`;",
+        );
+        assert_eq!(result.unwrap(), "a.txt:42:3:text/plain:true:[object File]");
+    }
+
+    #[test]
+    fn file_default_last_modified() {
+        let result = common::run_test_script(
+            "const before = Date.now(); \
+             const f = new File([], 'empty'); \
+             f.lastModified >= before && f.lastModified <= Date.now();",
+        );
+        assert_eq!(result.unwrap(), "true");
+    }
+
+    #[test]
+    fn file_requires_name() {
+        let result = common::run_test_script(
+            "try { new File(['abc']); 'no error'; } catch (e) { e instanceof TypeError; }",
+        );
+        assert_eq!(result.unwrap(), "true");
+    }
+
+    #[test]
+    fn file_slice_is_blob() {
+        let result = common::run_test_script(
+            "const s = new File(['abc'], 'a.txt').slice(1); \
+             `${s instanceof File}:${s instanceof Blob}:${s.size}`;",
+        );
+        assert_eq!(result.unwrap(), "false:true:2");
+    }
+
+    // structuredClone integration
+    #[test]
+    fn structured_clone_blob() {
+        let result = run_async(
+            "const b = new Blob(['cloned'], { type: 'text/plain' }).slice(1); \
+             const c = structuredClone(b); \
+             c.text().then(t => { globalThis.result = `${c !== b}:${c instanceof Blob}:${t}:${c.type}`; });",
+        );
+        assert_eq!(result.unwrap(), "true:true:loned:");
+    }
+
+    #[test]
+    fn structured_clone_file_in_object() {
+        let result = run_async(
+            "const f = new File(['x'], 'x.bin', { type: 'application/octet-stream', lastModified: 7 }); \
+             const { file } = structuredClone({ file: f }); \
+             file.text().then(t => { \
+               globalThis.result = `${file instanceof File}:${file.name}:${file.lastModified}:${file.type}:${t}`; \
+             });",
+        );
+        assert_eq!(result.unwrap(), "true:x.bin:7:application/octet-stream:x");
+    }
+
+    // Response integration
+    #[test]
+    fn response_with_blob_body() {
+        let result = run_async(
+            "const r = new Response(new Blob(['body'], { type: 'text/custom' })); \
+             r.text().then(t => { globalThis.result = `${r.headers.get('content-type')}:${t}`; });",
+        );
+        assert_eq!(result.unwrap(), "text/custom:body");
+    }
+
+    #[test]
+    fn response_blob() {
+        let result = run_async(
+            "const r = new Response('abc', { headers: { 'content-type': 'text/x' } }); \
+             r.blob().then(b => b.text().then(t => { \
+               globalThis.result = `${b instanceof Blob}:${b.type}:${b.size}:${t}:${r.bodyUsed}`; \
+             }));",
+        );
+        assert_eq!(result.unwrap(), "true:text/x:3:abc:true");
+    }
+
+    #[test]
+    fn response_array_buffer_and_bytes() {
+        let result = run_async(
+            "Promise.all([ \
+               new Response(new Uint8Array([1, 2, 3])).arrayBuffer(), \
+               new Response('hi').bytes(), \
+             ]).then(([buf, bytes]) => { \
+               globalThis.result = `${new Uint8Array(buf).join(',')}:${bytes instanceof Uint8Array}:${bytes.join(',')}`; \
+             });",
+        );
+        assert_eq!(result.unwrap(), "1,2,3:true:104,105");
+    }
+
+    #[test]
+    fn response_body_used_rejects_second_read() {
+        let result = run_async(
+            "const r = new Response('once'); \
+             r.arrayBuffer().then(() => r.bytes()) \
+               .then(() => { globalThis.result = 'read twice'; }, e => { globalThis.result = e instanceof TypeError; });",
+        );
+        assert_eq!(result.unwrap(), "true");
+    }
+}
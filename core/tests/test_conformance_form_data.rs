@@ -0,0 +1,203 @@
+use jstime_core as jstime;
+
+mod common;
+
+#[cfg(test)]
+mod conformance_form_data {
+    use super::*;
+
+    // Run a script that settles asynchronously and read back globalThis.result
+    fn run_async(script: &str) -> Result<String, jstime::Error> {
+        let _setup_guard = common::setup();
+        let options = jstime::Options::default();
+        let mut jstime = jstime::JSTime::new(options);
+        jstime.run_script(
+            &format!("globalThis.result = null; {}", script),
+            "conformance_form_data",
+        )?;
+        jstime.run_script("globalThis.result;", "conformance_form_data")
+    }
+
+    // FormData Tests
+    #[test]
+    fn form_data_exists() {
+        let result = common::get_type_of("FormData");
+        assert_eq!(result.unwrap(), "function");
+    }
+
+    #[test]
+    fn form_data_append_get_get_all() {
+        let result = common::run_test_script(
+            "const f = new FormData(); \
+             f.append('a', '1'); \
+             f.append('b', 2); \
+             f.append('a', '3'); \
+             `${f.get('a')}:${f.get('b')}:${f.getAll('a').join(',')}:${f.get('missing')}:${f.has('b')}`;",
+        );
+        assert_eq!(result.unwrap(), "1:2:1,3:null:true");
+    }
+
+    #[test]
+    fn form_data_set_replaces_all() {
+        let result = common::run_test_script(
+            "const f = new FormData(); \
+             f.append('a', '1'); \
+             f.append('b', '2'); \
+             f.append('a', '3'); \
+             f.set('a', 'x'); \
+             JSON.stringify([...f]);",
+        );
+        assert_eq!(result.unwrap(), r#"[["a","x"],["b","2"]]"#);
+    }
+
+    #[test]
+    fn form_data_delete() {
+        let result = common::run_test_script(
+            "const f = new FormData(); \
+             f.append('a', '1'); \
+             f.append('b', '2'); \
+             f.append('a', '3'); \
+             f.delete('a'); \
+             `${[...f.keys()].join(',')}:${f.has('a')}`;",
+        );
+        assert_eq!(result.unwrap(), "b:false");
+    }
+
+    #[test]
+    fn form_data_iteration() {
+        let result = common::run_test_script(
+            "const f = new FormData(); \
+             f.append('a', '1'); \
+             f.append('b', '2'); \
+             const seen = []; \
+             f.forEach((value, name, form) => seen.push(`${name}=${value}:${form === f}`)); \
+             `${seen.join('&')}|${[...f.values()].join(',')}|${Object.prototype.toString.call(f)}`;",
+        );
+        assert_eq!(result.unwrap(), "a=1:true&b=2:true|1,2|[object FormData]");
+    }
+
+    #[test]
+    fn form_data_blob_becomes_file() {
+        let result = common::run_test_script(
+            "const f = new FormData(); \
+             f.append('plain', new Blob(['x'], { type: 'text/plain' })); \
+             f.append('named', new Blob(['y']), 'y.bin'); \
+             const plain = f.get('plain'); \
+             const named = f.get('named'); \
+             `${plain instanceof File}:${plain.name}:${plain.type}:${named.name}`;",
+        );
+        assert_eq!(result.unwrap(), "true:blob:text/plain:y.bin");
+    }
+
+    #[test]
+    fn form_data_file_renamed() {
+        let result = common::run_test_script(
+            "const f = new FormData(); \
+             const file = new File(['z'], 'orig.txt', { lastModified: 5 }); \
+             f.append('same', file); \
+             f.append('renamed', file, 'new.txt'); \
+             `${f.get('same') === file}:${f.get('renamed').name}:${f.get('renamed').lastModified}`;",
+        );
+        assert_eq!(result.unwrap(), "true:new.txt:5");
+    }
+
+    #[test]
+    fn form_data_filename_requires_blob() {
+        let result = common::run_test_script(
+            "try { new FormData().append('a', 'text', 'name.txt'); 'no error'; } \
+             catch (e) { e instanceof TypeError; }",
+        );
+        assert_eq!(result.unwrap(), "true");
+    }
+
+    #[test]
+    fn form_data_requires_two_arguments() {
+        let result = common::run_test_script(
+            "try { new FormData().append('a'); 'no error'; } catch (e) { e instanceof TypeError; }",
+        );
+        assert_eq!(result.unwrap(), "true");
+    }
+
+    // Body integration
+    #[test]
+    fn request_form_data_content_type() {
+        let result = common::run_test_script(
+            "const f = new FormData(); \
+             f.append('a', '1'); \
+             new Request('http://example.com', { method: 'POST', body: f }).headers.get('content-type');",
+        );
+        assert!(
+            result
+                .unwrap()
+                .starts_with("multipart/form-data; boundary=----jstimeFormBoundary")
+        );
+    }
+
+    #[test]
+    fn response_form_data_round_trip() {
+        let result = run_async(
+            "const f = new FormData(); \
+             f.append('name', 'line1\\nline2'); \
+             f.append('quote\"d', 'v'); \
+             f.append('file', new File([new Uint8Array([0, 1, 255])], 'data.bin', { type: 'application/octet-stream' })); \
+             new Response(f).formData().then(async parsed => { \
+               const file = parsed.get('file'); \
+               const bytes = new Uint8Array(await file.arrayBuffer()); \
+               globalThis.result = JSON.stringify([parsed.get('name'), parsed.get('quote\"d'), \
+                 file instanceof File, file.name, file.type, [...bytes]]); \
+             });",
+        );
+        assert_eq!(
+            result.unwrap(),
+            r#"["line1\r\nline2","v",true,"data.bin","application/octet-stream",[0,1,255]]"#
+        );
+    }
+
+    #[test]
+    fn response_form_data_parses_multipart() {
+        let result = run_async(
+            "const body = 'preamble\\r\\n--XyZ\\r\\n' + \
+               'Content-Disposition: form-data; name=\"a\"\\r\\n\\r\\nfirst\\r\\n' + \
+               '--XyZ\\r\\n' + \
+               'content-disposition: form-data; name=\"f\"; filename=\"f.txt\"\\r\\n\\r\\nfile body\\r\\n' + \
+               '--XyZ--\\r\\n'; \
+             const r = new Response(body, { headers: { 'Content-Type': 'multipart/form-data; boundary=\"XyZ\"' } }); \
+             r.formData().then(async f => { \
+               const file = f.get('f'); \
+               globalThis.result = `${f.get('a')}:${file.name}:${file.type}:${await file.text()}`; \
+             });",
+        );
+        assert_eq!(result.unwrap(), "first:f.txt:text/plain:file body");
+    }
+
+    #[test]
+    fn response_form_data_parses_urlencoded() {
+        let result = run_async(
+            "const r = new Response(new URLSearchParams('a=1&b=x+y%21&a=2&eq=c%3Dd=e')); \
+             r.formData().then(f => { \
+               globalThis.result = `${f.getAll('a').join(',')}:${f.get('b')}:${f.get('eq')}`; \
+             });",
+        );
+        assert_eq!(result.unwrap(), "1,2:x y!:c=d=e");
+    }
+
+    #[test]
+    fn response_form_data_rejects_other_types() {
+        let result = run_async(
+            "new Response('{}', { headers: { 'Content-Type': 'application/json' } }).formData() \
+               .then(() => { globalThis.result = 'parsed'; }, e => { globalThis.result = e instanceof TypeError; });",
+        );
+        assert_eq!(result.unwrap(), "true");
+    }
+
+    #[test]
+    fn response_form_data_rejects_malformed_multipart() {
+        let result = run_async(
+            "const r = new Response('--b\\r\\nno terminator', { \
+               headers: { 'Content-Type': 'multipart/form-data; boundary=b' } \
+             }); \
+             r.formData().then(() => { globalThis.result = 'parsed'; }, e => { globalThis.result = e instanceof TypeError; });",
+        );
+        assert_eq!(result.unwrap(), "true");
+    }
+}
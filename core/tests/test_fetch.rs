@@ -2,6 +2,132 @@ use jstime_core as jstime;
 
 mod common;
 
+/// Start a local HTTP server that answers every request with `body` after `delay`.
+/// Each connection is handled on its own thread so concurrent requests overlap.
+/// Returns the port the server is listening on.
+fn spawn_slow_server(delay: std::time::Duration, body: &'static str) -> u16 {
+    use std::io::{Read, Write};
+
+    let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+    let port = listener.local_addr().unwrap().port();
+    std::thread::spawn(move || {
+        for stream in listener.incoming() {
+            let Ok(mut stream) = stream else {
+                continue;
+            };
+            std::thread::spawn(move || {
+                // Read until the end of the request headers
+                let mut request = Vec::new();
+                let mut buf = [0u8; 1024];
+                while !request.windows(4).any(|w| w == b"\r\n\r\n") {
+                    match stream.read(&mut buf) {
+                        Ok(0) | Err(_) => return,
+                        Ok(n) => request.extend_from_slice(&buf[..n]),
+                    }
+                }
+                std::thread::sleep(delay);
+                let response = format!(
+                    "HTTP/1.1 200 OK\r\nContent-Type: text/plain\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+                    body.len(),
+                    body
+                );
+                let _ = stream.write_all(response.as_bytes());
+            });
+        }
+    });
+    port
+}
+
+/// Start a local HTTP server that echoes each request body back as lowercase hex.
+/// Handles both Content-Length and chunked request bodies. The request's
+/// Content-Type and Transfer-Encoding (or "none") are returned in `x-echo-content-type` and
+/// `x-echo-transfer-encoding` response headers.
+fn spawn_echo_server() -> u16 {
+    use std::io::{BufRead, BufReader, Read, Write};
+
+    let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+    let port = listener.local_addr().unwrap().port();
+    std::thread::spawn(move || {
+        for stream in listener.incoming() {
+            let Ok(stream) = stream else {
+                continue;
+            };
+            std::thread::spawn(move || {
+                let mut reader = BufReader::new(stream.try_clone().unwrap());
+                let mut content_length = 0usize;
+                let mut content_type = String::new();
+                let mut transfer_encoding = String::new();
+                loop {
+                    let mut line = String::new();
+                    if reader.read_line(&mut line).unwrap_or(0) == 0 {
+                        return;
+                    }
+                    let line = line.trim_end();
+                    if line.is_empty() {
+                        break;
+                    }
+                    if let Some((name, value)) = line.split_once(':') {
+                        let value = value.trim().to_string();
+                        match name.to_ascii_lowercase().as_str() {
+                            "content-length" => content_length = value.parse().unwrap_or(0),
+                            "content-type" => content_type = value,
+                            "transfer-encoding" => transfer_encoding = value,
+                            _ => {}
+                        }
+                    }
+                }
+
+                let mut body = Vec::new();
+                if transfer_encoding.eq_ignore_ascii_case("chunked") {
+                    loop {
+                        // The client may drop the connection mid-body (e.g. a failed stream)
+                        let mut size_line = String::new();
+                        if reader.read_line(&mut size_line).unwrap_or(0) == 0 {
+                            return;
+                        }
+                        let Ok(size) = usize::from_str_radix(size_line.trim(), 16) else {
+                            return;
+                        };
+                        let mut chunk = vec![0u8; size + 2];
+                        if reader.read_exact(&mut chunk).is_err() {
+                            return;
+                        }
+                        if size == 0 {
+                            break;
+                        }
+                        body.extend_from_slice(&chunk[..size]);
+                    }
+                } else {
+                    body.resize(content_length, 0);
+                    if reader.read_exact(&mut body).is_err() {
+                        return;
+                    }
+                }
+
+                // Report missing headers as "none" so they survive Headers.get()
+                if content_type.is_empty() {
+                    content_type = "none".to_string();
+                }
+                if transfer_encoding.is_empty() {
+                    transfer_encoding = "none".to_string();
+                }
+
+                let hex: String = body.iter().map(|b| format!("{:02x}", b)).collect();
+                let response = format!(
+                    "HTTP/1.1 200 OK\r\nx-echo-content-type: {}\r\nx-echo-transfer-encoding: {}\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+                    content_type,
+                    transfer_encoding,
+                    hex.len(),
+                    hex
+                );
+                let mut stream = stream;
+                let _ = stream.write_all(response.as_bytes());
+            });
+        }
+    });
+    port
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -83,6 +209,35 @@ mod tests {
         assert_eq!(result.unwrap(), "POST");
     }
 
+    #[test]
+    fn request_get_head_with_body_throws() {
+        let _setup_guard = common::setup();
+        let options = jstime::Options::default();
+        let mut jstime = jstime::JSTime::new(options);
+        let result = jstime.run_script(
+            "const errors = []; \
+             for (const method of ['GET', 'head', undefined]) { \
+               try { new Request('https://example.com', {method, body: 'x'}); } \
+               catch (e) { errors.push(e.name); } \
+             } \
+             errors.join();",
+            "jstime",
+        );
+        assert_eq!(result.unwrap(), "TypeError,TypeError,TypeError");
+
+        // fetch rejects instead of throwing
+        jstime
+            .run_script(
+                "globalThis.result = null; \
+                 fetch('http://127.0.0.1:1/', {body: 'x'}) \
+                   .catch(e => { globalThis.result = e instanceof TypeError; });",
+                "jstime",
+            )
+            .unwrap();
+        let result = jstime.run_script("globalThis.result;", "jstime");
+        assert_eq!(result.unwrap(), "true");
+    }
+
     #[test]
     fn response_basic() {
         let _setup_guard = common::setup();
@@ -307,4 +462,340 @@ mod tests {
         let result = jstime.run_script("globalThis.result;", "jstime");
         assert_eq!(result.unwrap(), "true");
     }
+
+    #[test]
+    fn fetch_local_server_body() {
+        let _setup_guard = common::setup();
+        let port = spawn_slow_server(std::time::Duration::from_millis(0), "hello from server");
+        let options = jstime::Options::default();
+        let mut jstime = jstime::JSTime::new(options);
+
+        jstime
+            .run_script(
+                &format!(
+                    "globalThis.result = null; \
+                     fetch('http://127.0.0.1:{port}/') \
+                       .then(r => r.text()) \
+                       .then(t => {{ globalThis.result = t; }});"
+                ),
+                "jstime",
+            )
+            .unwrap();
+
+        let result = jstime.run_script("globalThis.result;", "jstime");
+        assert_eq!(result.unwrap(), "hello from server");
+    }
+
+    #[test]
+    fn concurrent_fetches_overlap() {
+        let _setup_guard = common::setup();
+        let port = spawn_slow_server(std::time::Duration::from_millis(500), "ok");
+        let options = jstime::Options::default();
+        let mut jstime = jstime::JSTime::new(options);
+
+        // Four requests at 500ms each would take 2s if executed one after another
+        jstime
+            .run_script(
+                &format!(
+                    "globalThis.elapsed = null; \
+                     const start = Date.now(); \
+                     const url = 'http://127.0.0.1:{port}/'; \
+                     Promise.all([fetch(url), fetch(url), fetch(url), fetch(url)]) \
+                       .then(responses => {{ \
+                         globalThis.statuses = responses.map(r => r.status).join(','); \
+                         globalThis.elapsed = Date.now() - start; \
+                       }});"
+                ),
+                "jstime",
+            )
+            .unwrap();
+
+        let statuses = jstime.run_script("globalThis.statuses;", "jstime");
+        assert_eq!(statuses.unwrap(), "200,200,200,200");
+
+        let elapsed: f64 = jstime
+            .run_script("globalThis.elapsed;", "jstime")
+            .unwrap()
+            .parse()
+            .unwrap();
+        assert!(elapsed < 1500.0, "fetches did not overlap: {elapsed}ms");
+    }
+
+    #[test]
+    fn timers_fire_during_slow_fetch() {
+        let _setup_guard = common::setup();
+        let port = spawn_slow_server(std::time::Duration::from_millis(500), "slow");
+        let options = jstime::Options::default();
+        let mut jstime = jstime::JSTime::new(options);
+
+        jstime
+            .run_script(
+                &format!(
+                    "globalThis.order = []; \
+                     fetch('http://127.0.0.1:{port}/').then(() => order.push('fetch')); \
+                     setTimeout(() => order.push('timer'), 50);"
+                ),
+                "jstime",
+            )
+            .unwrap();
+
+        let result = jstime.run_script("globalThis.order.join(',');", "jstime");
+        assert_eq!(result.unwrap(), "timer,fetch");
+    }
+
+    #[test]
+    fn fetch_connection_refused_rejects() {
+        let _setup_guard = common::setup();
+        // Bind and immediately drop a listener to find a port nobody listens on
+        let port = {
+            let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+            listener.local_addr().unwrap().port()
+        };
+        let options = jstime::Options::default();
+        let mut jstime = jstime::JSTime::new(options);
+
+        jstime
+            .run_script(
+                &format!(
+                    "globalThis.result = null; \
+                     fetch('http://127.0.0.1:{port}/') \
+                       .then(() => {{ globalThis.result = 'resolved'; }}) \
+                       .catch(e => {{ globalThis.result = e.message.startsWith('Network error'); }});"
+                ),
+                "jstime",
+            )
+            .unwrap();
+
+        let result = jstime.run_script("globalThis.result;", "jstime");
+        assert_eq!(result.unwrap(), "true");
+    }
+
+    #[test]
+    fn fetch_abort_in_flight() {
+        let _setup_guard = common::setup();
+        let port = spawn_slow_server(std::time::Duration::from_millis(2000), "too late");
+        let options = jstime::Options::default();
+        let mut jstime = jstime::JSTime::new(options);
+
+        let start = std::time::Instant::now();
+        jstime
+            .run_script(
+                &format!(
+                    "globalThis.result = null; \
+                     const controller = new AbortController(); \
+                     fetch('http://127.0.0.1:{port}/', {{ signal: controller.signal }}) \
+                       .then(() => {{ globalThis.result = 'resolved'; }}) \
+                       .catch(e => {{ globalThis.result = e.name; }}); \
+                     setTimeout(() => controller.abort(), 50);"
+                ),
+                "jstime",
+            )
+            .unwrap();
+
+        // The loop must not wait for the aborted request to finish
+        assert!(start.elapsed() < std::time::Duration::from_millis(1500));
+        let result = jstime.run_script("globalThis.result;", "jstime");
+        assert_eq!(result.unwrap(), "AbortError");
+    }
+
+    #[test]
+    fn fetch_abort_signal_timeout() {
+        let _setup_guard = common::setup();
+        let port = spawn_slow_server(std::time::Duration::from_millis(2000), "too late");
+        let options = jstime::Options::default();
+        let mut jstime = jstime::JSTime::new(options);
+
+        jstime
+            .run_script(
+                &format!(
+                    "globalThis.result = null; \
+                     fetch('http://127.0.0.1:{port}/', {{ signal: AbortSignal.timeout(50) }}) \
+                       .then(() => {{ globalThis.result = 'resolved'; }}) \
+                       .catch(e => {{ globalThis.result = `${{e instanceof DOMException}}:${{e.name}}`; }});"
+                ),
+                "jstime",
+            )
+            .unwrap();
+
+        let result = jstime.run_script("globalThis.result;", "jstime");
+        assert_eq!(result.unwrap(), "true:TimeoutError");
+    }
+
+    #[test]
+    fn fetch_abort_cancels_body() {
+        let _setup_guard = common::setup();
+        let port = spawn_slow_server(std::time::Duration::from_millis(0), "unread body");
+        let options = jstime::Options::default();
+        let mut jstime = jstime::JSTime::new(options);
+
+        jstime
+            .run_script(
+                &format!(
+                    "globalThis.result = null; \
+                     const controller = new AbortController(); \
+                     fetch('http://127.0.0.1:{port}/', {{ signal: controller.signal }}) \
+                       .then(r => {{ controller.abort(); return r.text(); }}) \
+                       .then(() => {{ globalThis.result = 'read'; }}) \
+                       .catch(e => {{ globalThis.result = e.name; }});"
+                ),
+                "jstime",
+            )
+            .unwrap();
+
+        let result = jstime.run_script("globalThis.result;", "jstime");
+        assert_eq!(result.unwrap(), "AbortError");
+    }
+
+    /// POST `body_expr` to a local echo server and return "<content-type>|<transfer-encoding>|<hex body>"
+    fn echo_request_body(body_expr: &str) -> String {
+        let _setup_guard = common::setup();
+        let port = spawn_echo_server();
+        let options = jstime::Options::default();
+        let mut jstime = jstime::JSTime::new(options);
+
+        jstime
+            .run_script(
+                &format!(
+                    "globalThis.result = null; \
+                     fetch('http://127.0.0.1:{port}/', {{ method: 'POST', body: {body_expr} }}) \
+                       .then(async r => {{ \
+                         const type = r.headers.get('x-echo-content-type'); \
+                         const encoding = r.headers.get('x-echo-transfer-encoding'); \
+                         globalThis.result = `${{type}}|${{encoding}}|${{await r.text()}}`; \
+                       }}) \
+                       .catch(e => {{ globalThis.result = 'error: ' + e.message; }});"
+                ),
+                "jstime",
+            )
+            .unwrap();
+
+        jstime.run_script("globalThis.result;", "jstime").unwrap()
+    }
+
+    #[test]
+    fn fetch_body_uint8array_is_binary_safe() {
+        // Bytes that are not valid UTF-8 must arrive unchanged
+        let result = echo_request_body("new Uint8Array([0, 255, 128, 10, 0xc3])");
+        assert_eq!(result, "none|none|00ff800ac3");
+    }
+
+    #[test]
+    fn fetch_body_array_buffer_and_views() {
+        let result = echo_request_body("new Uint16Array([0x0102, 0xfffe]).buffer");
+        assert_eq!(result, "none|none|0201feff");
+
+        let result =
+            echo_request_body("new DataView(new Uint8Array([1, 2, 3, 4, 5]).buffer, 1, 3)");
+        assert_eq!(result, "none|none|020304");
+
+        let result = echo_request_body("new Uint8Array([9, 8, 7, 6]).subarray(1, 3)");
+        assert_eq!(result, "none|none|0807");
+    }
+
+    #[test]
+    fn fetch_body_string_content_type() {
+        let result = echo_request_body("'hi'");
+        assert_eq!(result, "text/plain;charset=UTF-8|none|6869");
+    }
+
+    #[test]
+    fn fetch_body_url_search_params() {
+        let result = echo_request_body("new URLSearchParams({ a: '1', b: 'x y' })");
+        assert_eq!(
+            result,
+            "application/x-www-form-urlencoded;charset=UTF-8|none|613d3126623d7825323079"
+        );
+    }
+
+    #[test]
+    fn fetch_body_readable_stream_is_chunked() {
+        let result = echo_request_body(
+            "new ReadableStream({ \
+               start(controller) { \
+                 controller.enqueue(new Uint8Array([1, 2])); \
+                 controller.enqueue(new Uint8Array([255])); \
+                 controller.close(); \
+               } \
+             })",
+        );
+        assert_eq!(result, "none|chunked|0102ff");
+    }
+
+    #[test]
+    fn fetch_body_stream_with_pull() {
+        let result = echo_request_body(
+            "(() => { \
+               let i = 0; \
+               return new ReadableStream({ \
+                 async pull(controller) { \
+                   await new Promise(resolve => setTimeout(resolve, 5)); \
+                   if (i < 3) { controller.enqueue(new Uint8Array([i++])); } \
+                   else { controller.close(); } \
+                 } \
+               }); \
+             })()",
+        );
+        assert_eq!(result, "none|chunked|000102");
+    }
+
+    #[test]
+    fn fetch_body_stream_error_rejects() {
+        let result = echo_request_body(
+            "new ReadableStream({ \
+               start(controller) { \
+                 controller.enqueue(new Uint8Array([1])); \
+                 controller.error(new Error('source failed')); \
+               } \
+             })",
+        );
+        assert!(result.starts_with("error: "), "unexpected result: {result}");
+    }
+
+    #[test]
+    fn fetch_body_form_data_is_multipart() {
+        let result = echo_request_body(
+            "(() => { \
+               const form = new FormData(); \
+               form.append('field', 'value'); \
+               form.append('upload', new Blob(['abc'], { type: 'text/plain' }), 'a.txt'); \
+               return form; \
+             })()",
+        );
+        let mut fields = result.splitn(3, '|');
+        let content_type = fields.next().unwrap();
+        assert_eq!(fields.next().unwrap(), "none");
+        let hex = fields.next().unwrap();
+        let body: Vec<u8> = (0..hex.len())
+            .step_by(2)
+            .map(|i| u8::from_str_radix(&hex[i..i + 2], 16).unwrap())
+            .collect();
+
+        let boundary = content_type
+            .strip_prefix("multipart/form-data; boundary=")
+            .unwrap_or_else(|| panic!("unexpected content type: {content_type}"));
+        let expected = format!(
+            "--{boundary}\r\n\
+             Content-Disposition: form-data; name=\"field\"\r\n\r\n\
+             value\r\n\
+             --{boundary}\r\n\
+             Content-Disposition: form-data; name=\"upload\"; filename=\"a.txt\"\r\n\
+             Content-Type: text/plain\r\n\r\n\
+             abc\r\n\
+             --{boundary}--\r\n"
+        );
+        assert_eq!(String::from_utf8(body).unwrap(), expected);
+    }
+
+    #[test]
+    fn fetch_body_explicit_content_type_kept() {
+        let result = common::run_test_script(
+            "const r = new Request('http://example.com', { \
+               method: 'POST', \
+               body: 'x', \
+               headers: { 'Content-Type': 'application/json' } \
+             }); \
+             r.headers.get('content-type');",
+        );
+        assert_eq!(result.unwrap(), "application/json");
+    }
 }
@@ -0,0 +1,260 @@
+use jstime_core as jstime;
+use std::fs;
+use std::path::Path;
+
+mod common;
+
+/// Write a file with the modification time `seconds`, so each version is
+/// told apart however coarse the file system's times are. It's renamed into
+/// place, so the new content is never seen with the old time.
+fn write_versioned(path: &Path, content: &str, seconds: i64) {
+    let temp = path.with_extension("tmp");
+    common::write_file(&temp, content);
+    filetime::set_file_mtime(&temp, filetime::FileTime::from_unix_time(seconds, 0))
+        .expect("Failed to set modification time");
+    fs::rename(&temp, path).expect("Failed to rename file");
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_invalidate_reloads_dependents() {
+        let _setup_guard = common::setup();
+
+        let temp_dir = std::env::temp_dir().join("jstime_test_hot_reload_invalidate");
+        common::cleanup(&temp_dir);
+        common::create_dir_all(&temp_dir);
+        let temp_dir = temp_dir.canonicalize().unwrap();
+        let dep = temp_dir.join("dep.js");
+        let main = temp_dir.join("main.js");
+
+        common::write_file(&dep, "export const value = 'first';");
+        common::write_file(
+            &main,
+            "import { value } from './dep.js';\nglobalThis.testResult = value;",
+        );
+
+        let mut jstime = jstime::JSTime::new(jstime::Options::default());
+        jstime.import(main.to_str().unwrap()).unwrap();
+        let result = jstime.run_script("globalThis.testResult", "test");
+        assert_eq!(result.unwrap(), "first");
+
+        // Modules are only loaded once until they're invalidated
+        common::write_file(&dep, "export const value = 'second';");
+        jstime.import(main.to_str().unwrap()).unwrap();
+        let result = jstime.run_script("globalThis.testResult", "test");
+        assert_eq!(result.unwrap(), "first");
+
+        let invalidated = jstime.invalidate(dep.to_str().unwrap());
+        assert_eq!(
+            invalidated,
+            vec![
+                dep.to_str().unwrap().to_string(),
+                main.to_str().unwrap().to_string()
+            ]
+        );
+        jstime.import(main.to_str().unwrap()).unwrap();
+        let result = jstime.run_script("globalThis.testResult", "test");
+        assert_eq!(result.unwrap(), "second");
+
+        common::cleanup(&temp_dir);
+    }
+
+    #[test]
+    fn test_reload_changed_reimports_entry() {
+        let _setup_guard = common::setup();
+
+        let temp_dir = std::env::temp_dir().join("jstime_test_hot_reload_changed");
+        common::cleanup(&temp_dir);
+        common::create_dir_all(&temp_dir);
+        let temp_dir = temp_dir.canonicalize().unwrap();
+        let dep = temp_dir.join("dep.js");
+        let other = temp_dir.join("other.js");
+        let main = temp_dir.join("main.js");
+
+        write_versioned(&dep, "export const value = 1;", 1_000_000);
+        write_versioned(&other, "export const other = 10;", 1_000_000);
+        write_versioned(
+            &main,
+            "import { value } from './dep.js';\nimport { other } from './other.js';\n\
+             globalThis.testResult = value + other;",
+            1_000_000,
+        );
+
+        let mut jstime = jstime::JSTime::new(jstime::Options::default());
+        jstime.import(main.to_str().unwrap()).unwrap();
+        assert_eq!(jstime.reload_changed().unwrap(), Vec::<String>::new());
+
+        write_versioned(&dep, "export const value = 2;", 2_000_000);
+        let reloaded = jstime.reload_changed().unwrap();
+        assert_eq!(
+            reloaded,
+            vec![
+                dep.to_str().unwrap().to_string(),
+                main.to_str().unwrap().to_string()
+            ]
+        );
+        let result = jstime.run_script("globalThis.testResult", "test");
+        assert_eq!(result.unwrap(), "12");
+
+        // Nothing changed since
+        assert_eq!(jstime.reload_changed().unwrap(), Vec::<String>::new());
+
+        // Errors are returned, and the fixed module is reloaded later
+        write_versioned(&dep, "export const value = ;", 3_000_000);
+        assert!(matches!(
+            jstime.reload_changed(),
+            Err(jstime::Error::Exception(_))
+        ));
+        write_versioned(&dep, "export const value = 3;", 4_000_000);
+        assert!(jstime.reload_changed().is_ok());
+        let result = jstime.run_script("globalThis.testResult", "test");
+        assert_eq!(result.unwrap(), "13");
+
+        common::cleanup(&temp_dir);
+    }
+
+    #[test]
+    fn test_reload_changed_after_entry_fails_to_compile() {
+        let _setup_guard = common::setup();
+
+        let temp_dir = std::env::temp_dir().join("jstime_test_hot_reload_entry_error");
+        common::cleanup(&temp_dir);
+        common::create_dir_all(&temp_dir);
+        let temp_dir = temp_dir.canonicalize().unwrap();
+        let main = temp_dir.join("main.js");
+
+        write_versioned(&main, "globalThis.testResult = ;", 1_000_000);
+        let mut jstime = jstime::JSTime::new(jstime::Options::default());
+        assert!(jstime.import(main.to_str().unwrap()).is_err());
+        assert_eq!(jstime.reload_changed().unwrap(), Vec::<String>::new());
+
+        // The entry is reloaded once it's fixed
+        write_versioned(&main, "globalThis.testResult = 'fixed';", 2_000_000);
+        let reloaded = jstime.reload_changed().unwrap();
+        assert_eq!(reloaded, vec![main.to_str().unwrap().to_string()]);
+        let result = jstime.run_script("globalThis.testResult", "test");
+        assert_eq!(result.unwrap(), "fixed");
+
+        common::cleanup(&temp_dir);
+    }
+
+    #[test]
+    fn test_hot_accept_and_dispose() {
+        let _setup_guard = common::setup();
+
+        let temp_dir = std::env::temp_dir().join("jstime_test_hot_reload_accept");
+        common::cleanup(&temp_dir);
+        common::create_dir_all(&temp_dir);
+        let temp_dir = temp_dir.canonicalize().unwrap();
+        let counter = temp_dir.join("counter.js");
+        let main = temp_dir.join("main.js");
+
+        let counter_source = |version: &str| {
+            format!(
+                r#"
+import.meta.hot.dispose((data) => {{ data.disposed = (data.disposed ?? 0) + 1; }});
+import.meta.hot.accept((module) => {{ globalThis.accepted = module.version; }});
+export const version = '{version}';
+globalThis.counterRuns = (globalThis.counterRuns ?? 0) + 1;
+globalThis.disposed = import.meta.hot.data.disposed ?? 0;
+"#
+            )
+        };
+        write_versioned(&counter, &counter_source("v1"), 1_000_000);
+        write_versioned(
+            &main,
+            "import './counter.js';\nglobalThis.mainRuns = (globalThis.mainRuns ?? 0) + 1;",
+            1_000_000,
+        );
+
+        let options = jstime::Options::default().with_hot_reload(true);
+        let mut jstime = jstime::JSTime::new(options);
+        jstime.import(main.to_str().unwrap()).unwrap();
+
+        // The counter accepts its own updates, so main isn't run again
+        write_versioned(&counter, &counter_source("v2"), 2_000_000);
+        let reloaded = jstime.reload_changed().unwrap();
+        assert_eq!(reloaded, vec![counter.to_str().unwrap().to_string()]);
+        let result = jstime.run_script(
+            "[globalThis.mainRuns, globalThis.counterRuns, globalThis.accepted, globalThis.disposed].join()",
+            "test",
+        );
+        assert_eq!(result.unwrap(), "1,2,v2,1");
+
+        common::cleanup(&temp_dir);
+    }
+
+    #[test]
+    fn test_hot_reload_polls_while_running() {
+        let _setup_guard = common::setup();
+
+        let temp_dir = std::env::temp_dir().join("jstime_test_hot_reload_poll");
+        common::cleanup(&temp_dir);
+        common::create_dir_all(&temp_dir);
+        let temp_dir = temp_dir.canonicalize().unwrap();
+        let dep = temp_dir.join("dep.js");
+        let main = temp_dir.join("main.js");
+
+        write_versioned(&dep, "export const value = 'a';", 1_000_000);
+        write_versioned(
+            &main,
+            r#"
+import { value } from './dep.js';
+(globalThis.values ??= []).push(value);
+// Keep the event loop running while dep.js changes, on the first run only
+if (globalThis.values.length === 1) setTimeout(() => {}, 1500);
+"#,
+            1_000_000,
+        );
+
+        let writer = {
+            let dep = dep.clone();
+            std::thread::spawn(move || {
+                std::thread::sleep(std::time::Duration::from_millis(300));
+                write_versioned(&dep, "export const value = 'b';", 2_000_000);
+            })
+        };
+
+        let options = jstime::Options::default().with_hot_reload(true);
+        let mut jstime = jstime::JSTime::new(options);
+        jstime.import(main.to_str().unwrap()).unwrap();
+        writer.join().unwrap();
+
+        let result = jstime.run_script("globalThis.values.join()", "test");
+        assert_eq!(result.unwrap(), "a,b");
+
+        common::cleanup(&temp_dir);
+    }
+
+    #[test]
+    fn test_import_meta_hot_requires_option() {
+        let _setup_guard = common::setup();
+
+        let temp_dir = std::env::temp_dir().join("jstime_test_hot_reload_meta");
+        common::cleanup(&temp_dir);
+        common::create_dir_all(&temp_dir);
+        let main = temp_dir.join("main.js");
+        common::write_file(
+            &main,
+            "globalThis.testResult = typeof import.meta.hot;\n\
+             try { import.meta.hot?.accept(['./dep.js']); } catch (e) { globalThis.testResult += ' ' + e.name; }",
+        );
+
+        let mut jstime = jstime::JSTime::new(jstime::Options::default());
+        jstime.import(main.to_str().unwrap()).unwrap();
+        let result = jstime.run_script("globalThis.testResult", "test");
+        assert_eq!(result.unwrap(), "undefined");
+
+        // Accepting updates of dependencies isn't supported
+        let options = jstime::Options::default().with_hot_reload(true);
+        let mut jstime = jstime::JSTime::new(options);
+        jstime.import(main.to_str().unwrap()).unwrap();
+        let result = jstime.run_script("globalThis.testResult", "test");
+        assert_eq!(result.unwrap(), "object TypeError");
+
+        common::cleanup(&temp_dir);
+    }
+}
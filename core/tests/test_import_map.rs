@@ -0,0 +1,250 @@
+use jstime_core as jstime;
+use std::path::Path;
+
+mod common;
+
+/// Import `entry` with `import_map` and return globalThis.testResult
+fn import_with_map(entry: &Path, import_map: jstime::ImportMap) -> Result<String, jstime::Error> {
+    let options = jstime::Options::default().with_import_map(import_map);
+    let mut jstime = jstime::JSTime::new(options);
+    jstime.import(entry.to_str().unwrap())?;
+    jstime.run_script("globalThis.testResult", "test")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_import_map_bare_and_prefix_specifiers() {
+        let _setup_guard = common::setup();
+
+        let temp_dir = std::env::temp_dir().join("jstime_test_import_map_bare");
+        common::cleanup(&temp_dir);
+        common::create_dir_all(&temp_dir.join("vendor").join("utils"));
+
+        common::write_file(
+            &temp_dir.join("vendor").join("greet.js"),
+            "export const greet = (name) => `hello ${name}`;",
+        );
+        common::write_file(
+            &temp_dir.join("vendor").join("utils").join("math.js"),
+            "export const double = (x) => x * 2;",
+        );
+        common::write_file(
+            &temp_dir.join("test.js"),
+            r#"
+import { greet } from 'greet';
+import { double } from 'utils/math.js';
+import { EventEmitter } from 'events-alias';
+const dynamic = await import('greet');
+globalThis.testResult = [
+  greet('map'),
+  double(21),
+  typeof EventEmitter,
+  dynamic.greet === greet,
+  import.meta.resolve('utils/math.js') === 'file://' + import.meta.dirname + '/vendor/utils/math.js',
+].join(',');
+"#,
+        );
+
+        let import_map = jstime::ImportMap::parse(
+            r#"{
+  "imports": {
+    "greet": "./vendor/greet.js",
+    "utils/": "./vendor/utils/",
+    "events-alias": "node:events"
+  }
+}"#,
+            &temp_dir,
+        )
+        .unwrap();
+
+        let result = import_with_map(&temp_dir.join("test.js"), import_map);
+        assert_eq!(result.unwrap(), "hello map,42,function,true,true");
+
+        common::cleanup(&temp_dir);
+    }
+
+    #[test]
+    fn test_import_map_scopes() {
+        let _setup_guard = common::setup();
+
+        let temp_dir = std::env::temp_dir().join("jstime_test_import_map_scopes");
+        common::cleanup(&temp_dir);
+        common::create_dir_all(&temp_dir.join("legacy"));
+
+        common::write_file(&temp_dir.join("dep-v1.js"), "export const version = 1;");
+        common::write_file(&temp_dir.join("dep-v2.js"), "export const version = 2;");
+        common::write_file(
+            &temp_dir.join("legacy").join("index.js"),
+            "export { version } from 'dep';",
+        );
+        common::write_file(
+            &temp_dir.join("test.js"),
+            r#"
+import { version } from 'dep';
+import { version as legacyVersion } from './legacy/index.js';
+globalThis.testResult = `${version},${legacyVersion}`;
+"#,
+        );
+
+        let import_map = jstime::ImportMap::parse(
+            r#"{
+  "imports": { "dep": "./dep-v2.js" },
+  "scopes": { "./legacy/": { "dep": "./dep-v1.js" } }
+}"#,
+            &temp_dir,
+        )
+        .unwrap();
+
+        let result = import_with_map(&temp_dir.join("test.js"), import_map);
+        assert_eq!(result.unwrap(), "2,1");
+
+        common::cleanup(&temp_dir);
+    }
+
+    #[test]
+    fn test_import_map_takes_precedence_over_node_modules() {
+        let _setup_guard = common::setup();
+
+        let temp_dir = std::env::temp_dir().join("jstime_test_import_map_precedence");
+        common::cleanup(&temp_dir);
+        let pkg_dir = temp_dir.join("node_modules").join("dep");
+        common::create_dir_all(&pkg_dir);
+        common::create_dir_all(&temp_dir.join("vendor"));
+
+        common::write_file(&pkg_dir.join("package.json"), r#"{"name": "dep"}"#);
+        common::write_file(
+            &pkg_dir.join("index.js"),
+            "export const from = 'node_modules';",
+        );
+        common::write_file(
+            &temp_dir.join("vendor").join("dep.js"),
+            "export const from = 'vendor';",
+        );
+        common::write_file(
+            &temp_dir.join("test.js"),
+            r#"
+import { from } from 'dep';
+globalThis.testResult = from;
+"#,
+        );
+
+        let import_map =
+            jstime::ImportMap::parse(r#"{"imports": {"dep": "./vendor/dep.js"}}"#, &temp_dir)
+                .unwrap();
+
+        let result = import_with_map(&temp_dir.join("test.js"), import_map);
+        assert_eq!(result.unwrap(), "vendor");
+
+        common::cleanup(&temp_dir);
+    }
+
+    #[test]
+    fn test_import_map_blocked_specifier() {
+        let _setup_guard = common::setup();
+
+        let temp_dir = std::env::temp_dir().join("jstime_test_import_map_blocked");
+        common::cleanup(&temp_dir);
+        common::create_dir_all(&temp_dir);
+
+        common::write_file(&temp_dir.join("test.js"), "import 'blocked';");
+        common::write_file(
+            &temp_dir.join("dynamic.js"),
+            r#"
+try {
+  await import('pkg/../../escape.js');
+} catch (e) {
+  globalThis.testResult = `${e.name}: ${e.message}`;
+}
+"#,
+        );
+
+        let import_map = jstime::ImportMap::parse(
+            r#"{"imports": {"blocked": null, "pkg/": "./vendor/pkg/"}}"#,
+            &temp_dir,
+        )
+        .unwrap();
+
+        let options = jstime::Options::default().with_import_map(import_map.clone());
+        let mut jstime = jstime::JSTime::new(options);
+        let err = jstime
+            .import(temp_dir.join("test.js").to_str().unwrap())
+            .unwrap_err()
+            .to_string();
+        assert!(
+            err.contains("Import of 'blocked' is blocked by the import map"),
+            "Unexpected error: {err}"
+        );
+
+        let result = import_with_map(&temp_dir.join("dynamic.js"), import_map).unwrap();
+        assert!(
+            result.starts_with("TypeError: Cannot map 'pkg/../../escape.js' with the import map"),
+            "Unexpected result: {result}"
+        );
+
+        common::cleanup(&temp_dir);
+    }
+
+    #[test]
+    fn test_import_map_from_config_file() {
+        let _setup_guard = common::setup();
+
+        let temp_dir = std::env::temp_dir().join("jstime_test_import_map_config");
+        common::cleanup(&temp_dir);
+        common::create_dir_all(&temp_dir.join("maps"));
+        common::create_dir_all(&temp_dir.join("inline"));
+
+        common::write_file(&temp_dir.join("lib.js"), "export const name = 'lib';");
+        common::write_file(
+            &temp_dir.join("test.js"),
+            r#"
+import { name } from 'lib';
+globalThis.testResult = name;
+"#,
+        );
+
+        // "importMap" paths are relative to the config, and addresses to the import map
+        common::write_file(
+            &temp_dir.join("jstime.json"),
+            r#"{"importMap": "./maps/import_map.json"}"#,
+        );
+        common::write_file(
+            &temp_dir.join("maps").join("import_map.json"),
+            r#"{"imports": {"lib": "../lib.js"}}"#,
+        );
+        let import_map = jstime::ImportMap::from_config_file(temp_dir.join("jstime.json"))
+            .unwrap()
+            .unwrap();
+        let result = import_with_map(&temp_dir.join("test.js"), import_map);
+        assert_eq!(result.unwrap(), "lib");
+
+        common::write_file(
+            &temp_dir.join("inline").join("jstime.json"),
+            r#"{"imports": {"lib": "../lib.js"}}"#,
+        );
+        let import_map =
+            jstime::ImportMap::from_config_file(temp_dir.join("inline").join("jstime.json"))
+                .unwrap()
+                .unwrap();
+        let result = import_with_map(&temp_dir.join("test.js"), import_map);
+        assert_eq!(result.unwrap(), "lib");
+
+        common::write_file(&temp_dir.join("jstime.json"), r#"{"other": true}"#);
+        let import_map = jstime::ImportMap::from_config_file(temp_dir.join("jstime.json")).unwrap();
+        assert!(import_map.is_none());
+
+        common::write_file(
+            &temp_dir.join("jstime.json"),
+            r#"{"importMap": "./missing.json"}"#,
+        );
+        let err = jstime::ImportMap::from_config_file(temp_dir.join("jstime.json")).unwrap_err();
+        assert!(
+            err.contains("Cannot read import map"),
+            "Unexpected error: {err}"
+        );
+
+        common::cleanup(&temp_dir);
+    }
+}
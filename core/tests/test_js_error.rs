@@ -0,0 +1,267 @@
+use jstime_core as jstime;
+
+mod common;
+
+/// Helper to get the exception a result failed with
+fn exception<T: std::fmt::Debug>(result: Result<T, jstime::Error>) -> jstime::JsError {
+    match result {
+        Err(jstime::Error::Exception(error)) => error,
+        other => panic!("Expected an exception, got {other:?}"),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_error_fields() {
+        let _setup_guard = common::setup();
+        let mut jstime = jstime::JSTime::new(jstime::Options::default());
+
+        let error = exception(jstime.run_script(
+            "function thrower() {\n  throw new TypeError('boom');\n}\nthrower();",
+            "test",
+        ));
+        assert_eq!(error.name, "TypeError");
+        assert_eq!(error.message, "boom");
+        assert_eq!(
+            error.frames[0],
+            jstime::StackFrame {
+                function: Some("thrower".to_string()),
+                file: Some("test".to_string()),
+                line: Some(2),
+                column: Some(9),
+            }
+        );
+        assert_eq!(error.frames[1].function, None);
+        assert_eq!(error.frames[1].line, Some(4));
+        assert_eq!(
+            error.source_line.as_deref(),
+            Some("  throw new TypeError('boom');")
+        );
+        assert!(error.cause.is_none());
+
+        // It displays as the formatted exception
+        let text = error.to_string();
+        assert!(text.contains("TypeError: boom"), "{text}");
+        assert!(text.contains("at thrower"), "{text}");
+    }
+
+    #[test]
+    fn test_cause_chain() {
+        let _setup_guard = common::setup();
+        let mut jstime = jstime::JSTime::new(jstime::Options::default());
+
+        let error = exception(jstime.run_script(
+            r#"
+const inner = new RangeError('inner', { cause: 'root' });
+throw new Error('outer', { cause: inner });
+"#,
+            "test",
+        ));
+        assert_eq!(error.message, "outer");
+        let cause = error.cause.as_deref().unwrap();
+        assert_eq!(cause.name, "RangeError");
+        assert_eq!(cause.message, "inner");
+        assert_eq!(cause.frames[0].line, Some(2));
+        let root = cause.cause.as_deref().unwrap();
+        assert_eq!(root.name, "");
+        assert_eq!(root.message, "root");
+
+        // The chain is the errors' sources
+        let source = std::error::Error::source(&error).unwrap();
+        assert!(source.to_string().contains("inner"), "{source}");
+
+        // A cycle ends
+        let error = exception(jstime.run_script(
+            "const e = new Error('cycle'); e.cause = e; throw e;",
+            "test",
+        ));
+        let mut depth = 0;
+        let mut cause = error.cause.as_deref();
+        while let Some(error) = cause {
+            assert_eq!(error.message, "cycle");
+            depth += 1;
+            cause = error.cause.as_deref();
+        }
+        assert!(depth > 0 && depth < 100, "{depth}");
+    }
+
+    #[test]
+    fn test_values_that_are_not_errors() {
+        let _setup_guard = common::setup();
+        let mut jstime = jstime::JSTime::new(jstime::Options::default());
+
+        let error = exception(jstime.run_script("\nthrow 42;", "test"));
+        assert_eq!(error.name, "");
+        assert_eq!(error.message, "42");
+        // Where it was thrown stands in for the stack it doesn't have
+        assert_eq!(error.frames.len(), 1);
+        assert_eq!(error.frames[0].file.as_deref(), Some("test"));
+        assert_eq!(error.frames[0].line, Some(2));
+        assert_eq!(error.source_line.as_deref(), Some("throw 42;"));
+        assert!(jstime.with_error_value(&error, |_, _| ()).is_none());
+
+        let error = exception(jstime.run_script("let x = ;", "test"));
+        assert_eq!(error.name, "SyntaxError");
+        assert_eq!(error.frames[0].line, Some(1));
+    }
+
+    #[test]
+    fn test_unhandled_rejection() {
+        let _setup_guard = common::setup();
+        let mut jstime = jstime::JSTime::new(jstime::Options::default());
+
+        let error =
+            exception(jstime.run_script("Promise.reject(new TypeError('rejected')); 1", "test"));
+        assert_eq!(error.name, "TypeError");
+        assert_eq!(error.message, "rejected");
+        assert!(
+            error.to_string().starts_with("Uncaught (in promise)"),
+            "{error}"
+        );
+    }
+
+    #[test]
+    fn test_report_error() {
+        let _setup_guard = common::setup();
+        let mut jstime = jstime::JSTime::new(jstime::Options::default());
+
+        let error = exception(jstime.run_script(
+            "function report() {\n  reportError(new RangeError('x'));\n}\nreport();",
+            "test",
+        ));
+        assert_eq!(error.name, "RangeError");
+        assert_eq!(error.message, "x");
+        assert_eq!(error.frames[0].function.as_deref(), Some("report"));
+        assert_eq!(error.frames[0].line, Some(2));
+    }
+
+    #[test]
+    fn test_to_json() {
+        let _setup_guard = common::setup();
+        let mut jstime = jstime::JSTime::new(jstime::Options::default());
+
+        let error = exception(jstime.run_script(
+            "throw new Error('a \"quoted\"\\nmessage', { cause: 42 });",
+            "test",
+        ));
+        let json: serde_json::Value = serde_json::from_str(&error.to_json()).unwrap();
+        assert_eq!(json["name"], "Error");
+        assert_eq!(json["message"], "a \"quoted\"\nmessage");
+        assert_eq!(json["frames"][0]["file"], "test");
+        assert_eq!(json["frames"][0]["line"], 1);
+        assert_eq!(json["frames"][0]["column"], 7);
+        assert!(json["frames"][0]["function"].is_null());
+        assert!(json["source_line"].is_string());
+        assert_eq!(json["cause"]["name"], "");
+        assert_eq!(json["cause"]["message"], "42");
+        assert!(json["cause"]["cause"].is_null());
+    }
+
+    #[test]
+    fn test_error_value() {
+        let _setup_guard = common::setup();
+        let mut jstime = jstime::JSTime::new(jstime::Options::default());
+
+        let error = exception(jstime.run_script(
+            "const e = new Error('with a code'); e.code = 7; throw e;",
+            "test",
+        ));
+        let code = jstime.with_error_value(&error, |scope, value| {
+            let object = v8::Local::<v8::Object>::try_from(value).unwrap();
+            let key = v8::String::new(scope, "code").unwrap();
+            object.get(scope, key.into()).unwrap().int32_value(scope)
+        });
+        assert_eq!(code, Some(Some(7)));
+
+        // It's the thrown object itself
+        jstime.run_script("globalThis.thrown = e;", "test").unwrap();
+        let same = jstime.with_error_value(&error, |scope, value| {
+            let context = scope.get_current_context();
+            let global = context.global(scope);
+            let key = v8::String::new(scope, "thrown").unwrap();
+            global.get(scope, key.into()).unwrap().strict_equals(value)
+        });
+        assert_eq!(same, Some(true));
+
+        // Another instance doesn't have it
+        let mut other = jstime::JSTime::new(jstime::Options::default());
+        assert!(other.with_error_value(&error, |_, _| ()).is_none());
+    }
+
+    #[test]
+    fn test_frames_do_not_depend_on_stack() {
+        let _setup_guard = common::setup();
+        let mut jstime = jstime::JSTime::new(jstime::Options::default());
+
+        // A custom format, and a stack that was overwritten
+        let error = exception(jstime.run_script(
+            r#"
+Error.prepareStackTrace = () => 'custom';
+function overwritten() {
+  const e = new Error('overwritten');
+  e.stack = 'not a stack (at all):1:2';
+  throw e;
+}
+overwritten();
+"#,
+            "test",
+        ));
+        assert_eq!(error.frames[0].function.as_deref(), Some("overwritten"));
+        assert_eq!(error.frames[0].file.as_deref(), Some("test"));
+        assert_eq!(error.frames[0].line, Some(4));
+        assert_eq!(error.frames[1].line, Some(8));
+
+        // Code from eval, in a file whose name has " ("
+        let error = exception(jstime.run_script(
+            "eval('function inEval() {\n  throw new Error(\'eval\');\n}\ninEval();');",
+            "dir (1)/test.js",
+        ));
+        assert_eq!(error.frames[0].function.as_deref(), Some("inEval"));
+        assert_eq!(error.frames[0].line, Some(2));
+        assert_eq!(
+            error.frames.last().unwrap().file.as_deref(),
+            Some("dir (1)/test.js")
+        );
+        assert_eq!(error.frames.last().unwrap().line, Some(1));
+    }
+
+    #[test]
+    fn test_import_error() {
+        let _setup_guard = common::setup();
+
+        let temp_dir = std::env::temp_dir().join("jstime_test_js_error_import");
+        common::cleanup(&temp_dir);
+        common::create_dir_all(&temp_dir);
+        let main = temp_dir.join("main.js");
+        common::write_file(&main, "import { fail } from './dep.js';\nfail();");
+        common::write_file(
+            &temp_dir.join("dep.js"),
+            "export function fail() {\n  throw new Error('from a module');\n}",
+        );
+
+        let mut jstime = jstime::JSTime::new(jstime::Options::default());
+        let error = exception(jstime.import(main.to_str().unwrap()));
+        assert_eq!(error.message, "from a module");
+        assert_eq!(error.frames[0].function.as_deref(), Some("fail"));
+        assert!(
+            error.frames[0].file.as_deref().unwrap().ends_with("dep.js"),
+            "{:?}",
+            error.frames
+        );
+        assert_eq!(error.frames[0].line, Some(2));
+        assert!(
+            error.frames[1]
+                .file
+                .as_deref()
+                .unwrap()
+                .ends_with("main.js"),
+            "{:?}",
+            error.frames
+        );
+
+        common::cleanup(&temp_dir);
+    }
+}
@@ -0,0 +1,145 @@
+use jstime_core as jstime;
+use std::fs;
+use std::path::Path;
+
+mod common;
+
+/// A project with an ES module and a CommonJS package in node_modules
+fn create_project(name: &str) -> std::path::PathBuf {
+    let temp_dir = std::env::temp_dir().join(name);
+    common::cleanup(&temp_dir);
+    let esm_dir = temp_dir.join("node_modules").join("esm-dep");
+    let cjs_dir = temp_dir.join("node_modules").join("cjs-dep");
+    common::create_dir_all(&esm_dir);
+    common::create_dir_all(&cjs_dir);
+
+    common::write_file(
+        &esm_dir.join("package.json"),
+        r#"{"name": "esm-dep", "type": "module"}"#,
+    );
+    common::write_file(&esm_dir.join("index.js"), "export const esm = 'esm';");
+    common::write_file(&cjs_dir.join("package.json"), r#"{"name": "cjs-dep"}"#);
+    common::write_file(&cjs_dir.join("index.cjs"), "exports.cjs = 'cjs';");
+    common::write_file(
+        &temp_dir.join("test.js"),
+        r#"
+import { esm } from 'esm-dep';
+import { createRequire } from 'node:module';
+const require = createRequire(import.meta.url);
+globalThis.testResult = `${esm},${require('cjs-dep/index.cjs').cjs}`;
+"#,
+    );
+    temp_dir
+}
+
+/// Import the project's test.js, checked against `lockfile`
+fn import_with_lockfile(
+    project: &Path,
+    lockfile: jstime::Lockfile,
+) -> Result<String, jstime::Error> {
+    let options = jstime::Options::default().with_lockfile(lockfile);
+    let mut jstime = jstime::JSTime::new(options);
+    jstime.import(project.join("test.js").to_str().unwrap())?;
+    jstime.run_script("globalThis.testResult", "test")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_lockfile_records_external_modules() {
+        let _setup_guard = common::setup();
+
+        let project = create_project("jstime_test_lockfile_records");
+        let lock_path = project.join("jstime.lock");
+
+        let lockfile = jstime::Lockfile::open(&lock_path).unwrap();
+        let result = import_with_lockfile(&project, lockfile);
+        assert_eq!(result.unwrap(), "esm,cjs");
+
+        let lock = fs::read_to_string(&lock_path).unwrap();
+        assert!(lock.contains(r#""node_modules/esm-dep/index.js": "sha256-"#));
+        assert!(lock.contains(r#""node_modules/cjs-dep/index.cjs": "sha256-"#));
+        assert!(
+            !lock.contains("test.js"),
+            "Project files are recorded: {lock}"
+        );
+
+        // A matching lockfile loads the same modules again
+        let lockfile = jstime::Lockfile::open(&lock_path).unwrap();
+        let result = import_with_lockfile(&project, lockfile);
+        assert_eq!(result.unwrap(), "esm,cjs");
+        assert_eq!(fs::read_to_string(&lock_path).unwrap(), lock);
+
+        common::cleanup(&project);
+    }
+
+    #[test]
+    fn test_lockfile_mismatch_is_refused() {
+        let _setup_guard = common::setup();
+
+        let project = create_project("jstime_test_lockfile_mismatch");
+        let lock_path = project.join("jstime.lock");
+        let stale_hash = format!("sha256-{}", "0".repeat(64));
+
+        common::write_file(
+            &lock_path,
+            &format!(
+                r#"{{"version": "1", "modules": {{"node_modules/esm-dep/index.js": "{stale_hash}"}}}}"#
+            ),
+        );
+        let lockfile = jstime::Lockfile::open(&lock_path).unwrap();
+        let err = import_with_lockfile(&project, lockfile)
+            .unwrap_err()
+            .to_string();
+        assert!(
+            err.contains("Integrity check failed") && err.contains("esm-dep/index.js"),
+            "Unexpected error: {err}"
+        );
+
+        common::write_file(
+            &lock_path,
+            &format!(
+                r#"{{"version": "1", "modules": {{"node_modules/cjs-dep/index.cjs": "{stale_hash}"}}}}"#
+            ),
+        );
+        let lockfile = jstime::Lockfile::open(&lock_path).unwrap();
+        let err = import_with_lockfile(&project, lockfile)
+            .unwrap_err()
+            .to_string();
+        assert!(
+            err.contains("Integrity check failed") && err.contains("cjs-dep/index.cjs"),
+            "Unexpected error: {err}"
+        );
+
+        // Regenerating replaces the stale hash
+        let lockfile = jstime::Lockfile::regenerate(&lock_path);
+        let result = import_with_lockfile(&project, lockfile);
+        assert_eq!(result.unwrap(), "esm,cjs");
+        let lock = fs::read_to_string(&lock_path).unwrap();
+        assert!(!lock.contains(&stale_hash));
+        assert!(lock.contains("node_modules/esm-dep/index.js"));
+
+        common::cleanup(&project);
+    }
+
+    #[test]
+    fn test_invalid_lockfile() {
+        let _setup_guard = common::setup();
+
+        let temp_dir = std::env::temp_dir().join("jstime_test_lockfile_invalid");
+        common::cleanup(&temp_dir);
+        common::create_dir_all(&temp_dir);
+        let lock_path = temp_dir.join("jstime.lock");
+
+        common::write_file(&lock_path, "{not json");
+        let err = jstime::Lockfile::open(&lock_path).unwrap_err();
+        assert!(
+            err.starts_with("Invalid lockfile"),
+            "Unexpected error: {err}"
+        );
+
+        common::cleanup(&temp_dir);
+    }
+}
@@ -0,0 +1,224 @@
+use jstime_core as jstime;
+
+mod common;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Run a script that settles asynchronously and read back globalThis.result
+    fn run_async(script: &str) -> Result<String, jstime::Error> {
+        let _setup_guard = common::setup();
+        let options = jstime::Options::default();
+        let mut jstime = jstime::JSTime::new(options);
+        jstime.run_script(
+            &format!(
+                "globalThis.result = null; const net = globalThis.__node_modules['node:net']; {}",
+                script
+            ),
+            "test_net",
+        )?;
+        jstime.run_script("globalThis.result;", "test_net")
+    }
+
+    #[test]
+    fn test_net_module_exists() {
+        let result = common::run_test_script(
+            "const net = globalThis.__node_modules['node:net']; \
+             net === globalThis.__node_modules['net'] && typeof net.createServer === 'function' \
+               && typeof net.connect === 'function' && net.createConnection === net.connect;",
+        );
+        assert_eq!(result.unwrap(), "true");
+    }
+
+    #[test]
+    fn test_net_esm_named_exports() {
+        let _setup_guard = common::setup();
+        let temp_dir = std::env::temp_dir().join("jstime_test_net_esm");
+        std::fs::create_dir_all(&temp_dir).unwrap();
+        let test_file = temp_dir.join("test.mjs");
+        std::fs::write(
+            &test_file,
+            "import net, { createServer, Socket, isIP } from 'node:net';\n\
+             globalThis.result = createServer === net.createServer && typeof Socket === 'function' && isIP('::1');\n",
+        )
+        .unwrap();
+
+        let options = jstime::Options::default();
+        let mut jstime = jstime::JSTime::new(options);
+        let result = jstime.import(test_file.to_str().unwrap());
+        assert!(result.is_ok(), "Failed to import: {:?}", result);
+        let result = jstime.run_script("globalThis.result", "test");
+        assert_eq!(result.unwrap(), "6");
+
+        let _ = std::fs::remove_dir_all(&temp_dir);
+    }
+
+    #[test]
+    fn test_net_is_ip() {
+        let result = common::run_test_script(
+            "const net = globalThis.__node_modules['node:net']; \
+             [net.isIP('127.0.0.1'), net.isIP('::1'), net.isIP('fe80::1%eth0'), net.isIP('256.1.1.1'), \
+              net.isIP('localhost'), net.isIPv4('10.0.0.1'), net.isIPv6('10.0.0.1')].join(',');",
+        );
+        assert_eq!(result.unwrap(), "4,6,6,0,0,true,false");
+    }
+
+    #[test]
+    fn test_net_echo_round_trip() {
+        let result = run_async(
+            "const server = net.createServer(socket => socket.on('data', d => socket.write(d))); \
+             server.listen(0, '127.0.0.1', () => { \
+               const client = net.connect(server.address().port, '127.0.0.1', () => client.write('ping')); \
+               client.on('data', d => { \
+                 globalThis.result = `${d.toString()}:${client.remotePort === server.address().port}`; \
+                 client.destroy(); \
+                 server.close(); \
+               }); \
+             });",
+        );
+        assert_eq!(result.unwrap(), "ping:true");
+    }
+
+    #[test]
+    fn test_net_server_address() {
+        let result = run_async(
+            "const server = net.createServer(); \
+             server.listen({ port: 0, host: '127.0.0.1' }, () => { \
+               const addr = server.address(); \
+               globalThis.result = `${addr.address}:${addr.family}:${addr.port > 0}:${server.listening}`; \
+               server.close(() => { globalThis.result += `:${server.address()}`; }); \
+             });",
+        );
+        assert_eq!(result.unwrap(), "127.0.0.1:IPv4:true:true:null");
+    }
+
+    #[test]
+    fn test_net_connection_refused() {
+        let port = {
+            let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+            listener.local_addr().unwrap().port()
+        };
+        let result = run_async(&format!(
+            "const socket = net.connect({port}, '127.0.0.1'); \
+             socket.on('error', e => {{ globalThis.result = e.code; }}); \
+             socket.on('close', hadError => {{ globalThis.result += `:${{hadError}}`; }});"
+        ));
+        assert_eq!(result.unwrap(), "ECONNREFUSED:true");
+    }
+
+    #[test]
+    fn test_net_listen_address_in_use() {
+        let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+        let port = listener.local_addr().unwrap().port();
+        let result = run_async(&format!(
+            "const server = net.createServer(); \
+             server.on('error', e => {{ globalThis.result = `${{e.code}}:${{server.listening}}`; }}); \
+             server.listen({port}, '127.0.0.1');"
+        ));
+        assert_eq!(result.unwrap(), "EADDRINUSE:false");
+    }
+
+    #[test]
+    fn test_net_write_before_connect_is_queued() {
+        let result = run_async(
+            "const server = net.createServer(socket => { \
+               socket.setEncoding('utf8'); \
+               let received = ''; \
+               socket.on('data', d => { received += d; }); \
+               socket.on('end', () => { globalThis.result = received; server.close(); }); \
+             }); \
+             server.listen(0, '127.0.0.1', () => { \
+               const client = net.connect({ port: server.address().port, host: '127.0.0.1' }); \
+               client.write('queued '); \
+               client.end('before connect'); \
+             });",
+        );
+        assert_eq!(result.unwrap(), "queued before connect");
+    }
+
+    #[test]
+    fn test_net_half_open() {
+        let result = run_async(
+            "const server = net.createServer({ allowHalfOpen: true }, socket => { \
+               socket.on('end', () => socket.end('reply after end')); \
+             }); \
+             server.listen(0, '127.0.0.1', () => { \
+               const client = net.connect(server.address().port, '127.0.0.1', () => client.end()); \
+               let received = ''; \
+               client.on('data', d => { received += d; }); \
+               client.on('close', () => { globalThis.result = received; server.close(); }); \
+             });",
+        );
+        assert_eq!(result.unwrap(), "reply after end");
+    }
+
+    #[test]
+    fn test_net_large_payload() {
+        let result = run_async(
+            "const size = 4 * 1024 * 1024; \
+             const server = net.createServer(socket => { \
+               let total = 0; \
+               socket.on('data', d => { total += d.length; }); \
+               socket.on('end', () => { globalThis.result = total; server.close(); }); \
+             }); \
+             server.listen(0, '127.0.0.1', () => { \
+               const client = net.connect(server.address().port, '127.0.0.1'); \
+               const flushed = client.write(new Uint8Array(size)); \
+               if (flushed) { client.end(); } else { client.once('drain', () => client.end()); } \
+             });",
+        );
+        assert_eq!(result.unwrap(), (4 * 1024 * 1024).to_string());
+    }
+
+    #[test]
+    fn test_net_socket_options_chain() {
+        let result = run_async(
+            "const server = net.createServer(socket => socket.end()); \
+             server.listen(0, '127.0.0.1', () => { \
+               const client = new net.Socket(); \
+               const chained = client.setNoDelay(true).setKeepAlive(true, 1000).setTimeout(0) === client; \
+               client.connect(server.address().port, '127.0.0.1', () => { \
+                 globalThis.result = `${chained}:${client.readyState}:${client.setNoDelay() === client}`; \
+               }); \
+               client.on('close', () => server.close()); \
+             });",
+        );
+        assert_eq!(result.unwrap(), "true:open:true");
+    }
+
+    #[test]
+    fn test_net_unref_server_allows_exit() {
+        let result = run_async(
+            "const server = net.createServer(); \
+             server.listen(0, '127.0.0.1'); \
+             server.unref(); \
+             globalThis.result = 'exited';",
+        );
+        assert_eq!(result.unwrap(), "exited");
+    }
+
+    #[test]
+    fn test_net_client_against_rust_server() {
+        use std::io::{Read, Write};
+
+        let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+        let port = listener.local_addr().unwrap().port();
+        let server = std::thread::spawn(move || {
+            let (mut stream, _) = listener.accept().unwrap();
+            let mut received = Vec::new();
+            stream.read_to_end(&mut received).unwrap();
+            stream.write_all(b"bye").unwrap();
+            String::from_utf8(received).unwrap()
+        });
+
+        let result = run_async(&format!(
+            "const client = net.connect({port}, '127.0.0.1'); \
+             client.setEncoding('utf8'); \
+             client.end(new TextEncoder().encode('hello from js')); \
+             client.on('data', d => {{ globalThis.result = d; }});"
+        ));
+        assert_eq!(result.unwrap(), "bye");
+        assert_eq!(server.join().unwrap(), "hello from js");
+    }
+}
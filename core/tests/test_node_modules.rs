@@ -1,24 +1,7 @@
 use jstime_core as jstime;
-use std::fs;
-use std::path::Path;
 
 mod common;
 
-/// Helper to create a directory structure
-fn create_dir_all(path: &Path) {
-    fs::create_dir_all(path).expect("Failed to create directory");
-}
-
-/// Helper to write a file
-fn write_file(path: &Path, content: &str) {
-    fs::write(path, content).expect("Failed to write file");
-}
-
-/// Helper to clean up test directory
-fn cleanup(path: &Path) {
-    let _ = fs::remove_dir_all(path);
-}
-
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -30,26 +13,26 @@ mod tests {
 
         // Create test directory structure
         let temp_dir = std::env::temp_dir().join("jstime_test_simple_main");
-        cleanup(&temp_dir);
-        create_dir_all(&temp_dir);
+        common::cleanup(&temp_dir);
+        common::create_dir_all(&temp_dir);
 
         // Create node_modules/test-pkg with package.json and main entry
         let pkg_dir = temp_dir.join("node_modules").join("test-pkg");
-        create_dir_all(&pkg_dir);
+        common::create_dir_all(&pkg_dir);
 
-        write_file(
+        common::write_file(
             &pkg_dir.join("package.json"),
             r#"{"name": "test-pkg", "main": "index.js"}"#,
         );
 
-        write_file(
+        common::write_file(
             &pkg_dir.join("index.js"),
             r#"export const message = "Hello from test-pkg!";"#,
         );
 
         // Create test file that imports from node_modules
         let test_file = temp_dir.join("test.js");
-        write_file(
+        common::write_file(
             &test_file,
             r#"
 import { message } from 'test-pkg';
@@ -66,7 +49,7 @@ globalThis.testResult = message;
         let result = jstime.run_script("globalThis.testResult", "test");
         assert_eq!(result.unwrap(), "Hello from test-pkg!");
 
-        cleanup(&temp_dir);
+        common::cleanup(&temp_dir);
     }
 
     /// Test importing a package with exports field
@@ -75,24 +58,24 @@ globalThis.testResult = message;
         let _setup_guard = common::setup();
 
         let temp_dir = std::env::temp_dir().join("jstime_test_exports");
-        cleanup(&temp_dir);
-        create_dir_all(&temp_dir);
+        common::cleanup(&temp_dir);
+        common::create_dir_all(&temp_dir);
 
         let pkg_dir = temp_dir.join("node_modules").join("exports-pkg");
-        create_dir_all(&pkg_dir.join("dist"));
+        common::create_dir_all(&pkg_dir.join("dist"));
 
-        write_file(
+        common::write_file(
             &pkg_dir.join("package.json"),
             r#"{"name": "exports-pkg", "exports": { ".": "./dist/index.js" }}"#,
         );
 
-        write_file(
+        common::write_file(
             &pkg_dir.join("dist").join("index.js"),
             r#"export const version = "1.0.0";"#,
         );
 
         let test_file = temp_dir.join("test.js");
-        write_file(
+        common::write_file(
             &test_file,
             r#"
 import { version } from 'exports-pkg';
@@ -109,7 +92,7 @@ globalThis.pkgVersion = version;
         let result = jstime.run_script("globalThis.pkgVersion", "test");
         assert_eq!(result.unwrap(), "1.0.0");
 
-        cleanup(&temp_dir);
+        common::cleanup(&temp_dir);
     }
 
     /// Test importing a scoped package (@scope/package)
@@ -118,27 +101,27 @@ globalThis.pkgVersion = version;
         let _setup_guard = common::setup();
 
         let temp_dir = std::env::temp_dir().join("jstime_test_scoped");
-        cleanup(&temp_dir);
-        create_dir_all(&temp_dir);
+        common::cleanup(&temp_dir);
+        common::create_dir_all(&temp_dir);
 
         let pkg_dir = temp_dir
             .join("node_modules")
             .join("@test-scope")
             .join("test-pkg");
-        create_dir_all(&pkg_dir);
+        common::create_dir_all(&pkg_dir);
 
-        write_file(
+        common::write_file(
             &pkg_dir.join("package.json"),
             r#"{"name": "@test-scope/test-pkg", "main": "index.js"}"#,
         );
 
-        write_file(
+        common::write_file(
             &pkg_dir.join("index.js"),
             r#"export const scopedMessage = "Hello from scoped package!";"#,
         );
 
         let test_file = temp_dir.join("test.js");
-        write_file(
+        common::write_file(
             &test_file,
             r#"
 import { scopedMessage } from '@test-scope/test-pkg';
@@ -155,7 +138,7 @@ globalThis.scopedResult = scopedMessage;
         let result = jstime.run_script("globalThis.scopedResult", "test");
         assert_eq!(result.unwrap(), "Hello from scoped package!");
 
-        cleanup(&temp_dir);
+        common::cleanup(&temp_dir);
     }
 
     /// Test importing a subpath from a package
@@ -164,26 +147,26 @@ globalThis.scopedResult = scopedMessage;
         let _setup_guard = common::setup();
 
         let temp_dir = std::env::temp_dir().join("jstime_test_subpath");
-        cleanup(&temp_dir);
-        create_dir_all(&temp_dir);
+        common::cleanup(&temp_dir);
+        common::create_dir_all(&temp_dir);
 
         let pkg_dir = temp_dir.join("node_modules").join("subpath-pkg");
-        create_dir_all(&pkg_dir.join("lib"));
+        common::create_dir_all(&pkg_dir.join("lib"));
 
-        write_file(
+        common::write_file(
             &pkg_dir.join("package.json"),
             r#"{"name": "subpath-pkg", "main": "index.js"}"#,
         );
 
-        write_file(&pkg_dir.join("index.js"), r#"export const main = "main";"#);
+        common::write_file(&pkg_dir.join("index.js"), r#"export const main = "main";"#);
 
-        write_file(
+        common::write_file(
             &pkg_dir.join("lib").join("utils.js"),
             r#"export const helper = "helper function";"#,
         );
 
         let test_file = temp_dir.join("test.js");
-        write_file(
+        common::write_file(
             &test_file,
             r#"
 import { helper } from 'subpath-pkg/lib/utils.js';
@@ -200,7 +183,7 @@ globalThis.subpathResult = helper;
         let result = jstime.run_script("globalThis.subpathResult", "test");
         assert_eq!(result.unwrap(), "helper function");
 
-        cleanup(&temp_dir);
+        common::cleanup(&temp_dir);
     }
 
     /// Test walking up directory tree to find node_modules
@@ -209,29 +192,29 @@ globalThis.subpathResult = helper;
         let _setup_guard = common::setup();
 
         let temp_dir = std::env::temp_dir().join("jstime_test_parent");
-        cleanup(&temp_dir);
-        create_dir_all(&temp_dir);
+        common::cleanup(&temp_dir);
+        common::create_dir_all(&temp_dir);
 
         // Create node_modules at root level
         let pkg_dir = temp_dir.join("node_modules").join("parent-pkg");
-        create_dir_all(&pkg_dir);
+        common::create_dir_all(&pkg_dir);
 
-        write_file(
+        common::write_file(
             &pkg_dir.join("package.json"),
             r#"{"name": "parent-pkg", "main": "index.js"}"#,
         );
 
-        write_file(
+        common::write_file(
             &pkg_dir.join("index.js"),
             r#"export const found = "Found in parent!";"#,
         );
 
         // Create nested directory for test file
         let nested_dir = temp_dir.join("src").join("lib").join("deep");
-        create_dir_all(&nested_dir);
+        common::create_dir_all(&nested_dir);
 
         let test_file = nested_dir.join("test.js");
-        write_file(
+        common::write_file(
             &test_file,
             r#"
 import { found } from 'parent-pkg';
@@ -248,7 +231,7 @@ globalThis.parentResult = found;
         let result = jstime.run_script("globalThis.parentResult", "test");
         assert_eq!(result.unwrap(), "Found in parent!");
 
-        cleanup(&temp_dir);
+        common::cleanup(&temp_dir);
     }
 
     /// Test that relative imports still work with node_modules resolution
@@ -257,16 +240,16 @@ globalThis.parentResult = found;
         let _setup_guard = common::setup();
 
         let temp_dir = std::env::temp_dir().join("jstime_test_relative");
-        cleanup(&temp_dir);
-        create_dir_all(&temp_dir);
+        common::cleanup(&temp_dir);
+        common::create_dir_all(&temp_dir);
 
-        write_file(
+        common::write_file(
             &temp_dir.join("helper.js"),
             r#"export const helper = "from relative import";"#,
         );
 
         let test_file = temp_dir.join("test.js");
-        write_file(
+        common::write_file(
             &test_file,
             r#"
 import { helper } from './helper.js';
@@ -283,7 +266,7 @@ globalThis.relativeResult = helper;
         let result = jstime.run_script("globalThis.relativeResult", "test");
         assert_eq!(result.unwrap(), "from relative import");
 
-        cleanup(&temp_dir);
+        common::cleanup(&temp_dir);
     }
 
     /// Test dynamic import with bare specifier
@@ -292,24 +275,24 @@ globalThis.relativeResult = helper;
         let _setup_guard = common::setup();
 
         let temp_dir = std::env::temp_dir().join("jstime_test_dynamic_bare");
-        cleanup(&temp_dir);
-        create_dir_all(&temp_dir);
+        common::cleanup(&temp_dir);
+        common::create_dir_all(&temp_dir);
 
         let pkg_dir = temp_dir.join("node_modules").join("dynamic-pkg");
-        create_dir_all(&pkg_dir);
+        common::create_dir_all(&pkg_dir);
 
-        write_file(
+        common::write_file(
             &pkg_dir.join("package.json"),
             r#"{"name": "dynamic-pkg", "main": "index.js"}"#,
         );
 
-        write_file(
+        common::write_file(
             &pkg_dir.join("index.js"),
             r#"export const dynamicExport = "loaded dynamically";"#,
         );
 
         let test_file = temp_dir.join("test.js");
-        write_file(
+        common::write_file(
             &test_file,
             r#"
 const mod = await import('dynamic-pkg');
@@ -326,7 +309,7 @@ globalThis.dynamicResult = mod.dynamicExport;
         let result = jstime.run_script("globalThis.dynamicResult", "test");
         assert_eq!(result.unwrap(), "loaded dynamically");
 
-        cleanup(&temp_dir);
+        common::cleanup(&temp_dir);
     }
 
     /// Test error when package is not found
@@ -335,11 +318,11 @@ globalThis.dynamicResult = mod.dynamicExport;
         let _setup_guard = common::setup();
 
         let temp_dir = std::env::temp_dir().join("jstime_test_not_found");
-        cleanup(&temp_dir);
-        create_dir_all(&temp_dir);
+        common::cleanup(&temp_dir);
+        common::create_dir_all(&temp_dir);
 
         let test_file = temp_dir.join("test.js");
-        write_file(
+        common::write_file(
             &test_file,
             r#"
 import { something } from 'nonexistent-package';
@@ -356,7 +339,7 @@ globalThis.result = something;
             "Should have failed to import nonexistent package"
         );
 
-        cleanup(&temp_dir);
+        common::cleanup(&temp_dir);
     }
 
     /// Test package with index.js fallback when no main field
@@ -365,22 +348,22 @@ globalThis.result = something;
         let _setup_guard = common::setup();
 
         let temp_dir = std::env::temp_dir().join("jstime_test_index_fallback");
-        cleanup(&temp_dir);
-        create_dir_all(&temp_dir);
+        common::cleanup(&temp_dir);
+        common::create_dir_all(&temp_dir);
 
         let pkg_dir = temp_dir.join("node_modules").join("index-pkg");
-        create_dir_all(&pkg_dir);
+        common::create_dir_all(&pkg_dir);
 
         // Package.json without main field
-        write_file(&pkg_dir.join("package.json"), r#"{"name": "index-pkg"}"#);
+        common::write_file(&pkg_dir.join("package.json"), r#"{"name": "index-pkg"}"#);
 
-        write_file(
+        common::write_file(
             &pkg_dir.join("index.js"),
             r#"export const indexDefault = "from index.js";"#,
         );
 
         let test_file = temp_dir.join("test.js");
-        write_file(
+        common::write_file(
             &test_file,
             r#"
 import { indexDefault } from 'index-pkg';
@@ -397,7 +380,7 @@ globalThis.indexResult = indexDefault;
         let result = jstime.run_script("globalThis.indexResult", "test");
         assert_eq!(result.unwrap(), "from index.js");
 
-        cleanup(&temp_dir);
+        common::cleanup(&temp_dir);
     }
 
     /// Test package with string exports (not object)
@@ -406,24 +389,24 @@ globalThis.indexResult = indexDefault;
         let _setup_guard = common::setup();
 
         let temp_dir = std::env::temp_dir().join("jstime_test_exports_string");
-        cleanup(&temp_dir);
-        create_dir_all(&temp_dir);
+        common::cleanup(&temp_dir);
+        common::create_dir_all(&temp_dir);
 
         let pkg_dir = temp_dir.join("node_modules").join("string-exports-pkg");
-        create_dir_all(&pkg_dir.join("lib"));
+        common::create_dir_all(&pkg_dir.join("lib"));
 
-        write_file(
+        common::write_file(
             &pkg_dir.join("package.json"),
             r#"{"name": "string-exports-pkg", "exports": "./lib/main.js"}"#,
         );
 
-        write_file(
+        common::write_file(
             &pkg_dir.join("lib").join("main.js"),
             r#"export const stringExport = "from string exports";"#,
         );
 
         let test_file = temp_dir.join("test.js");
-        write_file(
+        common::write_file(
             &test_file,
             r#"
 import { stringExport } from 'string-exports-pkg';
@@ -440,7 +423,7 @@ globalThis.stringExportResult = stringExport;
         let result = jstime.run_script("globalThis.stringExportResult", "test");
         assert_eq!(result.unwrap(), "from string exports");
 
-        cleanup(&temp_dir);
+        common::cleanup(&temp_dir);
     }
 
     /// Test transitive dependencies
@@ -449,19 +432,19 @@ globalThis.stringExportResult = stringExport;
         let _setup_guard = common::setup();
 
         let temp_dir = std::env::temp_dir().join("jstime_test_transitive");
-        cleanup(&temp_dir);
-        create_dir_all(&temp_dir);
+        common::cleanup(&temp_dir);
+        common::create_dir_all(&temp_dir);
 
         // Create pkg-a which depends on pkg-b
         let pkg_a_dir = temp_dir.join("node_modules").join("pkg-a");
-        create_dir_all(&pkg_a_dir);
+        common::create_dir_all(&pkg_a_dir);
 
-        write_file(
+        common::write_file(
             &pkg_a_dir.join("package.json"),
             r#"{"name": "pkg-a", "main": "index.js"}"#,
         );
 
-        write_file(
+        common::write_file(
             &pkg_a_dir.join("index.js"),
             r#"
 import { bValue } from 'pkg-b';
@@ -471,17 +454,17 @@ export const aValue = "a:" + bValue;
 
         // Create pkg-b
         let pkg_b_dir = temp_dir.join("node_modules").join("pkg-b");
-        create_dir_all(&pkg_b_dir);
+        common::create_dir_all(&pkg_b_dir);
 
-        write_file(
+        common::write_file(
             &pkg_b_dir.join("package.json"),
             r#"{"name": "pkg-b", "main": "index.js"}"#,
         );
 
-        write_file(&pkg_b_dir.join("index.js"), r#"export const bValue = "b";"#);
+        common::write_file(&pkg_b_dir.join("index.js"), r#"export const bValue = "b";"#);
 
         let test_file = temp_dir.join("test.js");
-        write_file(
+        common::write_file(
             &test_file,
             r#"
 import { aValue } from 'pkg-a';
@@ -498,7 +481,7 @@ globalThis.transitiveResult = aValue;
         let result = jstime.run_script("globalThis.transitiveResult", "test");
         assert_eq!(result.unwrap(), "a:b");
 
-        cleanup(&temp_dir);
+        common::cleanup(&temp_dir);
     }
 
     /// Test conditional exports with "import" key
@@ -507,14 +490,14 @@ globalThis.transitiveResult = aValue;
         let _setup_guard = common::setup();
 
         let temp_dir = std::env::temp_dir().join("jstime_test_conditional");
-        cleanup(&temp_dir);
-        create_dir_all(&temp_dir);
+        common::cleanup(&temp_dir);
+        common::create_dir_all(&temp_dir);
 
         let pkg_dir = temp_dir.join("node_modules").join("conditional-pkg");
-        create_dir_all(&pkg_dir.join("esm"));
-        create_dir_all(&pkg_dir.join("cjs"));
+        common::create_dir_all(&pkg_dir.join("esm"));
+        common::create_dir_all(&pkg_dir.join("cjs"));
 
-        write_file(
+        common::write_file(
             &pkg_dir.join("package.json"),
             r#"{
   "name": "conditional-pkg",
@@ -527,18 +510,18 @@ globalThis.transitiveResult = aValue;
 }"#,
         );
 
-        write_file(
+        common::write_file(
             &pkg_dir.join("esm").join("index.js"),
             r#"export const moduleType = "ESM";"#,
         );
 
-        write_file(
+        common::write_file(
             &pkg_dir.join("cjs").join("index.js"),
             r#"module.exports = { moduleType: "CJS" };"#,
         );
 
         let test_file = temp_dir.join("test.js");
-        write_file(
+        common::write_file(
             &test_file,
             r#"
 import { moduleType } from 'conditional-pkg';
@@ -555,7 +538,7 @@ globalThis.conditionalResult = moduleType;
         let result = jstime.run_script("globalThis.conditionalResult", "test");
         assert_eq!(result.unwrap(), "ESM");
 
-        cleanup(&temp_dir);
+        common::cleanup(&temp_dir);
     }
 
     /// Test that node: prefix still works
@@ -564,11 +547,11 @@ globalThis.conditionalResult = moduleType;
         let _setup_guard = common::setup();
 
         let temp_dir = std::env::temp_dir().join("jstime_test_node_prefix");
-        cleanup(&temp_dir);
-        create_dir_all(&temp_dir);
+        common::cleanup(&temp_dir);
+        common::create_dir_all(&temp_dir);
 
         let test_file = temp_dir.join("test.js");
-        write_file(
+        common::write_file(
             &test_file,
             r#"
 import fs from 'node:fs/promises';
@@ -585,7 +568,7 @@ globalThis.hasReadFile = typeof fs.readFile === 'function';
         let result = jstime.run_script("globalThis.hasReadFile", "test");
         assert_eq!(result.unwrap(), "true");
 
-        cleanup(&temp_dir);
+        common::cleanup(&temp_dir);
     }
 
     /// Test package imports with exact match (#utils)
@@ -594,12 +577,12 @@ globalThis.hasReadFile = typeof fs.readFile === 'function';
         let _setup_guard = common::setup();
 
         let temp_dir = std::env::temp_dir().join("jstime_test_imports_exact");
-        cleanup(&temp_dir);
-        create_dir_all(&temp_dir);
-        create_dir_all(&temp_dir.join("src"));
+        common::cleanup(&temp_dir);
+        common::create_dir_all(&temp_dir);
+        common::create_dir_all(&temp_dir.join("src"));
 
         // Create package.json with imports field
-        write_file(
+        common::write_file(
             &temp_dir.join("package.json"),
             r##"{
   "name": "my-app",
@@ -609,13 +592,13 @@ globalThis.hasReadFile = typeof fs.readFile === 'function';
 }"##,
         );
 
-        write_file(
+        common::write_file(
             &temp_dir.join("src").join("utils.js"),
             r##"export const helper = "Hello from #utils!";"##,
         );
 
         let test_file = temp_dir.join("test.js");
-        write_file(
+        common::write_file(
             &test_file,
             r##"
 import { helper } from '#utils';
@@ -632,7 +615,7 @@ globalThis.importsResult = helper;
         let result = jstime.run_script("globalThis.importsResult", "test");
         assert_eq!(result.unwrap(), "Hello from #utils!");
 
-        cleanup(&temp_dir);
+        common::cleanup(&temp_dir);
     }
 
     /// Test package imports with wildcard pattern (#internal/*)
@@ -641,9 +624,9 @@ globalThis.importsResult = helper;
         let _setup_guard = common::setup();
 
         let temp_dir = std::env::temp_dir().join("jstime_test_imports_wildcard");
-        cleanup(&temp_dir);
-        create_dir_all(&temp_dir);
-        create_dir_all(&temp_dir.join("src").join("internal"));
+        common::cleanup(&temp_dir);
+        common::create_dir_all(&temp_dir);
+        common::create_dir_all(&temp_dir.join("src").join("internal"));
 
         // Create package.json with wildcard imports
         // Using concat to avoid Rust raw string issues with /*
@@ -657,15 +640,15 @@ globalThis.importsResult = helper;
             "  }\n",
             "}"
         );
-        write_file(&temp_dir.join("package.json"), package_json);
+        common::write_file(&temp_dir.join("package.json"), package_json);
 
-        write_file(
+        common::write_file(
             &temp_dir.join("src").join("internal").join("helpers.js"),
             r#"export const internalHelper = "From internal helpers!";"#,
         );
 
         let test_file = temp_dir.join("test.js");
-        write_file(
+        common::write_file(
             &test_file,
             r#"
 import { internalHelper } from '#internal/helpers';
@@ -682,7 +665,7 @@ globalThis.wildcardResult = internalHelper;
         let result = jstime.run_script("globalThis.wildcardResult", "test");
         assert_eq!(result.unwrap(), "From internal helpers!");
 
-        cleanup(&temp_dir);
+        common::cleanup(&temp_dir);
     }
 
     /// Test package imports with conditional exports
@@ -691,13 +674,13 @@ globalThis.wildcardResult = internalHelper;
         let _setup_guard = common::setup();
 
         let temp_dir = std::env::temp_dir().join("jstime_test_imports_conditional");
-        cleanup(&temp_dir);
-        create_dir_all(&temp_dir);
-        create_dir_all(&temp_dir.join("src").join("esm"));
-        create_dir_all(&temp_dir.join("src").join("cjs"));
+        common::cleanup(&temp_dir);
+        common::create_dir_all(&temp_dir);
+        common::create_dir_all(&temp_dir.join("src").join("esm"));
+        common::create_dir_all(&temp_dir.join("src").join("cjs"));
 
         // Create package.json with conditional imports
-        write_file(
+        common::write_file(
             &temp_dir.join("package.json"),
             r##"{
   "name": "my-app",
@@ -710,18 +693,18 @@ globalThis.wildcardResult = internalHelper;
 }"##,
         );
 
-        write_file(
+        common::write_file(
             &temp_dir.join("src").join("esm").join("config.js"),
             r##"export const configType = "ESM config";"##,
         );
 
-        write_file(
+        common::write_file(
             &temp_dir.join("src").join("cjs").join("config.js"),
             r##"module.exports = { configType: "CJS config" };"##,
         );
 
         let test_file = temp_dir.join("test.js");
-        write_file(
+        common::write_file(
             &test_file,
             r##"
 import { configType } from '#config';
@@ -738,7 +721,7 @@ globalThis.conditionalImportResult = configType;
         let result = jstime.run_script("globalThis.conditionalImportResult", "test");
         assert_eq!(result.unwrap(), "ESM config");
 
-        cleanup(&temp_dir);
+        common::cleanup(&temp_dir);
     }
 
     /// Test package imports in nested directory
@@ -747,12 +730,12 @@ globalThis.conditionalImportResult = configType;
         let _setup_guard = common::setup();
 
         let temp_dir = std::env::temp_dir().join("jstime_test_imports_nested");
-        cleanup(&temp_dir);
-        create_dir_all(&temp_dir);
-        create_dir_all(&temp_dir.join("src").join("lib").join("deep"));
+        common::cleanup(&temp_dir);
+        common::create_dir_all(&temp_dir);
+        common::create_dir_all(&temp_dir.join("src").join("lib").join("deep"));
 
         // Create package.json at root with imports field
-        write_file(
+        common::write_file(
             &temp_dir.join("package.json"),
             r##"{
   "name": "my-app",
@@ -762,7 +745,7 @@ globalThis.conditionalImportResult = configType;
 }"##,
         );
 
-        write_file(
+        common::write_file(
             &temp_dir.join("src").join("shared.js"),
             r##"export const sharedValue = "Shared from root!";"##,
         );
@@ -773,7 +756,7 @@ globalThis.conditionalImportResult = configType;
             .join("lib")
             .join("deep")
             .join("test.js");
-        write_file(
+        common::write_file(
             &test_file,
             r##"
 import { sharedValue } from '#shared';
@@ -790,7 +773,7 @@ globalThis.nestedImportResult = sharedValue;
         let result = jstime.run_script("globalThis.nestedImportResult", "test");
         assert_eq!(result.unwrap(), "Shared from root!");
 
-        cleanup(&temp_dir);
+        common::cleanup(&temp_dir);
     }
 
     /// Test self-referencing import (package imports itself by its own name)
@@ -799,12 +782,12 @@ globalThis.nestedImportResult = sharedValue;
         let _setup_guard = common::setup();
 
         let temp_dir = std::env::temp_dir().join("jstime_test_self_ref");
-        cleanup(&temp_dir);
-        create_dir_all(&temp_dir);
-        create_dir_all(&temp_dir.join("src"));
+        common::cleanup(&temp_dir);
+        common::create_dir_all(&temp_dir);
+        common::create_dir_all(&temp_dir.join("src"));
 
         // Create package.json with name and exports
-        write_file(
+        common::write_file(
             &temp_dir.join("package.json"),
             r#"{
   "name": "my-library",
@@ -815,19 +798,19 @@ globalThis.nestedImportResult = sharedValue;
 }"#,
         );
 
-        write_file(
+        common::write_file(
             &temp_dir.join("src").join("index.js"),
             r#"export const main = "Hello from main!";"#,
         );
 
-        write_file(
+        common::write_file(
             &temp_dir.join("src").join("utils.js"),
             r#"export const helper = "Hello from utils!";"#,
         );
 
         // Test file that imports from its own package using the package name
         let test_file = temp_dir.join("src").join("test.js");
-        write_file(
+        common::write_file(
             &test_file,
             r#"
 import { main } from 'my-library';
@@ -844,7 +827,7 @@ globalThis.selfRefResult = main;
         let result = jstime.run_script("globalThis.selfRefResult", "test");
         assert_eq!(result.unwrap(), "Hello from main!");
 
-        cleanup(&temp_dir);
+        common::cleanup(&temp_dir);
     }
 
     /// Test self-referencing import with subpath (package imports a subpath of itself)
@@ -853,12 +836,12 @@ globalThis.selfRefResult = main;
         let _setup_guard = common::setup();
 
         let temp_dir = std::env::temp_dir().join("jstime_test_self_ref_sub");
-        cleanup(&temp_dir);
-        create_dir_all(&temp_dir);
-        create_dir_all(&temp_dir.join("src"));
+        common::cleanup(&temp_dir);
+        common::create_dir_all(&temp_dir);
+        common::create_dir_all(&temp_dir.join("src"));
 
         // Create package.json with name and exports
-        write_file(
+        common::write_file(
             &temp_dir.join("package.json"),
             r#"{
   "name": "my-library",
@@ -869,19 +852,19 @@ globalThis.selfRefResult = main;
 }"#,
         );
 
-        write_file(
+        common::write_file(
             &temp_dir.join("src").join("index.js"),
             r#"export const main = "Hello from main!";"#,
         );
 
-        write_file(
+        common::write_file(
             &temp_dir.join("src").join("utils.js"),
             r#"export const helper = "Hello from utils!";"#,
         );
 
         // Test file that imports a subpath from its own package using the package name
         let test_file = temp_dir.join("src").join("test.js");
-        write_file(
+        common::write_file(
             &test_file,
             r#"
 import { helper } from 'my-library/utils';
@@ -898,7 +881,7 @@ globalThis.selfRefSubpathResult = helper;
         let result = jstime.run_script("globalThis.selfRefSubpathResult", "test");
         assert_eq!(result.unwrap(), "Hello from utils!");
 
-        cleanup(&temp_dir);
+        common::cleanup(&temp_dir);
     }
 
     /// Test self-referencing import from nested directory
@@ -907,12 +890,12 @@ globalThis.selfRefSubpathResult = helper;
         let _setup_guard = common::setup();
 
         let temp_dir = std::env::temp_dir().join("jstime_test_self_ref_nested");
-        cleanup(&temp_dir);
-        create_dir_all(&temp_dir);
-        create_dir_all(&temp_dir.join("src").join("lib").join("deep"));
+        common::cleanup(&temp_dir);
+        common::create_dir_all(&temp_dir);
+        common::create_dir_all(&temp_dir.join("src").join("lib").join("deep"));
 
         // Create package.json with name and exports at root
-        write_file(
+        common::write_file(
             &temp_dir.join("package.json"),
             r#"{
   "name": "my-library",
@@ -920,7 +903,7 @@ globalThis.selfRefSubpathResult = helper;
 }"#,
         );
 
-        write_file(
+        common::write_file(
             &temp_dir.join("src").join("index.js"),
             r#"export const value = "From the root!";"#,
         );
@@ -931,7 +914,7 @@ globalThis.selfRefSubpathResult = helper;
             .join("lib")
             .join("deep")
             .join("test.js");
-        write_file(
+        common::write_file(
             &test_file,
             r#"
 import { value } from 'my-library';
@@ -948,7 +931,7 @@ globalThis.nestedSelfRefResult = value;
         let result = jstime.run_script("globalThis.nestedSelfRefResult", "test");
         assert_eq!(result.unwrap(), "From the root!");
 
-        cleanup(&temp_dir);
+        common::cleanup(&temp_dir);
     }
 
     /// Test self-referencing import with scoped package name
@@ -957,12 +940,12 @@ globalThis.nestedSelfRefResult = value;
         let _setup_guard = common::setup();
 
         let temp_dir = std::env::temp_dir().join("jstime_test_self_ref_scoped");
-        cleanup(&temp_dir);
-        create_dir_all(&temp_dir);
-        create_dir_all(&temp_dir.join("src"));
+        common::cleanup(&temp_dir);
+        common::create_dir_all(&temp_dir);
+        common::create_dir_all(&temp_dir.join("src"));
 
         // Create package.json with scoped name and exports
-        write_file(
+        common::write_file(
             &temp_dir.join("package.json"),
             r#"{
   "name": "@myorg/my-library",
@@ -972,14 +955,14 @@ globalThis.nestedSelfRefResult = value;
 }"#,
         );
 
-        write_file(
+        common::write_file(
             &temp_dir.join("src").join("index.js"),
             r#"export const scopedMain = "Hello from scoped package!";"#,
         );
 
         // Test file that imports from its own scoped package
         let test_file = temp_dir.join("src").join("test.js");
-        write_file(
+        common::write_file(
             &test_file,
             r#"
 import { scopedMain } from '@myorg/my-library';
@@ -996,7 +979,7 @@ globalThis.scopedSelfRefResult = scopedMain;
         let result = jstime.run_script("globalThis.scopedSelfRefResult", "test");
         assert_eq!(result.unwrap(), "Hello from scoped package!");
 
-        cleanup(&temp_dir);
+        common::cleanup(&temp_dir);
     }
 
     /// Test self-referencing import with conditional exports
@@ -1005,12 +988,12 @@ globalThis.scopedSelfRefResult = scopedMain;
         let _setup_guard = common::setup();
 
         let temp_dir = std::env::temp_dir().join("jstime_test_self_ref_cond");
-        cleanup(&temp_dir);
-        create_dir_all(&temp_dir);
-        create_dir_all(&temp_dir.join("src").join("esm"));
+        common::cleanup(&temp_dir);
+        common::create_dir_all(&temp_dir);
+        common::create_dir_all(&temp_dir.join("src").join("esm"));
 
         // Create package.json with conditional exports
-        write_file(
+        common::write_file(
             &temp_dir.join("package.json"),
             r#"{
   "name": "my-library",
@@ -1023,14 +1006,14 @@ globalThis.scopedSelfRefResult = scopedMain;
 }"#,
         );
 
-        write_file(
+        common::write_file(
             &temp_dir.join("src").join("esm").join("index.js"),
             r#"export const moduleType = "ESM version";"#,
         );
 
         // Test file that imports from its own package
         let test_file = temp_dir.join("src").join("test.js");
-        write_file(
+        common::write_file(
             &test_file,
             r#"
 import { moduleType } from 'my-library';
@@ -1047,6 +1030,177 @@ globalThis.conditionalSelfRefResult = moduleType;
         let result = jstime.run_script("globalThis.conditionalSelfRefResult", "test");
         assert_eq!(result.unwrap(), "ESM version");
 
-        cleanup(&temp_dir);
+        common::cleanup(&temp_dir);
+    }
+
+    /// Test nested conditions, matched in package.json order
+    #[test]
+    fn test_node_modules_nested_conditions() {
+        let _setup_guard = common::setup();
+
+        let temp_dir = std::env::temp_dir().join("jstime_test_nested_conditions");
+        common::cleanup(&temp_dir);
+        let pkg_dir = temp_dir.join("node_modules").join("cond-pkg");
+        common::create_dir_all(&pkg_dir);
+
+        common::write_file(
+            &pkg_dir.join("package.json"),
+            r#"{
+  "name": "cond-pkg",
+  "exports": {
+    ".": {
+      "node": "./node.js",
+      "import": {
+        "development": "./dev.js",
+        "jstime": { "production": "./prod.js", "default": "./jstime.js" },
+        "default": "./import.js"
+      },
+      "default": "./fallback.js"
+    }
+  }
+}"#,
+        );
+        for name in ["node", "dev", "prod", "jstime", "import", "fallback"] {
+            common::write_file(
+                &pkg_dir.join(format!("{name}.js")),
+                &format!("export const entry = '{name}';"),
+            );
+        }
+
+        let test_file = temp_dir.join("test.js");
+        common::write_file(
+            &test_file,
+            r#"
+import { entry } from 'cond-pkg';
+globalThis.testResult = entry;
+            "#,
+        );
+
+        let mut jstime = jstime::JSTime::new(jstime::Options::default());
+        jstime.import(test_file.to_str().unwrap()).unwrap();
+        let result = jstime.run_script("globalThis.testResult", "test");
+        assert_eq!(result.unwrap(), "prod");
+
+        // Custom conditions replace "production"
+        let options = jstime::Options::default().with_conditions(vec!["development".into()]);
+        let mut jstime = jstime::JSTime::new(options);
+        jstime.import(test_file.to_str().unwrap()).unwrap();
+        let result = jstime.run_script("globalThis.testResult", "test");
+        assert_eq!(result.unwrap(), "dev");
+
+        common::cleanup(&temp_dir);
+    }
+
+    /// Test subpath patterns, null exclusions and unexported subpaths
+    #[test]
+    fn test_node_modules_exports_patterns() {
+        let _setup_guard = common::setup();
+
+        let temp_dir = std::env::temp_dir().join("jstime_test_exports_patterns");
+        common::cleanup(&temp_dir);
+        let pkg_dir = temp_dir.join("node_modules").join("pattern-pkg");
+        common::create_dir_all(&pkg_dir.join("src").join("features"));
+        common::create_dir_all(&pkg_dir.join("src").join("internal"));
+
+        common::write_file(
+            &pkg_dir.join("package.json"),
+            r#"{
+  "name": "pattern-pkg",
+  "exports": {
+    "./features/*.js": "./src/features/*.js",
+    "./features/internal/*": null,
+    "./*": "./src/*.js"
+  }
+}"#,
+        );
+        common::write_file(
+            &pkg_dir.join("src").join("features").join("a.js"),
+            "export const name = 'feature a';",
+        );
+        common::write_file(
+            &pkg_dir.join("src").join("util.js"),
+            "export const name = 'util';",
+        );
+        common::write_file(
+            &pkg_dir.join("src").join("internal").join("secret.js"),
+            "export const name = 'secret';",
+        );
+        common::write_file(&pkg_dir.join("hidden.js"), "export const name = 'hidden';");
+
+        let test_file = temp_dir.join("test.js");
+        common::write_file(
+            &test_file,
+            r#"
+import { name as feature } from 'pattern-pkg/features/a.js';
+import { name as util } from 'pattern-pkg/util';
+const blocked = [];
+for (const specifier of ['pattern-pkg/features/internal/secret', 'pattern-pkg/hidden.js']) {
+  try {
+    await import(specifier);
+    blocked.push('loaded');
+  } catch {
+    blocked.push('blocked');
+  }
+}
+globalThis.testResult = [feature, util, ...blocked].join(',');
+            "#,
+        );
+
+        let mut jstime = jstime::JSTime::new(jstime::Options::default());
+        let result = jstime.import(test_file.to_str().unwrap());
+        assert!(result.is_ok(), "Failed to import: {:?}", result);
+        let result = jstime.run_script("globalThis.testResult", "test");
+        assert_eq!(result.unwrap(), "feature a,util,blocked,blocked");
+
+        common::cleanup(&temp_dir);
+    }
+
+    /// Test array fallbacks and escaped strings in package.json
+    #[test]
+    fn test_node_modules_exports_fallbacks() {
+        let _setup_guard = common::setup();
+
+        let temp_dir = std::env::temp_dir().join("jstime_test_exports_fallbacks");
+        common::cleanup(&temp_dir);
+        let pkg_dir = temp_dir.join("node_modules").join("fallback-pkg");
+        common::create_dir_all(&pkg_dir.join("dir with space"));
+
+        common::write_file(
+            &pkg_dir.join("package.json"),
+            r#"{
+  "name": "fallback-pkg",
+  "description": "Escapes: \"quoted\" \\ é",
+  "exports": {
+    ".": ["invalid:target", { "browser": "./browser.js" }, "./dir with space/main.js"],
+    "./escaped": "./dir with space\/escaped.js"
+  }
+}"#,
+        );
+        common::write_file(
+            &pkg_dir.join("dir with space").join("main.js"),
+            "export const name = 'main';",
+        );
+        common::write_file(
+            &pkg_dir.join("dir with space").join("escaped.js"),
+            "export const name = 'escaped';",
+        );
+
+        let test_file = temp_dir.join("test.js");
+        common::write_file(
+            &test_file,
+            r#"
+import { name as main } from 'fallback-pkg';
+import { name as escaped } from 'fallback-pkg/escaped';
+globalThis.testResult = `${main},${escaped}`;
+            "#,
+        );
+
+        let mut jstime = jstime::JSTime::new(jstime::Options::default());
+        let result = jstime.import(test_file.to_str().unwrap());
+        assert!(result.is_ok(), "Failed to import: {:?}", result);
+        let result = jstime.run_script("globalThis.testResult", "test");
+        assert_eq!(result.unwrap(), "main,escaped");
+
+        common::cleanup(&temp_dir);
     }
 }
@@ -0,0 +1,156 @@
+use jstime_core as jstime;
+use std::path::Path;
+use std::sync::Arc;
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+mod common;
+
+/// Start a local HTTP server that serves `files` by path, and 404 for
+/// anything else. Returns the port and a count of the requests served.
+fn spawn_module_server(files: &'static [(&'static str, &'static str)]) -> (u16, Arc<AtomicUsize>) {
+    use std::io::{Read, Write};
+
+    let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+    let port = listener.local_addr().unwrap().port();
+    let requests = Arc::new(AtomicUsize::new(0));
+    let counter = requests.clone();
+    std::thread::spawn(move || {
+        for stream in listener.incoming() {
+            let Ok(mut stream) = stream else {
+                continue;
+            };
+            let counter = counter.clone();
+            std::thread::spawn(move || {
+                // Read until the end of the request headers
+                let mut request = Vec::new();
+                let mut buf = [0u8; 1024];
+                while !request.windows(4).any(|w| w == b"\r\n\r\n") {
+                    match stream.read(&mut buf) {
+                        Ok(0) | Err(_) => return,
+                        Ok(n) => request.extend_from_slice(&buf[..n]),
+                    }
+                }
+                counter.fetch_add(1, Ordering::SeqCst);
+                let request = String::from_utf8_lossy(&request);
+                let path = request.split(' ').nth(1).unwrap_or("/");
+                let response = match files.iter().find(|(p, _)| *p == path) {
+                    Some((_, body)) => format!(
+                        "HTTP/1.1 200 OK\r\nContent-Type: application/javascript\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+                        body.len(),
+                        body
+                    ),
+                    None => {
+                        "HTTP/1.1 404 Not Found\r\nContent-Length: 0\r\nConnection: close\r\n\r\n"
+                            .to_string()
+                    }
+                };
+                let _ = stream.write_all(response.as_bytes());
+            });
+        }
+    });
+    (port, requests)
+}
+
+/// A runtime caching remote modules in `cache_dir`
+fn runtime(cache_dir: &Path, cached_only: bool) -> jstime::JSTime {
+    let options = jstime::Options::default()
+        .with_cache_dir(cache_dir)
+        .with_cached_only(cached_only);
+    jstime::JSTime::new(options)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_import_remote_module() {
+        let _setup_guard = common::setup();
+
+        static FILES: &[(&str, &str)] = &[
+            (
+                "/lib/mod.js",
+                "export { add } from './math.js';\nexport const url = import.meta.url;",
+            ),
+            ("/lib/math.js", "export const add = (a, b) => a + b;"),
+            ("/data.json", r#"{"remote": true}"#),
+        ];
+        let (port, requests) = spawn_module_server(FILES);
+
+        let cache_dir = std::env::temp_dir().join("jstime_test_remote_import");
+        let _ = std::fs::remove_dir_all(&cache_dir);
+        let temp_dir = std::env::temp_dir().join("jstime_test_remote_import_src");
+        std::fs::create_dir_all(&temp_dir).unwrap();
+        let test_file = temp_dir.join("test.js");
+        std::fs::write(
+            &test_file,
+            format!(
+                r#"
+import {{ add, url }} from 'http://127.0.0.1:{port}/lib/mod.js';
+import data from 'http://127.0.0.1:{port}/lib/../data.json';
+const again = await import('http://127.0.0.1:{port}/lib/math.js');
+globalThis.testResult = [add(2, 3), url, data.remote, again.add === add].join(',');
+"#
+            ),
+        )
+        .unwrap();
+
+        let mut jstime = runtime(&cache_dir, false);
+        let result = jstime.import(test_file.to_str().unwrap());
+        assert!(result.is_ok(), "Failed to import: {:?}", result);
+        let result = jstime.run_script("globalThis.testResult", "test");
+        assert_eq!(
+            result.unwrap(),
+            format!("5,http://127.0.0.1:{port}/lib/mod.js,true,true")
+        );
+
+        // Each module is downloaded once and written to the cache
+        assert_eq!(requests.load(Ordering::SeqCst), 3);
+        let cached_urls = std::fs::read_dir(cache_dir.join("remote").join("urls")).unwrap();
+        assert_eq!(cached_urls.count(), 3);
+
+        let _ = std::fs::remove_dir_all(&cache_dir);
+        let _ = std::fs::remove_dir_all(&temp_dir);
+    }
+
+    #[test]
+    fn test_remote_module_not_found() {
+        let _setup_guard = common::setup();
+
+        let (port, _) = spawn_module_server(&[]);
+        let cache_dir = std::env::temp_dir().join("jstime_test_remote_not_found");
+        let _ = std::fs::remove_dir_all(&cache_dir);
+
+        let mut jstime = runtime(&cache_dir, false);
+        let err = jstime
+            .import(&format!("http://127.0.0.1:{port}/missing.js"))
+            .unwrap_err()
+            .to_string();
+        assert!(
+            err.contains("HTTP 404 Not Found"),
+            "Unexpected error: {err}"
+        );
+
+        let _ = std::fs::remove_dir_all(&cache_dir);
+    }
+
+    #[test]
+    fn test_cached_only_does_not_download() {
+        let _setup_guard = common::setup();
+
+        static FILES: &[(&str, &str)] = &[("/mod.js", "globalThis.testResult = 'loaded';")];
+        let (port, requests) = spawn_module_server(FILES);
+        let cache_dir = std::env::temp_dir().join("jstime_test_remote_cached_only");
+        let _ = std::fs::remove_dir_all(&cache_dir);
+
+        let mut jstime = runtime(&cache_dir, true);
+        let err = jstime
+            .import(&format!("http://127.0.0.1:{port}/mod.js"))
+            .unwrap_err()
+            .to_string();
+        assert!(err.contains("isn't cached"), "Unexpected error: {err}");
+        assert_eq!(requests.load(Ordering::SeqCst), 0);
+
+        let _ = std::fs::remove_dir_all(&cache_dir);
+    }
+}
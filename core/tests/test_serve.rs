@@ -0,0 +1,353 @@
+use jstime_core as jstime;
+
+mod common;
+
+/// Find a port nobody is listening on by binding and dropping a listener
+fn free_port() -> u16 {
+    let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+    listener.local_addr().unwrap().port()
+}
+
+/// Send `request` as raw bytes to a server that will start listening on `port`,
+/// and return everything the server writes before closing the connection.
+fn spawn_raw_client(port: u16, request: &'static str) -> std::thread::JoinHandle<String> {
+    use std::io::{Read, Write};
+
+    std::thread::spawn(move || {
+        // The server starts once the script runs, so retry until it is listening
+        let mut stream = loop {
+            match std::net::TcpStream::connect(("127.0.0.1", port)) {
+                Ok(stream) => break stream,
+                Err(_) => std::thread::sleep(std::time::Duration::from_millis(10)),
+            }
+        };
+        stream.write_all(request.as_bytes()).unwrap();
+        let mut response = Vec::new();
+        let _ = stream.read_to_end(&mut response);
+        String::from_utf8_lossy(&response).into_owned()
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Run a script that settles asynchronously and read back globalThis.result
+    fn run_async(script: &str) -> Result<String, jstime::Error> {
+        let _setup_guard = common::setup();
+        let options = jstime::Options::default();
+        let mut jstime = jstime::JSTime::new(options);
+        jstime.run_script(
+            &format!("globalThis.result = null; {}", script),
+            "test_serve",
+        )?;
+        jstime.run_script("globalThis.result;", "test_serve")
+    }
+
+    #[test]
+    fn serve_exists() {
+        let result = common::run_test_script("typeof jstime.serve;");
+        assert_eq!(result.unwrap(), "function");
+    }
+
+    #[test]
+    fn serve_basic_response() {
+        let result = run_async(
+            "const server = jstime.serve({ port: 0, hostname: '127.0.0.1', onListen() {} }, \
+               () => new Response('hello', { headers: { 'x-test': 'yes' } })); \
+             fetch(`http://127.0.0.1:${server.addr.port}/`).then(async r => { \
+               globalThis.result = `${r.status}:${r.headers.get('x-test')}:${r.headers.get('content-type')}:${await r.text()}`; \
+               server.shutdown(); \
+             });",
+        );
+        assert_eq!(result.unwrap(), "200:yes:text/plain;charset=UTF-8:hello");
+    }
+
+    #[test]
+    fn serve_request_details() {
+        let result = run_async(
+            "const server = jstime.serve({ port: 0, hostname: '127.0.0.1', onListen() {} }, \
+               async (req, info) => { \
+                 const url = new URL(req.url); \
+                 return new Response(JSON.stringify({ \
+                   method: req.method, path: url.pathname + url.search, \
+                   header: req.headers.get('x-custom'), body: await req.text(), \
+                   remote: info.remoteAddr.hostname \
+                 })); \
+               }); \
+             fetch(`http://127.0.0.1:${server.addr.port}/a/b?c=d`, { \
+               method: 'POST', headers: { 'x-custom': 'value' }, body: 'payload' \
+             }).then(r => r.text()).then(t => { globalThis.result = t; server.shutdown(); });",
+        );
+        assert_eq!(
+            result.unwrap(),
+            r#"{"method":"POST","path":"/a/b?c=d","header":"value","body":"payload","remote":"127.0.0.1"}"#
+        );
+    }
+
+    #[test]
+    fn serve_streaming_response() {
+        let result = run_async(
+            "const server = jstime.serve({ port: 0, hostname: '127.0.0.1', onListen() {} }, () => { \
+               let n = 0; \
+               const body = new ReadableStream({ \
+                 pull(controller) { \
+                   if (n === 3) { controller.close(); return; } \
+                   controller.enqueue(new TextEncoder().encode(`chunk${n++};`)); \
+                 } \
+               }); \
+               return new Response(body); \
+             }); \
+             fetch(`http://127.0.0.1:${server.addr.port}/`).then(async r => { \
+               globalThis.result = await r.text(); \
+               server.shutdown(); \
+             });",
+        );
+        assert_eq!(result.unwrap(), "chunk0;chunk1;chunk2;");
+    }
+
+    #[test]
+    fn serve_rejects_bodies_too_large() {
+        let _setup_guard = common::setup();
+        let options = jstime::Options::default();
+        let mut jstime = jstime::JSTime::new(options);
+
+        for request in [
+            "POST / HTTP/1.1\r\nHost: localhost\r\nContent-Length: 99999999999\r\n\r\n",
+            "POST / HTTP/1.1\r\nHost: localhost\r\nTransfer-Encoding: chunked\r\n\r\nffffffffffff\r\n",
+        ] {
+            let port = free_port();
+            // The connection threads answer without calling into JS
+            jstime
+                .run_script_no_event_loop(
+                    &format!(
+                        "jstime.serve({{ port: {port}, hostname: '127.0.0.1', onListen() {{}} }}, \
+                           () => new Response('unreachable')).unref();"
+                    ),
+                    "test_serve",
+                )
+                .unwrap();
+            let response = spawn_raw_client(port, request).join().unwrap();
+            assert!(
+                response.starts_with("HTTP/1.1 413 Content Too Large\r\n"),
+                "{response}"
+            );
+        }
+    }
+
+    #[test]
+    fn serve_rejects_ambiguous_framing() {
+        let _setup_guard = common::setup();
+        let options = jstime::Options::default();
+        let mut jstime = jstime::JSTime::new(options);
+
+        for request in [
+            "POST / HTTP/1.1\r\nHost: localhost\r\nContent-Length: 1\r\nContent-Length: 2\r\n\r\nab",
+            "POST / HTTP/1.1\r\nHost: localhost\r\nContent-Length: +1\r\n\r\na",
+            "POST / HTTP/1.1\r\nHost: localhost\r\nTransfer-Encoding: gzip, chunked\r\n\r\n0\r\n\r\n",
+            "POST / HTTP/1.1\r\nHost: localhost\r\nTransfer-Encoding: chunked\r\nTransfer-Encoding: chunked\r\n\r\n0\r\n\r\n",
+        ] {
+            let port = free_port();
+            // The connection threads answer without calling into JS
+            jstime
+                .run_script_no_event_loop(
+                    &format!(
+                        "jstime.serve({{ port: {port}, hostname: '127.0.0.1', onListen() {{}} }}, \
+                           () => new Response('unreachable')).unref();"
+                    ),
+                    "test_serve",
+                )
+                .unwrap();
+            let response = spawn_raw_client(port, request).join().unwrap();
+            assert!(
+                response.starts_with("HTTP/1.1 400 Bad Request\r\n"),
+                "{response}"
+            );
+        }
+    }
+
+    #[test]
+    fn serve_closes_after_both_framings() {
+        let _setup_guard = common::setup();
+        let port = free_port();
+        // The second request would be answered if the connection were reused
+        let client = spawn_raw_client(
+            port,
+            "POST / HTTP/1.1\r\nHost: localhost\r\nTransfer-Encoding: chunked\r\nContent-Length: 8\r\n\r\n\
+             2\r\nab\r\n0\r\n\r\n\
+             GET / HTTP/1.1\r\nHost: localhost\r\nConnection: close\r\n\r\n",
+        );
+        let options = jstime::Options::default();
+        let mut jstime = jstime::JSTime::new(options);
+
+        jstime
+            .run_script(
+                &format!(
+                    "const server = jstime.serve({{ port: {port}, hostname: '127.0.0.1', onListen() {{}} }}, async req => {{ \
+                       const body = await req.text(); \
+                       setTimeout(() => server.shutdown(), 50); \
+                       return new Response(body); \
+                     }});"
+                ),
+                "test_serve",
+            )
+            .unwrap();
+
+        let response = client.join().unwrap();
+        assert_eq!(response.matches("HTTP/1.1 ").count(), 1, "{response}");
+        assert!(response.starts_with("HTTP/1.1 200 OK\r\n"), "{response}");
+        assert!(response.contains("connection: close\r\n"), "{response}");
+        assert!(response.ends_with("\r\n\r\nab"), "{response}");
+    }
+
+    #[test]
+    fn serve_streaming_response_is_chunked() {
+        let _setup_guard = common::setup();
+        let port = free_port();
+        let client = spawn_raw_client(
+            port,
+            "GET / HTTP/1.1\r\nHost: localhost\r\nConnection: close\r\n\r\n",
+        );
+        let options = jstime::Options::default();
+        let mut jstime = jstime::JSTime::new(options);
+
+        jstime
+            .run_script(
+                &format!(
+                    "const server = jstime.serve({{ port: {port}, hostname: '127.0.0.1', onListen() {{}} }}, () => {{ \
+                       queueMicrotask(() => server.shutdown()); \
+                       return new Response(new ReadableStream({{ \
+                         start(controller) {{ controller.enqueue(new TextEncoder().encode('abc')); controller.close(); }} \
+                       }})); \
+                     }});"
+                ),
+                "test_serve",
+            )
+            .unwrap();
+
+        let response = client.join().unwrap();
+        assert!(response.starts_with("HTTP/1.1 200 OK\r\n"), "{response}");
+        assert!(
+            response.contains("transfer-encoding: chunked\r\n"),
+            "{response}"
+        );
+        assert!(
+            response.ends_with("\r\n\r\n3\r\nabc\r\n0\r\n\r\n"),
+            "{response}"
+        );
+    }
+
+    #[test]
+    fn serve_keep_alive() {
+        let _setup_guard = common::setup();
+        let port = free_port();
+        let client = spawn_raw_client(
+            port,
+            "GET /first HTTP/1.1\r\nHost: localhost\r\n\r\n\
+             GET /second HTTP/1.1\r\nHost: localhost\r\nConnection: close\r\n\r\n",
+        );
+        let options = jstime::Options::default();
+        let mut jstime = jstime::JSTime::new(options);
+
+        jstime
+            .run_script(
+                &format!(
+                    "let count = 0; \
+                     const server = jstime.serve({{ port: {port}, hostname: '127.0.0.1', onListen() {{}} }}, req => {{ \
+                       if (++count === 2) server.shutdown(); \
+                       return new Response(new URL(req.url).pathname); \
+                     }});"
+                ),
+                "test_serve",
+            )
+            .unwrap();
+
+        let response = client.join().unwrap();
+        assert_eq!(response.matches("HTTP/1.1 200 OK").count(), 2, "{response}");
+        assert!(response.contains("\r\n\r\n/first"), "{response}");
+        assert!(response.ends_with("\r\n\r\n/second"), "{response}");
+    }
+
+    #[test]
+    fn serve_handler_error_returns_500() {
+        let result = run_async(
+            "const server = jstime.serve({ port: 0, hostname: '127.0.0.1', onListen() {}, \
+               onError: e => new Response(`handled: ${e.message}`, { status: 500 }) }, \
+               () => { throw new Error('boom'); }); \
+             fetch(`http://127.0.0.1:${server.addr.port}/`).then(async r => { \
+               globalThis.result = `${r.status}:${await r.text()}`; \
+               server.shutdown(); \
+             });",
+        );
+        assert_eq!(result.unwrap(), "500:handled: boom");
+    }
+
+    #[test]
+    fn serve_non_response_is_error() {
+        let result = run_async(
+            "const server = jstime.serve({ port: 0, hostname: '127.0.0.1', onListen() {}, \
+               onError: e => new Response(e.constructor.name, { status: 500 }) }, \
+               () => 'not a response'); \
+             fetch(`http://127.0.0.1:${server.addr.port}/`).then(async r => { \
+               globalThis.result = `${r.status}:${await r.text()}`; \
+               server.shutdown(); \
+             });",
+        );
+        assert_eq!(result.unwrap(), "500:TypeError");
+    }
+
+    #[test]
+    fn serve_on_listen_and_addr() {
+        let result = run_async(
+            "let listened = null; \
+             const server = jstime.serve({ port: 0, hostname: '127.0.0.1', onListen(addr) { listened = addr; } }, \
+               () => new Response('')); \
+             globalThis.result = `${listened === server.addr}:${server.addr.hostname}:${server.addr.port > 0}:${server.addr.transport}`; \
+             server.shutdown();",
+        );
+        assert_eq!(result.unwrap(), "true:127.0.0.1:true:tcp");
+    }
+
+    #[test]
+    fn serve_finished_after_shutdown() {
+        let result = run_async(
+            "const server = jstime.serve({ port: 0, hostname: '127.0.0.1', onListen() {} }, () => new Response('')); \
+             server.finished.then(() => { globalThis.result = 'finished'; }); \
+             setTimeout(() => server.shutdown(), 10);",
+        );
+        assert_eq!(result.unwrap(), "finished");
+    }
+
+    #[test]
+    fn serve_abort_signal_shuts_down() {
+        let result = run_async(
+            "const controller = new AbortController(); \
+             const server = jstime.serve({ port: 0, hostname: '127.0.0.1', onListen() {}, signal: controller.signal }, \
+               () => new Response('')); \
+             server.finished.then(() => { globalThis.result = 'finished'; }); \
+             controller.abort();",
+        );
+        assert_eq!(result.unwrap(), "finished");
+    }
+
+    #[test]
+    fn serve_unref_does_not_keep_loop_alive() {
+        let result = run_async(
+            "const server = jstime.serve({ port: 0, hostname: '127.0.0.1', onListen() {} }, () => new Response('')); \
+             server.unref(); \
+             globalThis.result = 'exited';",
+        );
+        assert_eq!(result.unwrap(), "exited");
+    }
+
+    #[test]
+    fn serve_address_in_use_throws() {
+        let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+        let port = listener.local_addr().unwrap().port();
+        let result = common::run_test_script(&format!(
+            "try {{ jstime.serve({{ port: {port}, hostname: '127.0.0.1', onListen() {{}} }}, () => new Response('')); 'listening'; }} \
+             catch (e) {{ e.message.startsWith('Failed to listen'); }}"
+        ));
+        assert_eq!(result.unwrap(), "true");
+    }
+}
@@ -0,0 +1,145 @@
+use jstime_core as jstime;
+
+mod common;
+
+/// The original source of BUNDLE
+const APP_TS: &str = r#"// Built into dist/bundle.js
+
+export function fail(): never {
+  // always throws
+    throw new Error("boom");
+}
+
+try {
+
+  fail();
+} catch (e) {
+  globalThis.testResult = (e as Error).stack;
+}
+"#;
+
+/// APP_TS compiled, without its sourceMappingURL comment
+const BUNDLE: &str = r#"function fail() {
+  throw new Error("boom");
+}
+try { fail(); } catch (e) { globalThis.testResult = e.stack; }
+"#;
+
+/// Maps `function` to 3:1, `throw new` to 5:5 and 5:11, and `fail()` to 10:3
+const MAPPINGS: &str = "AAEA;EAEI,MAAM;;MAKR";
+
+/// A source map for BUNDLE in dist/, with or without the original source
+fn source_map(with_content: bool) -> String {
+    let mut map = serde_json::json!({
+        "version": 3,
+        "file": "bundle.js",
+        "sources": ["../src/app.ts"],
+        "names": [],
+        "mappings": MAPPINGS,
+    });
+    if with_content {
+        map["sourcesContent"] = serde_json::json!([APP_TS]);
+    }
+    map.to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_inline_source_map_stack() {
+        let _setup_guard = common::setup();
+
+        let temp_dir = std::env::temp_dir().join("jstime_test_source_map_inline");
+        common::cleanup(&temp_dir);
+        common::create_dir_all(&temp_dir.join("dist"));
+
+        let data = base64_simd::STANDARD.encode_to_string(source_map(true));
+        common::write_file(
+            &temp_dir.join("dist").join("bundle.js"),
+            &format!(
+                "{}//# sourceMappingURL=data:application/json;base64,{}\n",
+                BUNDLE, data
+            ),
+        );
+
+        let mut jstime = jstime::JSTime::new(jstime::Options::default());
+        jstime
+            .import(temp_dir.join("dist").join("bundle.js").to_str().unwrap())
+            .unwrap();
+        let stack = jstime.run_script("globalThis.testResult", "test").unwrap();
+
+        // Module paths are canonical, and so are the sources they map to
+        let app = temp_dir.canonicalize().unwrap().join("src").join("app.ts");
+        let app = app.to_str().unwrap();
+        assert!(stack.starts_with("Error: boom\n"), "{stack}");
+        assert!(
+            stack.contains(&format!("at fail ({}:5:11)", app)),
+            "{stack}"
+        );
+        assert!(stack.contains(&format!("{}:10:3", app)), "{stack}");
+        assert!(!stack.contains("bundle.js"), "{stack}");
+
+        common::cleanup(&temp_dir);
+    }
+
+    #[test]
+    fn test_uncaught_error_reports_original_source() {
+        let _setup_guard = common::setup();
+
+        let temp_dir = std::env::temp_dir().join("jstime_test_source_map_external");
+        common::cleanup(&temp_dir);
+        common::create_dir_all(&temp_dir.join("dist"));
+        common::create_dir_all(&temp_dir.join("src"));
+
+        // Without the try/catch, so the error is uncaught
+        let bundle = BUNDLE.replace(
+            "try { fail(); } catch (e) { globalThis.testResult = e.stack; }",
+            "      fail();",
+        );
+        common::write_file(
+            &temp_dir.join("dist").join("bundle.js"),
+            &format!("{}//# sourceMappingURL=bundle.js.map\n", bundle),
+        );
+        common::write_file(
+            &temp_dir.join("dist").join("bundle.js.map"),
+            &source_map(false),
+        );
+        // The original source is read from disk without sourcesContent
+        common::write_file(&temp_dir.join("src").join("app.ts"), APP_TS);
+
+        let mut jstime = jstime::JSTime::new(jstime::Options::default());
+        let err = jstime
+            .import(temp_dir.join("dist").join("bundle.js").to_str().unwrap())
+            .unwrap_err()
+            .to_string();
+
+        // Module paths are canonical, and so are the sources they map to
+        let app = temp_dir.canonicalize().unwrap().join("src").join("app.ts");
+        let app = app.to_str().unwrap();
+        assert!(err.contains(&format!("{}:5", app)), "{err}");
+        assert!(err.contains("    throw new Error(\"boom\");"), "{err}");
+        assert!(err.contains(&format!("at fail ({}:5:11)", app)), "{err}");
+
+        common::cleanup(&temp_dir);
+    }
+
+    #[test]
+    fn test_prepare_stack_trace_override() {
+        let _setup_guard = common::setup();
+        let mut jstime = jstime::JSTime::new(jstime::Options::default());
+
+        let result = jstime.run_script(
+            r#"
+Error.prepareStackTrace = (error, callSites) =>
+  `${error.message} from ${callSites.length > 0 && typeof callSites[0].getLineNumber()}`;
+const stack = new Error('custom').stack;
+delete Error.prepareStackTrace;
+stack + '|' + new TypeError('default').stack.split('\n')[0];
+"#,
+            "test",
+        );
+        assert_eq!(result.unwrap(), "custom from number|TypeError: default");
+    }
+}
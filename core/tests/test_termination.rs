@@ -0,0 +1,162 @@
+use jstime_core as jstime;
+use std::time::{Duration, Instant};
+
+mod common;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_execution_timeout() {
+        let _setup_guard = common::setup();
+        let options = jstime::Options::default().with_execution_timeout(Duration::from_millis(200));
+        let mut jstime = jstime::JSTime::new(options);
+
+        let result = jstime.run_script("while (true) {}", "test");
+        assert!(
+            matches!(result, Err(jstime::Error::Terminated)),
+            "{result:?}"
+        );
+
+        // The isolate runs scripts again afterwards
+        let result = jstime.run_script("1 + 1", "test");
+        assert_eq!(result.unwrap(), "2");
+        let result = jstime.run_script_no_event_loop("while (true) {}", "test");
+        assert!(
+            matches!(result, Err(jstime::Error::Terminated)),
+            "{result:?}"
+        );
+        let result = jstime.run_script_no_event_loop("'still usable'", "test");
+        assert_eq!(result.unwrap(), "still usable");
+    }
+
+    #[test]
+    fn test_execution_timeout_in_event_loop() {
+        let _setup_guard = common::setup();
+        let options = jstime::Options::default().with_execution_timeout(Duration::from_millis(200));
+        let mut jstime = jstime::JSTime::new(options);
+
+        // A callback that never returns
+        let result = jstime.run_script("setTimeout(() => { while (true) {} }, 0);", "test");
+        assert!(
+            matches!(result, Err(jstime::Error::Terminated)),
+            "{result:?}"
+        );
+
+        // An event loop that never runs out of work, and one that waits
+        // longer than the timeout
+        let result = jstime.run_script("setInterval(() => {}, 1);", "test");
+        assert!(
+            matches!(result, Err(jstime::Error::Terminated)),
+            "{result:?}"
+        );
+        let start = Instant::now();
+        let result = jstime.run_script("setTimeout(() => {}, 60000);", "test");
+        assert!(
+            matches!(result, Err(jstime::Error::Terminated)),
+            "{result:?}"
+        );
+        assert!(start.elapsed() < Duration::from_secs(10));
+
+        // The terminated scripts' timers were dropped, so this run ends
+        let result = jstime.run_script(
+            "globalThis.ran = false; setTimeout(() => { globalThis.ran = true; }, 0); 'ok'",
+            "test",
+        );
+        assert_eq!(result.unwrap(), "ok");
+        let result = jstime.run_script("globalThis.ran", "test");
+        assert_eq!(result.unwrap(), "true");
+    }
+
+    #[test]
+    fn test_termination_handle() {
+        let _setup_guard = common::setup();
+        let mut jstime = jstime::JSTime::new(jstime::Options::default());
+        let handle = jstime.termination_handle();
+
+        let terminator = std::thread::spawn(move || {
+            std::thread::sleep(Duration::from_millis(100));
+            assert!(handle.terminate());
+        });
+        let result = jstime.run_script("let i = 0; while (true) { i++; }", "test");
+        terminator.join().unwrap();
+        assert!(
+            matches!(result, Err(jstime::Error::Terminated)),
+            "{result:?}"
+        );
+
+        // A termination requested while nothing runs doesn't affect later runs
+        jstime.termination_handle().terminate();
+        let result = jstime.run_script("'not terminated'", "test");
+        assert_eq!(result.unwrap(), "not terminated");
+    }
+
+    #[test]
+    fn test_exceptions_are_not_terminations() {
+        let _setup_guard = common::setup();
+        let options = jstime::Options::default().with_execution_timeout(Duration::from_secs(10));
+        let mut jstime = jstime::JSTime::new(options);
+
+        let result = jstime.run_script("throw new Error('thrown')", "test");
+        match result {
+            Err(jstime::Error::Exception(e)) => assert!(e.to_string().contains("thrown"), "{e}"),
+            other => panic!("Unexpected result: {other:?}"),
+        }
+        let result = jstime.run_script("'done in time'", "test");
+        assert_eq!(result.unwrap(), "done in time");
+    }
+
+    #[test]
+    fn test_heap_limit_exceeded() {
+        let _setup_guard = common::setup();
+        let options = jstime::Options::default().with_heap_limits(0, 64 * 1024 * 1024);
+        let mut jstime = jstime::JSTime::new(options);
+
+        let result = jstime.run_script(
+            "(() => { const objects = []; while (true) objects.push({ n: objects.length }); })()",
+            "test",
+        );
+        assert!(
+            matches!(result, Err(jstime::Error::HeapLimitExceeded)),
+            "{result:?}"
+        );
+
+        // The garbage is collected, and the isolate runs scripts again
+        let result = jstime.run_script("new Array(1000).fill(1).length", "test");
+        assert_eq!(result.unwrap(), "1000");
+
+        // And it's reported again when the limit is reached again
+        let result = jstime.run_script(
+            "setTimeout(() => { const strings = []; while (true) strings.push('x'.repeat(1024) + strings.length); }, 0);",
+            "test",
+        );
+        assert!(
+            matches!(result, Err(jstime::Error::HeapLimitExceeded)),
+            "{result:?}"
+        );
+        let result = jstime.run_script("'recovered'", "test");
+        assert_eq!(result.unwrap(), "recovered");
+    }
+
+    #[test]
+    fn test_import_timeout() {
+        let _setup_guard = common::setup();
+
+        let temp_dir = std::env::temp_dir().join("jstime_test_termination_import");
+        common::cleanup(&temp_dir);
+        common::create_dir_all(&temp_dir);
+        let main = temp_dir.join("main.js");
+        common::write_file(&main, "await Promise.resolve();\nwhile (true) {}");
+
+        let options = jstime::Options::default().with_execution_timeout(Duration::from_millis(200));
+        let mut jstime = jstime::JSTime::new(options);
+        let result = jstime.import(main.to_str().unwrap());
+        assert!(
+            matches!(result, Err(jstime::Error::Terminated)),
+            "{result:?}"
+        );
+
+        common::cleanup(&temp_dir);
+    }
+}
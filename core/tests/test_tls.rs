@@ -0,0 +1,247 @@
+use jstime_core as jstime;
+
+mod common;
+
+use std::sync::Arc;
+
+fn fixture(name: &str) -> String {
+    format!("{}/tests/fixtures/tls/{}", env!("CARGO_MANIFEST_DIR"), name)
+}
+
+/// The test CA certificate as a JS string literal
+fn ca_literal() -> String {
+    serde_json::to_string(&std::fs::read_to_string(fixture("ca.pem")).unwrap()).unwrap()
+}
+
+/// Start a TLS echo server for one connection, using the certificate signed by the test CA.
+/// The server thread returns the SNI name the client sent.
+fn spawn_tls_server(alpn: &[&str]) -> (u16, std::thread::JoinHandle<Option<String>>) {
+    use rustls::pki_types::pem::PemObject;
+    use rustls::pki_types::{CertificateDer, PrivateKeyDer};
+    use std::io::{Read, Write};
+
+    let certs = CertificateDer::pem_file_iter(fixture("server.pem"))
+        .unwrap()
+        .collect::<Result<Vec<_>, _>>()
+        .unwrap();
+    let key = PrivateKeyDer::from_pem_file(fixture("server-key.pem")).unwrap();
+    let provider = Arc::new(rustls::crypto::ring::default_provider());
+    let mut config = rustls::ServerConfig::builder_with_provider(provider)
+        .with_safe_default_protocol_versions()
+        .unwrap()
+        .with_no_client_auth()
+        .with_single_cert(certs, key)
+        .unwrap();
+    config.alpn_protocols = alpn.iter().map(|p| p.as_bytes().to_vec()).collect();
+    let config = Arc::new(config);
+
+    let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+    let port = listener.local_addr().unwrap().port();
+    let handle = std::thread::spawn(move || {
+        let (stream, _) = listener.accept().unwrap();
+        let conn = rustls::ServerConnection::new(config).unwrap();
+        let mut tls = rustls::StreamOwned::new(conn, stream);
+        let mut buf = [0u8; 4096];
+        // Echo until the client closes the connection or the handshake fails
+        loop {
+            match tls.read(&mut buf) {
+                Ok(0) | Err(_) => break,
+                Ok(n) => {
+                    if tls.write_all(&buf[..n]).is_err() {
+                        break;
+                    }
+                }
+            }
+        }
+        tls.conn.send_close_notify();
+        let _ = tls.flush();
+        tls.conn.server_name().map(str::to_string)
+    });
+    (port, handle)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Run a script that settles asynchronously and read back globalThis.result
+    fn run_async(script: &str) -> Result<String, jstime::Error> {
+        let _setup_guard = common::setup();
+        let options = jstime::Options::default();
+        let mut jstime = jstime::JSTime::new(options);
+        jstime.run_script(
+            &format!(
+                "globalThis.result = null; const tls = globalThis.__node_modules['node:tls']; \
+                 const CA = {}; {}",
+                ca_literal(),
+                script
+            ),
+            "test_tls",
+        )?;
+        jstime.run_script("globalThis.result;", "test_tls")
+    }
+
+    #[test]
+    fn test_tls_module_exists() {
+        let result = common::run_test_script(
+            "const tls = globalThis.__node_modules['node:tls']; \
+             tls === globalThis.__node_modules['tls'] && typeof tls.connect === 'function' \
+               && new tls.TLSSocket() instanceof globalThis.__node_modules['node:net'].Socket \
+               && typeof jstime.connectTls === 'function';",
+        );
+        assert_eq!(result.unwrap(), "true");
+    }
+
+    #[test]
+    fn test_tls_esm_named_exports() {
+        let _setup_guard = common::setup();
+        let temp_dir = std::env::temp_dir().join("jstime_test_tls_esm");
+        std::fs::create_dir_all(&temp_dir).unwrap();
+        let test_file = temp_dir.join("test.mjs");
+        std::fs::write(
+            &test_file,
+            "import tls, { connect, TLSSocket } from 'node:tls';\n\
+             globalThis.result = connect === tls.connect && typeof TLSSocket === 'function';\n",
+        )
+        .unwrap();
+
+        let options = jstime::Options::default();
+        let mut jstime = jstime::JSTime::new(options);
+        let result = jstime.import(test_file.to_str().unwrap());
+        assert!(result.is_ok(), "Failed to import: {:?}", result);
+        let result = jstime.run_script("globalThis.result", "test");
+        assert_eq!(result.unwrap(), "true");
+
+        let _ = std::fs::remove_dir_all(&temp_dir);
+    }
+
+    #[test]
+    fn test_tls_echo_with_custom_ca() {
+        let (port, server) = spawn_tls_server(&[]);
+        let result = run_async(&format!(
+            "const socket = tls.connect({{ port: {port}, host: '127.0.0.1', servername: 'localhost', ca: CA }}, () => {{ \
+               globalThis.result = `${{socket.authorized}}:${{socket.encrypted}}:${{socket.getProtocol()}}:${{socket.alpnProtocol}}`; \
+               socket.write('hello tls'); \
+             }}); \
+             socket.setEncoding('utf8'); \
+             socket.on('data', d => {{ globalThis.result += `:${{d}}`; socket.end(); }});"
+        ));
+        assert_eq!(result.unwrap(), "true:true:TLSv1.3:false:hello tls");
+        assert_eq!(server.join().unwrap().as_deref(), Some("localhost"));
+    }
+
+    #[test]
+    fn test_tls_sni_defaults_to_host() {
+        let (port, server) = spawn_tls_server(&[]);
+        let result = run_async(&format!(
+            "const socket = tls.connect({port}, 'localhost', {{ ca: [CA] }}, () => {{ \
+               globalThis.result = socket.servername; \
+               socket.end(); \
+             }});"
+        ));
+        assert_eq!(result.unwrap(), "localhost");
+        assert_eq!(server.join().unwrap().as_deref(), Some("localhost"));
+    }
+
+    #[test]
+    fn test_tls_ip_address_host() {
+        // IP addresses are verified against the certificate but not sent with SNI
+        let (port, server) = spawn_tls_server(&[]);
+        let result = run_async(&format!(
+            "const socket = tls.connect({{ port: {port}, host: '127.0.0.1', ca: CA }}, () => {{ \
+               globalThis.result = socket.authorized; \
+               socket.end(); \
+             }});"
+        ));
+        assert_eq!(result.unwrap(), "true");
+        assert_eq!(server.join().unwrap(), None);
+    }
+
+    #[test]
+    fn test_tls_alpn_negotiation() {
+        let (port, server) = spawn_tls_server(&["h2", "http/1.1"]);
+        let result = run_async(&format!(
+            "const socket = tls.connect({{ port: {port}, host: '127.0.0.1', servername: 'localhost', ca: CA, \
+               ALPNProtocols: ['http/1.1', 'h2'] }}, () => {{ \
+               globalThis.result = socket.alpnProtocol; \
+               socket.end(); \
+             }});"
+        ));
+        // The server's preference wins
+        assert_eq!(result.unwrap(), "h2");
+        server.join().unwrap();
+    }
+
+    #[test]
+    fn test_tls_unknown_issuer() {
+        let (port, server) = spawn_tls_server(&[]);
+        let result = run_async(&format!(
+            "const socket = tls.connect({{ port: {port}, host: '127.0.0.1', servername: 'localhost' }}); \
+             socket.on('secureConnect', () => {{ globalThis.result = 'connected'; }}); \
+             socket.on('error', e => {{ globalThis.result = `${{e.code}}:${{socket.authorized}}`; }});"
+        ));
+        assert_eq!(result.unwrap(), "UNABLE_TO_GET_ISSUER_CERT_LOCALLY:false");
+        server.join().unwrap();
+    }
+
+    #[test]
+    fn test_tls_name_mismatch() {
+        let (port, server) = spawn_tls_server(&[]);
+        let result = run_async(&format!(
+            "const socket = tls.connect({{ port: {port}, host: '127.0.0.1', servername: 'example.com', ca: CA }}); \
+             socket.on('error', e => {{ globalThis.result = e.code; }});"
+        ));
+        assert_eq!(result.unwrap(), "ERR_TLS_CERT_ALTNAME_INVALID");
+        server.join().unwrap();
+    }
+
+    #[test]
+    fn test_tls_invalid_ca_throws() {
+        let result = run_async(
+            "try { tls.connect({ port: 443, host: '127.0.0.1', ca: 'not a certificate' }); } \
+             catch (e) { globalThis.result = `${e.code}:${e.message}`; }",
+        );
+        assert_eq!(
+            result.unwrap(),
+            "EINVAL:Invalid CA certificate: no PEM certificate found"
+        );
+    }
+
+    #[test]
+    fn test_tls_reject_unauthorized_false_throws() {
+        let result = run_async(
+            "try { tls.connect({ port: 443, host: '127.0.0.1', rejectUnauthorized: false }); } \
+             catch (e) { globalThis.result = e.message; }",
+        );
+        assert_eq!(
+            result.unwrap(),
+            "tls: rejectUnauthorized: false is not supported"
+        );
+    }
+
+    #[test]
+    fn test_connect_tls() {
+        let (port, server) = spawn_tls_server(&["echo"]);
+        let result = run_async(&format!(
+            "jstime.connectTls({{ hostname: '127.0.0.1', port: {port}, serverName: 'localhost', \
+               caCerts: [CA], alpnProtocols: ['echo'] }}).then(conn => {{ \
+               conn.on('data', d => {{ globalThis.result += `:${{d}}`; conn.end(); }}); \
+               globalThis.result = conn.alpnProtocol; \
+               conn.write('ping'); \
+             }});"
+        ));
+        assert_eq!(result.unwrap(), "echo:ping");
+        server.join().unwrap();
+    }
+
+    #[test]
+    fn test_connect_tls_rejects_on_certificate_error() {
+        let (port, server) = spawn_tls_server(&[]);
+        let result = run_async(&format!(
+            "jstime.connectTls({{ hostname: '127.0.0.1', port: {port}, serverName: 'localhost' }}) \
+               .then(() => {{ globalThis.result = 'connected'; }}, e => {{ globalThis.result = e.code; }});"
+        ));
+        assert_eq!(result.unwrap(), "UNABLE_TO_GET_ISSUER_CERT_LOCALLY");
+        server.join().unwrap();
+    }
+}
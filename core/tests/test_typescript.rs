@@ -0,0 +1,176 @@
+use jstime_core as jstime;
+use std::path::Path;
+
+mod common;
+
+/// Import `entry` and return globalThis.testResult
+fn import(entry: &Path, options: jstime::Options) -> Result<String, jstime::Error> {
+    let mut jstime = jstime::JSTime::new(options);
+    jstime.import(entry.to_str().unwrap())?;
+    jstime.run_script("globalThis.testResult", "test")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_typescript_modules() {
+        let _setup_guard = common::setup();
+
+        let temp_dir = std::env::temp_dir().join("jstime_test_typescript_modules");
+        common::cleanup(&temp_dir);
+        common::create_dir_all(&temp_dir.join("lib"));
+
+        common::write_file(
+            &temp_dir.join("util.ts"),
+            r#"
+export interface Point { x: number; y: number }
+export type Pair<T> = [T, T];
+export function add(a: Point, b: Point): Point {
+  return { x: a.x + b.x, y: a.y + b.y };
+}
+"#,
+        );
+        common::write_file(
+            &temp_dir.join("lib").join("index.mts"),
+            "export const double = (n: number): number => n * 2;",
+        );
+        common::write_file(
+            &temp_dir.join("shape.ts"),
+            r#"
+export abstract class Shape {
+  private readonly sides: number;
+  constructor(sides: number) { this.sides = sides; }
+  abstract area(): number;
+  count(): number { return this.sides; }
+}
+"#,
+        );
+        common::write_file(
+            &temp_dir.join("test.ts"),
+            r#"
+import { add, type Point } from './util';
+import type { Pair } from './util.ts';
+import { double } from './lib';
+import { Shape } from './shape.js';
+
+class Square extends Shape implements Iterable<number> {
+  constructor(private_side: number) { super(4); this.side = private_side; }
+  declare side: number;
+  area(): number { return this.side ** 2; }
+  *[Symbol.iterator](): Iterator<number> { yield this.side; }
+}
+
+const p: Point = add({ x: 1, y: 2 }, { x: 3, y: 4 } as Point);
+const pair: Pair<string> = ['a', 'b'];
+const square = new Square(3)!;
+globalThis.testResult = [p.x, p.y, double(21), pair.join(''), square.area(), square.count()].join(',');
+"#,
+        );
+
+        let result = import(&temp_dir.join("test.ts"), jstime::Options::default());
+        assert_eq!(result.unwrap(), "4,6,42,ab,9,4");
+
+        common::cleanup(&temp_dir);
+    }
+
+    #[test]
+    fn test_tsx_with_custom_factory() {
+        let _setup_guard = common::setup();
+
+        let temp_dir = std::env::temp_dir().join("jstime_test_tsx_factory");
+        common::cleanup(&temp_dir);
+        common::create_dir_all(&temp_dir);
+
+        common::write_file(
+            &temp_dir.join("jsx.js"),
+            r#"
+export const Fragment = 'fragment';
+export function h(tag, props, ...children) {
+  const name = typeof tag === 'function' ? tag.name : tag;
+  const attrs = Object.entries(props || {}).map(([k, v]) => ` ${k}="${v}"`).join('');
+  return `<${name}${attrs}>${children.flat().join('')}</${name}>`;
+}
+"#,
+        );
+        common::write_file(
+            &temp_dir.join("test.tsx"),
+            r#"
+import { h, Fragment } from './jsx.js';
+
+function Greeting({ name }: { name: string }) {
+  return <b>{name}</b>;
+}
+const items: string[] = ['x', 'y'];
+globalThis.testResult = (
+  <>
+    <p class="title" data-count={items.length}>Hello &amp; welcome</p>
+    {items.map((item) => <i>{item}</i>)}
+  </>
+) + '|' + h(Greeting, { name: 'jstime' });
+"#,
+        );
+
+        let options = jstime::Options::default()
+            .with_jsx_factory("h")
+            .with_jsx_fragment("Fragment");
+        let result = import(&temp_dir.join("test.tsx"), options);
+        assert_eq!(
+            result.unwrap(),
+            r#"<fragment><p class="title" data-count="2">Hello & welcome</p><i>x</i><i>y</i></fragment>|<Greeting name="jstime"></Greeting>"#
+        );
+
+        common::cleanup(&temp_dir);
+    }
+
+    #[test]
+    fn test_typescript_errors_keep_source_positions() {
+        let _setup_guard = common::setup();
+
+        let temp_dir = std::env::temp_dir().join("jstime_test_typescript_positions");
+        common::cleanup(&temp_dir);
+        common::create_dir_all(&temp_dir);
+
+        common::write_file(
+            &temp_dir.join("test.ts"),
+            r#"interface Options {
+  strict: boolean;
+}
+const options: Options = { strict: true };
+try {
+  throw new Error('failed');
+} catch (e: unknown) {
+  globalThis.testResult = (e as Error).stack;
+}
+"#,
+        );
+
+        let result = import(&temp_dir.join("test.ts"), jstime::Options::default()).unwrap();
+        assert!(result.contains("test.ts:6:9"), "{result}");
+
+        common::cleanup(&temp_dir);
+    }
+
+    #[test]
+    fn test_unsupported_typescript_is_a_syntax_error() {
+        let _setup_guard = common::setup();
+
+        let temp_dir = std::env::temp_dir().join("jstime_test_typescript_enum");
+        common::cleanup(&temp_dir);
+        common::create_dir_all(&temp_dir);
+
+        common::write_file(
+            &temp_dir.join("test.ts"),
+            "const a: number = 1;\nenum Color { Red, Green }\n",
+        );
+
+        let result = import(&temp_dir.join("test.ts"), jstime::Options::default());
+        let err = result.unwrap_err().to_string();
+        assert!(err.contains("SyntaxError"), "{err}");
+        assert!(err.contains("Enums aren't supported"), "{err}");
+        assert!(err.contains("test.ts:2:1"), "{err}");
+
+        common::cleanup(&temp_dir);
+    }
+}
@@ -0,0 +1,206 @@
+use jstime_core as jstime;
+
+mod common;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_timer_exception_is_an_error() {
+        let _setup_guard = common::setup();
+        let mut jstime = jstime::JSTime::new(jstime::Options::default());
+
+        let err = jstime
+            .run_script(
+                r#"
+globalThis.ran = [];
+setTimeout(() => { throw new Error('from a timer'); }, 0);
+setTimeout(() => globalThis.ran.push('later'), 50);
+1
+"#,
+                "test",
+            )
+            .unwrap_err()
+            .to_string();
+        assert!(err.contains("from a timer"), "{err}");
+
+        // The event loop stopped at the exception
+        let ran = jstime.run_script("globalThis.ran.length", "test");
+        assert_eq!(ran.unwrap(), "0");
+    }
+
+    #[test]
+    fn test_error_event() {
+        let _setup_guard = common::setup();
+        let mut jstime = jstime::JSTime::new(jstime::Options::default());
+
+        let result = jstime.run_script(
+            r#"
+globalThis.events = [];
+addEventListener('error', (event) => {
+  globalThis.events.push(
+    `${event instanceof ErrorEvent}:${event.error.message}:${event.filename}:${event.lineno}:${event.colno > 0}`,
+  );
+  event.preventDefault();
+});
+setTimeout(() => {
+  throw new Error('handled by listener');
+}, 0);
+setTimeout(() => globalThis.events.push('still running'), 10);
+"#,
+            "test",
+        );
+        assert!(result.is_ok(), "{result:?}");
+        let events = jstime.run_script("globalThis.events.join()", "test");
+        assert_eq!(
+            events.unwrap(),
+            "true:handled by listener:test:10:true,still running"
+        );
+
+        // A listener that doesn't cancel the event leaves it unhandled
+        let mut jstime = jstime::JSTime::new(jstime::Options::default());
+        let err = jstime
+            .run_script(
+                r#"
+addEventListener('error', () => {});
+setTimeout(() => { throw new Error('observed'); }, 0);
+"#,
+                "test",
+            )
+            .unwrap_err()
+            .to_string();
+        assert!(err.contains("observed"), "{err}");
+    }
+
+    #[test]
+    fn test_process_uncaught_exception() {
+        let _setup_guard = common::setup();
+        let mut jstime = jstime::JSTime::new(jstime::Options::default());
+
+        let result = jstime.run_script(
+            r#"
+globalThis.errors = [];
+process.on('uncaughtException', (error, origin) => {
+  globalThis.errors.push(error.message, origin);
+});
+setTimeout(() => { throw new Error('first'); }, 0);
+const interval = setInterval(() => {
+  clearInterval(interval);
+  throw new Error('second');
+}, 5);
+"#,
+            "test",
+        );
+        assert!(result.is_ok(), "{result:?}");
+        let errors = jstime.run_script("globalThis.errors.join()", "test");
+        assert_eq!(
+            errors.unwrap(),
+            "first,uncaughtException,second,uncaughtException"
+        );
+
+        // A listener that throws is reported
+        let err = jstime
+            .run_script(
+                r#"
+process.removeAllListeners('uncaughtException');
+process.on('uncaughtException', () => { throw new Error('from listener'); });
+setTimeout(() => { throw new Error('third'); }, 0);
+"#,
+                "test",
+            )
+            .unwrap_err()
+            .to_string();
+        assert!(err.contains("from listener"), "{err}");
+    }
+
+    #[test]
+    fn test_report_error() {
+        let _setup_guard = common::setup();
+        let mut jstime = jstime::JSTime::new(jstime::Options::default());
+
+        let result = jstime.run_script(
+            r#"
+globalThis.messages = [];
+addEventListener('error', (event) => {
+  globalThis.messages.push(event.message);
+  event.preventDefault();
+});
+reportError(new TypeError('reported'));
+'done'
+"#,
+            "test",
+        );
+        assert_eq!(result.unwrap(), "done");
+        let messages = jstime.run_script("globalThis.messages.join()", "test");
+        assert_eq!(messages.unwrap(), "Uncaught TypeError: reported");
+
+        // Unhandled, it stops the event loop once the script has run
+        let mut jstime = jstime::JSTime::new(jstime::Options::default());
+        let err = jstime
+            .run_script(
+                "reportError(new Error('nobody listens')); globalThis.after = true;",
+                "test",
+            )
+            .unwrap_err()
+            .to_string();
+        assert!(err.contains("nobody listens"), "{err}");
+        let after = jstime.run_script("globalThis.after", "test");
+        assert_eq!(after.unwrap(), "true");
+
+        let result = jstime.run_script("try { reportError(); } catch (e) { e.name }", "test");
+        assert_eq!(result.unwrap(), "TypeError");
+    }
+
+    #[test]
+    fn test_errors_are_not_left_for_the_next_run() {
+        let _setup_guard = common::setup();
+        let mut jstime = jstime::JSTime::new(jstime::Options::default());
+
+        // An exception and a rejection at the same checkpoint
+        let err = jstime
+            .run_script(
+                "reportError(new Error('reported')); Promise.reject(new Error('rejected'));",
+                "test",
+            )
+            .unwrap_err()
+            .to_string();
+        assert!(err.contains("reported"), "{err}");
+        assert_eq!(jstime.run_script("1", "test").unwrap(), "1");
+
+        // A timer that rejects a promise, then throws
+        let err = jstime
+            .run_script(
+                "setTimeout(() => { Promise.reject(new Error('rejected')); throw new Error('thrown'); }, 0);",
+                "test",
+            )
+            .unwrap_err()
+            .to_string();
+        assert!(err.contains("thrown"), "{err}");
+        assert_eq!(jstime.run_script("2", "test").unwrap(), "2");
+    }
+
+    #[test]
+    fn test_import_fails_on_uncaught_exception() {
+        let _setup_guard = common::setup();
+
+        let temp_dir = std::env::temp_dir().join("jstime_test_uncaught_exception_import");
+        common::cleanup(&temp_dir);
+        common::create_dir_all(&temp_dir);
+        let main = temp_dir.join("main.js");
+        common::write_file(
+            &main,
+            "setTimeout(() => { throw new Error('in a module timer'); }, 0);",
+        );
+
+        let mut jstime = jstime::JSTime::new(jstime::Options::default());
+        let err = jstime
+            .import(main.to_str().unwrap())
+            .unwrap_err()
+            .to_string();
+        assert!(err.contains("in a module timer"), "{err}");
+        assert!(err.contains("main.js"), "{err}");
+
+        common::cleanup(&temp_dir);
+    }
+}
@@ -0,0 +1,184 @@
+use jstime_core as jstime;
+
+mod common;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_unhandled_rejection_is_an_error() {
+        let _setup_guard = common::setup();
+        let mut jstime = jstime::JSTime::new(jstime::Options::default());
+
+        let err = jstime
+            .run_script("Promise.reject(new Error('boom')); 1", "test")
+            .unwrap_err()
+            .to_string();
+        assert!(err.contains("Uncaught (in promise)"), "{err}");
+        assert!(err.contains("boom"), "{err}");
+
+        // Values that aren't errors are reported too
+        let err = jstime
+            .run_script("Promise.reject(42); 1", "test")
+            .unwrap_err()
+            .to_string();
+        assert!(err.contains("42"), "{err}");
+    }
+
+    #[test]
+    fn test_rejection_handled_in_time_is_not_reported() {
+        let _setup_guard = common::setup();
+        let mut jstime = jstime::JSTime::new(jstime::Options::default());
+
+        let result = jstime.run_script(
+            r#"
+const p = Promise.reject(new Error('late'));
+Promise.resolve().then(() => p.catch(() => {}));
+async function f() { throw new Error('awaited'); }
+(async () => { try { await f(); } catch {} })();
+'ok'
+"#,
+            "test",
+        );
+        assert_eq!(result.unwrap(), "ok");
+    }
+
+    #[test]
+    fn test_unhandledrejection_event() {
+        let _setup_guard = common::setup();
+        let mut jstime = jstime::JSTime::new(jstime::Options::default());
+
+        let result = jstime.run_script(
+            r#"
+globalThis.events = [];
+addEventListener('unhandledrejection', (event) => {
+  globalThis.events.push(`${event.type}:${event.reason.message}:${event.promise instanceof Promise}`);
+  event.preventDefault();
+});
+Promise.reject(new Error('handled by listener'));
+"#,
+            "test",
+        );
+        assert!(result.is_ok(), "{result:?}");
+        let events = jstime.run_script("globalThis.events.join()", "test");
+        assert_eq!(
+            events.unwrap(),
+            "unhandledrejection:handled by listener:true"
+        );
+
+        // A listener that doesn't cancel the event leaves it unhandled
+        let mut jstime = jstime::JSTime::new(jstime::Options::default());
+        let err = jstime
+            .run_script(
+                r#"
+addEventListener('unhandledrejection', () => {});
+Promise.reject(new Error('observed'));
+"#,
+                "test",
+            )
+            .unwrap_err()
+            .to_string();
+        assert!(err.contains("observed"), "{err}");
+    }
+
+    #[test]
+    fn test_process_unhandled_rejection() {
+        let _setup_guard = common::setup();
+        let mut jstime = jstime::JSTime::new(jstime::Options::default());
+
+        let result = jstime.run_script(
+            r#"
+globalThis.reasons = [];
+process.on('unhandledRejection', (reason, promise) => {
+  globalThis.reasons.push(reason, promise instanceof Promise);
+});
+Promise.reject('first');
+setTimeout(() => Promise.reject('second'), 0);
+"#,
+            "test",
+        );
+        assert!(result.is_ok(), "{result:?}");
+        let reasons = jstime.run_script("globalThis.reasons.join()", "test");
+        assert_eq!(reasons.unwrap(), "first,true,second,true");
+
+        // A listener that throws is reported
+        let err = jstime
+            .run_script(
+                r#"
+process.removeAllListeners('unhandledRejection');
+process.on('unhandledRejection', () => { throw new Error('from listener'); });
+Promise.reject('third');
+"#,
+                "test",
+            )
+            .unwrap_err()
+            .to_string();
+        assert!(err.contains("from listener"), "{err}");
+    }
+
+    #[test]
+    fn test_rejectionhandled_event() {
+        let _setup_guard = common::setup();
+        let mut jstime = jstime::JSTime::new(jstime::Options::default());
+
+        let result = jstime.run_script(
+            r#"
+globalThis.events = [];
+addEventListener('unhandledrejection', (event) => {
+  globalThis.events.push(event.type);
+  event.preventDefault();
+});
+addEventListener('rejectionhandled', (event) => {
+  globalThis.events.push(`${event.type}:${event.reason}`);
+});
+process.on('rejectionHandled', (promise) => {
+  globalThis.events.push(`process:${promise === globalThis.promise}`);
+});
+globalThis.promise = Promise.reject('later');
+setTimeout(() => globalThis.promise.catch(() => {}), 10);
+"#,
+            "test",
+        );
+        assert!(result.is_ok(), "{result:?}");
+        let events = jstime.run_script("globalThis.events.join()", "test");
+        assert_eq!(
+            events.unwrap(),
+            "unhandledrejection,rejectionhandled:later,process:true"
+        );
+    }
+
+    #[test]
+    fn test_import_fails_on_unhandled_rejection() {
+        let _setup_guard = common::setup();
+
+        let temp_dir = std::env::temp_dir().join("jstime_test_unhandled_rejection_import");
+        common::cleanup(&temp_dir);
+        common::create_dir_all(&temp_dir);
+        let main = temp_dir.join("main.js");
+        common::write_file(
+            &main,
+            "globalThis.ran = true;\nsetTimeout(() => { Promise.reject(new Error('in a timer')); }, 0);",
+        );
+
+        let mut jstime = jstime::JSTime::new(jstime::Options::default());
+        let err = jstime
+            .import(main.to_str().unwrap())
+            .unwrap_err()
+            .to_string();
+        assert!(err.contains("in a timer"), "{err}");
+
+        // A module that fails to evaluate is reported once, as the import's error
+        let failing = temp_dir.join("failing.js");
+        common::write_file(&failing, "await 0;\nthrow new Error('top-level');");
+        let mut jstime = jstime::JSTime::new(jstime::Options::default());
+        let err = jstime
+            .import(failing.to_str().unwrap())
+            .unwrap_err()
+            .to_string();
+        assert!(err.contains("top-level"), "{err}");
+        assert!(!err.contains("Uncaught (in promise)"), "{err}");
+
+        common::cleanup(&temp_dir);
+    }
+}
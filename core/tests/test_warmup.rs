@@ -82,7 +82,7 @@ mod warmup {
         // Warmup should fail on error
         let result = jstime.run_script("throw new Error('test error')", "test_warmup.js");
         assert!(result.is_err());
-        let err = result.unwrap_err();
+        let err = result.unwrap_err().to_string();
         assert!(err.contains("Error: test error"));
     }
 
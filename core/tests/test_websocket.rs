@@ -0,0 +1,544 @@
+use jstime_core as jstime;
+
+mod common;
+
+use std::io::{Read, Write};
+use std::net::TcpStream;
+
+/// Accept one WebSocket connection, answer the handshake (choosing `protocol` if given)
+/// and hand the stream and request head to `handler`.
+fn spawn_ws_server(
+    protocol: Option<&'static str>,
+    handler: impl FnOnce(&mut TcpStream, String) + Send + 'static,
+) -> (u16, std::thread::JoinHandle<()>) {
+    let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+    let port = listener.local_addr().unwrap().port();
+    let handle = std::thread::spawn(move || {
+        let (mut stream, _) = listener.accept().unwrap();
+        let head = read_head(&mut stream);
+        let key = head
+            .lines()
+            .find_map(|line| line.strip_prefix("Sec-WebSocket-Key: "))
+            .unwrap()
+            .trim()
+            .to_string();
+        let mut response = format!(
+            "HTTP/1.1 101 Switching Protocols\r\nUpgrade: websocket\r\nConnection: Upgrade\r\n\
+             Sec-WebSocket-Accept: {}\r\n",
+            accept_key(&key)
+        );
+        if let Some(protocol) = protocol {
+            response.push_str(&format!("Sec-WebSocket-Protocol: {}\r\n", protocol));
+        }
+        response.push_str("\r\n");
+        stream.write_all(response.as_bytes()).unwrap();
+        handler(&mut stream, head);
+    });
+    (port, handle)
+}
+
+/// Accept one connection and answer with a plain HTTP response instead of upgrading
+fn spawn_http_server(response: &'static str) -> (u16, std::thread::JoinHandle<()>) {
+    let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+    let port = listener.local_addr().unwrap().port();
+    let handle = std::thread::spawn(move || {
+        let (mut stream, _) = listener.accept().unwrap();
+        read_head(&mut stream);
+        stream.write_all(response.as_bytes()).unwrap();
+    });
+    (port, handle)
+}
+
+fn read_head(stream: &mut TcpStream) -> String {
+    let mut head = Vec::new();
+    let mut byte = [0u8; 1];
+    while !head.ends_with(b"\r\n\r\n") {
+        stream.read_exact(&mut byte).unwrap();
+        head.push(byte[0]);
+    }
+    String::from_utf8(head).unwrap()
+}
+
+fn accept_key(key: &str) -> String {
+    let mut context = ring::digest::Context::new(&ring::digest::SHA1_FOR_LEGACY_USE_ONLY);
+    context.update(key.as_bytes());
+    context.update(b"258EAFA5-E914-47DA-95CA-C5AB0DC85B11");
+    base64_simd::STANDARD.encode_to_string(context.finish().as_ref())
+}
+
+/// Write an unmasked frame, as servers do
+fn write_frame(stream: &mut TcpStream, fin: bool, opcode: u8, payload: &[u8]) {
+    let mut frame = vec![if fin { 0x80 } else { 0 } | opcode];
+    if payload.len() < 126 {
+        frame.push(payload.len() as u8);
+    } else {
+        frame.push(126);
+        frame.extend_from_slice(&(payload.len() as u16).to_be_bytes());
+    }
+    frame.extend_from_slice(payload);
+    stream.write_all(&frame).unwrap();
+}
+
+/// Read a frame from the client, checking that it is masked. Returns (opcode, payload).
+fn read_frame(stream: &mut TcpStream) -> (u8, Vec<u8>) {
+    let mut header = [0u8; 2];
+    stream.read_exact(&mut header).unwrap();
+    assert_ne!(header[1] & 0x80, 0, "client frames must be masked");
+    let len = match header[1] & 0x7F {
+        126 => {
+            let mut len = [0u8; 2];
+            stream.read_exact(&mut len).unwrap();
+            u16::from_be_bytes(len) as usize
+        }
+        127 => {
+            let mut len = [0u8; 8];
+            stream.read_exact(&mut len).unwrap();
+            u64::from_be_bytes(len) as usize
+        }
+        len => len as usize,
+    };
+    let mut mask = [0u8; 4];
+    stream.read_exact(&mut mask).unwrap();
+    let mut payload = vec![0u8; len];
+    stream.read_exact(&mut payload).unwrap();
+    for (i, byte) in payload.iter_mut().enumerate() {
+        *byte ^= mask[i % 4];
+    }
+    (header[0] & 0x0F, payload)
+}
+
+/// Write a masked frame, as clients do
+fn write_masked_frame(stream: &mut TcpStream, opcode: u8, payload: &[u8]) {
+    let mask = [0x12, 0x34, 0x56, 0x78];
+    let mut frame = vec![0x80 | opcode, 0x80 | payload.len() as u8];
+    frame.extend_from_slice(&mask);
+    frame.extend(payload.iter().enumerate().map(|(i, b)| b ^ mask[i % 4]));
+    stream.write_all(&frame).unwrap();
+}
+
+/// Read a short frame from a server, checking that it is unmasked. Returns (opcode, payload).
+fn read_server_frame(stream: &mut TcpStream) -> (u8, Vec<u8>) {
+    let mut header = [0u8; 2];
+    stream.read_exact(&mut header).unwrap();
+    assert_eq!(header[1] & 0x80, 0, "server frames must not be masked");
+    let mut payload = vec![0u8; (header[1] & 0x7F) as usize];
+    stream.read_exact(&mut payload).unwrap();
+    (header[0] & 0x0F, payload)
+}
+
+/// Answer the client's close frame and close the connection, returning the close payload
+fn finish_close(stream: &mut TcpStream) -> Vec<u8> {
+    let (opcode, payload) = read_frame(stream);
+    assert_eq!(opcode, 0x8);
+    write_frame(stream, true, 0x8, &payload);
+    payload
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Run a script that settles asynchronously and read back globalThis.result
+    fn run_async(script: &str) -> Result<String, jstime::Error> {
+        let _setup_guard = common::setup();
+        let options = jstime::Options::default();
+        let mut jstime = jstime::JSTime::new(options);
+        jstime.run_script(
+            &format!("globalThis.result = null; {}", script),
+            "test_websocket",
+        )?;
+        jstime.run_script("globalThis.result;", "test_websocket")
+    }
+
+    #[test]
+    fn test_websocket_globals() {
+        let result = common::run_test_script(
+            "[typeof WebSocket, WebSocket.CONNECTING, WebSocket.OPEN, WebSocket.CLOSING, WebSocket.CLOSED, \
+              WebSocket.prototype.OPEN, new MessageEvent('message', { data: 1 }).data, \
+              new CloseEvent('close', { code: 1000, wasClean: true }).code, \
+              new CloseEvent('close') instanceof Event].join(',');",
+        );
+        assert_eq!(result.unwrap(), "function,0,1,2,3,1,1,1000,true");
+    }
+
+    #[test]
+    fn test_websocket_invalid_urls() {
+        let result = common::run_test_script(
+            "const errors = []; \
+             for (const url of ['ftp://example.com', 'ws://example.com/#frag', 'not a url']) { \
+               try { new WebSocket(url); errors.push('none'); } catch (e) { errors.push(e.name); } \
+             } \
+             try { new WebSocket('ws://127.0.0.1:1', ['a', 'a']); } catch (e) { errors.push(e.name); } \
+             errors.join(',');",
+        );
+        assert_eq!(
+            result.unwrap(),
+            "SyntaxError,SyntaxError,SyntaxError,SyntaxError"
+        );
+    }
+
+    #[test]
+    fn test_websocket_echo_and_clean_close() {
+        let (port, server) = spawn_ws_server(None, |stream, head| {
+            assert!(head.starts_with("GET /chat?room=1 HTTP/1.1\r\n"), "{head}");
+            let (opcode, payload) = read_frame(stream);
+            write_frame(stream, true, opcode, &payload);
+            let payload = finish_close(stream);
+            assert_eq!(payload, b"\x03\xe8done");
+        });
+        let result = run_async(&format!(
+            "const ws = new WebSocket('ws://127.0.0.1:{port}/chat?room=1'); \
+             const states = [ws.readyState]; \
+             ws.onopen = () => {{ states.push(ws.readyState); ws.send('hello'); }}; \
+             ws.onmessage = e => {{ states.push(e.data); ws.close(1000, 'done'); states.push(ws.readyState); }}; \
+             ws.onclose = e => {{ globalThis.result = `${{states.join(',')}}|${{e.code}}:${{e.reason}}:${{e.wasClean}}:${{ws.readyState}}`; }};"
+        ));
+        assert_eq!(result.unwrap(), "0,1,hello,2|1000:done:true:3");
+        server.join().unwrap();
+    }
+
+    #[test]
+    fn test_websocket_binary_arraybuffer() {
+        let (port, server) = spawn_ws_server(None, |stream, _| {
+            let (opcode, payload) = read_frame(stream);
+            assert_eq!(opcode, 0x2);
+            assert_eq!(payload, vec![1, 2, 3]);
+            write_frame(stream, true, 0x2, &[4, 5, 6]);
+            finish_close(stream);
+        });
+        let result = run_async(&format!(
+            "const ws = new WebSocket('ws://127.0.0.1:{port}/'); \
+             ws.binaryType = 'arraybuffer'; \
+             ws.binaryType = 'invalid'; \
+             ws.onopen = () => ws.send(new Uint8Array([1, 2, 3])); \
+             ws.onmessage = e => {{ \
+               globalThis.result = `${{ws.binaryType}}:${{e.data instanceof ArrayBuffer}}:${{[...new Uint8Array(e.data)]}}`; \
+               ws.close(); \
+             }};"
+        ));
+        assert_eq!(result.unwrap(), "arraybuffer:true:4,5,6");
+        server.join().unwrap();
+    }
+
+    #[test]
+    fn test_websocket_binary_blob() {
+        let (port, server) = spawn_ws_server(None, |stream, _| {
+            // Blobs are sent as binary, in order with other messages
+            assert_eq!(read_frame(stream), (0x2, b"blob".to_vec()));
+            assert_eq!(read_frame(stream), (0x1, b"after".to_vec()));
+            write_frame(stream, true, 0x2, b"bytes");
+            finish_close(stream);
+        });
+        let result = run_async(&format!(
+            "const ws = new WebSocket('ws://127.0.0.1:{port}/'); \
+             ws.onopen = () => {{ ws.send(new Blob(['blob'])); ws.send('after'); }}; \
+             ws.onmessage = async e => {{ \
+               globalThis.result = `${{ws.binaryType}}:${{e.data instanceof Blob}}:${{await e.data.text()}}`; \
+               ws.close(); \
+             }};"
+        ));
+        assert_eq!(result.unwrap(), "blob:true:bytes");
+        server.join().unwrap();
+    }
+
+    #[test]
+    fn test_websocket_ping_pong() {
+        let (port, server) = spawn_ws_server(None, |stream, _| {
+            write_frame(stream, true, 0x9, b"are you there");
+            let (opcode, payload) = read_frame(stream);
+            assert_eq!(opcode, 0xA);
+            write_frame(stream, true, 0x1, &payload);
+            finish_close(stream);
+        });
+        let result = run_async(&format!(
+            "const ws = new WebSocket('ws://127.0.0.1:{port}/'); \
+             ws.onmessage = e => {{ globalThis.result = e.data; ws.close(); }};"
+        ));
+        assert_eq!(result.unwrap(), "are you there");
+        server.join().unwrap();
+    }
+
+    #[test]
+    fn test_websocket_fragmented_message() {
+        let (port, server) = spawn_ws_server(None, |stream, _| {
+            write_frame(stream, false, 0x1, b"frag");
+            // Control frames may be interleaved with fragments
+            write_frame(stream, true, 0x9, b"");
+            write_frame(stream, false, 0x0, "men".as_bytes());
+            write_frame(stream, true, 0x0, "ted ✓".as_bytes());
+            assert_eq!(read_frame(stream).0, 0xA);
+            finish_close(stream);
+        });
+        let result = run_async(&format!(
+            "const ws = new WebSocket('ws://127.0.0.1:{port}/'); \
+             ws.onmessage = e => {{ globalThis.result = e.data; ws.close(); }};"
+        ));
+        assert_eq!(result.unwrap(), "fragmented ✓");
+        server.join().unwrap();
+    }
+
+    #[test]
+    fn test_websocket_server_initiated_close() {
+        let (port, server) = spawn_ws_server(None, |stream, _| {
+            let mut payload = 4001u16.to_be_bytes().to_vec();
+            payload.extend_from_slice(b"bye");
+            write_frame(stream, true, 0x8, &payload);
+            // The client echoes the close code
+            let (opcode, reply) = read_frame(stream);
+            assert_eq!(opcode, 0x8);
+            assert_eq!(reply, 4001u16.to_be_bytes());
+        });
+        let result = run_async(&format!(
+            "const ws = new WebSocket('ws://127.0.0.1:{port}/'); \
+             ws.addEventListener('close', e => {{ globalThis.result = `${{e.code}}:${{e.reason}}:${{e.wasClean}}`; }});"
+        ));
+        assert_eq!(result.unwrap(), "4001:bye:true");
+        server.join().unwrap();
+    }
+
+    #[test]
+    fn test_websocket_subprotocol() {
+        let (port, server) = spawn_ws_server(Some("chat"), |stream, head| {
+            assert!(
+                head.contains("Sec-WebSocket-Protocol: chat, superchat\r\n"),
+                "{head}"
+            );
+            finish_close(stream);
+        });
+        let result = run_async(&format!(
+            "const ws = new WebSocket('ws://127.0.0.1:{port}/', ['chat', 'superchat']); \
+             ws.onopen = () => {{ globalThis.result = `${{ws.protocol}}:${{ws.extensions}}`; ws.close(); }};"
+        ));
+        assert_eq!(result.unwrap(), "chat:");
+        server.join().unwrap();
+    }
+
+    #[test]
+    fn test_websocket_handshake_rejected() {
+        let (port, server) = spawn_http_server(
+            "HTTP/1.1 404 Not Found\r\nContent-Length: 0\r\nConnection: close\r\n\r\n",
+        );
+        let result = run_async(&format!(
+            "const ws = new WebSocket('ws://127.0.0.1:{port}/'); \
+             const events = []; \
+             ws.onopen = () => events.push('open'); \
+             ws.onerror = e => events.push(e.type); \
+             ws.onclose = e => {{ events.push(`close:${{e.code}}:${{e.wasClean}}`); globalThis.result = events.join(','); }};"
+        ));
+        assert_eq!(result.unwrap(), "error,close:1006:false");
+        server.join().unwrap();
+    }
+
+    #[test]
+    fn test_websocket_masked_server_frame_fails() {
+        let (port, server) = spawn_ws_server(None, |stream, _| {
+            // Servers must not mask frames
+            stream
+                .write_all(&[0x81, 0x82, 0, 0, 0, 0, b'h', b'i'])
+                .unwrap();
+            let (opcode, payload) = read_frame(stream);
+            assert_eq!(opcode, 0x8);
+            assert_eq!(payload, 1002u16.to_be_bytes());
+        });
+        let result = run_async(&format!(
+            "const ws = new WebSocket('ws://127.0.0.1:{port}/'); \
+             const events = []; \
+             ws.onmessage = () => events.push('message'); \
+             ws.onerror = () => events.push('error'); \
+             ws.onclose = e => {{ events.push(e.code); globalThis.result = events.join(','); }};"
+        ));
+        assert_eq!(result.unwrap(), "error,1006");
+        server.join().unwrap();
+    }
+
+    #[test]
+    fn test_websocket_connection_refused() {
+        let port = {
+            let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+            listener.local_addr().unwrap().port()
+        };
+        let result = run_async(&format!(
+            "const ws = new WebSocket('ws://127.0.0.1:{port}/'); \
+             let errored = false; \
+             ws.onerror = () => {{ errored = true; }}; \
+             ws.onclose = e => {{ globalThis.result = `${{errored}}:${{e.code}}`; }};"
+        ));
+        assert_eq!(result.unwrap(), "true:1006");
+    }
+
+    #[test]
+    fn test_websocket_close_before_open() {
+        // Connections are never accepted, so the handshake can't complete
+        let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+        let port = listener.local_addr().unwrap().port();
+        let result = run_async(&format!(
+            "const ws = new WebSocket('ws://127.0.0.1:{port}/'); \
+             const events = []; \
+             ws.onerror = () => events.push('error'); \
+             ws.onclose = e => {{ events.push(`close:${{e.code}}`); globalThis.result = events.join(','); }}; \
+             ws.close(); \
+             events.push(ws.readyState);"
+        ));
+        assert_eq!(result.unwrap(), "2,error,close:1006");
+        drop(listener);
+    }
+
+    #[test]
+    fn test_websocket_send_and_close_validation() {
+        let (port, server) = spawn_ws_server(None, |stream, _| {
+            finish_close(stream);
+        });
+        let result = run_async(&format!(
+            "const ws = new WebSocket('ws://127.0.0.1:{port}/'); \
+             const errors = []; \
+             try {{ ws.send('too early'); }} catch (e) {{ errors.push(e.name); }} \
+             ws.onopen = () => {{ \
+               try {{ ws.close(1001); }} catch (e) {{ errors.push(e.name); }} \
+               try {{ ws.close(1000, 'x'.repeat(124)); }} catch (e) {{ errors.push(e.name); }} \
+               ws.close(); \
+               ws.send('after close'); \
+               errors.push(ws.bufferedAmount); \
+               globalThis.result = errors.join(','); \
+             }};"
+        ));
+        assert_eq!(
+            result.unwrap(),
+            "InvalidStateError,InvalidAccessError,SyntaxError,11"
+        );
+        server.join().unwrap();
+    }
+
+    #[test]
+    fn test_upgrade_websocket_exists() {
+        let result = common::run_test_script("typeof jstime.upgradeWebSocket;");
+        assert_eq!(result.unwrap(), "function");
+    }
+
+    #[test]
+    fn test_upgrade_websocket_echo() {
+        let result = run_async(
+            "const events = []; \
+             let closed = 0; \
+             const onClosed = () => { \
+               if (++closed === 2) { globalThis.result = events.sort().join(','); server.shutdown(); } \
+             }; \
+             const server = jstime.serve({ port: 0, hostname: '127.0.0.1', onListen() {} }, (req) => { \
+               const { socket, response } = jstime.upgradeWebSocket(req, { protocol: 'b' }); \
+               socket.onopen = () => events.push(`server open:${socket.protocol}:${new URL(socket.url).pathname}`); \
+               socket.onmessage = e => socket.send(`echo:${e.data}`); \
+               socket.onclose = e => { events.push(`server close:${e.code}:${e.reason}:${e.wasClean}`); onClosed(); }; \
+               return response; \
+             }); \
+             const ws = new WebSocket(`ws://127.0.0.1:${server.addr.port}/live`, ['a', 'b']); \
+             ws.onopen = () => { events.push(`client open:${ws.protocol}`); ws.send('hi'); }; \
+             ws.onmessage = e => { events.push(e.data); ws.close(1000, 'bye'); }; \
+             ws.onclose = e => { events.push(`client close:${e.code}:${e.reason}:${e.wasClean}`); onClosed(); };",
+        );
+        assert_eq!(
+            result.unwrap(),
+            // The server echoes the close code but not the reason
+            "client close:1000::true,client open:b,echo:hi,server close:1000:bye:true,server open:b:/live"
+        );
+    }
+
+    #[test]
+    fn test_upgrade_websocket_server_sends_and_closes() {
+        let result = run_async(
+            "const server = jstime.serve({ port: 0, hostname: '127.0.0.1', onListen() {} }, (req) => { \
+               const { socket, response } = jstime.upgradeWebSocket(req); \
+               socket.onopen = () => { socket.send(new Uint8Array([1, 2, 3])); socket.close(4000, 'done'); }; \
+               return response; \
+             }); \
+             const ws = new WebSocket(`ws://127.0.0.1:${server.addr.port}/`); \
+             ws.binaryType = 'arraybuffer'; \
+             const received = []; \
+             ws.onmessage = e => received.push([...new Uint8Array(e.data)].join(' ')); \
+             ws.onclose = e => { \
+               globalThis.result = `${received}|${ws.protocol}|${e.code}:${e.reason}:${e.wasClean}`; \
+               server.shutdown(); \
+             };",
+        );
+        assert_eq!(result.unwrap(), "1 2 3||4000:done:true");
+    }
+
+    #[test]
+    fn test_upgrade_websocket_rejects_invalid_requests() {
+        let result = run_async(
+            "const server = jstime.serve({ port: 0, hostname: '127.0.0.1', onListen() {} }, (req) => { \
+               try { \
+                 return jstime.upgradeWebSocket(req).response; \
+               } catch (e) { \
+                 return new Response(`${e.name}: ${e.message}`, { status: 400 }); \
+               } \
+             }); \
+             const errors = []; \
+             try { jstime.upgradeWebSocket(new Request('http://localhost/')); } catch (e) { errors.push(e.name); } \
+             fetch(`http://127.0.0.1:${server.addr.port}/`).then(async r => { \
+               globalThis.result = `${errors}|${r.status}:${await r.text()}`; \
+               server.shutdown(); \
+             });",
+        );
+        assert_eq!(
+            result.unwrap(),
+            "TypeError|400:TypeError: jstime.upgradeWebSocket: missing 'Upgrade: websocket' header"
+        );
+    }
+
+    #[test]
+    fn test_upgrade_websocket_raw_client() {
+        let port = {
+            let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+            listener.local_addr().unwrap().port()
+        };
+        let client = std::thread::spawn(move || {
+            // The server starts once the script runs, so retry until it is listening
+            let mut stream = loop {
+                match TcpStream::connect(("127.0.0.1", port)) {
+                    Ok(stream) => break stream,
+                    Err(_) => std::thread::sleep(std::time::Duration::from_millis(10)),
+                }
+            };
+            // The example handshake from RFC 6455 section 1.3
+            stream
+                .write_all(
+                    b"GET /chat HTTP/1.1\r\nHost: 127.0.0.1\r\nUpgrade: websocket\r\n\
+                      Connection: keep-alive, Upgrade\r\nSec-WebSocket-Key: dGhlIHNhbXBsZSBub25jZQ==\r\n\
+                      Sec-WebSocket-Version: 13\r\n\r\n",
+                )
+                .unwrap();
+            let head = read_head(&mut stream);
+            assert!(
+                head.starts_with("HTTP/1.1 101 Switching Protocols\r\n"),
+                "{head}"
+            );
+            assert!(
+                head.contains("sec-websocket-accept: s3pPLMBiTxaQ9kYGzrZoK+xOo4M=\r\n"),
+                "{head}"
+            );
+            assert!(head.contains("x-extra: 1\r\n"), "{head}");
+
+            write_masked_frame(&mut stream, 0x1, b"ping");
+            assert_eq!(read_server_frame(&mut stream), (0x1, b"pong".to_vec()));
+            // Clients must mask their frames
+            stream.write_all(&[0x81, 0x01, b'x']).unwrap();
+            assert_eq!(
+                read_server_frame(&mut stream),
+                (0x8, 1002u16.to_be_bytes().to_vec())
+            );
+        });
+
+        let result = run_async(&format!(
+            "const events = []; \
+             const server = jstime.serve({{ port: {port}, hostname: '127.0.0.1', onListen() {{}} }}, (req) => {{ \
+               const {{ socket, response }} = jstime.upgradeWebSocket(req); \
+               response.headers.set('x-extra', '1'); \
+               socket.onmessage = e => {{ events.push(e.data); socket.send('pong'); }}; \
+               socket.onerror = () => events.push('error'); \
+               socket.onclose = e => {{ events.push(e.code); globalThis.result = events.join(','); server.shutdown(); }}; \
+               return response; \
+             }});"
+        ));
+        assert_eq!(result.unwrap(), "ping,error,1006");
+        client.join().unwrap();
+    }
+}